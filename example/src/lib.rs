@@ -1 +1,7 @@
+/// Closes the transactions abandoned by their callers once past their TTL.
+#[ic_cdk::heartbeat]
+fn heartbeat() {
+    ic_dbms_canister::prelude::expire_stale_transactions();
+}
+
 ic_cdk::export_candid!();
@@ -1,6 +1,7 @@
 pub mod database;
 pub mod foreign_fetcher;
 pub mod query;
+pub mod sharding;
 pub mod table;
 pub mod transaction;
 pub mod types;
@@ -1,5 +1,6 @@
 use crate::prelude::{
-    DeleteBehavior, Filter, IcDbmsResult, InsertRecord, Query, TableSchema, UpdateRecord,
+    DeleteBehavior, ExecutionEstimate, Filter, IcDbmsError, IcDbmsResult, InsertRecord, Query,
+    QueryError, TableRecord as _, TableSchema, UpdateBehavior, UpdateRecord, Value,
 };
 
 /// This module defines the Database trait and related database functionalities.
@@ -13,10 +14,70 @@ pub trait Database {
     /// # Returns
     ///
     /// The returned results are a vector of [`table::TableRecord`] matching the query.
+    ///
+    /// Fails with [`crate::prelude::QueryError::Timeout`] if the [`Query::timeout_ns`] is exceeded.
     fn select<T>(&self, query: Query<T>) -> IcDbmsResult<Vec<T::Record>>
     where
         T: TableSchema;
 
+    /// Executes a SELECT query which must return exactly one record.
+    ///
+    /// # Arguments
+    ///
+    /// - `query` - The SELECT [`Query`] to be executed. Its limit is overridden.
+    ///
+    /// # Returns
+    ///
+    /// The only record matching the query.
+    /// Fails with [`QueryError::RecordNotFound`] if no record matches the query,
+    /// and with [`QueryError::TooManyResults`] if more than one record matches the query.
+    fn select_one<T>(&self, query: Query<T>) -> IcDbmsResult<T::Record>
+    where
+        T: TableSchema,
+    {
+        self.select_opt(query)?
+            .ok_or(IcDbmsError::Query(QueryError::RecordNotFound))
+    }
+
+    /// Executes a SELECT query which must return at most one record.
+    ///
+    /// # Arguments
+    ///
+    /// - `query` - The SELECT [`Query`] to be executed. Its limit is overridden.
+    ///
+    /// # Returns
+    ///
+    /// The only record matching the query, if any.
+    /// Fails with [`QueryError::TooManyResults`] if more than one record matches the query.
+    fn select_opt<T>(&self, mut query: Query<T>) -> IcDbmsResult<Option<T::Record>>
+    where
+        T: TableSchema,
+    {
+        // select up to two records to detect whether the query matches more than one record
+        query.limit = Some(2);
+        let mut records = self.select(query)?;
+        if records.len() > 1 {
+            return Err(IcDbmsError::Query(QueryError::TooManyResults(
+                records.len(),
+            )));
+        }
+
+        Ok(records.pop())
+    }
+
+    /// Estimates the cost of executing a SELECT query, without executing it.
+    ///
+    /// # Arguments
+    ///
+    /// - `query` - The SELECT [`Query`] to be estimated.
+    ///
+    /// # Returns
+    ///
+    /// The [`ExecutionEstimate`] of the query, in terms of records scanned and stable memory reads.
+    fn explain_execution<T>(&self, query: &Query<T>) -> IcDbmsResult<ExecutionEstimate>
+    where
+        T: TableSchema;
+
     /// Executes an INSERT query.
     ///
     /// # Arguments
@@ -27,17 +88,74 @@ pub trait Database {
         T: TableSchema,
         T::Insert: InsertRecord<Schema = T>;
 
+    /// Executes an INSERT query and returns the value of the given field of the inserted record.
+    ///
+    /// # Arguments
+    ///
+    /// - `record` - The INSERT record to be executed.
+    /// - `field` - The name of the column whose value is returned.
+    ///
+    /// # Returns
+    ///
+    /// The value of `field` in the inserted record, as read back by its primary key;
+    /// within a transaction, the record is read from the uncommitted changes.
+    /// Fails with [`QueryError::UnknownColumn`] if the table has no such column, in which case nothing is inserted.
+    fn insert_returning_field<T>(
+        &self,
+        record: T::Insert,
+        field: &'static str,
+    ) -> IcDbmsResult<Value>
+    where
+        T: TableSchema,
+        T::Insert: InsertRecord<Schema = T>,
+    {
+        if !T::columns().iter().any(|column| column.name == field) {
+            return Err(IcDbmsError::Query(QueryError::UnknownColumn(
+                field.to_string(),
+            )));
+        }
+        let values = record.clone().into_values();
+        let primary_key = T::primary_keys()
+            .iter()
+            .map(|pk| {
+                values
+                    .iter()
+                    .find(|(column, _)| column.name == *pk)
+                    .map(|(_, value)| value.clone())
+                    .ok_or(IcDbmsError::Query(QueryError::MissingNonNullableField(*pk)))
+            })
+            .collect::<IcDbmsResult<Vec<Value>>>()?;
+
+        self.insert::<T>(record)?;
+        let inserted = self.select_one::<T>(
+            Query::builder()
+                .all()
+                .and_where(Filter::eq_composite(T::primary_keys(), primary_key))
+                .build(),
+        )?;
+
+        inserted
+            .to_values()
+            .into_iter()
+            .find(|(column, _)| column.name == field)
+            .map(|(_, value)| value)
+            .ok_or(IcDbmsError::Query(QueryError::UnknownColumn(
+                field.to_string(),
+            )))
+    }
+
     /// Executes an UPDATE query.
     ///
     /// # Arguments
     ///
+    /// - `behaviour` - The [`UpdateBehavior`] to apply for foreign key constraints when a primary key is changed.
     /// - `patch` - The UPDATE patch to be applied.
     /// - `filter` - An optional [`Filter`] to specify which records to update.
     ///
     /// # Returns
     ///
     /// The number of rows updated.
-    fn update<T>(&self, patch: T::Update) -> IcDbmsResult<u64>
+    fn update<T>(&self, behaviour: UpdateBehavior, patch: T::Update) -> IcDbmsResult<u64>
     where
         T: TableSchema,
         T::Update: UpdateRecord<Schema = T>;
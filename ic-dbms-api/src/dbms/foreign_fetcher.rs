@@ -70,6 +70,16 @@ mod tests {
             unimplemented!()
         }
 
+        fn explain_execution<T>(
+            &self,
+            _query: &crate::prelude::Query<T>,
+        ) -> IcDbmsResult<crate::prelude::ExecutionEstimate>
+        where
+            T: crate::prelude::TableSchema,
+        {
+            unimplemented!()
+        }
+
         fn insert<T>(&self, _record: T::Insert) -> IcDbmsResult<()>
         where
             T: crate::prelude::TableSchema,
@@ -78,7 +88,11 @@ mod tests {
             unimplemented!()
         }
 
-        fn update<T>(&self, _patch: T::Update) -> IcDbmsResult<u64>
+        fn update<T>(
+            &self,
+            _behaviour: crate::prelude::UpdateBehavior,
+            _patch: T::Update,
+        ) -> IcDbmsResult<u64>
         where
             T: crate::prelude::TableSchema,
             T::Update: crate::prelude::UpdateRecord<Schema = T>,
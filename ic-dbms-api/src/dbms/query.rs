@@ -1,17 +1,32 @@
 //! This module exposes all the types related to queries that can be performed on the DBMS.
 
+mod aggregate;
 mod builder;
+mod count;
 mod delete;
+mod explain;
 mod filter;
+mod filters;
+mod join;
+mod update;
 
+use std::hash::{Hash, Hasher};
 use std::marker::PhantomData;
 
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+pub use self::aggregate::AggregateOp;
 pub use self::builder::QueryBuilder;
+pub use self::count::CountQuery;
 pub use self::delete::DeleteBehavior;
-pub use self::filter::Filter;
-use crate::dbms::table::TableSchema;
+pub use self::explain::{ExecutionEstimate, IndexUsage, QueryPlan, QueryPlanNode};
+pub use self::filter::{BoundaryKind, DynamicValues, Filter, FilterParseError};
+pub use self::filters::{CREATED_AT_COLUMN, Filters};
+pub use self::join::{JoinQuery, JoinRecord, JoinType};
+pub use self::update::UpdateBehavior;
+use crate::dbms::table::{ColumnDef, TableSchema};
 use crate::dbms::value::Value;
 use crate::memory::MemoryError;
 
@@ -26,7 +41,7 @@ pub enum QueryError {
     PrimaryKeyConflict,
 
     /// A foreign key references a non-existent record in another table.
-    #[error("Broken foreign key reference to table '{table}' with key '{key:?}'")]
+    #[error("Broken foreign key reference to table '{table}' with key {key}")]
     BrokenForeignKeyReference { table: &'static str, key: Value },
 
     /// Tried to delete or update a record that is referenced by another table's foreign key.
@@ -53,13 +68,27 @@ pub enum QueryError {
     },
 
     /// The specified transaction was not found or has expired.
-    #[error("transaction not found")]
+    #[error("Transaction not found")]
     TransactionNotFound,
 
     /// Query contains syntactically or semantically invalid conditions.
     #[error("Invalid query: {0}")]
     InvalidQuery(String),
 
+    /// A record doesn't satisfy the CHECK constraint of a column.
+    #[error(
+        "Check constraint violation on column '{column}' with value {value}: {constraint_desc}"
+    )]
+    CheckConstraintViolation {
+        column: &'static str,
+        constraint_desc: &'static str,
+        value: Value,
+    },
+
+    /// A record has the same value of another record in a unique column.
+    #[error("Unique constraint violation on column '{column}'")]
+    UniqueConstraintViolation { column: &'static str },
+
     /// Generic constraint violation (e.g., UNIQUE, CHECK, etc.)
     #[error("Constraint violation: {0}")]
     ConstraintViolation(String),
@@ -76,6 +105,10 @@ pub enum QueryError {
     #[error("Record not found")]
     RecordNotFound,
 
+    /// A query expected to return a single record matched at least the given number of records.
+    #[error("Too many results: expected one record, found at least {0}")]
+    TooManyResults(usize),
+
     /// Any low-level IO or serialization/deserialization issue.
     #[error("Serialization error: {0}")]
     SerializationError(String),
@@ -83,25 +116,116 @@ pub enum QueryError {
     /// Generic catch-all error (for internal, unexpected conditions).
     #[error("Internal error: {0}")]
     Internal(String),
+
+    /// The caller is not allowed to perform the operation, since it's not in the access control list.
+    #[error("Access denied for caller {caller}")]
+    AccessDenied { caller: candid::Principal },
+
+    /// The query exceeded its timeout, after scanning the given number of records.
+    #[error("Query timed out after {elapsed_ns}ns, having scanned {records_scanned} records")]
+    Timeout {
+        elapsed_ns: u64,
+        records_scanned: u64,
+    },
+}
+
+impl QueryError {
+    /// Returns the stable code of the error, as defined in [`crate::error::error_codes`].
+    pub fn error_code(&self) -> u32 {
+        use crate::error::error_codes::*;
+
+        match self {
+            QueryError::PrimaryKeyConflict => PRIMARY_KEY_CONFLICT,
+            QueryError::BrokenForeignKeyReference { .. } => FOREIGN_KEY_VIOLATION,
+            QueryError::ForeignKeyConstraintViolation { .. } => FOREIGN_KEY_CONSTRAINT_VIOLATION,
+            QueryError::UnknownColumn(_) => UNKNOWN_COLUMN,
+            QueryError::MissingNonNullableField(_) => MISSING_NON_NULLABLE_FIELD,
+            QueryError::TypeMismatch { .. } => TYPE_MISMATCH,
+            QueryError::TransactionNotFound => TRANSACTION_NOT_FOUND,
+            QueryError::InvalidQuery(_) => INVALID_QUERY,
+            QueryError::CheckConstraintViolation { .. } => CHECK_CONSTRAINT_VIOLATION,
+            QueryError::ConstraintViolation(_) => CONSTRAINT_VIOLATION,
+            QueryError::MemoryError(_) => QUERY_MEMORY_ERROR,
+            QueryError::TableNotFound(_) => QUERY_TABLE_NOT_FOUND,
+            QueryError::RecordNotFound => RECORD_NOT_FOUND,
+            QueryError::TooManyResults(_) => TOO_MANY_RESULTS,
+            QueryError::SerializationError(_) => SERIALIZATION_ERROR,
+            QueryError::Internal(_) => INTERNAL_ERROR,
+            QueryError::AccessDenied { .. } => ACCESS_DENIED,
+            QueryError::Timeout { .. } => QUERY_TIMEOUT,
+            QueryError::UniqueConstraintViolation { .. } => UNIQUE_CONSTRAINT_VIOLATION,
+        }
+    }
 }
 
 /// An enum representing the fields to select in a query.
-#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
 pub enum Select {
     #[default]
     All,
     Columns(Vec<&'static str>),
+    /// Selects the source columns of the mappings, renamed and transformed.
+    Mapped(Vec<ColumnMapping>),
+    /// Selects the given columns, returning only one row for each distinct combination of their values.
+    Distinct(Vec<&'static str>),
+    /// Selects all the columns, returning only one row for each distinct combination of their values.
+    AllDistinct,
+}
+
+/// A selected column, returned with the name `alias` and optionally transformed.
+#[derive(Debug, Clone, Copy, Eq)]
+pub struct ColumnMapping {
+    /// The selected column of the table.
+    pub source: &'static str,
+    /// The name of the column in the results.
+    pub alias: &'static str,
+    /// A function applied to the selected values, if any.
+    pub transform: Option<fn(Value) -> Value>,
+}
+
+/// Transforms are compared by address, which is enough to tell cached queries apart.
+impl PartialEq for ColumnMapping {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+            && self.alias == other.alias
+            && self.transform_addr() == other.transform_addr()
+    }
+}
+
+impl Hash for ColumnMapping {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.source.hash(state);
+        self.alias.hash(state);
+        self.transform_addr().hash(state);
+    }
+}
+
+impl ColumnMapping {
+    /// Returns the address of the transform function, if any.
+    fn transform_addr(&self) -> Option<usize> {
+        self.transform.map(|transform| transform as usize)
+    }
+
+    /// Applies the mapping to the given column value, renaming the column and transforming the value.
+    pub fn apply(&self, mut column: ColumnDef, value: Value) -> (ColumnDef, Value) {
+        column.name = self.alias;
+        let value = match self.transform {
+            Some(transform) => transform(value),
+            None => value,
+        };
+        (column, value)
+    }
 }
 
 /// An enum representing the direction of ordering in a query.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, CandidType, Serialize, Deserialize)]
 pub enum OrderDirection {
     Ascending,
     Descending,
 }
 
 /// A struct representing a query in the DBMS.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(PartialEq, Eq)]
 pub struct Query<T>
 where
     T: TableSchema,
@@ -118,6 +242,25 @@ where
     pub limit: Option<usize>,
     /// Offset for pagination.
     pub offset: Option<usize>,
+    /// Primary key value to resume a cursor-based pagination after, if any.
+    ///
+    /// Only the records whose [`TableSchema::primary_key`] value is strictly greater than the cursor are selected,
+    /// in ascending primary key order; the [`Query::offset`] applies to the records after the cursor.
+    pub cursor: Option<Value>,
+    /// Whether to include soft-deleted records in the results.
+    ///
+    /// Only relevant for tables implementing [`crate::prelude::SoftDeleteSchema`].
+    pub include_deleted: bool,
+    /// Maximum time in nanoseconds the query can spend scanning the table, if any.
+    pub timeout_ns: Option<u64>,
+    /// Aggregate function to compute over the matching records, if any.
+    ///
+    /// It's evaluated by the aggregate queries, while selects ignore it.
+    pub aggregate: Option<AggregateOp>,
+    /// Columns to group the records by, computing the aggregate function for each group.
+    ///
+    /// It's evaluated by the grouped aggregate queries, while selects ignore it.
+    pub group_by: Vec<&'static str>,
     /// Marker for the table schema type.
     _marker: PhantomData<T>,
 }
@@ -134,11 +277,82 @@ where
             order_by: Vec::new(),
             limit: None,
             offset: None,
+            cursor: None,
+            include_deleted: false,
+            timeout_ns: None,
+            aggregate: None,
+            group_by: Vec::new(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// Queries are cloned without requiring the table schema to be [`Clone`].
+impl<T> Clone for Query<T>
+where
+    T: TableSchema,
+{
+    fn clone(&self) -> Self {
+        Self {
+            columns: self.columns.clone(),
+            eager_relations: self.eager_relations.clone(),
+            filter: self.filter.clone(),
+            order_by: self.order_by.clone(),
+            limit: self.limit,
+            offset: self.offset,
+            cursor: self.cursor.clone(),
+            include_deleted: self.include_deleted,
+            timeout_ns: self.timeout_ns,
+            aggregate: self.aggregate,
+            group_by: self.group_by.clone(),
             _marker: PhantomData,
         }
     }
 }
 
+/// Queries are debugged along with their table, rendering the filter as an expression.
+impl<T> std::fmt::Debug for Query<T>
+where
+    T: TableSchema,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Query")
+            .field("table", &T::table_name())
+            .field("columns", &self.columns)
+            .field("eager_relations", &self.eager_relations)
+            .field("filter", &self.filter.as_ref().map(Filter::to_expression))
+            .field("order_by", &self.order_by)
+            .field("limit", &self.limit)
+            .field("offset", &self.offset)
+            .field("cursor", &self.cursor)
+            .field("include_deleted", &self.include_deleted)
+            .field("timeout_ns", &self.timeout_ns)
+            .field("aggregate", &self.aggregate)
+            .field("group_by", &self.group_by)
+            .finish()
+    }
+}
+
+impl<T> Hash for Query<T>
+where
+    T: TableSchema,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        T::table_name().hash(state);
+        self.columns.hash(state);
+        self.eager_relations.hash(state);
+        self.filter.hash(state);
+        self.order_by.hash(state);
+        self.limit.hash(state);
+        self.offset.hash(state);
+        self.cursor.hash(state);
+        self.include_deleted.hash(state);
+        self.timeout_ns.hash(state);
+        self.aggregate.hash(state);
+        self.group_by.hash(state);
+    }
+}
+
 impl<T> Query<T>
 where
     T: TableSchema,
@@ -148,16 +362,41 @@ where
         QueryBuilder::default()
     }
 
+    /// Sets the maximum time in nanoseconds the query can spend scanning the table.
+    ///
+    /// Once exceeded, the query fails with [`QueryError::Timeout`].
+    pub fn with_timeout_ns(mut self, ns: u64) -> Self {
+        self.timeout_ns = Some(ns);
+        self
+    }
+
     /// Returns whether all columns are selected in the query.
     pub fn all_selected(&self) -> bool {
-        matches!(self.columns, Select::All)
+        matches!(self.columns, Select::All | Select::AllDistinct)
+    }
+
+    /// Returns whether the query selects only the rows with distinct values of the selected columns,
+    /// with [`Select::Distinct`] or [`Select::AllDistinct`].
+    pub fn is_distinct(&self) -> bool {
+        matches!(self.columns, Select::Distinct(_) | Select::AllDistinct)
     }
 
     /// Returns the list of columns to be selected in the query.
+    ///
+    /// For [`Select::Mapped`] queries, these are the source columns of the mappings.
     pub fn columns(&self) -> Vec<&'static str> {
         match &self.columns {
-            Select::All => T::columns().iter().map(|col| col.name).collect(),
-            Select::Columns(cols) => cols.clone(),
+            Select::All | Select::AllDistinct => T::columns().iter().map(|col| col.name).collect(),
+            Select::Columns(cols) | Select::Distinct(cols) => cols.clone(),
+            Select::Mapped(mappings) => mappings.iter().map(|mapping| mapping.source).collect(),
+        }
+    }
+
+    /// Returns the [`ColumnMapping`]s of the query, if it selects [`Select::Mapped`] columns.
+    pub fn column_mappings(&self) -> Option<&[ColumnMapping]> {
+        match &self.columns {
+            Select::Mapped(mappings) => Some(mappings),
+            _ => None,
         }
     }
 }
@@ -177,6 +416,10 @@ mod tests {
         assert!(query.order_by.is_empty());
         assert!(query.limit.is_none());
         assert!(query.offset.is_none());
+        assert!(query.cursor.is_none());
+        assert!(!query.include_deleted);
+        assert!(query.timeout_ns.is_none());
+        assert!(query.group_by.is_empty());
     }
 
     #[test]
@@ -199,4 +442,72 @@ mod tests {
         let query = Query::<User>::default();
         assert!(query.all_selected());
     }
+
+    #[test]
+    fn test_should_get_distinct_columns() {
+        let query = Query::<User> {
+            columns: Select::Distinct(vec!["name"]),
+            ..Default::default()
+        };
+        assert!(query.is_distinct());
+        assert!(!query.all_selected());
+        assert_eq!(query.columns(), vec!["name"]);
+
+        let query = Query::<User> {
+            columns: Select::AllDistinct,
+            ..Default::default()
+        };
+        assert!(query.is_distinct());
+        assert!(query.all_selected());
+        assert_eq!(query.columns(), vec!["id", "name"]);
+
+        assert!(!Query::<User>::default().is_distinct());
+    }
+
+    #[test]
+    fn test_should_hash_query() {
+        fn hash(query: &Query<User>) -> u64 {
+            let mut hasher = std::hash::DefaultHasher::new();
+            query.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        let query = Query::<User>::builder()
+            .and_where(Filter::eq("id", Value::Uint32(1u32.into())))
+            .build();
+        assert_eq!(hash(&query), hash(&query.clone()));
+
+        let other = Query::<User>::builder()
+            .and_where(Filter::eq("id", Value::Uint32(2u32.into())))
+            .build();
+        assert_ne!(hash(&query), hash(&other));
+    }
+
+    #[test]
+    fn test_should_debug_query() {
+        let query = Query::<User>::builder()
+            .field("name")
+            .and_where(Filter::eq("id", Value::Uint32(1u32.into())))
+            .order_by_desc("name")
+            .limit(10)
+            .offset(5)
+            .build();
+
+        assert_eq!(
+            format!("{query:?}"),
+            "Query { table: \"users\", columns: Columns([\"name\"]), eager_relations: [], \
+             filter: Some(\"id = 1u32\"), order_by: [(\"name\", Descending)], limit: Some(10), \
+             offset: Some(5), cursor: None, include_deleted: false, timeout_ns: None, aggregate: None, \
+             group_by: [] }"
+        );
+    }
+
+    #[test]
+    fn test_should_set_query_timeout() {
+        let query = Query::<User>::builder()
+            .all()
+            .build()
+            .with_timeout_ns(1_000);
+        assert_eq!(query.timeout_ns, Some(1_000));
+    }
 }
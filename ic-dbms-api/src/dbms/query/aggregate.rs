@@ -0,0 +1,46 @@
+/// An aggregate function computed over the records matched by a [`super::Query`].
+///
+/// Set it with the aggregate terminators of the [`super::QueryBuilder`], such as [`super::QueryBuilder::count`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AggregateOp {
+    /// Counts the matching records.
+    Count,
+    /// Sums the values of a numeric column, skipping `NULL` values.
+    Sum(&'static str),
+    /// Takes the minimum value of a column, skipping `NULL` values.
+    Min(&'static str),
+    /// Takes the maximum value of a column, skipping `NULL` values.
+    Max(&'static str),
+    /// Averages the values of a numeric column, skipping `NULL` values.
+    ///
+    /// The average of integer columns is truncated towards zero.
+    Avg(&'static str),
+}
+
+impl AggregateOp {
+    /// Returns the column the aggregate is computed on, if any.
+    pub fn column(&self) -> Option<&'static str> {
+        match self {
+            AggregateOp::Count => None,
+            AggregateOp::Sum(column)
+            | AggregateOp::Min(column)
+            | AggregateOp::Max(column)
+            | AggregateOp::Avg(column) => Some(column),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_should_get_aggregate_column() {
+        assert_eq!(AggregateOp::Count.column(), None);
+        assert_eq!(AggregateOp::Sum("age").column(), Some("age"));
+        assert_eq!(AggregateOp::Min("age").column(), Some("age"));
+        assert_eq!(AggregateOp::Max("age").column(), Some("age"));
+        assert_eq!(AggregateOp::Avg("age").column(), Some("age"));
+    }
+}
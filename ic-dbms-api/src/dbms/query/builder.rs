@@ -1,7 +1,11 @@
 use std::marker::PhantomData;
 
-use crate::dbms::query::{Filter, OrderDirection, Query};
+use crate::dbms::query::{
+    AggregateOp, ColumnMapping, CountQuery, Filter, JoinQuery, JoinType, OrderDirection, Query,
+    Select,
+};
 use crate::dbms::table::TableSchema;
+use crate::dbms::value::Value;
 
 /// A builder for constructing database [`Query`]es.
 #[derive(Debug, Clone)]
@@ -40,14 +44,56 @@ where
             crate::dbms::query::Select::All => {
                 self.query.columns = crate::dbms::query::Select::Columns(vec![field]);
             }
-            crate::dbms::query::Select::Columns(cols) if !cols.contains(&field) => {
+            crate::dbms::query::Select::AllDistinct => {
+                self.query.columns = crate::dbms::query::Select::Distinct(vec![field]);
+            }
+            crate::dbms::query::Select::Columns(cols)
+            | crate::dbms::query::Select::Distinct(cols)
+                if !cols.contains(&field) =>
+            {
                 cols.push(field);
             }
+            crate::dbms::query::Select::Mapped(mappings) => {
+                mappings.push(ColumnMapping {
+                    source: field,
+                    alias: field,
+                    transform: None,
+                });
+            }
             _ => {}
         }
         self
     }
 
+    /// Selects the `source` field, returned as `alias`.
+    ///
+    /// The fields already selected keep their names.
+    /// Note that aliased columns are not part of the table records, so the results should be
+    /// read as column values, and ordering applies to the aliases.
+    pub fn map_field(self, source: &'static str, alias: &'static str) -> Self {
+        self.push_mapping(ColumnMapping {
+            source,
+            alias,
+            transform: None,
+        })
+    }
+
+    /// Selects the `source` field, returned as `alias` and transformed with `transform`.
+    ///
+    /// See [`QueryBuilder::map_field`].
+    pub fn transform_field(
+        self,
+        source: &'static str,
+        alias: &'static str,
+        transform: fn(Value) -> Value,
+    ) -> Self {
+        self.push_mapping(ColumnMapping {
+            source,
+            alias,
+            transform: Some(transform),
+        })
+    }
+
     /// Adds multiple fields to select in the query.
     pub fn fields<I>(mut self, fields: I) -> Self
     where
@@ -59,9 +105,47 @@ where
         self
     }
 
+    /// Adds a [`ColumnMapping`] to the selected fields, turning them into [`Select::Mapped`].
+    fn push_mapping(mut self, mapping: ColumnMapping) -> Self {
+        let identity = |field: &'static str| ColumnMapping {
+            source: field,
+            alias: field,
+            transform: None,
+        };
+        let mut mappings = match std::mem::take(&mut self.query.columns) {
+            Select::All | Select::AllDistinct => vec![],
+            Select::Columns(cols) | Select::Distinct(cols) => {
+                cols.into_iter().map(identity).collect()
+            }
+            Select::Mapped(mappings) => mappings,
+        };
+        mappings.push(mapping);
+        self.query.columns = Select::Mapped(mappings);
+        self
+    }
+
     /// Sets the query to select all fields.
+    ///
+    /// A [`QueryBuilder::distinct`] query stays distinct.
     pub fn all(mut self) -> Self {
-        self.query.columns = crate::dbms::query::Select::All;
+        self.query.columns = if self.query.is_distinct() {
+            crate::dbms::query::Select::AllDistinct
+        } else {
+            crate::dbms::query::Select::All
+        };
+        self
+    }
+
+    /// Sets the query to select only the rows with distinct values of the selected fields.
+    ///
+    /// `NULL` values are equal to each other; when ordered, the first of the duplicate rows is kept.
+    /// Mapped fields are never deduplicated.
+    pub fn distinct(mut self) -> Self {
+        self.query.columns = match std::mem::take(&mut self.query.columns) {
+            Select::All | Select::AllDistinct => Select::AllDistinct,
+            Select::Columns(cols) | Select::Distinct(cols) => Select::Distinct(cols),
+            Select::Mapped(mappings) => Select::Mapped(mappings),
+        };
         self
     }
 
@@ -87,6 +171,16 @@ where
         self
     }
 
+    /// Adds a field to group the records by, for the grouped aggregate queries.
+    ///
+    /// Records with `NULL` values of the field form their own group.
+    pub fn group_by(mut self, field: &'static str) -> Self {
+        if !self.query.group_by.contains(&field) {
+            self.query.group_by.push(field);
+        }
+        self
+    }
+
     /// Sets a limit on the number of records to return.
     pub fn limit(mut self, limit: usize) -> Self {
         self.query.limit = Some(limit);
@@ -99,6 +193,22 @@ where
         self
     }
 
+    /// Selects only the records whose primary key value is strictly greater than `pk`,
+    /// to resume a cursor-based pagination.
+    pub fn after(mut self, pk: Value) -> Self {
+        self.query.cursor = Some(pk);
+        self
+    }
+
+    /// Includes soft-deleted records in the query results.
+    ///
+    /// By default, records of tables implementing [`crate::prelude::SoftDeleteSchema`]
+    /// with a non-null `deleted_at` column are excluded from the results.
+    pub fn include_deleted(mut self) -> Self {
+        self.query.include_deleted = true;
+        self
+    }
+
     /// Sets a filter for the query, replacing any existing filter.
     pub fn filter(mut self, filter: Option<Filter>) -> Self {
         self.query.filter = filter;
@@ -122,6 +232,147 @@ where
         };
         self
     }
+
+    /// Adds a case-insensitive LIKE filter on `field`, combining with existing filters using AND.
+    pub fn and_where_ilike(self, field: &'static str, pattern: &str) -> Self {
+        self.and_where(Filter::ilike(field, pattern))
+    }
+
+    /// Adds a case-insensitive LIKE filter on `field`, combining with existing filters using OR.
+    pub fn or_where_ilike(self, field: &'static str, pattern: &str) -> Self {
+        self.or_where(Filter::ilike(field, pattern))
+    }
+
+    /// Adds a filter matching the values of `field` between `low` and `high`, both inclusive,
+    /// combining with existing filters using AND.
+    pub fn between_where(self, field: &'static str, low: Value, high: Value) -> Self {
+        self.and_where(Filter::between(field, low, high))
+    }
+
+    /// Adds a filter matching when column `left` is equal to column `right`,
+    /// combining with existing filters using AND.
+    pub fn column_eq_where(self, left: &'static str, right: &'static str) -> Self {
+        self.and_where(Filter::column_eq(left, right))
+    }
+
+    /// Adds a filter matching when column `left` is not equal to column `right`,
+    /// combining with existing filters using AND.
+    pub fn column_ne_where(self, left: &'static str, right: &'static str) -> Self {
+        self.and_where(Filter::column_ne(left, right))
+    }
+
+    /// Adds a filter matching when column `left` is greater than column `right`,
+    /// combining with existing filters using AND.
+    pub fn column_gt_where(self, left: &'static str, right: &'static str) -> Self {
+        self.and_where(Filter::column_gt(left, right))
+    }
+
+    /// Adds a filter matching when column `left` is less than column `right`,
+    /// combining with existing filters using AND.
+    pub fn column_lt_where(self, left: &'static str, right: &'static str) -> Self {
+        self.and_where(Filter::column_lt(left, right))
+    }
+
+    /// Adds a filter matching when column `left` is greater than or equal to column `right`,
+    /// combining with existing filters using AND.
+    pub fn column_ge_where(self, left: &'static str, right: &'static str) -> Self {
+        self.and_where(Filter::column_ge(left, right))
+    }
+
+    /// Adds a filter matching when column `left` is less than or equal to column `right`,
+    /// combining with existing filters using AND.
+    pub fn column_le_where(self, left: &'static str, right: &'static str) -> Self {
+        self.and_where(Filter::column_le(left, right))
+    }
+
+    /// Adds a filter matching the Text values of `field` starting with `prefix`,
+    /// combining with existing filters using AND.
+    pub fn starts_with_where(self, field: &'static str, prefix: &str) -> Self {
+        self.and_where(Filter::starts_with(field, prefix))
+    }
+
+    /// Adds a filter matching the Text values of `field` ending with `suffix`,
+    /// combining with existing filters using AND.
+    pub fn ends_with_where(self, field: &'static str, suffix: &str) -> Self {
+        self.and_where(Filter::ends_with(field, suffix))
+    }
+
+    /// Adds a case-insensitive filter matching the Text values of `field` starting with `prefix`,
+    /// combining with existing filters using AND.
+    pub fn istarts_with_where(self, field: &'static str, prefix: &str) -> Self {
+        self.and_where(Filter::istarts_with(field, prefix))
+    }
+
+    /// Adds a case-insensitive filter matching the Text values of `field` ending with `suffix`,
+    /// combining with existing filters using AND.
+    pub fn iends_with_where(self, field: &'static str, suffix: &str) -> Self {
+        self.and_where(Filter::iends_with(field, suffix))
+    }
+
+    /// Builds a [`CountQuery`] counting the records matching the filter of the query.
+    ///
+    /// The selected fields, relations, ordering, limit and offset are discarded.
+    pub fn count_only(self) -> CountQuery<T> {
+        CountQuery::new(self.build())
+    }
+
+    /// Builds a [`Query`] counting the matching records.
+    pub fn count(self) -> Query<T> {
+        self.aggregate(AggregateOp::Count)
+    }
+
+    /// Builds a [`Query`] summing the values of `field` of the matching records.
+    pub fn sum(self, field: &'static str) -> Query<T> {
+        self.aggregate(AggregateOp::Sum(field))
+    }
+
+    /// Builds a [`Query`] taking the minimum value of `field` of the matching records.
+    pub fn min(self, field: &'static str) -> Query<T> {
+        self.aggregate(AggregateOp::Min(field))
+    }
+
+    /// Builds a [`Query`] taking the maximum value of `field` of the matching records.
+    pub fn max(self, field: &'static str) -> Query<T> {
+        self.aggregate(AggregateOp::Max(field))
+    }
+
+    /// Builds a [`Query`] averaging the values of `field` of the matching records.
+    pub fn avg(self, field: &'static str) -> Query<T> {
+        self.aggregate(AggregateOp::Avg(field))
+    }
+
+    /// Sets the aggregate function of the query and builds it.
+    fn aggregate(mut self, aggregate: AggregateOp) -> Query<T> {
+        self.query.aggregate = Some(aggregate);
+        self.build()
+    }
+
+    /// Builds an inner [`JoinQuery`] of the queried records with the records of the table `B`,
+    /// where the column `on_a` is equal to the column `on_b` of `B`.
+    pub fn join<B>(self, on_a: &'static str, on_b: &'static str) -> JoinQuery<T, B>
+    where
+        B: TableSchema,
+    {
+        JoinQuery::new(self.build(), on_a, on_b, JoinType::Inner)
+    }
+
+    /// Builds a left [`JoinQuery`] of the queried records with the records of the table `B`,
+    /// where the column `on_a` is equal to the column `on_b` of `B`.
+    pub fn left_join<B>(self, on_a: &'static str, on_b: &'static str) -> JoinQuery<T, B>
+    where
+        B: TableSchema,
+    {
+        JoinQuery::new(self.build(), on_a, on_b, JoinType::Left)
+    }
+
+    /// Builds a right [`JoinQuery`] of the queried records with the records of the table `B`,
+    /// where the column `on_a` is equal to the column `on_b` of `B`.
+    pub fn right_join<B>(self, on_a: &'static str, on_b: &'static str) -> JoinQuery<T, B>
+    where
+        B: TableSchema,
+    {
+        JoinQuery::new(self.build(), on_a, on_b, JoinType::Right)
+    }
 }
 
 #[cfg(test)]
@@ -167,6 +418,30 @@ mod tests {
         assert!(matches!(query.columns, crate::dbms::query::Select::All));
     }
 
+    #[test]
+    fn test_should_map_fields() {
+        fn to_upper(value: Value) -> Value {
+            match value {
+                Value::Text(text) => Value::Text(text.0.to_uppercase().into()),
+                value => value,
+            }
+        }
+
+        let query = QueryBuilder::<User>::default()
+            .field("id")
+            .map_field("name", "username")
+            .transform_field("email", "upper_email", to_upper)
+            .build();
+
+        assert_eq!(query.columns(), vec!["id", "name", "email"]);
+        let mappings = query.column_mappings().expect("should be mapped");
+        assert_eq!(mappings[0].alias, "id");
+        assert_eq!(mappings[1].alias, "username");
+        assert!(mappings[1].transform.is_none());
+        assert_eq!(mappings[2].alias, "upper_email");
+        assert!(!query.all_selected());
+    }
+
     #[test]
     fn test_should_add_eager_relation() {
         let query_builder = QueryBuilder::<User>::default().with("posts");
@@ -204,6 +479,47 @@ mod tests {
         assert_eq!(query.offset, Some(5));
     }
 
+    #[test]
+    fn test_should_set_distinct() {
+        let query = QueryBuilder::<User>::default()
+            .distinct()
+            .field("name")
+            .build();
+        assert!(matches!(
+            query.columns,
+            crate::dbms::query::Select::Distinct(ref cols) if cols == &["name"]
+        ));
+
+        let query = QueryBuilder::<User>::default()
+            .field("id")
+            .distinct()
+            .all()
+            .build();
+        assert!(matches!(
+            query.columns,
+            crate::dbms::query::Select::AllDistinct
+        ));
+    }
+
+    #[test]
+    fn test_should_set_cursor() {
+        let query = QueryBuilder::<User>::default()
+            .after(Value::Uint32(10u32.into()))
+            .limit(10)
+            .build();
+        assert_eq!(query.cursor, Some(Value::Uint32(10u32.into())));
+        assert_eq!(query.limit, Some(10));
+    }
+
+    #[test]
+    fn test_should_include_deleted() {
+        let query = QueryBuilder::<User>::default().build();
+        assert!(!query.include_deleted);
+
+        let query = QueryBuilder::<User>::default().include_deleted().build();
+        assert!(query.include_deleted);
+    }
+
     #[test]
     fn test_should_create_filters() {
         let query = QueryBuilder::<User>::default()
@@ -220,4 +536,111 @@ mod tests {
             panic!("Expected OR filter at the top level");
         }
     }
+
+    #[test]
+    fn test_should_create_ilike_filters() {
+        let query = QueryBuilder::<User>::default()
+            .and_where_ilike("name", "%ALICE%")
+            .or_where_ilike("name", "bob%")
+            .build();
+
+        let filter = query.filter.expect("should have filter");
+        assert_eq!(
+            filter,
+            Filter::ilike("name", "%ALICE%").or(Filter::ilike("name", "bob%"))
+        );
+    }
+
+    #[test]
+    fn test_should_create_column_filters() {
+        let query = QueryBuilder::<User>::default()
+            .column_le_where("start_date", "end_date")
+            .column_ne_where("id", "name")
+            .build();
+
+        let filter = query.filter.expect("should have filter");
+        assert_eq!(
+            filter,
+            Filter::column_le("start_date", "end_date").and(Filter::column_ne("id", "name"))
+        );
+    }
+
+    #[test]
+    fn test_should_create_starts_with_and_ends_with_filters() {
+        let query = QueryBuilder::<User>::default()
+            .starts_with_where("name", "Jo")
+            .ends_with_where("name", "hn")
+            .istarts_with_where("name", "JO")
+            .iends_with_where("name", "HN")
+            .build();
+
+        let filter = query.filter.expect("should have filter");
+        assert_eq!(
+            filter,
+            Filter::starts_with("name", "Jo")
+                .and(Filter::ends_with("name", "hn"))
+                .and(Filter::istarts_with("name", "JO"))
+                .and(Filter::iends_with("name", "HN"))
+        );
+    }
+
+    #[test]
+    fn test_should_create_between_filter() {
+        let query = QueryBuilder::<User>::default()
+            .and_where(Filter::ne("name", Value::Text("John".to_string().into())))
+            .between_where(
+                "id",
+                Value::Uint32(1u32.into()),
+                Value::Uint32(10u32.into()),
+            )
+            .build();
+
+        let filter = query.filter.expect("should have filter");
+        assert_eq!(
+            filter,
+            Filter::ne("name", Value::Text("John".to_string().into())).and(Filter::between(
+                "id",
+                Value::Uint32(1u32.into()),
+                Value::Uint32(10u32.into())
+            ))
+        );
+    }
+
+    #[test]
+    fn test_should_group_by_fields() {
+        let query = QueryBuilder::<User>::default()
+            .group_by("name")
+            .group_by("id")
+            .group_by("name")
+            .count();
+        assert_eq!(query.group_by, vec!["name", "id"]);
+        assert_eq!(query.aggregate, Some(AggregateOp::Count));
+    }
+
+    #[test]
+    fn test_should_build_aggregate_queries() {
+        let query = QueryBuilder::<User>::default()
+            .and_where(Filter::gt("id", Value::Uint32(1u32.into())))
+            .count();
+        assert_eq!(query.aggregate, Some(AggregateOp::Count));
+        assert!(query.filter.is_some());
+
+        assert_eq!(
+            QueryBuilder::<User>::default().sum("id").aggregate,
+            Some(AggregateOp::Sum("id"))
+        );
+        assert_eq!(
+            QueryBuilder::<User>::default().min("id").aggregate,
+            Some(AggregateOp::Min("id"))
+        );
+        assert_eq!(
+            QueryBuilder::<User>::default().max("id").aggregate,
+            Some(AggregateOp::Max("id"))
+        );
+        assert_eq!(
+            QueryBuilder::<User>::default().avg("id").aggregate,
+            Some(AggregateOp::Avg("id"))
+        );
+        assert_eq!(QueryBuilder::<User>::default().build().aggregate, None);
+    }
 }
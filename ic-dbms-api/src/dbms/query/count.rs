@@ -0,0 +1,69 @@
+use std::marker::PhantomData;
+
+use crate::dbms::query::{Filter, Query};
+use crate::dbms::table::TableSchema;
+
+/// A query counting the records of the table `T` matching a [`Filter`], without loading them.
+///
+/// Build it with [`crate::prelude::QueryBuilder::count_only`].
+/// Ordering, limit and offset are meaningless for counts, so they are not supported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CountQuery<T>
+where
+    T: TableSchema,
+{
+    /// [`Filter`] the counted records must match.
+    pub filter: Option<Filter>,
+    /// Whether to count soft-deleted records too.
+    ///
+    /// Only relevant for tables implementing [`crate::prelude::SoftDeleteSchema`].
+    pub include_deleted: bool,
+    /// Marker for the table schema type.
+    _marker: PhantomData<T>,
+}
+
+impl<T> CountQuery<T>
+where
+    T: TableSchema,
+{
+    /// Creates a [`CountQuery`] counting the records matched by the filter of the given [`Query`].
+    pub fn new(query: Query<T>) -> Self {
+        Self {
+            filter: query.filter,
+            include_deleted: query.include_deleted,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Sets a filter for the count, replacing any existing filter.
+    pub fn filter(mut self, filter: Option<Filter>) -> Self {
+        self.filter = filter;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::dbms::value::Value;
+    use crate::tests::User;
+
+    #[test]
+    fn test_should_build_count_query() {
+        let count = Query::<User>::builder()
+            .and_where(Filter::eq("name", Value::Text("Alice".to_string().into())))
+            .include_deleted()
+            .order_by_asc("id")
+            .limit(10)
+            .count_only();
+        assert_eq!(
+            count.filter,
+            Some(Filter::eq("name", Value::Text("Alice".to_string().into())))
+        );
+        assert!(count.include_deleted);
+
+        let count = count.filter(None);
+        assert_eq!(count.filter, None);
+    }
+}
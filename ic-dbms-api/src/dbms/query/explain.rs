@@ -0,0 +1,269 @@
+use std::fmt;
+
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+use crate::dbms::query::OrderDirection;
+
+/// Estimated cost of the execution of a [`super::Query`],
+/// as returned by [`crate::prelude::Database::explain_execution`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExecutionEstimate {
+    /// Estimated amount of records read to execute the query.
+    pub estimated_rows_scanned: u64,
+    /// Estimated amount of memory pages read to execute the query.
+    pub estimated_pages_read: u64,
+    /// Estimated amount of reads from stable memory to execute the query.
+    pub estimated_stable_reads: u64,
+    /// Indexes used to execute the query.
+    pub index_uses: Vec<IndexUsage>,
+    /// Estimated fraction of the scanned records matching the query filter, in the range `0.0..=1.0`.
+    pub filter_selectivity: f64,
+}
+
+/// An index used to execute a query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexUsage {
+    /// The indexed column.
+    pub column: &'static str,
+}
+
+/// The plan of the execution of a [`super::Query`], as returned by `IcDbmsDatabase::explain`.
+///
+/// The plan is a tree of [`QueryPlanNode`]s, where each node processes the records produced by its input;
+/// it's displayed with the root first, and the leaf, which reads the records, last.
+#[derive(Debug, Clone, PartialEq, Eq, CandidType, Serialize, Deserialize)]
+pub struct QueryPlan {
+    /// The last step of the plan.
+    pub root: QueryPlanNode,
+}
+
+/// A step of a [`QueryPlan`].
+#[derive(Debug, Clone, PartialEq, Eq, CandidType, Serialize, Deserialize)]
+pub enum QueryPlanNode {
+    /// Reads all the records of the table, keeping the ones matching the filter.
+    TableScan {
+        /// The scanned table.
+        table: String,
+        /// The filter expression the records must match, if any.
+        filter: Option<String>,
+        /// Estimated amount of records stored in the table.
+        estimated_rows: u64,
+    },
+    /// Reads the records of a table located by the index on a column compared by the filter.
+    IndexScan {
+        /// The table whose records are read.
+        table: String,
+        /// The indexed column.
+        column: String,
+        /// The filter expression the records must match, if any.
+        filter: Option<String>,
+        /// Amount of records located by the index.
+        estimated_rows: u64,
+    },
+    /// Sorts the records by a column.
+    Sort {
+        /// The column to sort by.
+        column: String,
+        /// The direction of the ordering.
+        direction: OrderDirection,
+        /// The step producing the records to sort.
+        input: Box<QueryPlanNode>,
+    },
+    /// Skips the first `n` records.
+    Offset {
+        /// The amount of records to skip.
+        n: u64,
+        /// The step producing the records to skip.
+        input: Box<QueryPlanNode>,
+    },
+    /// Keeps at most `n` records.
+    Limit {
+        /// The maximum amount of records.
+        n: u64,
+        /// The step producing the records to limit.
+        input: Box<QueryPlanNode>,
+    },
+    /// Loads the records of a related table for each record.
+    EagerLoad {
+        /// The related table.
+        table: String,
+        /// The step producing the records to load the relation for.
+        input: Box<QueryPlanNode>,
+    },
+}
+
+impl QueryPlan {
+    /// Returns the nodes of the plan in execution order, starting from the leaf.
+    pub fn steps(&self) -> Vec<&QueryPlanNode> {
+        let mut steps = vec![&self.root];
+        while let Some(input) = steps.last().and_then(|node| node.input()) {
+            steps.push(input);
+        }
+        steps.reverse();
+
+        steps
+    }
+}
+
+impl QueryPlanNode {
+    /// Returns the node producing the records processed by this node, if any.
+    pub fn input(&self) -> Option<&QueryPlanNode> {
+        match self {
+            QueryPlanNode::TableScan { .. } | QueryPlanNode::IndexScan { .. } => None,
+            QueryPlanNode::Sort { input, .. }
+            | QueryPlanNode::Offset { input, .. }
+            | QueryPlanNode::Limit { input, .. }
+            | QueryPlanNode::EagerLoad { input, .. } => Some(input),
+        }
+    }
+
+    /// Writes the node, without its input.
+    fn fmt_node(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueryPlanNode::TableScan {
+                table,
+                filter,
+                estimated_rows,
+            } => {
+                write!(f, "TableScan on {table}")?;
+                if let Some(filter) = filter {
+                    write!(f, " (filter: {filter})")?;
+                }
+                write!(f, " (estimated rows: {estimated_rows})")
+            }
+            QueryPlanNode::IndexScan {
+                table,
+                column,
+                filter,
+                estimated_rows,
+            } => {
+                write!(f, "IndexScan on {table} using {column}")?;
+                if let Some(filter) = filter {
+                    write!(f, " (filter: {filter})")?;
+                }
+                write!(f, " (estimated rows: {estimated_rows})")
+            }
+            QueryPlanNode::Sort {
+                column, direction, ..
+            } => {
+                let direction = match direction {
+                    OrderDirection::Ascending => "ASC",
+                    OrderDirection::Descending => "DESC",
+                };
+                write!(f, "Sort by {column} {direction}")
+            }
+            QueryPlanNode::Offset { n, .. } => write!(f, "Offset {n}"),
+            QueryPlanNode::Limit { n, .. } => write!(f, "Limit {n}"),
+            QueryPlanNode::EagerLoad { table, .. } => write!(f, "EagerLoad {table}"),
+        }
+    }
+}
+
+/// Plans are displayed one node per line, from the root, with each input indented below its node, e.g.:
+///
+/// ```text
+/// Limit 10
+///   -> Sort by name DESC
+///     -> TableScan on users (filter: id > 1u32) (estimated rows: 100)
+/// ```
+impl fmt::Display for QueryPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut node = Some(&self.root);
+        let mut depth = 0;
+        while let Some(current) = node {
+            if depth > 0 {
+                write!(f, "\n{:indent$}-> ", "", indent = depth * 2)?;
+            }
+            current.fmt_node(f)?;
+            node = current.input();
+            depth += 1;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_should_display_query_plan() {
+        let plan = QueryPlan {
+            root: QueryPlanNode::Limit {
+                n: 10,
+                input: Box::new(QueryPlanNode::Sort {
+                    column: "name".to_string(),
+                    direction: OrderDirection::Descending,
+                    input: Box::new(QueryPlanNode::TableScan {
+                        table: "users".to_string(),
+                        filter: Some("id > 1u32".to_string()),
+                        estimated_rows: 100,
+                    }),
+                }),
+            },
+        };
+
+        assert_eq!(
+            plan.to_string(),
+            "Limit 10\n  -> Sort by name DESC\n    -> TableScan on users (filter: id > 1u32) (estimated rows: 100)"
+        );
+    }
+
+    #[test]
+    fn test_should_display_index_scan() {
+        let plan = QueryPlan {
+            root: QueryPlanNode::IndexScan {
+                table: "posts".to_string(),
+                column: "user_id".to_string(),
+                filter: Some("user_id = 1u32".to_string()),
+                estimated_rows: 3,
+            },
+        };
+
+        assert_eq!(
+            plan.to_string(),
+            "IndexScan on posts using user_id (filter: user_id = 1u32) (estimated rows: 3)"
+        );
+        assert_eq!(plan.steps().len(), 1);
+    }
+
+    #[test]
+    fn test_should_list_steps_in_execution_order() {
+        let plan = QueryPlan {
+            root: QueryPlanNode::EagerLoad {
+                table: "posts".to_string(),
+                input: Box::new(QueryPlanNode::TableScan {
+                    table: "users".to_string(),
+                    filter: None,
+                    estimated_rows: 0,
+                }),
+            },
+        };
+
+        let steps = plan.steps();
+        assert_eq!(steps.len(), 2);
+        assert!(matches!(steps[0], QueryPlanNode::TableScan { .. }));
+        assert!(matches!(steps[1], QueryPlanNode::EagerLoad { .. }));
+    }
+
+    #[test]
+    fn test_should_encode_query_plan_with_candid() {
+        let plan = QueryPlan {
+            root: QueryPlanNode::Offset {
+                n: 5,
+                input: Box::new(QueryPlanNode::TableScan {
+                    table: "users".to_string(),
+                    filter: None,
+                    estimated_rows: 42,
+                }),
+            },
+        };
+
+        let bytes = candid::encode_one(&plan).expect("failed to encode plan");
+        let decoded: QueryPlan = candid::decode_one(&bytes).expect("failed to decode plan");
+        assert_eq!(decoded, plan);
+    }
+}
@@ -1,11 +1,36 @@
-use crate::dbms::query::QueryResult;
-use crate::dbms::table::ColumnDef;
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
+
+use regex::Regex;
+
+use crate::dbms::database::Database;
+use crate::dbms::query::{Query, QueryResult};
+use crate::dbms::table::{ColumnDef, TableRecord as _, TableSchema};
 use crate::dbms::types::Text;
 use crate::dbms::value::Value;
-use crate::prelude::QueryError;
+use crate::prelude::{IcDbmsError, QueryError};
+
+mod expression;
+
+pub use self::expression::FilterParseError;
+
+/// Estimated selectivity of the filters which can't be estimated more accurately (e.g. range filters).
+const DEFAULT_SELECTIVITY: f64 = 0.1;
+
+/// Maximum amount of compiled patterns kept in the [`REGEX_CACHE`].
+const REGEX_CACHE_CAPACITY: usize = 64;
+
+thread_local! {
+    /// Compiled patterns of the [`Filter::Regex`] filters, by pattern,
+    /// so that a pattern is compiled once rather than for each matched record.
+    static REGEX_CACHE: RefCell<HashMap<String, Regex>> = RefCell::new(HashMap::new());
+}
 
 /// [`super::Query`] filters.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Filter {
     Eq(&'static str, Value),
     Ne(&'static str, Value),
@@ -13,21 +38,128 @@ pub enum Filter {
     Lt(&'static str, Value),
     Ge(&'static str, Value),
     In(&'static str, Vec<Value>),
+    /// Like [`Filter::In`], but the list of values is computed lazily when the filter is matched.
+    DynamicIn(&'static str, DynamicValues),
     Le(&'static str, Value),
+    Between(&'static str, Value, BoundaryKind, Value, BoundaryKind),
     Like(&'static str, String),
+    ILike(&'static str, String),
+    /// Matches the Text values starting with the given prefix.
+    StartsWith(&'static str, String),
+    /// Matches the Text values ending with the given suffix.
+    EndsWith(&'static str, String),
+    /// Like [`Filter::StartsWith`], but case-insensitive.
+    IStartsWith(&'static str, String),
+    /// Like [`Filter::EndsWith`], but case-insensitive.
+    IEndsWith(&'static str, String),
+    /// Matches the Text values matching the given regular expression, anywhere in the text unless anchored.
+    Regex(&'static str, String),
+    /// Matches the Array values containing the given element.
+    Contains(&'static str, Value),
     NotNull(&'static str),
     IsNull(&'static str),
+    ColumnEq(&'static str, &'static str),
+    ColumnNe(&'static str, &'static str),
+    ColumnGt(&'static str, &'static str),
+    ColumnLt(&'static str, &'static str),
+    ColumnGe(&'static str, &'static str),
+    ColumnLe(&'static str, &'static str),
     And(Box<Filter>, Box<Filter>),
     Or(Box<Filter>, Box<Filter>),
     Not(Box<Filter>),
 }
 
+/// The lazily computed list of values of a [`Filter::DynamicIn`].
+///
+/// Clones share the same function, and two [`DynamicValues`] are equal only if they share it.
+#[derive(Clone)]
+pub struct DynamicValues(Rc<dyn Fn() -> QueryResult<Vec<Value>>>);
+
+impl DynamicValues {
+    /// Creates new [`DynamicValues`] computed by the given function.
+    pub fn new(values: impl Fn() -> QueryResult<Vec<Value>> + 'static) -> Self {
+        Self(Rc::new(values))
+    }
+
+    /// Computes the values.
+    pub fn values(&self) -> QueryResult<Vec<Value>> {
+        (self.0)()
+    }
+}
+
+impl std::fmt::Debug for DynamicValues {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("DynamicValues").finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for DynamicValues {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for DynamicValues {}
+
+impl Hash for DynamicValues {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (Rc::as_ptr(&self.0) as *const () as usize).hash(state);
+    }
+}
+
+/// Whether a boundary of a [`Filter::Between`] range includes the boundary value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BoundaryKind {
+    /// The boundary value is part of the range (`>=` / `<=`).
+    Inclusive,
+    /// The boundary value is not part of the range (`>` / `<`).
+    Exclusive,
+}
+
+impl BoundaryKind {
+    /// Checks whether `value` lies on the range side of the lower boundary `low`.
+    fn check_lower(self, value: &Value, low: &Value) -> bool {
+        match self {
+            BoundaryKind::Inclusive => value >= low,
+            BoundaryKind::Exclusive => value > low,
+        }
+    }
+
+    /// Checks whether `value` lies on the range side of the upper boundary `high`.
+    fn check_upper(self, value: &Value, high: &Value) -> bool {
+        match self {
+            BoundaryKind::Inclusive => value <= high,
+            BoundaryKind::Exclusive => value < high,
+        }
+    }
+}
+
 impl Filter {
     /// Creates an equality filter.
     pub fn eq(field: &'static str, value: Value) -> Self {
         Filter::Eq(field, value)
     }
 
+    /// Creates a filter matching each of `fields` to the value at the same position in `values`,
+    /// chaining the equality filters with [`Filter::And`]; used to match composite primary keys.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `fields` is empty or if `fields` and `values` have different lengths.
+    pub fn eq_composite(fields: &'static [&'static str], values: Vec<Value>) -> Self {
+        assert_eq!(
+            fields.len(),
+            values.len(),
+            "composite filter fields and values must have the same length"
+        );
+        fields
+            .iter()
+            .zip(values)
+            .map(|(field, value)| Filter::eq(*field, value))
+            .reduce(Filter::and)
+            .expect("composite filter must have at least one field")
+    }
+
     /// Creates a not-equal filter.
     pub fn ne(field: &'static str, value: Value) -> Self {
         Filter::Ne(field, value)
@@ -53,16 +185,160 @@ impl Filter {
         Filter::Le(field, value)
     }
 
+    /// Creates a BETWEEN filter including both boundaries (`low <= field <= high`),
+    /// as the SQL `BETWEEN` operator; see [`Filter::between_inclusive`].
+    ///
+    /// A range where `low > high` matches no value.
+    pub fn between(field: &'static str, low: Value, high: Value) -> Self {
+        Self::between_inclusive(field, low, high)
+    }
+
+    /// Creates a BETWEEN filter including both boundaries (`low <= field <= high`).
+    pub fn between_inclusive(field: &'static str, low: Value, high: Value) -> Self {
+        Filter::Between(
+            field,
+            low,
+            BoundaryKind::Inclusive,
+            high,
+            BoundaryKind::Inclusive,
+        )
+    }
+
+    /// Creates a BETWEEN filter excluding both boundaries (`low < field < high`).
+    pub fn between_exclusive(field: &'static str, low: Value, high: Value) -> Self {
+        Filter::Between(
+            field,
+            low,
+            BoundaryKind::Exclusive,
+            high,
+            BoundaryKind::Exclusive,
+        )
+    }
+
+    /// Creates a BETWEEN filter excluding the lower boundary (`low < field <= high`).
+    pub fn between_left_open(field: &'static str, low: Value, high: Value) -> Self {
+        Filter::Between(
+            field,
+            low,
+            BoundaryKind::Exclusive,
+            high,
+            BoundaryKind::Inclusive,
+        )
+    }
+
+    /// Creates a BETWEEN filter excluding the upper boundary (`low <= field < high`).
+    pub fn between_right_open(field: &'static str, low: Value, high: Value) -> Self {
+        Filter::Between(
+            field,
+            low,
+            BoundaryKind::Inclusive,
+            high,
+            BoundaryKind::Exclusive,
+        )
+    }
+
     /// Creates an IN filter.
     pub fn in_list(field: &'static str, values: Vec<Value>) -> Self {
         Filter::In(field, values)
     }
 
+    /// Creates an IN filter whose list of values is computed by `values` each time the filter is matched.
+    pub fn dynamic_in(
+        field: &'static str,
+        values: impl Fn() -> QueryResult<Vec<Value>> + 'static,
+    ) -> Self {
+        Filter::DynamicIn(field, DynamicValues::new(values))
+    }
+
+    /// Creates an IN filter matching the records whose `field` is among the values of `sub_column`
+    /// of the records selected by `sub_query` on `database`.
+    ///
+    /// The sub-select is executed the first time the filter is matched and its values are reused afterwards,
+    /// also by the clones of the filter. `sub_column` can't be a foreign key column,
+    /// since foreign keys are not part of the selected records values.
+    pub fn subselect_in<T, D>(
+        database: D,
+        field: &'static str,
+        sub_query: Query<T>,
+        sub_column: &'static str,
+    ) -> Self
+    where
+        T: TableSchema + Clone,
+        D: Database + 'static,
+    {
+        let cache: RefCell<Option<Vec<Value>>> = RefCell::new(None);
+
+        Filter::dynamic_in(field, move || {
+            if let Some(values) = cache.borrow().as_ref() {
+                return Ok(values.clone());
+            }
+            if !T::columns().iter().any(|column| column.name == sub_column) {
+                return Err(QueryError::UnknownColumn(sub_column.to_string()));
+            }
+
+            let values: Vec<Value> = database
+                .select::<T>(sub_query.clone())
+                .map_err(|err| match err {
+                    IcDbmsError::Query(err) => err,
+                    err => QueryError::Internal(err.to_string()),
+                })?
+                .into_iter()
+                .filter_map(|record| {
+                    record
+                        .to_values()
+                        .into_iter()
+                        .find(|(column, _)| column.name == sub_column)
+                        .map(|(_, value)| value)
+                })
+                .collect();
+            *cache.borrow_mut() = Some(values.clone());
+
+            Ok(values)
+        })
+    }
+
     /// Creates a LIKE filter.
     pub fn like(field: &'static str, pattern: &str) -> Self {
         Filter::Like(field, pattern.to_string())
     }
 
+    /// Creates a case-insensitive LIKE filter.
+    pub fn ilike(field: &'static str, pattern: &str) -> Self {
+        Filter::ILike(field, pattern.to_string())
+    }
+
+    /// Creates a filter matching the Text values of `field` starting with `prefix`.
+    pub fn starts_with(field: &'static str, prefix: &str) -> Self {
+        Filter::StartsWith(field, prefix.to_string())
+    }
+
+    /// Creates a filter matching the Text values of `field` ending with `suffix`.
+    pub fn ends_with(field: &'static str, suffix: &str) -> Self {
+        Filter::EndsWith(field, suffix.to_string())
+    }
+
+    /// Creates a case-insensitive filter matching the Text values of `field` starting with `prefix`.
+    pub fn istarts_with(field: &'static str, prefix: &str) -> Self {
+        Filter::IStartsWith(field, prefix.to_string())
+    }
+
+    /// Creates a case-insensitive filter matching the Text values of `field` ending with `suffix`.
+    pub fn iends_with(field: &'static str, suffix: &str) -> Self {
+        Filter::IEndsWith(field, suffix.to_string())
+    }
+
+    /// Creates a filter matching the Text values of `field` matching the regular expression `pattern`.
+    ///
+    /// The pattern uses the syntax of the [`regex`] crate; a malformed pattern fails when the filter is matched.
+    pub fn regex(field: &'static str, pattern: &str) -> Self {
+        Filter::Regex(field, pattern.to_string())
+    }
+
+    /// Creates a filter matching the Array values of `field` containing `element`.
+    pub fn contains(field: &'static str, element: Value) -> Self {
+        Filter::Contains(field, element)
+    }
+
     /// Creates a NOT NULL filter.
     pub fn not_null(field: &'static str) -> Self {
         Filter::NotNull(field)
@@ -73,6 +349,36 @@ impl Filter {
         Filter::IsNull(field)
     }
 
+    /// Creates a filter matching when column `left` is equal to column `right`.
+    pub fn column_eq(left: &'static str, right: &'static str) -> Self {
+        Filter::ColumnEq(left, right)
+    }
+
+    /// Creates a filter matching when column `left` is not equal to column `right`.
+    pub fn column_ne(left: &'static str, right: &'static str) -> Self {
+        Filter::ColumnNe(left, right)
+    }
+
+    /// Creates a filter matching when column `left` is greater than column `right`.
+    pub fn column_gt(left: &'static str, right: &'static str) -> Self {
+        Filter::ColumnGt(left, right)
+    }
+
+    /// Creates a filter matching when column `left` is less than column `right`.
+    pub fn column_lt(left: &'static str, right: &'static str) -> Self {
+        Filter::ColumnLt(left, right)
+    }
+
+    /// Creates a filter matching when column `left` is greater than or equal to column `right`.
+    pub fn column_ge(left: &'static str, right: &'static str) -> Self {
+        Filter::ColumnGe(left, right)
+    }
+
+    /// Creates a filter matching when column `left` is less than or equal to column `right`.
+    pub fn column_le(left: &'static str, right: &'static str) -> Self {
+        Filter::ColumnLe(left, right)
+    }
+
     /// Chain two filters with AND.
     pub fn and(self, other: Filter) -> Self {
         Filter::And(Box::new(self), Box::new(other))
@@ -98,47 +404,77 @@ impl Filter {
             Filter::Ne(field, value) => values
                 .iter()
                 .any(|(col, val)| col.name == *field && val != value),
-            Filter::Gt(field, value) => values
-                .iter()
-                .any(|(col, val)| col.name == *field && val > value),
-            Filter::Lt(field, value) => values
-                .iter()
-                .any(|(col, val)| col.name == *field && val < value),
-            Filter::Ge(field, value) => values
-                .iter()
-                .any(|(col, val)| col.name == *field && val >= value),
-            Filter::Le(field, value) => values
-                .iter()
-                .any(|(col, val)| col.name == *field && val <= value),
+            Filter::Gt(field, value) => Self::compare_value(values, field, value, Ordering::is_gt)?,
+            Filter::Lt(field, value) => Self::compare_value(values, field, value, Ordering::is_lt)?,
+            Filter::Ge(field, value) => Self::compare_value(values, field, value, Ordering::is_ge)?,
+            Filter::Le(field, value) => Self::compare_value(values, field, value, Ordering::is_le)?,
+            Filter::Between(field, low, low_kind, high, high_kind) => {
+                Self::match_between(values, field, (low, *low_kind), (high, *high_kind))?
+            }
             Filter::In(field, list) => values
                 .iter()
                 .any(|(col, val)| col.name == *field && list.iter().any(|v| v == val)),
+            Filter::DynamicIn(field, list) => {
+                let list = list.values()?;
+                values
+                    .iter()
+                    .any(|(col, val)| col.name == *field && list.contains(val))
+            }
             Filter::Like(field, pattern) => {
-                for (col, val) in values {
-                    if col.name == *field {
-                        if let Value::Text(Text(text)) = val {
-                            let res =
-                                like::Like::<true>::like(text.as_str(), pattern).map_err(|e| {
-                                    QueryError::InvalidQuery(format!(
-                                        "Invalid LIKE pattern {pattern}: {e}"
-                                    ))
-                                })?;
-
-                            return Ok(res);
-                        }
-                        return Err(QueryError::InvalidQuery(
-                            "LIKE operator can only be applied to Text values".to_string(),
-                        ));
-                    }
-                }
-                false
+                Self::match_like(values, field, pattern, like::Like::<true>::like)?
+            }
+            Filter::ILike(field, pattern) => {
+                Self::match_like(values, field, pattern, like::ILike::<true>::ilike)?
+            }
+            Filter::StartsWith(field, prefix) => {
+                Self::match_text(values, field, "STARTS WITH", |text| {
+                    text.starts_with(prefix.as_str())
+                })?
+            }
+            Filter::EndsWith(field, suffix) => {
+                Self::match_text(values, field, "ENDS WITH", |text| {
+                    text.ends_with(suffix.as_str())
+                })?
+            }
+            Filter::IStartsWith(field, prefix) => {
+                Self::match_text(values, field, "ISTARTS WITH", |text| {
+                    text.to_lowercase().starts_with(&prefix.to_lowercase())
+                })?
             }
+            Filter::IEndsWith(field, suffix) => {
+                Self::match_text(values, field, "IENDS WITH", |text| {
+                    text.to_lowercase().ends_with(&suffix.to_lowercase())
+                })?
+            }
+            Filter::Regex(field, pattern) => {
+                let regex = Self::compile_regex(pattern)?;
+                Self::match_text(values, field, "REGEX", |text| regex.is_match(text))?
+            }
+            Filter::Contains(field, element) => Self::match_contains(values, field, element)?,
             Filter::NotNull(field) => values
                 .iter()
                 .any(|(col, val)| col.name == *field && !val.is_null()),
             Filter::IsNull(field) => values
                 .iter()
                 .any(|(col, val)| col.name == *field && val.is_null()),
+            Filter::ColumnEq(left, right) => {
+                Self::compare_columns(values, left, right, Ordering::is_eq)
+            }
+            Filter::ColumnNe(left, right) => {
+                Self::compare_columns(values, left, right, Ordering::is_ne)
+            }
+            Filter::ColumnGt(left, right) => {
+                Self::compare_columns(values, left, right, Ordering::is_gt)
+            }
+            Filter::ColumnLt(left, right) => {
+                Self::compare_columns(values, left, right, Ordering::is_lt)
+            }
+            Filter::ColumnGe(left, right) => {
+                Self::compare_columns(values, left, right, Ordering::is_ge)
+            }
+            Filter::ColumnLe(left, right) => {
+                Self::compare_columns(values, left, right, Ordering::is_le)
+            }
             Filter::And(left, right) => left.matches(values)? && right.matches(values)?,
             Filter::Or(left, right) => left.matches(values)? || right.matches(values)?,
             Filter::Not(inner) => !inner.matches(values)?,
@@ -146,13 +482,268 @@ impl Filter {
 
         Ok(res)
     }
+
+    /// Estimates the fraction of the records matching the filter, in the range `0.0..=1.0`,
+    /// for a table with the given primary key and amount of records.
+    ///
+    /// The estimate follows a rough selectivity model:
+    ///
+    /// - equality on the primary key matches `1 / rows` of the records;
+    /// - any other comparison (ranges, LIKE, equality on other columns, ...) matches 10% of the records;
+    /// - negations and logical combinations are derived assuming independent filters.
+    pub fn estimated_selectivity(&self, primary_key: &str, rows: u64) -> f64 {
+        let pk_selectivity = 1.0 / rows.max(1) as f64;
+        let selectivity = match self {
+            Filter::Eq(field, _) if *field == primary_key => pk_selectivity,
+            Filter::In(field, list) if *field == primary_key => pk_selectivity * list.len() as f64,
+            Filter::Ne(field, _) if *field == primary_key => 1.0 - pk_selectivity,
+            Filter::Ne(..) | Filter::NotNull(_) => 1.0 - DEFAULT_SELECTIVITY,
+            Filter::And(left, right) => {
+                left.estimated_selectivity(primary_key, rows)
+                    * right.estimated_selectivity(primary_key, rows)
+            }
+            Filter::Or(left, right) => {
+                let left = left.estimated_selectivity(primary_key, rows);
+                let right = right.estimated_selectivity(primary_key, rows);
+                left + right - left * right
+            }
+            Filter::Not(inner) => 1.0 - inner.estimated_selectivity(primary_key, rows),
+            _ => DEFAULT_SELECTIVITY,
+        };
+
+        selectivity.clamp(0.0, 1.0)
+    }
+
+    /// Matches the value of the column `field` against a LIKE `pattern` with the given `like` function.
+    ///
+    /// The column must be a Text value.
+    fn match_like<E>(
+        values: &[(ColumnDef, Value)],
+        field: &str,
+        pattern: &str,
+        like: fn(&str, &str) -> Result<bool, E>,
+    ) -> QueryResult<bool>
+    where
+        E: std::fmt::Display,
+    {
+        for (col, val) in values {
+            if col.name == field {
+                if let Value::Text(Text(text)) = val {
+                    return like(text.as_str(), pattern).map_err(|e| {
+                        QueryError::InvalidQuery(format!("Invalid LIKE pattern {pattern}: {e}"))
+                    });
+                }
+                return Err(QueryError::InvalidQuery(format!(
+                    "LIKE operator can only be applied to Text values, found {} in column {field}",
+                    val.type_name()
+                )));
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Compiles the regular expression `pattern`, reusing the compiled pattern cached in the [`REGEX_CACHE`] if any.
+    fn compile_regex(pattern: &str) -> QueryResult<Regex> {
+        REGEX_CACHE.with_borrow_mut(|cache| {
+            if let Some(regex) = cache.get(pattern) {
+                return Ok(regex.clone());
+            }
+
+            let regex = Regex::new(pattern).map_err(|e| {
+                QueryError::InvalidQuery(format!("Invalid REGEX pattern {pattern}: {e}"))
+            })?;
+            if cache.len() >= REGEX_CACHE_CAPACITY {
+                cache.clear();
+            }
+            cache.insert(pattern.to_string(), regex.clone());
+
+            Ok(regex)
+        })
+    }
+
+    /// Returns whether the filter constrains the column `field`, anywhere in its tree.
+    pub fn references(&self, field: &str) -> bool {
+        match self {
+            Filter::Eq(column, _)
+            | Filter::Ne(column, _)
+            | Filter::Gt(column, _)
+            | Filter::Lt(column, _)
+            | Filter::Ge(column, _)
+            | Filter::Le(column, _)
+            | Filter::In(column, _)
+            | Filter::DynamicIn(column, _)
+            | Filter::Between(column, ..)
+            | Filter::Like(column, _)
+            | Filter::ILike(column, _)
+            | Filter::StartsWith(column, _)
+            | Filter::EndsWith(column, _)
+            | Filter::IStartsWith(column, _)
+            | Filter::IEndsWith(column, _)
+            | Filter::Regex(column, _)
+            | Filter::Contains(column, _)
+            | Filter::NotNull(column)
+            | Filter::IsNull(column) => *column == field,
+            Filter::ColumnEq(left, right)
+            | Filter::ColumnNe(left, right)
+            | Filter::ColumnGt(left, right)
+            | Filter::ColumnLt(left, right)
+            | Filter::ColumnGe(left, right)
+            | Filter::ColumnLe(left, right) => *left == field || *right == field,
+            Filter::And(left, right) | Filter::Or(left, right) => {
+                left.references(field) || right.references(field)
+            }
+            Filter::Not(filter) => filter.references(field),
+        }
+    }
+
+    /// Returns the column and the range of Text values matched by a [`Filter::StartsWith`],
+    /// which can be answered by a range scan on an ordered index.
+    ///
+    /// The range starts at the prefix (inclusive) and ends at the prefix followed by `'\u{10FFFF}'` (inclusive).
+    pub fn prefix_range(&self) -> Option<(&'static str, Value, Value)> {
+        match self {
+            Filter::StartsWith(field, prefix) => Some((
+                field,
+                Value::Text(prefix.clone().into()),
+                Value::Text(format!("{prefix}\u{10FFFF}").into()),
+            )),
+            _ => None,
+        }
+    }
+
+    /// Matches the value of the column `field` with the given `predicate`, for the `operator` filter.
+    ///
+    /// The column must be a Text value.
+    fn match_text(
+        values: &[(ColumnDef, Value)],
+        field: &str,
+        operator: &str,
+        predicate: impl Fn(&str) -> bool,
+    ) -> QueryResult<bool> {
+        for (col, val) in values {
+            if col.name == field {
+                if let Value::Text(Text(text)) = val {
+                    return Ok(predicate(text.as_str()));
+                }
+                return Err(QueryError::InvalidQuery(format!(
+                    "{operator} operator can only be applied to Text values, found {} in column {field}",
+                    val.type_name()
+                )));
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Checks whether the value of the column `field` contains `element`.
+    ///
+    /// The column must be an Array value; if it's NULL, the filter doesn't match.
+    fn match_contains(
+        values: &[(ColumnDef, Value)],
+        field: &str,
+        element: &Value,
+    ) -> QueryResult<bool> {
+        for (col, val) in values {
+            if col.name == field {
+                return match val {
+                    Value::Array(array) => Ok(array.contains(element)),
+                    Value::Null => Ok(false),
+                    val => Err(QueryError::InvalidQuery(format!(
+                        "CONTAINS operator can only be applied to Array values, found {} in column {field}",
+                        val.type_name()
+                    ))),
+                };
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Compares the value of the column `field` with `value` and checks the ordering with `predicate`.
+    ///
+    /// If the column is missing or any of the two values is NULL, the comparison doesn't match.
+    /// Values of different types can't be compared.
+    fn compare_value(
+        values: &[(ColumnDef, Value)],
+        field: &'static str,
+        value: &Value,
+        predicate: fn(Ordering) -> bool,
+    ) -> QueryResult<bool> {
+        let Some((_, val)) = values.iter().find(|(col, _)| col.name == field) else {
+            return Ok(false);
+        };
+        if val.is_null() || value.is_null() {
+            return Ok(false);
+        }
+
+        val.typed_cmp(value)
+            .map(predicate)
+            .map_err(|_| QueryError::TypeMismatch {
+                column: field,
+                expected: value.type_name(),
+                found: val.type_name(),
+            })
+    }
+
+    /// Checks whether the value of `field` lies within the range between the `low` and `high` boundaries.
+    ///
+    /// If the field is missing or NULL, or any boundary is NULL, the range doesn't match.
+    /// Returns [`QueryError::InvalidQuery`] if the boundaries and the value don't have the same type.
+    fn match_between(
+        values: &[(ColumnDef, Value)],
+        field: &'static str,
+        (low, low_kind): (&Value, BoundaryKind),
+        (high, high_kind): (&Value, BoundaryKind),
+    ) -> QueryResult<bool> {
+        let Some((_, val)) = values.iter().find(|(col, _)| col.name == field) else {
+            return Ok(false);
+        };
+        if val.is_null() || low.is_null() || high.is_null() {
+            return Ok(false);
+        }
+
+        for boundary in [low, high] {
+            if val.typed_cmp(boundary).is_err() {
+                return Err(QueryError::InvalidQuery(format!(
+                    "BETWEEN on column '{field}' of type {} with a {} boundary",
+                    val.type_name(),
+                    boundary.type_name()
+                )));
+            }
+        }
+
+        Ok(low_kind.check_lower(val, low) && high_kind.check_upper(val, high))
+    }
+
+    /// Compares the values of the columns `left` and `right` and checks the ordering with `predicate`.
+    ///
+    /// If any of the two columns is missing or NULL, the comparison doesn't match.
+    fn compare_columns(
+        values: &[(ColumnDef, Value)],
+        left: &str,
+        right: &str,
+        predicate: fn(Ordering) -> bool,
+    ) -> bool {
+        let find_value = |field: &str| {
+            values
+                .iter()
+                .find(|(col, val)| col.name == field && !val.is_null())
+                .map(|(_, val)| val)
+        };
+
+        match (find_value(left), find_value(right)) {
+            (Some(left), Some(right)) => predicate(left.cmp(right)),
+            _ => false,
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
 
     use super::*;
-    use crate::dbms::types::{DataTypeKind, Int32};
+    use crate::dbms::types::{DataTypeKind, Date, Decimal, Float64, Int32, Int64};
 
     #[test]
     fn test_should_build_filter() {
@@ -183,6 +774,18 @@ mod tests {
         let like = Filter::like("name", "John%");
         assert!(matches!(like, Filter::Like("name", _)));
 
+        let ilike = Filter::ilike("name", "john%");
+        assert!(matches!(ilike, Filter::ILike("name", _)));
+
+        let regex = Filter::regex("name", "^[Jj]ohn");
+        assert!(matches!(regex, Filter::Regex("name", _)));
+
+        let contains = Filter::contains("tags", Value::Int32(1.into()));
+        assert!(matches!(
+            contains,
+            Filter::Contains("tags", Value::Int32(Int32(1)))
+        ));
+
         // chained filters
         let combined = eq.and(gt).or(is_null.not());
         if let Filter::Or(left, right) = combined {
@@ -202,7 +805,10 @@ mod tests {
                 data_type: DataTypeKind::Int32,
                 nullable: false,
                 primary_key: true,
+                unique: false,
+                auto_increment: false,
                 foreign_key: None,
+                check: None,
             },
             Value::Int32(30.into()),
         )];
@@ -216,7 +822,10 @@ mod tests {
                 data_type: DataTypeKind::Int32,
                 nullable: false,
                 primary_key: true,
+                unique: false,
+                auto_increment: false,
                 foreign_key: None,
+                check: None,
             },
             Value::Int32(35.into()),
         )];
@@ -225,6 +834,47 @@ mod tests {
         assert!(!result);
     }
 
+    #[test]
+    fn test_should_check_eq_composite() {
+        let filter = Filter::eq_composite(
+            &["tenant", "id"],
+            vec![Value::Int32(1.into()), Value::Int32(30.into())],
+        );
+        assert_eq!(
+            filter,
+            Filter::eq("tenant", Value::Int32(1.into()))
+                .and(Filter::eq("id", Value::Int32(30.into())))
+        );
+
+        let column = |name| ColumnDef {
+            name,
+            data_type: DataTypeKind::Int32,
+            nullable: false,
+            primary_key: true,
+            unique: false,
+            auto_increment: false,
+            foreign_key: None,
+            check: None,
+        };
+        let values = vec![
+            (column("tenant"), Value::Int32(1.into())),
+            (column("id"), Value::Int32(30.into())),
+        ];
+        assert!(filter.matches(&values).unwrap());
+
+        let values = vec![
+            (column("tenant"), Value::Int32(2.into())),
+            (column("id"), Value::Int32(30.into())),
+        ];
+        assert!(!filter.matches(&values).unwrap());
+    }
+
+    #[test]
+    #[should_panic(expected = "composite filter must have at least one field")]
+    fn test_should_not_build_empty_eq_composite() {
+        let _ = Filter::eq_composite(&[], vec![]);
+    }
+
     #[test]
     fn test_should_check_ne() {
         let filter = Filter::ne("id", Value::Int32(30.into()));
@@ -234,7 +884,10 @@ mod tests {
                 data_type: DataTypeKind::Int32,
                 nullable: false,
                 primary_key: true,
+                unique: false,
+                auto_increment: false,
                 foreign_key: None,
+                check: None,
             },
             Value::Int32(25.into()),
         )];
@@ -248,7 +901,10 @@ mod tests {
                 data_type: DataTypeKind::Int32,
                 nullable: false,
                 primary_key: true,
+                unique: false,
+                auto_increment: false,
                 foreign_key: None,
+                check: None,
             },
             Value::Int32(30.into()),
         )];
@@ -257,6 +913,95 @@ mod tests {
         assert!(!result);
     }
 
+    #[test]
+    fn test_should_not_compare_values_of_different_types() {
+        let values = vec![(
+            ColumnDef {
+                name: "id",
+                data_type: DataTypeKind::Int32,
+                nullable: false,
+                primary_key: true,
+                unique: false,
+                auto_increment: false,
+                foreign_key: None,
+                check: None,
+            },
+            Value::Int32(5.into()),
+        )];
+
+        let filter = Filter::gt("id", Value::Text("3".to_string().into()));
+        assert!(matches!(
+            filter.matches(&values),
+            Err(QueryError::TypeMismatch {
+                column: "id",
+                expected: "Text",
+                found: "Int32",
+            })
+        ));
+    }
+
+    #[test]
+    fn test_should_not_match_null_in_comparison() {
+        let values = vec![(
+            ColumnDef {
+                name: "age",
+                data_type: DataTypeKind::Int32,
+                nullable: true,
+                primary_key: false,
+                unique: false,
+                auto_increment: false,
+                foreign_key: None,
+                check: None,
+            },
+            Value::Null,
+        )];
+
+        for filter in [
+            Filter::gt("age", Value::Int32(3.into())),
+            Filter::lt("age", Value::Int32(3.into())),
+            Filter::ge("age", Value::Int32(3.into())),
+            Filter::le("age", Value::Int32(3.into())),
+        ] {
+            assert!(!filter.matches(&values).expect("should match"));
+        }
+    }
+
+    #[test]
+    fn test_should_check_dynamic_in() {
+        let calls = Rc::new(RefCell::new(0));
+        let calls_counter = calls.clone();
+        let filter = Filter::dynamic_in("id", move || {
+            *calls_counter.borrow_mut() += 1;
+            Ok(vec![Value::Int32(1.into()), Value::Int32(2.into())])
+        });
+        let column = ColumnDef {
+            name: "id",
+            data_type: DataTypeKind::Int32,
+            nullable: false,
+            primary_key: true,
+            unique: false,
+            auto_increment: false,
+            foreign_key: None,
+            check: None,
+        };
+
+        assert!(
+            filter
+                .matches(&[(column, Value::Int32(2.into()))])
+                .expect("should match")
+        );
+        assert!(
+            !filter
+                .matches(&[(column, Value::Int32(3.into()))])
+                .expect("should match")
+        );
+        assert_eq!(*calls.borrow(), 2);
+
+        // clones share the same values
+        assert_eq!(filter.clone(), filter);
+        assert_ne!(filter, Filter::dynamic_in("id", || Ok(vec![])));
+    }
+
     #[test]
     fn test_should_check_gt() {
         let filter = Filter::gt("id", Value::Int32(20.into()));
@@ -266,7 +1011,10 @@ mod tests {
                 data_type: DataTypeKind::Int32,
                 nullable: false,
                 primary_key: true,
+                unique: false,
+                auto_increment: false,
                 foreign_key: None,
+                check: None,
             },
             Value::Int32(25.into()),
         )];
@@ -280,7 +1028,10 @@ mod tests {
                 data_type: DataTypeKind::Int32,
                 nullable: false,
                 primary_key: true,
+                unique: false,
+                auto_increment: false,
                 foreign_key: None,
+                check: None,
             },
             Value::Int32(10.into()),
         )];
@@ -298,7 +1049,10 @@ mod tests {
                 data_type: DataTypeKind::Int32,
                 nullable: false,
                 primary_key: true,
+                unique: false,
+                auto_increment: false,
                 foreign_key: None,
+                check: None,
             },
             Value::Int32(25.into()),
         )];
@@ -312,7 +1066,10 @@ mod tests {
                 data_type: DataTypeKind::Int32,
                 nullable: false,
                 primary_key: true,
+                unique: false,
+                auto_increment: false,
                 foreign_key: None,
+                check: None,
             },
             Value::Int32(40.into()),
         )];
@@ -330,7 +1087,10 @@ mod tests {
                 data_type: DataTypeKind::Int32,
                 nullable: false,
                 primary_key: true,
+                unique: false,
+                auto_increment: false,
                 foreign_key: None,
+                check: None,
             },
             Value::Int32(25.into()),
         )];
@@ -345,7 +1105,10 @@ mod tests {
                 data_type: DataTypeKind::Int32,
                 nullable: false,
                 primary_key: true,
+                unique: false,
+                auto_increment: false,
                 foreign_key: None,
+                check: None,
             },
             Value::Int32(30.into()),
         )];
@@ -360,7 +1123,10 @@ mod tests {
                 data_type: DataTypeKind::Int32,
                 nullable: false,
                 primary_key: true,
+                unique: false,
+                auto_increment: false,
                 foreign_key: None,
+                check: None,
             },
             Value::Int32(20.into()),
         )];
@@ -378,7 +1144,10 @@ mod tests {
                 data_type: DataTypeKind::Int32,
                 nullable: false,
                 primary_key: true,
+                unique: false,
+                auto_increment: false,
                 foreign_key: None,
+                check: None,
             },
             Value::Int32(25.into()),
         )];
@@ -393,7 +1162,10 @@ mod tests {
                 data_type: DataTypeKind::Int32,
                 nullable: false,
                 primary_key: true,
+                unique: false,
+                auto_increment: false,
                 foreign_key: None,
+                check: None,
             },
             Value::Int32(20.into()),
         )];
@@ -408,7 +1180,10 @@ mod tests {
                 data_type: DataTypeKind::Int32,
                 nullable: false,
                 primary_key: true,
+                unique: false,
+                auto_increment: false,
                 foreign_key: None,
+                check: None,
             },
             Value::Int32(35.into()),
         )];
@@ -426,7 +1201,10 @@ mod tests {
                 data_type: DataTypeKind::Text,
                 nullable: true,
                 primary_key: false,
+                unique: false,
+                auto_increment: false,
                 foreign_key: None,
+                check: None,
             },
             Value::Null,
         )];
@@ -440,7 +1218,10 @@ mod tests {
                 data_type: DataTypeKind::Text,
                 nullable: true,
                 primary_key: false,
+                unique: false,
+                auto_increment: false,
                 foreign_key: None,
+                check: None,
             },
             Value::Text(Text("Alice".to_string())),
         )];
@@ -458,7 +1239,10 @@ mod tests {
                 data_type: DataTypeKind::Text,
                 nullable: true,
                 primary_key: false,
+                unique: false,
+                auto_increment: false,
                 foreign_key: None,
+                check: None,
             },
             Value::Text(Text("Alice".to_string())),
         )];
@@ -471,7 +1255,10 @@ mod tests {
                 data_type: DataTypeKind::Text,
                 nullable: true,
                 primary_key: false,
+                unique: false,
+                auto_increment: false,
                 foreign_key: None,
+                check: None,
             },
             Value::Null,
         )];
@@ -488,7 +1275,10 @@ mod tests {
                 data_type: DataTypeKind::Text,
                 nullable: false,
                 primary_key: false,
+                unique: false,
+                auto_increment: false,
                 foreign_key: None,
+                check: None,
             },
             Value::Text(Text("Johnathan".to_string())),
         )];
@@ -502,7 +1292,10 @@ mod tests {
                 data_type: DataTypeKind::Text,
                 nullable: false,
                 primary_key: false,
+                unique: false,
+                auto_increment: false,
                 foreign_key: None,
+                check: None,
             },
             Value::Text(Text("Alice".to_string())),
         )];
@@ -511,6 +1304,86 @@ mod tests {
         assert!(!result);
     }
 
+    #[test]
+    fn test_should_check_ilike() {
+        let values = vec![(
+            ColumnDef {
+                name: "name",
+                data_type: DataTypeKind::Text,
+                nullable: false,
+                primary_key: false,
+                unique: false,
+                auto_increment: false,
+                foreign_key: None,
+                check: None,
+            },
+            Value::Text(Text("Johnathan".to_string())),
+        )];
+
+        assert!(
+            Filter::ilike("name", "%OHN%")
+                .matches(&values)
+                .expect("ILIKE match failed")
+        );
+        assert!(
+            !Filter::like("name", "%OHN%")
+                .matches(&values)
+                .expect("LIKE match failed")
+        );
+        assert!(
+            !Filter::ilike("name", "%alice%")
+                .matches(&values)
+                .expect("ILIKE match failed")
+        );
+    }
+
+    #[test]
+    fn test_should_check_ilike_ignoring_case() {
+        let filter = Filter::ilike("value", "%ALICE%");
+        for name in ["Alice", "ALICE", "alice", "Mary Alice Smith"] {
+            assert!(
+                matches_value(
+                    &filter,
+                    DataTypeKind::Text,
+                    Value::Text(Text(name.to_string()))
+                ),
+                "{name} should match"
+            );
+        }
+        assert!(!matches_value(
+            &filter,
+            DataTypeKind::Text,
+            Value::Text(Text("Bob".to_string()))
+        ));
+    }
+
+    #[test]
+    fn test_should_raise_same_error_on_invalid_like_and_ilike_pattern() {
+        let values = vec![(
+            ColumnDef {
+                name: "name",
+                data_type: DataTypeKind::Text,
+                nullable: false,
+                primary_key: false,
+                unique: false,
+                auto_increment: false,
+                foreign_key: None,
+                check: None,
+            },
+            Value::Text(Text("Alice".to_string())),
+        )];
+
+        // a trailing escape character doesn't escape anything
+        let like_err = Filter::like("name", "Alice\\")
+            .matches(&values)
+            .expect_err("LIKE should fail");
+        let ilike_err = Filter::ilike("name", "Alice\\")
+            .matches(&values)
+            .expect_err("ILIKE should fail");
+        assert!(matches!(like_err, QueryError::InvalidQuery(_)));
+        assert_eq!(like_err.to_string(), ilike_err.to_string());
+    }
+
     #[test]
     fn test_should_raise_error_or_like_on_non_text() {
         let filter = Filter::like("age", "%30%");
@@ -520,7 +1393,10 @@ mod tests {
                 data_type: DataTypeKind::Int32,
                 nullable: false,
                 primary_key: false,
+                unique: false,
+                auto_increment: false,
                 foreign_key: None,
+                check: None,
             },
             Value::Int32(30.into()),
         )];
@@ -529,6 +1405,170 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_should_check_starts_with_and_ends_with() {
+        let text = || Value::Text(Text("Johnathan".to_string()));
+
+        assert!(matches_value(
+            &Filter::starts_with("value", "John"),
+            DataTypeKind::Text,
+            text()
+        ));
+        assert!(!matches_value(
+            &Filter::starts_with("value", "john"),
+            DataTypeKind::Text,
+            text()
+        ));
+        assert!(matches_value(
+            &Filter::ends_with("value", "than"),
+            DataTypeKind::Text,
+            text()
+        ));
+        assert!(!matches_value(
+            &Filter::ends_with("value", "John"),
+            DataTypeKind::Text,
+            text()
+        ));
+        assert!(matches_value(
+            &Filter::istarts_with("value", "JOHN"),
+            DataTypeKind::Text,
+            text()
+        ));
+        assert!(!matches_value(
+            &Filter::istarts_with("value", "THAN"),
+            DataTypeKind::Text,
+            text()
+        ));
+        assert!(matches_value(
+            &Filter::iends_with("value", "THAN"),
+            DataTypeKind::Text,
+            text()
+        ));
+        assert!(!matches_value(
+            &Filter::iends_with("value", "JOHN"),
+            DataTypeKind::Text,
+            text()
+        ));
+        // wildcards have no special meaning
+        assert!(!matches_value(
+            &Filter::starts_with("value", "J%"),
+            DataTypeKind::Text,
+            text()
+        ));
+    }
+
+    #[test]
+    fn test_should_check_regex() {
+        let text = |text: &str| Value::Text(Text(text.to_string()));
+
+        // anchored patterns
+        assert!(matches_value(
+            &Filter::regex("value", "^foo"),
+            DataTypeKind::Text,
+            text("foobar")
+        ));
+        assert!(!matches_value(
+            &Filter::regex("value", "^foo"),
+            DataTypeKind::Text,
+            text("barfoo")
+        ));
+        assert!(matches_value(
+            &Filter::regex("value", "foo"),
+            DataTypeKind::Text,
+            text("barfoo")
+        ));
+        // character classes and quantifiers
+        assert!(matches_value(
+            &Filter::regex("value", "^order-[0-9]+$"),
+            DataTypeKind::Text,
+            text("order-42")
+        ));
+        assert!(!matches_value(
+            &Filter::regex("value", "^order-[0-9]+$"),
+            DataTypeKind::Text,
+            text("order-")
+        ));
+        // alternation
+        assert!(matches_value(
+            &Filter::regex("value", "^(cat|dog)s?$"),
+            DataTypeKind::Text,
+            text("dogs")
+        ));
+        assert!(!matches_value(
+            &Filter::regex("value", "^(cat|dog)s?$"),
+            DataTypeKind::Text,
+            text("bird")
+        ));
+    }
+
+    #[test]
+    fn test_should_raise_error_on_invalid_regex() {
+        let values = vec![(
+            ColumnDef {
+                name: "name",
+                data_type: DataTypeKind::Text,
+                nullable: false,
+                primary_key: false,
+                unique: false,
+                auto_increment: false,
+                foreign_key: None,
+                check: None,
+            },
+            Value::Text(Text("foo".to_string())),
+        )];
+
+        assert!(matches!(
+            Filter::regex("name", "[0-9").matches(&values),
+            Err(QueryError::InvalidQuery(_))
+        ));
+        assert!(matches!(
+            Filter::regex("name", "(foo").matches(&values),
+            Err(QueryError::InvalidQuery(_))
+        ));
+    }
+
+    #[test]
+    fn test_should_raise_error_on_starts_with_and_ends_with_on_non_text() {
+        let values = vec![(
+            ColumnDef {
+                name: "age",
+                data_type: DataTypeKind::Int32,
+                nullable: false,
+                primary_key: false,
+                unique: false,
+                auto_increment: false,
+                foreign_key: None,
+                check: None,
+            },
+            Value::Int32(30.into()),
+        )];
+
+        for filter in [
+            Filter::starts_with("age", "3"),
+            Filter::ends_with("age", "0"),
+            Filter::istarts_with("age", "3"),
+            Filter::iends_with("age", "0"),
+            Filter::regex("age", "^3"),
+        ] {
+            assert!(matches!(
+                filter.matches(&values),
+                Err(QueryError::InvalidQuery(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_should_get_prefix_range() {
+        let (field, low, high) = Filter::starts_with("name", "Jo")
+            .prefix_range()
+            .expect("should have range");
+        assert_eq!(field, "name");
+        assert_eq!(low, Value::Text(Text("Jo".to_string())));
+        assert!(Value::Text(Text("Johnathan".to_string())) < high);
+        assert!(Value::Text(Text("Jp".to_string())) > high);
+        assert!(Filter::ends_with("name", "Jo").prefix_range().is_none());
+    }
+
     #[test]
     fn test_should_escape_like() {
         let filter = Filter::like("name", "100%% match");
@@ -538,7 +1578,10 @@ mod tests {
                 data_type: DataTypeKind::Text,
                 nullable: false,
                 primary_key: false,
+                unique: false,
+                auto_increment: false,
                 foreign_key: None,
+                check: None,
             },
             Value::Text(Text("100% match".to_string())),
         )];
@@ -560,7 +1603,10 @@ mod tests {
                     data_type: DataTypeKind::Int32,
                     nullable: false,
                     primary_key: true,
+                    unique: false,
+                    auto_increment: false,
                     foreign_key: None,
+                    check: None,
                 },
                 Value::Int32(30.into()),
             ),
@@ -570,7 +1616,10 @@ mod tests {
                     data_type: DataTypeKind::Int32,
                     nullable: false,
                     primary_key: false,
+                    unique: false,
+                    auto_increment: false,
                     foreign_key: None,
+                    check: None,
                 },
                 Value::Int32(20.into()),
             ),
@@ -580,7 +1629,10 @@ mod tests {
                     data_type: DataTypeKind::Text,
                     nullable: true,
                     primary_key: false,
+                    unique: false,
+                    auto_increment: false,
                     foreign_key: None,
+                    check: None,
                 },
                 Value::Text(Text("Alice".to_string())),
             ),
@@ -597,7 +1649,10 @@ mod tests {
                     data_type: DataTypeKind::Int32,
                     nullable: false,
                     primary_key: true,
+                    unique: false,
+                    auto_increment: false,
                     foreign_key: None,
+                    check: None,
                 },
                 Value::Int32(25.into()),
             ),
@@ -607,7 +1662,10 @@ mod tests {
                     data_type: DataTypeKind::Int32,
                     nullable: false,
                     primary_key: false,
+                    unique: false,
+                    auto_increment: false,
                     foreign_key: None,
+                    check: None,
                 },
                 Value::Int32(16.into()),
             ),
@@ -617,7 +1675,10 @@ mod tests {
                     data_type: DataTypeKind::Text,
                     nullable: true,
                     primary_key: false,
+                    unique: false,
+                    auto_increment: false,
                     foreign_key: None,
+                    check: None,
                 },
                 Value::Null,
             ),
@@ -636,7 +1697,10 @@ mod tests {
                 data_type: DataTypeKind::Text,
                 nullable: true,
                 primary_key: false,
+                unique: false,
+                auto_increment: false,
                 foreign_key: None,
+                check: None,
             },
             Value::Null,
         )];
@@ -650,7 +1714,10 @@ mod tests {
                 data_type: DataTypeKind::Text,
                 nullable: true,
                 primary_key: false,
+                unique: false,
+                auto_increment: false,
                 foreign_key: None,
+                check: None,
             },
             Value::Text(Text("Bob".to_string())),
         )];
@@ -675,7 +1742,10 @@ mod tests {
                 data_type: DataTypeKind::Int32,
                 nullable: false,
                 primary_key: true,
+                unique: false,
+                auto_increment: false,
                 foreign_key: None,
+                check: None,
             },
             Value::Int32(20.into()),
         )];
@@ -688,11 +1758,401 @@ mod tests {
                 data_type: DataTypeKind::Int32,
                 nullable: false,
                 primary_key: true,
+                unique: false,
+                auto_increment: false,
                 foreign_key: None,
+                check: None,
             },
             Value::Int32(40.into()),
         )];
         let result = filter.matches(&values).unwrap();
         assert!(!result);
     }
+
+    #[test]
+    fn test_should_check_column_comparisons() {
+        let column = |name: &'static str| ColumnDef {
+            name,
+            data_type: DataTypeKind::Int32,
+            nullable: true,
+            primary_key: false,
+            unique: false,
+            auto_increment: false,
+            foreign_key: None,
+            check: None,
+        };
+        let values = vec![
+            (column("balance"), Value::Int32(100.into())),
+            (column("reserved"), Value::Int32(40.into())),
+            (column("limit"), Value::Int32(100.into())),
+        ];
+
+        assert!(
+            Filter::column_gt("balance", "reserved")
+                .matches(&values)
+                .unwrap()
+        );
+        assert!(
+            !Filter::column_lt("balance", "reserved")
+                .matches(&values)
+                .unwrap()
+        );
+        assert!(
+            Filter::column_ge("balance", "limit")
+                .matches(&values)
+                .unwrap()
+        );
+        assert!(
+            Filter::column_le("balance", "limit")
+                .matches(&values)
+                .unwrap()
+        );
+        assert!(
+            Filter::column_eq("balance", "limit")
+                .matches(&values)
+                .unwrap()
+        );
+        assert!(
+            Filter::column_ne("balance", "reserved")
+                .matches(&values)
+                .unwrap()
+        );
+        assert!(
+            !Filter::column_gt("balance", "reserved")
+                .and(Filter::column_eq("balance", "limit").not())
+                .matches(&values)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_should_not_match_column_comparison_with_null_or_missing_column() {
+        let column = |name: &'static str| ColumnDef {
+            name,
+            data_type: DataTypeKind::Int32,
+            nullable: true,
+            primary_key: false,
+            unique: false,
+            auto_increment: false,
+            foreign_key: None,
+            check: None,
+        };
+        let values = vec![
+            (column("balance"), Value::Int32(100.into())),
+            (column("reserved"), Value::Null),
+        ];
+
+        assert!(
+            !Filter::column_gt("balance", "reserved")
+                .matches(&values)
+                .unwrap()
+        );
+        assert!(
+            !Filter::column_ne("balance", "reserved")
+                .matches(&values)
+                .unwrap()
+        );
+        assert!(
+            !Filter::column_eq("balance", "unknown")
+                .matches(&values)
+                .unwrap()
+        );
+    }
+
+    /// Checks the given filter against a single `value` column with the given data type.
+    fn matches_value(filter: &Filter, data_type: DataTypeKind, value: Value) -> bool {
+        let values = vec![(
+            ColumnDef {
+                name: "value",
+                data_type,
+                nullable: true,
+                primary_key: false,
+                unique: false,
+                auto_increment: false,
+                foreign_key: None,
+                check: None,
+            },
+            value,
+        )];
+        filter.matches(&values).unwrap()
+    }
+
+    /// Checks all the boundary combinations on the range `low..high`,
+    /// where `low < mid < high` and `below < low` and `above > high`.
+    fn check_between_boundaries(
+        data_type: DataTypeKind,
+        [below, low, mid, high, above]: [Value; 5],
+    ) {
+        let inclusive = Filter::between_inclusive("value", low.clone(), high.clone());
+        let exclusive = Filter::between_exclusive("value", low.clone(), high.clone());
+        let left_open = Filter::between_left_open("value", low.clone(), high.clone());
+        let right_open = Filter::between_right_open("value", low.clone(), high.clone());
+
+        // (filter, below, low, mid, high, above)
+        let cases = [
+            (&inclusive, false, true, true, true, false),
+            (&exclusive, false, false, true, false, false),
+            (&left_open, false, false, true, true, false),
+            (&right_open, false, true, true, false, false),
+        ];
+        for (filter, exp_below, exp_low, exp_mid, exp_high, exp_above) in cases {
+            assert_eq!(
+                matches_value(filter, data_type, below.clone()),
+                exp_below,
+                "{filter:?} with {below:?}"
+            );
+            assert_eq!(
+                matches_value(filter, data_type, low.clone()),
+                exp_low,
+                "{filter:?} with {low:?}"
+            );
+            assert_eq!(
+                matches_value(filter, data_type, mid.clone()),
+                exp_mid,
+                "{filter:?} with {mid:?}"
+            );
+            assert_eq!(
+                matches_value(filter, data_type, high.clone()),
+                exp_high,
+                "{filter:?} with {high:?}"
+            );
+            assert_eq!(
+                matches_value(filter, data_type, above.clone()),
+                exp_above,
+                "{filter:?} with {above:?}"
+            );
+            assert!(!matches_value(filter, data_type, Value::Null));
+        }
+    }
+
+    #[test]
+    fn test_should_build_between_filter() {
+        let filter =
+            Filter::between_left_open("age", Value::Int32(Int32(18)), Value::Int32(Int32(65)));
+        assert_eq!(
+            filter,
+            Filter::Between(
+                "age",
+                Value::Int32(Int32(18)),
+                BoundaryKind::Exclusive,
+                Value::Int32(Int32(65)),
+                BoundaryKind::Inclusive
+            )
+        );
+    }
+
+    #[test]
+    fn test_should_build_sql_between_filter() {
+        assert_eq!(
+            Filter::between("age", Value::Int32(Int32(18)), Value::Int32(Int32(65))),
+            Filter::between_inclusive("age", Value::Int32(Int32(18)), Value::Int32(Int32(65)))
+        );
+    }
+
+    #[test]
+    fn test_should_not_match_between_with_swapped_boundaries() {
+        let filter = Filter::between("value", Value::Int32(Int32(65)), Value::Int32(Int32(18)));
+        for value in [18, 30, 65] {
+            assert!(!matches_value(
+                &filter,
+                DataTypeKind::Int32,
+                Value::Int32(Int32(value))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_should_reject_between_with_mismatched_types() {
+        let values = vec![(
+            ColumnDef {
+                name: "value",
+                data_type: DataTypeKind::Int32,
+                nullable: true,
+                primary_key: false,
+                unique: false,
+                auto_increment: false,
+                foreign_key: None,
+                check: None,
+            },
+            Value::Int32(Int32(30)),
+        )];
+
+        let filter = Filter::between(
+            "value",
+            Value::Int32(Int32(18)),
+            Value::Text("65".to_string().into()),
+        );
+        assert!(matches!(
+            filter.matches(&values),
+            Err(QueryError::InvalidQuery(_))
+        ));
+        let filter = Filter::between("value", Value::Int64(Int64(18)), Value::Int64(Int64(65)));
+        assert!(matches!(
+            filter.matches(&values),
+            Err(QueryError::InvalidQuery(_))
+        ));
+    }
+
+    #[test]
+    fn test_should_check_between_with_integers() {
+        check_between_boundaries(
+            DataTypeKind::Int32,
+            [-5, 0, 5, 10, 15].map(|value| Value::Int32(Int32(value))),
+        );
+    }
+
+    #[test]
+    fn test_should_check_between_with_decimals() {
+        check_between_boundaries(
+            DataTypeKind::Decimal,
+            [99, 100, 150, 200, 201]
+                .map(|value| Value::Decimal(Decimal(rust_decimal::Decimal::new(value, 2)))),
+        );
+    }
+
+    #[test]
+    fn test_should_check_between_with_floats() {
+        check_between_boundaries(
+            DataTypeKind::Float64,
+            [f64::NEG_INFINITY, -1.5, 0.0, 1.5, f64::NAN]
+                .map(|value| Value::Float64(Float64(value))),
+        );
+    }
+
+    #[test]
+    fn test_should_check_array_contains_element() {
+        let data_type = DataTypeKind::Array(&DataTypeKind::Int32);
+        let array = Value::Array(vec![Value::Int32(Int32(1)), Value::Int32(Int32(3))]);
+        let contains = |value| Filter::contains("value", Value::Int32(Int32(value)));
+
+        assert!(matches_value(&contains(1), data_type, array.clone()));
+        assert!(matches_value(&contains(3), data_type, array.clone()));
+        assert!(!matches_value(&contains(2), data_type, array.clone()));
+        assert!(!matches_value(
+            &contains(1),
+            data_type,
+            Value::Array(vec![])
+        ));
+        assert!(!matches_value(&contains(1), data_type, Value::Null));
+        assert!(matches_value(&contains(2).not(), data_type, array));
+    }
+
+    #[test]
+    fn test_should_not_check_contains_on_non_array_values() {
+        let values = vec![(
+            ColumnDef {
+                name: "value",
+                data_type: DataTypeKind::Int32,
+                nullable: false,
+                primary_key: false,
+                unique: false,
+                auto_increment: false,
+                foreign_key: None,
+                check: None,
+            },
+            Value::Int32(Int32(1)),
+        )];
+
+        assert!(matches!(
+            Filter::contains("value", Value::Int32(Int32(1))).matches(&values),
+            Err(QueryError::InvalidQuery(_))
+        ));
+    }
+
+    #[test]
+    fn test_should_compare_floats() {
+        let float = |value: f64| Value::Float64(Float64(value));
+
+        assert!(matches_value(
+            &Filter::gt("value", float(1.0)),
+            DataTypeKind::Float64,
+            float(f64::INFINITY)
+        ));
+        assert!(matches_value(
+            &Filter::gt("value", float(f64::INFINITY)),
+            DataTypeKind::Float64,
+            float(f64::NAN)
+        ));
+        assert!(matches_value(
+            &Filter::eq("value", float(f64::NAN)),
+            DataTypeKind::Float64,
+            float(f64::NAN)
+        ));
+        assert!(matches_value(
+            &Filter::eq("value", float(0.0)),
+            DataTypeKind::Float64,
+            float(-0.0)
+        ));
+        assert!(matches_value(
+            &Filter::lt("value", float(-1.0)),
+            DataTypeKind::Float64,
+            float(f64::NEG_INFINITY)
+        ));
+        assert!(!matches_value(
+            &Filter::le("value", float(2.5)),
+            DataTypeKind::Float64,
+            float(2.5000001)
+        ));
+        assert!(matches_value(
+            &Filter::in_list("value", vec![float(0.1), float(f64::NAN)]),
+            DataTypeKind::Float64,
+            float(f64::NAN)
+        ));
+    }
+
+    #[test]
+    fn test_should_check_between_with_dates() {
+        check_between_boundaries(
+            DataTypeKind::Date,
+            [
+                (2023, 12, 31),
+                (2024, 1, 1),
+                (2024, 6, 15),
+                (2024, 12, 31),
+                (2025, 1, 1),
+            ]
+            .map(|(year, month, day)| Value::Date(Date { year, month, day })),
+        );
+    }
+
+    #[test]
+    fn test_should_estimate_filter_selectivity() {
+        let pk_eq = Filter::eq("id", Value::Int32(Int32(1)));
+        assert_eq!(pk_eq.estimated_selectivity("id", 200), 1.0 / 200.0);
+        // an empty table doesn't divide by zero
+        assert_eq!(pk_eq.estimated_selectivity("id", 0), 1.0);
+
+        let pk_in = Filter::in_list("id", vec![Value::Int32(Int32(1)), Value::Int32(Int32(2))]);
+        assert_eq!(pk_in.estimated_selectivity("id", 200), 2.0 / 200.0);
+
+        let range =
+            Filter::between_inclusive("age", Value::Int32(Int32(18)), Value::Int32(Int32(65)));
+        assert_eq!(range.estimated_selectivity("id", 200), 0.1);
+        assert_eq!(
+            Filter::gt("id", Value::Int32(Int32(1))).estimated_selectivity("id", 200),
+            0.1
+        );
+        assert_eq!(
+            Filter::eq("age", Value::Int32(Int32(30))).estimated_selectivity("id", 200),
+            0.1
+        );
+
+        let not_range = range.clone().not();
+        assert!((not_range.estimated_selectivity("id", 200) - 0.9).abs() < f64::EPSILON);
+        let and = range.clone().and(Filter::like("name", "A%"));
+        assert!((and.estimated_selectivity("id", 200) - 0.01).abs() < f64::EPSILON);
+        let or = range.or(Filter::like("name", "A%"));
+        assert!((or.estimated_selectivity("id", 200) - 0.19).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_should_check_referenced_columns() {
+        let filter = Filter::eq("id", Value::Int32(Int32(1)))
+            .and(Filter::not_null("deleted_at").not())
+            .or(Filter::column_eq("name", "email"));
+        assert!(filter.references("id"));
+        assert!(filter.references("deleted_at"));
+        assert!(filter.references("email"));
+        assert!(!filter.references("age"));
+    }
 }
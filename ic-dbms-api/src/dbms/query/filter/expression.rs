@@ -0,0 +1,1058 @@
+//! Human-readable expression language of [`Filter`]s.
+
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr as _;
+
+use thiserror::Error;
+
+use super::{BoundaryKind, Filter};
+use crate::dbms::types::{
+    Blob, Boolean, Date, DateTime, Decimal, Float64, Int32, Int64, Principal, Text, Uint32, Uint64,
+    Uuid,
+};
+use crate::dbms::value::Value;
+
+/// Keywords of the expression language, which can't be used as column names.
+const KEYWORDS: [&str; 17] = [
+    "AND", "OR", "NOT", "IN", "LIKE", "ILIKE", "STARTS", "ENDS", "ISTARTS", "IENDS", "REGEX",
+    "CONTAINS", "IS", "NULL", "BETWEEN", "TRUE", "FALSE",
+];
+
+/// Symbols of the expression language; longer symbols come first, so they are matched before their prefixes.
+const SYMBOLS: [&str; 12] = [
+    "!=", "<>", ">=", "<=", "=", ">", "<", "(", ")", "[", "]", ",",
+];
+
+thread_local! {
+    /// Column names parsed from expressions.
+    ///
+    /// Filters reference columns by static strings, so each distinct parsed name is leaked once and reused afterwards.
+    static COLUMN_NAMES: RefCell<HashSet<&'static str>> = RefCell::new(HashSet::new());
+}
+
+/// An error raised parsing a [`Filter`] from an expression.
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum FilterParseError {
+    /// The expression ends before the filter is complete.
+    #[error("unexpected end of expression")]
+    UnexpectedEnd,
+    /// The expression contains a token which is not allowed at the given position.
+    #[error("unexpected '{token}' at position {position}")]
+    UnexpectedToken { token: String, position: usize },
+    /// A quoted string is not terminated.
+    #[error("unterminated string at position {0}")]
+    UnterminatedString(usize),
+    /// A literal doesn't represent a valid value.
+    #[error("invalid literal '{literal}' at position {position}")]
+    InvalidLiteral { literal: String, position: usize },
+}
+
+impl Filter {
+    /// Renders the filter as an expression, which can be parsed back with [`Filter::from_expression`].
+    ///
+    /// [`Filter::DynamicIn`] filters are rendered as `column IN <dynamic>`, since their values are not known
+    /// until the filter is matched, and can't be parsed back.
+    pub fn to_expression(&self) -> String {
+        match self {
+            Filter::Eq(field, value) => format!("{field} = {}", render_value(value)),
+            Filter::Ne(field, value) => format!("{field} != {}", render_value(value)),
+            Filter::Gt(field, value) => format!("{field} > {}", render_value(value)),
+            Filter::Lt(field, value) => format!("{field} < {}", render_value(value)),
+            Filter::Ge(field, value) => format!("{field} >= {}", render_value(value)),
+            Filter::Le(field, value) => format!("{field} <= {}", render_value(value)),
+            Filter::In(field, values) => format!(
+                "{field} IN ({})",
+                values
+                    .iter()
+                    .map(render_value)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Filter::DynamicIn(field, _) => format!("{field} IN <dynamic>"),
+            Filter::Between(field, low, low_kind, high, high_kind) => format!(
+                "{field} BETWEEN {}{}, {}{}",
+                match low_kind {
+                    BoundaryKind::Inclusive => '[',
+                    BoundaryKind::Exclusive => '(',
+                },
+                render_value(low),
+                render_value(high),
+                match high_kind {
+                    BoundaryKind::Inclusive => ']',
+                    BoundaryKind::Exclusive => ')',
+                },
+            ),
+            Filter::Like(field, pattern) => format!("{field} LIKE {}", quote(pattern, '\'')),
+            Filter::ILike(field, pattern) => format!("{field} ILIKE {}", quote(pattern, '\'')),
+            Filter::StartsWith(field, prefix) => {
+                format!("{field} STARTS WITH {}", quote(prefix, '\''))
+            }
+            Filter::EndsWith(field, suffix) => {
+                format!("{field} ENDS WITH {}", quote(suffix, '\''))
+            }
+            Filter::IStartsWith(field, prefix) => {
+                format!("{field} ISTARTS WITH {}", quote(prefix, '\''))
+            }
+            Filter::IEndsWith(field, suffix) => {
+                format!("{field} IENDS WITH {}", quote(suffix, '\''))
+            }
+            Filter::Regex(field, pattern) => format!("{field} REGEX {}", quote(pattern, '\'')),
+            Filter::Contains(field, element) => {
+                format!("{field} CONTAINS {}", render_value(element))
+            }
+            Filter::NotNull(field) => format!("{field} IS NOT NULL"),
+            Filter::IsNull(field) => format!("{field} IS NULL"),
+            Filter::ColumnEq(left, right) => format!("{left} = {right}"),
+            Filter::ColumnNe(left, right) => format!("{left} != {right}"),
+            Filter::ColumnGt(left, right) => format!("{left} > {right}"),
+            Filter::ColumnLt(left, right) => format!("{left} < {right}"),
+            Filter::ColumnGe(left, right) => format!("{left} >= {right}"),
+            Filter::ColumnLe(left, right) => format!("{left} <= {right}"),
+            Filter::And(left, right) => {
+                format!("({}) AND ({})", left.to_expression(), right.to_expression())
+            }
+            Filter::Or(left, right) => {
+                format!("({}) OR ({})", left.to_expression(), right.to_expression())
+            }
+            Filter::Not(filter) => format!("NOT ({})", filter.to_expression()),
+        }
+    }
+
+    /// Parses a filter from an expression, such as `(id = 42u32) AND (name LIKE '%john%')`.
+    ///
+    /// Expressions are made of predicates on columns, combined with `AND`, `OR` and `NOT`
+    /// and grouped with parentheses; `NOT` binds tighter than `AND`, which binds tighter than `OR`.
+    ///
+    /// | Filter                     | Expression                   |
+    /// |----------------------------|------------------------------|
+    /// | [`Filter::Eq`]             | `id = 42u32`                 |
+    /// | [`Filter::Ne`]             | `id != 42u32`                |
+    /// | [`Filter::Gt`], ...        | `age > 18i32`                |
+    /// | [`Filter::In`]             | `id IN (1u32, 2u32)`         |
+    /// | [`Filter::Between`]        | `age BETWEEN [18i32, 65i32)` |
+    /// | [`Filter::Like`]           | `name LIKE '%john%'`         |
+    /// | [`Filter::ILike`]          | `name ILIKE '%john%'`        |
+    /// | [`Filter::StartsWith`]     | `name STARTS WITH 'jo'`      |
+    /// | [`Filter::EndsWith`]       | `name ENDS WITH 'hn'`        |
+    /// | [`Filter::IStartsWith`]    | `name ISTARTS WITH 'JO'`     |
+    /// | [`Filter::IEndsWith`]      | `name IENDS WITH 'HN'`       |
+    /// | [`Filter::Regex`]          | `name REGEX '^jo(hn)?$'`     |
+    /// | [`Filter::Contains`]       | `tags CONTAINS 'rust'`       |
+    /// | [`Filter::NotNull`]        | `email IS NOT NULL`          |
+    /// | [`Filter::IsNull`]         | `email IS NULL`              |
+    /// | [`Filter::ColumnEq`], ...  | `created_at < updated_at`    |
+    ///
+    /// Values are written as tagged literals, so that their type is preserved:
+    ///
+    /// - integers are suffixed with their type: `-42i32`, `42i64`, `42u32`, `42u64`;
+    /// - decimals are suffixed with `dec`: `12.50dec`;
+    /// - floats are suffixed with `f64`: `-1.5f64`, while `NaN` and infinities are tagged strings:
+    ///   `f64"NaN"`, `f64"inf"`, `f64"-inf"`;
+    /// - texts are quoted: `"text"` or `'text'`, escaping quotes and backslashes with a backslash;
+    /// - booleans and null are keywords: `true`, `false`, `NULL`;
+    /// - arrays are lists of values in square brackets: `[1u32, 2u32]`;
+    /// - the other types are quoted strings prefixed by a tag: `x"0a0b"` (hex blob), `date"2024-01-31"`,
+    ///   `datetime"2024-01-31T10:30:00.000000+01:00"`, `principal"aaaaa-aa"`,
+    ///   `uuid"67e55044-10b1-426f-9247-bb680e5fe0c8"`.
+    ///
+    /// Keywords are case-insensitive and can't be used as column names.
+    pub fn from_expression(expr: &str) -> Result<Filter, FilterParseError> {
+        let mut parser = Parser {
+            tokens: tokenize(expr)?,
+            index: 0,
+        };
+        let filter = parser.parse_or()?;
+        match parser.tokens.get(parser.index) {
+            Some((position, token)) => Err(FilterParseError::UnexpectedToken {
+                token: token.to_string(),
+                position: *position,
+            }),
+            None => Ok(filter),
+        }
+    }
+}
+
+/// Filters are displayed as their expression, see [`Filter::to_expression`].
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_expression())
+    }
+}
+
+/// Renders a value as a tagged literal.
+fn render_value(value: &Value) -> String {
+    match value {
+        Value::Array(values) => format!(
+            "[{}]",
+            values
+                .iter()
+                .map(render_value)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        Value::AutoIncrement => "AUTO_INCREMENT".to_string(),
+        Value::Blob(Blob(bytes)) => format!(
+            "x\"{}\"",
+            bytes
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect::<String>()
+        ),
+        Value::Boolean(Boolean(value)) => value.to_string(),
+        Value::Date(date) => format!("date\"{date}\""),
+        Value::DateTime(date_time) => format!(
+            "datetime\"{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:06}{}{:02}:{:02}\"",
+            date_time.year,
+            date_time.month,
+            date_time.day,
+            date_time.hour,
+            date_time.minute,
+            date_time.second,
+            date_time.microsecond,
+            if date_time.timezone_offset_minutes < 0 {
+                '-'
+            } else {
+                '+'
+            },
+            date_time.timezone_offset_minutes.unsigned_abs() / 60,
+            date_time.timezone_offset_minutes.unsigned_abs() % 60
+        ),
+        Value::Decimal(decimal) => format!("{decimal}dec"),
+        Value::Float64(Float64(value)) if value.is_finite() => format!("{value}f64"),
+        Value::Float64(Float64(value)) => format!("f64\"{value}\""),
+        Value::Int32(Int32(value)) => format!("{value}i32"),
+        Value::Int64(Int64(value)) => format!("{value}i64"),
+        Value::Null => "NULL".to_string(),
+        Value::Principal(principal) => format!("principal\"{principal}\""),
+        Value::Text(Text(text)) => quote(text, '"'),
+        Value::Uint32(Uint32(value)) => format!("{value}u32"),
+        Value::Uint64(Uint64(value)) => format!("{value}u64"),
+        Value::Uuid(uuid) => format!("uuid\"{uuid}\""),
+    }
+}
+
+/// Quotes a string with the given quote character, escaping quotes and backslashes.
+fn quote(text: &str, quote: char) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push(quote);
+    for c in text.chars() {
+        if c == quote || c == '\\' {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push(quote);
+    quoted
+}
+
+/// Returns the static string of the given column name.
+fn intern(name: &str) -> &'static str {
+    COLUMN_NAMES.with_borrow_mut(|names| {
+        if let Some(interned) = names.get(name) {
+            return *interned;
+        }
+        let interned: &'static str = Box::leak(name.to_string().into_boxed_str());
+        names.insert(interned);
+        interned
+    })
+}
+
+/// A token of an expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    /// A column name or a keyword.
+    Word(String),
+    /// A number with its type suffix, such as `42u32`.
+    Number(String),
+    /// A quoted string, unescaped.
+    Str(String),
+    /// A quoted string prefixed by a tag, such as `date"2024-01-31"`.
+    Tagged(String, String),
+    /// One of [`SYMBOLS`].
+    Symbol(&'static str),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Word(word) | Token::Number(word) => write!(f, "{word}"),
+            Token::Str(text) => write!(f, "{}", quote(text, '"')),
+            Token::Tagged(tag, text) => write!(f, "{tag}{}", quote(text, '"')),
+            Token::Symbol(symbol) => write!(f, "{symbol}"),
+        }
+    }
+}
+
+/// Splits an expression into tokens, each with its byte position in the expression.
+fn tokenize(expr: &str) -> Result<Vec<(usize, Token)>, FilterParseError> {
+    let chars = expr.char_indices().collect::<Vec<_>>();
+    let mut tokens = vec![];
+    let mut index = 0;
+
+    while let Some(&(position, c)) = chars.get(index) {
+        let is_digit_at = |index: usize| chars.get(index).is_some_and(|(_, c)| c.is_ascii_digit());
+        if c.is_whitespace() {
+            index += 1;
+        } else if c == '"' || c == '\'' {
+            let (text, next) = read_string(&chars, index)?;
+            tokens.push((position, Token::Str(text)));
+            index = next;
+        } else if c.is_ascii_digit() || (c == '-' && is_digit_at(index + 1)) {
+            let start = index;
+            index += 1;
+            while chars
+                .get(index)
+                .is_some_and(|(_, c)| c.is_ascii_alphanumeric() || *c == '.')
+            {
+                index += 1;
+            }
+            tokens.push((
+                position,
+                Token::Number(chars[start..index].iter().map(|(_, c)| c).collect()),
+            ));
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let start = index;
+            while chars
+                .get(index)
+                .is_some_and(|(_, c)| c.is_ascii_alphanumeric() || *c == '_')
+            {
+                index += 1;
+            }
+            let word = chars[start..index].iter().map(|(_, c)| c).collect();
+            if chars.get(index).is_some_and(|(_, c)| *c == '"') {
+                let (text, next) = read_string(&chars, index)?;
+                tokens.push((position, Token::Tagged(word, text)));
+                index = next;
+            } else {
+                tokens.push((position, Token::Word(word)));
+            }
+        } else {
+            let symbol = SYMBOLS
+                .into_iter()
+                .find(|symbol| expr[position..].starts_with(symbol))
+                .ok_or_else(|| FilterParseError::UnexpectedToken {
+                    token: c.to_string(),
+                    position,
+                })?;
+            tokens.push((position, Token::Symbol(symbol)));
+            // symbols are ASCII, so their length in bytes is their length in chars
+            index += symbol.len();
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Reads the quoted string starting at `start`, returning the unescaped string and the index after the closing quote.
+fn read_string(chars: &[(usize, char)], start: usize) -> Result<(String, usize), FilterParseError> {
+    let (position, quote) = chars[start];
+    let mut text = String::new();
+    let mut index = start + 1;
+    loop {
+        match chars.get(index) {
+            Some((_, '\\')) => {
+                let (_, escaped) = chars
+                    .get(index + 1)
+                    .ok_or(FilterParseError::UnterminatedString(position))?;
+                text.push(*escaped);
+                index += 2;
+            }
+            Some((_, c)) if *c == quote => return Ok((text, index + 1)),
+            Some((_, c)) => {
+                text.push(*c);
+                index += 1;
+            }
+            None => return Err(FilterParseError::UnterminatedString(position)),
+        }
+    }
+}
+
+/// Recursive-descent parser of the tokens of an expression.
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    index: usize,
+}
+
+impl Parser {
+    /// Parses a disjunction: `and (OR and)*`.
+    fn parse_or(&mut self) -> Result<Filter, FilterParseError> {
+        let mut filter = self.parse_and()?;
+        while self.eat_keyword("OR") {
+            filter = filter.or(self.parse_and()?);
+        }
+        Ok(filter)
+    }
+
+    /// Parses a conjunction: `unary (AND unary)*`.
+    fn parse_and(&mut self) -> Result<Filter, FilterParseError> {
+        let mut filter = self.parse_unary()?;
+        while self.eat_keyword("AND") {
+            filter = filter.and(self.parse_unary()?);
+        }
+        Ok(filter)
+    }
+
+    /// Parses a negation, a parenthesized expression or a predicate.
+    fn parse_unary(&mut self) -> Result<Filter, FilterParseError> {
+        if self.eat_keyword("NOT") {
+            Ok(self.parse_unary()?.not())
+        } else if self.eat_symbol("(") {
+            let filter = self.parse_or()?;
+            self.expect_symbol(")")?;
+            Ok(filter)
+        } else {
+            self.parse_predicate()
+        }
+    }
+
+    /// Parses a predicate on a column.
+    fn parse_predicate(&mut self) -> Result<Filter, FilterParseError> {
+        let field = self.parse_column()?;
+        let (position, token) = self.next()?;
+        match token {
+            Token::Symbol("=") => self.parse_comparison(field, Filter::Eq, Filter::ColumnEq),
+            Token::Symbol("!=" | "<>") => {
+                self.parse_comparison(field, Filter::Ne, Filter::ColumnNe)
+            }
+            Token::Symbol(">") => self.parse_comparison(field, Filter::Gt, Filter::ColumnGt),
+            Token::Symbol("<") => self.parse_comparison(field, Filter::Lt, Filter::ColumnLt),
+            Token::Symbol(">=") => self.parse_comparison(field, Filter::Ge, Filter::ColumnGe),
+            Token::Symbol("<=") => self.parse_comparison(field, Filter::Le, Filter::ColumnLe),
+            Token::Word(word) if word.eq_ignore_ascii_case("IN") => {
+                self.expect_symbol("(")?;
+                let mut values = vec![];
+                if !self.eat_symbol(")") {
+                    loop {
+                        values.push(self.parse_value()?);
+                        if self.eat_symbol(")") {
+                            break;
+                        }
+                        self.expect_symbol(",")?;
+                    }
+                }
+                Ok(Filter::In(field, values))
+            }
+            Token::Word(word) if word.eq_ignore_ascii_case("LIKE") => {
+                Ok(Filter::Like(field, self.parse_pattern()?))
+            }
+            Token::Word(word) if word.eq_ignore_ascii_case("ILIKE") => {
+                Ok(Filter::ILike(field, self.parse_pattern()?))
+            }
+            Token::Word(word) if word.eq_ignore_ascii_case("STARTS") => {
+                Ok(Filter::StartsWith(field, self.parse_affix()?))
+            }
+            Token::Word(word) if word.eq_ignore_ascii_case("ENDS") => {
+                Ok(Filter::EndsWith(field, self.parse_affix()?))
+            }
+            Token::Word(word) if word.eq_ignore_ascii_case("ISTARTS") => {
+                Ok(Filter::IStartsWith(field, self.parse_affix()?))
+            }
+            Token::Word(word) if word.eq_ignore_ascii_case("IENDS") => {
+                Ok(Filter::IEndsWith(field, self.parse_affix()?))
+            }
+            Token::Word(word) if word.eq_ignore_ascii_case("REGEX") => {
+                Ok(Filter::Regex(field, self.parse_pattern()?))
+            }
+            Token::Word(word) if word.eq_ignore_ascii_case("CONTAINS") => {
+                Ok(Filter::Contains(field, self.parse_value()?))
+            }
+            Token::Word(word) if word.eq_ignore_ascii_case("IS") => {
+                let not_null = self.eat_keyword("NOT");
+                if !self.eat_keyword("NULL") {
+                    return Err(self.unexpected()?);
+                }
+                Ok(if not_null {
+                    Filter::NotNull(field)
+                } else {
+                    Filter::IsNull(field)
+                })
+            }
+            Token::Word(word) if word.eq_ignore_ascii_case("BETWEEN") => {
+                let low_kind = if self.eat_symbol("[") {
+                    BoundaryKind::Inclusive
+                } else {
+                    self.expect_symbol("(")?;
+                    BoundaryKind::Exclusive
+                };
+                let low = self.parse_value()?;
+                self.expect_symbol(",")?;
+                let high = self.parse_value()?;
+                let high_kind = if self.eat_symbol("]") {
+                    BoundaryKind::Inclusive
+                } else {
+                    self.expect_symbol(")")?;
+                    BoundaryKind::Exclusive
+                };
+                Ok(Filter::Between(field, low, low_kind, high, high_kind))
+            }
+            token => Err(FilterParseError::UnexpectedToken {
+                token: token.to_string(),
+                position,
+            }),
+        }
+    }
+
+    /// Parses the right side of a comparison, which is either a column or a value.
+    fn parse_comparison(
+        &mut self,
+        field: &'static str,
+        with_value: fn(&'static str, Value) -> Filter,
+        with_column: fn(&'static str, &'static str) -> Filter,
+    ) -> Result<Filter, FilterParseError> {
+        match self.peek() {
+            Some(Token::Word(word)) if !is_keyword(word) => {
+                Ok(with_column(field, self.parse_column()?))
+            }
+            _ => Ok(with_value(field, self.parse_value()?)),
+        }
+    }
+
+    /// Parses a column name.
+    fn parse_column(&mut self) -> Result<&'static str, FilterParseError> {
+        match self.next()? {
+            (_, Token::Word(word)) if !is_keyword(&word) => Ok(intern(&word)),
+            (position, token) => Err(FilterParseError::UnexpectedToken {
+                token: token.to_string(),
+                position,
+            }),
+        }
+    }
+
+    /// Parses the quoted pattern of a LIKE filter.
+    fn parse_pattern(&mut self) -> Result<String, FilterParseError> {
+        match self.next()? {
+            (_, Token::Str(pattern)) => Ok(pattern),
+            (position, token) => Err(FilterParseError::UnexpectedToken {
+                token: token.to_string(),
+                position,
+            }),
+        }
+    }
+
+    /// Parses the `WITH` keyword and the quoted prefix or suffix of a STARTS WITH or ENDS WITH filter.
+    fn parse_affix(&mut self) -> Result<String, FilterParseError> {
+        if !self.eat_keyword("WITH") {
+            return Err(self.unexpected()?);
+        }
+        self.parse_pattern()
+    }
+
+    /// Parses a tagged literal.
+    fn parse_value(&mut self) -> Result<Value, FilterParseError> {
+        let (position, token) = self.next()?;
+        let invalid = |token: &Token| FilterParseError::InvalidLiteral {
+            literal: token.to_string(),
+            position,
+        };
+
+        match &token {
+            Token::Word(word) if word.eq_ignore_ascii_case("NULL") => Ok(Value::Null),
+            Token::Word(word) if word.eq_ignore_ascii_case("TRUE") => {
+                Ok(Value::Boolean(Boolean(true)))
+            }
+            Token::Word(word) if word.eq_ignore_ascii_case("FALSE") => {
+                Ok(Value::Boolean(Boolean(false)))
+            }
+            Token::Number(number) => parse_number(number).ok_or_else(|| invalid(&token)),
+            Token::Str(text) => Ok(Value::Text(Text(text.clone()))),
+            Token::Tagged(tag, text) => parse_tagged(tag, text).ok_or_else(|| invalid(&token)),
+            Token::Symbol("[") => {
+                let mut values = vec![];
+                if !self.eat_symbol("]") {
+                    loop {
+                        values.push(self.parse_value()?);
+                        if self.eat_symbol("]") {
+                            break;
+                        }
+                        self.expect_symbol(",")?;
+                    }
+                }
+                Ok(Value::Array(values))
+            }
+            _ => Err(FilterParseError::UnexpectedToken {
+                token: token.to_string(),
+                position,
+            }),
+        }
+    }
+
+    /// Returns the next token without consuming it.
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.index).map(|(_, token)| token)
+    }
+
+    /// Consumes the next token.
+    fn next(&mut self) -> Result<(usize, Token), FilterParseError> {
+        let token = self
+            .tokens
+            .get(self.index)
+            .cloned()
+            .ok_or(FilterParseError::UnexpectedEnd)?;
+        self.index += 1;
+        Ok(token)
+    }
+
+    /// Consumes the next token if it's the given keyword.
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let matches =
+            matches!(self.peek(), Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword));
+        if matches {
+            self.index += 1;
+        }
+        matches
+    }
+
+    /// Consumes the next token if it's the given symbol.
+    fn eat_symbol(&mut self, symbol: &str) -> bool {
+        let matches = matches!(self.peek(), Some(Token::Symbol(s)) if *s == symbol);
+        if matches {
+            self.index += 1;
+        }
+        matches
+    }
+
+    /// Consumes the next token, which must be the given symbol.
+    fn expect_symbol(&mut self, symbol: &str) -> Result<(), FilterParseError> {
+        if self.eat_symbol(symbol) {
+            Ok(())
+        } else {
+            Err(self.unexpected()?)
+        }
+    }
+
+    /// Returns the error for the next token, which is not allowed.
+    fn unexpected(&self) -> Result<FilterParseError, FilterParseError> {
+        let (position, token) = self
+            .tokens
+            .get(self.index)
+            .ok_or(FilterParseError::UnexpectedEnd)?;
+        Ok(FilterParseError::UnexpectedToken {
+            token: token.to_string(),
+            position: *position,
+        })
+    }
+}
+
+/// Returns whether the given word is a keyword.
+fn is_keyword(word: &str) -> bool {
+    KEYWORDS
+        .iter()
+        .any(|keyword| word.eq_ignore_ascii_case(keyword))
+}
+
+/// Parses a number with its type suffix.
+fn parse_number(number: &str) -> Option<Value> {
+    let suffix_start = number.find(|c: char| c.is_ascii_alphabetic())?;
+    let (digits, suffix) = number.split_at(suffix_start);
+    match suffix {
+        "i32" => digits.parse().ok().map(|value| Value::Int32(Int32(value))),
+        "i64" => digits.parse().ok().map(|value| Value::Int64(Int64(value))),
+        "u32" => digits
+            .parse()
+            .ok()
+            .map(|value| Value::Uint32(Uint32(value))),
+        "u64" => digits
+            .parse()
+            .ok()
+            .map(|value| Value::Uint64(Uint64(value))),
+        "dec" => Decimal::from_str(digits).ok().map(Value::Decimal),
+        "f64" => digits
+            .parse()
+            .ok()
+            .map(|value| Value::Float64(Float64(value))),
+        _ => None,
+    }
+}
+
+/// Parses the string of a tagged literal.
+fn parse_tagged(tag: &str, text: &str) -> Option<Value> {
+    match tag {
+        "x" => {
+            if text.len() % 2 != 0 || !text.is_ascii() {
+                return None;
+            }
+            (0..text.len())
+                .step_by(2)
+                .map(|index| u8::from_str_radix(&text[index..index + 2], 16).ok())
+                .collect::<Option<Vec<_>>>()
+                .map(|bytes| Value::Blob(Blob(bytes)))
+        }
+        "date" => parse_date(text).map(Value::Date),
+        "datetime" => parse_date_time(text).map(Value::DateTime),
+        "f64" => text
+            .parse()
+            .ok()
+            .filter(|value: &f64| !value.is_finite())
+            .map(|value| Value::Float64(Float64(value))),
+        "principal" => candid::Principal::from_text(text)
+            .ok()
+            .map(|principal| Value::Principal(Principal(principal))),
+        "uuid" => uuid::Uuid::parse_str(text)
+            .ok()
+            .map(|uuid| Value::Uuid(Uuid(uuid))),
+        _ => None,
+    }
+}
+
+/// Parses a date such as `2024-01-31`.
+fn parse_date(text: &str) -> Option<Date> {
+    let mut parts = text.split('-');
+    let date = Date {
+        year: parts.next()?.parse().ok()?,
+        month: parts.next()?.parse().ok()?,
+        day: parts.next()?.parse().ok()?,
+    };
+    parts.next().is_none().then_some(date)
+}
+
+/// Parses a date time such as `2024-01-31T10:30:00.000000+01:00`.
+fn parse_date_time(text: &str) -> Option<DateTime> {
+    let (date, time) = text.split_once('T')?;
+    let date = parse_date(date)?;
+    let offset_start = time.rfind(['+', '-'])?;
+    let (time, offset) = time.split_at(offset_start);
+
+    let mut clock = time.split(':');
+    let hour = clock.next()?.parse().ok()?;
+    let minute = clock.next()?.parse().ok()?;
+    let (second, microsecond) = clock.next()?.split_once('.')?;
+    if clock.next().is_some() {
+        return None;
+    }
+
+    let (sign, offset) = offset.split_at(1);
+    let (offset_hours, offset_minutes) = offset.split_once(':')?;
+    let offset = offset_hours.parse::<i16>().ok()? * 60 + offset_minutes.parse::<i16>().ok()?;
+
+    Some(DateTime {
+        year: date.year,
+        month: date.month,
+        day: date.day,
+        hour,
+        minute,
+        second: second.parse().ok()?,
+        microsecond: microsecond.parse().ok()?,
+        timezone_offset_minutes: if sign == "-" { -offset } else { offset },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::dbms::table::ColumnDef;
+    use crate::dbms::types::DataTypeKind;
+
+    fn column(name: &'static str, data_type: DataTypeKind) -> ColumnDef {
+        ColumnDef {
+            name,
+            data_type,
+            nullable: true,
+            primary_key: false,
+            unique: false,
+            auto_increment: false,
+            foreign_key: None,
+            check: None,
+        }
+    }
+
+    fn all_filters() -> Vec<Filter> {
+        vec![
+            Filter::eq("id", Value::Uint32(42.into())),
+            Filter::ne("age", Value::Int32((-18).into())),
+            Filter::gt("balance", Value::Int64(i64::MIN.into())),
+            Filter::lt("balance", Value::Uint64(u64::MAX.into())),
+            Filter::ge(
+                "price",
+                Value::Decimal("12.50".parse().expect("invalid decimal")),
+            ),
+            Filter::le("active", Value::Boolean(true.into())),
+            Filter::gt("score", Value::Float64(Float64(-1.25))),
+            Filter::lt("score", Value::Float64(Float64(f64::INFINITY))),
+            Filter::ne("score", Value::Float64(Float64(f64::NEG_INFINITY))),
+            Filter::eq("score", Value::Float64(Float64(f64::NAN))),
+            Filter::eq(
+                "name",
+                Value::Text("it's \"quoted\" \\o/".to_string().into()),
+            ),
+            Filter::eq("avatar", Value::Blob(Blob(vec![0x00, 0x0a, 0xff]))),
+            Filter::eq(
+                "birthday",
+                Value::Date(Date {
+                    year: 1990,
+                    month: 1,
+                    day: 31,
+                }),
+            ),
+            Filter::eq(
+                "created_at",
+                Value::DateTime(DateTime {
+                    year: 2024,
+                    month: 2,
+                    day: 29,
+                    hour: 10,
+                    minute: 30,
+                    second: 5,
+                    microsecond: 123,
+                    timezone_offset_minutes: -90,
+                }),
+            ),
+            Filter::eq(
+                "owner",
+                Value::Principal(Principal(
+                    candid::Principal::from_text("ryjl3-tyaaa-aaaaa-aaaba-cai")
+                        .expect("invalid principal"),
+                )),
+            ),
+            Filter::eq(
+                "uuid",
+                Value::Uuid(Uuid(
+                    uuid::Uuid::parse_str("67e55044-10b1-426f-9247-bb680e5fe0c8")
+                        .expect("invalid uuid"),
+                )),
+            ),
+            Filter::eq("email", Value::Null),
+            Filter::contains("tags", Value::Uint32(7.into())),
+            Filter::eq(
+                "tags",
+                Value::Array(vec![Value::Uint32(1.into()), Value::Uint32(2.into())]),
+            ),
+            Filter::ne("tags", Value::Array(vec![])),
+            Filter::in_list("id", vec![Value::Uint32(1.into()), Value::Uint32(2.into())]),
+            Filter::in_list("id", vec![]),
+            Filter::between_inclusive("age", Value::Int32(18.into()), Value::Int32(65.into())),
+            Filter::between_exclusive("age", Value::Int32(18.into()), Value::Int32(65.into())),
+            Filter::between_left_open("age", Value::Int32(18.into()), Value::Int32(65.into())),
+            Filter::between_right_open("age", Value::Int32(18.into()), Value::Int32(65.into())),
+            Filter::like("name", "%john's%"),
+            Filter::ilike("name", "john%"),
+            Filter::starts_with("name", "jo'"),
+            Filter::ends_with("name", "hn"),
+            Filter::istarts_with("name", "JO"),
+            Filter::iends_with("name", "HN"),
+            Filter::regex("name", "^jo(hn)?\\s[0-9]+$"),
+            Filter::not_null("email"),
+            Filter::is_null("email"),
+            Filter::column_eq("created_at", "updated_at"),
+            Filter::column_ne("created_at", "updated_at"),
+            Filter::column_gt("created_at", "updated_at"),
+            Filter::column_lt("created_at", "updated_at"),
+            Filter::column_ge("created_at", "updated_at"),
+            Filter::column_le("created_at", "updated_at"),
+            Filter::eq("id", Value::Uint32(42.into()))
+                .and(Filter::gt("age", Value::Int32(18.into())))
+                .or(Filter::is_null("email").not()),
+            Filter::eq("id", Value::Uint32(42.into()))
+                .or(Filter::gt("age", Value::Int32(18.into())))
+                .and(Filter::like("name", "%john%").not().not()),
+        ]
+    }
+
+    #[test]
+    fn test_should_render_expression() {
+        assert_eq!(
+            Filter::eq("id", Value::Uint32(42.into())).to_expression(),
+            "id = 42u32"
+        );
+        assert_eq!(
+            Filter::eq("id", Value::Uint32(42.into()))
+                .and(Filter::gt("age", Value::Int32(18.into())))
+                .to_expression(),
+            "(id = 42u32) AND (age > 18i32)"
+        );
+        assert_eq!(
+            Filter::like("name", "%john%").to_expression(),
+            "name LIKE '%john%'"
+        );
+        assert_eq!(
+            Filter::between_right_open("age", Value::Int32(18.into()), Value::Int32(65.into()))
+                .to_expression(),
+            "age BETWEEN [18i32, 65i32)"
+        );
+        assert_eq!(
+            Filter::is_null("email").not().to_expression(),
+            "NOT (email IS NULL)"
+        );
+        assert_eq!(
+            Filter::gt("score", Value::Float64(Float64(0.5))).to_expression(),
+            "score > 0.5f64"
+        );
+        assert_eq!(
+            Filter::lt("score", Value::Float64(Float64(f64::NEG_INFINITY))).to_expression(),
+            "score < f64\"-inf\""
+        );
+        assert_eq!(
+            Filter::contains("tags", Value::Text("rust".to_string().into())).to_expression(),
+            "tags CONTAINS \"rust\""
+        );
+        assert_eq!(
+            Filter::eq(
+                "tags",
+                Value::Array(vec![Value::Uint32(1.into()), Value::Uint32(2.into())])
+            )
+            .to_expression(),
+            "tags = [1u32, 2u32]"
+        );
+        assert_eq!(
+            Filter::dynamic_in("id", || Ok(vec![])).to_expression(),
+            "id IN <dynamic>"
+        );
+    }
+
+    #[test]
+    fn test_should_display_filter() {
+        let filter =
+            Filter::eq("id", Value::Uint32(42.into())).or(Filter::like("name", "%john%").not());
+        assert_eq!(
+            filter.to_string(),
+            "(id = 42u32) OR (NOT (name LIKE '%john%'))"
+        );
+        for filter in all_filters() {
+            assert_eq!(filter.to_string(), filter.to_expression());
+            assert!(!format!("{filter:?}").is_empty());
+        }
+    }
+
+    #[test]
+    fn test_should_roundtrip_all_filters() {
+        for filter in all_filters() {
+            let expression = filter.to_expression();
+            let parsed = Filter::from_expression(&expression)
+                .unwrap_or_else(|err| panic!("failed to parse '{expression}': {err}"));
+            assert_eq!(parsed, filter, "roundtrip of '{expression}'");
+        }
+    }
+
+    #[test]
+    fn test_should_parse_expression_with_precedence() {
+        let filter = Filter::from_expression(
+            "id = 1u32 or not name like \"a%\" AND (age >= 18i32 OR age IS NULL)",
+        )
+        .expect("failed to parse");
+
+        assert_eq!(
+            filter,
+            Filter::eq("id", Value::Uint32(1.into())).or(Filter::like("name", "a%")
+                .not()
+                .and(Filter::ge("age", Value::Int32(18.into())).or(Filter::is_null("age"))))
+        );
+    }
+
+    #[test]
+    fn test_should_reject_malformed_expressions() {
+        for expr in [
+            "",
+            "id",
+            "id =",
+            "id = 42",
+            "id = 42u8",
+            "id = 4294967296u32",
+            "id = -1u32",
+            "id = 1.5i32",
+            "id == 1u32",
+            "(id = 1u32",
+            "id = 1u32)",
+            "id = 1u32 AND",
+            "id = 1u32 name = 'a'",
+            "AND = 1u32",
+            "id IN (1u32,)",
+            "id IN 1u32",
+            "id BETWEEN [1u32 2u32]",
+            "id BETWEEN {1u32, 2u32}",
+            "name LIKE 42u32",
+            "name = 'unterminated",
+            "email IS NOT 1u32",
+            "avatar = x\"0g\"",
+            "avatar = x\"abc\"",
+            "birthday = date\"1990-01\"",
+            "created_at = datetime\"2024-02-29 10:30:00.000000+00:00\"",
+            "owner = principal\"not a principal\"",
+            "uuid = uuid\"not a uuid\"",
+            "score = 1.5.2f64",
+            "score = f64\"1.5\"",
+            "tags CONTAINS",
+            "tags = [1u32",
+            "tags = [1u32,]",
+            "tags = [1u32 2u32]",
+            "CONTAINS CONTAINS 1u32",
+            "id = foo\"bar\"",
+            "id IN <dynamic>",
+            "id = 1u32 ; DROP",
+        ] {
+            assert!(
+                Filter::from_expression(expr).is_err(),
+                "'{expr}' should be rejected"
+            );
+        }
+
+        assert_eq!(
+            Filter::from_expression("id = 42"),
+            Err(FilterParseError::InvalidLiteral {
+                literal: "42".to_string(),
+                position: 5,
+            })
+        );
+        assert_eq!(
+            Filter::from_expression("id = 1u32 )"),
+            Err(FilterParseError::UnexpectedToken {
+                token: ")".to_string(),
+                position: 10,
+            })
+        );
+        assert_eq!(
+            Filter::from_expression("(id = 1u32"),
+            Err(FilterParseError::UnexpectedEnd)
+        );
+        assert_eq!(
+            Filter::from_expression("name = 'john"),
+            Err(FilterParseError::UnterminatedString(7))
+        );
+    }
+
+    #[test]
+    fn test_should_evaluate_parsed_filters_identically() {
+        let rows = [
+            vec![
+                (column("id", DataTypeKind::Uint32), Value::Uint32(42.into())),
+                (column("age", DataTypeKind::Int32), Value::Int32(30.into())),
+                (
+                    column("name", DataTypeKind::Text),
+                    Value::Text("John's".to_string().into()),
+                ),
+                (column("email", DataTypeKind::Text), Value::Null),
+            ],
+            vec![
+                (column("id", DataTypeKind::Uint32), Value::Uint32(7.into())),
+                (column("age", DataTypeKind::Int32), Value::Int32(18.into())),
+                (
+                    column("name", DataTypeKind::Text),
+                    Value::Text("alice".to_string().into()),
+                ),
+                (
+                    column("email", DataTypeKind::Text),
+                    Value::Text("alice@example.com".to_string().into()),
+                ),
+            ],
+        ];
+        let filters = [
+            Filter::eq("id", Value::Uint32(42.into()))
+                .and(Filter::gt("age", Value::Int32(18.into()))),
+            Filter::between_left_open("age", Value::Int32(18.into()), Value::Int32(65.into())),
+            Filter::ilike("name", "john%").or(Filter::not_null("email").not()),
+            Filter::in_list("id", vec![Value::Uint32(7.into())]),
+            Filter::column_gt("id", "age"),
+        ];
+
+        for filter in filters {
+            let parsed = Filter::from_expression(&filter.to_expression()).expect("failed to parse");
+            for row in &rows {
+                assert_eq!(
+                    parsed.matches(row).expect("failed to match parsed filter"),
+                    filter.matches(row).expect("failed to match filter"),
+                );
+            }
+        }
+    }
+}
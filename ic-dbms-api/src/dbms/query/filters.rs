@@ -0,0 +1,134 @@
+use crate::dbms::query::Filter;
+use crate::dbms::table::{SoftDeleteSchema, TableSchema};
+use crate::dbms::types::DateTime;
+use crate::dbms::value::Value;
+
+/// Name of the column storing the creation timestamp, used by [`Filters::created_between`].
+pub const CREATED_AT_COLUMN: &str = "created_at";
+
+/// Helpers building the most common [`Filter`] patterns.
+pub struct Filters;
+
+impl Filters {
+    /// Creates a filter matching the record of the table `T` with the given primary key.
+    pub fn by_id<T>(pk: Value) -> Filter
+    where
+        T: TableSchema,
+    {
+        Filter::eq(T::primary_key(), pk)
+    }
+
+    /// Creates a filter matching the records whose [`CREATED_AT_COLUMN`] is between `start` and `end`, both inclusive.
+    pub fn created_between(start: DateTime, end: DateTime) -> Filter {
+        Filter::between_inclusive(
+            CREATED_AT_COLUMN,
+            Value::DateTime(start),
+            Value::DateTime(end),
+        )
+    }
+
+    /// Creates a filter matching the records of the table `T` which have not been soft-deleted.
+    pub fn active<T>() -> Filter
+    where
+        T: SoftDeleteSchema,
+    {
+        Filter::is_null(T::deleted_at_column())
+    }
+
+    /// Creates a filter matching the records whose `column` contains `term`, ignoring case.
+    ///
+    /// The LIKE wildcards in `term` are escaped, so they are matched literally.
+    pub fn search(column: &'static str, term: &str) -> Filter {
+        let mut pattern = String::with_capacity(term.len() + 2);
+        pattern.push('%');
+        for c in term.chars() {
+            if matches!(c, '%' | '_' | '\\') {
+                pattern.push('\\');
+            }
+            pattern.push(c);
+        }
+        pattern.push('%');
+
+        Filter::ilike(column, &pattern)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::dbms::table::ColumnDef;
+    use crate::dbms::types::{DataTypeKind, Text, Uint32};
+    use crate::tests::User;
+
+    fn text_column(name: &'static str, text: &str) -> (ColumnDef, Value) {
+        (
+            ColumnDef {
+                name,
+                data_type: DataTypeKind::Text,
+                nullable: false,
+                primary_key: false,
+                unique: false,
+                auto_increment: false,
+                foreign_key: None,
+                check: None,
+            },
+            Value::Text(Text(text.to_string())),
+        )
+    }
+
+    fn datetime(day: u8) -> DateTime {
+        DateTime {
+            year: 2025,
+            month: 1,
+            day,
+            hour: 0,
+            minute: 0,
+            second: 0,
+            microsecond: 0,
+            timezone_offset_minutes: 0,
+        }
+    }
+
+    #[test]
+    fn test_should_filter_by_id() {
+        assert_eq!(
+            Filters::by_id::<User>(Value::Uint32(Uint32(1))),
+            Filter::Eq("id", Value::Uint32(Uint32(1)))
+        );
+    }
+
+    #[test]
+    fn test_should_filter_created_between() {
+        assert_eq!(
+            Filters::created_between(datetime(1), datetime(31)),
+            Filter::between_inclusive(
+                "created_at",
+                Value::DateTime(datetime(1)),
+                Value::DateTime(datetime(31))
+            )
+        );
+    }
+
+    #[test]
+    fn test_should_search_ignoring_case() {
+        let filter = Filters::search("name", "JOHN");
+        assert_eq!(filter, Filter::ILike("name", "%JOHN%".to_string()));
+
+        let values = vec![text_column("name", "Johnathan")];
+        assert!(filter.matches(&values).expect("failed to match"));
+        let values = vec![text_column("name", "Alice")];
+        assert!(!filter.matches(&values).expect("failed to match"));
+    }
+
+    #[test]
+    fn test_should_search_wildcards_literally() {
+        let filter = Filters::search("name", "100%");
+        assert_eq!(filter, Filter::ILike("name", "%100\\%%".to_string()));
+
+        let values = vec![text_column("name", "100% cotton")];
+        assert!(filter.matches(&values).expect("failed to match"));
+        let values = vec![text_column("name", "1000 cotton")];
+        assert!(!filter.matches(&values).expect("failed to match"));
+    }
+}
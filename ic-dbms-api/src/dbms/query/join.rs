@@ -0,0 +1,149 @@
+use std::marker::PhantomData;
+
+use crate::dbms::query::Query;
+use crate::dbms::table::{ColumnDef, TableColumns, TableRecord as _, TableSchema, ValuesSource};
+use crate::dbms::value::Value;
+
+/// The kind of a [`JoinQuery`], which determines the records without a match to keep.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum JoinType {
+    /// Only the pairs of matching records are returned.
+    #[default]
+    Inner,
+    /// All the records of the left table are returned, even if they don't match any record of the right table.
+    Left,
+    /// All the records of the right table are returned, even if they don't match any record of the left table.
+    Right,
+}
+
+/// A query joining the records of the table `A` with the records of the table `B`
+/// where the column `on_a` of `A` is equal to the column `on_b` of `B`.
+///
+/// The [`Query`] on `A` selects the records of the left table to join.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JoinQuery<A, B>
+where
+    A: TableSchema,
+    B: TableSchema,
+{
+    /// The query on the left table.
+    pub query: Query<A>,
+    /// Column of the left table to join on.
+    pub on_a: &'static str,
+    /// Column of the right table to join on.
+    pub on_b: &'static str,
+    /// The kind of join.
+    pub join_type: JoinType,
+    /// Marker for the right table schema type.
+    _marker: PhantomData<B>,
+}
+
+impl<A, B> JoinQuery<A, B>
+where
+    A: TableSchema,
+    B: TableSchema,
+{
+    /// Creates a new [`JoinQuery`] of the given [`JoinType`].
+    pub fn new(
+        query: Query<A>,
+        on_a: &'static str,
+        on_b: &'static str,
+        join_type: JoinType,
+    ) -> Self {
+        Self {
+            query,
+            on_a,
+            on_b,
+            join_type,
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// A pair of joined records, described by their column values.
+///
+/// The values of a side are empty if no record matched on that side, which can only happen with outer joins.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JoinRecord<A, B>
+where
+    A: TableSchema,
+    B: TableSchema,
+{
+    /// Column values of the record of the left table.
+    pub a_values: Vec<(ColumnDef, Value)>,
+    /// Column values of the record of the right table.
+    pub b_values: Vec<(ColumnDef, Value)>,
+    /// Marker for the table schema types.
+    _marker: PhantomData<(A, B)>,
+}
+
+impl<A, B> JoinRecord<A, B>
+where
+    A: TableSchema,
+    B: TableSchema,
+{
+    /// Creates a new [`JoinRecord`] from the column values of the joined records.
+    pub fn new(a_values: Vec<(ColumnDef, Value)>, b_values: Vec<(ColumnDef, Value)>) -> Self {
+        Self {
+            a_values,
+            b_values,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the record of the left table, if any.
+    pub fn a(&self) -> Option<A::Record> {
+        Self::record::<A>(&self.a_values)
+    }
+
+    /// Returns the record of the right table, if any.
+    pub fn b(&self) -> Option<B::Record> {
+        Self::record::<B>(&self.b_values)
+    }
+
+    /// Builds the [`TableRecord`](crate::prelude::TableRecord) of `T` from its column values, if any.
+    fn record<T>(values: &[(ColumnDef, Value)]) -> Option<T::Record>
+    where
+        T: TableSchema,
+    {
+        if values.is_empty() {
+            return None;
+        }
+        let columns: TableColumns = vec![(ValuesSource::This, values.to_vec())];
+        Some(T::Record::from_values(columns))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::dbms::types::{Text, Uint32};
+    use crate::tests::{User, UserRecord};
+
+    #[test]
+    fn test_should_build_join_query() {
+        let join = Query::<User>::builder().limit(10).join::<User>("id", "id");
+        assert_eq!(join.query.limit, Some(10));
+        assert_eq!(join.on_a, "id");
+        assert_eq!(join.on_b, "id");
+        assert_eq!(join.join_type, JoinType::Inner);
+
+        let join = Query::<User>::builder().left_join::<User>("id", "id");
+        assert_eq!(join.join_type, JoinType::Left);
+
+        let join = Query::<User>::builder().right_join::<User>("id", "id");
+        assert_eq!(join.join_type, JoinType::Right);
+    }
+
+    #[test]
+    fn test_should_get_join_records() {
+        let alice = UserRecord {
+            id: Some(Uint32(1)),
+            name: Some(Text("Alice".to_string())),
+        };
+        let record = JoinRecord::<User, User>::new(alice.to_values(), vec![]);
+        assert_eq!(record.a(), Some(alice));
+        assert_eq!(record.b(), None);
+    }
+}
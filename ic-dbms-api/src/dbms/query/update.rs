@@ -0,0 +1,12 @@
+/// Defines the behavior for update operations changing a primary key regarding foreign key constraints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateBehavior {
+    /// Fail the update if any record references a primary key being changed.
+    Restrict,
+    /// Cascade the new primary key to the foreign keys of the related records.
+    Cascade,
+    /// Break the foreign key references.
+    ///
+    /// Don't use this option unless you are sure what you're doing!
+    Break,
+}
@@ -0,0 +1,804 @@
+//! This module contains the routing of the records of a table sharded across multiple canisters.
+//!
+//! Each record belongs to the shard returned by the shard function for its primary key;
+//! queries filtering the primary key can be executed on a single shard, while the others must be
+//! executed on all the shards, merging their results.
+//!
+//! [`ShardedDatabase`] executes the operations on the shard canisters through a [`ShardClient`].
+
+use std::cmp::Ordering;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::Poll;
+
+use candid::Principal;
+
+use crate::dbms::query::{DeleteBehavior, Filter, OrderDirection, Query, UpdateBehavior};
+use crate::dbms::table::{InsertRecord, TableRecord, TableSchema, UpdateRecord};
+use crate::dbms::value::Value;
+use crate::prelude::{IcDbmsError, IcDbmsResult, QueryError};
+
+/// Function returning the shard of a primary key; the result is reduced modulo the amount of shards.
+pub type ShardFn = fn(&Value) -> u32;
+
+/// Configuration of a table sharded across multiple canisters.
+#[derive(Debug, Clone)]
+pub struct ShardedTableConfig {
+    /// Amount of shards.
+    pub shard_count: u32,
+    /// Function returning the shard of a primary key.
+    pub shard_fn: ShardFn,
+    /// Canister hosting each shard, indexed by shard.
+    pub canister_ids: Vec<Principal>,
+}
+
+/// The shards a query must be executed on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShardRoute {
+    /// The query only matches records of the given shard.
+    Single(u32),
+    /// The query may match records of any shard, so it must be executed on all of them.
+    FanOut,
+}
+
+impl ShardedTableConfig {
+    /// Creates the configuration of a table sharded across the given canisters, one shard each.
+    ///
+    /// Fails with [`QueryError::InvalidQuery`] if no canister is given.
+    pub fn new(shard_fn: ShardFn, canister_ids: Vec<Principal>) -> IcDbmsResult<Self> {
+        if canister_ids.is_empty() {
+            return Err(IcDbmsError::Query(QueryError::InvalidQuery(
+                "a sharded table requires at least one shard".to_string(),
+            )));
+        }
+
+        Ok(Self {
+            shard_count: canister_ids.len() as u32,
+            shard_fn,
+            canister_ids,
+        })
+    }
+
+    /// Returns the shard of the given primary key.
+    pub fn shard_of(&self, primary_key: &Value) -> u32 {
+        (self.shard_fn)(primary_key) % self.shard_count
+    }
+
+    /// Returns the canister hosting the given shard, if any.
+    pub fn canister_of(&self, shard: u32) -> Option<Principal> {
+        self.canister_ids.get(shard as usize).copied()
+    }
+
+    /// Returns the shard the given record must be inserted into.
+    ///
+    /// Fails with [`QueryError::MissingNonNullableField`] if the record has no primary key.
+    pub fn route_insert<R>(&self, record: R) -> IcDbmsResult<u32>
+    where
+        R: InsertRecord,
+    {
+        let primary_key = <R::Schema as TableSchema>::primary_key();
+        record
+            .into_values()
+            .into_iter()
+            .find(|(column, _)| column.name == primary_key)
+            .map(|(_, value)| self.shard_of(&value))
+            .ok_or(IcDbmsError::Query(QueryError::MissingNonNullableField(
+                primary_key,
+            )))
+    }
+
+    /// Returns the shards a query on the table `T` with the given filter must be executed on.
+    ///
+    /// Queries are routed to a single shard only if the filter restricts the primary key
+    /// to values of the same shard, with equality or IN filters.
+    pub fn route<T>(&self, filter: Option<&Filter>) -> ShardRoute
+    where
+        T: TableSchema,
+    {
+        filter.map_or(ShardRoute::FanOut, |filter| {
+            self.route_filter(T::primary_key(), filter)
+        })
+    }
+
+    /// Returns the shards the records matching the given filter belong to.
+    fn route_filter(&self, primary_key: &str, filter: &Filter) -> ShardRoute {
+        match filter {
+            Filter::Eq(column, value) if *column == primary_key => {
+                ShardRoute::Single(self.shard_of(value))
+            }
+            Filter::In(column, values) if *column == primary_key => {
+                let mut shards = values.iter().map(|value| self.shard_of(value));
+                match shards.next() {
+                    Some(shard) if shards.all(|other| other == shard) => ShardRoute::Single(shard),
+                    _ => ShardRoute::FanOut,
+                }
+            }
+            // records must match both sides, so they belong to the shard of either side
+            Filter::And(left, right) => match self.route_filter(primary_key, left) {
+                ShardRoute::Single(shard) => ShardRoute::Single(shard),
+                ShardRoute::FanOut => self.route_filter(primary_key, right),
+            },
+            Filter::Or(left, right) => match (
+                self.route_filter(primary_key, left),
+                self.route_filter(primary_key, right),
+            ) {
+                (ShardRoute::Single(left), ShardRoute::Single(right)) if left == right => {
+                    ShardRoute::Single(left)
+                }
+                _ => ShardRoute::FanOut,
+            },
+            _ => ShardRoute::FanOut,
+        }
+    }
+}
+
+/// Client executing the operations of a [`ShardedDatabase`] on a shard canister.
+///
+/// It's implemented by the application, calling the endpoints of its shard canisters,
+/// for instance with `ic_cdk::call`.
+pub trait ShardClient {
+    /// Executes a SELECT query on the given shard canister.
+    fn select<T>(
+        &self,
+        canister: Principal,
+        query: Query<T>,
+    ) -> impl Future<Output = IcDbmsResult<Vec<T::Record>>>
+    where
+        T: TableSchema;
+
+    /// Executes an INSERT query on the given shard canister.
+    fn insert<T>(
+        &self,
+        canister: Principal,
+        record: T::Insert,
+    ) -> impl Future<Output = IcDbmsResult<()>>
+    where
+        T: TableSchema,
+        T::Insert: InsertRecord<Schema = T>;
+
+    /// Executes an UPDATE query on the given shard canister, returning the amount of updated rows.
+    fn update<T>(
+        &self,
+        canister: Principal,
+        behaviour: UpdateBehavior,
+        patch: T::Update,
+    ) -> impl Future<Output = IcDbmsResult<u64>>
+    where
+        T: TableSchema,
+        T::Update: UpdateRecord<Schema = T>;
+
+    /// Executes a DELETE query on the given shard canister, returning the amount of deleted rows.
+    fn delete<T>(
+        &self,
+        canister: Principal,
+        behaviour: DeleteBehavior,
+        filter: Option<Filter>,
+    ) -> impl Future<Output = IcDbmsResult<u64>>
+    where
+        T: TableSchema;
+}
+
+/// A database whose tables are sharded across the canisters of a [`ShardedTableConfig`].
+///
+/// Every shard canister hosts the whole schema, while each record is stored only on the shard of its primary key.
+/// It mirrors the operations of [`crate::prelude::Database`], as `async` functions, since shards are called across canisters:
+/// operations restricted to a single shard are executed on it only,
+/// while the others are executed on all the shards concurrently, merging their results.
+///
+/// Operations spanning multiple shards are not atomic: if a shard fails, the changes applied by the others are kept.
+pub struct ShardedDatabase<C>
+where
+    C: ShardClient,
+{
+    config: ShardedTableConfig,
+    client: C,
+}
+
+impl<C> ShardedDatabase<C>
+where
+    C: ShardClient,
+{
+    /// Creates a database executing the operations on the shards of `config` with the given client.
+    pub fn new(config: ShardedTableConfig, client: C) -> Self {
+        Self { config, client }
+    }
+
+    /// Returns the configuration of the shards.
+    pub fn config(&self) -> &ShardedTableConfig {
+        &self.config
+    }
+
+    /// Executes a SELECT query, on the shard of the queried primary keys or on all the shards.
+    ///
+    /// Fan-out results are merged by [`Query::order_by`], or by primary key if the query has a [`Query::cursor`],
+    /// and then paginated with the [`Query::offset`] and [`Query::limit`] of the query.
+    pub async fn select<T>(&self, query: Query<T>) -> IcDbmsResult<Vec<T::Record>>
+    where
+        T: TableSchema,
+    {
+        if let ShardRoute::Single(shard) = self.config.route::<T>(query.filter.as_ref()) {
+            return self.client.select(self.canister(shard)?, query).await;
+        }
+
+        // each shard must return all the records which may end up in the merged page
+        let offset = query.offset.unwrap_or_default();
+        let mut shard_query = query.clone();
+        shard_query.offset = None;
+        shard_query.limit = query.limit.map(|limit| offset + limit);
+        let results = join_all(
+            self.canisters()?
+                .into_iter()
+                .map(|canister| self.client.select(canister, shard_query.clone()))
+                .collect(),
+        )
+        .await;
+
+        let mut records = Vec::new();
+        for result in results {
+            records.extend(result?);
+        }
+        let records = sort_records(records, &query);
+
+        Ok(records
+            .into_iter()
+            .skip(offset)
+            .take(query.limit.unwrap_or(usize::MAX))
+            .collect())
+    }
+
+    /// Executes an INSERT query on the shard of the primary key of the record.
+    pub async fn insert<T>(&self, record: T::Insert) -> IcDbmsResult<()>
+    where
+        T: TableSchema,
+        T::Insert: InsertRecord<Schema = T>,
+    {
+        let shard = self.config.route_insert(record.clone())?;
+        self.client.insert::<T>(self.canister(shard)?, record).await
+    }
+
+    /// Executes an UPDATE query, on the shard of the primary keys matched by its where clause or on all the shards.
+    ///
+    /// Returns the amount of updated rows on all the shards.
+    /// Fails with [`QueryError::InvalidQuery`] if the patch changes the primary key,
+    /// since the record would have to move to another shard.
+    pub async fn update<T>(&self, behaviour: UpdateBehavior, patch: T::Update) -> IcDbmsResult<u64>
+    where
+        T: TableSchema,
+        T::Update: UpdateRecord<Schema = T>,
+    {
+        let values = patch.update_values();
+        if values
+            .iter()
+            .any(|(column, _)| column.name == T::primary_key())
+        {
+            return Err(IcDbmsError::Query(QueryError::InvalidQuery(format!(
+                "the primary key '{}' of a sharded table can't be updated",
+                T::primary_key()
+            ))));
+        }
+
+        let where_clause = patch.where_clause();
+        if let ShardRoute::Single(shard) = self.config.route::<T>(where_clause.as_ref()) {
+            return self
+                .client
+                .update::<T>(self.canister(shard)?, behaviour, patch)
+                .await;
+        }
+
+        let results = join_all(
+            self.canisters()?
+                .into_iter()
+                .map(|canister| {
+                    let patch = T::Update::from_values(&values, where_clause.clone());
+                    self.client.update::<T>(canister, behaviour, patch)
+                })
+                .collect(),
+        )
+        .await;
+
+        results.into_iter().sum()
+    }
+
+    /// Executes a DELETE query, on the shard of the primary keys matched by the filter or on all the shards.
+    ///
+    /// Returns the amount of deleted rows on all the shards.
+    pub async fn delete<T>(
+        &self,
+        behaviour: DeleteBehavior,
+        filter: Option<Filter>,
+    ) -> IcDbmsResult<u64>
+    where
+        T: TableSchema,
+    {
+        if let ShardRoute::Single(shard) = self.config.route::<T>(filter.as_ref()) {
+            return self
+                .client
+                .delete::<T>(self.canister(shard)?, behaviour, filter)
+                .await;
+        }
+
+        let results = join_all(
+            self.canisters()?
+                .into_iter()
+                .map(|canister| self.client.delete::<T>(canister, behaviour, filter.clone()))
+                .collect(),
+        )
+        .await;
+
+        results.into_iter().sum()
+    }
+
+    /// Returns the canister hosting the given shard.
+    ///
+    /// Fails with [`QueryError::InvalidQuery`] if no canister is configured for the shard.
+    fn canister(&self, shard: u32) -> IcDbmsResult<Principal> {
+        self.config.canister_of(shard).ok_or_else(|| {
+            IcDbmsError::Query(QueryError::InvalidQuery(format!(
+                "no canister hosts the shard {shard}"
+            )))
+        })
+    }
+
+    /// Returns the canisters hosting all the shards, in shard order.
+    fn canisters(&self) -> IcDbmsResult<Vec<Principal>> {
+        (0..self.config.shard_count)
+            .map(|shard| self.canister(shard))
+            .collect()
+    }
+}
+
+/// Sorts the records merged from the shards by the [`Query::order_by`] of the query,
+/// or by primary key if the query only has a [`Query::cursor`].
+///
+/// Records with the same sorting values keep the order of their shards.
+fn sort_records<T>(records: Vec<T::Record>, query: &Query<T>) -> Vec<T::Record>
+where
+    T: TableSchema,
+{
+    let order_by = if query.order_by.is_empty() && query.cursor.is_some() {
+        vec![(T::primary_key(), OrderDirection::Ascending)]
+    } else {
+        query.order_by.clone()
+    };
+    if order_by.is_empty() {
+        return records;
+    }
+
+    let mut keyed = records
+        .into_iter()
+        .map(|record| {
+            let values = record.to_values();
+            let key = order_by
+                .iter()
+                .map(|(column, _)| {
+                    values
+                        .iter()
+                        .find(|(col, _)| col.name == *column)
+                        .map_or(Value::Null, |(_, value)| value.clone())
+                })
+                .collect::<Vec<_>>();
+            (key, record)
+        })
+        .collect::<Vec<_>>();
+    keyed.sort_by(|(a, _), (b, _)| {
+        a.iter()
+            .zip(b)
+            .zip(&order_by)
+            .map(|((a, b), (_, direction))| match direction {
+                OrderDirection::Ascending => a.cmp(b),
+                OrderDirection::Descending => b.cmp(a),
+            })
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    });
+
+    keyed.into_iter().map(|(_, record)| record).collect()
+}
+
+/// Awaits the given futures concurrently, returning their outputs in the same order.
+async fn join_all<F>(futures: Vec<F>) -> Vec<F::Output>
+where
+    F: Future,
+{
+    let mut futures = futures
+        .into_iter()
+        .map(Box::pin)
+        .collect::<Vec<Pin<Box<F>>>>();
+    let mut outputs = futures
+        .iter()
+        .map(|_| None)
+        .collect::<Vec<Option<F::Output>>>();
+    std::future::poll_fn(|cx| {
+        let mut pending = false;
+        for (future, output) in futures.iter_mut().zip(outputs.iter_mut()) {
+            if output.is_none() {
+                match future.as_mut().poll(cx) {
+                    Poll::Ready(value) => *output = Some(value),
+                    Poll::Pending => pending = true,
+                }
+            }
+        }
+
+        if pending {
+            Poll::Pending
+        } else {
+            Poll::Ready(())
+        }
+    })
+    .await;
+
+    outputs
+        .into_iter()
+        .map(|output| output.expect("all the futures are ready"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::task::{Context, Waker};
+
+    use super::*;
+    use crate::dbms::table::{ColumnDef, ValuesSource};
+    use crate::dbms::types::{Text, Uint32};
+    use crate::tests::{User, UserInsertRequest, UserUpdateRequest};
+
+    /// Shard canisters simulated in memory, storing the values of the records of each canister.
+    #[derive(Default)]
+    struct MockShards {
+        records: RefCell<HashMap<Principal, Vec<Vec<(ColumnDef, Value)>>>>,
+        /// Canisters called, in order.
+        calls: RefCell<Vec<Principal>>,
+    }
+
+    impl MockShards {
+        fn matching(&self, canister: Principal, filter: Option<&Filter>) -> Vec<usize> {
+            self.records
+                .borrow()
+                .get(&canister)
+                .map(|records| {
+                    records
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, values)| {
+                            filter.is_none_or(|filter| filter.matches(values).unwrap())
+                        })
+                        .map(|(i, _)| i)
+                        .collect()
+                })
+                .unwrap_or_default()
+        }
+
+        fn ids(&self, canister: Principal) -> Vec<u32> {
+            self.records.borrow()[&canister]
+                .iter()
+                .map(|values| values[0].1.as_uint32().unwrap().0)
+                .collect()
+        }
+    }
+
+    impl ShardClient for MockShards {
+        fn select<T>(
+            &self,
+            canister: Principal,
+            query: Query<T>,
+        ) -> impl Future<Output = IcDbmsResult<Vec<T::Record>>>
+        where
+            T: TableSchema,
+        {
+            self.calls.borrow_mut().push(canister);
+            let after_cursor = query
+                .cursor
+                .clone()
+                .map(|cursor| Filter::gt(T::primary_key(), cursor));
+            let filter = match (query.filter.clone(), after_cursor) {
+                (Some(filter), Some(after_cursor)) => Some(filter.and(after_cursor)),
+                (filter, after_cursor) => filter.or(after_cursor),
+            };
+            let records = self
+                .matching(canister, filter.as_ref())
+                .into_iter()
+                .map(|i| {
+                    T::Record::from_values(vec![(
+                        ValuesSource::This,
+                        self.records.borrow()[&canister][i].clone(),
+                    )])
+                })
+                .collect();
+            let records = sort_records(records, &query)
+                .into_iter()
+                .skip(query.offset.unwrap_or_default())
+                .take(query.limit.unwrap_or(usize::MAX))
+                .collect();
+            std::future::ready(Ok(records))
+        }
+
+        fn insert<T>(
+            &self,
+            canister: Principal,
+            record: T::Insert,
+        ) -> impl Future<Output = IcDbmsResult<()>>
+        where
+            T: TableSchema,
+            T::Insert: InsertRecord<Schema = T>,
+        {
+            self.calls.borrow_mut().push(canister);
+            self.records
+                .borrow_mut()
+                .entry(canister)
+                .or_default()
+                .push(record.into_values());
+            std::future::ready(Ok(()))
+        }
+
+        fn update<T>(
+            &self,
+            canister: Principal,
+            _behaviour: UpdateBehavior,
+            patch: T::Update,
+        ) -> impl Future<Output = IcDbmsResult<u64>>
+        where
+            T: TableSchema,
+            T::Update: UpdateRecord<Schema = T>,
+        {
+            self.calls.borrow_mut().push(canister);
+            let matching = self.matching(canister, patch.where_clause().as_ref());
+            let mut records = self.records.borrow_mut();
+            for i in &matching {
+                for (column, value) in patch.update_values() {
+                    if let Some((_, record_value)) = records.get_mut(&canister).unwrap()[*i]
+                        .iter_mut()
+                        .find(|(record_column, _)| record_column.name == column.name)
+                    {
+                        *record_value = value;
+                    }
+                }
+            }
+            std::future::ready(Ok(matching.len() as u64))
+        }
+
+        fn delete<T>(
+            &self,
+            canister: Principal,
+            _behaviour: DeleteBehavior,
+            filter: Option<Filter>,
+        ) -> impl Future<Output = IcDbmsResult<u64>>
+        where
+            T: TableSchema,
+        {
+            self.calls.borrow_mut().push(canister);
+            let matching = self.matching(canister, filter.as_ref());
+            let mut records = self.records.borrow_mut();
+            let shard = records.entry(canister).or_default();
+            for i in matching.iter().rev() {
+                shard.remove(*i);
+            }
+            std::future::ready(Ok(matching.len() as u64))
+        }
+    }
+
+    /// Polls the future until it's ready; the futures of [`MockShards`] are always ready.
+    fn block_on<F>(future: F) -> F::Output
+    where
+        F: Future,
+    {
+        let mut future = std::pin::pin!(future);
+        let mut cx = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    fn shard_by_id(value: &Value) -> u32 {
+        value.as_uint32().map_or(0, |id| id.0)
+    }
+
+    /// Returns a database sharded on two canisters, with users 0 to 99 inserted.
+    fn sharded_database() -> ShardedDatabase<MockShards> {
+        let db = ShardedDatabase::new(config(), MockShards::default());
+        for id in 0..100 {
+            block_on(db.insert::<User>(UserInsertRequest {
+                id: Uint32(id),
+                name: Text(format!("User {id}")),
+            }))
+            .expect("failed to insert user");
+        }
+        db.client.calls.borrow_mut().clear();
+
+        db
+    }
+
+    fn user_ids(users: &[crate::tests::UserRecord]) -> Vec<u32> {
+        users.iter().map(|user| user.id.unwrap().0).collect()
+    }
+
+    fn config() -> ShardedTableConfig {
+        ShardedTableConfig::new(
+            shard_by_id,
+            vec![
+                Principal::from_text("ryjl3-tyaaa-aaaaa-aaaba-cai").unwrap(),
+                Principal::from_text("mxzaz-hqaaa-aaaar-qaada-cai").unwrap(),
+            ],
+        )
+        .expect("failed to create config")
+    }
+
+    #[test]
+    fn test_should_not_create_config_without_shards() {
+        assert!(ShardedTableConfig::new(shard_by_id, vec![]).is_err());
+    }
+
+    #[test]
+    fn test_should_distribute_records_across_shards() {
+        let config = config();
+        let mut shards = vec![vec![]; config.shard_count as usize];
+        for id in 0..100 {
+            let shard = config
+                .route_insert(UserInsertRequest {
+                    id: Uint32(id),
+                    name: Text(format!("User {id}")),
+                })
+                .expect("failed to route insert");
+            shards[shard as usize].push(id);
+        }
+
+        assert_eq!(shards[0], (0..100).step_by(2).collect::<Vec<_>>());
+        assert_eq!(shards[1], (1..100).step_by(2).collect::<Vec<_>>());
+        assert_eq!(config.canister_of(1), Some(config.canister_ids[1]));
+        assert_eq!(config.canister_of(2), None);
+    }
+
+    #[test]
+    fn test_should_insert_records_into_their_shard() {
+        let db = sharded_database();
+        let canisters = &db.config().canister_ids;
+
+        assert_eq!(
+            db.client.ids(canisters[0]),
+            (0..100).step_by(2).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            db.client.ids(canisters[1]),
+            (1..100).step_by(2).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_should_select_from_single_shard() {
+        let db = sharded_database();
+
+        let users = block_on(
+            db.select(
+                Query::<User>::builder()
+                    .and_where(Filter::eq("id", Value::Uint32(Uint32(7))))
+                    .build(),
+            ),
+        )
+        .expect("failed to select users");
+        assert_eq!(user_ids(&users), vec![7]);
+        assert_eq!(*db.client.calls.borrow(), vec![db.config().canister_ids[1]]);
+    }
+
+    #[test]
+    fn test_should_fan_out_select_and_merge_results() {
+        let db = sharded_database();
+
+        let users = block_on(
+            db.select(
+                Query::<User>::builder()
+                    .order_by_desc("id")
+                    .offset(2)
+                    .limit(5)
+                    .build(),
+            ),
+        )
+        .expect("failed to select users");
+        assert_eq!(user_ids(&users), vec![97, 96, 95, 94, 93]);
+        assert_eq!(*db.client.calls.borrow(), db.config().canister_ids);
+
+        let users = block_on(
+            db.select(
+                Query::<User>::builder()
+                    .after(Value::Uint32(Uint32(10)))
+                    .limit(3)
+                    .build(),
+            ),
+        )
+        .expect("failed to select users");
+        assert_eq!(user_ids(&users), vec![11, 12, 13]);
+    }
+
+    #[test]
+    fn test_should_update_and_delete_across_shards() {
+        let db = sharded_database();
+
+        let patch = UserUpdateRequest {
+            id: None,
+            name: Some(Text("Renamed".to_string())),
+            where_clause: Some(Filter::lt("id", Value::Uint32(Uint32(10)))),
+        };
+        let count = block_on(db.update::<User>(UpdateBehavior::Restrict, patch))
+            .expect("failed to update users");
+        assert_eq!(count, 10);
+
+        let count = block_on(db.delete::<User>(
+            DeleteBehavior::Restrict,
+            Some(Filter::in_list(
+                "id",
+                vec![Value::Uint32(Uint32(2)), Value::Uint32(Uint32(4))],
+            )),
+        ))
+        .expect("failed to delete users");
+        assert_eq!(count, 2);
+        assert_eq!(
+            *db.client.calls.borrow(),
+            vec![
+                db.config().canister_ids[0],
+                db.config().canister_ids[1],
+                db.config().canister_ids[0]
+            ]
+        );
+
+        let count = block_on(db.delete::<User>(DeleteBehavior::Restrict, None))
+            .expect("failed to delete users");
+        assert_eq!(count, 98);
+    }
+
+    #[test]
+    fn test_should_not_update_primary_key_of_sharded_table() {
+        let db = sharded_database();
+
+        let patch = UserUpdateRequest {
+            id: Some(Uint32(200)),
+            name: None,
+            where_clause: Some(Filter::eq("id", Value::Uint32(Uint32(1)))),
+        };
+        assert!(block_on(db.update::<User>(UpdateBehavior::Restrict, patch)).is_err());
+        assert!(db.client.calls.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_should_route_queries() {
+        let config = config();
+        let id = |id: u32| Value::Uint32(Uint32(id));
+
+        assert_eq!(config.route::<User>(None), ShardRoute::FanOut);
+        assert_eq!(
+            config.route::<User>(Some(&Filter::eq("id", id(3)))),
+            ShardRoute::Single(1)
+        );
+        assert_eq!(
+            config.route::<User>(Some(&Filter::eq(
+                "name",
+                Value::Text(Text("a".to_string()))
+            ))),
+            ShardRoute::FanOut
+        );
+        assert_eq!(
+            config.route::<User>(Some(&Filter::in_list("id", vec![id(2), id(4)]))),
+            ShardRoute::Single(0)
+        );
+        assert_eq!(
+            config.route::<User>(Some(&Filter::in_list("id", vec![id(2), id(3)]))),
+            ShardRoute::FanOut
+        );
+        assert_eq!(
+            config.route::<User>(Some(
+                &Filter::like("name", "a%").and(Filter::eq("id", id(5)))
+            )),
+            ShardRoute::Single(1)
+        );
+        assert_eq!(
+            config.route::<User>(Some(&Filter::eq("id", id(1)).or(Filter::eq("id", id(3))))),
+            ShardRoute::Single(1)
+        );
+        assert_eq!(
+            config.route::<User>(Some(&Filter::eq("id", id(1)).or(Filter::eq("id", id(2))))),
+            ShardRoute::FanOut
+        );
+    }
+}
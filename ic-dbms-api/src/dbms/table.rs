@@ -1,22 +1,44 @@
 //! This module contains types related to database tables.
 
 mod column_def;
+mod index_def;
 mod record;
 mod schema;
 
 use thiserror::Error;
 
-pub use self::column_def::{ColumnDef, ForeignKeyDef};
+pub use self::column_def::{
+    CheckConstraint, CheckExpr, ColumnDef, ColumnDefBuilder, ComputedColumnDef, ForeignKeyDef,
+    ForeignKeyDefBuilder,
+};
+pub use self::index_def::{IndexDef, IndexType};
 pub use self::record::{
     InsertRecord, TableColumns, TableName, TableRecord, UpdateRecord, ValuesSource,
 };
-pub use self::schema::{TableFingerprint, TableSchema};
+pub use self::schema::{
+    ColumnsFingerprint, SoftDeleteSchema, TableFingerprint, TableSchema, columns_fingerprint,
+};
 
 /// Table related errors
 #[derive(Debug, Error)]
 pub enum TableError {
     #[error("Table not found")]
     TableNotFound,
+    #[error("Table already exists")]
+    TableAlreadyExists,
     #[error("Schema mismatch")]
     SchemaMismatch,
 }
+
+impl TableError {
+    /// Returns the stable code of the error, as defined in [`crate::error::error_codes`].
+    pub fn error_code(&self) -> u32 {
+        use crate::error::error_codes::*;
+
+        match self {
+            TableError::TableNotFound => TABLE_NOT_FOUND,
+            TableError::TableAlreadyExists => TABLE_ALREADY_EXISTS,
+            TableError::SchemaMismatch => SCHEMA_MISMATCH,
+        }
+    }
+}
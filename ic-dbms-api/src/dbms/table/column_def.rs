@@ -1,4 +1,6 @@
+use crate::dbms::query::{Filter, QueryResult};
 use crate::dbms::types::DataTypeKind;
+use crate::dbms::value::Value;
 
 /// Defines a column in a database table.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -11,8 +13,214 @@ pub struct ColumnDef {
     pub nullable: bool,
     /// Indicates if this column is part of the primary key.
     pub primary_key: bool,
+    /// Indicates if the values of this column must be unique among the records of the table.
+    ///
+    /// It's meant for the columns which are not part of the primary key, which are unique anyway.
+    pub unique: bool,
+    /// Indicates if the values of this column are generated from a per-table sequence.
+    ///
+    /// An auto-increment column must be a [`DataTypeKind::AutoIncrement`], [`DataTypeKind::Uint32`]
+    /// or [`DataTypeKind::Uint64`] primary key; inserting a [`Value::AutoIncrement`] into it assigns
+    /// the next value of the sequence.
+    pub auto_increment: bool,
     /// Foreign key definition, if any.
     pub foreign_key: Option<ForeignKeyDef>,
+    /// CHECK constraint applied to the column, if any.
+    pub check: Option<CheckConstraint>,
+}
+
+impl ColumnDef {
+    /// Creates a new [`ColumnDefBuilder`].
+    pub const fn builder() -> ColumnDefBuilder {
+        ColumnDefBuilder::new()
+    }
+}
+
+/// Builder for [`ColumnDef`].
+///
+/// All the methods are `const`, so the builder can be used to define the
+/// [`crate::prelude::TableSchema::columns`] of a table.
+///
+/// ```rust
+/// use ic_dbms_api::prelude::{ColumnDef, DataTypeKind};
+///
+/// const ID: ColumnDef = ColumnDef::builder()
+///     .name("id")
+///     .data_type(DataTypeKind::Uint32)
+///     .primary_key()
+///     .build();
+/// ```
+///
+/// Since a [`ColumnDef`] is `Copy` and built in `const` context, it can't hold a [`Value`] or a [`Filter`],
+/// which own their data:
+///
+/// - there is no `default(Value)` method, since columns have no default value:
+///   an insert sets every column, with [`Value::Null`] for the nullable ones it leaves empty;
+/// - CHECK constraints take a function returning the [`Filter`], as in [`ColumnDefBuilder::check_filter`],
+///   instead of the [`Filter`] itself.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ColumnDefBuilder {
+    name: Option<&'static str>,
+    data_type: Option<DataTypeKind>,
+    nullable: bool,
+    primary_key: bool,
+    unique: bool,
+    auto_increment: bool,
+    foreign_key: Option<ForeignKeyDef>,
+    check: Option<CheckConstraint>,
+}
+
+impl Default for ColumnDefBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ColumnDefBuilder {
+    /// Creates a new builder for a non-nullable column.
+    pub const fn new() -> Self {
+        Self {
+            name: None,
+            data_type: None,
+            nullable: false,
+            primary_key: false,
+            unique: false,
+            auto_increment: false,
+            foreign_key: None,
+            check: None,
+        }
+    }
+
+    /// Sets the name of the column.
+    pub const fn name(mut self, name: &'static str) -> Self {
+        self.name = Some(name);
+        self
+    }
+
+    /// Sets the data type of the column.
+    ///
+    /// A [`DataTypeKind::AutoIncrement`] column is marked as auto-increment as well.
+    pub const fn data_type(mut self, data_type: DataTypeKind) -> Self {
+        self.data_type = Some(data_type);
+        self.auto_increment |= matches!(data_type, DataTypeKind::AutoIncrement);
+        self
+    }
+
+    /// Marks the column as nullable.
+    pub const fn nullable(mut self) -> Self {
+        self.nullable = true;
+        self
+    }
+
+    /// Marks the column as not nullable.
+    pub const fn not_nullable(mut self) -> Self {
+        self.nullable = false;
+        self
+    }
+
+    /// Marks the column as primary key.
+    pub const fn primary_key(mut self) -> Self {
+        self.primary_key = true;
+        self
+    }
+
+    /// Marks the column as unique.
+    pub const fn unique(mut self) -> Self {
+        self.unique = true;
+        self
+    }
+
+    /// Marks the column as auto-increment.
+    pub const fn auto_increment(mut self) -> Self {
+        self.auto_increment = true;
+        self
+    }
+
+    /// Sets the foreign key definition of the column.
+    pub const fn foreign_key(mut self, foreign_key: ForeignKeyDef) -> Self {
+        self.foreign_key = Some(foreign_key);
+        self
+    }
+
+    /// Sets the CHECK constraint of the column.
+    pub const fn check(mut self, check: CheckConstraint) -> Self {
+        self.check = Some(check);
+        self
+    }
+
+    /// Sets a CHECK constraint on the record values, which must match the [`Filter`] returned by `filter`.
+    pub const fn check_filter(mut self, description: &'static str, filter: fn() -> Filter) -> Self {
+        self.check = Some(CheckConstraint::filter(description, filter));
+        self
+    }
+
+    /// Sets a CHECK constraint on the value of the column, which must satisfy `predicate`.
+    pub const fn check_value(
+        mut self,
+        description: &'static str,
+        predicate: fn(&Value) -> bool,
+    ) -> Self {
+        self.check = Some(CheckConstraint::predicate(description, predicate));
+        self
+    }
+
+    /// Builds the [`ColumnDef`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if either the name or the data type of the column has not been set.
+    pub const fn build(self) -> ColumnDef {
+        ColumnDef {
+            name: self.name.expect("column name must be set"),
+            data_type: self.data_type.expect("column data type must be set"),
+            nullable: self.nullable,
+            primary_key: self.primary_key,
+            unique: self.unique,
+            auto_increment: self.auto_increment,
+            foreign_key: self.foreign_key,
+            check: self.check,
+        }
+    }
+}
+
+/// Defines a [`ColumnDef`] in a compact way.
+///
+/// The data type is the name of a [`crate::prelude::DataTypeKind`] variant,
+/// and it can be followed by any of the [`ColumnDefBuilder`] flags
+/// (`primary_key`, `unique`, `auto_increment`, `nullable`, `not_nullable`) and by a foreign key.
+///
+/// ```rust
+/// use ic_dbms_api::col;
+/// use ic_dbms_api::prelude::ColumnDef;
+///
+/// const COLUMNS: &[ColumnDef] = &[
+///     col!("id", Uint32, primary_key),
+///     col!("title", Text),
+///     col!("subtitle", Text, nullable),
+///     col!("user_id", Uint32, foreign_key = ("users", "id")),
+/// ];
+/// ```
+#[macro_export]
+macro_rules! col {
+    ($name:expr, $data_type:ident, foreign_key = ($table:expr, $column:expr) $(, $flag:ident)* $(,)?) => {
+        $crate::prelude::ColumnDef::builder()
+            .name($name)
+            .data_type($crate::prelude::DataTypeKind::$data_type)
+            .foreign_key($crate::prelude::ForeignKeyDef {
+                local_column: $name,
+                foreign_table: $table,
+                foreign_column: $column,
+            })
+            $(.$flag())*
+            .build()
+    };
+    ($name:expr, $data_type:ident $(, $flag:ident)* $(,)?) => {
+        $crate::prelude::ColumnDef::builder()
+            .name($name)
+            .data_type($crate::prelude::DataTypeKind::$data_type)
+            $(.$flag())*
+            .build()
+    };
 }
 
 /// Defines a foreign key relationship for a column.
@@ -25,3 +233,509 @@ pub struct ForeignKeyDef {
     /// Name of the foreign column that the FK points to (e.g., "id")
     pub foreign_column: &'static str,
 }
+
+impl ForeignKeyDef {
+    /// Creates a new [`ForeignKeyDefBuilder`].
+    pub const fn builder() -> ForeignKeyDefBuilder {
+        ForeignKeyDefBuilder::new()
+    }
+}
+
+/// Builder for [`ForeignKeyDef`].
+///
+/// As for [`ColumnDefBuilder`], all the methods are `const`.
+///
+/// ```rust
+/// use ic_dbms_api::prelude::ForeignKeyDef;
+///
+/// const USER_FK: ForeignKeyDef = ForeignKeyDef::builder()
+///     .local_column("user_id")
+///     .foreign_table("users")
+///     .foreign_column("id")
+///     .build();
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ForeignKeyDefBuilder {
+    local_column: Option<&'static str>,
+    foreign_table: Option<&'static str>,
+    foreign_column: Option<&'static str>,
+}
+
+impl ForeignKeyDefBuilder {
+    /// Creates a new empty builder.
+    pub const fn new() -> Self {
+        Self {
+            local_column: None,
+            foreign_table: None,
+            foreign_column: None,
+        }
+    }
+
+    /// Sets the name of the local column holding the foreign key.
+    pub const fn local_column(mut self, local_column: &'static str) -> Self {
+        self.local_column = Some(local_column);
+        self
+    }
+
+    /// Sets the name of the foreign table.
+    pub const fn foreign_table(mut self, foreign_table: &'static str) -> Self {
+        self.foreign_table = Some(foreign_table);
+        self
+    }
+
+    /// Sets the name of the foreign column the foreign key points to.
+    pub const fn foreign_column(mut self, foreign_column: &'static str) -> Self {
+        self.foreign_column = Some(foreign_column);
+        self
+    }
+
+    /// Builds the [`ForeignKeyDef`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the local column, the foreign table or the foreign column has not been set.
+    pub const fn build(self) -> ForeignKeyDef {
+        ForeignKeyDef {
+            local_column: self.local_column.expect("local column must be set"),
+            foreign_table: self.foreign_table.expect("foreign table must be set"),
+            foreign_column: self.foreign_column.expect("foreign column must be set"),
+        }
+    }
+}
+
+/// Defines the `foreign_key` of a [`ColumnDef`] literal, as an `Option<ForeignKeyDef>`.
+///
+/// ```rust
+/// use ic_dbms_api::fk;
+/// use ic_dbms_api::prelude::{ColumnDef, DataTypeKind};
+///
+/// static USER_ID: ColumnDef = ColumnDef {
+///     name: "user_id",
+///     data_type: DataTypeKind::Uint32,
+///     nullable: false,
+///     primary_key: false,
+///     unique: false,
+///     auto_increment: false,
+///     foreign_key: fk!(local: "user_id", table: "users", column: "id"),
+///     check: None,
+/// };
+/// ```
+#[macro_export]
+macro_rules! fk {
+    (local: $local:expr, table: $table:expr, column: $column:expr $(,)?) => {
+        Some($crate::prelude::ForeignKeyDef {
+            local_column: $local,
+            foreign_table: $table,
+            foreign_column: $column,
+        })
+    };
+}
+
+/// Defines a CHECK constraint for a column.
+///
+/// The constraint is a [`CheckExpr`] which must be satisfied for the record to be valid,
+/// e.g. `age > 0 AND age < 150`.
+#[derive(Clone, Copy, Debug)]
+pub struct CheckConstraint {
+    /// Human readable description of the constraint (e.g. "age > 0 AND age < 150").
+    pub description: &'static str,
+    /// The expression the record must satisfy.
+    pub expr: CheckExpr,
+}
+
+/// The expression of a [`CheckConstraint`].
+///
+/// Since [`ColumnDef`] must be constructible in a `const` context, expressions are function pointers,
+/// which are `'static`, `Send` and `Sync`.
+#[derive(Clone, Copy, Debug)]
+pub enum CheckExpr {
+    /// Function returning the [`Filter`] the record values must match.
+    Filter(fn() -> Filter),
+    /// Predicate the value of the column must satisfy.
+    Predicate(fn(&Value) -> bool),
+}
+
+impl CheckConstraint {
+    /// Creates a constraint on the record values, which must match the [`Filter`] returned by `filter`.
+    pub const fn filter(description: &'static str, filter: fn() -> Filter) -> Self {
+        Self {
+            description,
+            expr: CheckExpr::Filter(filter),
+        }
+    }
+
+    /// Creates a constraint on the value of the column, which must satisfy `predicate`.
+    pub const fn predicate(description: &'static str, predicate: fn(&Value) -> bool) -> Self {
+        Self {
+            description,
+            expr: CheckExpr::Predicate(predicate),
+        }
+    }
+
+    /// Checks whether the given value of the column, along with the values of its record,
+    /// satisfies the constraint.
+    pub fn check(&self, value: &Value, values: &[(ColumnDef, Value)]) -> QueryResult<bool> {
+        match self.expr {
+            CheckExpr::Filter(filter) => filter().matches(values),
+            CheckExpr::Predicate(predicate) => Ok(predicate(value)),
+        }
+    }
+}
+
+impl PartialEq for CheckConstraint {
+    fn eq(&self, other: &Self) -> bool {
+        self.description == other.description
+    }
+}
+
+impl Eq for CheckConstraint {}
+
+/// Defines a computed column, whose value is not stored but evaluated from the other columns on read.
+///
+/// Computed columns can be selected with [`crate::prelude::Query`], but can't be used in a [`Filter`],
+/// since filters are evaluated against the stored values only.
+#[derive(Clone, Copy, Debug)]
+pub struct ComputedColumnDef {
+    /// The name of the column.
+    pub name: &'static str,
+    /// The data type of the computed value.
+    pub data_type: DataTypeKind,
+    /// Function computing the value of the column from the stored values of the record.
+    pub expression: fn(&[(ColumnDef, Value)]) -> Value,
+}
+
+impl ComputedColumnDef {
+    /// Returns the nullable [`ColumnDef`] describing the computed column.
+    pub const fn column_def(&self) -> ColumnDef {
+        ColumnDef {
+            name: self.name,
+            data_type: self.data_type,
+            nullable: true,
+            primary_key: false,
+            unique: false,
+            auto_increment: false,
+            foreign_key: None,
+            check: None,
+        }
+    }
+
+    /// Evaluates the column for the given record values.
+    pub fn evaluate(&self, values: &[(ColumnDef, Value)]) -> (ColumnDef, Value) {
+        (self.column_def(), (self.expression)(values))
+    }
+}
+
+impl PartialEq for ComputedColumnDef {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.data_type == other.data_type
+    }
+}
+
+impl Eq for ComputedColumnDef {}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::dbms::types::Int32;
+
+    fn age_check() -> Filter {
+        Filter::gt("age", Value::Int32(0.into())).and(Filter::lt("age", Value::Int32(150.into())))
+    }
+
+    const AGE_COLUMN: ColumnDef = ColumnDef {
+        name: "age",
+        data_type: DataTypeKind::Int32,
+        nullable: false,
+        primary_key: false,
+        unique: false,
+        auto_increment: false,
+        foreign_key: None,
+        check: Some(CheckConstraint::filter("age > 0 AND age < 150", age_check)),
+    };
+
+    #[test]
+    fn test_should_build_column_def() {
+        const BUILT: ColumnDef = ColumnDef::builder()
+            .name("age")
+            .data_type(DataTypeKind::Int32)
+            .check(CheckConstraint::filter("age > 0 AND age < 150", age_check))
+            .build();
+        assert_eq!(BUILT, AGE_COLUMN);
+
+        let built = ColumnDef::builder()
+            .name("user_id")
+            .data_type(DataTypeKind::Uint32)
+            .nullable()
+            .foreign_key(ForeignKeyDef {
+                local_column: "user_id",
+                foreign_table: "users",
+                foreign_column: "id",
+            })
+            .build();
+        assert_eq!(
+            built,
+            ColumnDef {
+                name: "user_id",
+                data_type: DataTypeKind::Uint32,
+                nullable: true,
+                primary_key: false,
+                unique: false,
+                auto_increment: false,
+                foreign_key: Some(ForeignKeyDef {
+                    local_column: "user_id",
+                    foreign_table: "users",
+                    foreign_column: "id",
+                }),
+                check: None,
+            }
+        );
+
+        let not_nullable = ColumnDef::builder()
+            .name("name")
+            .data_type(DataTypeKind::Text)
+            .nullable()
+            .not_nullable()
+            .build();
+        assert!(!not_nullable.nullable);
+
+        let unique = ColumnDef::builder()
+            .name("email")
+            .data_type(DataTypeKind::Text)
+            .unique()
+            .build();
+        assert!(unique.unique);
+        assert!(!unique.primary_key);
+
+        let auto_increment = ColumnDef::builder()
+            .name("id")
+            .data_type(DataTypeKind::Uint64)
+            .primary_key()
+            .auto_increment()
+            .build();
+        assert!(auto_increment.auto_increment);
+        assert!(auto_increment.primary_key);
+    }
+
+    #[test]
+    #[should_panic(expected = "column name must be set")]
+    fn test_should_not_build_column_def_without_name() {
+        ColumnDef::builder().data_type(DataTypeKind::Text).build();
+    }
+
+    #[test]
+    fn test_should_define_column_with_macro() {
+        const COLUMNS: &[ColumnDef] = &[
+            crate::col!("id", Uint32, primary_key),
+            crate::col!("title", Text),
+            crate::col!("subtitle", Text, nullable),
+            crate::col!("user_id", Uint32, foreign_key = ("users", "id")),
+            crate::col!(
+                "reviewer_id",
+                Uint32,
+                foreign_key = ("users", "id"),
+                nullable
+            ),
+            crate::col!("email", Text, unique),
+        ];
+
+        assert_eq!(
+            COLUMNS[0],
+            ColumnDef {
+                name: "id",
+                data_type: DataTypeKind::Uint32,
+                nullable: false,
+                primary_key: true,
+                unique: false,
+                auto_increment: false,
+                foreign_key: None,
+                check: None,
+            }
+        );
+        assert_eq!(
+            COLUMNS[1],
+            ColumnDef {
+                name: "title",
+                data_type: DataTypeKind::Text,
+                nullable: false,
+                primary_key: false,
+                unique: false,
+                auto_increment: false,
+                foreign_key: None,
+                check: None,
+            }
+        );
+        assert_eq!(
+            COLUMNS[2],
+            ColumnDef {
+                name: "subtitle",
+                data_type: DataTypeKind::Text,
+                nullable: true,
+                primary_key: false,
+                unique: false,
+                auto_increment: false,
+                foreign_key: None,
+                check: None,
+            }
+        );
+        assert_eq!(
+            COLUMNS[3],
+            ColumnDef {
+                name: "user_id",
+                data_type: DataTypeKind::Uint32,
+                nullable: false,
+                primary_key: false,
+                unique: false,
+                auto_increment: false,
+                foreign_key: Some(ForeignKeyDef {
+                    local_column: "user_id",
+                    foreign_table: "users",
+                    foreign_column: "id",
+                }),
+                check: None,
+            }
+        );
+        assert!(COLUMNS[4].nullable);
+        assert_eq!(
+            COLUMNS[4].foreign_key.map(|fk| fk.local_column),
+            Some("reviewer_id")
+        );
+        assert!(COLUMNS[5].unique);
+    }
+
+    #[test]
+    fn test_should_build_foreign_key_def() {
+        const BUILT: ForeignKeyDef = ForeignKeyDef::builder()
+            .local_column("user_id")
+            .foreign_table("users")
+            .foreign_column("id")
+            .build();
+        let expected = ForeignKeyDef {
+            local_column: "user_id",
+            foreign_table: "users",
+            foreign_column: "id",
+        };
+        assert_eq!(BUILT, expected);
+        assert_eq!(
+            crate::fk!(local: "user_id", table: "users", column: "id"),
+            Some(expected)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "foreign table must be set")]
+    fn test_should_not_build_foreign_key_def_without_table() {
+        ForeignKeyDef::builder()
+            .local_column("user_id")
+            .foreign_column("id")
+            .build();
+    }
+
+    #[test]
+    fn test_should_define_foreign_key_with_macro_in_static() {
+        static COLUMNS: [ColumnDef; 2] = [
+            ColumnDef {
+                name: "id",
+                data_type: DataTypeKind::Uint32,
+                nullable: false,
+                primary_key: true,
+                unique: false,
+                auto_increment: false,
+                foreign_key: None,
+                check: None,
+            },
+            ColumnDef {
+                name: "user_id",
+                data_type: DataTypeKind::Uint32,
+                nullable: false,
+                primary_key: false,
+                unique: false,
+                auto_increment: false,
+                foreign_key: crate::fk!(local: "user_id", table: "users", column: "id"),
+                check: None,
+            },
+        ];
+
+        assert_eq!(
+            COLUMNS[1].foreign_key,
+            Some(ForeignKeyDef {
+                local_column: "user_id",
+                foreign_table: "users",
+                foreign_column: "id",
+            })
+        );
+    }
+
+    #[test]
+    fn test_should_check_constraint() {
+        let check = AGE_COLUMN.check.expect("should have check");
+        let is_valid = |age: i32| {
+            let value = Value::Int32(Int32(age));
+            check
+                .check(&value, &[(AGE_COLUMN, value.clone())])
+                .expect("should check")
+        };
+
+        assert!(is_valid(42));
+        assert!(!is_valid(-1));
+        assert!(!is_valid(200));
+    }
+
+    #[test]
+    fn test_should_build_column_def_with_check_filter() {
+        const BUILT: ColumnDef = ColumnDef::builder()
+            .name("age")
+            .data_type(DataTypeKind::Int32)
+            .check_filter("age > 0 AND age < 150", age_check)
+            .build();
+        assert_eq!(BUILT, AGE_COLUMN);
+        let check = BUILT.check.expect("should have check");
+        assert!(matches!(check.expr, CheckExpr::Filter(_)));
+    }
+
+    #[test]
+    fn test_should_check_value_predicate() {
+        const SCORE: ColumnDef = ColumnDef::builder()
+            .name("score")
+            .data_type(DataTypeKind::Int32)
+            .check_value(
+                "score BETWEEN 0 AND 100",
+                |value| matches!(value, Value::Int32(Int32(score)) if (0..=100).contains(score)),
+            )
+            .build();
+        let check = SCORE.check.expect("should have check");
+        assert!(matches!(check.expr, CheckExpr::Predicate(_)));
+        assert_eq!(check.description, "score BETWEEN 0 AND 100");
+
+        for (score, expected) in [(0, true), (100, true), (-1, false), (101, false)] {
+            // the predicate only sees the value of the column
+            assert_eq!(
+                check
+                    .check(&Value::Int32(Int32(score)), &[])
+                    .expect("should check"),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_should_evaluate_computed_column() {
+        fn double_age(values: &[(ColumnDef, Value)]) -> Value {
+            match values.iter().find(|(column, _)| column.name == "age") {
+                Some((_, Value::Int32(age))) => Value::Int32(Int32(age.0 * 2)),
+                _ => Value::Null,
+            }
+        }
+
+        let computed = ComputedColumnDef {
+            name: "double_age",
+            data_type: DataTypeKind::Int32,
+            expression: double_age,
+        };
+        let (column, value) = computed.evaluate(&[(AGE_COLUMN, Value::Int32(Int32(21)))]);
+        assert_eq!(column.name, "double_age");
+        assert!(column.nullable);
+        assert_eq!(value, Value::Int32(Int32(42)));
+        assert_eq!(computed.evaluate(&[]).1, Value::Null);
+    }
+}
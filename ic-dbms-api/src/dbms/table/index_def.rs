@@ -0,0 +1,52 @@
+/// Defines an index on one or more columns of a database table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct IndexDef {
+    /// The name of the index.
+    pub name: &'static str,
+    /// The indexed columns, in order.
+    pub columns: &'static [&'static str],
+    /// Indicates if the indexed values must be unique.
+    pub unique: bool,
+    /// The [`IndexType`] of the index.
+    pub index_type: IndexType,
+}
+
+/// The data structure backing an [`IndexDef`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum IndexType {
+    /// Ordered index, supporting both equality and range lookups.
+    #[default]
+    BTree,
+    /// Hash index, supporting only equality lookups.
+    Hash,
+    /// Full-text index on text columns.
+    FullText,
+}
+
+impl IndexDef {
+    /// Returns whether the given column is the leading column of the index,
+    /// so that the index can be used to look up a filter on that column.
+    pub fn is_leading_column(&self, column: &str) -> bool {
+        self.columns.first().is_some_and(|first| *first == column)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_should_check_leading_column() {
+        let index = IndexDef {
+            name: "idx_bookings_dates",
+            columns: &["start_date", "end_date"],
+            unique: false,
+            index_type: IndexType::BTree,
+        };
+
+        assert!(index.is_leading_column("start_date"));
+        assert!(!index.is_leading_column("end_date"));
+        assert!(!index.is_leading_column("id"));
+    }
+}
@@ -43,7 +43,10 @@ pub trait InsertRecord: Sized + Clone {
     fn into_values(self) -> Vec<(ColumnDef, Value)>;
 
     /// Converts the insert record into the corresponding table record.
-    fn into_record(self) -> Self::Schema;
+    ///
+    /// Fails if the record can't be built from the insert record, e.g. because a generated value,
+    /// such as a [`Value::AutoIncrement`] one, has not been assigned yet.
+    fn into_record(self) -> IcDbmsResult<Self::Schema>;
 }
 
 /// This trait represents a record for updating a table.
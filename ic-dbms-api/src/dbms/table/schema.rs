@@ -1,8 +1,10 @@
 use std::hash::{Hash as _, Hasher as _};
 
 use crate::dbms::foreign_fetcher::ForeignFetcher;
-use crate::dbms::table::column_def::ColumnDef;
+use crate::dbms::table::column_def::{ColumnDef, ComputedColumnDef};
+use crate::dbms::table::index_def::IndexDef;
 use crate::dbms::table::{InsertRecord, TableRecord, UpdateRecord};
+use crate::dbms::types::DataTypeKind;
 use crate::memory::Encode;
 
 /// A type representing a unique fingerprint for a table schema.
@@ -25,14 +27,51 @@ where
     /// The [`ForeignFetcher`] type associated with this table schema.
     type ForeignFetcher: ForeignFetcher;
 
+    /// Name of the `deleted_at` column if the table supports soft-deletion, see [`SoftDeleteSchema`].
+    ///
+    /// Soft-deleted records, whose column is not NULL, are excluded from selects by default.
+    const SOFT_DELETE_COLUMN: Option<&'static str> = None;
+
     /// Returns the name of the table.
     fn table_name() -> &'static str;
 
     /// Returns the column definitions of the table.
     fn columns() -> &'static [ColumnDef];
 
+    /// Returns the names of the primary key columns.
+    ///
+    /// A table with a composite primary key returns more than one column;
+    /// the list must never be empty.
+    fn primary_keys() -> &'static [&'static str];
+
     /// Returns the name of the primary key column.
-    fn primary_key() -> &'static str;
+    ///
+    /// For tables with a composite primary key, it's the first of the [`TableSchema::primary_keys`].
+    fn primary_key() -> &'static str {
+        Self::primary_keys()[0]
+    }
+
+    /// Returns the index definitions of the table.
+    ///
+    /// By default a table has no indexes.
+    fn indexes() -> &'static [IndexDef] {
+        &[]
+    }
+
+    /// Returns the computed columns of the table, which are evaluated on read.
+    ///
+    /// By default a table has no computed columns.
+    fn computed_columns() -> &'static [ComputedColumnDef] {
+        &[]
+    }
+
+    /// Returns whether the records of the table are compressed with LZ4 when stored in memory.
+    ///
+    /// Records are compressed only with the `compression` feature of the canister, and only if it makes them smaller.
+    /// By default records are not compressed.
+    fn use_compression() -> bool {
+        false
+    }
 
     /// Converts itself into a vector of column-value pairs.
     fn to_values(self) -> Vec<(ColumnDef, crate::dbms::value::Value)>;
@@ -48,4 +87,172 @@ where
         std::any::TypeId::of::<Self>().hash(&mut hasher);
         hasher.finish()
     }
+
+    /// Returns the fingerprint of the column definitions of the table schema.
+    ///
+    /// Unlike [`TableSchema::fingerprint`], it is stable across builds,
+    /// so it can be stored to detect schema changes after an upgrade.
+    fn columns_fingerprint() -> u64 {
+        columns_fingerprint(Self::columns())
+    }
+}
+
+/// Computes a fingerprint of the given column definitions, which is stable across builds.
+///
+/// The fingerprint is a 64-bit FNV-1a hash of the name, type, nullability, primary key,
+/// foreign key and check constraint of each column.
+pub fn columns_fingerprint(columns: &[ColumnDef]) -> u64 {
+    let mut fingerprint = ColumnsFingerprint::default();
+    for column in columns {
+        fingerprint.column(
+            column.name,
+            column.data_type,
+            column.nullable,
+            column.primary_key,
+        );
+        if let Some(fk) = column.foreign_key {
+            fingerprint.foreign_key(fk.local_column, fk.foreign_table, fk.foreign_column);
+        }
+        if let Some(check) = column.check {
+            fingerprint.check(check.description);
+        }
+    }
+
+    fingerprint.finish()
+}
+
+/// Incremental computation of [`columns_fingerprint`], for columns which are not available as [`ColumnDef`]s,
+/// such as the columns stored in memory.
+///
+/// The properties of each column must be written in the same order as [`columns_fingerprint`] does:
+/// [`ColumnsFingerprint::column`], then [`ColumnsFingerprint::foreign_key`] and [`ColumnsFingerprint::check`] if any.
+#[derive(Debug, Clone, Copy)]
+pub struct ColumnsFingerprint {
+    hash: u64,
+}
+
+impl Default for ColumnsFingerprint {
+    fn default() -> Self {
+        Self {
+            hash: Self::FNV_OFFSET_BASIS,
+        }
+    }
+}
+
+impl ColumnsFingerprint {
+    const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    /// Writes the name, type, nullability and primary key of a column.
+    pub fn column(
+        &mut self,
+        name: &str,
+        data_type: DataTypeKind,
+        nullable: bool,
+        primary_key: bool,
+    ) {
+        self.write(name.as_bytes());
+        self.write(format!("{data_type:?}").as_bytes());
+        self.write(&[nullable as u8, primary_key as u8]);
+    }
+
+    /// Writes the foreign key of the last written column.
+    pub fn foreign_key(&mut self, local_column: &str, foreign_table: &str, foreign_column: &str) {
+        self.write(local_column.as_bytes());
+        self.write(foreign_table.as_bytes());
+        self.write(foreign_column.as_bytes());
+    }
+
+    /// Writes the description of the check constraint of the last written column.
+    pub fn check(&mut self, description: &str) {
+        self.write(description.as_bytes());
+    }
+
+    /// Returns the fingerprint of the written columns.
+    pub fn finish(&self) -> u64 {
+        self.hash
+    }
+
+    /// Writes the given bytes, followed by a separator.
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes.iter().chain(std::iter::once(&0xff)) {
+            self.hash ^= *byte as u64;
+            self.hash = self.hash.wrapping_mul(Self::FNV_PRIME);
+        }
+    }
+}
+
+/// Marker trait for tables supporting soft-deletion, which must set [`TableSchema::SOFT_DELETE_COLUMN`].
+///
+/// Instead of physically removing records, soft-deleted records have their `deleted_at`
+/// column set to the deletion timestamp, and they are filtered out from selects,
+/// unless the query explicitly requests to include them.
+pub trait SoftDeleteSchema: TableSchema {
+    /// Returns the name of the nullable [`crate::prelude::DateTime`] column
+    /// storing the deletion timestamp, which is the [`TableSchema::SOFT_DELETE_COLUMN`].
+    ///
+    /// Using it on a table without a [`TableSchema::SOFT_DELETE_COLUMN`] fails to compile.
+    fn deleted_at_column() -> &'static str {
+        const {
+            Self::SOFT_DELETE_COLUMN
+                .expect("a SoftDeleteSchema table must set TableSchema::SOFT_DELETE_COLUMN")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::tests::User;
+
+    #[test]
+    fn test_should_compute_stable_columns_fingerprint() {
+        assert_eq!(User::columns_fingerprint(), User::columns_fingerprint());
+        assert_eq!(
+            User::columns_fingerprint(),
+            columns_fingerprint(User::columns())
+        );
+    }
+
+    #[test]
+    fn test_should_change_columns_fingerprint_when_columns_change() {
+        let mut columns = User::columns().to_vec();
+        columns.push(ColumnDef {
+            name: "email",
+            data_type: DataTypeKind::Text,
+            nullable: true,
+            primary_key: false,
+            unique: false,
+            auto_increment: false,
+            foreign_key: None,
+            check: None,
+        });
+        assert_ne!(User::columns_fingerprint(), columns_fingerprint(&columns));
+
+        let mut columns = User::columns().to_vec();
+        columns[1].nullable = !columns[1].nullable;
+        assert_ne!(User::columns_fingerprint(), columns_fingerprint(&columns));
+    }
+
+    #[test]
+    fn test_should_compute_columns_fingerprint_incrementally() {
+        let mut fingerprint = ColumnsFingerprint::default();
+        for column in User::columns() {
+            fingerprint.column(
+                column.name,
+                column.data_type,
+                column.nullable,
+                column.primary_key,
+            );
+            if let Some(fk) = column.foreign_key {
+                fingerprint.foreign_key(fk.local_column, fk.foreign_table, fk.foreign_column);
+            }
+            if let Some(check) = column.check {
+                fingerprint.check(check.description);
+            }
+        }
+
+        assert_eq!(fingerprint.finish(), User::columns_fingerprint());
+    }
 }
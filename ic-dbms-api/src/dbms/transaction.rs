@@ -1,9 +1,35 @@
 /// Type alias for Transaction ID
 pub type TransactionId = candid::Nat;
 
+/// Type alias for the ID of a savepoint within a transaction.
+pub type SavepointId = u64;
+
 /// An enum representing possible errors that can occur during transaction operations.
 #[derive(Debug, thiserror::Error)]
 pub enum TransactionError {
     #[error("No active transaction")]
     NoActiveTransaction,
+    #[error("Caller {caller} is not authorized to operate on the transaction owned by {expected}")]
+    Unauthorized {
+        expected: candid::Principal,
+        caller: candid::Principal,
+    },
+    #[error("Savepoint {0} not found")]
+    SavepointNotFound(SavepointId),
+    #[error("Read-only transactions can't perform write operations")]
+    ReadOnlyTransaction,
+}
+
+impl TransactionError {
+    /// Returns the stable code of the error, as defined in [`crate::error::error_codes`].
+    pub fn error_code(&self) -> u32 {
+        use crate::error::error_codes::*;
+
+        match self {
+            TransactionError::NoActiveTransaction => NO_ACTIVE_TRANSACTION,
+            TransactionError::Unauthorized { .. } => UNAUTHORIZED,
+            TransactionError::SavepointNotFound(_) => SAVEPOINT_NOT_FOUND,
+            TransactionError::ReadOnlyTransaction => READ_ONLY_TRANSACTION,
+        }
+    }
 }
@@ -4,13 +4,15 @@ use candid::CandidType;
 use serde::{Deserialize, Serialize};
 
 use crate::dbms::value::Value;
-use crate::memory::Encode;
+use crate::memory::{DataSize, Encode};
 
+mod array;
 mod blob;
 mod boolean;
 mod date;
 mod datetime;
 mod decimal;
+mod float64;
 mod int32;
 mod int64;
 mod nullable;
@@ -20,11 +22,13 @@ mod uint32;
 mod uint64;
 mod uuid;
 
+pub use self::array::Array;
 pub use self::blob::Blob;
 pub use self::boolean::Boolean;
-pub use self::date::Date;
+pub use self::date::{Date, DateError};
 pub use self::datetime::DateTime;
-pub use self::decimal::Decimal;
+pub use self::decimal::{Decimal, DecimalParseError};
+pub use self::float64::Float64;
 pub use self::int32::Int32;
 pub use self::int64::Int64;
 pub use self::nullable::Nullable;
@@ -61,11 +65,18 @@ pub trait DataType:
 /// An enumeration of all supported data type kinds in the DBMS.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum DataTypeKind {
+    /// An [`Array`] of values of the given type; see [`DataTypeKind::array_of`].
+    Array(&'static DataTypeKind),
+    /// An unsigned 64 bits integer generated by the sequence of the table, whose values are [`Uint64`]s.
+    ///
+    /// Columns of this type are auto-increment ones; see [`crate::prelude::ColumnDef::auto_increment`].
+    AutoIncrement,
     Blob,
     Boolean,
     Date,
     DateTime,
     Decimal,
+    Float64,
     Int32,
     Int64,
     Principal,
@@ -74,3 +85,82 @@ pub enum DataTypeKind {
     Uint64,
     Uuid,
 }
+
+impl DataTypeKind {
+    /// Returns the [`DataTypeKind::Array`] of values of the given type.
+    ///
+    /// Returns `None` if the given type is an array, since arrays can only hold scalar values.
+    pub fn array_of(element: DataTypeKind) -> Option<DataTypeKind> {
+        let element: &'static DataTypeKind = match element {
+            // arrays of generated values can't be inserted
+            DataTypeKind::Array(_) | DataTypeKind::AutoIncrement => return None,
+            DataTypeKind::Blob => &DataTypeKind::Blob,
+            DataTypeKind::Boolean => &DataTypeKind::Boolean,
+            DataTypeKind::Date => &DataTypeKind::Date,
+            DataTypeKind::DateTime => &DataTypeKind::DateTime,
+            DataTypeKind::Decimal => &DataTypeKind::Decimal,
+            DataTypeKind::Float64 => &DataTypeKind::Float64,
+            DataTypeKind::Int32 => &DataTypeKind::Int32,
+            DataTypeKind::Int64 => &DataTypeKind::Int64,
+            DataTypeKind::Principal => &DataTypeKind::Principal,
+            DataTypeKind::Text => &DataTypeKind::Text,
+            DataTypeKind::Uint32 => &DataTypeKind::Uint32,
+            DataTypeKind::Uint64 => &DataTypeKind::Uint64,
+            DataTypeKind::Uuid => &DataTypeKind::Uuid,
+        };
+
+        Some(DataTypeKind::Array(element))
+    }
+
+    /// Returns the name of the type, as returned by [`Value::type_name`] for its values.
+    pub fn to_value_type_name(&self) -> &'static str {
+        match self {
+            DataTypeKind::Array(_) => "Array",
+            DataTypeKind::AutoIncrement => "Uint64",
+            DataTypeKind::Blob => "Blob",
+            DataTypeKind::Boolean => "Boolean",
+            DataTypeKind::Date => "Date",
+            DataTypeKind::DateTime => "DateTime",
+            DataTypeKind::Decimal => "Decimal",
+            DataTypeKind::Float64 => "Float64",
+            DataTypeKind::Int32 => "Int32",
+            DataTypeKind::Int64 => "Int64",
+            DataTypeKind::Principal => "Principal",
+            DataTypeKind::Text => "Text",
+            DataTypeKind::Uint32 => "Uint32",
+            DataTypeKind::Uint64 => "Uint64",
+            DataTypeKind::Uuid => "Uuid",
+        }
+    }
+
+    /// Returns the type of the values of the type, as returned by [`Value::kind`].
+    ///
+    /// It's [`DataTypeKind::Uint64`] for [`DataTypeKind::AutoIncrement`] and the type itself otherwise.
+    pub fn value_kind(&self) -> DataTypeKind {
+        match self {
+            DataTypeKind::AutoIncrement => DataTypeKind::Uint64,
+            data_type => *data_type,
+        }
+    }
+
+    /// Returns the [`DataSize`] of the encoded values of the type.
+    pub fn encoded_size(&self) -> DataSize {
+        match self {
+            DataTypeKind::Array(_) => DataSize::Dynamic,
+            DataTypeKind::AutoIncrement => Uint64::SIZE,
+            DataTypeKind::Blob => Blob::SIZE,
+            DataTypeKind::Boolean => Boolean::SIZE,
+            DataTypeKind::Date => Date::SIZE,
+            DataTypeKind::DateTime => DateTime::SIZE,
+            DataTypeKind::Decimal => Decimal::SIZE,
+            DataTypeKind::Float64 => Float64::SIZE,
+            DataTypeKind::Int32 => Int32::SIZE,
+            DataTypeKind::Int64 => Int64::SIZE,
+            DataTypeKind::Principal => Principal::SIZE,
+            DataTypeKind::Text => Text::SIZE,
+            DataTypeKind::Uint32 => Uint32::SIZE,
+            DataTypeKind::Uint64 => Uint64::SIZE,
+            DataTypeKind::Uuid => Uuid::SIZE,
+        }
+    }
+}
@@ -0,0 +1,227 @@
+use std::fmt;
+
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+use crate::dbms::types::DataType;
+use crate::dbms::value::Value;
+use crate::memory::{DataSize, Encode, MSize, MemoryResult};
+
+/// Array data type for the DBMS.
+///
+/// It holds an ordered collection of values of another [`DataType`] T,
+/// and it's encoded as the `u32` amount of values followed by the encoded values.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(bound(deserialize = "T: DataType"))]
+pub struct Array<T>(pub Vec<T>)
+where
+    T: DataType;
+
+impl<T> Array<T>
+where
+    T: DataType,
+{
+    /// Returns the amount of values in the array.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Checks if the array has no values.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Checks if the array contains the given value.
+    pub fn contains(&self, value: &T) -> bool {
+        self.0.contains(value)
+    }
+}
+
+impl<T> Default for Array<T>
+where
+    T: DataType,
+{
+    fn default() -> Self {
+        Self(Vec::new())
+    }
+}
+
+impl<T> fmt::Display for Array<T>
+where
+    T: DataType,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[")?;
+        for (index, value) in self.0.iter().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{value}")?;
+        }
+        write!(f, "]")
+    }
+}
+
+impl<T> CandidType for Array<T>
+where
+    T: DataType,
+{
+    fn _ty() -> candid::types::Type {
+        Vec::<T>::ty()
+    }
+
+    fn idl_serialize<S>(&self, serializer: S) -> Result<(), S::Error>
+    where
+        S: candid::types::Serializer,
+    {
+        self.0.idl_serialize(serializer)
+    }
+}
+
+impl<T> Encode for Array<T>
+where
+    T: DataType,
+{
+    const SIZE: DataSize = DataSize::Dynamic;
+
+    fn size(&self) -> MSize {
+        self.0.size()
+    }
+
+    fn encode(&'_ self) -> std::borrow::Cow<'_, [u8]> {
+        self.0.encode()
+    }
+
+    fn decode(data: std::borrow::Cow<[u8]>) -> MemoryResult<Self>
+    where
+        Self: Sized,
+    {
+        Vec::<T>::decode(data).map(Self)
+    }
+}
+
+impl<T> From<Vec<T>> for Array<T>
+where
+    T: DataType,
+{
+    fn from(values: Vec<T>) -> Self {
+        Self(values)
+    }
+}
+
+impl<T> From<Array<T>> for Value
+where
+    T: DataType,
+{
+    fn from(array: Array<T>) -> Self {
+        Value::Array(array.0.into_iter().map(Into::into).collect())
+    }
+}
+
+/// Converts a [`Value::Array`] into an [`Array`], giving back the value if it's not an array,
+/// or the first element which doesn't have the type of the array.
+impl<T> TryFrom<Value> for Array<T>
+where
+    T: DataType + TryFrom<Value, Error = Value>,
+{
+    type Error = Value;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        let Value::Array(values) = value else {
+            return Err(value);
+        };
+
+        values
+            .into_iter()
+            .map(T::try_from)
+            .collect::<Result<Vec<_>, _>>()
+            .map(Self)
+    }
+}
+
+impl<T> DataType for Array<T> where T: DataType {}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::dbms::types::{Nullable, Text, Uint32};
+
+    fn roundtrip(array: Array<Uint32>) {
+        let encoded = array.encode();
+        assert_eq!(encoded.len(), array.size() as usize);
+        let decoded = Array::<Uint32>::decode(encoded).unwrap();
+        assert_eq!(decoded, array);
+    }
+
+    #[test]
+    fn test_should_encode_decode_empty_array() {
+        let array = Array::<Uint32>::default();
+        assert_eq!(array.encode().as_ref(), &[0, 0, 0, 0]);
+        roundtrip(array);
+    }
+
+    #[test]
+    fn test_should_encode_decode_single_element_array() {
+        let array = Array(vec![Uint32(42)]);
+        assert_eq!(array.encode().as_ref(), &[1, 0, 0, 0, 42, 0, 0, 0]);
+        roundtrip(array);
+    }
+
+    #[test]
+    fn test_should_encode_decode_many_elements_array() {
+        roundtrip(Array((0..1000).map(Uint32).collect()));
+    }
+
+    #[test]
+    fn test_should_encode_decode_array_of_dynamic_values() {
+        let array = Array(vec![Text("foo".to_string()), Text(String::new())]);
+        let decoded = Array::<Text>::decode(array.encode()).unwrap();
+        assert_eq!(decoded, array);
+    }
+
+    #[test]
+    fn test_should_encode_decode_nullable_array() {
+        for nullable in [
+            Nullable::Null,
+            Nullable::Value(Array(vec![Uint32(1), Uint32(2)])),
+        ] {
+            let decoded = Nullable::<Array<Uint32>>::decode(nullable.encode()).unwrap();
+            assert_eq!(decoded, nullable);
+        }
+    }
+
+    #[test]
+    fn test_should_convert_array_into_value_and_back() {
+        let array = Array(vec![Uint32(1), Uint32(2)]);
+        let value = Value::from(array.clone());
+        assert_eq!(
+            value,
+            Value::Array(vec![Value::Uint32(Uint32(1)), Value::Uint32(Uint32(2))])
+        );
+        assert_eq!(Array::<Uint32>::try_from(value), Ok(array));
+
+        assert_eq!(
+            Array::<Uint32>::try_from(Value::Array(vec![Value::Null])),
+            Err(Value::Null)
+        );
+        assert_eq!(
+            Array::<Uint32>::try_from(Value::Uint32(Uint32(1))),
+            Err(Value::Uint32(Uint32(1)))
+        );
+    }
+
+    #[test]
+    fn test_should_display_array() {
+        assert_eq!(Array(vec![Uint32(1), Uint32(2)]).to_string(), "[1, 2]");
+        assert_eq!(Array::<Uint32>::default().to_string(), "[]");
+    }
+
+    #[test]
+    fn test_should_candid_encode_decode() {
+        let src = Array(vec![Uint32(1), Uint32(2)]);
+        let buf = candid::encode_one(&src).expect("Candid encoding failed");
+        let decoded: Array<Uint32> = candid::decode_one(&buf).expect("Candid decoding failed");
+        assert_eq!(src, decoded);
+    }
+}
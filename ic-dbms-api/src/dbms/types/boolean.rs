@@ -28,6 +28,13 @@ impl Encode for Boolean {
     }
 
     fn decode(data: std::borrow::Cow<[u8]>) -> crate::memory::MemoryResult<Self>
+    where
+        Self: Sized,
+    {
+        Self::decode_ref(&data)
+    }
+
+    fn decode_ref(data: &[u8]) -> crate::memory::MemoryResult<Self>
     where
         Self: Sized,
     {
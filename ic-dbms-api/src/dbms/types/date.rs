@@ -2,6 +2,7 @@ use core::fmt;
 
 use candid::CandidType;
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
 use crate::dbms::types::DataType;
 use crate::memory::{DataSize, Encode};
@@ -16,6 +17,114 @@ pub struct Date {
     pub day: u8,
 }
 
+/// An error raised building an invalid [`Date`].
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+pub enum DateError {
+    /// The year can't be represented by a [`Date`].
+    #[error("year {0} out of range")]
+    YearOutOfRange(i32),
+    /// The month is not between 1 and 12.
+    #[error("invalid month {0}")]
+    InvalidMonth(u8),
+    /// The day doesn't exist in the given month.
+    #[error("invalid day {day} for {year:04}-{month:02}")]
+    InvalidDay { year: i32, month: u8, day: u8 },
+}
+
+impl Date {
+    /// Creates a [`Date`] from its year, month and day, checking that the date exists.
+    pub fn from_ymd(year: i32, month: u8, day: u8) -> Result<Date, DateError> {
+        let year_u16 = u16::try_from(year).map_err(|_| DateError::YearOutOfRange(year))?;
+        if !(1..=12).contains(&month) {
+            return Err(DateError::InvalidMonth(month));
+        }
+        if day == 0 || day > days_in_month(year_u16, month) {
+            return Err(DateError::InvalidDay { year, month, day });
+        }
+
+        Ok(Date {
+            year: year_u16,
+            month,
+            day,
+        })
+    }
+
+    /// Creates a [`Date`] from the amount of days since the Unix epoch (1970-01-01),
+    /// negative for dates before it.
+    ///
+    /// Fails with [`DateError::YearOutOfRange`] if the resulting year is out of the range of a [`Date`].
+    pub fn from_days_since_epoch(days: i64) -> Result<Date, DateError> {
+        // civil from days; see <http://howardhinnant.github.io/date_algorithms.html#civil_from_days>
+        // saturating keeps the year out of range on overflow
+        let z = days.saturating_add(719_468);
+        let era = z.div_euclid(146_097);
+        let doe = z.rem_euclid(146_097);
+        let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u8;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u8;
+        let year = yoe + era * 400 + i64::from(month <= 2);
+
+        let year = u16::try_from(year).map_err(|_| {
+            DateError::YearOutOfRange(year.clamp(i32::MIN.into(), i32::MAX.into()) as i32)
+        })?;
+
+        Ok(Date { year, month, day })
+    }
+
+    /// Returns the year of the date.
+    pub fn year(&self) -> i32 {
+        i32::from(self.year)
+    }
+
+    /// Returns the month of the date, from 1 to 12.
+    pub fn month(&self) -> u8 {
+        self.month
+    }
+
+    /// Returns the day of the month of the date, from 1 to 31.
+    pub fn day(&self) -> u8 {
+        self.day
+    }
+
+    /// Returns the date `n` days after this one, or before it if `n` is negative.
+    ///
+    /// Fails with [`DateError::YearOutOfRange`] if the resulting year is out of the range of a [`Date`].
+    pub fn add_days(&self, n: i64) -> Result<Date, DateError> {
+        Date::from_days_since_epoch(self.days_since_epoch().saturating_add(n))
+    }
+
+    /// Returns the amount of days since the Unix epoch (1970-01-01), negative for dates before it.
+    pub fn days_since_epoch(&self) -> i64 {
+        // days from civil; see <http://howardhinnant.github.io/date_algorithms.html#days_from_civil>
+        let month = i64::from(self.month);
+        let year = i64::from(self.year) - i64::from(month <= 2);
+        let era = year.div_euclid(400);
+        let yoe = year.rem_euclid(400);
+        let mp = if month > 2 { month - 3 } else { month + 9 };
+        let doy = (153 * mp + 2) / 5 + i64::from(self.day) - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+
+        era * 146_097 + doe - 719_468
+    }
+}
+
+/// Returns whether `year` is a leap year.
+fn is_leap_year(year: u16) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+/// Returns the amount of days in the given month of `year`.
+fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
 impl fmt::Display for Date {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
@@ -40,6 +149,13 @@ impl Encode for Date {
     }
 
     fn decode(data: std::borrow::Cow<[u8]>) -> crate::memory::MemoryResult<Self>
+    where
+        Self: Sized,
+    {
+        Self::decode_ref(&data)
+    }
+
+    fn decode_ref(data: &[u8]) -> crate::memory::MemoryResult<Self>
     where
         Self: Sized,
     {
@@ -96,6 +212,84 @@ mod tests {
         assert_eq!(src, decoded);
     }
 
+    #[test]
+    fn test_should_validate_dates_from_ymd() {
+        let date = Date::from_ymd(2024, 2, 29).expect("2024 is a leap year");
+        assert_eq!(date.year(), 2024);
+        assert_eq!(date.month(), 2);
+        assert_eq!(date.day(), 29);
+
+        assert_eq!(
+            Date::from_ymd(2023, 2, 29),
+            Err(DateError::InvalidDay {
+                year: 2023,
+                month: 2,
+                day: 29
+            })
+        );
+        assert!(Date::from_ymd(2000, 2, 29).is_ok());
+        assert!(Date::from_ymd(1900, 2, 29).is_err());
+        assert_eq!(
+            Date::from_ymd(2024, 13, 1),
+            Err(DateError::InvalidMonth(13))
+        );
+        assert!(Date::from_ymd(2024, 4, 31).is_err());
+        assert!(Date::from_ymd(2024, 1, 0).is_err());
+        assert_eq!(Date::from_ymd(-1, 1, 1), Err(DateError::YearOutOfRange(-1)));
+    }
+
+    #[test]
+    fn test_should_convert_days_since_epoch() {
+        let epoch = Date::from_ymd(1970, 1, 1).expect("invalid date");
+        assert_eq!(epoch.days_since_epoch(), 0);
+        assert_eq!(Date::from_days_since_epoch(0), Ok(epoch));
+
+        let date = Date::from_ymd(2024, 2, 29).expect("invalid date");
+        assert_eq!(date.days_since_epoch(), 19_782);
+        assert_eq!(Date::from_days_since_epoch(19_782), Ok(date));
+
+        let before_epoch = Date::from_ymd(1969, 12, 31).expect("invalid date");
+        assert_eq!(before_epoch.days_since_epoch(), -1);
+        assert_eq!(Date::from_days_since_epoch(-1), Ok(before_epoch));
+
+        let first = Date::from_ymd(0, 1, 1).expect("invalid date");
+        assert_eq!(
+            Date::from_days_since_epoch(first.days_since_epoch()),
+            Ok(first)
+        );
+        assert_eq!(
+            Date::from_days_since_epoch(first.days_since_epoch() - 1),
+            Err(DateError::YearOutOfRange(-1))
+        );
+        assert!(matches!(
+            Date::from_days_since_epoch(i64::MAX),
+            Err(DateError::YearOutOfRange(_))
+        ));
+    }
+
+    #[test]
+    fn test_should_add_days_across_year_boundary() {
+        let date = Date::from_ymd(2023, 3, 1).expect("invalid date");
+        assert_eq!(date.add_days(365), Date::from_ymd(2024, 2, 29));
+
+        let date = Date::from_ymd(2024, 12, 31).expect("invalid date");
+        assert_eq!(date.add_days(1), Date::from_ymd(2025, 1, 1));
+        assert_eq!(date.add_days(-366), Date::from_ymd(2023, 12, 31));
+    }
+
+    #[test]
+    fn test_should_not_add_days_out_of_range() {
+        let date = Date::from_ymd(0, 1, 1).expect("invalid date");
+        assert_eq!(date.add_days(-1), Err(DateError::YearOutOfRange(-1)));
+
+        let date = Date::from_ymd(i32::from(u16::MAX), 12, 31).expect("invalid date");
+        assert_eq!(
+            date.add_days(1),
+            Err(DateError::YearOutOfRange(i32::from(u16::MAX) + 1))
+        );
+        assert!(date.add_days(i64::MIN).is_err());
+    }
+
     #[test]
     fn test_should_compare_dates() {
         let date1 = Date {
@@ -3,7 +3,7 @@ use core::fmt;
 use candid::CandidType;
 use serde::{Deserialize, Serialize};
 
-use crate::dbms::types::DataType;
+use crate::dbms::types::{DataType, Date};
 use crate::memory::{DataSize, Encode, MSize};
 
 const TYPE_SIZE: usize = 2 + 1 + 1 + 1 + 1 + 1 + 4 + 2; // year + month + day + hour + minute + second + microsecond + timezone_offset_minutes
@@ -23,6 +23,39 @@ pub struct DateTime {
     pub timezone_offset_minutes: i16,
 }
 
+impl DateTime {
+    /// Creates a UTC [`DateTime`] from a UNIX timestamp expressed in nanoseconds,
+    /// such as the one returned by the IC system time.
+    pub fn from_timestamp_nanos(nanos: u64) -> Self {
+        let secs = nanos / 1_000_000_000;
+        let microsecond = ((nanos % 1_000_000_000) / 1_000) as u32;
+        // a u64 of nanoseconds ends in 2554, well within the range of a date
+        let date = Date::from_days_since_epoch((secs / 86_400) as i64)
+            .expect("timestamp out of the range of a date");
+        let secs_of_day = secs % 86_400;
+
+        Self {
+            year: date.year,
+            month: date.month,
+            day: date.day,
+            hour: (secs_of_day / 3_600) as u8,
+            minute: ((secs_of_day % 3_600) / 60) as u8,
+            second: (secs_of_day % 60) as u8,
+            microsecond,
+            timezone_offset_minutes: 0,
+        }
+    }
+
+    /// Returns the [`Date`] component of the date time.
+    pub fn date(&self) -> Date {
+        Date {
+            year: self.year,
+            month: self.month,
+            day: self.day,
+        }
+    }
+}
+
 impl fmt::Display for DateTime {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
@@ -64,6 +97,13 @@ impl Encode for DateTime {
     }
 
     fn decode(data: std::borrow::Cow<[u8]>) -> crate::memory::MemoryResult<Self>
+    where
+        Self: Sized,
+    {
+        Self::decode_ref(&data)
+    }
+
+    fn decode_ref(data: &[u8]) -> crate::memory::MemoryResult<Self>
     where
         Self: Sized,
     {
@@ -117,6 +157,28 @@ mod tests {
         assert_eq!(value, decoded);
     }
 
+    #[test]
+    fn test_should_decode_ref_as_decode() {
+        let value = DateTime {
+            year: 1999,
+            month: 12,
+            day: 31,
+            hour: 23,
+            minute: 59,
+            second: 59,
+            microsecond: 999_999,
+            timezone_offset_minutes: 330,
+        };
+        let encoded = value.encode();
+        let decoded = DateTime::decode_ref(&encoded).unwrap();
+        assert_eq!(decoded, value);
+        assert_eq!(
+            decoded,
+            DateTime::decode(std::borrow::Cow::Owned(encoded.to_vec())).unwrap()
+        );
+        assert!(DateTime::decode_ref(&encoded[..TYPE_SIZE - 1]).is_err());
+    }
+
     #[test]
     fn test_date_display() {
         let date = DateTime {
@@ -132,6 +194,20 @@ mod tests {
         assert_eq!(date.to_string(), "2024-06-15T12:30:45.123456-02:00");
     }
 
+    #[test]
+    fn test_should_create_datetime_from_timestamp_nanos() {
+        let dt = DateTime::from_timestamp_nanos(0);
+        assert_eq!(dt.to_string(), "1970-01-01T00:00:00.000000+00:00");
+
+        // 2024-02-29T13:45:30.250000Z
+        let dt = DateTime::from_timestamp_nanos(1_709_214_330_250_000_000);
+        assert_eq!(dt.to_string(), "2024-02-29T13:45:30.250000+00:00");
+        assert_eq!(
+            dt.date(),
+            Date::from_ymd(2024, 2, 29).expect("invalid date")
+        );
+    }
+
     #[test]
     fn test_should_candid_encode_decode() {
         let src = DateTime {
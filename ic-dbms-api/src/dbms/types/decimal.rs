@@ -1,17 +1,147 @@
 use std::rc::Rc;
+use std::str::FromStr;
 
 use candid::CandidType;
 use rust_decimal::Decimal as RustDecimal;
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
 use crate::memory::{DataSize, DecodeError, Encode, MSize};
 
 const RUST_DECIMAL_ENCODE_SIZE: MSize = 16;
 
+/// Separator of the optional scale suffix of a decimal string, such as `12.5:3`.
+const SCALE_SEPARATOR: char = ':';
+
 /// Decimal data type for the DBMS.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Decimal(pub RustDecimal);
 
+/// An error raised parsing a [`Decimal`] from a string.
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum DecimalParseError {
+    /// The string has no digits.
+    #[error("decimal string has no digits")]
+    Empty,
+    /// The string contains a character which is not allowed at the given position.
+    #[error("invalid character '{character}' at position {position}")]
+    InvalidCharacter { character: char, position: usize },
+    /// The scale suffix is not a valid number.
+    #[error("invalid scale suffix: {0}")]
+    InvalidScale(String),
+    /// The number doesn't fit in a [`Decimal`].
+    #[error("decimal value out of range")]
+    Overflow,
+    /// The scale is greater than [`Decimal::MAX_SCALE`].
+    #[error("scale {0} exceeds the maximum scale of {max}", max = Decimal::MAX_SCALE)]
+    ScaleTooLarge(u32),
+}
+
+impl Decimal {
+    /// The maximum amount of decimal places of a [`Decimal`].
+    pub const MAX_SCALE: u8 = RustDecimal::MAX_SCALE as u8;
+
+    /// Returns the amount of decimal places of the value.
+    pub fn scale(&self) -> u8 {
+        self.0.scale() as u8
+    }
+
+    /// Returns the value with the given amount of decimal places.
+    ///
+    /// Extra decimal places are rounded half away from zero; the scale is capped to [`Decimal::MAX_SCALE`].
+    pub fn rescale(&self, new_scale: u8) -> Decimal {
+        let mut value = self.0;
+        value.rescale(u32::from(new_scale.min(Self::MAX_SCALE)));
+        Decimal(value)
+    }
+
+    /// Renders the value with the given amount of decimal places, as [`Decimal::rescale`] does.
+    pub fn to_string_with_scale(&self, scale: u8) -> String {
+        self.rescale(scale).to_string()
+    }
+}
+
+/// Parses a [`Decimal`] from a string such as `-1234.5678`, keeping all the fractional digits, including trailing zeros.
+///
+/// The string is made of an optional sign, the integer part, and an optional decimal point followed by the fractional part;
+/// either part can be omitted, but not both.
+/// An optional scale suffix, such as `12.5:3`, rescales the value to the given amount of decimal places.
+impl FromStr for Decimal {
+    type Err = DecimalParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (number, scale) = match s.split_once(SCALE_SEPARATOR) {
+            Some((number, scale)) => {
+                let scale = scale
+                    .parse::<u32>()
+                    .map_err(|_| DecimalParseError::InvalidScale(scale.to_string()))?;
+                (number, Some(scale))
+            }
+            None => (s, None),
+        };
+
+        let mut chars = number.char_indices().peekable();
+        let negative = match chars.peek() {
+            Some((_, '-')) => {
+                chars.next();
+                true
+            }
+            Some((_, '+')) => {
+                chars.next();
+                false
+            }
+            _ => false,
+        };
+
+        let mut mantissa: i128 = 0;
+        let mut digits = 0;
+        let mut fraction_digits: Option<u32> = None;
+        for (position, character) in chars {
+            match character {
+                '0'..='9' => {
+                    let digit = i128::from(character as u8 - b'0');
+                    mantissa = mantissa
+                        .checked_mul(10)
+                        .and_then(|mantissa| mantissa.checked_add(digit))
+                        .ok_or(DecimalParseError::Overflow)?;
+                    digits += 1;
+                    if let Some(fraction_digits) = fraction_digits.as_mut() {
+                        *fraction_digits += 1;
+                    }
+                }
+                '.' if fraction_digits.is_none() => fraction_digits = Some(0),
+                character => {
+                    return Err(DecimalParseError::InvalidCharacter {
+                        character,
+                        position,
+                    });
+                }
+            }
+        }
+        if digits == 0 {
+            return Err(DecimalParseError::Empty);
+        }
+
+        let fraction_digits = fraction_digits.unwrap_or_default();
+        if fraction_digits > RustDecimal::MAX_SCALE {
+            return Err(DecimalParseError::ScaleTooLarge(fraction_digits));
+        }
+        if negative {
+            mantissa = -mantissa;
+        }
+        let value = RustDecimal::try_from_i128_with_scale(mantissa, fraction_digits)
+            .map_err(|_| DecimalParseError::Overflow)?;
+
+        match scale {
+            Some(scale) if scale > RustDecimal::MAX_SCALE => {
+                Err(DecimalParseError::ScaleTooLarge(scale))
+            }
+            Some(scale) => Ok(Decimal(value).rescale(scale as u8)),
+            None => Ok(Decimal(value)),
+        }
+    }
+}
+
 impl From<RustDecimal> for Decimal {
     fn from(value: RustDecimal) -> Self {
         Decimal(value)
@@ -58,8 +188,7 @@ impl<'de> Deserialize<'de> for Decimal {
         D: serde::Deserializer<'de>,
     {
         let s = String::deserialize(deserializer)?;
-        let decimal = RustDecimal::from_str_exact(&s).map_err(serde::de::Error::custom)?;
-        Ok(Decimal(decimal))
+        Decimal::from_str(&s).map_err(serde::de::Error::custom)
     }
 }
 
@@ -108,6 +237,105 @@ mod tests {
         assert_eq!(original_decimal, decoded);
     }
 
+    fn parse(s: &str) -> Decimal {
+        Decimal::from_str(s).expect("failed to parse decimal")
+    }
+
+    #[test]
+    fn test_should_parse_decimal() {
+        assert_eq!(parse("1234.5678"), Decimal(RustDecimal::new(12345678, 4)));
+        assert_eq!(parse("42"), Decimal(RustDecimal::new(42, 0)));
+        assert_eq!(parse("0.5"), Decimal(RustDecimal::new(5, 1)));
+        assert_eq!(parse(".5"), Decimal(RustDecimal::new(5, 1)));
+        assert_eq!(parse("7."), Decimal(RustDecimal::new(7, 0)));
+        assert_eq!(parse("-12.50"), Decimal(RustDecimal::new(-1250, 2)));
+        assert_eq!(parse("+3.1"), Decimal(RustDecimal::new(31, 1)));
+        assert_eq!(parse("0007.250"), Decimal(RustDecimal::new(7250, 3)));
+        assert_eq!(parse("12.5:3"), Decimal(RustDecimal::new(12500, 3)));
+        assert_eq!(parse("12.345:1"), Decimal(RustDecimal::new(123, 1)));
+    }
+
+    #[test]
+    fn test_should_keep_trailing_zeros() {
+        let decimal = parse("1.500");
+        assert_eq!(decimal.scale(), 3);
+        assert_eq!(decimal.to_string(), "1.500");
+    }
+
+    #[test]
+    fn test_should_not_parse_invalid_decimal() {
+        assert_eq!(Decimal::from_str(""), Err(DecimalParseError::Empty));
+        assert_eq!(Decimal::from_str("-"), Err(DecimalParseError::Empty));
+        assert_eq!(Decimal::from_str("."), Err(DecimalParseError::Empty));
+        assert_eq!(
+            Decimal::from_str("1.2.3"),
+            Err(DecimalParseError::InvalidCharacter {
+                character: '.',
+                position: 3
+            })
+        );
+        assert_eq!(
+            Decimal::from_str("12a"),
+            Err(DecimalParseError::InvalidCharacter {
+                character: 'a',
+                position: 2
+            })
+        );
+        assert_eq!(
+            Decimal::from_str("--1"),
+            Err(DecimalParseError::InvalidCharacter {
+                character: '-',
+                position: 1
+            })
+        );
+        assert_eq!(
+            Decimal::from_str("1.5:x"),
+            Err(DecimalParseError::InvalidScale("x".to_string()))
+        );
+        assert_eq!(
+            Decimal::from_str("1.5:29"),
+            Err(DecimalParseError::ScaleTooLarge(29))
+        );
+    }
+
+    #[test]
+    fn test_should_not_parse_overflowing_decimal() {
+        assert_eq!(
+            parse("79228162514264337593543950335"),
+            Decimal(RustDecimal::MAX)
+        );
+        assert_eq!(
+            Decimal::from_str("79228162514264337593543950336"),
+            Err(DecimalParseError::Overflow)
+        );
+        assert_eq!(
+            Decimal::from_str(&"9".repeat(50)),
+            Err(DecimalParseError::Overflow)
+        );
+        assert_eq!(
+            Decimal::from_str(&format!("0.{}", "1".repeat(29))),
+            Err(DecimalParseError::ScaleTooLarge(29))
+        );
+    }
+
+    #[test]
+    fn test_should_rescale_decimal() {
+        let decimal = parse("2.345");
+        assert_eq!(decimal.rescale(2), parse("2.35"));
+        assert_eq!(decimal.rescale(5), parse("2.34500"));
+        assert_eq!(decimal.to_string_with_scale(1), "2.3");
+        assert_eq!(parse("-2.5").to_string_with_scale(0), "-3");
+    }
+
+    #[test]
+    fn test_should_encode_decode_parsed_decimal() {
+        let decimal = parse("-1234.5600");
+        let decoded = Decimal::decode(decimal.encode()).expect("Decoding failed");
+        assert_eq!(decoded, decimal);
+        assert_eq!(decoded.scale(), 4);
+        assert_eq!(decoded.to_string(), "-1234.5600");
+    }
+
     #[test]
     fn test_should_candid_encode_decode() {
         let original_decimal = Decimal(RustDecimal::new(67890, 3)); // Represents 67.890
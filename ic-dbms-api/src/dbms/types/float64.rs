@@ -0,0 +1,213 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+use crate::dbms::types::DataType;
+use crate::memory::{DataSize, Encode};
+
+/// Floating point 64-bit data type for the DBMS.
+///
+/// Unlike [`f64`], values are totally ordered, as in PostgreSQL:
+/// `NaN` is equal to any other `NaN` and greater than any other value, including infinity,
+/// while `-0.0` is equal to `0.0`.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Float64(pub f64);
+
+impl Float64 {
+    /// Returns the value with a single representation for `NaN` and zero, so that equal values have the same bits.
+    fn canonical(&self) -> f64 {
+        if self.0.is_nan() {
+            f64::NAN
+        } else if self.0 == 0.0 {
+            0.0
+        } else {
+            self.0
+        }
+    }
+}
+
+impl PartialEq for Float64 {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Float64 {}
+
+impl PartialOrd for Float64 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Float64 {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.0.is_nan(), other.0.is_nan()) {
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            (false, false) => self
+                .0
+                .partial_cmp(&other.0)
+                .expect("non-NaN floats are comparable"),
+        }
+    }
+}
+
+impl Hash for Float64 {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.canonical().to_bits().hash(state);
+    }
+}
+
+impl fmt::Display for Float64 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl CandidType for Float64 {
+    fn _ty() -> candid::types::Type {
+        candid::types::Type(std::rc::Rc::new(candid::types::TypeInner::Float64))
+    }
+
+    fn idl_serialize<S>(&self, serializer: S) -> Result<(), S::Error>
+    where
+        S: candid::types::Serializer,
+    {
+        serializer.serialize_float64(self.0)
+    }
+}
+
+impl Encode for Float64 {
+    const SIZE: DataSize = DataSize::Fixed(8);
+
+    fn size(&self) -> crate::memory::MSize {
+        Self::SIZE.get_fixed_size().expect("should be fixed")
+    }
+
+    fn encode(&'_ self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Owned(self.0.to_le_bytes().to_vec())
+    }
+
+    fn decode(data: std::borrow::Cow<[u8]>) -> crate::memory::MemoryResult<Self>
+    where
+        Self: Sized,
+    {
+        Self::decode_ref(&data)
+    }
+
+    fn decode_ref(data: &[u8]) -> crate::memory::MemoryResult<Self>
+    where
+        Self: Sized,
+    {
+        if data.len() < 8 {
+            return Err(crate::memory::MemoryError::DecodeError(
+                crate::memory::DecodeError::TooShort,
+            ));
+        }
+
+        let mut array = [0u8; 8];
+        array.copy_from_slice(&data[0..8]);
+        Ok(Self(f64::from_le_bytes(array)))
+    }
+}
+
+impl From<f64> for Float64 {
+    fn from(value: f64) -> Self {
+        Float64(value)
+    }
+}
+
+impl DataType for Float64 {}
+
+#[cfg(test)]
+mod tests {
+
+    use std::collections::hash_map::DefaultHasher;
+
+    use super::*;
+
+    fn hash(value: Float64) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn test_float64_encode_decode() {
+        for value in [
+            0.0,
+            -0.0,
+            1.5,
+            -1234.5678,
+            f64::MIN_POSITIVE,
+            f64::MAX,
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+        ] {
+            let value = Float64(value);
+            let encoded = value.encode();
+            assert_eq!(encoded.len(), 8);
+            let decoded = Float64::decode(encoded).unwrap();
+            assert_eq!(value.0.to_bits(), decoded.0.to_bits());
+        }
+    }
+
+    #[test]
+    fn test_should_encode_decode_nan() {
+        let decoded = Float64::decode(Float64(f64::NAN).encode()).unwrap();
+        assert!(decoded.0.is_nan());
+        assert_eq!(decoded, Float64(f64::NAN));
+    }
+
+    #[test]
+    fn test_should_not_decode_too_short_data() {
+        assert!(Float64::decode_ref(&[0u8; 7]).is_err());
+    }
+
+    #[test]
+    fn test_should_order_nan_greater_than_any_value() {
+        let mut values = [
+            Float64(f64::NAN),
+            Float64(f64::INFINITY),
+            Float64(1.5),
+            Float64(f64::NEG_INFINITY),
+            Float64(-2.0),
+            Float64(0.0),
+        ];
+        values.sort();
+        assert_eq!(
+            values,
+            [
+                Float64(f64::NEG_INFINITY),
+                Float64(-2.0),
+                Float64(0.0),
+                Float64(1.5),
+                Float64(f64::INFINITY),
+                Float64(f64::NAN),
+            ]
+        );
+        assert_eq!(Float64(f64::NAN), Float64(-f64::NAN));
+        assert!(Float64(f64::NAN) > Float64(f64::INFINITY));
+    }
+
+    #[test]
+    fn test_should_hash_equal_values_equally() {
+        assert_eq!(Float64(0.0), Float64(-0.0));
+        assert_eq!(hash(Float64(0.0)), hash(Float64(-0.0)));
+        assert_eq!(hash(Float64(f64::NAN)), hash(Float64(-f64::NAN)));
+        assert_ne!(hash(Float64(1.0)), hash(Float64(2.0)));
+    }
+
+    #[test]
+    fn test_should_candid_encode_decode() {
+        let src = Float64(-1234.5678);
+        let buf = candid::encode_one(src).expect("Candid encoding failed");
+        let decoded: Float64 = candid::decode_one(&buf).expect("Candid decoding failed");
+        assert_eq!(src, decoded);
+    }
+}
@@ -41,6 +41,13 @@ impl Encode for Int32 {
     }
 
     fn decode(data: std::borrow::Cow<[u8]>) -> crate::memory::MemoryResult<Self>
+    where
+        Self: Sized,
+    {
+        Self::decode_ref(&data)
+    }
+
+    fn decode_ref(data: &[u8]) -> crate::memory::MemoryResult<Self>
     where
         Self: Sized,
     {
@@ -77,6 +84,22 @@ mod tests {
         assert_eq!(value, decoded);
     }
 
+    #[test]
+    fn test_should_decode_ref_as_decode() {
+        for value in [Int32(0), Int32(-1), Int32(i32::MIN), Int32(i32::MAX)] {
+            let encoded = value.encode();
+            let decoded = Int32::decode_ref(&encoded).unwrap();
+            assert_eq!(decoded, value);
+            assert_eq!(
+                decoded,
+                Int32::decode(std::borrow::Cow::Owned(encoded.to_vec())).unwrap()
+            );
+        }
+        // trailing bytes are ignored
+        assert_eq!(Int32::decode_ref(&[1, 0, 0, 0, 0xff]).unwrap(), Int32(1));
+        assert!(Int32::decode_ref(&[1, 0, 0]).is_err());
+    }
+
     #[test]
     fn test_should_candid_encode_decode() {
         let src = Int32(123456);
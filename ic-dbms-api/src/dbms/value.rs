@@ -1,13 +1,38 @@
+mod candid_conversion;
+
+use std::cmp::Ordering;
+use std::fmt;
+
+use thiserror::Error;
+
 use super::types;
 
+/// An error raised comparing [`Value`]s of different types.
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+pub enum TypeError {
+    /// The two values have different types.
+    #[error("Type mismatch: cannot compare {left} with {right}")]
+    TypeMismatch {
+        left: &'static str,
+        right: &'static str,
+    },
+}
+
 /// A generic wrapper enum to hold any DBMS value.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Value {
+    /// An ordered collection of values, of the [`types::DataTypeKind::Array`] columns.
+    Array(Vec<Value>),
+    /// Placeholder for the next value of the sequence of an auto-increment column.
+    ///
+    /// It's replaced by the DBMS on insert, so it's never stored nor returned by a query.
+    AutoIncrement,
     Blob(types::Blob),
     Boolean(types::Boolean),
     Date(types::Date),
     DateTime(types::DateTime),
     Decimal(types::Decimal),
+    Float64(types::Float64),
     Int32(types::Int32),
     Int64(types::Int64),
     Null,
@@ -37,14 +62,29 @@ macro_rules! impl_conv_for_value {
                 }
             }
         }
+
+        /// Converts the value into the inner value, giving back the value if it doesn't match the variant.
+        impl TryFrom<Value> for $ty {
+            type Error = Value;
+
+            fn try_from(value: Value) -> Result<Self, Self::Error> {
+                if let Value::$variant(v) = value {
+                    Ok(v)
+                } else {
+                    Err(value)
+                }
+            }
+        }
     };
 }
 
+impl_conv_for_value!(Array, Vec<Value>, as_array);
 impl_conv_for_value!(Blob, types::Blob, as_blob);
 impl_conv_for_value!(Boolean, types::Boolean, as_boolean);
 impl_conv_for_value!(Date, types::Date, as_date);
 impl_conv_for_value!(DateTime, types::DateTime, as_datetime);
 impl_conv_for_value!(Decimal, types::Decimal, as_decimal);
+impl_conv_for_value!(Float64, types::Float64, as_float64);
 impl_conv_for_value!(Int32, types::Int32, as_int32);
 impl_conv_for_value!(Int64, types::Int64, as_int64);
 impl_conv_for_value!(Principal, types::Principal, as_principal);
@@ -62,11 +102,14 @@ impl Value {
     /// Returns the type name of the value as a string.
     pub fn type_name(&self) -> &'static str {
         match self {
+            Value::Array(_) => "Array",
+            Value::AutoIncrement => "AutoIncrement",
             Value::Blob(_) => "Blob",
             Value::Boolean(_) => "Boolean",
             Value::Date(_) => "Date",
             Value::DateTime(_) => "DateTime",
             Value::Decimal(_) => "Decimal",
+            Value::Float64(_) => "Float64",
             Value::Int32(_) => "Int32",
             Value::Int64(_) => "Int64",
             Value::Null => "Null",
@@ -77,6 +120,95 @@ impl Value {
             Value::Uuid(_) => "Uuid",
         }
     }
+
+    /// Returns the [`types::DataTypeKind`] of the value, or `None` if the value is [`Value::Null`]
+    /// or [`Value::AutoIncrement`].
+    ///
+    /// The kind of a [`Value::Array`] is given by its first non-`NULL` element,
+    /// so it's `None` as well for the arrays without any.
+    pub fn kind_opt(&self) -> Option<types::DataTypeKind> {
+        use types::DataTypeKind;
+
+        match self {
+            Value::Array(values) => values
+                .iter()
+                .find_map(Value::kind_opt)
+                .and_then(DataTypeKind::array_of),
+            Value::AutoIncrement | Value::Null => None,
+            Value::Blob(_) => Some(DataTypeKind::Blob),
+            Value::Boolean(_) => Some(DataTypeKind::Boolean),
+            Value::Date(_) => Some(DataTypeKind::Date),
+            Value::DateTime(_) => Some(DataTypeKind::DateTime),
+            Value::Decimal(_) => Some(DataTypeKind::Decimal),
+            Value::Float64(_) => Some(DataTypeKind::Float64),
+            Value::Int32(_) => Some(DataTypeKind::Int32),
+            Value::Int64(_) => Some(DataTypeKind::Int64),
+            Value::Principal(_) => Some(DataTypeKind::Principal),
+            Value::Text(_) => Some(DataTypeKind::Text),
+            Value::Uint32(_) => Some(DataTypeKind::Uint32),
+            Value::Uint64(_) => Some(DataTypeKind::Uint64),
+            Value::Uuid(_) => Some(DataTypeKind::Uuid),
+        }
+    }
+
+    /// Returns the [`types::DataTypeKind`] of the value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is [`Value::Null`], which has no type, or an array without non-`NULL` elements;
+    /// use [`Value::kind_opt`] instead.
+    pub fn kind(&self) -> types::DataTypeKind {
+        self.kind_opt().expect("NULL values have no data type kind")
+    }
+
+    /// Compares two values of the same type.
+    ///
+    /// Unlike the [`Ord`] implementation, which orders values of different types by their variant,
+    /// it returns [`TypeError::TypeMismatch`] if the two values have different types.
+    pub fn typed_cmp(&self, other: &Value) -> Result<Ordering, TypeError> {
+        if std::mem::discriminant(self) != std::mem::discriminant(other) {
+            return Err(TypeError::TypeMismatch {
+                left: self.type_name(),
+                right: other.type_name(),
+            });
+        }
+
+        Ok(self.cmp(other))
+    }
+}
+
+/// Values are displayed along with their type, e.g. `Uint32(42)`, `Text("hello")` or `Null`.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Array(values) => {
+                write!(f, "Array([")?;
+                for (index, value) in values.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{value}")?;
+                }
+                write!(f, "])")
+            }
+            Value::AutoIncrement => write!(f, "AutoIncrement"),
+            // blobs are already displayed with their type
+            Value::Blob(value) => write!(f, "{value}"),
+            Value::Boolean(value) => write!(f, "Boolean({value})"),
+            Value::Date(value) => write!(f, "Date({value})"),
+            Value::DateTime(value) => write!(f, "DateTime({value})"),
+            Value::Decimal(value) => write!(f, "Decimal({value})"),
+            Value::Float64(value) => write!(f, "Float64({value})"),
+            Value::Int32(value) => write!(f, "Int32({value})"),
+            Value::Int64(value) => write!(f, "Int64({value})"),
+            Value::Null => write!(f, "Null"),
+            Value::Principal(value) => write!(f, "Principal({value})"),
+            Value::Text(value) => write!(f, "Text({:?})", value.0),
+            Value::Uint32(value) => write!(f, "Uint32({value})"),
+            Value::Uint64(value) => write!(f, "Uint64({value})"),
+            Value::Uuid(value) => write!(f, "Uuid({value})"),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -95,6 +227,29 @@ mod tests {
         assert!(null_value.is_null());
     }
 
+    #[test]
+    fn test_value_conversion_array() {
+        let array = types::Array(vec![types::Uint32(1), types::Uint32(2)]);
+        let value: Value = array.clone().into();
+        assert_eq!(
+            value.as_array(),
+            Some(&vec![
+                Value::Uint32(types::Uint32(1)),
+                Value::Uint32(types::Uint32(2))
+            ])
+        );
+        assert_eq!(types::Array::<types::Uint32>::try_from(value), Ok(array));
+    }
+
+    #[test]
+    fn test_should_try_convert_value_into_inner_type() {
+        assert_eq!(
+            types::Uint32::try_from(Value::Uint32(types::Uint32(7))),
+            Ok(types::Uint32(7))
+        );
+        assert_eq!(types::Uint32::try_from(Value::Null), Err(Value::Null));
+    }
+
     #[test]
     fn test_value_conversion_blob() {
         let blob = types::Blob(vec![1, 2, 3]);
@@ -143,6 +298,13 @@ mod tests {
         assert_eq!(value.as_decimal(), Some(&decimal));
     }
 
+    #[test]
+    fn test_value_conversion_float64() {
+        let float64 = types::Float64(-12.5);
+        let value: Value = float64.into();
+        assert_eq!(value.as_float64(), Some(&float64));
+    }
+
     #[test]
     fn test_value_conversion_int32() {
         let int32 = types::Int32(1234567890);
@@ -205,4 +367,147 @@ mod tests {
         let null_value = Value::Null;
         assert_eq!(null_value.type_name(), "Null");
     }
+
+    #[test]
+    fn test_should_get_kind_of_all_values() {
+        use types::DataTypeKind;
+
+        let values = [
+            (Value::Blob(types::Blob(vec![1])), DataTypeKind::Blob),
+            (Value::Boolean(types::Boolean(true)), DataTypeKind::Boolean),
+            (
+                Value::Date(types::Date {
+                    year: 2024,
+                    month: 1,
+                    day: 1,
+                }),
+                DataTypeKind::Date,
+            ),
+            (
+                Value::DateTime(types::DateTime::from_timestamp_nanos(0)),
+                DataTypeKind::DateTime,
+            ),
+            (
+                Value::Decimal("1.5".parse().expect("invalid decimal")),
+                DataTypeKind::Decimal,
+            ),
+            (Value::Float64(types::Float64(0.5)), DataTypeKind::Float64),
+            (Value::Int32(types::Int32(-1)), DataTypeKind::Int32),
+            (Value::Int64(types::Int64(-1)), DataTypeKind::Int64),
+            (
+                Value::Principal(types::Principal(candid::Principal::anonymous())),
+                DataTypeKind::Principal,
+            ),
+            (
+                Value::Text(types::Text("a".to_string())),
+                DataTypeKind::Text,
+            ),
+            (Value::Uint32(types::Uint32(1)), DataTypeKind::Uint32),
+            (Value::Uint64(types::Uint64(1)), DataTypeKind::Uint64),
+            (Value::Uuid(types::Uuid(Uuid::nil())), DataTypeKind::Uuid),
+        ];
+
+        for (value, kind) in values {
+            assert_eq!(value.kind_opt(), Some(kind));
+            assert_eq!(value.kind(), kind);
+            assert_eq!(value.type_name(), kind.to_value_type_name());
+            assert_eq!(kind.value_kind(), kind);
+        }
+        assert_eq!(
+            DataTypeKind::AutoIncrement.value_kind(),
+            DataTypeKind::Uint64
+        );
+        assert_eq!(DataTypeKind::AutoIncrement.to_value_type_name(), "Uint64");
+        assert_eq!(
+            DataTypeKind::AutoIncrement.encoded_size(),
+            DataTypeKind::Uint64.encoded_size()
+        );
+        assert_eq!(DataTypeKind::array_of(DataTypeKind::AutoIncrement), None);
+        assert_eq!(Value::Null.kind_opt(), None);
+        assert_eq!(Value::Null.type_name(), "Null");
+        assert_eq!(Value::AutoIncrement.kind_opt(), None);
+        assert_eq!(Value::AutoIncrement.type_name(), "AutoIncrement");
+    }
+
+    #[test]
+    fn test_should_get_kind_of_arrays() {
+        use types::DataTypeKind;
+
+        let array = Value::Array(vec![Value::Null, Value::Text(types::Text("a".to_string()))]);
+        assert_eq!(
+            array.kind_opt(),
+            Some(DataTypeKind::Array(&DataTypeKind::Text))
+        );
+        assert_eq!(array.type_name(), "Array");
+        assert_eq!(array.kind().to_value_type_name(), "Array");
+        assert_eq!(Value::Array(vec![]).kind_opt(), None);
+        assert_eq!(Value::Array(vec![Value::Null]).kind_opt(), None);
+        assert_eq!(
+            DataTypeKind::array_of(DataTypeKind::Uint32),
+            Some(DataTypeKind::Array(&DataTypeKind::Uint32))
+        );
+        assert_eq!(
+            DataTypeKind::array_of(DataTypeKind::Array(&DataTypeKind::Uint32)),
+            None
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_should_panic_getting_kind_of_null() {
+        Value::Null.kind();
+    }
+
+    #[test]
+    fn test_should_compare_values_of_the_same_type() {
+        assert_eq!(
+            Value::Int32(types::Int32(5)).typed_cmp(&Value::Int32(types::Int32(3))),
+            Ok(Ordering::Greater)
+        );
+        assert_eq!(Value::Null.typed_cmp(&Value::Null), Ok(Ordering::Equal));
+        assert_eq!(
+            Value::Float64(types::Float64(f64::NAN))
+                .typed_cmp(&Value::Float64(types::Float64(f64::INFINITY))),
+            Ok(Ordering::Greater)
+        );
+    }
+
+    #[test]
+    fn test_should_not_compare_values_of_different_types() {
+        assert_eq!(
+            Value::Int32(types::Int32(5)).typed_cmp(&Value::Text(types::Text("3".to_string()))),
+            Err(TypeError::TypeMismatch {
+                left: "Int32",
+                right: "Text",
+            })
+        );
+    }
+
+    #[test]
+    fn test_should_display_value() {
+        assert_eq!(Value::Uint32(types::Uint32(42)).to_string(), "Uint32(42)");
+        assert_eq!(Value::Int64(types::Int64(-7)).to_string(), "Int64(-7)");
+        assert_eq!(
+            Value::Float64(types::Float64(1.5)).to_string(),
+            "Float64(1.5)"
+        );
+        assert_eq!(
+            Value::Text(types::Text("hello".to_string())).to_string(),
+            "Text(\"hello\")"
+        );
+        assert_eq!(
+            Value::Boolean(types::Boolean(true)).to_string(),
+            "Boolean(true)"
+        );
+        assert_eq!(
+            Value::Blob(types::Blob(vec![1, 2, 3])).to_string(),
+            "Blob(len=3)"
+        );
+        assert_eq!(Value::Null.to_string(), "Null");
+        assert_eq!(Value::AutoIncrement.to_string(), "AutoIncrement");
+        assert_eq!(
+            Value::Array(vec![Value::Uint32(types::Uint32(1)), Value::Null]).to_string(),
+            "Array([Uint32(1), Null])"
+        );
+    }
 }
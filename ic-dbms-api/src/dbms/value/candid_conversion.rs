@@ -0,0 +1,375 @@
+use candid::types::Label;
+use candid::types::value::{IDLField, IDLValue};
+use candid::{Int, Nat};
+
+use super::Value;
+use crate::dbms::query::QueryError;
+use crate::dbms::types::{self, DataTypeKind};
+use crate::error::{IcDbmsError, IcDbmsResult};
+
+impl Value {
+    /// Converts a raw Candid value into a [`Value`] of the given [`DataTypeKind`].
+    ///
+    /// Candid `null`, `opt` without a value and the untyped `None` are converted into [`Value::Null`],
+    /// while `opt` values are unwrapped.
+    /// Numbers are accepted as long as they fit the target type, so a `nat` can be converted into
+    /// a [`DataTypeKind::Uint64`] and a `nat16` into a [`DataTypeKind::Uint32`].
+    ///
+    /// Returns [`QueryError::TypeMismatch`] if the Candid value can't represent the given type,
+    /// and [`QueryError::InvalidQuery`] if it has the right type but an invalid content,
+    /// such as a number out of range or a malformed decimal.
+    /// Since the value is not bound to any column, the `column` of the mismatch is empty.
+    pub fn from_candid(candid: &IDLValue, kind: DataTypeKind) -> IcDbmsResult<Value> {
+        let value = match (kind, candid) {
+            (_, IDLValue::Null | IDLValue::None) => Value::Null,
+            (_, IDLValue::Opt(value)) => return Value::from_candid(value, kind),
+            (DataTypeKind::Array(element), IDLValue::Vec(values)) => Value::Array(
+                values
+                    .iter()
+                    .map(|value| Value::from_candid(value, *element))
+                    .collect::<IcDbmsResult<_>>()?,
+            ),
+            (DataTypeKind::Blob, IDLValue::Blob(bytes)) => Value::Blob(types::Blob(bytes.clone())),
+            (DataTypeKind::Blob, IDLValue::Vec(values)) => {
+                Value::Blob(types::Blob(bytes_from_vec(values, kind, candid)?))
+            }
+            (DataTypeKind::Boolean, IDLValue::Bool(value)) => {
+                Value::Boolean(types::Boolean(*value))
+            }
+            (DataTypeKind::Date, IDLValue::Record(fields)) => Value::Date(types::Date {
+                year: record_field(fields, "year", kind)?,
+                month: record_field(fields, "month", kind)?,
+                day: record_field(fields, "day", kind)?,
+            }),
+            (DataTypeKind::DateTime, IDLValue::Record(fields)) => {
+                Value::DateTime(types::DateTime {
+                    year: record_field(fields, "year", kind)?,
+                    month: record_field(fields, "month", kind)?,
+                    day: record_field(fields, "day", kind)?,
+                    hour: record_field(fields, "hour", kind)?,
+                    minute: record_field(fields, "minute", kind)?,
+                    second: record_field(fields, "second", kind)?,
+                    microsecond: record_field(fields, "microsecond", kind)?,
+                    timezone_offset_minutes: record_field(fields, "timezone_offset_minutes", kind)?,
+                })
+            }
+            (DataTypeKind::Decimal, IDLValue::Text(text)) => {
+                Value::Decimal(text.parse().map_err(|err| {
+                    QueryError::InvalidQuery(format!("invalid decimal {text}: {err}"))
+                })?)
+            }
+            (DataTypeKind::Float64, IDLValue::Float64(value)) => {
+                Value::Float64(types::Float64(*value))
+            }
+            (DataTypeKind::Float64, IDLValue::Float32(value)) => {
+                Value::Float64(types::Float64((*value).into()))
+            }
+            (DataTypeKind::Int32, value) => Value::Int32(types::Int32(to_integer(value, kind)?)),
+            (DataTypeKind::Int64, value) => Value::Int64(types::Int64(to_integer(value, kind)?)),
+            (DataTypeKind::Principal, IDLValue::Principal(principal)) => {
+                Value::Principal(types::Principal(*principal))
+            }
+            (DataTypeKind::Text, IDLValue::Text(text)) => Value::Text(types::Text(text.clone())),
+            (DataTypeKind::Uint32, value) => Value::Uint32(types::Uint32(to_integer(value, kind)?)),
+            (DataTypeKind::Uint64 | DataTypeKind::AutoIncrement, value) => {
+                Value::Uint64(types::Uint64(to_integer(value, kind)?))
+            }
+            (DataTypeKind::Uuid, IDLValue::Blob(bytes)) => uuid_from_bytes(bytes)?,
+            (DataTypeKind::Uuid, IDLValue::Vec(values)) => {
+                uuid_from_bytes(&bytes_from_vec(values, kind, candid)?)?
+            }
+            (kind, candid) => return Err(type_mismatch(kind, candid)),
+        };
+
+        Ok(value)
+    }
+
+    /// Converts the value into a raw Candid value.
+    ///
+    /// Each value is converted into the Candid type of the wrapped DBMS type, arrays into a `vec` of their elements,
+    /// and [`Value::Null`] is converted into `null`, as is [`Value::AutoIncrement`], which has no value yet.
+    pub fn into_candid(self) -> IDLValue {
+        match self {
+            Value::Array(values) => {
+                IDLValue::Vec(values.into_iter().map(Value::into_candid).collect())
+            }
+            Value::AutoIncrement => IDLValue::Null,
+            Value::Blob(blob) => IDLValue::Blob(blob.0),
+            Value::Boolean(boolean) => IDLValue::Bool(boolean.0),
+            Value::Date(date) => IDLValue::Record(vec![
+                field("year", IDLValue::Nat16(date.year)),
+                field("month", IDLValue::Nat8(date.month)),
+                field("day", IDLValue::Nat8(date.day)),
+            ]),
+            Value::DateTime(datetime) => IDLValue::Record(vec![
+                field("year", IDLValue::Nat16(datetime.year)),
+                field("month", IDLValue::Nat8(datetime.month)),
+                field("day", IDLValue::Nat8(datetime.day)),
+                field("hour", IDLValue::Nat8(datetime.hour)),
+                field("minute", IDLValue::Nat8(datetime.minute)),
+                field("second", IDLValue::Nat8(datetime.second)),
+                field("microsecond", IDLValue::Nat32(datetime.microsecond)),
+                field(
+                    "timezone_offset_minutes",
+                    IDLValue::Int16(datetime.timezone_offset_minutes),
+                ),
+            ]),
+            Value::Decimal(decimal) => IDLValue::Text(decimal.0.to_string()),
+            Value::Float64(float) => IDLValue::Float64(float.0),
+            Value::Int32(int) => IDLValue::Int32(int.0),
+            Value::Int64(int) => IDLValue::Int64(int.0),
+            Value::Null => IDLValue::Null,
+            Value::Principal(principal) => IDLValue::Principal(principal.0),
+            Value::Text(text) => IDLValue::Text(text.0),
+            Value::Uint32(uint) => IDLValue::Nat32(uint.0),
+            Value::Uint64(uint) => IDLValue::Nat64(uint.0),
+            Value::Uuid(uuid) => IDLValue::Blob(uuid.0.as_bytes().to_vec()),
+        }
+    }
+}
+
+/// Builds a named record field.
+fn field(name: &str, val: IDLValue) -> IDLField {
+    IDLField {
+        id: Label::Named(name.to_string()),
+        val,
+    }
+}
+
+/// Reads the integer field `name` of a record of the given type.
+fn record_field<N>(fields: &[IDLField], name: &str, kind: DataTypeKind) -> IcDbmsResult<N>
+where
+    N: TryFrom<i128>,
+{
+    let label = Label::Named(name.to_string());
+    let field = fields
+        .iter()
+        .find(|field| field.id == label)
+        .ok_or_else(|| {
+            QueryError::InvalidQuery(format!(
+                "missing field {name} of {}",
+                kind.to_value_type_name()
+            ))
+        })?;
+
+    to_integer(&field.val, kind)
+}
+
+/// Converts a Candid number into an integer, checking that it fits the target type.
+fn to_integer<N>(candid: &IDLValue, kind: DataTypeKind) -> IcDbmsResult<N>
+where
+    N: TryFrom<i128>,
+{
+    let value: i128 = match candid {
+        IDLValue::Nat8(n) => (*n).into(),
+        IDLValue::Nat16(n) => (*n).into(),
+        IDLValue::Nat32(n) => (*n).into(),
+        IDLValue::Nat64(n) => (*n).into(),
+        IDLValue::Int8(n) => (*n).into(),
+        IDLValue::Int16(n) => (*n).into(),
+        IDLValue::Int32(n) => (*n).into(),
+        IDLValue::Int64(n) => (*n).into(),
+        IDLValue::Nat(Nat(n)) => n.try_into().map_err(|_| out_of_range(candid, kind))?,
+        IDLValue::Int(Int(n)) => n.try_into().map_err(|_| out_of_range(candid, kind))?,
+        candid => return Err(type_mismatch(kind, candid)),
+    };
+
+    N::try_from(value).map_err(|_| out_of_range(candid, kind))
+}
+
+/// Collects a Candid `vec nat8` into bytes.
+fn bytes_from_vec(
+    values: &[IDLValue],
+    kind: DataTypeKind,
+    candid: &IDLValue,
+) -> IcDbmsResult<Vec<u8>> {
+    values
+        .iter()
+        .map(|value| match value {
+            IDLValue::Nat8(byte) => Ok(*byte),
+            _ => Err(type_mismatch(kind, candid)),
+        })
+        .collect()
+}
+
+/// Builds a [`Value::Uuid`] from its bytes.
+fn uuid_from_bytes(bytes: &[u8]) -> IcDbmsResult<Value> {
+    let uuid = uuid::Uuid::from_slice(bytes)
+        .map_err(|err| QueryError::InvalidQuery(format!("invalid UUID: {err}")))?;
+
+    Ok(Value::Uuid(types::Uuid(uuid)))
+}
+
+fn out_of_range(candid: &IDLValue, kind: DataTypeKind) -> IcDbmsError {
+    QueryError::InvalidQuery(format!(
+        "value {candid} is out of range for {}",
+        kind.to_value_type_name()
+    ))
+    .into()
+}
+
+fn type_mismatch(kind: DataTypeKind, candid: &IDLValue) -> IcDbmsError {
+    QueryError::TypeMismatch {
+        column: "",
+        expected: kind.to_value_type_name(),
+        found: candid_type_name(candid),
+    }
+    .into()
+}
+
+/// Returns the name of the Candid type of the given value.
+fn candid_type_name(candid: &IDLValue) -> &'static str {
+    match candid {
+        IDLValue::Bool(_) => "bool",
+        IDLValue::Null => "null",
+        IDLValue::Text(_) => "text",
+        IDLValue::Number(_) => "number",
+        IDLValue::Float64(_) => "float64",
+        IDLValue::Opt(_) => "opt",
+        IDLValue::Vec(_) => "vec",
+        IDLValue::Record(_) => "record",
+        IDLValue::Variant(_) => "variant",
+        IDLValue::Blob(_) => "blob",
+        IDLValue::Principal(_) => "principal",
+        IDLValue::Service(_) => "service",
+        IDLValue::Func(_, _) => "func",
+        IDLValue::None => "none",
+        IDLValue::Int(_) => "int",
+        IDLValue::Nat(_) => "nat",
+        IDLValue::Nat8(_) => "nat8",
+        IDLValue::Nat16(_) => "nat16",
+        IDLValue::Nat32(_) => "nat32",
+        IDLValue::Nat64(_) => "nat64",
+        IDLValue::Int8(_) => "int8",
+        IDLValue::Int16(_) => "int16",
+        IDLValue::Int32(_) => "int32",
+        IDLValue::Int64(_) => "int64",
+        IDLValue::Float32(_) => "float32",
+        IDLValue::Reserved => "reserved",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn all_values() -> Vec<Value> {
+        vec![
+            Value::Array(vec![
+                Value::Uint32(types::Uint32(1)),
+                Value::Uint32(types::Uint32(2)),
+            ]),
+            Value::Blob(types::Blob(vec![1, 2, 3])),
+            Value::Boolean(types::Boolean(true)),
+            Value::Date(types::Date {
+                year: 2024,
+                month: 2,
+                day: 29,
+            }),
+            Value::DateTime(types::DateTime {
+                year: 2024,
+                month: 2,
+                day: 29,
+                hour: 13,
+                minute: 45,
+                second: 30,
+                microsecond: 250,
+                timezone_offset_minutes: -60,
+            }),
+            Value::Decimal("123.45".parse().expect("invalid decimal")),
+            Value::Float64(types::Float64(-1.25)),
+            Value::Int32(types::Int32(-32)),
+            Value::Int64(types::Int64(-64)),
+            Value::Principal(types::Principal(candid::Principal::anonymous())),
+            Value::Text(types::Text("hello".to_string())),
+            Value::Uint32(types::Uint32(32)),
+            Value::Uint64(types::Uint64(64)),
+            Value::Uuid(types::Uuid(uuid::Uuid::from_bytes([7; 16]))),
+        ]
+    }
+
+    #[test]
+    fn test_should_roundtrip_values_through_candid() {
+        for value in all_values() {
+            let kind = value.kind();
+            let candid = value.clone().into_candid();
+            let decoded = Value::from_candid(&candid, kind).expect("failed to convert");
+            assert_eq!(decoded, value);
+        }
+
+        assert_eq!(Value::Null.into_candid(), IDLValue::Null);
+        for kind in all_values().iter().map(Value::kind) {
+            for candid in [
+                IDLValue::Null,
+                IDLValue::None,
+                IDLValue::Opt(Box::new(IDLValue::Null)),
+            ] {
+                assert_eq!(
+                    Value::from_candid(&candid, kind).expect("failed to convert"),
+                    Value::Null
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_should_convert_wider_candid_numbers() {
+        assert_eq!(
+            Value::from_candid(&IDLValue::Nat(Nat::from(42u64)), DataTypeKind::Uint64)
+                .expect("failed to convert"),
+            Value::Uint64(types::Uint64(42))
+        );
+        assert_eq!(
+            Value::from_candid(&IDLValue::Int(Int::from(-42i64)), DataTypeKind::Int64)
+                .expect("failed to convert"),
+            Value::Int64(types::Int64(-42))
+        );
+        assert_eq!(
+            Value::from_candid(
+                &IDLValue::Opt(Box::new(IDLValue::Nat16(7))),
+                DataTypeKind::Uint32
+            )
+            .expect("failed to convert"),
+            Value::Uint32(types::Uint32(7))
+        );
+        assert_eq!(
+            Value::from_candid(&IDLValue::Float32(0.5), DataTypeKind::Float64)
+                .expect("failed to convert"),
+            Value::Float64(types::Float64(0.5))
+        );
+        assert!(matches!(
+            Value::from_candid(&IDLValue::Nat64(u64::MAX), DataTypeKind::Uint32),
+            Err(IcDbmsError::Query(QueryError::InvalidQuery(_)))
+        ));
+        assert!(matches!(
+            Value::from_candid(&IDLValue::Int32(-1), DataTypeKind::Uint64),
+            Err(IcDbmsError::Query(QueryError::InvalidQuery(_)))
+        ));
+    }
+
+    #[test]
+    fn test_should_reject_candid_type_mismatch() {
+        let err = Value::from_candid(&IDLValue::Text("42".to_string()), DataTypeKind::Uint64)
+            .expect_err("should fail");
+        assert!(matches!(
+            err,
+            IcDbmsError::Query(QueryError::TypeMismatch {
+                column: "",
+                expected: "Uint64",
+                found: "text",
+            })
+        ));
+
+        let err =
+            Value::from_candid(&IDLValue::Bool(true), DataTypeKind::Text).expect_err("should fail");
+        assert!(matches!(
+            err,
+            IcDbmsError::Query(QueryError::TypeMismatch {
+                column: "",
+                expected: "Text",
+                found: "bool",
+            })
+        ));
+    }
+}
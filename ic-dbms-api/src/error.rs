@@ -1,5 +1,18 @@
+mod candid_error;
+pub mod error_codes;
+
 use thiserror::Error;
 
+pub use self::candid_error::{
+    CandidIcDbmsError, CandidIcDbmsErrorKind, CandidMemoryError, CandidQueryError,
+    CandidTableError, CandidTransactionError,
+};
+use crate::dbms::query::QueryError;
+use crate::dbms::table::TableError;
+use crate::dbms::transaction::TransactionError;
+use crate::dbms::value::Value;
+use crate::memory::{DecodeError, MemoryError};
+
 /// IcDbms Error type
 #[derive(Debug, Error)]
 pub enum IcDbmsError {
@@ -11,7 +24,231 @@ pub enum IcDbmsError {
     Table(#[from] crate::dbms::table::TableError),
     #[error("Transaction error: {0}")]
     Transaction(#[from] crate::dbms::transaction::TransactionError),
+    /// The caller exceeded the amount of calls allowed by its rate limit.
+    #[error(
+        "Rate limit exceeded for caller {caller}: at most {limit} calls allowed until {reset_at}"
+    )]
+    RateLimitExceeded {
+        caller: candid::Principal,
+        limit: u32,
+        /// Time in nanoseconds since the UNIX epoch when the rate limit window resets.
+        reset_at: u64,
+    },
+}
+
+impl IcDbmsError {
+    /// Returns the stable code of the error, as defined in [`error_codes`].
+    pub fn error_code(&self) -> u32 {
+        match self {
+            IcDbmsError::Memory(err) => err.error_code(),
+            IcDbmsError::Query(err) => err.error_code(),
+            IcDbmsError::Table(err) => err.error_code(),
+            IcDbmsError::Transaction(err) => err.error_code(),
+            IcDbmsError::RateLimitExceeded { .. } => error_codes::RATE_LIMIT_EXCEEDED,
+        }
+    }
+
+    /// Returns a representative error for the given code, as defined in [`error_codes`].
+    ///
+    /// The details of the error, such as column names or values, are set to placeholders,
+    /// so only the variant of the returned error is meaningful.
+    ///
+    /// Returns `None` if the code is unknown.
+    pub fn from_code(code: u32) -> Option<IcDbmsError> {
+        use self::error_codes::*;
+
+        let error = match code {
+            PRIMARY_KEY_CONFLICT => QueryError::PrimaryKeyConflict.into(),
+            FOREIGN_KEY_VIOLATION => QueryError::BrokenForeignKeyReference {
+                table: "",
+                key: Value::Null,
+            }
+            .into(),
+            FOREIGN_KEY_CONSTRAINT_VIOLATION => QueryError::ForeignKeyConstraintViolation {
+                referencing_table: "",
+                field: "",
+            }
+            .into(),
+            UNKNOWN_COLUMN => QueryError::UnknownColumn(String::new()).into(),
+            MISSING_NON_NULLABLE_FIELD => QueryError::MissingNonNullableField("").into(),
+            TYPE_MISMATCH => QueryError::TypeMismatch {
+                column: "",
+                expected: "",
+                found: "",
+            }
+            .into(),
+            TRANSACTION_NOT_FOUND => QueryError::TransactionNotFound.into(),
+            INVALID_QUERY => QueryError::InvalidQuery(String::new()).into(),
+            CHECK_CONSTRAINT_VIOLATION => QueryError::CheckConstraintViolation {
+                column: "",
+                constraint_desc: "",
+                value: Value::Null,
+            }
+            .into(),
+            CONSTRAINT_VIOLATION => QueryError::ConstraintViolation(String::new()).into(),
+            QUERY_MEMORY_ERROR => QueryError::MemoryError(MemoryError::OutOfBounds).into(),
+            QUERY_TABLE_NOT_FOUND => QueryError::TableNotFound("").into(),
+            RECORD_NOT_FOUND => QueryError::RecordNotFound.into(),
+            TOO_MANY_RESULTS => QueryError::TooManyResults(0).into(),
+            SERIALIZATION_ERROR => QueryError::SerializationError(String::new()).into(),
+            INTERNAL_ERROR => QueryError::Internal(String::new()).into(),
+            ACCESS_DENIED => QueryError::AccessDenied {
+                caller: candid::Principal::anonymous(),
+            }
+            .into(),
+            QUERY_TIMEOUT => QueryError::Timeout {
+                elapsed_ns: 0,
+                records_scanned: 0,
+            }
+            .into(),
+            UNIQUE_CONSTRAINT_VIOLATION => {
+                QueryError::UniqueConstraintViolation { column: "" }.into()
+            }
+            TABLE_NOT_FOUND => TableError::TableNotFound.into(),
+            TABLE_ALREADY_EXISTS => TableError::TableAlreadyExists.into(),
+            SCHEMA_MISMATCH => TableError::SchemaMismatch.into(),
+            NO_ACTIVE_TRANSACTION => TransactionError::NoActiveTransaction.into(),
+            UNAUTHORIZED => TransactionError::Unauthorized {
+                expected: candid::Principal::anonymous(),
+                caller: candid::Principal::anonymous(),
+            }
+            .into(),
+            SAVEPOINT_NOT_FOUND => TransactionError::SavepointNotFound(0).into(),
+            READ_ONLY_TRANSACTION => TransactionError::ReadOnlyTransaction.into(),
+            DATA_TOO_LARGE => MemoryError::DataTooLarge {
+                page_size: 0,
+                requested: 0,
+            }
+            .into(),
+            DECODE_ERROR => MemoryError::DecodeError(DecodeError::TooShort).into(),
+            FAILED_TO_ALLOCATE_PAGE => MemoryError::FailedToAllocatePage.into(),
+            OUT_OF_BOUNDS => MemoryError::OutOfBounds.into(),
+            SEGMENTATION_FAULT => MemoryError::SegmentationFault {
+                page: 0,
+                offset: 0,
+                data_size: 0,
+                page_size: 0,
+            }
+            .into(),
+            STABLE_MEMORY_ERROR => {
+                MemoryError::StableMemoryError(ic_cdk::stable::StableMemoryError::OutOfMemory)
+                    .into()
+            }
+            QUOTA_EXCEEDED => MemoryError::QuotaExceeded {
+                table_fingerprint: 0,
+                limit: 0,
+                actual: 0,
+            }
+            .into(),
+            CHECKSUM_MISMATCH => MemoryError::ChecksumMismatch {
+                page: 0,
+                expected: 0,
+                actual: 0,
+            }
+            .into(),
+            RATE_LIMIT_EXCEEDED => IcDbmsError::RateLimitExceeded {
+                caller: candid::Principal::anonymous(),
+                limit: 0,
+                reset_at: 0,
+            },
+            _ => return None,
+        };
+
+        Some(error)
+    }
 }
 
 /// IcDbms Result type
 pub type IcDbmsResult<T> = Result<T, IcDbmsError>;
+
+#[cfg(test)]
+mod tests {
+
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn test_should_have_distinct_non_zero_error_codes() {
+        let codes = error_codes::ALL.iter().copied().collect::<HashSet<_>>();
+        assert_eq!(codes.len(), error_codes::ALL.len());
+        assert!(!codes.contains(&0));
+    }
+
+    #[test]
+    fn test_should_reconstruct_error_from_each_code() {
+        for code in error_codes::ALL {
+            let error = IcDbmsError::from_code(*code).expect("unknown error code");
+            assert_eq!(error.error_code(), *code);
+        }
+        assert!(IcDbmsError::from_code(0).is_none());
+        assert!(IcDbmsError::from_code(9999).is_none());
+    }
+
+    #[test]
+    fn test_should_reconstruct_representative_errors() {
+        assert!(matches!(
+            IcDbmsError::from_code(error_codes::PRIMARY_KEY_CONFLICT),
+            Some(IcDbmsError::Query(QueryError::PrimaryKeyConflict))
+        ));
+        assert!(matches!(
+            IcDbmsError::from_code(error_codes::TABLE_NOT_FOUND),
+            Some(IcDbmsError::Table(TableError::TableNotFound))
+        ));
+        assert!(matches!(
+            IcDbmsError::from_code(error_codes::UNAUTHORIZED),
+            Some(IcDbmsError::Transaction(
+                TransactionError::Unauthorized { .. }
+            ))
+        ));
+        assert!(matches!(
+            IcDbmsError::from_code(error_codes::SEGMENTATION_FAULT),
+            Some(IcDbmsError::Memory(MemoryError::SegmentationFault { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_should_distinguish_query_and_table_not_found() {
+        assert_ne!(
+            IcDbmsError::from(QueryError::TableNotFound("users")).error_code(),
+            IcDbmsError::from(TableError::TableNotFound).error_code()
+        );
+    }
+
+    #[test]
+    fn test_should_display_errors() {
+        for code in error_codes::ALL {
+            let error = IcDbmsError::from_code(*code).expect("code should be known");
+            assert!(!error.to_string().is_empty());
+            assert!(!format!("{error:?}").is_empty());
+        }
+
+        assert_eq!(
+            IcDbmsError::Memory(MemoryError::SegmentationFault {
+                page: 3,
+                offset: 60_000,
+                data_size: 8_000,
+                page_size: 65_536,
+            })
+            .to_string(),
+            "Memory error: Segmentation fault: tried to write 8000 bytes at offset 60000 of page 3, \
+             beyond the page size of 65536 bytes"
+        );
+        assert_eq!(
+            IcDbmsError::Query(QueryError::BrokenForeignKeyReference {
+                table: "users",
+                key: Value::Uint32(9999.into()),
+            })
+            .to_string(),
+            "Query error: Broken foreign key reference to table 'users' with key Uint32(9999)"
+        );
+        assert_eq!(
+            IcDbmsError::Query(QueryError::TransactionNotFound).to_string(),
+            "Query error: Transaction not found"
+        );
+        assert_eq!(
+            IcDbmsError::Transaction(TransactionError::NoActiveTransaction).to_string(),
+            "Transaction error: No active transaction"
+        );
+    }
+}
@@ -0,0 +1,414 @@
+use candid::{CandidType, Principal};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::dbms::query::QueryError;
+use crate::dbms::table::TableError;
+use crate::dbms::transaction::TransactionError;
+use crate::error::IcDbmsError;
+use crate::memory::{MSize, MemoryError, Page, PageOffset};
+
+/// Candid-serializable representation of [`IcDbmsError`], which can be returned by the canister endpoints.
+///
+/// The [`CandidIcDbmsError::error_code`] allows to distinguish the errors programmatically,
+/// without depending on the error types; see [`crate::error::error_codes`].
+#[derive(Debug, Error, Clone, PartialEq, Eq, CandidType, Serialize, Deserialize)]
+#[error("{error}")]
+pub struct CandidIcDbmsError {
+    /// Stable code of the error, as defined in [`crate::error::error_codes`].
+    pub error_code: u32,
+    /// The error.
+    pub error: CandidIcDbmsErrorKind,
+}
+
+/// The kind of a [`CandidIcDbmsError`], mirroring the variants of [`IcDbmsError`].
+#[derive(Debug, Error, Clone, PartialEq, Eq, CandidType, Serialize, Deserialize)]
+pub enum CandidIcDbmsErrorKind {
+    #[error("Memory error: {0}")]
+    Memory(CandidMemoryError),
+    #[error("Query error: {0}")]
+    Query(CandidQueryError),
+    #[error("Table error: {0}")]
+    Table(CandidTableError),
+    #[error("Transaction error: {0}")]
+    Transaction(CandidTransactionError),
+    #[error(
+        "Rate limit exceeded for caller {caller}: at most {limit} calls allowed until {reset_at}"
+    )]
+    RateLimitExceeded {
+        caller: Principal,
+        limit: u32,
+        reset_at: u64,
+    },
+}
+
+impl CandidIcDbmsError {
+    /// Returns the human readable description of the error.
+    pub fn to_human_readable(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl From<IcDbmsError> for CandidIcDbmsError {
+    fn from(err: IcDbmsError) -> Self {
+        let error_code = err.error_code();
+        let error = match err {
+            IcDbmsError::Memory(err) => CandidIcDbmsErrorKind::Memory(err.into()),
+            IcDbmsError::Query(err) => CandidIcDbmsErrorKind::Query(err.into()),
+            IcDbmsError::Table(err) => CandidIcDbmsErrorKind::Table(err.into()),
+            IcDbmsError::Transaction(err) => CandidIcDbmsErrorKind::Transaction(err.into()),
+            IcDbmsError::RateLimitExceeded {
+                caller,
+                limit,
+                reset_at,
+            } => CandidIcDbmsErrorKind::RateLimitExceeded {
+                caller,
+                limit,
+                reset_at,
+            },
+        };
+        CandidIcDbmsError { error_code, error }
+    }
+}
+
+/// Candid-serializable representation of [`QueryError`].
+///
+/// The values of the original error are converted to their textual representation.
+#[derive(Debug, Error, Clone, PartialEq, Eq, CandidType, Serialize, Deserialize)]
+pub enum CandidQueryError {
+    #[error("Primary key conflict: record with the same primary key already exists")]
+    PrimaryKeyConflict,
+    #[error("Broken foreign key reference to table '{table}' with key '{key}'")]
+    BrokenForeignKeyReference { table: String, key: String },
+    #[error("Foreign key constraint violation on table '{referencing_table}' for field '{field}'")]
+    ForeignKeyConstraintViolation {
+        referencing_table: String,
+        field: String,
+    },
+    #[error("Unknown column: {0}")]
+    UnknownColumn(String),
+    #[error("Missing non-nullable field: {0}")]
+    MissingNonNullableField(String),
+    #[error("Type mismatch: expected {expected}, found {found}")]
+    TypeMismatch {
+        column: String,
+        expected: String,
+        found: String,
+    },
+    #[error("transaction not found")]
+    TransactionNotFound,
+    #[error("Invalid query: {0}")]
+    InvalidQuery(String),
+    #[error(
+        "Check constraint violation on column '{column}' with value {value}: {constraint_desc}"
+    )]
+    CheckConstraintViolation {
+        column: String,
+        constraint_desc: String,
+        value: String,
+    },
+    #[error("Unique constraint violation on column '{column}'")]
+    UniqueConstraintViolation { column: String },
+    #[error("Constraint violation: {0}")]
+    ConstraintViolation(String),
+    #[error("Memory error: {0}")]
+    MemoryError(CandidMemoryError),
+    #[error("Table not found: {0}")]
+    TableNotFound(String),
+    #[error("Record not found")]
+    RecordNotFound,
+    #[error("Too many results: expected one record, found at least {0}")]
+    TooManyResults(u64),
+    #[error("Serialization error: {0}")]
+    SerializationError(String),
+    #[error("Internal error: {0}")]
+    Internal(String),
+    #[error("Access denied for caller {caller}")]
+    AccessDenied { caller: Principal },
+    #[error("Query timed out after {elapsed_ns}ns, having scanned {records_scanned} records")]
+    Timeout {
+        elapsed_ns: u64,
+        records_scanned: u64,
+    },
+}
+
+impl From<QueryError> for CandidQueryError {
+    fn from(err: QueryError) -> Self {
+        match err {
+            QueryError::PrimaryKeyConflict => CandidQueryError::PrimaryKeyConflict,
+            QueryError::BrokenForeignKeyReference { table, key } => {
+                CandidQueryError::BrokenForeignKeyReference {
+                    table: table.to_string(),
+                    key: format!("{key:?}"),
+                }
+            }
+            QueryError::ForeignKeyConstraintViolation {
+                referencing_table,
+                field,
+            } => CandidQueryError::ForeignKeyConstraintViolation {
+                referencing_table: referencing_table.to_string(),
+                field: field.to_string(),
+            },
+            QueryError::UnknownColumn(column) => CandidQueryError::UnknownColumn(column),
+            QueryError::MissingNonNullableField(field) => {
+                CandidQueryError::MissingNonNullableField(field.to_string())
+            }
+            QueryError::TypeMismatch {
+                column,
+                expected,
+                found,
+            } => CandidQueryError::TypeMismatch {
+                column: column.to_string(),
+                expected: expected.to_string(),
+                found: found.to_string(),
+            },
+            QueryError::TransactionNotFound => CandidQueryError::TransactionNotFound,
+            QueryError::InvalidQuery(reason) => CandidQueryError::InvalidQuery(reason),
+            QueryError::CheckConstraintViolation {
+                column,
+                constraint_desc,
+                value,
+            } => CandidQueryError::CheckConstraintViolation {
+                column: column.to_string(),
+                constraint_desc: constraint_desc.to_string(),
+                value: format!("{value:?}"),
+            },
+            QueryError::UniqueConstraintViolation { column } => {
+                CandidQueryError::UniqueConstraintViolation {
+                    column: column.to_string(),
+                }
+            }
+            QueryError::ConstraintViolation(reason) => {
+                CandidQueryError::ConstraintViolation(reason)
+            }
+            QueryError::MemoryError(err) => CandidQueryError::MemoryError(err.into()),
+            QueryError::TableNotFound(table) => CandidQueryError::TableNotFound(table.to_string()),
+            QueryError::RecordNotFound => CandidQueryError::RecordNotFound,
+            QueryError::TooManyResults(count) => CandidQueryError::TooManyResults(count as u64),
+            QueryError::SerializationError(reason) => CandidQueryError::SerializationError(reason),
+            QueryError::Internal(reason) => CandidQueryError::Internal(reason),
+            QueryError::AccessDenied { caller } => CandidQueryError::AccessDenied { caller },
+            QueryError::Timeout {
+                elapsed_ns,
+                records_scanned,
+            } => CandidQueryError::Timeout {
+                elapsed_ns,
+                records_scanned,
+            },
+        }
+    }
+}
+
+/// Candid-serializable representation of [`TableError`].
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq, CandidType, Serialize, Deserialize)]
+pub enum CandidTableError {
+    #[error("Table not found")]
+    TableNotFound,
+    #[error("Table already exists")]
+    TableAlreadyExists,
+    #[error("Schema mismatch")]
+    SchemaMismatch,
+}
+
+impl From<TableError> for CandidTableError {
+    fn from(err: TableError) -> Self {
+        match err {
+            TableError::TableNotFound => CandidTableError::TableNotFound,
+            TableError::TableAlreadyExists => CandidTableError::TableAlreadyExists,
+            TableError::SchemaMismatch => CandidTableError::SchemaMismatch,
+        }
+    }
+}
+
+/// Candid-serializable representation of [`TransactionError`].
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq, CandidType, Serialize, Deserialize)]
+pub enum CandidTransactionError {
+    #[error("No active transaction")]
+    NoActiveTransaction,
+    #[error("Caller {caller} is not authorized to operate on the transaction owned by {expected}")]
+    Unauthorized {
+        expected: Principal,
+        caller: Principal,
+    },
+    #[error("Savepoint {0} not found")]
+    SavepointNotFound(u64),
+    #[error("Read-only transactions can't perform write operations")]
+    ReadOnlyTransaction,
+}
+
+impl From<TransactionError> for CandidTransactionError {
+    fn from(err: TransactionError) -> Self {
+        match err {
+            TransactionError::NoActiveTransaction => CandidTransactionError::NoActiveTransaction,
+            TransactionError::Unauthorized { expected, caller } => {
+                CandidTransactionError::Unauthorized { expected, caller }
+            }
+            TransactionError::SavepointNotFound(id) => {
+                CandidTransactionError::SavepointNotFound(id)
+            }
+            TransactionError::ReadOnlyTransaction => CandidTransactionError::ReadOnlyTransaction,
+        }
+    }
+}
+
+/// Candid-serializable representation of [`MemoryError`].
+///
+/// The underlying decoding and stable memory errors are converted to their textual representation.
+#[derive(Debug, Error, Clone, PartialEq, Eq, CandidType, Serialize, Deserialize)]
+pub enum CandidMemoryError {
+    #[error("Data too large for page (page size: {page_size}, requested: {requested})")]
+    DataTooLarge { page_size: u64, requested: u64 },
+    #[error("Failed to decode data from bytes: {0}")]
+    DecodeError(String),
+    #[error("Failed to allocate a new page")]
+    FailedToAllocatePage,
+    #[error("Stable memory access out of bounds")]
+    OutOfBounds,
+    #[error(
+        "Segmentation fault: tried to write {data_size} bytes at offset {offset} of page {page}, beyond the page size of {page_size} bytes"
+    )]
+    SegmentationFault {
+        page: Page,
+        offset: PageOffset,
+        data_size: MSize,
+        page_size: u64,
+    },
+    #[error("Failed to grow stable memory: {0}")]
+    StableMemoryError(String),
+    #[error("Quota exceeded for table {table_fingerprint} (limit: {limit}, actual: {actual})")]
+    QuotaExceeded {
+        table_fingerprint: u64,
+        limit: u64,
+        actual: u64,
+    },
+    #[error(
+        "Checksum mismatch on page {page} (expected: {expected:#010x}, actual: {actual:#010x})"
+    )]
+    ChecksumMismatch {
+        page: Page,
+        expected: u32,
+        actual: u32,
+    },
+}
+
+impl From<MemoryError> for CandidMemoryError {
+    fn from(err: MemoryError) -> Self {
+        match err {
+            MemoryError::DataTooLarge {
+                page_size,
+                requested,
+            } => CandidMemoryError::DataTooLarge {
+                page_size,
+                requested,
+            },
+            MemoryError::DecodeError(err) => CandidMemoryError::DecodeError(err.to_string()),
+            MemoryError::FailedToAllocatePage => CandidMemoryError::FailedToAllocatePage,
+            MemoryError::OutOfBounds => CandidMemoryError::OutOfBounds,
+            MemoryError::SegmentationFault {
+                page,
+                offset,
+                data_size,
+                page_size,
+            } => CandidMemoryError::SegmentationFault {
+                page,
+                offset,
+                data_size,
+                page_size,
+            },
+            MemoryError::StableMemoryError(err) => {
+                CandidMemoryError::StableMemoryError(err.to_string())
+            }
+            MemoryError::QuotaExceeded {
+                table_fingerprint,
+                limit,
+                actual,
+            } => CandidMemoryError::QuotaExceeded {
+                table_fingerprint,
+                limit,
+                actual,
+            },
+            MemoryError::ChecksumMismatch {
+                page,
+                expected,
+                actual,
+            } => CandidMemoryError::ChecksumMismatch {
+                page,
+                expected,
+                actual,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::dbms::types::Uint32;
+    use crate::dbms::value::Value;
+
+    #[test]
+    fn test_should_convert_primary_key_conflict() {
+        let error = CandidIcDbmsError::from(IcDbmsError::Query(QueryError::PrimaryKeyConflict));
+        assert_eq!(
+            error,
+            CandidIcDbmsError {
+                error_code: crate::error::error_codes::PRIMARY_KEY_CONFLICT,
+                error: CandidIcDbmsErrorKind::Query(CandidQueryError::PrimaryKeyConflict),
+            }
+        );
+        assert_eq!(
+            error.to_human_readable(),
+            "Query error: Primary key conflict: record with the same primary key already exists"
+        );
+    }
+
+    #[test]
+    fn test_should_convert_values_to_text() {
+        let error =
+            CandidIcDbmsError::from(IcDbmsError::Query(QueryError::BrokenForeignKeyReference {
+                table: "users",
+                key: Value::Uint32(Uint32(1)),
+            }));
+        assert_eq!(
+            error.error,
+            CandidIcDbmsErrorKind::Query(CandidQueryError::BrokenForeignKeyReference {
+                table: "users".to_string(),
+                key: format!("{:?}", Value::Uint32(Uint32(1))),
+            })
+        );
+        assert_eq!(
+            error.error_code,
+            crate::error::error_codes::FOREIGN_KEY_VIOLATION
+        );
+    }
+
+    #[test]
+    fn test_should_candid_encode_decode() {
+        let src = CandidIcDbmsError {
+            error_code: crate::error::error_codes::UNAUTHORIZED,
+            error: CandidIcDbmsErrorKind::Transaction(CandidTransactionError::Unauthorized {
+                expected: Principal::anonymous(),
+                caller: Principal::management_canister(),
+            }),
+        };
+        let buf = candid::encode_one(&src).expect("Candid encoding failed");
+        let decoded: CandidIcDbmsError = candid::decode_one(&buf).expect("Candid decoding failed");
+        assert_eq!(src, decoded);
+
+        let src = CandidIcDbmsError {
+            error_code: crate::error::error_codes::QUERY_MEMORY_ERROR,
+            error: CandidIcDbmsErrorKind::Query(CandidQueryError::MemoryError(
+                CandidMemoryError::SegmentationFault {
+                    page: 1,
+                    offset: 2,
+                    data_size: 3,
+                    page_size: 4,
+                },
+            )),
+        };
+        let buf = candid::encode_one(&src).expect("Candid encoding failed");
+        let decoded: CandidIcDbmsError = candid::decode_one(&buf).expect("Candid decoding failed");
+        assert_eq!(src, decoded);
+    }
+}
@@ -0,0 +1,121 @@
+//! Stable numeric codes of the DBMS errors, returned by the `error_code` method of each error type
+//! and by [`super::CandidIcDbmsError::error_code`].
+//!
+//! Codes are grouped by error type: `1xxx` for [`crate::prelude::QueryError`], `2xxx` for [`crate::prelude::TableError`],
+//! `3xxx` for [`crate::prelude::TransactionError`], `4xxx` for [`crate::prelude::MemoryError`]
+//! and `5xxx` for the errors raised by the canister itself, such as [`crate::prelude::IcDbmsError::RateLimitExceeded`].
+//! Codes are never reused or changed, so they can be matched by external canisters.
+
+/// [`crate::prelude::QueryError::PrimaryKeyConflict`]
+pub const PRIMARY_KEY_CONFLICT: u32 = 1001;
+/// [`crate::prelude::QueryError::BrokenForeignKeyReference`]
+pub const FOREIGN_KEY_VIOLATION: u32 = 1002;
+/// [`crate::prelude::QueryError::ForeignKeyConstraintViolation`]
+pub const FOREIGN_KEY_CONSTRAINT_VIOLATION: u32 = 1003;
+/// [`crate::prelude::QueryError::UnknownColumn`]
+pub const UNKNOWN_COLUMN: u32 = 1004;
+/// [`crate::prelude::QueryError::MissingNonNullableField`]
+pub const MISSING_NON_NULLABLE_FIELD: u32 = 1005;
+/// [`crate::prelude::QueryError::TypeMismatch`]
+pub const TYPE_MISMATCH: u32 = 1006;
+/// [`crate::prelude::QueryError::TransactionNotFound`]
+pub const TRANSACTION_NOT_FOUND: u32 = 1007;
+/// [`crate::prelude::QueryError::InvalidQuery`]
+pub const INVALID_QUERY: u32 = 1008;
+/// [`crate::prelude::QueryError::CheckConstraintViolation`]
+pub const CHECK_CONSTRAINT_VIOLATION: u32 = 1009;
+/// [`crate::prelude::QueryError::ConstraintViolation`]
+pub const CONSTRAINT_VIOLATION: u32 = 1010;
+/// [`crate::prelude::QueryError::MemoryError`]
+pub const QUERY_MEMORY_ERROR: u32 = 1011;
+/// [`crate::prelude::QueryError::TableNotFound`]
+pub const QUERY_TABLE_NOT_FOUND: u32 = 1012;
+/// [`crate::prelude::QueryError::RecordNotFound`]
+pub const RECORD_NOT_FOUND: u32 = 1013;
+/// [`crate::prelude::QueryError::TooManyResults`]
+pub const TOO_MANY_RESULTS: u32 = 1014;
+/// [`crate::prelude::QueryError::SerializationError`]
+pub const SERIALIZATION_ERROR: u32 = 1015;
+/// [`crate::prelude::QueryError::Internal`]
+pub const INTERNAL_ERROR: u32 = 1016;
+/// [`crate::prelude::QueryError::AccessDenied`]
+pub const ACCESS_DENIED: u32 = 1017;
+/// [`crate::prelude::QueryError::Timeout`]
+pub const QUERY_TIMEOUT: u32 = 1018;
+/// [`crate::prelude::QueryError::UniqueConstraintViolation`]
+pub const UNIQUE_CONSTRAINT_VIOLATION: u32 = 1019;
+
+/// [`crate::prelude::TableError::TableNotFound`]
+pub const TABLE_NOT_FOUND: u32 = 2001;
+/// [`crate::prelude::TableError::TableAlreadyExists`]
+pub const TABLE_ALREADY_EXISTS: u32 = 2002;
+/// [`crate::prelude::TableError::SchemaMismatch`]
+pub const SCHEMA_MISMATCH: u32 = 2003;
+
+/// [`crate::prelude::TransactionError::NoActiveTransaction`]
+pub const NO_ACTIVE_TRANSACTION: u32 = 3001;
+/// [`crate::prelude::TransactionError::Unauthorized`]
+pub const UNAUTHORIZED: u32 = 3002;
+/// [`crate::prelude::TransactionError::SavepointNotFound`]
+pub const SAVEPOINT_NOT_FOUND: u32 = 3003;
+/// [`crate::prelude::TransactionError::ReadOnlyTransaction`]
+pub const READ_ONLY_TRANSACTION: u32 = 3004;
+
+/// [`crate::prelude::MemoryError::DataTooLarge`]
+pub const DATA_TOO_LARGE: u32 = 4001;
+/// [`crate::prelude::MemoryError::DecodeError`]
+pub const DECODE_ERROR: u32 = 4002;
+/// [`crate::prelude::MemoryError::FailedToAllocatePage`]
+pub const FAILED_TO_ALLOCATE_PAGE: u32 = 4003;
+/// [`crate::prelude::MemoryError::OutOfBounds`]
+pub const OUT_OF_BOUNDS: u32 = 4004;
+/// [`crate::prelude::MemoryError::SegmentationFault`]
+pub const SEGMENTATION_FAULT: u32 = 4005;
+/// [`crate::prelude::MemoryError::StableMemoryError`]
+pub const STABLE_MEMORY_ERROR: u32 = 4006;
+/// [`crate::prelude::MemoryError::QuotaExceeded`]
+pub const QUOTA_EXCEEDED: u32 = 4007;
+/// [`crate::prelude::MemoryError::ChecksumMismatch`]
+pub const CHECKSUM_MISMATCH: u32 = 4008;
+
+/// [`crate::prelude::IcDbmsError::RateLimitExceeded`]
+pub const RATE_LIMIT_EXCEEDED: u32 = 5001;
+
+/// All the error codes.
+pub const ALL: &[u32] = &[
+    PRIMARY_KEY_CONFLICT,
+    FOREIGN_KEY_VIOLATION,
+    FOREIGN_KEY_CONSTRAINT_VIOLATION,
+    UNKNOWN_COLUMN,
+    MISSING_NON_NULLABLE_FIELD,
+    TYPE_MISMATCH,
+    TRANSACTION_NOT_FOUND,
+    INVALID_QUERY,
+    CHECK_CONSTRAINT_VIOLATION,
+    CONSTRAINT_VIOLATION,
+    QUERY_MEMORY_ERROR,
+    QUERY_TABLE_NOT_FOUND,
+    RECORD_NOT_FOUND,
+    TOO_MANY_RESULTS,
+    SERIALIZATION_ERROR,
+    INTERNAL_ERROR,
+    ACCESS_DENIED,
+    QUERY_TIMEOUT,
+    UNIQUE_CONSTRAINT_VIOLATION,
+    TABLE_NOT_FOUND,
+    TABLE_ALREADY_EXISTS,
+    SCHEMA_MISMATCH,
+    NO_ACTIVE_TRANSACTION,
+    UNAUTHORIZED,
+    SAVEPOINT_NOT_FOUND,
+    READ_ONLY_TRANSACTION,
+    DATA_TOO_LARGE,
+    DECODE_ERROR,
+    FAILED_TO_ALLOCATE_PAGE,
+    OUT_OF_BOUNDS,
+    SEGMENTATION_FAULT,
+    STABLE_MEMORY_ERROR,
+    QUOTA_EXCEEDED,
+    CHECKSUM_MISMATCH,
+    RATE_LIMIT_EXCEEDED,
+];
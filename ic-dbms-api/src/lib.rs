@@ -50,16 +50,19 @@
 //!
 //! ### Transaction
 //!
+//! - [`SavepointId`](crate::prelude::SavepointId)
 //! - [`TransactionError`](crate::prelude::TransactionError)
 //! - [`TransactionId`](crate::prelude::TransactionId)
 //!
 //! ### Types
 //!
+//! - [`Array`](crate::prelude::Array)
 //! - [`Blob`](crate::prelude::Blob)
 //! - [`Boolean`](crate::prelude::Boolean)
 //! - [`Date`](crate::prelude::Date)
 //! - [`DateTime`](crate::prelude::DateTime)
 //! - [`Decimal`](crate::prelude::Decimal)
+//! - [`Float64`](crate::prelude::Float64)
 //! - [`Int32`](crate::prelude::Int32)
 //! - [`Int64`](crate::prelude::Int64)
 //! - [`Nullable`](crate::prelude::Nullable)
@@ -2,6 +2,8 @@ use std::borrow::Cow;
 
 use crate::memory::{MSize, MemoryResult};
 
+mod collections;
+
 /// This trait defines the encoding and decoding behaviour for data types used in the DBMS canister.
 pub trait Encode: Clone {
     const SIZE: DataSize;
@@ -14,6 +16,17 @@ pub trait Encode: Clone {
     where
         Self: Sized;
 
+    /// Decodes the data type from a borrowed slice of bytes.
+    ///
+    /// By default it calls [`Encode::decode`] with a borrowed [`Cow`];
+    /// fixed-size types override it to decode the bytes in place, without any allocation.
+    fn decode_ref(data: &[u8]) -> MemoryResult<Self>
+    where
+        Self: Sized,
+    {
+        Self::decode(Cow::Borrowed(data))
+    }
+
     /// Returns the size in bytes of the encoded data type.
     fn size(&self) -> MSize;
 }
@@ -0,0 +1,274 @@
+//! [`Encode`] implementations for the std collections.
+//!
+//! Collections are encoded as a `u32` length followed by the encoded items;
+//! map entries are encoded as the key followed by the value.
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::hash::Hash;
+
+use crate::memory::{DataSize, DecodeError, Encode, MSize, MemoryError, MemoryResult};
+
+/// Size in bytes of the encoded length of a collection.
+const LEN_SIZE: MSize = 4;
+
+/// Encodes the given items, preceded by their amount.
+fn encode_items<'a, T>(len: usize, items: impl Iterator<Item = &'a T>, size: MSize) -> Vec<u8>
+where
+    T: Encode + 'a,
+{
+    let mut encoded = Vec::with_capacity(size as usize);
+    encoded.extend_from_slice(&(len as u32).to_le_bytes());
+    for item in items {
+        encoded.extend_from_slice(&item.encode());
+    }
+    encoded
+}
+
+/// Reads the length of a collection, returning it along with the offset of the first item.
+fn decode_len(data: &[u8]) -> MemoryResult<(usize, usize)> {
+    let len = data
+        .get(..LEN_SIZE as usize)
+        .ok_or(MemoryError::DecodeError(DecodeError::TooShort))?;
+    Ok((
+        u32::from_le_bytes(len.try_into()?) as usize,
+        LEN_SIZE as usize,
+    ))
+}
+
+/// Decodes the item at the given offset, advancing the offset past it.
+fn decode_item<T>(data: &[u8], offset: &mut usize) -> MemoryResult<T>
+where
+    T: Encode,
+{
+    let item = T::decode_ref(
+        data.get(*offset..)
+            .ok_or(MemoryError::DecodeError(DecodeError::TooShort))?,
+    )?;
+    *offset += item.size() as usize;
+    Ok(item)
+}
+
+impl<T> Encode for Vec<T>
+where
+    T: Encode,
+{
+    const SIZE: DataSize = DataSize::Dynamic;
+
+    fn size(&self) -> MSize {
+        LEN_SIZE + self.iter().map(Encode::size).sum::<MSize>()
+    }
+
+    fn encode(&'_ self) -> Cow<'_, [u8]> {
+        Cow::Owned(encode_items(self.len(), self.iter(), self.size()))
+    }
+
+    fn decode(data: Cow<[u8]>) -> MemoryResult<Self>
+    where
+        Self: Sized,
+    {
+        let (len, mut offset) = decode_len(&data)?;
+        (0..len).map(|_| decode_item(&data, &mut offset)).collect()
+    }
+}
+
+impl<T> Encode for HashSet<T>
+where
+    T: Encode + Eq + Hash,
+{
+    const SIZE: DataSize = DataSize::Dynamic;
+
+    fn size(&self) -> MSize {
+        LEN_SIZE + self.iter().map(Encode::size).sum::<MSize>()
+    }
+
+    fn encode(&'_ self) -> Cow<'_, [u8]> {
+        Cow::Owned(encode_items(self.len(), self.iter(), self.size()))
+    }
+
+    fn decode(data: Cow<[u8]>) -> MemoryResult<Self>
+    where
+        Self: Sized,
+    {
+        let (len, mut offset) = decode_len(&data)?;
+        (0..len).map(|_| decode_item(&data, &mut offset)).collect()
+    }
+}
+
+impl<K, V> Encode for HashMap<K, V>
+where
+    K: Encode + Eq + Hash,
+    V: Encode,
+{
+    const SIZE: DataSize = DataSize::Dynamic;
+
+    fn size(&self) -> MSize {
+        LEN_SIZE
+            + self
+                .iter()
+                .map(|(key, value)| key.size() + value.size())
+                .sum::<MSize>()
+    }
+
+    fn encode(&'_ self) -> Cow<'_, [u8]> {
+        let mut encoded = Vec::with_capacity(self.size() as usize);
+        encoded.extend_from_slice(&(self.len() as u32).to_le_bytes());
+        for (key, value) in self {
+            encoded.extend_from_slice(&key.encode());
+            encoded.extend_from_slice(&value.encode());
+        }
+        Cow::Owned(encoded)
+    }
+
+    fn decode(data: Cow<[u8]>) -> MemoryResult<Self>
+    where
+        Self: Sized,
+    {
+        let (len, mut offset) = decode_len(&data)?;
+        (0..len)
+            .map(|_| {
+                Ok((
+                    decode_item(&data, &mut offset)?,
+                    decode_item(&data, &mut offset)?,
+                ))
+            })
+            .collect()
+    }
+}
+
+impl<K, V> Encode for BTreeMap<K, V>
+where
+    K: Encode + Ord,
+    V: Encode,
+{
+    const SIZE: DataSize = DataSize::Dynamic;
+
+    fn size(&self) -> MSize {
+        LEN_SIZE
+            + self
+                .iter()
+                .map(|(key, value)| key.size() + value.size())
+                .sum::<MSize>()
+    }
+
+    fn encode(&'_ self) -> Cow<'_, [u8]> {
+        let mut encoded = Vec::with_capacity(self.size() as usize);
+        encoded.extend_from_slice(&(self.len() as u32).to_le_bytes());
+        for (key, value) in self {
+            encoded.extend_from_slice(&key.encode());
+            encoded.extend_from_slice(&value.encode());
+        }
+        Cow::Owned(encoded)
+    }
+
+    fn decode(data: Cow<[u8]>) -> MemoryResult<Self>
+    where
+        Self: Sized,
+    {
+        let (len, mut offset) = decode_len(&data)?;
+        (0..len)
+            .map(|_| {
+                Ok((
+                    decode_item(&data, &mut offset)?,
+                    decode_item(&data, &mut offset)?,
+                ))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::dbms::types::{Boolean, Int64, Nullable, Text, Uint32};
+
+    fn roundtrip<T>(value: T)
+    where
+        T: Encode + PartialEq + std::fmt::Debug,
+    {
+        let encoded = value.encode();
+        assert_eq!(encoded.len(), value.size() as usize);
+        let decoded = T::decode(encoded).expect("failed to decode");
+        assert_eq!(decoded, value);
+    }
+
+    #[test]
+    fn test_should_encode_and_decode_vec() {
+        roundtrip(Vec::<Uint32>::new());
+        roundtrip((0..100).map(Uint32).collect::<Vec<_>>());
+        roundtrip(
+            (0..100)
+                .map(|i| Text(format!("item {i}")))
+                .collect::<Vec<_>>(),
+        );
+        roundtrip(vec![
+            Nullable::Value(Int64(-1)),
+            Nullable::Null,
+            Nullable::Value(Int64(i64::MAX)),
+        ]);
+        roundtrip(vec![vec![Boolean(true)], vec![], vec![Boolean(false); 3]]);
+    }
+
+    #[test]
+    fn test_should_encode_and_decode_hash_set() {
+        roundtrip(HashSet::<Text>::new());
+        roundtrip((0..100).map(Uint32).collect::<HashSet<_>>());
+        roundtrip(
+            (0..100)
+                .map(|i| Text(format!("item {i}")))
+                .collect::<HashSet<_>>(),
+        );
+    }
+
+    #[test]
+    fn test_should_encode_and_decode_hash_map() {
+        roundtrip(HashMap::<Uint32, Text>::new());
+        roundtrip(
+            (0..100)
+                .map(|i| (Uint32(i), Text(format!("value {i}"))))
+                .collect::<HashMap<_, _>>(),
+        );
+        roundtrip(
+            (0..100)
+                .map(|i| (Text(format!("key {i}")), Int64(-i64::from(i))))
+                .collect::<HashMap<_, _>>(),
+        );
+        roundtrip(
+            (0..10)
+                .map(|i| (Uint32(i), (0..i).map(Uint32).collect::<Vec<_>>()))
+                .collect::<HashMap<_, _>>(),
+        );
+    }
+
+    #[test]
+    fn test_should_encode_and_decode_btree_map() {
+        roundtrip(BTreeMap::<Text, Uint32>::new());
+        roundtrip(
+            (0..100)
+                .map(|i| (Text(format!("key {i}")), Uint32(i)))
+                .collect::<BTreeMap<_, _>>(),
+        );
+        roundtrip(
+            (0..100)
+                .map(|i| {
+                    (
+                        Int64(i64::from(i) - 50),
+                        Nullable::Value(Boolean(i % 2 == 0)),
+                    )
+                })
+                .collect::<BTreeMap<_, _>>(),
+        );
+    }
+
+    #[test]
+    fn test_should_not_decode_truncated_collection() {
+        let encoded = (0..10)
+            .map(Uint32)
+            .collect::<Vec<_>>()
+            .encode()
+            .into_owned();
+        assert!(Vec::<Uint32>::decode(Cow::Borrowed(&encoded[..2])).is_err());
+        assert!(Vec::<Uint32>::decode(Cow::Borrowed(&encoded[..encoded.len() - 1])).is_err());
+    }
+}
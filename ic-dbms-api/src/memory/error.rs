@@ -2,6 +2,7 @@ use std::array::TryFromSliceError;
 
 use thiserror::Error;
 
+use crate::dbms::table::TableFingerprint;
 use crate::memory::{MSize, Page, PageOffset};
 
 /// An enum representing possible memory-related errors.
@@ -21,7 +22,7 @@ pub enum MemoryError {
     OutOfBounds,
     /// Error when attempting to write out of the allocated page.
     #[error(
-        "Tried to write out of the allocated page (page: {page}, offset: {offset}, data size: {data_size}, page size: {page_size})"
+        "Segmentation fault: tried to write {data_size} bytes at offset {offset} of page {page}, beyond the page size of {page_size} bytes"
     )]
     SegmentationFault {
         page: Page,
@@ -32,6 +33,40 @@ pub enum MemoryError {
     /// Error when failing to grow stable memory.
     #[error("Failed to grow stable memory: {0}")]
     StableMemoryError(#[from] ic_cdk::stable::StableMemoryError),
+    /// Error when an allocation would exceed the quota of a table.
+    #[error("Quota exceeded for table {table_fingerprint} (limit: {limit}, actual: {actual})")]
+    QuotaExceeded {
+        table_fingerprint: TableFingerprint,
+        limit: u64,
+        actual: u64,
+    },
+    /// Error when the checksum stored in a page doesn't match its data, meaning the page is corrupted.
+    #[error(
+        "Checksum mismatch on page {page} (expected: {expected:#010x}, actual: {actual:#010x})"
+    )]
+    ChecksumMismatch {
+        page: Page,
+        expected: u32,
+        actual: u32,
+    },
+}
+
+impl MemoryError {
+    /// Returns the stable code of the error, as defined in [`crate::error::error_codes`].
+    pub fn error_code(&self) -> u32 {
+        use crate::error::error_codes::*;
+
+        match self {
+            MemoryError::DataTooLarge { .. } => DATA_TOO_LARGE,
+            MemoryError::DecodeError(_) => DECODE_ERROR,
+            MemoryError::FailedToAllocatePage => FAILED_TO_ALLOCATE_PAGE,
+            MemoryError::OutOfBounds => OUT_OF_BOUNDS,
+            MemoryError::SegmentationFault { .. } => SEGMENTATION_FAULT,
+            MemoryError::StableMemoryError(_) => STABLE_MEMORY_ERROR,
+            MemoryError::QuotaExceeded { .. } => QUOTA_EXCEEDED,
+            MemoryError::ChecksumMismatch { .. } => CHECKSUM_MISMATCH,
+        }
+    }
 }
 
 impl From<TryFromSliceError> for MemoryError {
@@ -64,6 +99,15 @@ pub enum DecodeError {
     /// Error when the raw record header is invalid.
     #[error("Bad raw record header")]
     BadRawRecordHeader,
+    /// Error when the raw record checksum doesn't match its data.
+    #[error("Raw record checksum mismatch")]
+    ChecksumMismatch,
+    /// Error when the raw record format version is not supported.
+    #[error("Unsupported raw record format version: {0}")]
+    UnsupportedFormatVersion(u8),
+    /// Error when the compressed raw record body can't be decompressed.
+    #[error("Failed to decompress raw record: {0}")]
+    DecompressionError(String),
     /// Principal error
     #[error("Principal error: {0}")]
     PrincipalError(#[from] candid::types::principal::PrincipalError),
@@ -79,6 +123,9 @@ pub enum DecodeError {
     /// UUID error
     #[error("UUID error: {0}")]
     UuidError(uuid::Error),
+    /// Error when failing to deserialize JSON data.
+    #[error("Invalid JSON: {0}")]
+    InvalidJson(String),
 }
 
 impl From<uuid::Error> for DecodeError {
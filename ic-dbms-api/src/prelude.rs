@@ -3,13 +3,23 @@
 pub use crate::dbms::database::Database;
 pub use crate::dbms::foreign_fetcher::{ForeignFetcher, NoForeignFetcher};
 pub use crate::dbms::query::{
-    DeleteBehavior, Filter, OrderDirection, Query, QueryBuilder, QueryError, QueryResult, Select,
+    AggregateOp, BoundaryKind, CREATED_AT_COLUMN, ColumnMapping, CountQuery, DeleteBehavior,
+    DynamicValues, ExecutionEstimate, Filter, FilterParseError, Filters, IndexUsage, JoinQuery,
+    JoinRecord, JoinType, OrderDirection, Query, QueryBuilder, QueryError, QueryPlan,
+    QueryPlanNode, QueryResult, Select, UpdateBehavior,
+};
+pub use crate::dbms::sharding::{
+    ShardClient, ShardFn, ShardRoute, ShardedDatabase, ShardedTableConfig,
 };
 pub use crate::dbms::table::*;
-pub use crate::dbms::transaction::{TransactionError, TransactionId};
+pub use crate::dbms::transaction::{SavepointId, TransactionError, TransactionId};
 pub use crate::dbms::types::*;
-pub use crate::dbms::value::Value;
-pub use crate::error::{IcDbmsError, IcDbmsResult};
+pub use crate::dbms::value::{TypeError, Value};
+pub use crate::error::{
+    CandidIcDbmsError, CandidIcDbmsErrorKind, CandidMemoryError, CandidQueryError,
+    CandidTableError, CandidTransactionError, IcDbmsError, IcDbmsResult, error_codes,
+};
 pub use crate::memory::{
     DataSize, DecodeError, Encode, MSize, MemoryError, MemoryResult, Page, PageOffset,
 };
+pub use crate::{col, fk};
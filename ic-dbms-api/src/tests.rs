@@ -1,7 +1,11 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
 use ic_dbms_macros::Encode;
 
-use crate::dbms::table::{ColumnDef, TableColumns, TableRecord, TableSchema, ValuesSource};
-use crate::dbms::types::{DataTypeKind, Text, Uint32};
+use crate::dbms::table::{
+    ColumnDef, IndexDef, IndexType, TableColumns, TableRecord, TableSchema, ValuesSource,
+};
+use crate::dbms::types::{DataTypeKind, Int32, Text, Uint32};
 use crate::dbms::value::Value;
 use crate::memory::Encode;
 use crate::prelude::{
@@ -15,6 +19,33 @@ pub struct User {
     pub name: Text,
 }
 
+/// A struct with optional fields for testing purposes.
+#[derive(Debug, Encode, Clone, PartialEq, Eq)]
+pub struct Profile {
+    pub id: Uint32,
+    pub bio: Option<Text>,
+    pub age: Option<Int32>,
+}
+
+/// A struct with a field which is not encoded for testing purposes.
+#[derive(Debug, Encode, Clone, PartialEq, Eq)]
+pub struct Point {
+    pub x: Int32,
+    pub y: Int32,
+    #[encode(skip)]
+    pub dirty: bool,
+}
+
+/// A struct with collection fields for testing purposes.
+#[derive(Debug, Encode, Clone, PartialEq, Eq)]
+pub struct Inventory {
+    pub id: Uint32,
+    pub tags: Vec<Text>,
+    pub stock: HashMap<Text, Uint32>,
+    pub prices: BTreeMap<Uint32, Int32>,
+    pub owners: HashSet<Uint32>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct UserRecord {
     pub id: Option<Uint32>,
@@ -74,11 +105,11 @@ impl InsertRecord for UserInsertRequest {
         ]
     }
 
-    fn into_record(self) -> Self::Schema {
-        User {
+    fn into_record(self) -> crate::prelude::IcDbmsResult<Self::Schema> {
+        Ok(User {
             id: self.id,
             name: self.name,
-        }
+        })
     }
 }
 
@@ -122,7 +153,10 @@ impl UpdateRecord for UserUpdateRequest {
                     data_type: DataTypeKind::Uint32,
                     nullable: false,
                     primary_key: true,
+                    unique: false,
+                    auto_increment: false,
                     foreign_key: None,
+                    check: None,
                 },
                 crate::dbms::value::Value::Uint32(id),
             ));
@@ -134,7 +168,10 @@ impl UpdateRecord for UserUpdateRequest {
                     data_type: DataTypeKind::Text,
                     nullable: false,
                     primary_key: false,
+                    unique: false,
+                    auto_increment: false,
                     foreign_key: None,
+                    check: None,
                 },
                 crate::dbms::value::Value::Text(name.clone()),
             ));
@@ -213,20 +250,36 @@ impl TableSchema for User {
                 data_type: DataTypeKind::Uint32,
                 nullable: false,
                 primary_key: true,
+                unique: false,
+                auto_increment: false,
                 foreign_key: None,
+                check: None,
             },
             ColumnDef {
                 name: "name",
                 data_type: DataTypeKind::Text,
                 nullable: false,
                 primary_key: false,
+                unique: false,
+                auto_increment: false,
                 foreign_key: None,
+                check: None,
             },
         ]
     }
 
-    fn primary_key() -> &'static str {
-        "id"
+    fn indexes() -> &'static [IndexDef] {
+        &[IndexDef {
+            name: "idx_users_name",
+            columns: &["name"],
+            unique: true,
+            auto_increment: false,
+            index_type: IndexType::BTree,
+        }]
+    }
+
+    fn primary_keys() -> &'static [&'static str] {
+        &["id"]
     }
 
     fn to_values(self) -> Vec<(ColumnDef, Value)> {
@@ -253,6 +306,112 @@ mod tests {
         assert_eq!(user, decoded);
     }
 
+    #[test]
+    fn test_should_encode_decode_optional_fields() {
+        assert_eq!(Profile::SIZE, crate::memory::DataSize::Dynamic);
+
+        let profile = Profile {
+            id: 1u32.into(),
+            bio: Some("Hello".to_string().into()),
+            age: Some(Int32(42)),
+        };
+        let encoded = profile.encode();
+        assert_eq!(encoded.len(), profile.size() as usize);
+        let decoded = Profile::decode(encoded).unwrap();
+        assert_eq!(profile, decoded);
+
+        let profile = Profile {
+            id: 2u32.into(),
+            bio: None,
+            age: Some(Int32(-7)),
+        };
+        let decoded = Profile::decode(profile.encode()).unwrap();
+        assert_eq!(profile, decoded);
+    }
+
+    #[test]
+    fn test_should_encode_none_as_single_zero_byte() {
+        let profile = Profile {
+            id: 3u32.into(),
+            bio: None,
+            age: None,
+        };
+        let id_size = Uint32(3).size() as usize;
+        let encoded = profile.encode();
+        assert_eq!(encoded.len(), id_size + 2);
+        assert_eq!(&encoded[id_size..], &[0, 0]);
+        assert_eq!(Profile::decode(encoded).unwrap(), profile);
+    }
+
+    #[test]
+    fn test_should_fail_decoding_truncated_optional_field() {
+        let profile = Profile {
+            id: 4u32.into(),
+            bio: None,
+            age: None,
+        };
+        let encoded = profile.encode();
+        let truncated = &encoded[..encoded.len() - 1];
+        assert!(Profile::decode(std::borrow::Cow::Borrowed(truncated)).is_err());
+    }
+
+    #[test]
+    fn test_should_not_encode_skipped_fields() {
+        assert_eq!(Point::SIZE, crate::memory::DataSize::Fixed(8));
+
+        let point = Point {
+            x: Int32(10),
+            y: Int32(-20),
+            dirty: true,
+        };
+        assert_eq!(point.size(), 8);
+        let encoded = point.encode();
+        assert_eq!(encoded.len(), 8);
+
+        // the skipped field doesn't affect the encoded output
+        let clean_point = Point {
+            dirty: false,
+            ..point.clone()
+        };
+        assert_eq!(clean_point.encode(), encoded);
+
+        // the skipped field is restored to its default value
+        let decoded = Point::decode(encoded).unwrap();
+        assert_eq!(decoded, clean_point);
+        assert!(!decoded.dirty);
+    }
+
+    #[test]
+    fn test_should_encode_collection_fields() {
+        assert_eq!(Inventory::SIZE, crate::memory::DataSize::Dynamic);
+
+        let inventory = Inventory {
+            id: Uint32(1),
+            tags: vec![Text("fruit".to_string()), Text("fresh".to_string())],
+            stock: HashMap::from([
+                (Text("apple".to_string()), Uint32(10)),
+                (Text("pear".to_string()), Uint32(0)),
+            ]),
+            prices: BTreeMap::from([(Uint32(1), Int32(-5)), (Uint32(2), Int32(7))]),
+            owners: HashSet::new(),
+        };
+        let encoded = inventory.encode();
+        assert_eq!(encoded.len(), inventory.size() as usize);
+
+        let decoded = Inventory::decode(encoded).unwrap();
+        assert_eq!(decoded, inventory);
+    }
+
+    #[test]
+    fn test_should_have_indexes() {
+        let indexes = User::indexes();
+        assert_eq!(indexes.len(), 1);
+        assert_eq!(indexes[0].name, "idx_users_name");
+        assert_eq!(indexes[0].columns, &["name"]);
+        assert!(indexes[0].unique);
+        assert_eq!(indexes[0].index_type, IndexType::BTree);
+    }
+
     #[test]
     fn test_should_have_fingerprint() {
         let fingerprint = User::fingerprint();
@@ -1,23 +1,74 @@
 //! This module exposes all the types related to the DBMS engine.
 
+pub mod aggregate;
+pub mod budget;
+pub mod cache;
+pub mod change_log;
+pub mod health;
+pub mod import;
 pub mod integrity;
+pub mod logger;
+pub mod migration;
+pub mod rate_limiter;
 pub mod schema;
 pub mod transaction;
+pub mod watcher;
 
+use std::collections::{BTreeSet, HashSet};
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
+
+use candid::Principal;
 use ic_dbms_api::prelude::{
-    ColumnDef, Database, DeleteBehavior, Filter, ForeignFetcher, IcDbmsError, IcDbmsResult,
-    InsertRecord, OrderDirection, Query, QueryError, TableColumns, TableError, TableRecord,
-    TableSchema, TransactionError, TransactionId, UpdateRecord, Value, ValuesSource,
+    AggregateOp, CandidIcDbmsError, ColumnDef, CountQuery, DataTypeKind, Database, DateTime,
+    DeleteBehavior, ExecutionEstimate, Filter, ForeignFetcher, IcDbmsError, IcDbmsResult,
+    IndexUsage, InsertRecord, JoinQuery, JoinRecord, JoinType, MemoryError, OrderDirection, Page,
+    PageOffset, Query, QueryError, QueryPlan, QueryPlanNode, SavepointId, SoftDeleteSchema,
+    TableColumns, TableError, TableFingerprint, TableRecord, TableSchema, TransactionError,
+    TransactionId, UpdateBehavior, UpdateRecord, Value, ValuesSource,
 };
 
+use crate::dbms::aggregate::{Accumulator, GroupedAccumulator, GroupedResult};
+use crate::dbms::budget::{
+    BUDGET_CHECK_INTERVAL, BudgetStatus, Cursor, CycleBudget, PartialSelectResult,
+};
+use crate::dbms::cache::QueryCache;
+use crate::dbms::change_log::{ChangeLog, LogOp};
+use crate::dbms::health::{DEFAULT_HEALTH_CHECK_SAMPLE_SIZE, HealthCheckResult, TableHealth};
+use crate::dbms::import::{ImportResult, import_order, json_to_record_values, parse_database_json};
+use crate::dbms::integrity::{
+    InsertIntegrityValidator, IntegrityWarning, UpdateIntegrityValidator, ValidationMode,
+};
+use crate::dbms::migration::MigrationHandle;
+use crate::dbms::rate_limiter::RateLimiter;
+use crate::dbms::schema::{SchemaMismatch, export_candid, validate_schemas};
 use crate::dbms::transaction::{DatabaseOverlay, Transaction, TransactionOp};
-use crate::memory::{SCHEMA_REGISTRY, TableRegistry};
+use crate::dbms::watcher::{TableChange, notify_watchers};
+#[cfg(feature = "indexes")]
+use crate::memory::TableIndexes;
+use crate::memory::{
+    ACL, AccessControlList, IDEMPOTENCY_REGISTRY, IdempotencyRegistry, IdempotencyToken,
+    IdempotentResult, MEMORY_MANAGER, SCHEMA_REGISTRY, SchemaRegistry, TableQuota, TableRegistry,
+    TableRegistryPage, TableStats, VacuumStats, WriteAmpStats,
+};
 use crate::prelude::{DatabaseSchema, TRANSACTION_SESSION};
-use crate::utils::trap;
+use crate::utils::{caller, performance_counter, time, trap};
 
 /// Default capacity limit for SELECT queries.
 const DEFAULT_SELECT_LIMIT: usize = 128;
 
+/// Stable memory reads required to load a [`TableRegistry`] (the page ledger and the free segments ledger).
+const TABLE_REGISTRY_LOAD_READS: u64 = 2;
+
+/// Mask applied to a table fingerprint, along with the hash of the clone name, to derive the fingerprint of its clone.
+const CLONED_TABLE_FINGERPRINT_MASK: TableFingerprint = 0xC10E_C10E_C10E_C10E;
+
+/// Mask applied to a table fingerprint to derive the fingerprint of the staging table used by [`IcDbmsDatabase::vacuum`].
+const VACUUM_STAGING_FINGERPRINT_MASK: TableFingerprint = 0x57A6_57A6_57A6_57A6;
+
+/// Iterator over the column values of the records read by a scan.
+type ScannedRecords<'a> = Box<dyn Iterator<Item = IcDbmsResult<Vec<(ColumnDef, Value)>>> + 'a>;
+
 /// The main DBMS struct.
 ///
 /// This struct serves as the entry point for interacting with the DBMS engine.
@@ -25,6 +76,7 @@ const DEFAULT_SELECT_LIMIT: usize = 128;
 /// It provides methods for executing queries.
 ///
 /// - [`Database::select`] - Execute a SELECT query.
+/// - [`Database::explain_execution`] - Estimate the cost of a SELECT query.
 /// - [`Database::insert`] - Execute an INSERT query.
 /// - [`Database::update`] - Execute an UPDATE query.
 /// - [`Database::delete`] - Execute a DELETE query.
@@ -38,17 +90,55 @@ const DEFAULT_SELECT_LIMIT: usize = 128;
 /// If a transaction is active, all operations will be part of that transaction until it is committed or rolled back.
 pub struct IcDbmsDatabase {
     /// Database schema to perform generic operations, without knowing the concrete table schema at compile time.
-    schema: Box<dyn DatabaseSchema>,
+    schema: Rc<dyn DatabaseSchema>,
     /// Id of the loaded transaction, if any.
     transaction: Option<TransactionId>,
+    /// How the integrity violations of the inserts performed outside of transactions are handled.
+    validation_mode: ValidationMode,
+    /// Whether the loaded transaction is read-only, rejecting write operations.
+    read_only: bool,
+}
+
+/// The outcome of an [`IcDbmsDatabase::upsert`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpsertResult {
+    /// No record had the primary key of the upserted record, so it has been inserted.
+    Inserted,
+    /// A record with the same primary key existed, and its columns have been updated.
+    Updated,
+}
+
+/// A page of records selected with [`IcDbmsDatabase::select_page`].
+pub struct SelectPage<T>
+where
+    T: TableSchema,
+{
+    /// The selected records, in ascending primary key order unless the query orders them otherwise.
+    pub records: Vec<T::Record>,
+    /// The primary key value to select the next page after with [`ic_dbms_api::prelude::QueryBuilder::after`],
+    /// or `None` if this is the last page.
+    pub next_cursor: Option<Value>,
+}
+
+impl std::fmt::Debug for IcDbmsDatabase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IcDbmsDatabase")
+            .field("transaction", &self.transaction)
+            .field("schema", &self.schema.type_name())
+            .field("validation_mode", &self.validation_mode)
+            .field("read_only", &self.read_only)
+            .finish()
+    }
 }
 
 impl IcDbmsDatabase {
     /// Load an instance of the [`Database`] for one-shot operations (no transaction).
     pub fn oneshot(schema: impl DatabaseSchema + 'static) -> Self {
         Self {
-            schema: Box::new(schema),
+            schema: Rc::new(schema),
             transaction: None,
+            validation_mode: ValidationMode::Strict,
+            read_only: false,
         }
     }
 
@@ -58,1269 +148,7294 @@ impl IcDbmsDatabase {
         transaction_id: TransactionId,
     ) -> Self {
         Self {
-            schema: Box::new(schema),
+            schema: Rc::new(schema),
             transaction: Some(transaction_id),
+            validation_mode: ValidationMode::Strict,
+            read_only: false,
         }
     }
 
-    /// Executes a closure with a mutable reference to the current [`Transaction`].
-    fn with_transaction_mut<F, R>(&self, f: F) -> IcDbmsResult<R>
-    where
-        F: FnOnce(&mut Transaction) -> IcDbmsResult<R>,
-    {
-        let txid = self.transaction.as_ref().ok_or(IcDbmsError::Transaction(
-            TransactionError::NoActiveTransaction,
-        ))?;
+    /// Load an instance of the [`Database`] within a read-only transaction context.
+    ///
+    /// Write operations fail with [`TransactionError::ReadOnlyTransaction`] without being added to the transaction,
+    /// so committing the transaction doesn't write anything to memory.
+    /// The transaction should be begun with [`crate::dbms::transaction::TransactionSession::begin_read_only_transaction`],
+    /// which makes the [`TRANSACTION_SESSION`] reject write operations regardless of this instance.
+    pub fn from_read_only_transaction(
+        schema: impl DatabaseSchema + 'static,
+        transaction_id: TransactionId,
+    ) -> Self {
+        Self {
+            read_only: true,
+            ..Self::from_transaction(schema, transaction_id)
+        }
+    }
 
-        TRANSACTION_SESSION.with_borrow_mut(|ts| {
-            let tx = ts.get_transaction_mut(txid)?;
-            f(tx)
-        })
+    /// Sets the [`ValidationMode`] of the inserts performed outside of transactions.
+    ///
+    /// In [`ValidationMode::Lenient`] mode, [`Database::insert`] discards the integrity warnings;
+    /// use [`IcDbmsDatabase::insert_lenient`] to get them.
+    /// Inserts within a transaction are always validated in [`ValidationMode::Strict`] mode,
+    /// since they're validated again on commit.
+    pub fn with_validation_mode(mut self, mode: ValidationMode) -> Self {
+        self.validation_mode = mode;
+        self
     }
 
-    /// Executes a closure with a reference to the current [`Transaction`].
-    fn with_transaction<F, R>(&self, f: F) -> IcDbmsResult<R>
+    /// Soft-deletes the records matching the given filter.
+    ///
+    /// Instead of physically removing the records, their `deleted_at` column is set to the current time.
+    /// Soft-deleted records are excluded from [`Database::select`] results,
+    /// unless the query is built with [`ic_dbms_api::prelude::QueryBuilder::include_deleted`].
+    ///
+    /// # Arguments
+    ///
+    /// - `filter` - An optional [`Filter`] to specify which records to soft-delete.
+    ///
+    /// # Returns
+    ///
+    /// The number of rows soft-deleted.
+    pub fn soft_delete<T>(&self, filter: Option<Filter>) -> IcDbmsResult<u64>
     where
-        F: FnOnce(&Transaction) -> IcDbmsResult<R>,
+        T: SoftDeleteSchema,
     {
-        let txid = self.transaction.as_ref().ok_or(IcDbmsError::Transaction(
-            TransactionError::NoActiveTransaction,
-        ))?;
-
-        TRANSACTION_SESSION.with_borrow_mut(|ts| {
-            let tx = ts.get_transaction_mut(txid)?;
-            f(tx)
-        })
+        let deleted_at_column = T::deleted_at_column();
+        let col_def = T::columns()
+            .iter()
+            .find(|col_def| col_def.name == deleted_at_column)
+            .copied()
+            .ok_or(IcDbmsError::Query(QueryError::UnknownColumn(
+                deleted_at_column.to_string(),
+            )))?;
+        let deleted_at = Value::DateTime(DateTime::from_timestamp_nanos(time()));
+
+        let patch = T::Update::from_values(&[(col_def, deleted_at)], filter);
+        self.update::<T>(UpdateBehavior::Restrict, patch)
     }
 
-    /// Executes a closure atomically within the database context.
+    /// Restores the soft-deleted records matching the given filter, setting their `deleted_at` column to NULL.
     ///
-    /// If the closure returns an error, the changes are rolled back by trapping the canister.
-    fn atomic<F, R>(&self, f: F) -> R
+    /// Restored records are returned again by [`Database::select`].
+    ///
+    /// # Arguments
+    ///
+    /// - `filter` - An optional [`Filter`] to specify which soft-deleted records to restore.
+    ///
+    /// # Returns
+    ///
+    /// The number of rows restored.
+    pub fn restore<T>(&self, filter: Option<Filter>) -> IcDbmsResult<u64>
     where
-        F: FnOnce(&IcDbmsDatabase) -> IcDbmsResult<R>,
+        T: SoftDeleteSchema,
     {
-        match f(self) {
-            Ok(res) => res,
-            Err(err) => trap(err.to_string()),
-        }
+        let deleted_at_column = T::deleted_at_column();
+        let col_def = T::columns()
+            .iter()
+            .find(|col_def| col_def.name == deleted_at_column)
+            .copied()
+            .ok_or(IcDbmsError::Query(QueryError::UnknownColumn(
+                deleted_at_column.to_string(),
+            )))?;
+
+        // targeting the `deleted_at` column makes the soft-deleted records visible to the update
+        let deleted = Filter::not_null(deleted_at_column);
+        let filter = match filter {
+            Some(filter) => filter.and(deleted),
+            None => deleted,
+        };
+        let patch = T::Update::from_values(&[(col_def, Value::Null)], Some(filter));
+        self.update::<T>(UpdateBehavior::Restrict, patch)
     }
 
-    /// Deletes foreign key related records recursively if the delete behavior is [`DeleteBehavior::Cascade`].
-    fn delete_foreign_keys_cascade<T>(
-        &self,
-        record_values: &[(ColumnDef, Value)],
-    ) -> IcDbmsResult<u64>
+    /// Returns the fingerprint under which the clone of the table `T` named `dest_name` is registered.
+    pub fn cloned_table_fingerprint<T>(dest_name: &str) -> TableFingerprint
     where
         T: TableSchema,
     {
-        let mut count = 0;
-        // verify referenced tables for foreign key constraints
-        for (table, columns) in self.schema.referenced_tables(T::table_name()) {
-            for column in columns.iter() {
-                // prepare filter
-                let pk = record_values
-                    .iter()
-                    .find(|(col_def, _)| col_def.primary_key)
-                    .ok_or(IcDbmsError::Query(QueryError::UnknownColumn(
-                        column.to_string(),
-                    )))?
-                    .1
-                    .clone();
-                // make filter to find records in the referenced table
-                let filter = Filter::eq(column, pk);
-                let res = self
-                    .schema
-                    .delete(self, table, DeleteBehavior::Cascade, Some(filter))?;
-                count += res;
-            }
-        }
-        Ok(count)
-    }
-
-    /// Retrieves the current [`DatabaseOverlay`].
-    fn overlay(&self) -> IcDbmsResult<DatabaseOverlay> {
-        self.with_transaction(|tx| Ok(tx.overlay().clone()))
-    }
-
-    /// Returns whether the read given record matches the provided filter.
-    fn record_matches_filter(
-        &self,
-        record_values: &[(ColumnDef, Value)],
-        filter: &Filter,
-    ) -> IcDbmsResult<bool> {
-        filter.matches(record_values).map_err(IcDbmsError::from)
+        let mut hasher = std::hash::DefaultHasher::new();
+        dest_name.hash(&mut hasher);
+        T::fingerprint() ^ CLONED_TABLE_FINGERPRINT_MASK ^ hasher.finish()
     }
 
-    /// Select only the queried fields from the given record values.
+    /// Clones the table `T` into a new, independent table named `dest_name`.
     ///
-    /// It also loads eager relations if any.
-    fn select_queried_fields<T>(
-        &self,
-        mut record_values: Vec<(ColumnDef, Value)>,
-        query: &Query<T>,
-    ) -> IcDbmsResult<TableColumns>
+    /// The clone is registered in the schema registry under `dest_name`,
+    /// with the fingerprint returned by [`IcDbmsDatabase::cloned_table_fingerprint`],
+    /// and all the committed records of `T` are copied into it, along with its indexes.
+    /// Changes made to the original table after cloning are not reflected in the clone.
+    ///
+    /// Fails with [`TableError::TableAlreadyExists`] if a table is already registered as `dest_name`.
+    pub fn clone_table<T>(&self, dest_name: &str) -> IcDbmsResult<()>
     where
         T: TableSchema,
     {
-        let mut queried_fields = vec![];
-
-        // handle eager relations
-        // FIXME: currently we fetch the FK for each record, which is shit.
-        // In the future, we should batch fetch foreign keys for all records in the result set.
-        for relation in &query.eager_relations {
-            let mut fetched = false;
-            // iter all foreign key with that table
-            for (fk, fk_value) in record_values
-                .iter()
-                .filter(|(col_def, _)| {
-                    col_def
-                        .foreign_key
-                        .is_some_and(|fk| fk.foreign_table == *relation)
-                })
-                .map(|(col, value)| {
-                    (
-                        col.foreign_key.as_ref().expect("cannot be empty"),
-                        value.clone(),
-                    )
-                })
-            {
-                // get foreign values
-                queried_fields.extend(T::foreign_fetcher().fetch(
-                    self,
-                    relation,
-                    fk.local_column,
-                    fk_value,
-                )?);
-                fetched = true;
-            }
-
-            if !fetched {
-                return Err(IcDbmsError::Query(QueryError::InvalidQuery(format!(
-                    "Cannot load relation '{}' for table '{}': no foreign key found",
-                    relation,
-                    T::table_name()
-                ))));
-            }
+        let clone_fingerprint = Self::cloned_table_fingerprint::<T>(dest_name);
+        if SCHEMA_REGISTRY.with_borrow(|sr| {
+            sr.table_registry_page_by_fingerprint(clone_fingerprint)
+                .is_some()
+                || sr.fingerprint_by_name(dest_name).is_some()
+        }) {
+            return Err(IcDbmsError::Table(TableError::TableAlreadyExists));
         }
 
-        // short-circuit if all selected
-        if query.all_selected() {
-            queried_fields.extend(vec![(ValuesSource::This, record_values)]);
-            return Ok(queried_fields);
+        let table_registry = self.load_table_registry::<T>()?;
+        let records = table_registry
+            .read::<T>()
+            .map(|next| next.map(|next| next.record))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let clone_pages = SCHEMA_REGISTRY
+            .with_borrow_mut(|sr| sr.register_clone::<T>(clone_fingerprint, dest_name))?
+            .ok_or(IcDbmsError::Table(TableError::TableAlreadyExists))?;
+        let mut clone_registry = TableRegistry::load(clone_pages)?;
+        #[cfg(not(feature = "indexes"))]
+        SCHEMA_REGISTRY.with_borrow_mut(|sr| sr.mark_indexes_stale(clone_fingerprint))?;
+        #[cfg(feature = "compression")]
+        {
+            clone_registry = clone_registry.with_compression(T::use_compression());
         }
-        record_values.retain(|(col_def, _)| query.columns().contains(&col_def.name));
-        queried_fields.extend(vec![(ValuesSource::This, record_values)]);
-        Ok(queried_fields)
+        #[cfg(feature = "indexes")]
+        {
+            let indexes = SCHEMA_REGISTRY
+                .with_borrow(|sr| sr.index_registry().indexes_for_table(clone_fingerprint));
+            clone_registry = clone_registry.with_indexes(TableIndexes::new::<T>(indexes));
+        }
+        clone_registry.insert_batch(records)?;
+
+        Ok(())
     }
 
-    /// Retrieves existing primary keys for records matching the given filter.
-    fn existing_primary_keys_for_filter<T>(
-        &self,
-        filter: Option<Filter>,
-    ) -> IcDbmsResult<Vec<Value>>
+    /// Swaps the tables `T` and `U`, along with all their metadata, see [`SchemaRegistry::swap_tables`].
+    ///
+    /// After the swap, `T` reads and writes the records previously stored for `U` and vice versa.
+    /// The two tables must have identical columns.
+    /// The swap is performed within the call, so it is never left halfway.
+    pub fn swap_tables<T, U>(&self) -> IcDbmsResult<()>
     where
         T: TableSchema,
+        U: TableSchema,
     {
-        let pk = T::primary_key();
-        let fields = self.select(Query::<T>::builder().filter(filter).build())?;
-        let pks = fields
-            .into_iter()
-            .map(|record| {
-                record
-                    .to_values()
-                    .into_iter()
-                    .find(|(col_def, _value)| col_def.name == pk)
-                    .expect("primary key not found") // this can't fail.
-                    .1
-            })
-            .collect::<Vec<Value>>();
+        if T::columns() != U::columns() {
+            return Err(IcDbmsError::Table(TableError::SchemaMismatch));
+        }
 
-        Ok(pks)
+        Self::swap_fingerprints(T::fingerprint(), U::fingerprint())?;
+        QueryCache::<T>::invalidate_all();
+        QueryCache::<U>::invalidate_all();
+
+        Ok(())
     }
 
-    /// Load the table registry for the given table schema.
-    fn load_table_registry<T>(&self) -> IcDbmsResult<TableRegistry>
+    /// Swaps the table `T` with its clone named `clone_name`, made by [`IcDbmsDatabase::clone_table`],
+    /// as [`IcDbmsDatabase::swap_tables`] does, so that `T` reads and writes the records of the clone.
+    ///
+    /// Swapping them again with the same name restores them.
+    ///
+    /// Fails with [`TableError::TableNotFound`] if `T` has no clone named `clone_name`,
+    /// or with [`TableError::SchemaMismatch`] if the columns of `T` have been altered since cloning.
+    pub fn swap_with_clone<T>(&self, clone_name: &str) -> IcDbmsResult<()>
     where
         T: TableSchema,
     {
-        // get pages of the table registry from schema registry
-        let registry_pages = SCHEMA_REGISTRY
-            .with_borrow(|schema| schema.table_registry_page::<T>())
-            .ok_or(IcDbmsError::Table(TableError::TableNotFound))?;
+        let clone_fingerprint = Self::cloned_table_fingerprint::<T>(clone_name);
+        let (cloned, same_columns) = SCHEMA_REGISTRY.with_borrow(|sr| {
+            (
+                sr.table_registry_page_by_fingerprint(clone_fingerprint)
+                    .is_some(),
+                sr.table_columns(T::fingerprint()) == sr.table_columns(clone_fingerprint),
+            )
+        });
+        if !cloned {
+            return Err(IcDbmsError::Table(TableError::TableNotFound));
+        }
+        if !same_columns {
+            return Err(IcDbmsError::Table(TableError::SchemaMismatch));
+        }
 
-        TableRegistry::load(registry_pages).map_err(IcDbmsError::from)
+        Self::swap_fingerprints(T::fingerprint(), clone_fingerprint)?;
+        QueryCache::<T>::invalidate_all();
+
+        Ok(())
     }
 
-    /// Sorts the query results based on the specified column and order direction.
+    /// Swaps the tables registered under the given fingerprints, see [`SchemaRegistry::swap_tables`].
     ///
-    /// We only sort values which have [`ValuesSource::This`].
-    #[allow(clippy::type_complexity)]
-    fn sort_query_results(
-        &self,
-        results: &mut [Vec<(ValuesSource, Vec<(ColumnDef, Value)>)>],
-        column: &'static str,
-        direction: OrderDirection,
-    ) {
-        results.sort_by(|a, b| {
-            let a_value = a
-                .iter()
-                .find(|(source, _)| *source == ValuesSource::This)
-                .and_then(|(_, cols)| {
-                    cols.iter()
-                        .find(|(col_def, _)| col_def.name == column)
-                        .map(|(_, value)| value)
-                });
-            let b_value = b
-                .iter()
-                .find(|(source, _)| *source == ValuesSource::This)
-                .and_then(|(_, cols)| {
-                    cols.iter()
-                        .find(|(col_def, _)| col_def.name == column)
-                        .map(|(_, value)| value)
-                });
+    /// Fails with [`TableError::TableNotFound`] if either of them is not registered.
+    fn swap_fingerprints(a: TableFingerprint, b: TableFingerprint) -> IcDbmsResult<()> {
+        let swapped = SCHEMA_REGISTRY.with_borrow_mut(|sr| sr.swap_tables(a, b))?;
+        if !swapped {
+            return Err(IcDbmsError::Table(TableError::TableNotFound));
+        }
 
-            match (a_value, b_value) {
-                (Some(a_val), Some(b_val)) => match direction {
-                    OrderDirection::Ascending => a_val.cmp(b_val),
-                    OrderDirection::Descending => b_val.cmp(a_val),
-                },
-                (Some(_), None) => std::cmp::Ordering::Greater,
-                (None, Some(_)) => std::cmp::Ordering::Less,
-                (None, None) => std::cmp::Ordering::Equal,
-            }
-        });
+        Ok(())
     }
-}
 
-impl Database for IcDbmsDatabase {
-    /// Executes a SELECT query and returns the results.
+    /// Runs `f` within a transaction nested in the current one, committing it if `f` succeeds
+    /// and rolling it back if `f` fails.
     ///
-    /// # Arguments
+    /// `f` receives an [`IcDbmsDatabase`] operating on the nested transaction, whose changes are merged into
+    /// the current transaction on success, leaving it as is on failure; see [`crate::dbms::transaction::TransactionSession::begin_nested`].
+    /// Without a current transaction, `f` runs within a new transaction, which is committed to memory on success.
+    pub fn nested_transaction<F, R>(&self, f: F) -> IcDbmsResult<R>
+    where
+        F: FnOnce(&IcDbmsDatabase) -> IcDbmsResult<R>,
+    {
+        let transaction_id = match &self.transaction {
+            Some(parent_id) => TRANSACTION_SESSION.with_borrow_mut(|ts| {
+                ts.check_access(parent_id, caller())?;
+                ts.begin_nested(parent_id)
+            })?,
+            None => TRANSACTION_SESSION.with_borrow_mut(|ts| ts.begin_transaction(caller())),
+        };
+        let mut nested = IcDbmsDatabase {
+            schema: Rc::clone(&self.schema),
+            transaction: Some(transaction_id),
+            validation_mode: self.validation_mode,
+            read_only: self.read_only,
+        };
+
+        match f(&nested) {
+            Ok(result) => {
+                nested.commit()?;
+                Ok(result)
+            }
+            Err(err) => {
+                nested.rollback()?;
+                Err(err)
+            }
+        }
+    }
+
+    /// Takes a savepoint of the current transaction and returns its [`SavepointId`],
+    /// see [`crate::dbms::transaction::TransactionSession::savepoint`].
     ///
-    /// - `query` - The SELECT [`Query`] to be executed.
+    /// Fails with [`TransactionError::NoActiveTransaction`] without a current transaction.
+    pub fn savepoint(&self) -> IcDbmsResult<SavepointId> {
+        let txid = self.transaction.as_ref().ok_or(IcDbmsError::Transaction(
+            TransactionError::NoActiveTransaction,
+        ))?;
+
+        TRANSACTION_SESSION.with_borrow_mut(|ts| {
+            ts.check_access(txid, caller())?;
+            ts.savepoint(txid)
+        })
+    }
+
+    /// Rolls the current transaction back to the savepoint with the given [`SavepointId`],
+    /// discarding the operations performed after it, while keeping the transaction open;
+    /// see [`crate::dbms::transaction::TransactionSession::rollback_to_savepoint`].
     ///
-    /// # Returns
+    /// Fails with [`TransactionError::NoActiveTransaction`] without a current transaction.
+    pub fn rollback_to_savepoint(&self, savepoint_id: SavepointId) -> IcDbmsResult<()> {
+        let txid = self.transaction.as_ref().ok_or(IcDbmsError::Transaction(
+            TransactionError::NoActiveTransaction,
+        ))?;
+
+        TRANSACTION_SESSION.with_borrow_mut(|ts| {
+            ts.check_access(txid, caller())?;
+            ts.rollback_to_savepoint(txid, savepoint_id)
+        })
+    }
+
+    /// Renames the table `T` in the schema registry, keeping its records.
     ///
-    /// The returned results are a vector of [`table::TableRecord`] matching the query.
-    fn select<T>(&self, query: Query<T>) -> IcDbmsResult<Vec<T::Record>>
+    /// After the rename, the table is looked up by `new_name` with [`crate::memory::SchemaRegistry::table_registry_page_by_name`],
+    /// while its former name is no longer found.
+    /// Fails with [`TableError::TableAlreadyExists`] if another table is registered as `new_name`.
+    pub fn rename_table<T>(&self, new_name: &'static str) -> IcDbmsResult<()>
     where
         T: TableSchema,
     {
-        // load table registry
+        let old_name = SCHEMA_REGISTRY
+            .with_borrow(|sr| sr.table_name(T::fingerprint()).map(str::to_string))
+            .ok_or(IcDbmsError::Table(TableError::TableNotFound))?;
+        if old_name == new_name {
+            return Ok(());
+        }
+        if SCHEMA_REGISTRY
+            .with_borrow(|sr| sr.fingerprint_by_name(new_name))
+            .is_some()
+        {
+            return Err(IcDbmsError::Table(TableError::TableAlreadyExists));
+        }
+
+        let renamed = SCHEMA_REGISTRY.with_borrow_mut(|sr| sr.rename_table(&old_name, new_name))?;
+        if !renamed {
+            return Err(IcDbmsError::Table(TableError::TableNotFound));
+        }
+
+        Ok(())
+    }
+
+    /// Executes a SELECT query on the state of the table `T` at the given timestamp, in nanoseconds since the UNIX epoch.
+    ///
+    /// The committed records are loaded and the changes logged by the [`ChangeLog`] after the timestamp are undone,
+    /// newest first. The pending changes of the current transaction are ignored,
+    /// while eager relations are loaded from the current state of the related tables.
+    ///
+    /// Fails with [`QueryError::InvalidQuery`] if the [`ChangeLog`] is not enabled.
+    /// States before the [`ChangeLog`] was enabled or before the last [`ChangeLog::purge_before`] can't be restored.
+    pub fn select_at<T>(&self, query: Query<T>, timestamp: u64) -> IcDbmsResult<Vec<T::Record>>
+    where
+        T: TableSchema,
+    {
+        if !ChangeLog::is_enabled() {
+            return Err(IcDbmsError::Query(QueryError::InvalidQuery(
+                "the change log is not enabled".to_string(),
+            )));
+        }
+
         let table_registry = self.load_table_registry::<T>()?;
-        // read table
-        let table_reader = table_registry.read::<T>();
-        // get database overlay
-        let mut table_overlay = if self.transaction.is_some() {
-            self.overlay()?
-        } else {
-            DatabaseOverlay::default()
-        };
-        // overlay table reader
-        let mut table_reader = table_overlay.reader(table_reader);
+        let records = table_registry
+            .read::<T>()
+            .map(|next| next.map(|next| next.record))
+            .collect::<Result<Vec<_>, _>>()?;
 
-        // prepare results vector
-        let mut results = Vec::with_capacity(query.limit.unwrap_or(DEFAULT_SELECT_LIMIT));
-        // iter and select
-        let mut count = 0;
+        // undo the changes performed after the timestamp
+        for entry in ChangeLog::entries_after(T::fingerprint(), timestamp)?
+            .into_iter()
+            .rev()
+        {
+            match entry.op {
+                LogOp::Insert(inserted) => {
+                    if let Some(index) = records
+                        .iter()
+                        .rposition(|record| record.encode().as_ref() == inserted.as_slice())
+                    {
+                        records.remove(index);
+                    }
+                }
+                LogOp::Delete(deleted) => {
+                    records.push(T::decode(std::borrow::Cow::Owned(deleted))?);
+                }
+            }
+        }
 
-        while let Some(values) = table_reader.try_next()? {
-            // check whether it matches the filter
-            if let Some(filter) = &query.filter {
+        let filter = self.select_filter(&query);
+        let mut results = Vec::new();
+        let mut count = 0;
+        for record in records {
+            let values = record.to_values();
+            if let Some(filter) = &filter {
                 if !self.record_matches_filter(&values, filter)? {
                     continue;
                 }
             }
-            // filter matched, check limit and offset
             count += 1;
-            // check whether is before offset
             if query.offset.is_some_and(|offset| count <= offset) {
                 continue;
             }
-            // get queried fields
-            let values = self.select_queried_fields::<T>(values, &query)?;
-            // push to results
-            results.push(values);
-            // check whether reached limit
+            results.push(self.select_queried_fields::<T>(values, &query)?);
             if query.limit.is_some_and(|limit| results.len() >= limit) {
                 break;
             }
         }
-
-        // sort results if needed and map to records
         for (column, direction) in query.order_by {
-            self.sort_query_results(&mut results, column, direction);
+            self.sort_query_results(&mut results, column, direction)?;
         }
 
         Ok(results.into_iter().map(T::Record::from_values).collect())
     }
 
-    /// Executes an INSERT query.
+    /// Inserts a record, unless an insert with the same [`IdempotencyToken`] has already been executed.
     ///
-    /// # Arguments
+    /// If the token is known and not expired, the cached result is returned without executing the insert again,
+    /// so clients can safely retry an update call which didn't get a response.
+    /// Otherwise the insert is executed and its result, either successful or not,
+    /// is cached for [`IdempotencyRegistry::DEFAULT_TTL`].
     ///
-    /// - `record` - The INSERT record to be executed.
-    fn insert<T>(&self, record: T::Insert) -> IcDbmsResult<()>
+    /// Within a transaction, the cached result is the one of the queued insert, regardless of the commit.
+    ///
+    /// Fails only if the [`IdempotencyRegistry`] can't be written.
+    pub fn insert_idempotent<T>(
+        &self,
+        record: T::Insert,
+        token: IdempotencyToken,
+    ) -> IcDbmsResult<IdempotentResult>
     where
         T: TableSchema,
         T::Insert: InsertRecord<Schema = T>,
     {
-        // check whether the insert is valid
-        let record_values = record.clone().into_values();
-        self.schema
-            .validate_insert(self, T::table_name(), &record_values)?;
-
-        if self.transaction.is_some() {
-            // insert a new `insert` into the transaction
-            self.with_transaction_mut(|tx| tx.insert::<T>(record_values))?;
-        } else {
-            // insert directly into the database
-            let mut table_registry = self.load_table_registry::<T>()?;
-            table_registry.insert(record.into_record())?;
+        if let Some(entry) = IDEMPOTENCY_REGISTRY.with_borrow(|ir| ir.get(&token).cloned()) {
+            return Ok(IdempotentResult {
+                result: entry.result,
+                cached: true,
+            });
         }
 
-        Ok(())
+        let result = self.insert::<T>(record).map_err(CandidIcDbmsError::from);
+        IDEMPOTENCY_REGISTRY.with_borrow_mut(|ir| {
+            ir.insert(
+                token,
+                result.clone(),
+                time().saturating_add(IdempotencyRegistry::DEFAULT_TTL),
+            )
+        })?;
+
+        Ok(IdempotentResult {
+            result,
+            cached: false,
+        })
     }
 
-    /// Executes an UPDATE query.
-    ///
-    /// # Arguments
-    ///
-    /// - `patch` - The UPDATE patch to be applied.
-    /// - `filter` - An optional [`Filter`] to specify which records to update.
-    ///
-    /// # Returns
+    /// Inserts the record if no record of `T` has its primary key,
+    /// otherwise overwrites all the non-primary key columns of the existing record with the values of `record`.
     ///
-    /// The number of rows updated.
-    fn update<T>(&self, patch: T::Update) -> IcDbmsResult<u64>
+    /// The existence of the record is determined by the insert integrity check, which is always strict,
+    /// so there's no window between the check and the write where another record with the same primary key can be inserted.
+    /// Within a transaction, the upsert is recorded as a single [`TransactionOp::Upsert`],
+    /// which is resolved again against the committed state on commit.
+    pub fn upsert<T>(&self, record: T::Insert) -> IcDbmsResult<UpsertResult>
     where
         T: TableSchema,
+        T::Insert: InsertRecord<Schema = T>,
         T::Update: UpdateRecord<Schema = T>,
     {
-        // get all records matching the filter
-        let query = Query::<T>::builder().filter(patch.where_clause()).build();
-        let records = self.select::<T>(query)?;
-        let count = records.len() as u64;
+        self.check_writable()?;
+        let record = Self::assign_auto_increment::<T>(record)?;
+        let record_values = record.clone().into_values();
+        let primary_key = match self
+            .schema
+            .validate_insert(self, T::table_name(), &record_values)
+        {
+            Ok(()) => None,
+            Err(IcDbmsError::Query(QueryError::PrimaryKeyConflict)) => {
+                Some(Self::primary_key_values::<T>(&record_values))
+            }
+            Err(err) => return Err(err),
+        };
+        let result = if primary_key.is_some() {
+            UpsertResult::Updated
+        } else {
+            UpsertResult::Inserted
+        };
 
         if self.transaction.is_some() {
-            let filter = patch.where_clause().clone();
-            let pks = self.existing_primary_keys_for_filter::<T>(filter.clone())?;
-            // insert a new `update` into the transaction
-            self.with_transaction_mut(|tx| tx.update::<T>(patch, filter, pks))?;
-
-            return Ok(count);
+            QueryCache::<T>::invalidate_all();
+            if let Some(pk) = &primary_key {
+                // validate the update of the existing record
+                self.updated_records::<T>(&Self::upsert_patch::<T>(&record_values, pk.clone()))?;
+            }
+            self.with_transaction_mut(|tx| tx.upsert::<T>(record_values, primary_key))?;
+            crate::dbms_log!(DEBUG, "upsert added to transaction"; "table" => T::table_name());
+            return Ok(result);
         }
 
-        let patch = patch.update_values();
-        // convert updates to values
-        // for each record apply update; delete and insert
-        let res = self.atomic(|db| {
-            for record in records {
-                let mut record_values = record.to_values();
-                // apply patch
-                for (col_def, value) in &patch {
-                    if let Some((_, record_value)) = record_values
-                        .iter_mut()
-                        .find(|(record_col_def, _)| record_col_def.name == col_def.name)
-                    {
-                        *record_value = value.clone();
-                    }
-                }
-                // create insert record
-                let insert_record = T::Insert::from_values(&record_values)?;
-                // delete old record
-                let pk = record_values
-                    .iter()
-                    .find(|(col_def, _)| col_def.primary_key)
-                    .expect("primary key not found") // this can't fail.
-                    .1
-                    .clone();
-                db.delete::<T>(
-                    DeleteBehavior::Break, // we just want to delete the old record
-                    Some(Filter::eq(T::primary_key(), pk)),
+        match primary_key {
+            None => self.insert::<T>(record)?,
+            Some(pk) => {
+                self.update::<T>(
+                    UpdateBehavior::Restrict,
+                    Self::upsert_patch::<T>(&record_values, pk),
                 )?;
-                // insert new record
-                db.insert::<T>(insert_record)?;
             }
-            Ok(count)
-        });
+        }
 
-        Ok(res)
+        Ok(result)
     }
 
-    /// Executes a DELETE query.
-    ///
-    /// # Arguments
-    ///
-    /// - `behaviour` - The [`DeleteBehavior`] to apply for foreign key constraints.
-    /// - `filter` - An optional [`Filter`] to specify which records to delete.
+    /// Builds the patch of an [`IcDbmsDatabase::upsert`] updating the record with the given primary key,
+    /// which sets all the non-primary key columns to the given record values.
+    fn upsert_patch<T>(record_values: &[(ColumnDef, Value)], primary_key: Vec<Value>) -> T::Update
+    where
+        T: TableSchema,
+        T::Update: UpdateRecord<Schema = T>,
+    {
+        let patch_values = record_values
+            .iter()
+            .filter(|(col_def, _)| !T::primary_keys().contains(&col_def.name))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        T::Update::from_values(
+            &patch_values,
+            Some(Filter::eq_composite(T::primary_keys(), primary_key)),
+        )
+    }
+
+    /// Packs the records of the table `T` into as few pages as possible, reclaiming the space left by deleted records.
     ///
-    /// # Returns
+    /// The records are sorted by size, largest first, and written to fresh pages of a staging table,
+    /// filling each page before allocating a new one.
+    /// Once all the records are written, the staging table is swapped with `T` with a single write of the schema registry,
+    /// so the records are never lost if the vacuum is interrupted; then the old pages are dropped.
     ///
-    /// The number of rows deleted.
-    fn delete<T>(&self, behaviour: DeleteBehavior, filter: Option<Filter>) -> IcDbmsResult<u64>
+    /// Must be called outside of a transaction, otherwise [`QueryError::InvalidQuery`] is returned.
+    pub fn vacuum<T>(&self) -> IcDbmsResult<VacuumStats>
     where
         T: TableSchema,
     {
         if self.transaction.is_some() {
-            let pks = self.existing_primary_keys_for_filter::<T>(filter.clone())?;
-            let count = pks.len() as u64;
-
-            self.with_transaction_mut(|tx| tx.delete::<T>(behaviour, filter, pks))?;
+            return Err(IcDbmsError::Query(QueryError::InvalidQuery(
+                "vacuum can't be run within a transaction".to_string(),
+            )));
+        }
 
-            return Ok(count);
+        // the records are moved, so the indexes locating them are written too
+        let table_registry = self.load_table_registry_for_write::<T>()?;
+        let stats_before = table_registry.stats::<T>()?;
+        let mut records = table_registry
+            .read::<T>()
+            .map(|next| next.map(|next| next.record))
+            .collect::<Result<Vec<_>, _>>()?;
+        records.sort_by_key(|record| std::cmp::Reverse(record.size()));
+        let records_moved = records.len() as u64;
+
+        // write the records to a staging table; a staging table left by a previous vacuum is emptied first
+        let staging_fingerprint = T::fingerprint() ^ VACUUM_STAGING_FINGERPRINT_MASK;
+        let staging_pages =
+            SCHEMA_REGISTRY.with_borrow_mut(|sr| sr.register_fingerprint(staging_fingerprint))?;
+        let mut staging_registry = TableRegistry::load(staging_pages)?;
+        #[cfg(feature = "compression")]
+        {
+            staging_registry = staging_registry.with_compression(T::use_compression());
         }
+        // the indexes are updated with the new locations of the moved records
+        #[cfg(feature = "indexes")]
+        if let Some(indexes) = TableIndexes::load::<T>() {
+            staging_registry = staging_registry.with_indexes(indexes);
+        }
+        staging_registry.drop_pages()?;
+        staging_registry.insert_batch(records)?;
 
-        // delete must be atomic
-        let res = self.atomic(|db| {
-            // delete directly from the database
-            // select all records matching the filter
-            // read table
-            let mut table_registry = db.load_table_registry::<T>()?;
-            let mut records = vec![];
-            // iter all records
-            // FIXME: this may be huge, we should do better
-            {
-                let mut table_reader = table_registry.read::<T>();
-                while let Some(values) = table_reader.try_next()? {
-                    let record_values = values.record.clone().to_values();
-                    if let Some(filter) = &filter {
-                        if !db.record_matches_filter(&record_values, filter)? {
-                            continue;
-                        }
-                    }
-                    records.push((values, record_values));
-                }
-            }
-            // deleted records
-            let mut count = records.len() as u64;
-            for (record, record_values) in records {
-                // match delete behaviour
-                match behaviour {
-                    DeleteBehavior::Cascade => {
-                        // delete recursively foreign keys if cascade
-                        count += self.delete_foreign_keys_cascade::<T>(&record_values)?;
-                    }
-                    DeleteBehavior::Restrict => {
-                        if self.delete_foreign_keys_cascade::<T>(&record_values)? > 0 {
-                            // it's okay; we panic here because we are in an atomic closure
-                            return Err(IcDbmsError::Query(
-                                QueryError::ForeignKeyConstraintViolation {
-                                    referencing_table: T::table_name(),
-                                    field: T::primary_key(),
-                                },
-                            ));
-                        }
-                    }
-                    DeleteBehavior::Break => {
-                        // do nothing
-                    }
-                }
-                // eventually delete the record
-                table_registry.delete(record.record, record.page, record.offset)?;
-            }
+        // swap the tables and drop the old pages, which now belong to the staging table
+        SCHEMA_REGISTRY
+            .with_borrow_mut(|sr| sr.swap_table_pages(T::fingerprint(), staging_fingerprint))?;
+        let old_pages = SCHEMA_REGISTRY
+            .with_borrow(|sr| sr.table_registry_page_by_fingerprint(staging_fingerprint))
+            .ok_or(IcDbmsError::Table(TableError::TableNotFound))?;
+        TableRegistry::load(old_pages)?.drop_pages()?;
+        QueryCache::<T>::invalidate_all();
+
+        let stats_after = self.load_table_registry::<T>()?.stats::<T>()?;
+        let page_size = MEMORY_MANAGER.with_borrow(|mm| mm.page_size());
+        let free_bytes =
+            |stats: TableStats| (stats.pages * page_size).saturating_sub(stats.used_bytes);
+
+        Ok(VacuumStats {
+            pages_before: stats_before.pages,
+            pages_after: stats_after.pages,
+            records_moved,
+            free_bytes_recovered: free_bytes(stats_before).saturating_sub(free_bytes(stats_after)),
+        })
+    }
 
-            Ok(count)
-        });
+    /// Sets the [`TableQuota`] of the table `T`, limiting the memory its records can take.
+    ///
+    /// Inserts exceeding the quota fail with [`ic_dbms_api::prelude::MemoryError::QuotaExceeded`].
+    /// The quota doesn't apply to the records already stored; an unlimited quota removes it.
+    ///
+    /// Only the principals in the [`ACL`] can set quotas, otherwise [`QueryError::AccessDenied`] is returned.
+    pub fn set_table_quota<T>(&self, quota: TableQuota) -> IcDbmsResult<()>
+    where
+        T: TableSchema,
+    {
+        let caller = caller();
+        if !ACL.with_borrow(|acl| acl.is_allowed(&caller)) {
+            return Err(IcDbmsError::Query(QueryError::AccessDenied { caller }));
+        }
+        if SCHEMA_REGISTRY
+            .with_borrow(|sr| sr.table_registry_page::<T>())
+            .is_none()
+        {
+            return Err(IcDbmsError::Table(TableError::TableNotFound));
+        }
 
-        Ok(res)
+        SCHEMA_REGISTRY
+            .with_borrow_mut(|sr| sr.set_table_quota(T::fingerprint(), quota))
+            .map_err(IcDbmsError::from)
     }
 
-    /// Commits the current transaction.
+    /// Executes a SELECT query on behalf of the given caller, applying its [`RateLimiter`].
     ///
-    /// The transaction is consumed.
+    /// Fails with [`IcDbmsError::RateLimitExceeded`] if the caller exceeded its rate limit.
+    pub fn select_as<T>(&self, caller: Principal, query: Query<T>) -> IcDbmsResult<Vec<T::Record>>
+    where
+        T: TableSchema,
+    {
+        RateLimiter::check(caller)?;
+        self.select(query)
+    }
+
+    /// Executes an INSERT query in [`ValidationMode::Lenient`] mode, regardless of the mode of the instance.
     ///
-    /// Any error during commit will trap the canister to ensure consistency.
-    fn commit(&mut self) -> IcDbmsResult<()> {
-        // take transaction out of self and get the transaction out of the storage
-        // this also invalidates the overlay, so we won't have conflicts during validation
-        let Some(txid) = self.transaction.take() else {
-            return Err(IcDbmsError::Transaction(
-                TransactionError::NoActiveTransaction,
-            ));
-        };
-        let transaction = TRANSACTION_SESSION.with_borrow_mut(|ts| ts.take_transaction(&txid))?;
+    /// The integrity violations of the record are returned as [`IntegrityWarning`]s instead of rejecting the insert;
+    /// see [`InsertIntegrityValidator::validate_lenient`] for the violations which are still errors.
+    ///
+    /// Fails with [`QueryError::InvalidQuery`] within a transaction, since its inserts are validated again on commit.
+    pub fn insert_lenient<T>(&self, record: T::Insert) -> IcDbmsResult<((), Vec<IntegrityWarning>)>
+    where
+        T: TableSchema,
+        T::Insert: InsertRecord<Schema = T>,
+    {
+        if self.transaction.is_some() {
+            return Err(IcDbmsError::Query(QueryError::InvalidQuery(
+                "lenient inserts can't be performed within a transaction".to_string(),
+            )));
+        }
+        QueryCache::<T>::invalidate_all();
 
-        // iterate over operations and apply them;
-        // for each operation, first validate, then apply
-        // using `self.atomic` when applying to ensure consistency
-        for op in transaction.operations {
-            match op {
-                TransactionOp::Insert { table, values } => {
-                    // validate
-                    self.schema.validate_insert(self, table, &values)?;
-                    // insert
-                    self.atomic(|db| db.schema.insert(db, table, &values));
-                }
-                TransactionOp::Delete {
-                    table,
-                    behaviour,
-                    filter,
-                } => {
-                    self.atomic(|db| db.schema.delete(db, table, behaviour, filter));
-                }
-                TransactionOp::Update {
-                    table,
-                    patch,
-                    filter,
-                } => {
-                    self.atomic(|db| db.schema.update(db, table, &patch, filter));
-                }
-            }
+        let record = Self::assign_auto_increment::<T>(record)?;
+        let record_values = record.clone().into_values();
+        let warnings = InsertIntegrityValidator::<T>::new(self).validate_lenient(&record_values)?;
+        self.store_record::<T>(record)?;
+        notify_watchers(T::table_name(), TableChange::Insert(&record_values));
+
+        Ok(((), warnings))
+    }
+
+    /// Begins an online migration of the table `T` to the given columns.
+    ///
+    /// The records of `T` are copied to new pages with the new columns; then, until the migration is completed
+    /// with [`MigrationHandle::complete`], the inserted records are written with both the old and the new columns,
+    /// while updates and deletes of `T` are rejected. See [`crate::dbms::migration`] for the phases of a migration.
+    ///
+    /// Fails with [`QueryError::InvalidQuery`] within a transaction.
+    pub fn begin_migration<T>(&self, new_columns: &[ColumnDef]) -> IcDbmsResult<MigrationHandle>
+    where
+        T: TableSchema,
+    {
+        if self.transaction.is_some() {
+            return Err(IcDbmsError::Query(QueryError::InvalidQuery(
+                "migrations can't be begun within a transaction".to_string(),
+            )));
         }
 
-        Ok(())
+        MigrationHandle::begin::<T>(new_columns)
     }
 
-    /// Rolls back the current transaction.
+    /// Executes a SELECT query within the given [`CycleBudget`].
     ///
-    /// The transaction is consumed.
-    fn rollback(&mut self) -> IcDbmsResult<()> {
-        let Some(txid) = self.transaction.take() else {
-            return Err(IcDbmsError::Transaction(
-                TransactionError::NoActiveTransaction,
-            ));
-        };
+    /// The instructions executed are checked every [`BUDGET_CHECK_INTERVAL`] scanned records:
+    /// if the soft limit is exceeded, the scan stops and the records selected so far are returned,
+    /// with a [`Cursor`] to resume the scan with [`IcDbmsDatabase::resume_select_with_budget`];
+    /// if the hard limit is exceeded, the canister traps.
+    ///
+    /// Offset, limit and ordering apply to the records selected by each call.
+    pub fn select_with_budget<T>(
+        &self,
+        query: Query<T>,
+        budget: CycleBudget,
+    ) -> IcDbmsResult<PartialSelectResult<T>>
+    where
+        T: TableSchema,
+    {
+        self.scan(query, None, Some(budget), true)
+    }
 
-        TRANSACTION_SESSION.with_borrow_mut(|ts| ts.close_transaction(&txid));
-        Ok(())
+    /// Resumes a SELECT query truncated by [`IcDbmsDatabase::select_with_budget`] from the given [`Cursor`].
+    pub fn resume_select_with_budget<T>(
+        &self,
+        query: Query<T>,
+        cursor: Cursor,
+        budget: CycleBudget,
+    ) -> IcDbmsResult<PartialSelectResult<T>>
+    where
+        T: TableSchema,
+    {
+        self.scan(query, Some(cursor), Some(budget), true)
     }
-}
 
-#[cfg(test)]
-mod tests {
+    /// Performs a SELECT query as [`Database::select`] does, but if the [`Query::timeout_ns`] is exceeded,
+    /// the scan stops and the records selected so far are returned, with a [`Cursor`] to resume the scan
+    /// with [`IcDbmsDatabase::resume_select_with_partial_results`].
+    ///
+    /// The elapsed time is checked every [`BUDGET_CHECK_INTERVAL`] scanned records.
+    /// Offset, limit and ordering apply to the records selected by each call.
+    pub fn select_with_partial_results<T>(
+        &self,
+        query: Query<T>,
+    ) -> IcDbmsResult<PartialSelectResult<T>>
+    where
+        T: TableSchema,
+    {
+        self.scan(query, None, None, true)
+    }
 
-    use candid::{Nat, Principal};
-    use ic_dbms_api::prelude::{Text, Uint32};
+    /// Resumes a SELECT query truncated by [`IcDbmsDatabase::select_with_partial_results`] from the given [`Cursor`].
+    pub fn resume_select_with_partial_results<T>(
+        &self,
+        query: Query<T>,
+        cursor: Cursor,
+    ) -> IcDbmsResult<PartialSelectResult<T>>
+    where
+        T: TableSchema,
+    {
+        self.scan(query, Some(cursor), None, true)
+    }
 
-    use super::*;
-    use crate::tests::{
-        Message, POSTS_FIXTURES, Post, TestDatabaseSchema, USERS_FIXTURES, User, UserInsertRequest,
-        UserUpdateRequest, load_fixtures,
-    };
+    /// Executes a SELECT query paginated by primary key.
+    ///
+    /// The records after the [`Query::cursor`], if any, are selected in ascending [`TableSchema::primary_key`] order,
+    /// up to the [`Query::limit`]; the [`SelectPage::next_cursor`] is the greatest primary key value of the page,
+    /// unless the page has fewer records than the limit, or there's no limit.
+    /// Since the cursor is a primary key value, records inserted or deleted between two pages
+    /// never cause the other records to be skipped or selected twice.
+    ///
+    /// If the table has an index on the primary key, it's walked from the cursor, reading only the records
+    /// up to the end of the page; otherwise the whole table is scanned for each page.
+    ///
+    /// For tables with a composite primary key, only the first of the [`TableSchema::primary_keys`] is compared.
+    ///
+    /// Fails with [`QueryError::InvalidQuery`] if the primary key column is not selected by the query.
+    pub fn select_page<T>(&self, query: Query<T>) -> IcDbmsResult<SelectPage<T>>
+    where
+        T: TableSchema,
+    {
+        let limit = query.limit;
+        let (results, _) = self.scan_columns(query, None, None, false, true)?;
 
-    #[test]
-    fn test_should_init_dbms() {
-        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
-        assert!(dbms.transaction.is_none());
+        let next_cursor = match limit {
+            Some(limit) if results.len() >= limit => results
+                .iter()
+                .map(|columns| {
+                    columns
+                        .iter()
+                        .filter(|(source, _)| *source == ValuesSource::This)
+                        .flat_map(|(_, values)| values)
+                        .find(|(col_def, _)| col_def.name == T::primary_key())
+                        .map(|(_, value)| value)
+                        .ok_or_else(|| {
+                            IcDbmsError::Query(QueryError::InvalidQuery(format!(
+                                "the primary key column '{}' must be selected to paginate",
+                                T::primary_key()
+                            )))
+                        })
+                })
+                .collect::<IcDbmsResult<Vec<_>>>()?
+                .into_iter()
+                .max()
+                .cloned(),
+            _ => None,
+        };
 
-        let tx_dbms = IcDbmsDatabase::from_transaction(TestDatabaseSchema, Nat::from(1u64));
-        assert!(tx_dbms.transaction.is_some());
+        Ok(SelectPage {
+            records: results.into_iter().map(T::Record::from_values).collect(),
+            next_cursor,
+        })
+    }
+
+    /// Returns the [`QueryPlan`] describing the steps performed to execute a SELECT query, without executing it.
+    ///
+    /// If an index on a column compared by the filter applies, the plan starts with a [`QueryPlanNode::IndexScan`]
+    /// of the records located by the index; otherwise with a [`QueryPlanNode::TableScan`],
+    /// whose rows are estimated from the space in use according to the ledgers of the table,
+    /// as [`TableRegistry::stats`] does.
+    pub fn explain<T>(&self, query: &Query<T>) -> IcDbmsResult<QueryPlan>
+    where
+        T: TableSchema,
+    {
+        let table_registry = self.load_table_registry::<T>()?;
+        let filter = self.select_filter(query);
+        let mut node = match self.index_lookup(&table_registry, filter.as_ref())? {
+            Some((column, locations)) => QueryPlanNode::IndexScan {
+                table: T::table_name().to_string(),
+                column: column.to_string(),
+                filter: filter.as_ref().map(Filter::to_expression),
+                estimated_rows: locations.len() as u64,
+            },
+            None => QueryPlanNode::TableScan {
+                table: T::table_name().to_string(),
+                filter: filter.as_ref().map(Filter::to_expression),
+                estimated_rows: table_registry.stats::<T>()?.estimated_records,
+            },
+        };
+        for (column, direction) in &query.order_by {
+            node = QueryPlanNode::Sort {
+                column: column.to_string(),
+                direction: direction.clone(),
+                input: Box::new(node),
+            };
+        }
+        if let Some(offset) = query.offset {
+            node = QueryPlanNode::Offset {
+                n: offset as u64,
+                input: Box::new(node),
+            };
+        }
+        if let Some(limit) = query.limit {
+            node = QueryPlanNode::Limit {
+                n: limit as u64,
+                input: Box::new(node),
+            };
+        }
+        for table in &query.eager_relations {
+            node = QueryPlanNode::EagerLoad {
+                table: table.to_string(),
+                input: Box::new(node),
+            };
+        }
+
+        Ok(QueryPlan { root: node })
+    }
+
+    /// Executes a [`JoinQuery`] as a nested-loop join.
+    ///
+    /// For each record of the left table selected by the query, the records of the right table
+    /// whose `on_b` column is equal to its `on_a` column are looked up.
+    /// A right join loops over all the records of the right table instead,
+    /// matching them with the records of the left table selected by the query.
+    ///
+    /// `NULL` values never match; the `on_a` column must be selected by the query.
+    pub fn select_join<A, B>(&self, query: JoinQuery<A, B>) -> IcDbmsResult<Vec<JoinRecord<A, B>>>
+    where
+        A: TableSchema,
+        B: TableSchema,
+    {
+        let JoinQuery {
+            query,
+            on_a,
+            on_b,
+            join_type,
+            ..
+        } = query;
+        for (columns, column) in [(A::columns(), on_a), (B::columns(), on_b)] {
+            if !columns.iter().any(|col_def| col_def.name == column) {
+                return Err(IcDbmsError::Query(QueryError::UnknownColumn(
+                    column.to_string(),
+                )));
+            }
+        }
+
+        let mut results = vec![];
+        match join_type {
+            JoinType::Inner | JoinType::Left => {
+                for a_values in self.select_values::<A>(query)? {
+                    let b_records = match Self::join_value(&a_values, on_a) {
+                        Some(value) => self.select_values::<B>(
+                            Query::builder()
+                                .and_where(Filter::eq(on_b, value.clone()))
+                                .build(),
+                        )?,
+                        None => vec![],
+                    };
+                    if b_records.is_empty() && join_type == JoinType::Left {
+                        results.push(JoinRecord::new(a_values.clone(), vec![]));
+                    }
+                    for b_values in b_records {
+                        results.push(JoinRecord::new(a_values.clone(), b_values));
+                    }
+                }
+            }
+            JoinType::Right => {
+                let a_records = self.select_values::<A>(query)?;
+                for b_values in self.select_values::<B>(Query::builder().build())? {
+                    let b_value = Self::join_value(&b_values, on_b);
+                    let mut matched = false;
+                    for a_values in a_records.iter().filter(|a_values| {
+                        b_value.is_some() && Self::join_value(a_values, on_a) == b_value
+                    }) {
+                        matched = true;
+                        results.push(JoinRecord::new(a_values.clone(), b_values.clone()));
+                    }
+                    if !matched {
+                        results.push(JoinRecord::new(vec![], b_values));
+                    }
+                }
+            }
+        }
+
+        Ok(results)
+    }
+
+    /// Selects the column values of the records of `T` matching the query, without building the records.
+    ///
+    /// It's the way to read the results of [`Select::Mapped`](ic_dbms_api::prelude::Select::Mapped) queries,
+    /// since aliased columns are not part of the records of `T`.
+    /// Eager relations are not included.
+    pub fn select_raw<T>(&self, query: Query<T>) -> IcDbmsResult<Vec<Vec<(ColumnDef, Value)>>>
+    where
+        T: TableSchema,
+    {
+        self.select_values(query)
+    }
+
+    /// Selects the column values of the records of `T` matching the query, excluding eager relations.
+    fn select_values<T>(&self, query: Query<T>) -> IcDbmsResult<Vec<Vec<(ColumnDef, Value)>>>
+    where
+        T: TableSchema,
+    {
+        let (results, _) = self.scan_columns(query, None, None, false, false)?;
+
+        Ok(results
+            .into_iter()
+            .filter_map(|columns| {
+                columns
+                    .into_iter()
+                    .find(|(source, _)| *source == ValuesSource::This)
+                    .map(|(_, values)| values)
+            })
+            .collect())
+    }
+
+    /// Returns the value of `column` among the given values to join on, if any and not `NULL`.
+    fn join_value<'a>(values: &'a [(ColumnDef, Value)], column: &str) -> Option<&'a Value> {
+        values
+            .iter()
+            .find(|(col_def, _)| col_def.name == column)
+            .map(|(_, value)| value)
+            .filter(|value| !matches!(value, Value::Null))
+    }
+
+    /// Selects the records matching both `query_a` and `query_b` (INTERSECT).
+    ///
+    /// The sets of records are determined by the filters of the two queries, while the records
+    /// are fetched with the selected columns, relations, ordering and pagination of `query_a`.
+    pub fn select_intersect<T>(
+        &self,
+        query_a: Query<T>,
+        query_b: Query<T>,
+    ) -> IcDbmsResult<Vec<T::Record>>
+    where
+        T: TableSchema,
+    {
+        self.select_combined(query_a, query_b, true)
+    }
+
+    /// Selects the records matching `query_a` but not `query_b` (EXCEPT).
+    ///
+    /// The sets of records are determined by the filters of the two queries, while the records
+    /// are fetched with the selected columns, relations, ordering and pagination of `query_a`.
+    pub fn select_except<T>(
+        &self,
+        query_a: Query<T>,
+        query_b: Query<T>,
+    ) -> IcDbmsResult<Vec<T::Record>>
+    where
+        T: TableSchema,
+    {
+        self.select_combined(query_a, query_b, false)
+    }
+
+    /// Counts the records of the table `T` matching the given [`CountQuery`].
+    ///
+    /// The table is scanned applying the filter, without building the [`TableRecord`]s,
+    /// so it's cheaper than the length of a [`Database::select`].
+    /// Within a transaction, the uncommitted inserts are counted and the uncommitted deletes are not.
+    pub fn count_query<T>(&self, query: CountQuery<T>) -> IcDbmsResult<u64>
+    where
+        T: TableSchema,
+    {
+        // load table registry
+        let table_registry = self.load_table_registry::<T>()?;
+        let table_reader = table_registry.read::<T>();
+        // get database overlay
+        let mut table_overlay = if self.transaction.is_some() {
+            self.overlay()?
+        } else {
+            DatabaseOverlay::default()
+        };
+        let table_reader = table_overlay.reader(table_reader);
+
+        // get filter, excluding soft-deleted records if needed
+        let mut select_query = Query::<T>::builder().filter(query.filter);
+        if query.include_deleted {
+            select_query = select_query.include_deleted();
+        }
+        let filter = self.select_filter(&select_query.build());
+
+        let mut count = 0;
+        for values in table_reader {
+            let values = values?;
+            if let Some(filter) = &filter {
+                if !self.record_matches_filter(&values, filter)? {
+                    continue;
+                }
+            }
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
+    /// Computes the [`ic_dbms_api::prelude::AggregateOp`] set on the query over the records matching its filter.
+    ///
+    /// The records are accumulated one at a time, so they are never collected;
+    /// limit, offset and ordering of the query are ignored.
+    ///
+    /// `COUNT` returns a [`Value::Uint64`], `MIN` and `MAX` a value of the column type,
+    /// while `SUM` and `AVG` return a [`Value::Int64`] for signed integers, a [`Value::Uint64`] for unsigned integers,
+    /// a [`Value::Decimal`] for decimals and a [`Value::Float64`] for floats.
+    /// `NULL` values are skipped, and the aggregates on a column return [`Value::Null`] if there are no values to aggregate.
+    pub fn aggregate<T>(&self, query: Query<T>) -> IcDbmsResult<Value>
+    where
+        T: TableSchema,
+    {
+        let aggregate = Self::aggregate_op(&query)?;
+
+        // load table registry
+        let table_registry = self.load_table_registry::<T>()?;
+        let table_reader = table_registry.read::<T>();
+        // get database overlay
+        let mut table_overlay = if self.transaction.is_some() {
+            self.overlay()?
+        } else {
+            DatabaseOverlay::default()
+        };
+        let table_reader = table_overlay.reader(table_reader);
+
+        // get filter, excluding soft-deleted records if needed
+        let filter = self.select_filter(&query);
+
+        let mut accumulator = Accumulator::new(aggregate);
+        for values in table_reader {
+            let values = values?;
+            if let Some(filter) = &filter {
+                if !self.record_matches_filter(&values, filter)? {
+                    continue;
+                }
+            }
+            accumulator.accumulate(&values)?;
+        }
+
+        Ok(accumulator.finish()?)
+    }
+
+    /// Groups the records matching the filter of the query by its [`Query::group_by`] columns,
+    /// and computes the [`ic_dbms_api::prelude::AggregateOp`] set on the query over each group,
+    /// as [`IcDbmsDatabase::aggregate`] does.
+    ///
+    /// Records with `NULL` values of the grouping columns form their own group.
+    /// The groups are returned in order of first occurrence, unless the query is ordered by
+    /// a grouping column or by the aggregate result, referred to as [`GroupedResult::AGGREGATE_COLUMN`];
+    /// offset and limit of the query apply to the groups.
+    pub fn select_grouped<T>(&self, query: Query<T>) -> IcDbmsResult<Vec<GroupedResult>>
+    where
+        T: TableSchema,
+    {
+        let aggregate = Self::aggregate_op(&query)?;
+        if query.group_by.is_empty() {
+            return Err(IcDbmsError::Query(QueryError::InvalidQuery(
+                "the query has no group by columns".to_string(),
+            )));
+        }
+        if let Some(column) = query
+            .group_by
+            .iter()
+            .find(|column| !T::columns().iter().any(|col_def| col_def.name == **column))
+        {
+            return Err(IcDbmsError::Query(QueryError::UnknownColumn(
+                column.to_string(),
+            )));
+        }
+        if let Some((column, _)) = query.order_by.iter().find(|(column, _)| {
+            !query.group_by.contains(column) && *column != GroupedResult::AGGREGATE_COLUMN
+        }) {
+            return Err(IcDbmsError::Query(QueryError::UnknownColumn(
+                column.to_string(),
+            )));
+        }
+
+        // load table registry
+        let table_registry = self.load_table_registry::<T>()?;
+        let table_reader = table_registry.read::<T>();
+        // get database overlay
+        let mut table_overlay = if self.transaction.is_some() {
+            self.overlay()?
+        } else {
+            DatabaseOverlay::default()
+        };
+        let table_reader = table_overlay.reader(table_reader);
+
+        // get filter, excluding soft-deleted records if needed
+        let filter = self.select_filter(&query);
+
+        let mut accumulator = GroupedAccumulator::new(aggregate, query.group_by.clone());
+        for values in table_reader {
+            let values = values?;
+            if let Some(filter) = &filter {
+                if !self.record_matches_filter(&values, filter)? {
+                    continue;
+                }
+            }
+            accumulator.accumulate(&values)?;
+        }
+        let mut results = accumulator.finish()?;
+
+        // sort groups if needed
+        for (column, direction) in &query.order_by {
+            results.sort_by(|a, b| match (a.value(column), b.value(column)) {
+                (Some(a_val), Some(b_val)) => {
+                    // NULL values are ordered as by the `Ord` implementation of `Value`
+                    let ordering = a_val.typed_cmp(b_val).unwrap_or_else(|_| a_val.cmp(b_val));
+                    match direction {
+                        OrderDirection::Ascending => ordering,
+                        OrderDirection::Descending => ordering.reverse(),
+                    }
+                }
+                (Some(_), None) => std::cmp::Ordering::Greater,
+                (None, Some(_)) => std::cmp::Ordering::Less,
+                (None, None) => std::cmp::Ordering::Equal,
+            });
+        }
+
+        Ok(results
+            .into_iter()
+            .skip(query.offset.unwrap_or_default())
+            .take(query.limit.unwrap_or(usize::MAX))
+            .collect())
+    }
+
+    /// Returns the [`ic_dbms_api::prelude::AggregateOp`] set on the query,
+    /// checking that the column it's computed on exists.
+    fn aggregate_op<T>(query: &Query<T>) -> IcDbmsResult<AggregateOp>
+    where
+        T: TableSchema,
+    {
+        let aggregate = query.aggregate.ok_or_else(|| {
+            IcDbmsError::Query(QueryError::InvalidQuery(
+                "the query has no aggregate function".to_string(),
+            ))
+        })?;
+        if let Some(column) = aggregate.column() {
+            if !T::columns().iter().any(|col_def| col_def.name == column) {
+                return Err(IcDbmsError::Query(QueryError::UnknownColumn(
+                    column.to_string(),
+                )));
+            }
+        }
+
+        Ok(aggregate)
+    }
+
+    /// Returns the [`WriteAmpStats`] of the memory, to measure the write amplification of the operations.
+    ///
+    /// Use [`crate::memory::MemoryManager::reset_stats`] to reset them.
+    pub fn perf_metrics(&self) -> WriteAmpStats {
+        MEMORY_MANAGER.with_borrow(|mm| mm.write_amplification_tracker())
+    }
+
+    /// Validates all the tables stored in the schema registry against the tables of the [`DatabaseSchema`].
+    ///
+    /// It should be called after a canister upgrade, to detect tables whose columns have changed
+    /// or which don't exist anymore, before reading records with an incompatible layout.
+    ///
+    /// # Returns
+    ///
+    /// The list of [`SchemaMismatch`] found; empty if all the schemas are valid.
+    pub fn validate_all_schemas(&self) -> IcDbmsResult<Vec<SchemaMismatch>> {
+        Ok(validate_schemas(&self.schema.table_fingerprints()))
+    }
+
+    /// Performs a lightweight self-diagnostic of the database,
+    /// verifying the first record and the last page of each table.
+    ///
+    /// See [`IcDbmsDatabase::health_check_with_sample_size`].
+    pub fn health_check(&self) -> HealthCheckResult {
+        self.health_check_with_sample_size(DEFAULT_HEALTH_CHECK_SAMPLE_SIZE)
+    }
+
+    /// Performs a self-diagnostic of the database, meant to be exposed by a canister query endpoint.
+    ///
+    /// The schema registry and the ACL are loaded from memory, the stored schemas are validated
+    /// against the [`DatabaseSchema`], and the integrity of each table is verified with
+    /// [`TableRegistry::verify_integrity`] on the first `sample_size` records and the records of its last page,
+    /// so the tables are never scanned.
+    ///
+    /// Every problem found is reported in [`HealthCheckResult::issues`].
+    pub fn health_check_with_sample_size(&self, sample_size: usize) -> HealthCheckResult {
+        let mut result = HealthCheckResult::default();
+        match SchemaRegistry::load() {
+            Ok(_) => result.schema_registry_ok = true,
+            Err(err) => result
+                .issues
+                .push(format!("failed to load the schema registry: {err}")),
+        }
+        match AccessControlList::load() {
+            Ok(_) => result.acl_ok = true,
+            Err(err) => result.issues.push(format!("failed to load the ACL: {err}")),
+        }
+
+        let tables = self.schema.table_fingerprints();
+        result.issues.extend(
+            validate_schemas(&tables)
+                .into_iter()
+                .map(|mismatch| mismatch.description),
+        );
+
+        for table in tables {
+            let Some(pages) = SCHEMA_REGISTRY
+                .with_borrow(|sr| sr.table_registry_page_by_fingerprint(table.fingerprint))
+            else {
+                result
+                    .issues
+                    .push(format!("table {} is not registered", table.table));
+                continue;
+            };
+            match Self::table_health(table.table, pages, sample_size) {
+                Ok(health) => {
+                    if !health.integrity_ok {
+                        result
+                            .issues
+                            .push(format!("table {} has corrupted records", table.table));
+                    }
+                    result.per_table_ok.push(health);
+                }
+                Err(err) => result
+                    .issues
+                    .push(format!("failed to check table {}: {err}", table.table)),
+            }
+        }
+
+        result
+    }
+
+    /// Gets the [`TableHealth`] of the table stored at the given pages, see [`IcDbmsDatabase::health_check`].
+    fn table_health(
+        table_name: &str,
+        pages: TableRegistryPage,
+        sample_size: usize,
+    ) -> IcDbmsResult<TableHealth> {
+        let registry = TableRegistry::load(pages)?;
+        let mut integrity_ok = registry.verify_integrity(sample_size)?;
+        // the row count is estimated from a sample of records, which may be corrupted as well
+        let row_count = if integrity_ok {
+            match registry.raw_stats() {
+                Ok(stats) => stats.estimated_records,
+                Err(MemoryError::DecodeError(_)) => {
+                    integrity_ok = false;
+                    0
+                }
+                Err(err) => return Err(err.into()),
+            }
+        } else {
+            0
+        };
+
+        Ok(TableHealth {
+            table_name: table_name.to_string(),
+            row_count,
+            free_segments: registry.free_segment_count() as u32,
+            page_count: registry.page_count() as u32,
+            integrity_ok,
+        })
+    }
+
+    /// Generates the Candid interface of the tables of the [`DatabaseSchema`] registered in the schema registry.
+    ///
+    /// For each table, the service exposes a `get_{table}` query, returning the rows matching an optional filter,
+    /// and an `insert_{table}` update, taking a record with the table columns.
+    pub fn export_candid(&self) -> String {
+        let registered = SCHEMA_REGISTRY.with_borrow(|sr| sr.list_tables());
+        let tables = self
+            .schema
+            .table_fingerprints()
+            .into_iter()
+            .filter(|table| registered.binary_search(&table.fingerprint).is_ok())
+            .collect::<Vec<_>>();
+
+        export_candid(&tables)
+    }
+
+    /// Imports the database state from a JSON object mapping each table name to its rows,
+    /// such as `{ "users": [{ "id": 1, "name": "Alice" }], "posts": [...] }`.
+    ///
+    /// Tables are imported in dependency order, so that the tables referenced by foreign keys are imported first.
+    /// Rows failing validation are skipped with a warning, as well as unknown tables.
+    ///
+    /// All the rows are inserted within a single transaction: if the dbms is not already in a transaction,
+    /// a new one is started and committed at the end, or rolled back if the import fails.
+    ///
+    /// # Returns
+    ///
+    /// The [`ImportResult`] with the amount of tables and rows imported, and the warnings.
+    pub fn import_database_json(&self, json: &str) -> IcDbmsResult<ImportResult> {
+        let tables = parse_database_json(json)?;
+
+        if self.transaction.is_some() {
+            return self.import_tables(&tables);
+        }
+
+        let transaction_id =
+            TRANSACTION_SESSION.with_borrow_mut(|ts| ts.begin_transaction(caller()));
+        let mut dbms = Self {
+            schema: self.schema.clone(),
+            transaction: Some(transaction_id),
+            validation_mode: ValidationMode::Strict,
+            read_only: self.read_only,
+        };
+        match dbms.import_tables(&tables) {
+            Ok(result) => {
+                dbms.commit()?;
+                Ok(result)
+            }
+            Err(err) => {
+                dbms.rollback()?;
+                Err(err)
+            }
+        }
+    }
+
+    /// Inserts the rows of the given tables, in dependency order.
+    fn import_tables(
+        &self,
+        tables: &serde_json::Map<String, serde_json::Value>,
+    ) -> IcDbmsResult<ImportResult> {
+        let schema_tables = self.schema.table_fingerprints();
+        let mut result = ImportResult::default();
+        for table in tables.keys() {
+            if !schema_tables.iter().any(|t| t.table == table.as_str()) {
+                result
+                    .warnings
+                    .push(format!("table '{table}' does not exist; skipped"));
+            }
+        }
+
+        for table in import_order(&schema_tables)? {
+            let Some(rows) = tables.get(table.table) else {
+                continue;
+            };
+            let Some(rows) = rows.as_array() else {
+                result.warnings.push(format!(
+                    "rows of table '{}' are not a JSON array; skipped",
+                    table.table
+                ));
+                continue;
+            };
+
+            for (index, row) in rows.iter().enumerate() {
+                let inserted = json_to_record_values(table.columns, row)
+                    .and_then(|values| self.schema.insert(self, table.table, &values));
+                match inserted {
+                    Ok(()) => result.rows_imported += 1,
+                    Err(IcDbmsError::Query(err)) => result.warnings.push(format!(
+                        "row {index} of table '{}' skipped: {err}",
+                        table.table
+                    )),
+                    Err(err) => return Err(err),
+                }
+            }
+            result.tables_imported += 1;
+        }
+
+        Ok(result)
+    }
+
+    /// Fails with [`TransactionError::ReadOnlyTransaction`] if the instance is read-only.
+    fn check_writable(&self) -> IcDbmsResult<()> {
+        if self.read_only {
+            return Err(IcDbmsError::Transaction(
+                TransactionError::ReadOnlyTransaction,
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Executes a closure with a mutable reference to the current [`Transaction`].
+    fn with_transaction_mut<F, R>(&self, f: F) -> IcDbmsResult<R>
+    where
+        F: FnOnce(&mut Transaction) -> IcDbmsResult<R>,
+    {
+        let txid = self.transaction.as_ref().ok_or(IcDbmsError::Transaction(
+            TransactionError::NoActiveTransaction,
+        ))?;
+
+        TRANSACTION_SESSION.with_borrow_mut(|ts| {
+            let tx = ts.get_transaction_mut(txid)?;
+            f(tx)
+        })
+    }
+
+    /// Executes a closure with a reference to the current [`Transaction`].
+    fn with_transaction<F, R>(&self, f: F) -> IcDbmsResult<R>
+    where
+        F: FnOnce(&Transaction) -> IcDbmsResult<R>,
+    {
+        let txid = self.transaction.as_ref().ok_or(IcDbmsError::Transaction(
+            TransactionError::NoActiveTransaction,
+        ))?;
+
+        TRANSACTION_SESSION.with_borrow_mut(|ts| {
+            let tx = ts.get_transaction_mut(txid)?;
+            f(tx)
+        })
+    }
+
+    /// Executes a closure atomically within the database context.
+    ///
+    /// If the closure returns an error, the changes are rolled back by trapping the canister.
+    fn atomic<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&IcDbmsDatabase) -> IcDbmsResult<R>,
+    {
+        match f(self) {
+            Ok(res) => res,
+            Err(err) => trap(err.to_string()),
+        }
+    }
+
+    /// Deletes foreign key related records recursively if the delete behavior is [`DeleteBehavior::Cascade`].
+    fn delete_foreign_keys_cascade<T>(
+        &self,
+        record_values: &[(ColumnDef, Value)],
+    ) -> IcDbmsResult<u64>
+    where
+        T: TableSchema,
+    {
+        let referencing_tables = self.schema.referenced_tables(T::table_name());
+        if referencing_tables.is_empty() {
+            return Ok(0);
+        }
+
+        let mut count = 0;
+        let pk = Self::referenced_key::<T>(record_values)?;
+        // verify referenced tables for foreign key constraints
+        for (table, columns) in referencing_tables {
+            for column in columns.iter() {
+                // make filter to find records in the referenced table
+                let filter = Filter::eq(column, pk.clone());
+                let res = self
+                    .schema
+                    .delete(self, table, DeleteBehavior::Cascade, Some(filter))?;
+                count += res;
+            }
+        }
+        Ok(count)
+    }
+
+    /// Returns whether any record references the record of `T` with the given values,
+    /// as checked by [`UpdateBehavior::Restrict`].
+    fn is_referenced<T>(&self, record_values: &[(ColumnDef, Value)]) -> IcDbmsResult<bool>
+    where
+        T: TableSchema,
+    {
+        let referencing_tables = self.schema.referenced_tables(T::table_name());
+        if referencing_tables.is_empty() {
+            return Ok(false);
+        }
+
+        let pk = Self::referenced_key::<T>(record_values)?;
+        for (table, columns) in referencing_tables {
+            for column in columns.iter() {
+                if self
+                    .schema
+                    .exists(self, table, Filter::eq(column, pk.clone()))?
+                {
+                    return Ok(true);
+                }
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Updates the foreign keys referencing the primary key of the record of `T` from its `old` values to the `new` ones
+    /// if the update behavior is [`UpdateBehavior::Cascade`].
+    ///
+    /// All the columns of a record referencing the old primary key are updated at once,
+    /// since the foreign key check of the updated record would fail on the columns left referencing it.
+    /// So the referencing records of each table are read once, walking their referencing columns to find the ones to update,
+    /// and the records sharing the same columns to update are updated together.
+    ///
+    /// The related records are updated with [`UpdateBehavior::Cascade`] too,
+    /// so the change is propagated if the foreign keys are part of their primary key.
+    fn update_foreign_keys_cascade<T>(
+        &self,
+        old: &[(ColumnDef, Value)],
+        new: &[(ColumnDef, Value)],
+    ) -> IcDbmsResult<()>
+    where
+        T: TableSchema,
+    {
+        let referencing_tables = self.schema.referenced_tables(T::table_name());
+        if referencing_tables.is_empty() {
+            return Ok(());
+        }
+
+        let old_pk = Self::referenced_key::<T>(old)?;
+        let new_pk = Self::referenced_key::<T>(new)?;
+        for (table, columns) in referencing_tables {
+            let Some(referencing) = columns
+                .iter()
+                .map(|column| Filter::eq(column, old_pk.clone()))
+                .reduce(Filter::or)
+            else {
+                continue;
+            };
+
+            // the referencing columns to update of each record, as indexes of `columns`
+            let mut updates = BTreeSet::new();
+            for record_values in self.schema.select_raw(self, table, referencing)? {
+                let update = columns
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, column)| {
+                        record_values
+                            .iter()
+                            .any(|(col_def, value)| col_def.name == **column && *value == old_pk)
+                    })
+                    .map(|(index, _)| index)
+                    .collect::<Vec<_>>();
+                updates.insert(update);
+            }
+            if updates.is_empty() {
+                continue;
+            }
+
+            let table_columns = self
+                .schema
+                .table_fingerprints()
+                .into_iter()
+                .find(|fingerprint| fingerprint.table == *table)
+                .map(|fingerprint| fingerprint.columns)
+                .ok_or(IcDbmsError::Query(QueryError::TableNotFound(table)))?;
+            for update in updates {
+                let mut patch = Vec::with_capacity(update.len());
+                let mut filter: Option<Filter> = None;
+                for (index, column) in columns.iter().enumerate() {
+                    let column_filter = Filter::eq(column, old_pk.clone());
+                    let column_filter = if update.contains(&index) {
+                        let col_def = table_columns
+                            .iter()
+                            .find(|col_def| col_def.name == *column)
+                            .copied()
+                            .ok_or(IcDbmsError::Query(QueryError::UnknownColumn(
+                                column.to_string(),
+                            )))?;
+                        patch.push((col_def, new_pk.clone()));
+                        column_filter
+                    } else {
+                        column_filter.not()
+                    };
+                    filter = Some(match filter {
+                        Some(filter) => filter.and(column_filter),
+                        None => column_filter,
+                    });
+                }
+                self.schema
+                    .update(self, table, UpdateBehavior::Cascade, &patch, filter)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Inserts a record directly into the database, without notifying the watchers.
+    ///
+    /// Returns the column values of the inserted record.
+    fn insert_record<T>(&self, record: T::Insert) -> IcDbmsResult<Vec<(ColumnDef, Value)>>
+    where
+        T: TableSchema,
+        T::Insert: InsertRecord<Schema = T>,
+    {
+        // check whether the insert is valid
+        let record_values = record.clone().into_values();
+        self.schema
+            .validate_insert(self, T::table_name(), &record_values)?;
+        self.store_record::<T>(record)?;
+
+        Ok(record_values)
+    }
+
+    /// Writes the given record of `T` to memory, logging it to the [`ChangeLog`], without validating it.
+    fn store_record<T>(&self, record: T::Insert) -> IcDbmsResult<()>
+    where
+        T: TableSchema,
+        T::Insert: InsertRecord<Schema = T>,
+    {
+        let migrated_row =
+            migration::prepare_dual_write(T::fingerprint(), &record.clone().into_values())?;
+        let record = record.into_record()?;
+        ChangeLog::record_insert(&record)?;
+        let mut table_registry = self.load_table_registry_for_write::<T>()?;
+        table_registry.insert(record)?;
+        if let Some(row) = migrated_row {
+            migration::dual_write(T::fingerprint(), row)?;
+        }
+
+        Ok(())
+    }
+
+    /// Deletes the records matching the filter directly from the database,
+    /// without notifying the watchers of `T`.
+    ///
+    /// Returns the number of deleted records, including the ones deleted by cascade,
+    /// and the column values of the deleted records of `T`.
+    #[allow(clippy::type_complexity)]
+    fn delete_records<T>(
+        &self,
+        behaviour: DeleteBehavior,
+        filter: Option<Filter>,
+    ) -> IcDbmsResult<(u64, Vec<Vec<(ColumnDef, Value)>>)>
+    where
+        T: TableSchema,
+    {
+        migration::check_not_migrating(T::fingerprint())?;
+
+        // select all records matching the filter
+        // read table
+        let mut table_registry = self.load_table_registry_for_write::<T>()?;
+        let mut records = vec![];
+        // iter all records
+        // FIXME: this may be huge, we should do better
+        for values in table_registry.read::<T>() {
+            let values = values?;
+            let record_values = values.record.clone().to_values();
+            if let Some(filter) = &filter {
+                if !self.record_matches_filter(&record_values, filter)? {
+                    continue;
+                }
+            }
+            records.push((values, record_values));
+        }
+        // deleted records
+        let mut count = records.len() as u64;
+        let mut deleted_records = Vec::with_capacity(records.len());
+        for (record, record_values) in records {
+            // match delete behaviour
+            match behaviour {
+                DeleteBehavior::Cascade => {
+                    // delete recursively foreign keys if cascade
+                    count += self.delete_foreign_keys_cascade::<T>(&record_values)?;
+                }
+                DeleteBehavior::Restrict => {
+                    if self.delete_foreign_keys_cascade::<T>(&record_values)? > 0 {
+                        // it's okay; the caller panics since deletes are atomic
+                        return Err(IcDbmsError::Query(
+                            QueryError::ForeignKeyConstraintViolation {
+                                referencing_table: T::table_name(),
+                                field: T::primary_key(),
+                            },
+                        ));
+                    }
+                }
+                DeleteBehavior::Break => {
+                    // do nothing
+                }
+            }
+            // eventually delete the record
+            ChangeLog::record_delete(&record.record)?;
+            table_registry.delete(record.record, record.page, record.offset)?;
+            deleted_records.push(record_values);
+        }
+
+        Ok((count, deleted_records))
+    }
+
+    /// Checks that no record references the records with the given primary keys,
+    /// as required by [`DeleteBehavior::Restrict`] when deleting within a transaction.
+    ///
+    /// References are found by cascading the delete within the transaction,
+    /// which is then restored to its previous state.
+    fn check_restrict_in_transaction<T>(&self, primary_keys: &[Vec<Value>]) -> IcDbmsResult<()>
+    where
+        T: TableSchema,
+    {
+        let pk_columns = T::primary_keys()
+            .iter()
+            .map(|pk| {
+                T::columns()
+                    .iter()
+                    .find(|col_def| col_def.name == *pk)
+                    .copied()
+                    .ok_or(IcDbmsError::Query(QueryError::UnknownColumn(
+                        pk.to_string(),
+                    )))
+            })
+            .collect::<IcDbmsResult<Vec<ColumnDef>>>()?;
+
+        let checkpoint = self.with_transaction(|tx| Ok(tx.clone()))?;
+        let references = primary_keys.iter().try_fold(0, |references, pk| {
+            let record_values = pk_columns
+                .iter()
+                .copied()
+                .zip(pk.iter().cloned())
+                .collect::<Vec<_>>();
+            self.delete_foreign_keys_cascade::<T>(&record_values)
+                .map(|count| references + count)
+        });
+        self.with_transaction_mut(|tx| {
+            *tx = checkpoint;
+            Ok(())
+        })?;
+
+        if references? > 0 {
+            return Err(IcDbmsError::Query(
+                QueryError::ForeignKeyConstraintViolation {
+                    referencing_table: T::table_name(),
+                    field: T::primary_key(),
+                },
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Applies the patch to the records matching its filter and validates the updated values.
+    ///
+    /// Returns the column values of each matching record, before and after the update.
+    #[allow(clippy::type_complexity)]
+    fn updated_records<T>(
+        &self,
+        patch: &T::Update,
+    ) -> IcDbmsResult<Vec<(Vec<(ColumnDef, Value)>, Vec<(ColumnDef, Value)>)>>
+    where
+        T: TableSchema,
+        T::Update: UpdateRecord<Schema = T>,
+    {
+        // get all records matching the filter
+        let records = self.select::<T>(Self::update_query::<T>(patch.where_clause()))?;
+
+        // apply patch to records and validate the updated values
+        let patch_values = patch.update_values();
+        let validator = UpdateIntegrityValidator::<T>::new(self);
+        validator.validate_unique(&patch_values, records.len(), patch.where_clause())?;
+        records
+            .into_iter()
+            .map(|record| {
+                let mut record_values = record.to_values();
+                for (col_def, value) in &patch_values {
+                    if let Some((_, record_value)) = record_values
+                        .iter_mut()
+                        .find(|(record_col_def, _)| record_col_def.name == col_def.name)
+                    {
+                        *record_value = value.clone();
+                    }
+                }
+                validator.validate(&record_values)?;
+                Ok((record.to_values(), record_values))
+            })
+            .collect()
+    }
+
+    /// Builds the query selecting the records updated by a patch with the given where clause.
+    ///
+    /// Soft-deleted records are updated only if the where clause explicitly targets the `deleted_at` column,
+    /// like [`IcDbmsDatabase::restore`] does.
+    fn update_query<T>(where_clause: Option<Filter>) -> Query<T>
+    where
+        T: TableSchema,
+    {
+        let targets_deleted = T::SOFT_DELETE_COLUMN.is_some_and(|column| {
+            where_clause
+                .as_ref()
+                .is_some_and(|filter| filter.references(column))
+        });
+        let query = Query::builder().filter(where_clause);
+        if targets_deleted {
+            query.include_deleted().build()
+        } else {
+            query.build()
+        }
+    }
+
+    /// Applies a [`TransactionOp`] of a transaction being committed.
+    fn apply_operation(&self, op: TransactionOp) -> IcDbmsResult<()> {
+        match op {
+            TransactionOp::Insert { table, values } => {
+                // validate
+                self.schema.validate_insert(self, table, &values)?;
+                // insert
+                self.atomic(|db| db.schema.insert(db, table, &values));
+            }
+            TransactionOp::Delete {
+                table,
+                behaviour,
+                filter,
+            } => {
+                self.atomic(|db| db.schema.delete(db, table, behaviour, filter));
+            }
+            TransactionOp::Update {
+                table,
+                behaviour,
+                patch,
+                filter,
+            } => {
+                self.atomic(|db| db.schema.update(db, table, behaviour, &patch, filter));
+            }
+            TransactionOp::Upsert { table, values } => {
+                // whether the record is inserted or updated depends on the committed state
+                self.atomic(|db| db.schema.upsert(db, table, &values));
+            }
+            TransactionOp::IfExists {
+                table,
+                filter,
+                then_ops,
+                else_ops,
+            } => {
+                // the filter is evaluated against the committed state
+                let ops = if self.atomic(|db| db.schema.exists(db, table, filter)) {
+                    then_ops
+                } else {
+                    else_ops
+                };
+                for op in ops {
+                    self.apply_operation(op)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Retrieves the [`DatabaseOverlay`] of the current transaction.
+    ///
+    /// Only the overlay of the transaction identified by `self.transaction` is returned,
+    /// so the uncommitted changes of the other transactions are not visible.
+    fn overlay(&self) -> IcDbmsResult<DatabaseOverlay> {
+        self.with_transaction(|tx| Ok(tx.overlay().clone()))
+    }
+
+    /// Returns whether the read given record matches the provided filter.
+    fn record_matches_filter(
+        &self,
+        record_values: &[(ColumnDef, Value)],
+        filter: &Filter,
+    ) -> IcDbmsResult<bool> {
+        filter.matches(record_values).map_err(IcDbmsError::from)
+    }
+
+    /// Returns the filter to apply when selecting records for the given query.
+    ///
+    /// If the table supports soft-deletion and the query doesn't include deleted records,
+    /// the query filter is combined with a [`Filter::IsNull`] on the `deleted_at` column.
+    fn select_filter<T>(&self, query: &Query<T>) -> Option<Filter>
+    where
+        T: TableSchema,
+    {
+        match (T::SOFT_DELETE_COLUMN, query.filter.clone()) {
+            (Some(column), Some(filter)) if !query.include_deleted => {
+                Some(filter.and(Filter::is_null(column)))
+            }
+            (Some(column), None) if !query.include_deleted => Some(Filter::is_null(column)),
+            (_, filter) => filter,
+        }
+    }
+
+    /// Select only the queried fields from the given record values.
+    ///
+    /// It also loads eager relations if any.
+    fn select_queried_fields<T>(
+        &self,
+        mut record_values: Vec<(ColumnDef, Value)>,
+        query: &Query<T>,
+    ) -> IcDbmsResult<TableColumns>
+    where
+        T: TableSchema,
+    {
+        let mut queried_fields = vec![];
+
+        // handle eager relations
+        // FIXME: currently we fetch the FK for each record, which is shit.
+        // In the future, we should batch fetch foreign keys for all records in the result set.
+        for relation in &query.eager_relations {
+            let mut fetched = false;
+            // iter all foreign key with that table
+            for (fk, fk_value) in record_values
+                .iter()
+                .filter(|(col_def, _)| {
+                    col_def
+                        .foreign_key
+                        .is_some_and(|fk| fk.foreign_table == *relation)
+                })
+                .map(|(col, value)| {
+                    (
+                        col.foreign_key.as_ref().expect("cannot be empty"),
+                        value.clone(),
+                    )
+                })
+            {
+                // get foreign values
+                queried_fields.extend(T::foreign_fetcher().fetch(
+                    self,
+                    relation,
+                    fk.local_column,
+                    fk_value,
+                )?);
+                fetched = true;
+            }
+
+            if !fetched {
+                return Err(IcDbmsError::Query(QueryError::InvalidQuery(format!(
+                    "Cannot load relation '{}' for table '{}': no foreign key found",
+                    relation,
+                    T::table_name()
+                ))));
+            }
+        }
+
+        // append computed columns, evaluated on the stored values
+        let computed_values: Vec<(ColumnDef, Value)> = T::computed_columns()
+            .iter()
+            .map(|computed| computed.evaluate(&record_values))
+            .collect();
+        record_values.extend(computed_values);
+
+        // short-circuit if all selected
+        if query.all_selected() {
+            queried_fields.extend(vec![(ValuesSource::This, record_values)]);
+            return Ok(queried_fields);
+        }
+        // rename and transform the mapped columns; a column may be mapped more than once
+        if let Some(mappings) = query.column_mappings() {
+            let mapped_values = mappings
+                .iter()
+                .filter_map(|mapping| {
+                    record_values
+                        .iter()
+                        .find(|(col_def, _)| col_def.name == mapping.source)
+                        .map(|(col_def, value)| mapping.apply(*col_def, value.clone()))
+                })
+                .collect();
+            queried_fields.extend(vec![(ValuesSource::This, mapped_values)]);
+            return Ok(queried_fields);
+        }
+        record_values.retain(|(col_def, _)| query.columns().contains(&col_def.name));
+        queried_fields.extend(vec![(ValuesSource::This, record_values)]);
+        Ok(queried_fields)
+    }
+
+    /// Selects the records of `query_a` whose primary key is among the ones selected by `query_b`,
+    /// if `in_b` is `true`, or is not among them otherwise.
+    fn select_combined<T>(
+        &self,
+        mut query_a: Query<T>,
+        query_b: Query<T>,
+        in_b: bool,
+    ) -> IcDbmsResult<Vec<T::Record>>
+    where
+        T: TableSchema,
+    {
+        let pks_b = self.primary_keys_set(&query_b)?;
+        let pks = self
+            .primary_keys_set(&query_a)?
+            .into_iter()
+            .filter(|pk| pks_b.contains(pk) == in_b)
+            .collect::<Vec<_>>();
+        if pks.is_empty() {
+            return Ok(vec![]);
+        }
+
+        // fetch the records by primary key
+        query_a.filter = Some(Self::primary_keys_filter::<T>(pks));
+        self.select(query_a)
+    }
+
+    /// Builds a filter matching the records with the given primary keys, which must not be empty.
+    fn primary_keys_filter<T>(pks: Vec<Vec<Value>>) -> Filter
+    where
+        T: TableSchema,
+    {
+        match T::primary_keys() {
+            [pk] => Filter::in_list(*pk, pks.into_iter().flatten().collect()),
+            primary_keys => pks
+                .into_iter()
+                .map(|pk| Filter::eq_composite(primary_keys, pk))
+                .reduce(Filter::or)
+                .expect("primary keys must not be empty"),
+        }
+    }
+
+    /// Retrieves the set of primary keys of the records matching the filter of the given query.
+    fn primary_keys_set<T>(&self, query: &Query<T>) -> IcDbmsResult<BTreeSet<Vec<Value>>>
+    where
+        T: TableSchema,
+    {
+        let mut pk_query = Query::builder().filter(query.filter.clone());
+        if query.include_deleted {
+            pk_query = pk_query.include_deleted();
+        }
+
+        Ok(self
+            .existing_primary_keys_for_query::<T>(pk_query.build())?
+            .into_iter()
+            .collect())
+    }
+
+    /// Retrieves existing primary keys for records matching the given query.
+    ///
+    /// Each primary key holds the values of [`TableSchema::primary_keys`], in the same order.
+    fn existing_primary_keys_for_query<T>(&self, query: Query<T>) -> IcDbmsResult<Vec<Vec<Value>>>
+    where
+        T: TableSchema,
+    {
+        let fields = self.select(query)?;
+        let pks = fields
+            .into_iter()
+            .map(|record| Self::primary_key_values::<T>(&record.to_values()))
+            .collect::<Vec<Vec<Value>>>();
+
+        Ok(pks)
+    }
+
+    /// Replaces the [`Value::AutoIncrement`] values of the record with the next values of the sequence of `T`.
+    ///
+    /// Fails with [`QueryError::InvalidQuery`] if the placeholder is set on a column which is not an auto-increment
+    /// [`DataTypeKind::AutoIncrement`], [`DataTypeKind::Uint32`] or [`DataTypeKind::Uint64`] column,
+    /// or if the sequence doesn't fit the column anymore.
+    fn assign_auto_increment<T>(record: T::Insert) -> IcDbmsResult<T::Insert>
+    where
+        T: TableSchema,
+        T::Insert: InsertRecord<Schema = T>,
+    {
+        if !T::columns().iter().any(|column| column.auto_increment) {
+            return Ok(record);
+        }
+
+        let mut record_values = record.clone().into_values();
+        if !record_values
+            .iter()
+            .any(|(_, value)| *value == Value::AutoIncrement)
+        {
+            return Ok(record);
+        }
+
+        for (column, value) in record_values
+            .iter_mut()
+            .filter(|(_, value)| *value == Value::AutoIncrement)
+        {
+            if !column.auto_increment {
+                return Err(IcDbmsError::Query(QueryError::InvalidQuery(format!(
+                    "column '{}' is not an auto-increment column",
+                    column.name
+                ))));
+            }
+            let next =
+                SCHEMA_REGISTRY.with_borrow_mut(|sr| sr.next_sequence_value(T::fingerprint()))?;
+            *value = match column.data_type {
+                DataTypeKind::Uint32 => u32::try_from(next)
+                    .map(|next| Value::Uint32(next.into()))
+                    .map_err(|_| {
+                        QueryError::InvalidQuery(format!(
+                            "sequence of column '{}' exceeded the Uint32 range",
+                            column.name
+                        ))
+                    })?,
+                DataTypeKind::Uint64 | DataTypeKind::AutoIncrement => Value::Uint64(next.into()),
+                data_type => {
+                    return Err(IcDbmsError::Query(QueryError::InvalidQuery(format!(
+                        "auto-increment column '{}' can't be of type {data_type:?}",
+                        column.name
+                    ))));
+                }
+            };
+        }
+
+        T::Insert::from_values(&record_values)
+    }
+
+    /// Returns the values of the [`TableSchema::primary_keys`] columns of a record, in the same order.
+    fn primary_key_values<T>(record_values: &[(ColumnDef, Value)]) -> Vec<Value>
+    where
+        T: TableSchema,
+    {
+        T::primary_keys()
+            .iter()
+            .map(|pk| {
+                record_values
+                    .iter()
+                    .find(|(col_def, _value)| col_def.name == *pk)
+                    .expect("primary key not found") // this can't fail.
+                    .1
+                    .clone()
+            })
+            .collect()
+    }
+
+    /// Returns the value of the primary key of a record of `T` referenced by foreign keys.
+    ///
+    /// A foreign key is a single column, so it can't reference a composite primary key:
+    /// matching only one of its columns would select the records referencing other records too.
+    /// Returns [`QueryError::InvalidQuery`] if `T` has a composite primary key.
+    fn referenced_key<T>(record_values: &[(ColumnDef, Value)]) -> IcDbmsResult<Value>
+    where
+        T: TableSchema,
+    {
+        if T::primary_keys().len() > 1 {
+            return Err(IcDbmsError::Query(QueryError::InvalidQuery(format!(
+                "foreign keys can't reference the composite primary key of table '{}'",
+                T::table_name()
+            ))));
+        }
+
+        record_values
+            .iter()
+            .find(|(col_def, _value)| col_def.name == T::primary_key())
+            .map(|(_, value)| value.clone())
+            .ok_or(IcDbmsError::Query(QueryError::UnknownColumn(
+                T::primary_key().to_string(),
+            )))
+    }
+
+    /// Returns the value of the [`TableSchema::primary_key`] column of a record.
+    fn primary_key_value<T>(record_values: &[(ColumnDef, Value)]) -> &Value
+    where
+        T: TableSchema,
+    {
+        record_values
+            .iter()
+            .find(|(col_def, _value)| col_def.name == T::primary_key())
+            .map(|(_, value)| value)
+            .expect("primary key not found") // this can't fail.
+    }
+
+    /// Compares the [`TableSchema::primary_key`] value of a record with the given [`Query::cursor`].
+    ///
+    /// Returns [`QueryError::TypeMismatch`] if the cursor has a different type than the primary key.
+    fn compare_primary_key<T>(
+        record_values: &[(ColumnDef, Value)],
+        cursor: &Value,
+    ) -> IcDbmsResult<std::cmp::Ordering>
+    where
+        T: TableSchema,
+    {
+        let pk = Self::primary_key_value::<T>(record_values);
+        pk.typed_cmp(cursor).map_err(|_| {
+            IcDbmsError::Query(QueryError::TypeMismatch {
+                column: T::primary_key(),
+                expected: pk.type_name(),
+                found: cursor.type_name(),
+            })
+        })
+    }
+
+    /// Scans the table of `T` selecting the records matching the query.
+    ///
+    /// The scan starts from the given [`Cursor`], if any, and it's bounded by the given [`CycleBudget`], if any.
+    /// If the [`Query::timeout_ns`] is exceeded, the scan is truncated when `partial_on_timeout` is set,
+    /// otherwise it fails with [`QueryError::Timeout`].
+    fn scan<T>(
+        &self,
+        query: Query<T>,
+        cursor: Option<Cursor>,
+        budget: Option<CycleBudget>,
+        partial_on_timeout: bool,
+    ) -> IcDbmsResult<PartialSelectResult<T>>
+    where
+        T: TableSchema,
+    {
+        let (results, next_cursor) =
+            self.scan_columns(query, cursor, budget, partial_on_timeout, false)?;
+
+        Ok(PartialSelectResult {
+            records: results.into_iter().map(T::Record::from_values).collect(),
+            truncated: next_cursor.is_some(),
+            cursor: next_cursor,
+        })
+    }
+
+    /// Scans the table of `T` selecting the column values of the records matching the query,
+    /// as [`IcDbmsDatabase::scan`] does.
+    ///
+    /// If the [`Query::cursor`] is set or `by_primary_key` is, the records are selected in primary key order:
+    /// if the table has an index on the primary key, it's walked from the cursor until the limit is reached,
+    /// otherwise the matching records are collected and sorted by primary key before applying the offset and the limit,
+    /// keeping only the first `offset + limit` of them along the way.
+    ///
+    /// Returns the [`TableColumns`] of the selected records and the [`Cursor`] to resume the scan, if truncated.
+    fn scan_columns<T>(
+        &self,
+        query: Query<T>,
+        cursor: Option<Cursor>,
+        budget: Option<CycleBudget>,
+        partial_on_timeout: bool,
+        by_primary_key: bool,
+    ) -> IcDbmsResult<(Vec<TableColumns>, Option<Cursor>)>
+    where
+        T: TableSchema,
+    {
+        let start_time = time();
+        // load table registry
+        let table_registry = self.load_table_registry::<T>()?;
+        // get filter, excluding soft-deleted records if needed
+        let filter = self.select_filter(&query);
+        // get database overlay
+        let mut table_overlay = if self.transaction.is_some() {
+            self.overlay()?
+        } else {
+            DatabaseOverlay::default()
+        };
+        // records to select in primary key order, when paginating by primary key
+        let by_primary_key = by_primary_key || query.cursor.is_some();
+        // walk the index on the primary key, if any, when paginating by primary key,
+        // otherwise read only the records located by an index, if any applies to the filter;
+        // the cursor is a position in the scanned records, so resumed scans read them the same way
+        let resumed_table_scan = cursor.is_some_and(|cursor| cursor.index.is_none());
+        let walked_rows = if by_primary_key && !resumed_table_scan {
+            self.primary_key_walk::<T>(&table_registry, query.cursor.as_ref())?
+        } else {
+            None
+        };
+        let located = if resumed_table_scan || walked_rows.is_some() {
+            None
+        } else {
+            self.index_lookup(&table_registry, filter.as_ref())?
+        };
+        let index = match walked_rows {
+            Some(_) => Some(T::primary_key()),
+            None => located.as_ref().map(|(column, _)| *column),
+        };
+        if let Some(column) = cursor.and_then(|cursor| cursor.index) {
+            if index != Some(column) {
+                return Err(IcDbmsError::Query(QueryError::InvalidQuery(format!(
+                    "the index on '{column}' used by the truncated scan can't be used to resume it"
+                ))));
+            }
+        }
+        // the walked records are already sorted by primary key
+        let sort_by_primary_key = by_primary_key && walked_rows.is_none();
+        // otherwise read table, with the overlay applied
+        let mut table_reader: ScannedRecords<'_> = match (walked_rows, located) {
+            (Some(rows), _) => rows,
+            (None, Some((_, locations))) => Box::new(
+                Self::read_located::<T>(&table_registry, locations)?
+                    .into_iter()
+                    .map(Ok),
+            ),
+            (None, None) => Box::new(table_overlay.reader(table_registry.read::<T>())),
+        };
+
+        // skip the records already scanned
+        let start = cursor.map(|cursor| cursor.position).unwrap_or_default();
+        let mut scanned = 0;
+        for next in table_reader.by_ref().take(start as usize) {
+            next?;
+            scanned += 1;
+        }
+
+        // prepare results vector
+        let mut results = Vec::with_capacity(query.limit.unwrap_or(DEFAULT_SELECT_LIMIT));
+        // iter and select
+        let mut count = 0;
+        let mut next_cursor = None;
+        // distinct records are deduplicated once sorted
+        let distinct = query.is_distinct();
+        let mut deferred_records = Vec::new();
+        // only the first records by primary key can be selected, so the others are dropped along the way
+        let keep = query
+            .limit
+            .filter(|_| sort_by_primary_key && !distinct)
+            .map(|limit| limit + query.offset.unwrap_or_default());
+
+        loop {
+            let periodic_check = scanned > start && (scanned - start) % BUDGET_CHECK_INTERVAL == 0;
+            // check the timeout periodically
+            if let Some(timeout_ns) = query.timeout_ns.filter(|_| periodic_check) {
+                let elapsed_ns = time().saturating_sub(start_time);
+                if elapsed_ns > timeout_ns {
+                    if partial_on_timeout {
+                        next_cursor = Some(Cursor {
+                            position: scanned,
+                            index,
+                        });
+                        break;
+                    }
+                    return Err(IcDbmsError::Query(QueryError::Timeout {
+                        elapsed_ns,
+                        records_scanned: scanned,
+                    }));
+                }
+            }
+            // check the budget periodically
+            if let Some(budget) = budget.filter(|_| periodic_check) {
+                match budget.check(performance_counter()) {
+                    BudgetStatus::Available => {}
+                    BudgetStatus::SoftLimitExceeded => {
+                        next_cursor = Some(Cursor {
+                            position: scanned,
+                            index,
+                        });
+                        break;
+                    }
+                    BudgetStatus::HardLimitExceeded => {
+                        trap(format!(
+                            "SELECT on {} exceeded the hard instructions limit",
+                            T::table_name()
+                        ));
+                    }
+                }
+            }
+            let Some(values) = table_reader.next().transpose()? else {
+                break;
+            };
+            scanned += 1;
+            // check whether it matches the filter
+            if let Some(filter) = &filter {
+                if !self.record_matches_filter(&values, filter)? {
+                    continue;
+                }
+            }
+            // check whether is up to the cursor
+            if let Some(after) = &query.cursor {
+                if Self::compare_primary_key::<T>(&values, after)?.is_le() {
+                    continue;
+                }
+            }
+            // offset and limit are applied once sorted by primary key or deduplicated
+            if sort_by_primary_key || distinct {
+                deferred_records.push(values);
+                if keep.is_some_and(|keep| deferred_records.len() > 2 * keep.max(1)) {
+                    Self::sort_by_primary_key::<T>(&mut deferred_records);
+                    deferred_records.truncate(keep.unwrap_or_default());
+                }
+                continue;
+            }
+            // filter matched, check limit and offset
+            count += 1;
+            // check whether is before offset
+            if query.offset.is_some_and(|offset| count <= offset) {
+                continue;
+            }
+            // get queried fields
+            let values = self.select_queried_fields::<T>(values, &query)?;
+            // push to results
+            results.push(values);
+            // check whether reached limit
+            if query.limit.is_some_and(|limit| results.len() >= limit) {
+                break;
+            }
+        }
+
+        if sort_by_primary_key {
+            Self::sort_by_primary_key::<T>(&mut deferred_records);
+        }
+        if distinct {
+            let mut distinct_results = deferred_records
+                .into_iter()
+                .map(|values| self.select_queried_fields::<T>(values, &query))
+                .collect::<IcDbmsResult<Vec<_>>>()?;
+            // sort before deduplicating, so that the first of the duplicates is kept
+            for (column, direction) in &query.order_by {
+                self.sort_query_results(&mut distinct_results, *column, direction.clone())?;
+            }
+            Self::deduplicate(&mut distinct_results);
+            results.extend(
+                distinct_results
+                    .into_iter()
+                    .skip(query.offset.unwrap_or_default())
+                    .take(query.limit.unwrap_or(usize::MAX)),
+            );
+
+            return Ok((results, next_cursor));
+        }
+        for values in deferred_records
+            .into_iter()
+            .skip(query.offset.unwrap_or_default())
+            .take(query.limit.unwrap_or(usize::MAX))
+        {
+            results.push(self.select_queried_fields::<T>(values, &query)?);
+        }
+
+        // sort results if needed
+        for (column, direction) in query.order_by {
+            self.sort_query_results(&mut results, column, direction)?;
+        }
+
+        Ok((results, next_cursor))
+    }
+
+    /// Sorts the given records of `T` by [`TableSchema::primary_key`] value.
+    fn sort_by_primary_key<T>(records: &mut [Vec<(ColumnDef, Value)>])
+    where
+        T: TableSchema,
+    {
+        records
+            .sort_by(|a, b| Self::primary_key_value::<T>(a).cmp(Self::primary_key_value::<T>(b)));
+    }
+
+    /// Removes the duplicate results of a DISTINCT query, keeping the first of them.
+    ///
+    /// Results are duplicates when all their selected column values are equal, `NULL` values included.
+    fn deduplicate(results: &mut Vec<TableColumns>) {
+        let mut seen = HashSet::new();
+        results.retain(|record| {
+            let key: Vec<Value> = record
+                .iter()
+                .filter(|(source, _)| *source == ValuesSource::This)
+                .flat_map(|(_, values)| values.iter().map(|(_, value)| value.clone()))
+                .collect();
+            seen.insert(key)
+        });
+    }
+
+    /// Looks up the locations of the records which may match the filter,
+    /// in the index on a column compared by the filter, as `TableIndexes::lookup_filter` does.
+    ///
+    /// Indexes are never used within a transaction, since its changes are not indexed.
+    ///
+    /// Returns the indexed column and the locations sorted as in the table, or [`None`] if no index applies.
+    #[cfg_attr(not(feature = "indexes"), allow(unused_variables))]
+    fn index_lookup(
+        &self,
+        table_registry: &TableRegistry,
+        filter: Option<&Filter>,
+    ) -> IcDbmsResult<Option<(&'static str, Vec<(Page, PageOffset)>)>> {
+        #[cfg(feature = "indexes")]
+        if let (Some(filter), Some(indexes), None) =
+            (filter, table_registry.indexes(), &self.transaction)
+        {
+            return Ok(indexes.lookup_filter(filter)?.map(|(column, entries)| {
+                let mut locations: Vec<_> = entries
+                    .into_iter()
+                    .map(|entry| (entry.page, entry.offset))
+                    .collect();
+                locations.sort_unstable();
+                (column, locations)
+            }));
+        }
+
+        Ok(None)
+    }
+
+    /// Returns the records of `T` after the given primary key value, if any, in primary key order,
+    /// reading them as the index on the [`TableSchema::primary_key`] is walked, if the table has one.
+    ///
+    /// Indexes are never used within a transaction, since its changes are not indexed.
+    ///
+    /// Returns [`None`] if the table has no index on the primary key.
+    #[cfg_attr(not(feature = "indexes"), allow(unused_variables))]
+    fn primary_key_walk<'a, T>(
+        &self,
+        table_registry: &'a TableRegistry,
+        after: Option<&Value>,
+    ) -> IcDbmsResult<Option<ScannedRecords<'a>>>
+    where
+        T: TableSchema,
+    {
+        #[cfg(feature = "indexes")]
+        if let (Some(indexes), None) = (table_registry.indexes(), &self.transaction) {
+            if let Some(maintainer) = indexes.maintainer(T::primary_key()) {
+                let lower = after.map_or(std::ops::Bound::Unbounded, |after| {
+                    std::ops::Bound::Excluded(after.clone())
+                });
+                let mut table_reader = table_registry.read::<T>();
+                let rows = maintainer
+                    .scan(lower, std::ops::Bound::Unbounded)?
+                    .filter_map(move |entry| {
+                        let entry = match entry {
+                            Ok(entry) => entry,
+                            Err(err) => return Some(Err(err.into())),
+                        };
+                        match table_reader.seek_to_offset(entry.page, entry.offset) {
+                            Ok(Some(next)) => Some(Ok(next.record.to_values())),
+                            Ok(None) => {
+                                crate::dbms_log!(WARN, "no record found at indexed location"; "table" => T::table_name(), "page" => entry.page, "offset" => entry.offset);
+                                None
+                            }
+                            Err(err) => Some(Err(err.into())),
+                        }
+                    });
+                return Ok(Some(Box::new(rows)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Reads the values of the records of `T` stored at the given locations, such as those returned by
+    /// [`IcDbmsDatabase::index_lookup`].
+    ///
+    /// Locations without a record are skipped.
+    fn read_located<T>(
+        table_registry: &TableRegistry,
+        locations: Vec<(Page, PageOffset)>,
+    ) -> IcDbmsResult<Vec<Vec<(ColumnDef, Value)>>>
+    where
+        T: TableSchema,
+    {
+        let mut table_reader = table_registry.read::<T>();
+        let mut rows = Vec::with_capacity(locations.len());
+        for (page, offset) in locations {
+            match table_reader.seek_to_offset(page, offset)? {
+                Some(next) => rows.push(next.record.to_values()),
+                None => {
+                    crate::dbms_log!(WARN, "no record found at indexed location"; "table" => T::table_name(), "page" => page, "offset" => offset);
+                }
+            }
+        }
+
+        Ok(rows)
+    }
+
+    /// Load the table registry for the given table schema.
+    fn load_table_registry<T>(&self) -> IcDbmsResult<TableRegistry>
+    where
+        T: TableSchema,
+    {
+        // get pages of the table registry from schema registry
+        let registry_pages = SCHEMA_REGISTRY
+            .with_borrow(|schema| schema.table_registry_page::<T>())
+            .ok_or(IcDbmsError::Table(TableError::TableNotFound))?;
+
+        let quota = SCHEMA_REGISTRY
+            .with_borrow(|schema| schema.table_quota(T::fingerprint()))
+            .unwrap_or_default();
+
+        let registry = TableRegistry::load(registry_pages)?
+            .with_quota(T::fingerprint(), quota)
+            .with_record_size_estimate(TableRegistry::estimate_max_record_size(T::columns()));
+
+        #[cfg(feature = "compression")]
+        let registry = registry.with_compression(T::use_compression());
+
+        // keep the indexes of the table up to date on every write, rebuilding them first
+        // if a build without the `indexes` feature has written the table
+        #[cfg(feature = "indexes")]
+        SCHEMA_REGISTRY.with_borrow_mut(|schema| schema.rebuild_stale_indexes::<T>())?;
+        #[cfg(feature = "indexes")]
+        let registry = match TableIndexes::load::<T>() {
+            Some(indexes) => registry.with_indexes(indexes),
+            None => registry,
+        };
+
+        Ok(registry)
+    }
+
+    /// Load the table registry for the given table schema to write its records.
+    ///
+    /// A build without the `indexes` feature doesn't keep the indexes of the table up to date,
+    /// so they are marked as stale before writing; reads never mark them.
+    fn load_table_registry_for_write<T>(&self) -> IcDbmsResult<TableRegistry>
+    where
+        T: TableSchema,
+    {
+        #[cfg(not(feature = "indexes"))]
+        SCHEMA_REGISTRY.with_borrow_mut(|schema| schema.mark_indexes_stale(T::fingerprint()))?;
+
+        self.load_table_registry::<T>()
+    }
+
+    /// Sorts the query results based on the specified column and order direction.
+    ///
+    /// We only sort values which have [`ValuesSource::This`].
+    ///
+    /// Returns [`QueryError::TypeMismatch`] if the non-NULL values of the column have different types.
+    #[allow(clippy::type_complexity)]
+    fn sort_query_results(
+        &self,
+        results: &mut [Vec<(ValuesSource, Vec<(ColumnDef, Value)>)>],
+        column: &'static str,
+        direction: OrderDirection,
+    ) -> IcDbmsResult<()> {
+        fn sort_value<'a>(
+            record: &'a [(ValuesSource, Vec<(ColumnDef, Value)>)],
+            column: &str,
+        ) -> Option<&'a Value> {
+            record
+                .iter()
+                .find(|(source, _)| *source == ValuesSource::This)
+                .and_then(|(_, cols)| {
+                    cols.iter()
+                        .find(|(col_def, _)| col_def.name == column)
+                        .map(|(_, value)| value)
+                })
+        }
+
+        // all the values must be comparable with each other
+        let mut sorted_values = results
+            .iter()
+            .filter_map(|record| sort_value(record, column))
+            .filter(|value| !value.is_null());
+        if let Some(first) = sorted_values.next() {
+            if let Some(mismatching) = sorted_values.find(|value| first.typed_cmp(value).is_err()) {
+                return Err(IcDbmsError::Query(QueryError::TypeMismatch {
+                    column,
+                    expected: first.type_name(),
+                    found: mismatching.type_name(),
+                }));
+            }
+        }
+
+        results.sort_by(|a, b| {
+            match (sort_value(a, column), sort_value(b, column)) {
+                (Some(a_val), Some(b_val)) => {
+                    // NULL values are ordered as by the `Ord` implementation of `Value`
+                    let ordering = a_val.typed_cmp(b_val).unwrap_or_else(|_| a_val.cmp(b_val));
+                    match direction {
+                        OrderDirection::Ascending => ordering,
+                        OrderDirection::Descending => ordering.reverse(),
+                    }
+                }
+                (Some(_), None) => std::cmp::Ordering::Greater,
+                (None, Some(_)) => std::cmp::Ordering::Less,
+                (None, None) => std::cmp::Ordering::Equal,
+            }
+        });
+
+        Ok(())
+    }
+}
+
+impl Database for IcDbmsDatabase {
+    /// Executes a SELECT query and returns the results.
+    ///
+    /// # Arguments
+    ///
+    /// - `query` - The SELECT [`Query`] to be executed.
+    ///
+    /// # Returns
+    ///
+    /// The returned results are a vector of [`table::TableRecord`] matching the query.
+    fn select<T>(&self, query: Query<T>) -> IcDbmsResult<Vec<T::Record>>
+    where
+        T: TableSchema,
+    {
+        self.scan(query, None, None, false)
+            .map(|result| result.records)
+    }
+
+    /// Estimates the cost of executing a SELECT query, without executing it.
+    ///
+    /// The estimate is based on the [`crate::memory::TableStats`] of the table:
+    /// the query reads the records located by an index on a column compared by the filter, if any applies;
+    /// otherwise it scans the table, until the limit is reached if the results are not sorted.
+    /// Eager relations and uncommitted changes of the transaction are not taken into account.
+    ///
+    /// # Arguments
+    ///
+    /// - `query` - The SELECT [`Query`] to be estimated.
+    fn explain_execution<T>(&self, query: &Query<T>) -> IcDbmsResult<ExecutionEstimate>
+    where
+        T: TableSchema,
+    {
+        let table_registry = self.load_table_registry::<T>()?;
+        let stats = table_registry.stats::<T>()?;
+        let rows = stats.estimated_records;
+        let filter = self.select_filter(query);
+        let filter_selectivity = filter
+            .as_ref()
+            .map(|filter| filter.estimated_selectivity(T::primary_key(), rows))
+            .unwrap_or(1.0);
+
+        let index_lookup = self.index_lookup(&table_registry, filter.as_ref())?;
+        let estimated_rows_scanned = match (&index_lookup, query.limit) {
+            // the records located by the index are read
+            (Some((_, locations)), _) => locations.len() as u64,
+            // unsorted queries stop scanning once enough records matched
+            (None, Some(limit)) if query.order_by.is_empty() && filter_selectivity > 0.0 => {
+                let needed = limit.saturating_add(query.offset.unwrap_or_default()) as f64;
+                ((needed / filter_selectivity).ceil() as u64).min(rows)
+            }
+            (None, _) => rows,
+        };
+        let page_size = MEMORY_MANAGER.with_borrow(|mm| mm.page_size());
+        let estimated_pages_read = estimated_rows_scanned
+            .saturating_mul(stats.average_record_size)
+            .div_ceil(page_size)
+            .min(stats.pages);
+
+        Ok(ExecutionEstimate {
+            estimated_rows_scanned,
+            estimated_pages_read,
+            estimated_stable_reads: TABLE_REGISTRY_LOAD_READS
+                + estimated_pages_read
+                + estimated_rows_scanned,
+            index_uses: index_lookup
+                .map(|(column, _)| vec![IndexUsage { column }])
+                .unwrap_or_default(),
+            filter_selectivity,
+        })
+    }
+
+    /// Executes an INSERT query.
+    ///
+    /// # Arguments
+    ///
+    /// - `record` - The INSERT record to be executed.
+    fn insert<T>(&self, record: T::Insert) -> IcDbmsResult<()>
+    where
+        T: TableSchema,
+        T::Insert: InsertRecord<Schema = T>,
+    {
+        self.check_writable()?;
+        QueryCache::<T>::invalidate_all();
+        let record = Self::assign_auto_increment::<T>(record)?;
+
+        if self.transaction.is_some() {
+            // check whether the insert is valid
+            let record_values = record.into_values();
+            self.schema
+                .validate_insert(self, T::table_name(), &record_values)?;
+            // insert a new `insert` into the transaction
+            self.with_transaction_mut(|tx| tx.insert::<T>(record_values))?;
+            crate::dbms_log!(DEBUG, "insert added to transaction"; "table" => T::table_name());
+        } else if self.validation_mode == ValidationMode::Lenient {
+            self.insert_lenient::<T>(record)?;
+            crate::dbms_log!(DEBUG, "record inserted"; "table" => T::table_name());
+        } else {
+            let record_values = self.insert_record::<T>(record)?;
+            notify_watchers(T::table_name(), TableChange::Insert(&record_values));
+            crate::dbms_log!(DEBUG, "record inserted"; "table" => T::table_name());
+        }
+
+        Ok(())
+    }
+
+    /// Executes an UPDATE query.
+    ///
+    /// # Arguments
+    ///
+    /// - `behaviour` - The [`UpdateBehavior`] to apply for foreign key constraints when a primary key is changed.
+    /// - `patch` - The UPDATE patch to be applied.
+    /// - `filter` - An optional [`Filter`] to specify which records to update.
+    ///
+    /// # Returns
+    ///
+    /// The number of rows updated, not including the related records updated by [`UpdateBehavior::Cascade`].
+    fn update<T>(&self, behaviour: UpdateBehavior, patch: T::Update) -> IcDbmsResult<u64>
+    where
+        T: TableSchema,
+        T::Update: UpdateRecord<Schema = T>,
+    {
+        self.check_writable()?;
+        migration::check_not_migrating(T::fingerprint())?;
+        QueryCache::<T>::invalidate_all();
+
+        let updated_records = self.updated_records::<T>(&patch)?;
+        let count = updated_records.len() as u64;
+
+        // records whose primary key is changed by the update, as old and new values
+        let rekeyed_records = updated_records
+            .iter()
+            .filter(|(old, new)| {
+                Self::primary_key_values::<T>(old) != Self::primary_key_values::<T>(new)
+            })
+            .collect::<Vec<_>>();
+        if behaviour == UpdateBehavior::Restrict {
+            for (old, _) in &rekeyed_records {
+                if self.is_referenced::<T>(old)? {
+                    return Err(IcDbmsError::Query(
+                        QueryError::ForeignKeyConstraintViolation {
+                            referencing_table: T::table_name(),
+                            field: T::primary_key(),
+                        },
+                    ));
+                }
+            }
+        }
+
+        if self.transaction.is_some() {
+            let filter = patch.where_clause().clone();
+            let pks =
+                self.existing_primary_keys_for_query::<T>(Self::update_query::<T>(filter.clone()))?;
+            // insert a new `update` into the transaction;
+            // the related records are updated by cascade on commit
+            self.with_transaction_mut(|tx| tx.update::<T>(behaviour, patch, filter, pks))?;
+            crate::dbms_log!(DEBUG, "update added to transaction"; "table" => T::table_name(), "count" => count);
+
+            return Ok(count);
+        }
+
+        // for each record apply update; delete and insert
+        let res = self.atomic(|db| {
+            for (old, record_values) in &updated_records {
+                // create insert record
+                let insert_record = T::Insert::from_values(record_values)?;
+                // delete old record
+                let pk = Self::primary_key_values::<T>(old);
+                db.delete_records::<T>(
+                    DeleteBehavior::Break, // we just want to delete the old record
+                    Some(Filter::eq_composite(T::primary_keys(), pk)),
+                )?;
+                // insert new record
+                db.insert_record::<T>(insert_record)?;
+            }
+            // update the references to the new primary keys, once the records having them exist
+            if behaviour == UpdateBehavior::Cascade {
+                for (old, new) in &rekeyed_records {
+                    db.update_foreign_keys_cascade::<T>(old, new)?;
+                }
+            }
+            Ok(count)
+        });
+
+        for (old, new) in &updated_records {
+            notify_watchers(T::table_name(), TableChange::Update { old, new });
+        }
+        crate::dbms_log!(DEBUG, "records updated"; "table" => T::table_name(), "count" => res);
+
+        Ok(res)
+    }
+
+    /// Executes a DELETE query.
+    ///
+    /// # Arguments
+    ///
+    /// - `behaviour` - The [`DeleteBehavior`] to apply for foreign key constraints.
+    /// - `filter` - An optional [`Filter`] to specify which records to delete.
+    ///
+    /// # Returns
+    ///
+    /// The number of rows deleted.
+    fn delete<T>(&self, behaviour: DeleteBehavior, filter: Option<Filter>) -> IcDbmsResult<u64>
+    where
+        T: TableSchema,
+    {
+        self.check_writable()?;
+        QueryCache::<T>::invalidate_all();
+
+        if self.transaction.is_some() {
+            // soft-deleted records can still be physically deleted
+            let pks = self.existing_primary_keys_for_query::<T>(
+                Query::builder()
+                    .filter(filter.clone())
+                    .include_deleted()
+                    .build(),
+            )?;
+            let count = pks.len() as u64;
+            if behaviour == DeleteBehavior::Restrict {
+                self.check_restrict_in_transaction::<T>(&pks)?;
+            }
+
+            self.with_transaction_mut(|tx| tx.delete::<T>(behaviour, filter, pks))?;
+            crate::dbms_log!(DEBUG, "delete added to transaction"; "table" => T::table_name(), "count" => count);
+
+            return Ok(count);
+        }
+
+        // delete must be atomic
+        let (count, deleted_records) = self.atomic(|db| db.delete_records::<T>(behaviour, filter));
+        for record_values in &deleted_records {
+            notify_watchers(T::table_name(), TableChange::Delete(record_values));
+        }
+        crate::dbms_log!(DEBUG, "records deleted"; "table" => T::table_name(), "count" => count);
+
+        Ok(count)
+    }
+
+    /// Commits the current transaction.
+    ///
+    /// The transaction is consumed.
+    ///
+    /// Any error during commit will trap the canister to ensure consistency.
+    fn commit(&mut self) -> IcDbmsResult<()> {
+        let Some(txid) = self.transaction.clone() else {
+            return Err(IcDbmsError::Transaction(
+                TransactionError::NoActiveTransaction,
+            ));
+        };
+        // only the owner of the transaction or a principal granted access to it can commit it
+        TRANSACTION_SESSION.with_borrow(|ts| ts.check_access(&txid, caller()))?;
+
+        // nested transactions are merged into their parent, which is committed to memory later
+        if TRANSACTION_SESSION.with_borrow(|ts| ts.parent(&txid).is_some()) {
+            self.transaction = None;
+            let parent_id =
+                TRANSACTION_SESSION.with_borrow_mut(|ts| ts.merge_into_parent(&txid))?;
+            crate::dbms_log!(INFO, "nested transaction merged"; "transaction" => txid, "parent" => parent_id);
+            return Ok(());
+        }
+
+        // take transaction out of self and get the transaction out of the storage
+        // this also invalidates the overlay, so we won't have conflicts during validation
+        self.transaction = None;
+        let transaction = TRANSACTION_SESSION.with_borrow_mut(|ts| ts.take_transaction(&txid))?;
+        if transaction.is_read_only() {
+            // read-only transactions have no operations to apply
+            crate::dbms_log!(INFO, "read-only transaction committed"; "transaction" => txid);
+            return Ok(());
+        }
+
+        // validate all the operations before applying any of them
+        if let Err(err) = transaction.pre_validate(self.schema.as_ref(), self) {
+            crate::dbms_log!(ERROR, "transaction validation failed: {err}"; "transaction" => txid);
+            return Err(err);
+        }
+
+        // iterate over operations and apply them;
+        // for each operation, first validate, then apply
+        // using `self.atomic` when applying to ensure consistency
+        for op in transaction.operations {
+            if let Err(err) = self.apply_operation(op) {
+                crate::dbms_log!(ERROR, "transaction commit failed: {err}"; "transaction" => txid);
+                return Err(err);
+            }
+        }
+        crate::dbms_log!(INFO, "transaction committed"; "transaction" => txid);
+
+        Ok(())
+    }
+
+    /// Rolls back the current transaction.
+    ///
+    /// The transaction is consumed.
+    fn rollback(&mut self) -> IcDbmsResult<()> {
+        let Some(txid) = self.transaction.clone() else {
+            return Err(IcDbmsError::Transaction(
+                TransactionError::NoActiveTransaction,
+            ));
+        };
+        // only the owner of the transaction or a principal granted access to it can roll it back
+        TRANSACTION_SESSION.with_borrow(|ts| ts.check_access(&txid, caller()))?;
+        self.transaction = None;
+
+        TRANSACTION_SESSION.with_borrow_mut(|ts| ts.close_transaction(&txid));
+        crate::dbms_log!(INFO, "transaction rolled back"; "transaction" => txid);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use candid::Nat;
+    use ic_dbms_api::prelude::{
+        Array, Boolean, CandidIcDbmsError, CandidIcDbmsErrorKind, CandidQueryError, Int32,
+        Nullable, Text, Uint32, error_codes,
+    };
+
+    use super::*;
+    use crate::prelude::{ImportResult, IntegrityWarningKind};
+    use crate::tests::{
+        Booking, CUSTOMERS_FIXTURES, Customer, CustomerInsertRequest, CustomerUpdateRequest,
+        DOCUMENTS_FIXTURES, Document, DocumentUpdateRequest, Enrollment, EnrollmentUpdateRequest,
+        MESSAGES_FIXTURES, Message, MessageInsertRequest, POSTS_FIXTURES, Person, Post,
+        PostInsertRequest, Product, ProductRecord, TestDatabaseSchema, Ticket, TicketInsertRequest,
+        USERS_FIXTURES, User, UserInsertRequest, UserRecord, UserUpdateRequest, load_fixtures,
+    };
+    use crate::utils::{set_caller, set_performance_counter, set_time};
+
+    #[test]
+    fn test_should_init_dbms() {
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        assert!(dbms.transaction.is_none());
+
+        let tx_dbms = IcDbmsDatabase::from_transaction(TestDatabaseSchema, Nat::from(1u64));
+        assert!(tx_dbms.transaction.is_some());
+    }
+
+    #[test]
+    fn test_should_debug_dbms() {
+        assert_eq!(
+            format!("{:?}", IcDbmsDatabase::oneshot(TestDatabaseSchema)),
+            "IcDbmsDatabase { transaction: None, schema: \"ic_dbms_canister::tests::TestDatabaseSchema\", \
+             validation_mode: Strict, read_only: false }"
+        );
+        let tx_dbms = IcDbmsDatabase::from_transaction(TestDatabaseSchema, Nat::from(1u64));
+        assert!(format!("{tx_dbms:?}").starts_with("IcDbmsDatabase { transaction: Some("));
+    }
+
+    #[test]
+    fn test_should_select_all_users() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        let query = Query::<User>::builder().all().build();
+        let users = dbms.select(query).expect("failed to select users");
+
+        assert_eq!(users.len(), USERS_FIXTURES.len());
+        // check if all users all loaded
+        for (i, user) in users.iter().enumerate() {
+            assert_eq!(user.id.expect("should have id").0 as usize, i);
+            assert_eq!(
+                user.name.as_ref().expect("should have name").0,
+                USERS_FIXTURES[i]
+            );
+        }
+    }
+
+    #[test]
+    fn test_should_select_user_in_overlay() {
+        load_fixtures();
+        // create a transaction
+        let transaction_id =
+            TRANSACTION_SESSION.with_borrow_mut(|ts| ts.begin_transaction(Principal::anonymous()));
+        // insert
+        TRANSACTION_SESSION.with_borrow_mut(|ts| {
+            let tx = ts
+                .get_transaction_mut(&transaction_id)
+                .expect("should have tx");
+            tx.overlay_mut()
+                .insert::<User>(vec![
+                    (
+                        ColumnDef {
+                            name: "id",
+                            data_type: ic_dbms_api::prelude::DataTypeKind::Uint32,
+                            nullable: false,
+                            primary_key: true,
+                            unique: false,
+                            auto_increment: false,
+                            foreign_key: None,
+                            check: None,
+                        },
+                        Value::Uint32(999.into()),
+                    ),
+                    (
+                        ColumnDef {
+                            name: "name",
+                            data_type: ic_dbms_api::prelude::DataTypeKind::Text,
+                            nullable: false,
+                            primary_key: false,
+                            unique: false,
+                            auto_increment: false,
+                            foreign_key: None,
+                            check: None,
+                        },
+                        Value::Text("OverlayUser".to_string().into()),
+                    ),
+                ])
+                .expect("failed to insert");
+        });
+
+        // select by pk
+        let dbms = IcDbmsDatabase::from_transaction(TestDatabaseSchema, transaction_id);
+        let query = Query::<User>::builder()
+            .and_where(Filter::eq("id", Value::Uint32(999.into())))
+            .build();
+        let users = dbms.select(query).expect("failed to select users");
+
+        assert_eq!(users.len(), 1);
+        let user = &users[0];
+        assert_eq!(user.id.expect("should have id").0, 999);
+        assert_eq!(
+            user.name.as_ref().expect("should have name").0,
+            "OverlayUser"
+        );
+    }
+
+    #[test]
+    fn test_should_select_users_with_offset_and_limit() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        let query = Query::<User>::builder().offset(2).limit(3).build();
+        let users = dbms.select(query).expect("failed to select users");
+
+        assert_eq!(users.len(), 3);
+        // check if correct users are loaded
+        for (i, user) in users.iter().enumerate() {
+            let expected_index = i + 2;
+            assert_eq!(user.id.expect("should have id").0 as usize, expected_index);
+            assert_eq!(
+                user.name.as_ref().expect("should have name").0,
+                USERS_FIXTURES[expected_index]
+            );
+        }
+    }
+
+    #[test]
+    fn test_should_select_users_with_offset_and_filter() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        let query = Query::<User>::builder()
+            .offset(1)
+            .and_where(Filter::gt("id", Value::Uint32(4.into())))
+            .build();
+        let users = dbms.select(query).expect("failed to select users");
+
+        assert_eq!(users.len(), 4);
+        // check if correct users are loaded
+        for (i, user) in users.iter().enumerate() {
+            let expected_index = i + 6;
+            assert_eq!(user.id.expect("should have id").0 as usize, expected_index);
+            assert_eq!(
+                user.name.as_ref().expect("should have name").0,
+                USERS_FIXTURES[expected_index]
+            );
+        }
+    }
+
+    #[test]
+    fn test_should_select_post_with_relation() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        let query = Query::<Post>::builder()
+            .all()
+            .with(User::table_name())
+            .build();
+        let posts = dbms.select(query).expect("failed to select posts");
+        assert_eq!(posts.len(), POSTS_FIXTURES.len());
+
+        for (id, post) in posts.into_iter().enumerate() {
+            let (expected_title, expected_content, expected_user_id) = &POSTS_FIXTURES[id];
+            assert_eq!(post.id.expect("should have id").0 as usize, id);
+            assert_eq!(
+                post.title.as_ref().expect("should have title").0,
+                *expected_title
+            );
+            assert_eq!(
+                post.content.as_ref().expect("should have content").0,
+                *expected_content
+            );
+            let user_query = Query::<User>::builder()
+                .and_where(Filter::eq("id", Value::Uint32((*expected_user_id).into())))
+                .build();
+            let author = dbms
+                .select(user_query)
+                .expect("failed to load user")
+                .pop()
+                .expect("should have user");
+            assert_eq!(post.user.expect("should have loaded user"), author);
+        }
+    }
+
+    #[test]
+    fn test_should_select_posts_with_subselect_in() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+
+        let author_id = 1;
+        let filter = Filter::subselect_in(
+            IcDbmsDatabase::oneshot(TestDatabaseSchema),
+            "user_id",
+            Query::<User>::builder()
+                .and_where(Filter::eq(
+                    "name",
+                    Value::Text(USERS_FIXTURES[author_id as usize].to_string().into()),
+                ))
+                .build(),
+            "id",
+        );
+
+        let expected_posts: Vec<u32> = POSTS_FIXTURES
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, _, user_id))| *user_id == author_id)
+            .map(|(id, _)| id as u32)
+            .collect();
+        assert!(!expected_posts.is_empty());
+        let select_post_ids = |filter: Filter| {
+            dbms.select::<Post>(Query::builder().and_where(filter).build())
+                .expect("failed to select posts")
+                .into_iter()
+                .map(|post| post.id.expect("should have id").0)
+                .collect::<Vec<u32>>()
+        };
+        assert_eq!(select_post_ids(filter.clone()), expected_posts);
+
+        // the sub-select is executed once, so renaming the author doesn't change the result
+        dbms.update::<User>(
+            UpdateBehavior::Restrict,
+            UserUpdateRequest {
+                id: None,
+                name: Some(Text("Renamed".to_string())),
+                where_clause: Some(Filter::eq("id", Value::Uint32(author_id.into()))),
+            },
+        )
+        .expect("failed to update user");
+        assert_eq!(select_post_ids(filter), expected_posts);
+    }
+
+    #[test]
+    fn test_should_fail_loading_unexisting_column_on_select() {
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        let query = Query::<User>::builder().field("unexisting_column").build();
+        let result = dbms.select(query);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_should_select_queried_fields() {
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+
+        let record_values = User::columns()
+            .iter()
+            .cloned()
+            .zip(vec![
+                Value::Uint32(1.into()),
+                Value::Text("Alice".to_string().into()),
+            ])
+            .collect::<Vec<(ColumnDef, Value)>>();
+
+        let query: Query<User> = Query::builder().field("name").build();
+        let selected_fields = dbms
+            .select_queried_fields::<User>(record_values, &query)
+            .expect("failed to select queried fields");
+        let user_fields = selected_fields
+            .into_iter()
+            .find(|(table_name, _)| *table_name == ValuesSource::This)
+            .map(|(_, cols)| cols)
+            .unwrap_or_default();
+
+        assert_eq!(user_fields.len(), 1);
+        assert_eq!(user_fields[0].0.name, "name");
+        assert_eq!(user_fields[0].1, Value::Text("Alice".to_string().into()));
+    }
+
+    #[test]
+    fn test_should_select_mapped_fields() {
+        fn to_upper(value: Value) -> Value {
+            match value {
+                Value::Text(text) => Value::Text(text.0.to_uppercase().into()),
+                value => value,
+            }
+        }
+
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+
+        let query: Query<User> = Query::builder()
+            .field("id")
+            .map_field("name", "username")
+            .transform_field("name", "upper_name", to_upper)
+            .and_where(Filter::eq("id", Value::Uint32(0u32.into())))
+            .build();
+        let rows = dbms.select_raw(query).expect("failed to select users");
+
+        assert_eq!(rows.len(), 1);
+        let names = rows[0]
+            .iter()
+            .map(|(col_def, _)| col_def.name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["id", "username", "upper_name"]);
+        let expected_name = USERS_FIXTURES[0];
+        assert_eq!(rows[0][1].1, Value::Text(expected_name.to_string().into()));
+        assert_eq!(
+            rows[0][2].1,
+            Value::Text(expected_name.to_uppercase().into())
+        );
+    }
+
+    #[test]
+    fn test_should_select_computed_column() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+
+        let persons = dbms
+            .select::<Person>(
+                Query::builder()
+                    .field("full_name")
+                    .and_where(Filter::eq("id", Value::Uint32(0.into())))
+                    .build(),
+            )
+            .expect("failed to select persons");
+        assert_eq!(persons.len(), 1);
+        assert_eq!(persons[0].full_name, Some(Text("Ada Lovelace".to_string())));
+        assert_eq!(persons[0].first_name, None);
+
+        // computed columns are returned when all the columns are selected too
+        let persons = dbms
+            .select::<Person>(Query::builder().all().build())
+            .expect("failed to select persons");
+        assert_eq!(
+            persons
+                .into_iter()
+                .map(|person| person.full_name.expect("should have full name").0)
+                .collect::<Vec<_>>(),
+            vec!["Ada Lovelace".to_string(), "Alan Turing".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_should_select_queried_fields_with_relations() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+
+        let record_values = Post::columns()
+            .iter()
+            .cloned()
+            .zip(vec![
+                Value::Uint32(1.into()),
+                Value::Text("Title".to_string().into()),
+                Value::Text("Content".to_string().into()),
+                Value::Uint32(2.into()), // author_id
+            ])
+            .collect::<Vec<(ColumnDef, Value)>>();
+
+        let query: Query<Post> = Query::builder()
+            .field("title")
+            .with(User::table_name())
+            .build();
+        let selected_fields = dbms
+            .select_queried_fields::<Post>(record_values, &query)
+            .expect("failed to select queried fields");
+
+        // check post fields
+        let post_fields = selected_fields
+            .iter()
+            .find(|(table_name, _)| *table_name == ValuesSource::This)
+            .map(|(_, cols)| cols)
+            .cloned()
+            .unwrap_or_default();
+        assert_eq!(post_fields.len(), 1);
+        assert_eq!(post_fields[0].0.name, "title");
+        assert_eq!(post_fields[0].1, Value::Text("Title".to_string().into()));
+
+        // check user fields
+        let user_fields = selected_fields
+            .iter()
+            .find(|(table_name, _)| {
+                *table_name
+                    == ValuesSource::Foreign {
+                        table: User::table_name(),
+                        column: "user_id",
+                    }
+            })
+            .map(|(_, cols)| cols)
+            .cloned()
+            .unwrap_or_default();
+
+        let expected_user = USERS_FIXTURES[2]; // author_id = 2
+
+        assert_eq!(user_fields.len(), 2);
+        assert_eq!(user_fields[0].0.name, "id");
+        assert_eq!(user_fields[0].1, Value::Uint32(2.into()));
+        assert_eq!(user_fields[1].0.name, "name");
+        assert_eq!(
+            user_fields[1].1,
+            Value::Text(expected_user.to_string().into())
+        );
+    }
+
+    #[test]
+    fn test_should_select_with_two_fk_on_the_same_table() {
+        load_fixtures();
+
+        let query: Query<Message> = Query::builder()
+            .all()
+            .and_where(Filter::Eq("id", Value::Uint32(0.into())))
+            .with("users")
+            .build();
+
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        let messages = dbms.select(query).expect("failed to select messages");
+        assert_eq!(messages.len(), 1);
+        let message = &messages[0];
+        assert_eq!(message.id.expect("should have id").0, 0);
+        assert_eq!(
+            message
+                .sender
+                .as_ref()
+                .expect("should have sender")
+                .name
+                .as_ref()
+                .unwrap()
+                .0,
+            "Alice"
+        );
+        assert_eq!(
+            message
+                .recipient
+                .as_ref()
+                .expect("should have recipient")
+                .name
+                .as_ref()
+                .unwrap()
+                .0,
+            "Bob"
+        );
+    }
+
+    #[test]
+    fn test_should_select_users_sorted_by_name_descending() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        let query = Query::<User>::builder().all().order_by_desc("name").build();
+        let users = dbms.select(query).expect("failed to select users");
+
+        let mut sorted_usernames = USERS_FIXTURES.to_vec();
+        sorted_usernames.sort_by(|a, b| b.cmp(a)); // descending
+
+        assert_eq!(users.len(), USERS_FIXTURES.len());
+        // check if all users all loaded in sorted order
+        for (i, user) in users.iter().enumerate() {
+            assert_eq!(
+                user.name.as_ref().expect("should have name").0,
+                sorted_usernames[i]
+            );
+        }
+    }
+
+    #[test]
+    fn test_should_not_sort_values_of_different_types() {
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        let column = User::columns()[1];
+        let mut results = vec![
+            vec![(
+                ValuesSource::This,
+                vec![(column, Value::Text("Alice".to_string().into()))],
+            )],
+            vec![(ValuesSource::This, vec![(column, Value::Null)])],
+            vec![(ValuesSource::This, vec![(column, Value::Int32(1.into()))])],
+        ];
+
+        let result = dbms.sort_query_results(&mut results, "name", OrderDirection::Ascending);
+        assert!(matches!(
+            result,
+            Err(IcDbmsError::Query(QueryError::TypeMismatch {
+                column: "name",
+                expected: "Text",
+                found: "Int32",
+            }))
+        ));
+    }
+
+    #[test]
+    fn test_should_explain_query_execution() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        let users = USERS_FIXTURES.len() as u64;
+
+        // full scan
+        let estimate = dbms
+            .explain_execution(&Query::<User>::builder().all().build())
+            .expect("failed to explain query");
+        assert_eq!(estimate.filter_selectivity, 1.0);
+        assert!(estimate.index_uses.is_empty());
+        // the row count is estimated from the average record size
+        let rows = estimate.estimated_rows_scanned;
+        assert!(
+            rows.abs_diff(users) <= 1,
+            "estimated {rows} of {users} rows"
+        );
+        // all the fixtures fit into a single page
+        assert_eq!(estimate.estimated_pages_read, 1);
+        assert_eq!(estimate.estimated_stable_reads, 2 + 1 + rows);
+
+        // equality on the primary key
+        let estimate = dbms
+            .explain_execution(
+                &Query::<User>::builder()
+                    .and_where(Filter::eq("id", Value::Uint32(Uint32(1))))
+                    .build(),
+            )
+            .expect("failed to explain query");
+        assert_eq!(estimate.filter_selectivity, 1.0 / rows as f64);
+        assert_eq!(estimate.estimated_rows_scanned, rows);
+
+        // range filter
+        let estimate = dbms
+            .explain_execution(
+                &Query::<User>::builder()
+                    .and_where(Filter::gt("id", Value::Uint32(Uint32(1))))
+                    .build(),
+            )
+            .expect("failed to explain query");
+        assert_eq!(estimate.filter_selectivity, 0.1);
+
+        // unsorted queries with a limit stop early
+        let estimate = dbms
+            .explain_execution(&Query::<User>::builder().all().limit(2).build())
+            .expect("failed to explain query");
+        assert_eq!(estimate.estimated_rows_scanned, 2);
+        let estimate = dbms
+            .explain_execution(
+                &Query::<User>::builder()
+                    .all()
+                    .limit(2)
+                    .order_by_asc("name")
+                    .build(),
+            )
+            .expect("failed to explain query");
+        assert_eq!(estimate.estimated_rows_scanned, rows);
+    }
+
+    /// Registers the `User` table and inserts `count` users into it.
+    fn insert_users(count: u32) {
+        let table_pages = SCHEMA_REGISTRY
+            .with_borrow_mut(|sr| sr.register_table::<User>())
+            .expect("failed to register table");
+        let mut registry = TableRegistry::load(table_pages).expect("failed to load table");
+        registry
+            .insert_batch((0..count).map(|id| User {
+                id: Uint32(id),
+                name: Text(format!("User {id}")),
+            }))
+            .expect("failed to insert users");
+    }
+
+    #[test]
+    fn test_should_truncate_select_when_soft_limit_exceeded() {
+        insert_users(1000);
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        let budget = CycleBudget {
+            soft_limit: 5_000,
+            hard_limit: 10_000,
+        };
+
+        // the soft limit is exceeded at the fifth check, after 500 records
+        let mut checks = 0;
+        set_performance_counter(move || {
+            checks += 1;
+            checks * 1_000
+        });
+        let result = dbms
+            .select_with_budget(Query::<User>::builder().all().build(), budget)
+            .expect("failed to select users");
+        assert!(result.truncated);
+        assert_eq!(
+            result.cursor,
+            Some(Cursor {
+                position: 500,
+                index: None
+            })
+        );
+        assert_eq!(result.records.len(), 500);
+        assert_eq!(result.records[499].id, Some(Uint32(499)));
+
+        // resume in a new message
+        set_performance_counter(|| 0);
+        let result = dbms
+            .resume_select_with_budget(
+                Query::<User>::builder().all().build(),
+                result.cursor.expect("should have cursor"),
+                budget,
+            )
+            .expect("failed to resume select");
+        assert!(!result.truncated);
+        assert!(result.cursor.is_none());
+        assert_eq!(result.records.len(), 500);
+        assert_eq!(result.records[0].id, Some(Uint32(500)));
+    }
+
+    #[test]
+    fn test_should_explain_filtered_sorted_limited_query() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+
+        let query = Query::<Post>::builder()
+            .all()
+            .and_where(Filter::gt("id", Value::Uint32(1u32.into())))
+            .order_by_desc("title")
+            .offset(2)
+            .limit(5)
+            .with(User::table_name())
+            .build();
+        let plan = dbms.explain(&query).expect("failed to explain query");
+
+        let steps = plan.steps();
+        assert_eq!(steps.len(), 5);
+        let QueryPlanNode::TableScan {
+            table,
+            filter,
+            estimated_rows,
+        } = steps[0]
+        else {
+            panic!("the plan should start with a table scan: {plan}");
+        };
+        assert_eq!(table, "posts");
+        assert_eq!(filter.as_deref(), Some("id > 1u32"));
+        // the row count is estimated from the average record size
+        let posts = POSTS_FIXTURES.len() as u64;
+        assert!(
+            estimated_rows.abs_diff(posts) <= 1,
+            "estimated {estimated_rows} of {posts} rows"
+        );
+        assert!(matches!(
+            steps[1],
+            QueryPlanNode::Sort {
+                column,
+                direction: OrderDirection::Descending,
+                ..
+            } if column == "title"
+        ));
+        assert!(matches!(steps[2], QueryPlanNode::Offset { n: 2, .. }));
+        assert!(matches!(steps[3], QueryPlanNode::Limit { n: 5, .. }));
+        assert!(matches!(
+            steps[4],
+            QueryPlanNode::EagerLoad { table, .. } if table == "users"
+        ));
+    }
+
+    #[test]
+    fn test_should_explain_unfiltered_query_as_table_scan() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+
+        let plan = dbms
+            .explain(&Query::<User>::builder().all().build())
+            .expect("failed to explain query");
+        assert!(matches!(
+            plan.root,
+            QueryPlanNode::TableScan { filter: None, .. }
+        ));
+        assert!(
+            plan.to_string()
+                .starts_with("TableScan on users (estimated rows: ")
+        );
+    }
+
+    #[cfg(feature = "indexes")]
+    #[test]
+    fn test_should_select_with_index_visiting_fewer_pages() {
+        let table_pages = SCHEMA_REGISTRY
+            .with_borrow_mut(|sr| sr.register_table::<Post>())
+            .expect("failed to register table");
+        let mut registry = TableRegistry::load(table_pages).expect("failed to load table");
+        registry
+            .insert_batch((0..10_000).map(|id| Post {
+                id: Uint32(id),
+                title: Text(format!("Post {id}")),
+                content: Text("x".repeat(1_000)),
+                user_id: Uint32(id % 1_000),
+            }))
+            .expect("failed to insert posts");
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        let query = || {
+            Query::<Post>::builder()
+                .all()
+                .and_where(Filter::eq("user_id", Value::Uint32(Uint32(42))))
+                .build()
+        };
+        let select = || {
+            crate::memory::BUFFER_POOL.with_borrow_mut(|pool| pool.clear());
+            MEMORY_MANAGER.with_borrow_mut(|mm| mm.reset_stats());
+            let ids: Vec<u32> = dbms
+                .select(query())
+                .expect("failed to select posts")
+                .into_iter()
+                .map(|post| post.id.unwrap().0)
+                .collect();
+            (ids, MEMORY_MANAGER.with_borrow(|mm| mm.physical_reads()))
+        };
+
+        let (unindexed_ids, unindexed_reads) = select();
+        SCHEMA_REGISTRY
+            .with_borrow_mut(|sr| sr.create_index::<Post>("user_id"))
+            .expect("failed to create index");
+        let (indexed_ids, indexed_reads) = select();
+
+        let expected: Vec<u32> = (0..10).map(|n| n * 1_000 + 42).collect();
+        assert_eq!(unindexed_ids, expected);
+        assert_eq!(indexed_ids, expected);
+        assert!(
+            indexed_reads < unindexed_reads,
+            "indexed select read {indexed_reads} pages, unindexed {unindexed_reads}"
+        );
+
+        let plan = dbms.explain(&query()).expect("failed to explain query");
+        assert_eq!(
+            plan.root,
+            QueryPlanNode::IndexScan {
+                table: "posts".to_string(),
+                column: "user_id".to_string(),
+                filter: Some("user_id = 42u32".to_string()),
+                estimated_rows: 10,
+            }
+        );
+    }
+
+    #[cfg(feature = "indexes")]
+    #[test]
+    fn test_should_resume_indexed_select() {
+        let table_pages = SCHEMA_REGISTRY
+            .with_borrow_mut(|sr| sr.register_table::<Post>())
+            .expect("failed to register table");
+        let mut registry = TableRegistry::load(table_pages).expect("failed to load table");
+        registry
+            .insert_batch((0..1_000).map(|id| Post {
+                id: Uint32(id),
+                title: Text(format!("Post {id}")),
+                content: Text("content".to_string()),
+                user_id: Uint32(id % 4),
+            }))
+            .expect("failed to insert posts");
+        SCHEMA_REGISTRY
+            .with_borrow_mut(|sr| sr.create_index::<Post>("user_id"))
+            .expect("failed to create index");
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        let query = || {
+            Query::<Post>::builder()
+                .all()
+                .and_where(Filter::eq("user_id", Value::Uint32(Uint32(1))))
+                .build()
+        };
+
+        // truncated after scanning 100 of the 250 records located by the index
+        let result = dbms
+            .select_with_partial_results(query().with_timeout_ns(1))
+            .expect("failed to select posts");
+        assert!(result.truncated);
+        assert_eq!(
+            result.cursor,
+            Some(Cursor {
+                position: 100,
+                index: Some("user_id")
+            })
+        );
+        let mut ids: Vec<u32> = result
+            .records
+            .into_iter()
+            .map(|post| post.id.unwrap().0)
+            .collect();
+        assert_eq!(ids.len(), 100);
+
+        let result = dbms
+            .resume_select_with_partial_results(query(), result.cursor.expect("should have cursor"))
+            .expect("failed to resume select");
+        assert!(!result.truncated);
+        ids.extend(result.records.into_iter().map(|post| post.id.unwrap().0));
+        assert_eq!(ids, (0..250).map(|n| n * 4 + 1).collect::<Vec<_>>());
+
+        // a cursor of a table scan resumes the table scan
+        let result = dbms
+            .resume_select_with_partial_results(
+                query(),
+                Cursor {
+                    position: 500,
+                    index: None,
+                },
+            )
+            .expect("failed to resume select");
+        assert_eq!(result.records.len(), 125);
+        assert_eq!(result.records[0].id, Some(Uint32(501)));
+
+        // the index of the cursor must still apply
+        assert!(matches!(
+            dbms.resume_select_with_partial_results(
+                Query::<Post>::builder().all().build(),
+                Cursor {
+                    position: 100,
+                    index: Some("user_id"),
+                },
+            ),
+            Err(IcDbmsError::Query(QueryError::InvalidQuery(_)))
+        ));
+    }
+
+    fn select_user_pages(dbms: &IcDbmsDatabase, between_pages: impl Fn(usize)) -> Vec<u32> {
+        let mut ids = Vec::new();
+        let mut cursor = None;
+        for page in 0.. {
+            let mut query = Query::<User>::builder().all().limit(10);
+            if let Some(cursor) = cursor {
+                query = query.after(cursor);
+            }
+            let page_result = dbms
+                .select_page(query.build())
+                .expect("failed to select page");
+            assert!(page_result.records.len() <= 10);
+            ids.extend(page_result.records.iter().map(|user| user.id.unwrap().0));
+            cursor = page_result.next_cursor;
+            if cursor.is_none() {
+                break;
+            }
+            between_pages(page);
+        }
+
+        ids
+    }
+
+    #[test]
+    fn test_should_select_distinct_names() {
+        let table_pages = SCHEMA_REGISTRY
+            .with_borrow_mut(|sr| sr.register_table::<User>())
+            .expect("failed to register table");
+        let mut registry = TableRegistry::load(table_pages).expect("failed to load table");
+        registry
+            .insert_batch(
+                ["alice", "bob", "alice", "alice", "bob"]
+                    .into_iter()
+                    .enumerate()
+                    .map(|(id, name)| User {
+                        id: Uint32(id as u32),
+                        name: Text(name.to_string()),
+                    }),
+            )
+            .expect("failed to insert users");
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        let names = |query: Query<User>| {
+            dbms.select(query)
+                .expect("failed to select users")
+                .into_iter()
+                .map(|user| user.name.expect("should have name").0)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            names(Query::builder().field("name").distinct().build()),
+            vec!["alice", "bob"]
+        );
+        assert_eq!(
+            names(
+                Query::builder()
+                    .field("name")
+                    .distinct()
+                    .order_by_desc("name")
+                    .limit(1)
+                    .build()
+            ),
+            vec!["bob"]
+        );
+        assert_eq!(names(Query::builder().all().distinct().build()).len(), 5);
+    }
+
+    #[test]
+    fn test_should_deduplicate_null_values() {
+        let columns = User::columns();
+        let record = |name: Value| {
+            vec![(
+                ValuesSource::This,
+                vec![(columns[0], Value::Uint32(1.into())), (columns[1], name)],
+            )]
+        };
+        let mut results = vec![
+            record(Value::Null),
+            record(Value::Text("alice".to_string().into())),
+            record(Value::Null),
+        ];
+
+        IcDbmsDatabase::deduplicate(&mut results);
+        assert_eq!(
+            results,
+            vec![
+                record(Value::Null),
+                record(Value::Text("alice".to_string().into()))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_should_select_pages_by_cursor() {
+        insert_users(100);
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+
+        let ids = select_user_pages(&dbms, |_| {});
+        assert_eq!(ids, (0..100).collect::<Vec<_>>());
+    }
+
+    #[cfg(feature = "indexes")]
+    #[test]
+    fn test_should_select_pages_walking_primary_key_index() {
+        let table_pages = SCHEMA_REGISTRY
+            .with_borrow_mut(|sr| sr.register_table::<Post>())
+            .expect("failed to register table");
+        let mut registry = TableRegistry::load(table_pages).expect("failed to load table");
+        // stored in reverse primary key order
+        registry
+            .insert_batch((0..1_000).rev().map(|id| Post {
+                id: Uint32(id),
+                title: Text(format!("Post {id}")),
+                content: Text("x".repeat(1_000)),
+                user_id: Uint32(id % 10),
+            }))
+            .expect("failed to insert posts");
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        let select_page = |after: Option<Value>| {
+            crate::memory::BUFFER_POOL.with_borrow_mut(|pool| pool.clear());
+            MEMORY_MANAGER.with_borrow_mut(|mm| mm.reset_stats());
+            let mut query = Query::<Post>::builder().all().limit(10);
+            if let Some(after) = after {
+                query = query.after(after);
+            }
+            let page = dbms
+                .select_page(query.build())
+                .expect("failed to select page");
+            let ids: Vec<u32> = page
+                .records
+                .into_iter()
+                .map(|post| post.id.unwrap().0)
+                .collect();
+            (
+                ids,
+                page.next_cursor,
+                MEMORY_MANAGER.with_borrow(|mm| mm.physical_reads()),
+            )
+        };
+
+        let (unindexed_ids, _, unindexed_reads) = select_page(Some(Value::Uint32(Uint32(500))));
+        SCHEMA_REGISTRY
+            .with_borrow_mut(|sr| sr.create_index::<Post>("id"))
+            .expect("failed to create index");
+        let (indexed_ids, _, indexed_reads) = select_page(Some(Value::Uint32(Uint32(500))));
+        assert_eq!(unindexed_ids, (501..511).collect::<Vec<_>>());
+        assert_eq!(indexed_ids, unindexed_ids);
+        assert!(
+            indexed_reads < unindexed_reads,
+            "indexed page read {indexed_reads} pages, unindexed {unindexed_reads}"
+        );
+
+        // paging through the whole table
+        let mut ids = Vec::new();
+        let mut cursor = None;
+        loop {
+            let (page_ids, next_cursor, _) = select_page(cursor);
+            ids.extend(page_ids);
+            cursor = next_cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+        assert_eq!(ids, (0..1_000).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_should_select_pages_by_cursor_with_changes_between_pages() {
+        insert_users(100);
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+
+        // the inserted record may reuse the space of the deleted one, before the cursor in the table
+        let ids = select_user_pages(&dbms, |page| {
+            if page == 0 {
+                dbms.delete::<User>(
+                    DeleteBehavior::Restrict,
+                    Some(Filter::eq("id", Value::Uint32(3u32.into()))),
+                )
+                .expect("failed to delete user");
+                dbms.insert::<User>(UserInsertRequest {
+                    id: Uint32(150),
+                    name: Text("User 150".to_string()),
+                })
+                .expect("failed to insert user");
+            }
+        });
+        assert_eq!(ids, (0..100).chain([150]).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_should_combine_cursor_and_offset() {
+        insert_users(100);
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+
+        let users = dbms
+            .select::<User>(
+                Query::builder()
+                    .all()
+                    .after(Value::Uint32(49u32.into()))
+                    .offset(5)
+                    .limit(3)
+                    .build(),
+            )
+            .expect("failed to select users");
+        let ids = users
+            .iter()
+            .map(|user| user.id.unwrap().0)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![55, 56, 57]);
+    }
+
+    #[test]
+    fn test_should_not_select_page_without_primary_key() {
+        insert_users(20);
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+
+        let result = dbms.select_page(Query::<User>::builder().field("name").limit(10).build());
+        assert!(matches!(
+            result,
+            Err(IcDbmsError::Query(QueryError::InvalidQuery(_)))
+        ));
+    }
+
+    #[test]
+    fn test_should_fail_select_on_timeout() {
+        insert_users(1000);
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+
+        // the timeout is exceeded at the first check, after 100 records
+        let result = dbms.select(Query::<User>::builder().all().build().with_timeout_ns(1));
+        assert!(matches!(
+            result,
+            Err(IcDbmsError::Query(QueryError::Timeout {
+                records_scanned: 100,
+                ..
+            }))
+        ));
+
+        let users = dbms
+            .select(
+                Query::<User>::builder()
+                    .all()
+                    .build()
+                    .with_timeout_ns(u64::MAX),
+            )
+            .expect("failed to select users");
+        assert_eq!(users.len(), 1000);
+    }
+
+    #[test]
+    fn test_should_return_partial_results_on_timeout() {
+        insert_users(1000);
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+
+        let result = dbms
+            .select_with_partial_results(Query::<User>::builder().all().build().with_timeout_ns(1))
+            .expect("failed to select users");
+        assert!(result.truncated);
+        assert_eq!(
+            result.cursor,
+            Some(Cursor {
+                position: 100,
+                index: None
+            })
+        );
+        assert_eq!(result.records.len(), 100);
+        assert_eq!(result.records[99].id, Some(Uint32(99)));
+
+        // resume without timeout
+        let result = dbms
+            .resume_select_with_partial_results(
+                Query::<User>::builder().all().build(),
+                result.cursor.expect("should have cursor"),
+            )
+            .expect("failed to resume select");
+        assert!(!result.truncated);
+        assert_eq!(result.records.len(), 900);
+        assert_eq!(result.records[0].id, Some(Uint32(100)));
+    }
+
+    #[test]
+    #[should_panic(expected = "exceeded the hard instructions limit")]
+    fn test_should_trap_select_when_hard_limit_exceeded() {
+        insert_users(1000);
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+
+        set_performance_counter(|| 10_000);
+        let _ = dbms.select_with_budget(
+            Query::<User>::builder().all().build(),
+            CycleBudget {
+                soft_limit: 5_000,
+                hard_limit: 10_000,
+            },
+        );
+    }
+
+    #[test]
+    fn test_should_fail_loading_unexisting_relation() {
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+
+        let record_values = Post::columns()
+            .iter()
+            .cloned()
+            .zip(vec![
+                Value::Uint32(1.into()),
+                Value::Text("Title".to_string().into()),
+                Value::Text("Content".to_string().into()),
+                Value::Uint32(2.into()), // author_id
+            ])
+            .collect::<Vec<(ColumnDef, Value)>>();
+
+        let query: Query<Post> = Query::builder()
+            .field("title")
+            .with("unexisting_relation")
+            .build();
+        let result = dbms.select_queried_fields::<Post>(record_values, &query);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_should_get_whether_record_matches_filter() {
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+
+        let record_values = User::columns()
+            .iter()
+            .cloned()
+            .zip(vec![
+                Value::Uint32(1.into()),
+                Value::Text("Alice".to_string().into()),
+            ])
+            .collect::<Vec<(ColumnDef, Value)>>();
+        let filter = Filter::eq("name", Value::Text("Alice".to_string().into()));
+
+        let matches = dbms
+            .record_matches_filter(&record_values, &filter)
+            .expect("failed to match");
+        assert!(matches);
+
+        let non_matching_filter = Filter::eq("name", Value::Text("Bob".to_string().into()));
+        let non_matches = dbms
+            .record_matches_filter(&record_values, &non_matching_filter)
+            .expect("failed to match");
+        assert!(!non_matches);
+    }
+
+    #[test]
+    fn test_should_load_table_registry() {
+        init_user_table();
+
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        let table_registry = dbms.load_table_registry::<User>();
+        assert!(table_registry.is_ok());
+    }
+
+    #[test]
+    fn test_should_not_write_schema_registry_on_select() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        MEMORY_MANAGER.with_borrow_mut(|mm| mm.reset_stats());
+
+        let query = Query::<User>::builder().all().build();
+        dbms.select(query).expect("failed to select users");
+
+        let stats = MEMORY_MANAGER.with_borrow(|mm| mm.write_amplification_tracker());
+        assert_eq!(stats.schema_registry_bytes, 0);
+    }
+
+    #[test]
+    fn test_should_insert_record_without_transaction() {
+        load_fixtures();
+
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        let new_user = UserInsertRequest {
+            id: Uint32(100u32),
+            name: Text("NewUser".to_string()),
+        };
+
+        let result = dbms.insert::<User>(new_user);
+        assert!(result.is_ok());
+
+        // find user
+        let query = Query::<User>::builder()
+            .and_where(Filter::eq("id", Value::Uint32(100u32.into())))
+            .build();
+        let users = dbms.select(query).expect("failed to select users");
+        assert_eq!(users.len(), 1);
+        let user = &users[0];
+        assert_eq!(user.id.expect("should have id").0, 100);
+        assert_eq!(
+            user.name.as_ref().expect("should have name").0,
+            "NewUser".to_string()
+        );
+    }
+
+    #[test]
+    fn test_should_assign_auto_increment_ids() {
+        load_fixtures();
+
+        for i in 0..100 {
+            // each insert uses its own instance, so the sequence must come from memory
+            let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+            dbms.insert::<Ticket>(TicketInsertRequest {
+                id: None,
+                title: Text(format!("ticket {i}")),
+            })
+            .expect("failed to insert ticket");
+        }
+
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        let tickets = dbms
+            .select::<Ticket>(Query::builder().order_by_asc("id").build())
+            .expect("failed to select tickets");
+        let ids = tickets
+            .iter()
+            .map(|ticket| ticket.id.expect("should have id").0)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, (1..=100).collect::<Vec<u64>>());
+        assert_eq!(
+            tickets[41].title.as_ref().expect("should have title").0,
+            "ticket 41"
+        );
+        assert_eq!(
+            SCHEMA_REGISTRY.with_borrow(|sr| sr.sequence_value(Ticket::fingerprint())),
+            Some(100)
+        );
+    }
+
+    #[test]
+    fn test_should_assign_auto_increment_ids_within_transaction() {
+        load_fixtures();
+
+        let transaction_id =
+            TRANSACTION_SESSION.with_borrow_mut(|ts| ts.begin_transaction(Principal::anonymous()));
+        let mut dbms = IcDbmsDatabase::from_transaction(TestDatabaseSchema, transaction_id);
+        for title in ["first", "second"] {
+            dbms.insert::<Ticket>(TicketInsertRequest {
+                id: None,
+                title: Text(title.to_string()),
+            })
+            .expect("failed to insert ticket");
+        }
+        dbms.commit().expect("failed to commit");
+
+        let tickets = IcDbmsDatabase::oneshot(TestDatabaseSchema)
+            .select::<Ticket>(Query::builder().order_by_asc("id").build())
+            .expect("failed to select tickets");
+        assert_eq!(tickets.len(), 2);
+        assert_eq!(tickets[0].id, Some(1u64.into()));
+        assert_eq!(tickets[1].id, Some(2u64.into()));
+        assert_eq!(
+            tickets[1].title.as_ref().expect("should have title").0,
+            "second"
+        );
+    }
+
+    #[test]
+    fn test_should_insert_and_select_derived_record() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+
+        let keyboard = Product {
+            id: Uint32(1),
+            name: Text("keyboard".to_string()),
+            price: Nullable::Value(4999u64.into()),
+            tags: Array(vec![
+                Text("peripheral".to_string()),
+                Text("input".to_string()),
+            ]),
+        };
+        let mouse = Product {
+            id: Uint32(2),
+            name: Text("mouse".to_string()),
+            price: Nullable::Null,
+            tags: Array::default(),
+        };
+        dbms.insert::<Product>(keyboard.clone())
+            .expect("failed to insert keyboard");
+        dbms.insert::<Product>(mouse.clone())
+            .expect("failed to insert mouse");
+
+        let products = dbms
+            .select::<Product>(Query::builder().order_by_asc("id").build())
+            .expect("failed to select products");
+        assert_eq!(
+            products,
+            vec![
+                ProductRecord {
+                    id: Some(keyboard.id),
+                    name: Some(keyboard.name),
+                    price: Some(keyboard.price),
+                    tags: Some(keyboard.tags),
+                },
+                ProductRecord {
+                    id: Some(mouse.id),
+                    name: Some(mouse.name),
+                    price: Some(Nullable::Null),
+                    tags: Some(Array::default()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_should_select_records_whose_array_contains_value() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+
+        for (id, name, tags) in [
+            (1, "keyboard", vec!["peripheral", "input"]),
+            (2, "monitor", vec!["peripheral", "output"]),
+            (3, "cable", vec![]),
+        ] {
+            dbms.insert::<Product>(Product {
+                id: Uint32(id),
+                name: Text(name.to_string()),
+                price: Nullable::Null,
+                tags: Array(tags.into_iter().map(|tag| Text(tag.to_string())).collect()),
+            })
+            .expect("failed to insert product");
+        }
+
+        let names = |filter: Filter| {
+            dbms.select::<Product>(
+                Query::builder()
+                    .and_where(filter)
+                    .order_by_asc("id")
+                    .build(),
+            )
+            .expect("failed to select products")
+            .into_iter()
+            .map(|product| product.name.expect("should have name").0)
+            .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            names(Filter::contains(
+                "tags",
+                Value::Text(Text("peripheral".to_string()))
+            )),
+            vec!["keyboard", "monitor"]
+        );
+        assert_eq!(
+            names(Filter::contains(
+                "tags",
+                Value::Text(Text("output".to_string()))
+            )),
+            vec!["monitor"]
+        );
+        assert!(
+            names(Filter::contains(
+                "tags",
+                Value::Text(Text("wireless".to_string()))
+            ))
+            .is_empty()
+        );
+        assert_eq!(
+            names(
+                Filter::from_expression("NOT (tags CONTAINS 'input')").expect("invalid expression")
+            ),
+            vec!["monitor", "cable"]
+        );
+    }
+
+    #[test]
+    fn test_should_insert_returning_field() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+
+        let id = dbms
+            .insert_returning_field::<User>(
+                UserInsertRequest {
+                    id: Uint32(500u32),
+                    name: Text("Returning".to_string()),
+                },
+                "id",
+            )
+            .expect("failed to insert user");
+        assert_eq!(id, Value::Uint32(500u32.into()));
+
+        let name = dbms
+            .insert_returning_field::<User>(
+                UserInsertRequest {
+                    id: Uint32(501u32),
+                    name: Text("Named".to_string()),
+                },
+                "name",
+            )
+            .expect("failed to insert user");
+        assert_eq!(name, Value::Text("Named".to_string().into()));
+    }
+
+    #[test]
+    fn test_should_insert_returning_field_within_transaction() {
+        load_fixtures();
+        let transaction_id =
+            TRANSACTION_SESSION.with_borrow_mut(|ts| ts.begin_transaction(Principal::anonymous()));
+        let mut dbms = IcDbmsDatabase::from_transaction(TestDatabaseSchema, transaction_id);
+
+        let id = dbms
+            .insert_returning_field::<User>(
+                UserInsertRequest {
+                    id: Uint32(510u32),
+                    name: Text("Pending".to_string()),
+                },
+                "id",
+            )
+            .expect("failed to insert user");
+        assert_eq!(id, Value::Uint32(510u32.into()));
+        dbms.commit().expect("failed to commit");
+    }
+
+    #[test]
+    fn test_should_not_insert_returning_unknown_field() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+
+        let result = dbms.insert_returning_field::<User>(
+            UserInsertRequest {
+                id: Uint32(520u32),
+                name: Text("Unknown".to_string()),
+            },
+            "unknown",
+        );
+        assert!(matches!(
+            result,
+            Err(IcDbmsError::Query(QueryError::UnknownColumn(column))) if column == "unknown"
+        ));
+
+        // nothing is inserted
+        let users = dbms
+            .select::<User>(
+                Query::builder()
+                    .and_where(Filter::eq("id", Value::Uint32(520u32.into())))
+                    .build(),
+            )
+            .expect("failed to select users");
+        assert!(users.is_empty());
+    }
+
+    #[test]
+    fn test_should_validate_user_insert_conflict() {
+        load_fixtures();
+
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        let new_user = UserInsertRequest {
+            id: Uint32(1u32),
+            name: Text("NewUser".to_string()),
+        };
+
+        let result = dbms.insert::<User>(new_user);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_should_insert_within_a_transaction() {
+        load_fixtures();
+
+        // create a transaction
+        let transaction_id =
+            TRANSACTION_SESSION.with_borrow_mut(|ts| ts.begin_transaction(Principal::anonymous()));
+        let mut dbms = IcDbmsDatabase::from_transaction(TestDatabaseSchema, transaction_id.clone());
+
+        let new_user = UserInsertRequest {
+            id: Uint32(200u32),
+            name: Text("TxUser".to_string()),
+        };
+
+        let result = dbms.insert::<User>(new_user);
+        assert!(result.is_ok());
+
+        // user should not be visible outside the transaction
+        let oneshot_dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        let query = Query::<User>::builder()
+            .and_where(Filter::eq("id", Value::Uint32(200u32.into())))
+            .build();
+        let users = oneshot_dbms
+            .select(query.clone())
+            .expect("failed to select users");
+        assert_eq!(users.len(), 0);
+
+        // commit transaction
+        let commit_result = dbms.commit();
+        assert!(commit_result.is_ok());
+
+        // now user should be visible
+        let users_after_commit = oneshot_dbms.select(query).expect("failed to select users");
+        assert_eq!(users_after_commit.len(), 1);
+
+        let user = &users_after_commit[0];
+        assert_eq!(user.id.expect("should have id").0, 200);
+        assert_eq!(
+            user.name.as_ref().expect("should have name").0,
+            "TxUser".to_string()
+        );
+
+        // transaction should have been removed
+        TRANSACTION_SESSION.with_borrow(|ts| {
+            let tx_res = ts.get_transaction(&transaction_id);
+            assert!(tx_res.is_err());
+        });
+    }
+
+    #[test]
+    fn test_should_rollback_transaction() {
+        load_fixtures();
+
+        // create a transaction
+        let transaction_id =
+            TRANSACTION_SESSION.with_borrow_mut(|ts| ts.begin_transaction(Principal::anonymous()));
+        let mut dbms = IcDbmsDatabase::from_transaction(TestDatabaseSchema, transaction_id.clone());
+        let new_user = UserInsertRequest {
+            id: Uint32(300u32),
+            name: Text("RollbackUser".to_string()),
+        };
+        let result = dbms.insert::<User>(new_user);
+        assert!(result.is_ok());
+
+        // rollback transaction
+        let rollback_result = dbms.rollback();
+        assert!(rollback_result.is_ok());
+
+        // user should not be visible
+        let oneshot_dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        let query = Query::<User>::builder()
+            .and_where(Filter::eq("id", Value::Uint32(300u32.into())))
+            .build();
+        let users = oneshot_dbms.select(query).expect("failed to select users");
+        assert_eq!(users.len(), 0);
+
+        // transaction should have been removed
+        TRANSACTION_SESSION.with_borrow(|ts| {
+            let tx_res = ts.get_transaction(&transaction_id);
+            assert!(tx_res.is_err());
+        });
+    }
+
+    #[test]
+    fn test_should_reject_writes_in_read_only_transaction() {
+        load_fixtures();
+
+        let transaction_id = TRANSACTION_SESSION
+            .with_borrow_mut(|ts| ts.begin_read_only_transaction(Principal::anonymous()));
+        let mut dbms =
+            IcDbmsDatabase::from_read_only_transaction(TestDatabaseSchema, transaction_id.clone());
+        let result = dbms.insert::<User>(UserInsertRequest {
+            id: Uint32(300u32),
+            name: Text("ReadOnlyUser".to_string()),
+        });
+        assert!(matches!(
+            result,
+            Err(IcDbmsError::Transaction(
+                TransactionError::ReadOnlyTransaction
+            ))
+        ));
+        assert!(matches!(
+            dbms.delete::<User>(DeleteBehavior::Cascade, None),
+            Err(IcDbmsError::Transaction(
+                TransactionError::ReadOnlyTransaction
+            ))
+        ));
+
+        // selects are allowed
+        let users = dbms
+            .select(Query::<User>::builder().all().build())
+            .expect("failed to select users");
+        assert!(!users.is_empty());
+
+        // the session rejects writes regardless of the instance
+        let writable = IcDbmsDatabase::from_transaction(TestDatabaseSchema, transaction_id.clone());
+        assert!(matches!(
+            writable.insert::<User>(UserInsertRequest {
+                id: Uint32(300u32),
+                name: Text("ReadOnlyUser".to_string()),
+            }),
+            Err(IcDbmsError::Transaction(
+                TransactionError::ReadOnlyTransaction
+            ))
+        ));
+        TRANSACTION_SESSION.with_borrow(|ts| {
+            assert!(
+                ts.get_transaction(&transaction_id)
+                    .expect("failed to get tx")
+                    .operations
+                    .is_empty()
+            );
+        });
+
+        dbms.commit().expect("failed to commit");
+        TRANSACTION_SESSION.with_borrow(|ts| {
+            assert!(ts.get_transaction(&transaction_id).is_err());
+        });
+        let users = IcDbmsDatabase::oneshot(TestDatabaseSchema)
+            .select(
+                Query::<User>::builder()
+                    .and_where(Filter::eq("id", Value::Uint32(300u32.into())))
+                    .build(),
+            )
+            .expect("failed to select users");
+        assert!(users.is_empty());
+    }
+
+    /// Returns the sorted ids of the users with the given ids visible to `dbms`.
+    fn select_user_ids(dbms: &IcDbmsDatabase, ids: &[u32]) -> Vec<u32> {
+        let filter = ids
+            .iter()
+            .map(|id| Filter::eq("id", Value::Uint32((*id).into())))
+            .reduce(|acc, filter| acc.or(filter))
+            .expect("no ids");
+        let mut found = dbms
+            .select::<User>(Query::builder().and_where(filter).build())
+            .expect("failed to select users")
+            .into_iter()
+            .map(|user| user.id.expect("missing id").0)
+            .collect::<Vec<_>>();
+        found.sort_unstable();
+        found
+    }
+
+    #[test]
+    fn test_should_merge_nested_transaction_into_parent() {
+        load_fixtures();
+
+        let transaction_id =
+            TRANSACTION_SESSION.with_borrow_mut(|ts| ts.begin_transaction(Principal::anonymous()));
+        let mut dbms = IcDbmsDatabase::from_transaction(TestDatabaseSchema, transaction_id);
+        dbms.insert::<User>(UserInsertRequest {
+            id: Uint32(400u32),
+            name: Text("Outer".to_string()),
+        })
+        .expect("failed to insert user");
+
+        dbms.nested_transaction(|nested| {
+            // the nested transaction sees the changes of its parent
+            assert_eq!(select_user_ids(nested, &[400]), vec![400]);
+            nested.insert::<User>(UserInsertRequest {
+                id: Uint32(401u32),
+                name: Text("Nested".to_string()),
+            })
+        })
+        .expect("nested transaction failed");
+
+        // merged into the parent, but not committed yet
+        assert_eq!(select_user_ids(&dbms, &[400, 401]), vec![400, 401]);
+        let oneshot = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        assert!(select_user_ids(&oneshot, &[400, 401]).is_empty());
+
+        dbms.commit().expect("failed to commit");
+        assert_eq!(select_user_ids(&oneshot, &[400, 401]), vec![400, 401]);
+    }
+
+    #[test]
+    fn test_should_rollback_nested_transaction_leaving_parent() {
+        load_fixtures();
+
+        let transaction_id =
+            TRANSACTION_SESSION.with_borrow_mut(|ts| ts.begin_transaction(Principal::anonymous()));
+        let mut dbms = IcDbmsDatabase::from_transaction(TestDatabaseSchema, transaction_id);
+        dbms.insert::<User>(UserInsertRequest {
+            id: Uint32(410u32),
+            name: Text("Outer".to_string()),
+        })
+        .expect("failed to insert user");
+
+        let result = dbms.nested_transaction(|nested| {
+            nested.insert::<User>(UserInsertRequest {
+                id: Uint32(411u32),
+                name: Text("Nested".to_string()),
+            })?;
+            // conflicts with the record inserted by the parent
+            nested.insert::<User>(UserInsertRequest {
+                id: Uint32(410u32),
+                name: Text("Conflict".to_string()),
+            })
+        });
+        assert!(result.is_err());
+
+        assert_eq!(select_user_ids(&dbms, &[410, 411]), vec![410]);
+        dbms.commit().expect("failed to commit");
+        let oneshot = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        assert_eq!(select_user_ids(&oneshot, &[410, 411]), vec![410]);
+    }
+
+    #[test]
+    fn test_should_merge_double_nested_transaction() {
+        load_fixtures();
+
+        let transaction_id =
+            TRANSACTION_SESSION.with_borrow_mut(|ts| ts.begin_transaction(Principal::anonymous()));
+        let mut dbms = IcDbmsDatabase::from_transaction(TestDatabaseSchema, transaction_id);
+
+        dbms.nested_transaction(|nested| {
+            nested.insert::<User>(UserInsertRequest {
+                id: Uint32(420u32),
+                name: Text("Nested".to_string()),
+            })?;
+            nested.nested_transaction(|inner| {
+                inner.insert::<User>(UserInsertRequest {
+                    id: Uint32(421u32),
+                    name: Text("Inner".to_string()),
+                })
+            })?;
+            // the failed inner transaction is discarded, while the outer ones go on
+            let failed = nested.nested_transaction(|inner| {
+                inner.insert::<User>(UserInsertRequest {
+                    id: Uint32(422u32),
+                    name: Text("Discarded".to_string()),
+                })?;
+                Err::<(), _>(IcDbmsError::Transaction(
+                    TransactionError::NoActiveTransaction,
+                ))
+            });
+            assert!(failed.is_err());
+            assert_eq!(select_user_ids(nested, &[420, 421, 422]), vec![420, 421]);
+
+            Ok(())
+        })
+        .expect("nested transaction failed");
+
+        dbms.commit().expect("failed to commit");
+        let oneshot = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        assert_eq!(select_user_ids(&oneshot, &[420, 421, 422]), vec![420, 421]);
+    }
+
+    #[test]
+    fn test_should_commit_nested_transaction_without_parent() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+
+        dbms.nested_transaction(|nested| {
+            nested.insert::<User>(UserInsertRequest {
+                id: Uint32(430u32),
+                name: Text("Standalone".to_string()),
+            })
+        })
+        .expect("nested transaction failed");
+
+        assert_eq!(select_user_ids(&dbms, &[430]), vec![430]);
+    }
+
+    #[test]
+    fn test_should_rollback_to_savepoint() {
+        load_fixtures();
+
+        let transaction_id =
+            TRANSACTION_SESSION.with_borrow_mut(|ts| ts.begin_transaction(Principal::anonymous()));
+        let mut dbms = IcDbmsDatabase::from_transaction(TestDatabaseSchema, transaction_id);
+        dbms.insert::<User>(UserInsertRequest {
+            id: Uint32(440u32),
+            name: Text("Before".to_string()),
+        })
+        .expect("failed to insert user");
+
+        let savepoint = dbms.savepoint().expect("failed to take savepoint");
+        dbms.insert::<User>(UserInsertRequest {
+            id: Uint32(441u32),
+            name: Text("After".to_string()),
+        })
+        .expect("failed to insert user");
+        assert_eq!(select_user_ids(&dbms, &[440, 441]), vec![440, 441]);
+
+        dbms.rollback_to_savepoint(savepoint)
+            .expect("failed to rollback to savepoint");
+        assert_eq!(select_user_ids(&dbms, &[440, 441]), vec![440]);
+
+        // the transaction is still open
+        dbms.insert::<User>(UserInsertRequest {
+            id: Uint32(441u32),
+            name: Text("Again".to_string()),
+        })
+        .expect("failed to insert user");
+        dbms.commit().expect("failed to commit");
+        let oneshot = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        assert_eq!(select_user_ids(&oneshot, &[440, 441]), vec![440, 441]);
+    }
+
+    #[test]
+    fn test_should_not_take_savepoint_without_transaction() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+
+        assert!(matches!(
+            dbms.savepoint(),
+            Err(IcDbmsError::Transaction(
+                TransactionError::NoActiveTransaction
+            ))
+        ));
+        assert!(matches!(
+            dbms.rollback_to_savepoint(0),
+            Err(IcDbmsError::Transaction(
+                TransactionError::NoActiveTransaction
+            ))
+        ));
+    }
+
+    /// Selects the `(student_id, course, grade)` of all the enrollments, sorted.
+    fn select_enrollments(dbms: &IcDbmsDatabase) -> Vec<(u32, String, u32)> {
+        let mut enrollments = dbms
+            .select::<Enrollment>(Query::builder().all().build())
+            .expect("failed to select enrollments")
+            .into_iter()
+            .map(|record| {
+                (
+                    record.student_id.expect("missing student_id").0,
+                    record.course.expect("missing course").0,
+                    record.grade.expect("missing grade").0,
+                )
+            })
+            .collect::<Vec<_>>();
+        enrollments.sort();
+        enrollments
+    }
+
+    #[test]
+    fn test_should_update_and_delete_by_composite_primary_key() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+
+        // only the record matching the whole primary key is replaced
+        let patch = EnrollmentUpdateRequest {
+            student_id: None,
+            course: None,
+            grade: Some(Uint32(18)),
+            where_clause: Some(Filter::eq_composite(
+                Enrollment::primary_keys(),
+                vec![
+                    Value::Uint32(0.into()),
+                    Value::Text("math".to_string().into()),
+                ],
+            )),
+        };
+        assert_eq!(
+            dbms.update::<Enrollment>(UpdateBehavior::Restrict, patch)
+                .expect("failed to update"),
+            1
+        );
+        assert_eq!(
+            select_enrollments(&dbms),
+            vec![
+                (0, "math".to_string(), 18),
+                (0, "physics".to_string(), 30),
+                (1, "math".to_string(), 24),
+            ]
+        );
+
+        let transaction_id =
+            TRANSACTION_SESSION.with_borrow_mut(|ts| ts.begin_transaction(Principal::anonymous()));
+        let mut dbms = IcDbmsDatabase::from_transaction(TestDatabaseSchema, transaction_id);
+        let patch = EnrollmentUpdateRequest {
+            student_id: None,
+            course: None,
+            grade: Some(Uint32(27)),
+            where_clause: Some(Filter::eq("course", Value::Text("math".to_string().into()))),
+        };
+        assert_eq!(
+            dbms.update::<Enrollment>(UpdateBehavior::Restrict, patch)
+                .expect("failed to update"),
+            2
+        );
+        let deleted = dbms
+            .delete::<Enrollment>(
+                DeleteBehavior::Restrict,
+                Some(Filter::eq_composite(
+                    Enrollment::primary_keys(),
+                    vec![
+                        Value::Uint32(0.into()),
+                        Value::Text("physics".to_string().into()),
+                    ],
+                )),
+            )
+            .expect("failed to delete");
+        assert_eq!(deleted, 1);
+
+        let expected = vec![(0, "math".to_string(), 27), (1, "math".to_string(), 27)];
+        assert_eq!(select_enrollments(&dbms), expected);
+        dbms.commit().expect("failed to commit");
+        let oneshot = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        assert_eq!(select_enrollments(&oneshot), expected);
+    }
+
+    #[test]
+    fn test_should_not_reference_composite_primary_key() {
+        let user = User::columns()
+            .iter()
+            .copied()
+            .zip([
+                Value::Uint32(1.into()),
+                Value::Text("Alice".to_string().into()),
+            ])
+            .collect::<Vec<_>>();
+        assert_eq!(
+            IcDbmsDatabase::referenced_key::<User>(&user).expect("failed to get key"),
+            Value::Uint32(1.into())
+        );
+
+        let enrollment = Enrollment::columns()
+            .iter()
+            .copied()
+            .zip([
+                Value::Uint32(0.into()),
+                Value::Text("math".to_string().into()),
+                Value::Uint32(28.into()),
+            ])
+            .collect::<Vec<_>>();
+        assert!(matches!(
+            IcDbmsDatabase::referenced_key::<Enrollment>(&enrollment),
+            Err(IcDbmsError::Query(QueryError::InvalidQuery(_)))
+        ));
+    }
+
+    #[test]
+    fn test_should_delete_one_shot() {
+        load_fixtures();
+
+        // insert user with id 100
+        let new_user = UserInsertRequest {
+            id: Uint32(100u32),
+            name: Text("DeleteUser".to_string()),
+        };
+        assert!(
+            IcDbmsDatabase::oneshot(TestDatabaseSchema)
+                .insert::<User>(new_user)
+                .is_ok()
+        );
+
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        let query = Query::<User>::builder()
+            .and_where(Filter::eq("id", Value::Uint32(100u32.into())))
+            .build();
+        let delete_count = dbms
+            .delete::<User>(
+                DeleteBehavior::Restrict,
+                Some(Filter::eq("id", Value::Uint32(100u32.into()))),
+            )
+            .expect("failed to delete user");
+        assert_eq!(delete_count, 1);
+
+        // verify user is deleted
+        let users = dbms.select(query).expect("failed to select users");
+        assert_eq!(users.len(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Foreign key constraint violation")]
+    fn test_should_not_delete_with_fk_restrict() {
+        load_fixtures();
+
+        // user 1 has post and messages for sure.
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        dbms.delete::<User>(
+            DeleteBehavior::Restrict,
+            Some(Filter::eq("id", Value::Uint32(1u32.into()))),
+        )
+        .expect("failed to delete user");
+    }
+
+    #[test]
+    fn test_should_delete_with_fk_cascade() {
+        load_fixtures();
+
+        // user 1 has posts and messages for sure.
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        let delete_count = dbms
+            .delete::<User>(
+                DeleteBehavior::Cascade,
+                Some(Filter::eq("id", Value::Uint32(1u32.into()))),
+            )
+            .expect("failed to delete user");
+        assert!(delete_count > 0);
+
+        // verify user is deleted
+        let query = Query::<User>::builder()
+            .and_where(Filter::eq("id", Value::Uint32(1u32.into())))
+            .build();
+        let users = dbms.select(query).expect("failed to select users");
+        assert_eq!(users.len(), 0);
+
+        // check posts are deleted (post ID 2)
+        let post_query = Query::<Post>::builder()
+            .and_where(Filter::eq("user_id", Value::Uint32(1u32.into())))
+            .build();
+        let posts = dbms.select(post_query).expect("failed to select posts");
+        assert_eq!(posts.len(), 0);
+
+        // check messages are deleted (message ID 1)
+        let message_query = Query::<Message>::builder()
+            .and_where(Filter::eq("sender_id", Value::Uint32(1u32.into())))
+            .or_where(Filter::eq("recipient_id", Value::Uint32(1u32.into())))
+            .build();
+        let messages = dbms
+            .select(message_query)
+            .expect("failed to select messages");
+        assert_eq!(messages.len(), 0);
+    }
+
+    #[test]
+    fn test_should_delete_within_transaction() {
+        load_fixtures();
+
+        // create a transaction
+        let transaction_id =
+            TRANSACTION_SESSION.with_borrow_mut(|ts| ts.begin_transaction(Principal::anonymous()));
+        let mut dbms = IcDbmsDatabase::from_transaction(TestDatabaseSchema, transaction_id.clone());
+
+        let delete_count = dbms
+            .delete::<User>(
+                DeleteBehavior::Cascade,
+                Some(Filter::eq("id", Value::Uint32(2u32.into()))),
+            )
+            .expect("failed to delete user");
+        assert!(delete_count > 0);
+
+        // user should not be visible outside the transaction
+        let oneshot_dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        let query = Query::<User>::builder()
+            .and_where(Filter::eq("id", Value::Uint32(2u32.into())))
+            .build();
+        let users = oneshot_dbms
+            .select(query.clone())
+            .expect("failed to select users");
+        assert_eq!(users.len(), 1);
+
+        // commit transaction
+        let commit_result = dbms.commit();
+        assert!(commit_result.is_ok());
+
+        // now user should be deleted
+        let users_after_commit = oneshot_dbms.select(query).expect("failed to select users");
+        assert_eq!(users_after_commit.len(), 0);
+
+        // check posts are deleted
+        let post_query = Query::<Post>::builder()
+            .and_where(Filter::eq("user_id", Value::Uint32(2u32.into())))
+            .build();
+        let posts = oneshot_dbms
+            .select(post_query)
+            .expect("failed to select posts");
+        assert_eq!(posts.len(), 0);
+
+        // check messages are deleted
+        let message_query = Query::<Message>::builder()
+            .and_where(Filter::eq("sender_id", Value::Uint32(2u32.into())))
+            .or_where(Filter::eq("recipient_id", Value::Uint32(2u32.into())))
+            .build();
+        let messages = oneshot_dbms
+            .select(message_query)
+            .expect("failed to select messages");
+        assert_eq!(messages.len(), 0);
+
+        // transaction should have been removed
+        TRANSACTION_SESSION.with_borrow(|ts| {
+            let tx_res = ts.get_transaction(&transaction_id);
+            assert!(tx_res.is_err());
+        });
+    }
+
+    #[test]
+    fn test_should_update_one_shot() {
+        load_fixtures();
+
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        let filter = Filter::eq("id", Value::Uint32(3u32.into()));
+
+        let patch = UserUpdateRequest {
+            id: None,
+            name: Some(Text("UpdatedName".to_string())),
+            where_clause: Some(filter.clone()),
+        };
+
+        let update_count = dbms
+            .update::<User>(UpdateBehavior::Restrict, patch)
+            .expect("failed to update user");
+        assert_eq!(update_count, 1);
+
+        // verify user is updated
+        let query = Query::<User>::builder().and_where(filter).build();
+        let users = dbms.select(query).expect("failed to select users");
+        assert_eq!(users.len(), 1);
+        let user = &users[0];
+        assert_eq!(user.id.expect("should have id").0, 3);
+        assert_eq!(
+            user.name.as_ref().expect("should have name").0,
+            "UpdatedName".to_string()
+        );
+    }
+
+    #[test]
+    fn test_should_update_primary_key_with_fk_cascade() {
+        load_fixtures();
+
+        // user 1 has posts and messages for sure.
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        let patch = UserUpdateRequest {
+            id: Some(Uint32(100)),
+            name: None,
+            where_clause: Some(Filter::eq("id", Value::Uint32(1u32.into()))),
+        };
+        let update_count = dbms
+            .update::<User>(UpdateBehavior::Cascade, patch)
+            .expect("failed to update user");
+        assert_eq!(update_count, 1);
+
+        // the old record is replaced by the new one
+        assert_eq!(select_user_ids(&dbms, &[1, 100]), vec![100]);
+
+        // posts reference the new primary key
+        assert_eq!(count_posts_of_user(&dbms, 1), 0);
+        assert_eq!(count_posts_of_user(&dbms, 100), 2);
+
+        // messages reference the new primary key, both as sender and recipient
+        let message_query = |user_id: u32| {
+            Query::<Message>::builder()
+                .and_where(Filter::eq("sender_id", Value::Uint32(user_id.into())))
+                .or_where(Filter::eq("recipient_id", Value::Uint32(user_id.into())))
+                .build()
+        };
+        let messages = dbms
+            .select(message_query(1))
+            .expect("failed to select messages");
+        assert!(messages.is_empty());
+        let messages = dbms
+            .select(message_query(100))
+            .expect("failed to select messages");
+        assert_eq!(messages.len(), MESSAGES_FIXTURES.len());
+    }
+
+    #[test]
+    fn test_should_cascade_primary_key_to_all_referencing_columns() {
+        load_fixtures();
+
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        dbms.insert::<Message>(MessageInsertRequest {
+            id: Uint32(100),
+            text: Text("Note to self".to_string()),
+            sender_id: Uint32(3),
+            recipient_id: Uint32(3),
+            read_at: Nullable::Null,
+        })
+        .expect("failed to insert message");
+
+        let patch = UserUpdateRequest {
+            id: Some(Uint32(300)),
+            name: None,
+            where_clause: Some(Filter::eq("id", Value::Uint32(3u32.into()))),
+        };
+        dbms.update::<User>(UpdateBehavior::Cascade, patch)
+            .expect("failed to update user");
+
+        let messages = dbms
+            .select(
+                Query::<Message>::builder()
+                    .and_where(Filter::eq("id", Value::Uint32(100u32.into())))
+                    .and_where(Filter::eq("sender_id", Value::Uint32(300u32.into())))
+                    .and_where(Filter::eq("recipient_id", Value::Uint32(300u32.into())))
+                    .build(),
+            )
+            .expect("failed to select messages");
+        assert_eq!(messages.len(), 1);
+    }
+
+    #[test]
+    fn test_should_not_update_primary_key_with_fk_restrict() {
+        load_fixtures();
+
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        let patch = UserUpdateRequest {
+            id: Some(Uint32(100)),
+            name: None,
+            where_clause: Some(Filter::eq("id", Value::Uint32(1u32.into()))),
+        };
+        assert!(matches!(
+            dbms.update::<User>(UpdateBehavior::Restrict, patch),
+            Err(IcDbmsError::Query(
+                QueryError::ForeignKeyConstraintViolation { .. }
+            ))
+        ));
+        assert_eq!(select_user_ids(&dbms, &[1, 100]), vec![1]);
+        assert_eq!(count_posts_of_user(&dbms, 1), 2);
+
+        // users without references can change their primary key
+        dbms.insert::<User>(UserInsertRequest {
+            id: Uint32(50),
+            name: Text("Unreferenced".to_string()),
+        })
+        .expect("failed to insert user");
+        let patch = UserUpdateRequest {
+            id: Some(Uint32(100)),
+            name: None,
+            where_clause: Some(Filter::eq("id", Value::Uint32(50u32.into()))),
+        };
+        dbms.update::<User>(UpdateBehavior::Restrict, patch)
+            .expect("failed to update user");
+        assert_eq!(select_user_ids(&dbms, &[50, 100]), vec![100]);
+    }
+
+    #[test]
+    fn test_should_update_primary_key_with_fk_cascade_within_transaction() {
+        load_fixtures();
+
+        let transaction_id =
+            TRANSACTION_SESSION.with_borrow_mut(|ts| ts.begin_transaction(Principal::anonymous()));
+        let mut dbms = IcDbmsDatabase::from_transaction(TestDatabaseSchema, transaction_id);
+        let patch = UserUpdateRequest {
+            id: Some(Uint32(200)),
+            name: None,
+            where_clause: Some(Filter::eq("id", Value::Uint32(2u32.into()))),
+        };
+        dbms.update::<User>(UpdateBehavior::Cascade, patch)
+            .expect("failed to update user");
+
+        // the related records are updated on commit
+        let oneshot_dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        assert_eq!(count_posts_of_user(&oneshot_dbms, 2), 2);
+        dbms.commit().expect("failed to commit");
+
+        assert_eq!(select_user_ids(&oneshot_dbms, &[2, 200]), vec![200]);
+        assert_eq!(count_posts_of_user(&oneshot_dbms, 2), 0);
+        assert_eq!(count_posts_of_user(&oneshot_dbms, 200), 2);
+    }
+
+    /// Returns the amount of posts of the user with the given id visible to `dbms`.
+    fn count_posts_of_user(dbms: &IcDbmsDatabase, user_id: u32) -> usize {
+        dbms.select(
+            Query::<Post>::builder()
+                .and_where(Filter::eq("user_id", Value::Uint32(user_id.into())))
+                .build(),
+        )
+        .expect("failed to select posts")
+        .len()
+    }
+
+    #[test]
+    fn test_should_update_within_transaction() {
+        load_fixtures();
+
+        // create a transaction
+        let transaction_id =
+            TRANSACTION_SESSION.with_borrow_mut(|ts| ts.begin_transaction(Principal::anonymous()));
+        let mut dbms = IcDbmsDatabase::from_transaction(TestDatabaseSchema, transaction_id.clone());
+
+        let filter = Filter::eq("id", Value::Uint32(4u32.into()));
+        let patch = UserUpdateRequest {
+            id: None,
+            name: Some(Text("TxUpdatedName".to_string())),
+            where_clause: Some(filter.clone()),
+        };
+
+        let update_count = dbms
+            .update::<User>(UpdateBehavior::Restrict, patch)
+            .expect("failed to update user");
+        assert_eq!(update_count, 1);
+
+        // user should not be visible outside the transaction
+        let oneshot_dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        let query = Query::<User>::builder().and_where(filter.clone()).build();
+        let users = oneshot_dbms
+            .select(query.clone())
+            .expect("failed to select users");
+        let user = &users[0];
+        assert_eq!(
+            user.name.as_ref().expect("should have name").0,
+            USERS_FIXTURES[4]
+        );
+
+        // commit transaction
+        let commit_result = dbms.commit();
+        assert!(commit_result.is_ok());
+
+        // now user should be updated
+        let users_after_commit = oneshot_dbms.select(query).expect("failed to select users");
+        assert_eq!(users_after_commit.len(), 1);
+        let user = &users_after_commit[0];
+        assert_eq!(
+            user.name.as_ref().expect("should have name").0,
+            "TxUpdatedName".to_string()
+        );
+
+        // transaction should have been removed
+        TRANSACTION_SESSION.with_borrow(|ts| {
+            let tx_res = ts.get_transaction(&transaction_id);
+            assert!(tx_res.is_err());
+        });
+    }
+
+    #[test]
+    fn test_should_soft_delete() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+
+        let filter = Filter::eq("id", Value::Uint32(1u32.into()));
+        let count = dbms
+            .soft_delete::<Document>(Some(filter.clone()))
+            .expect("failed to soft delete document");
+        assert_eq!(count, 1);
+
+        // document should be hidden from regular selects
+        let documents = dbms
+            .select(Query::<Document>::builder().build())
+            .expect("failed to select documents");
+        assert_eq!(documents.len(), DOCUMENTS_FIXTURES.len() - 1);
+        assert!(
+            documents
+                .iter()
+                .all(|document| document.id != Some(1u32.into()))
+        );
+        let documents = dbms
+            .select(
+                Query::<Document>::builder()
+                    .and_where(filter.clone())
+                    .build(),
+            )
+            .expect("failed to select documents");
+        assert!(documents.is_empty());
+
+        // document should be visible with `include_deleted`
+        let documents = dbms
+            .select(
+                Query::<Document>::builder()
+                    .and_where(filter)
+                    .include_deleted()
+                    .build(),
+            )
+            .expect("failed to select documents");
+        assert_eq!(documents.len(), 1);
+        let document = &documents[0];
+        assert_eq!(
+            document.title.as_ref().expect("should have title").0,
+            DOCUMENTS_FIXTURES[1]
+        );
+        assert!(matches!(
+            document.deleted_at,
+            Some(ic_dbms_api::prelude::Nullable::Value(_))
+        ));
+    }
+
+    #[test]
+    fn test_should_not_soft_delete_twice() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+
+        let filter = Filter::eq("id", Value::Uint32(2u32.into()));
+        let count = dbms
+            .soft_delete::<Document>(Some(filter.clone()))
+            .expect("failed to soft delete document");
+        assert_eq!(count, 1);
+        let count = dbms
+            .soft_delete::<Document>(Some(filter))
+            .expect("failed to soft delete document");
+        assert_eq!(count, 0);
+    }
+
+    #[test]
+    fn test_should_restore_soft_deleted_record() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+
+        let filter = Filter::eq("id", Value::Uint32(1u32.into()));
+        dbms.soft_delete::<Document>(Some(filter.clone()))
+            .expect("failed to soft delete document");
+        let count = dbms
+            .restore::<Document>(Some(filter.clone()))
+            .expect("failed to restore document");
+        assert_eq!(count, 1);
+
+        let documents = dbms
+            .select(
+                Query::<Document>::builder()
+                    .and_where(filter.clone())
+                    .build(),
+            )
+            .expect("failed to select documents");
+        assert_eq!(documents.len(), 1);
+        assert_eq!(documents[0].deleted_at, Some(Nullable::Null));
+
+        // restoring a record which isn't soft-deleted is a no-op
+        let count = dbms
+            .restore::<Document>(Some(filter))
+            .expect("failed to restore document");
+        assert_eq!(count, 0);
+    }
+
+    #[test]
+    fn test_should_update_soft_deleted_record_targeting_deleted_at() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+
+        let filter = Filter::eq("id", Value::Uint32(2u32.into()));
+        dbms.soft_delete::<Document>(Some(filter.clone()))
+            .expect("failed to soft delete document");
+
+        // a plain update doesn't see the soft-deleted record
+        let patch = DocumentUpdateRequest::from_values(
+            &[(
+                Document::columns()[1],
+                Value::Text("Archived".to_string().into()),
+            )],
+            Some(filter.clone()),
+        );
+        let count = dbms
+            .update::<Document>(UpdateBehavior::Restrict, patch)
+            .expect("failed to update document");
+        assert_eq!(count, 0);
+
+        // targeting the `deleted_at` column does
+        let patch = DocumentUpdateRequest::from_values(
+            &[(
+                Document::columns()[1],
+                Value::Text("Archived".to_string().into()),
+            )],
+            Some(filter.and(Filter::not_null("deleted_at"))),
+        );
+        let count = dbms
+            .update::<Document>(UpdateBehavior::Restrict, patch)
+            .expect("failed to update document");
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn test_should_physically_delete_soft_deleted_record() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+
+        let filter = Filter::eq("id", Value::Uint32(3u32.into()));
+        dbms.soft_delete::<Document>(Some(filter.clone()))
+            .expect("failed to soft delete document");
+        let count = dbms
+            .delete::<Document>(DeleteBehavior::Restrict, Some(filter.clone()))
+            .expect("failed to delete document");
+        assert_eq!(count, 1);
+
+        let documents = dbms
+            .select(
+                Query::<Document>::builder()
+                    .and_where(filter)
+                    .include_deleted()
+                    .build(),
+            )
+            .expect("failed to select documents");
+        assert!(documents.is_empty());
+    }
+
+    #[test]
+    fn test_should_insert_record_satisfying_check_constraint() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+
+        let customer = CustomerInsertRequest {
+            id: 100.into(),
+            name: "Dave".to_string().into(),
+            age: 42.into(),
+        };
+        dbms.insert::<Customer>(customer)
+            .expect("failed to insert customer");
+
+        let customers = dbms
+            .select(
+                Query::<Customer>::builder()
+                    .and_where(Filter::eq("id", Value::Uint32(100.into())))
+                    .build(),
+            )
+            .expect("failed to select customers");
+        assert_eq!(customers.len(), 1);
     }
 
     #[test]
-    fn test_should_select_all_users() {
+    fn test_should_not_insert_record_violating_check_constraint() {
         load_fixtures();
         let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
-        let query = Query::<User>::builder().all().build();
-        let users = dbms.select(query).expect("failed to select users");
 
-        assert_eq!(users.len(), USERS_FIXTURES.len());
-        // check if all users all loaded
-        for (i, user) in users.iter().enumerate() {
-            assert_eq!(user.id.expect("should have id").0 as usize, i);
-            assert_eq!(
-                user.name.as_ref().expect("should have name").0,
-                USERS_FIXTURES[i]
-            );
+        for (id, age) in [(100u32, -1i32), (101u32, 200i32)] {
+            let customer = CustomerInsertRequest {
+                id: id.into(),
+                name: "Dave".to_string().into(),
+                age: age.into(),
+            };
+            let result = dbms.insert::<Customer>(customer);
+            assert!(matches!(
+                result,
+                Err(IcDbmsError::Query(QueryError::CheckConstraintViolation {
+                    column: "age",
+                    constraint_desc: "age > 0 AND age < 150",
+                    value: Value::Int32(Int32(value)),
+                })) if value == age
+            ));
         }
     }
 
     #[test]
-    fn test_should_select_user_in_overlay() {
+    fn test_should_not_insert_duplicate_unique_value() {
         load_fixtures();
-        // create a transaction
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+
+        dbms.insert::<Customer>(CustomerInsertRequest {
+            id: 100.into(),
+            name: "Dave".to_string().into(),
+            age: 42.into(),
+        })
+        .expect("failed to insert customer");
+        let result = dbms.insert::<Customer>(CustomerInsertRequest {
+            id: 101.into(),
+            name: "Dave".to_string().into(),
+            age: 24.into(),
+        });
+        assert!(matches!(
+            result,
+            Err(IcDbmsError::Query(QueryError::UniqueConstraintViolation {
+                column: "name"
+            }))
+        ));
+    }
+
+    #[test]
+    fn test_should_not_update_to_duplicate_unique_value() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+
+        // Bob can't take the name of Alice
+        let filter = Filter::eq("id", Value::Uint32(1.into()));
+        let patch = CustomerUpdateRequest {
+            id: None,
+            name: Some("Alice".to_string().into()),
+            age: None,
+            where_clause: Some(filter.clone()),
+        };
+        assert!(matches!(
+            dbms.update::<Customer>(UpdateBehavior::Restrict, patch),
+            Err(IcDbmsError::Query(QueryError::UniqueConstraintViolation {
+                column: "name"
+            }))
+        ));
+
+        // many records can't get the same name
+        let patch = CustomerUpdateRequest {
+            id: None,
+            name: Some("Zoe".to_string().into()),
+            age: None,
+            where_clause: Some(Filter::gt("age", Value::Int32(30.into()))),
+        };
+        assert!(matches!(
+            dbms.update::<Customer>(UpdateBehavior::Restrict, patch),
+            Err(IcDbmsError::Query(QueryError::UniqueConstraintViolation {
+                column: "name"
+            }))
+        ));
+
+        // a record can keep its own name
+        let patch = CustomerUpdateRequest {
+            id: None,
+            name: Some("Bob".to_string().into()),
+            age: Some(28.into()),
+            where_clause: Some(filter.clone()),
+        };
+        assert_eq!(
+            dbms.update::<Customer>(UpdateBehavior::Restrict, patch)
+                .expect("failed to update customer"),
+            1
+        );
+    }
+
+    fn select_customer(dbms: &IcDbmsDatabase, id: u32) -> Option<(String, i32)> {
+        dbms.select::<Customer>(
+            Query::builder()
+                .filter(Some(Filter::eq("id", Value::Uint32(id.into()))))
+                .build(),
+        )
+        .expect("failed to select customer")
+        .into_iter()
+        .next()
+        .map(|customer| {
+            (
+                customer.name.expect("should have name").0,
+                customer.age.expect("should have age").0,
+            )
+        })
+    }
+
+    #[test]
+    fn test_should_upsert_idempotently() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        let record = CustomerInsertRequest {
+            id: 100.into(),
+            name: "Dave".to_string().into(),
+            age: 42.into(),
+        };
+
+        assert_eq!(
+            dbms.upsert::<Customer>(record.clone())
+                .expect("failed to upsert customer"),
+            UpsertResult::Inserted
+        );
+        assert_eq!(
+            dbms.upsert::<Customer>(record)
+                .expect("failed to upsert customer"),
+            UpsertResult::Updated
+        );
+        assert_eq!(select_customer(&dbms, 100), Some(("Dave".to_string(), 42)));
+        assert_eq!(
+            dbms.count_query(Query::<Customer>::builder().count_only())
+                .expect("failed to count customers"),
+            CUSTOMERS_FIXTURES.len() as u64 + 1
+        );
+    }
+
+    #[test]
+    fn test_should_upsert_existing_record() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+
+        let result = dbms
+            .upsert::<Customer>(CustomerInsertRequest {
+                id: 0.into(),
+                name: "Alice Cooper".to_string().into(),
+                age: 35.into(),
+            })
+            .expect("failed to upsert customer");
+        assert_eq!(result, UpsertResult::Updated);
+        assert_eq!(
+            select_customer(&dbms, 0),
+            Some(("Alice Cooper".to_string(), 35))
+        );
+
+        // the update is still validated
+        let result = dbms.upsert::<Customer>(CustomerInsertRequest {
+            id: 0.into(),
+            name: "Bob".to_string().into(),
+            age: 35.into(),
+        });
+        assert!(matches!(
+            result,
+            Err(IcDbmsError::Query(QueryError::UniqueConstraintViolation {
+                column: "name"
+            }))
+        ));
+    }
+
+    #[test]
+    fn test_should_upsert_within_transaction() {
+        load_fixtures();
+
         let transaction_id =
             TRANSACTION_SESSION.with_borrow_mut(|ts| ts.begin_transaction(Principal::anonymous()));
-        // insert
-        TRANSACTION_SESSION.with_borrow_mut(|ts| {
-            let tx = ts
-                .get_transaction_mut(&transaction_id)
-                .expect("should have tx");
-            tx.overlay_mut()
-                .insert::<User>(vec![
-                    (
-                        ColumnDef {
-                            name: "id",
-                            data_type: ic_dbms_api::prelude::DataTypeKind::Uint32,
-                            nullable: false,
-                            primary_key: true,
-                            foreign_key: None,
-                        },
-                        Value::Uint32(999.into()),
-                    ),
-                    (
-                        ColumnDef {
-                            name: "name",
-                            data_type: ic_dbms_api::prelude::DataTypeKind::Text,
-                            nullable: false,
-                            primary_key: false,
-                            foreign_key: None,
-                        },
-                        Value::Text("OverlayUser".to_string().into()),
-                    ),
-                ])
-                .expect("failed to insert");
+        let mut dbms = IcDbmsDatabase::from_transaction(TestDatabaseSchema, transaction_id.clone());
+        let record = CustomerInsertRequest {
+            id: 100.into(),
+            name: "Dave".to_string().into(),
+            age: 42.into(),
+        };
+
+        assert_eq!(
+            dbms.upsert::<Customer>(record.clone())
+                .expect("failed to upsert customer"),
+            UpsertResult::Inserted
+        );
+        assert_eq!(
+            dbms.upsert::<Customer>(CustomerInsertRequest {
+                age: 43.into(),
+                ..record
+            })
+            .expect("failed to upsert customer"),
+            UpsertResult::Updated
+        );
+        assert_eq!(select_customer(&dbms, 100), Some(("Dave".to_string(), 43)));
+
+        // each upsert is recorded as a single operation
+        let operations = TRANSACTION_SESSION.with_borrow(|ts| {
+            ts.get_transaction(&transaction_id)
+                .expect("transaction should exist")
+                .operations
+                .clone()
+        });
+        assert_eq!(operations.len(), 2);
+        assert!(
+            operations
+                .iter()
+                .all(|op| matches!(op, TransactionOp::Upsert { .. }))
+        );
+
+        // nothing is written until commit
+        let oneshot_dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        assert_eq!(select_customer(&oneshot_dbms, 100), None);
+
+        dbms.commit().expect("failed to commit transaction");
+        assert_eq!(
+            select_customer(&oneshot_dbms, 100),
+            Some(("Dave".to_string(), 43))
+        );
+    }
+
+    #[test]
+    fn test_should_return_candid_error_on_primary_key_conflict() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+
+        let error = dbms
+            .insert::<User>(UserInsertRequest {
+                id: Uint32(1),
+                name: Text("Conflicting".to_string()),
+            })
+            .expect_err("should conflict");
+        assert_eq!(
+            CandidIcDbmsError::from(error),
+            CandidIcDbmsError {
+                error_code: error_codes::PRIMARY_KEY_CONFLICT,
+                error: CandidIcDbmsErrorKind::Query(CandidQueryError::PrimaryKeyConflict),
+            }
+        );
+    }
+
+    #[test]
+    fn test_should_update_record_satisfying_check_constraint() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+
+        let filter = Filter::eq("id", Value::Uint32(0.into()));
+        let patch = CustomerUpdateRequest {
+            id: None,
+            name: None,
+            age: Some(35.into()),
+            where_clause: Some(filter.clone()),
+        };
+        let count = dbms
+            .update::<Customer>(UpdateBehavior::Restrict, patch)
+            .expect("failed to update customer");
+        assert_eq!(count, 1);
+
+        let customers = dbms
+            .select(Query::<Customer>::builder().and_where(filter).build())
+            .expect("failed to select customers");
+        assert_eq!(customers[0].age, Some(35.into()));
+    }
+
+    #[test]
+    fn test_should_not_update_record_violating_check_constraint() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+
+        let filter = Filter::eq("id", Value::Uint32(0.into()));
+        let patch = CustomerUpdateRequest {
+            id: None,
+            name: None,
+            age: Some(0.into()),
+            where_clause: Some(filter.clone()),
+        };
+        let result = dbms.update::<Customer>(UpdateBehavior::Restrict, patch);
+        assert!(matches!(
+            result,
+            Err(IcDbmsError::Query(QueryError::CheckConstraintViolation {
+                column: "age",
+                value: Value::Int32(Int32(0)),
+                ..
+            }))
+        ));
+
+        // record should be unchanged
+        let customers = dbms
+            .select(Query::<Customer>::builder().and_where(filter).build())
+            .expect("failed to select customers");
+        assert_eq!(customers[0].age, Some(34.into()));
+    }
+
+    /// Returns the names of the users stored in the table registered as `name`.
+    fn stored_user_names(name: &str) -> Vec<String> {
+        let pages = SCHEMA_REGISTRY
+            .with_borrow(|sr| sr.table_registry_page_by_name(name))
+            .expect("table not found");
+        let registry = TableRegistry::load(pages).expect("failed to load table registry");
+        let mut reader = registry.read::<User>();
+        let mut names = vec![];
+        while let Some(next) = reader.try_next().expect("failed to read table") {
+            names.push(next.record.name.0);
+        }
+        names
+    }
+
+    #[test]
+    fn test_should_clone_table() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+
+        dbms.clone_table::<User>("users_backup")
+            .expect("failed to clone table");
+        assert_eq!(
+            SCHEMA_REGISTRY.with_borrow(|sr| sr.fingerprint_by_name("users_backup")),
+            Some(IcDbmsDatabase::cloned_table_fingerprint::<User>(
+                "users_backup"
+            ))
+        );
+
+        // insert into the original table
+        let new_user = UserInsertRequest {
+            id: Uint32(100),
+            name: Text("Clone Tester".to_string()),
+        };
+        assert!(dbms.insert::<User>(new_user).is_ok());
+
+        // the clone must contain only the original fixtures
+        let fixtures = USERS_FIXTURES
+            .iter()
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(stored_user_names("users_backup"), fixtures);
+
+        // the original contains the new user
+        let users = dbms
+            .select(Query::<User>::builder().all().build())
+            .expect("failed to select users");
+        assert_eq!(users.len(), USERS_FIXTURES.len() + 1);
+
+        // another clone with another name
+        dbms.clone_table::<User>("users_copy")
+            .expect("failed to clone table");
+        assert_eq!(
+            stored_user_names("users_copy").len(),
+            USERS_FIXTURES.len() + 1
+        );
+    }
+
+    #[test]
+    fn test_should_not_clone_table_to_taken_name() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+
+        dbms.clone_table::<User>("users_backup")
+            .expect("failed to clone table");
+        assert!(matches!(
+            dbms.clone_table::<User>("users_backup"),
+            Err(IcDbmsError::Table(TableError::TableAlreadyExists))
+        ));
+        assert!(matches!(
+            dbms.clone_table::<User>(Post::table_name()),
+            Err(IcDbmsError::Table(TableError::TableAlreadyExists))
+        ));
+    }
+
+    #[test]
+    fn test_should_swap_table_with_its_clone() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        dbms.clone_table::<User>("users_backup")
+            .expect("failed to clone table");
+        dbms.insert::<User>(UserInsertRequest {
+            id: Uint32(100),
+            name: Text("Clone Tester".to_string()),
+        })
+        .expect("failed to insert user");
+        let quota = TableQuota {
+            max_pages: None,
+            max_rows: Some(1_000),
+            max_bytes: None,
+        };
+        SCHEMA_REGISTRY
+            .with_borrow_mut(|sr| sr.set_table_quota(User::fingerprint(), quota))
+            .expect("failed to set quota");
+
+        // restore the clone
+        dbms.swap_with_clone::<User>("users_backup")
+            .expect("failed to swap tables");
+        let users = dbms
+            .select(Query::<User>::builder().all().build())
+            .expect("failed to select users");
+        assert_eq!(users.len(), USERS_FIXTURES.len());
+        let clone_fingerprint = IcDbmsDatabase::cloned_table_fingerprint::<User>("users_backup");
+        SCHEMA_REGISTRY.with_borrow(|sr| {
+            assert_eq!(sr.table_quota(User::fingerprint()), None);
+            assert_eq!(sr.table_quota(clone_fingerprint), Some(quota));
         });
 
-        // select by pk
-        let dbms = IcDbmsDatabase::from_transaction(TestDatabaseSchema, transaction_id);
-        let query = Query::<User>::builder()
-            .and_where(Filter::eq("id", Value::Uint32(999.into())))
-            .build();
-        let users = dbms.select(query).expect("failed to select users");
+        // swapping them again restores the original table
+        dbms.swap_with_clone::<User>("users_backup")
+            .expect("failed to swap tables");
+        let users = dbms
+            .select(Query::<User>::builder().all().build())
+            .expect("failed to select users");
+        assert_eq!(users.len(), USERS_FIXTURES.len() + 1);
+        assert_eq!(
+            SCHEMA_REGISTRY.with_borrow(|sr| sr.table_quota(User::fingerprint())),
+            Some(quota)
+        );
+
+        assert!(matches!(
+            dbms.swap_with_clone::<User>("users_copy"),
+            Err(IcDbmsError::Table(TableError::TableNotFound))
+        ));
+    }
+
+    #[cfg(feature = "indexes")]
+    #[test]
+    fn test_should_clone_table_indexes() {
+        load_fixtures();
+        SCHEMA_REGISTRY
+            .with_borrow_mut(|sr| sr.create_index::<User>("name"))
+            .expect("failed to create index");
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        dbms.clone_table::<User>("users_backup")
+            .expect("failed to clone table");
+        dbms.delete::<User>(
+            DeleteBehavior::Cascade,
+            Some(Filter::eq(
+                "name",
+                Value::Text(Text(USERS_FIXTURES[0].to_string())),
+            )),
+        )
+        .expect("failed to delete user");
+
+        // the indexed query on the restored clone finds the deleted user
+        dbms.swap_with_clone::<User>("users_backup")
+            .expect("failed to swap tables");
+        let users = dbms
+            .select(
+                Query::<User>::builder()
+                    .all()
+                    .and_where(Filter::eq(
+                        "name",
+                        Value::Text(Text(USERS_FIXTURES[0].to_string())),
+                    ))
+                    .build(),
+            )
+            .expect("failed to select users");
+        assert_eq!(users.len(), 1);
+        let plan = dbms
+            .explain(
+                &Query::<User>::builder()
+                    .all()
+                    .and_where(Filter::eq("name", Value::Text(Text(String::new()))))
+                    .build(),
+            )
+            .expect("failed to explain query");
+        assert!(matches!(plan.root, QueryPlanNode::IndexScan { .. }));
+    }
+
+    #[test]
+    fn test_should_rename_table() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+
+        dbms.rename_table::<User>("members")
+            .expect("failed to rename table");
+
+        // the records are readable through the new name
+        let pages = SCHEMA_REGISTRY
+            .with_borrow(|sr| sr.table_registry_page_by_name("members"))
+            .expect("table not found by the new name");
+        let registry = TableRegistry::load(pages).expect("failed to load table registry");
+        let mut reader = registry.read::<User>();
+        let mut names = vec![];
+        while let Some(next) = reader.try_next().expect("failed to read table") {
+            names.push(next.record.name.0);
+        }
+        assert_eq!(
+            names,
+            USERS_FIXTURES
+                .iter()
+                .map(|name| name.to_string())
+                .collect::<Vec<_>>()
+        );
+        assert!(
+            SCHEMA_REGISTRY
+                .with_borrow(|sr| sr.table_registry_page_by_name("users"))
+                .is_none()
+        );
+
+        let users = dbms
+            .select::<User>(Query::builder().all().build())
+            .expect("failed to select users");
+        assert_eq!(users.len(), USERS_FIXTURES.len());
+    }
+
+    #[test]
+    fn test_should_not_rename_table_to_taken_name() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+
+        assert!(matches!(
+            dbms.rename_table::<User>(Post::table_name()),
+            Err(IcDbmsError::Table(TableError::TableAlreadyExists))
+        ));
+        assert!(
+            SCHEMA_REGISTRY
+                .with_borrow(|sr| sr.table_registry_page_by_name("users"))
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn test_should_not_swap_tables_with_different_schema() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+
+        assert!(matches!(
+            dbms.swap_tables::<User, Post>(),
+            Err(IcDbmsError::Table(TableError::SchemaMismatch))
+        ));
+    }
+
+    #[test]
+    fn test_should_select_with_column_comparison_filter() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+
+        let bookings = dbms
+            .select(
+                Query::<Booking>::builder()
+                    .column_le_where("start_date", "end_date")
+                    .build(),
+            )
+            .expect("failed to select bookings");
+        let ids = bookings
+            .iter()
+            .map(|booking| booking.id.expect("should have id").0)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![0, 1, 2]);
+
+        let invalid_bookings = dbms
+            .select(
+                Query::<Booking>::builder()
+                    .and_where(Filter::column_le("start_date", "end_date").not())
+                    .build(),
+            )
+            .expect("failed to select bookings");
+        assert_eq!(invalid_bookings.len(), 1);
+        assert_eq!(invalid_bookings[0].id, Some(Uint32(3)));
+    }
+
+    #[test]
+    fn test_should_select_one() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+
+        let user = dbms
+            .select_one(
+                Query::<User>::builder()
+                    .and_where(Filter::eq("id", Value::Uint32(Uint32(1))))
+                    .build(),
+            )
+            .expect("failed to select user");
+        assert_eq!(user.name, Some(Text(USERS_FIXTURES[1].to_string())));
+
+        let not_found = dbms.select_one(
+            Query::<User>::builder()
+                .and_where(Filter::eq("id", Value::Uint32(Uint32(1000))))
+                .build(),
+        );
+        assert!(matches!(
+            not_found,
+            Err(IcDbmsError::Query(QueryError::RecordNotFound))
+        ));
+
+        let too_many = dbms.select_one(
+            Query::<User>::builder()
+                .and_where(Filter::lt("id", Value::Uint32(Uint32(2))))
+                .build(),
+        );
+        assert!(matches!(
+            too_many,
+            Err(IcDbmsError::Query(QueryError::TooManyResults(2)))
+        ));
+    }
+
+    #[test]
+    fn test_should_select_opt() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+
+        let user = dbms
+            .select_opt(
+                Query::<User>::builder()
+                    .and_where(Filter::eq("id", Value::Uint32(Uint32(1))))
+                    .build(),
+            )
+            .expect("failed to select user")
+            .expect("user should exist");
+        assert_eq!(user.id, Some(Uint32(1)));
+
+        let not_found = dbms
+            .select_opt(
+                Query::<User>::builder()
+                    .and_where(Filter::eq("id", Value::Uint32(Uint32(1000))))
+                    .build(),
+            )
+            .expect("failed to select user");
+        assert!(not_found.is_none());
+
+        let too_many = dbms.select_opt(
+            Query::<User>::builder()
+                .and_where(Filter::lt("id", Value::Uint32(Uint32(2))))
+                .build(),
+        );
+        assert!(matches!(
+            too_many,
+            Err(IcDbmsError::Query(QueryError::TooManyResults(2)))
+        ));
+    }
+
+    #[test]
+    fn test_should_validate_all_schemas() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
 
-        assert_eq!(users.len(), 1);
-        let user = &users[0];
-        assert_eq!(user.id.expect("should have id").0, 999);
+        let mismatches = dbms
+            .validate_all_schemas()
+            .expect("failed to validate schemas");
+        assert!(mismatches.is_empty());
+    }
+
+    #[test]
+    fn test_should_report_healthy_database() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+
+        let health = dbms.health_check();
+        assert!(health.is_healthy(), "{:?}", health.issues);
+        assert!(health.schema_registry_ok);
+        assert!(health.acl_ok);
         assert_eq!(
-            user.name.as_ref().expect("should have name").0,
-            "OverlayUser"
+            health.per_table_ok.len(),
+            TestDatabaseSchema.table_fingerprints().len()
         );
+        assert!(health.per_table_ok.iter().all(|table| table.integrity_ok));
+
+        let users = health
+            .per_table_ok
+            .iter()
+            .find(|table| table.table_name == User::table_name())
+            .expect("users not checked");
+        assert!(users.row_count > 0);
+        assert_eq!(users.page_count, 1);
+        assert_eq!(users.free_segments, 0);
     }
 
     #[test]
-    fn test_should_select_users_with_offset_and_limit() {
+    fn test_should_report_corrupted_table() {
         load_fixtures();
         let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
-        let query = Query::<User>::builder().offset(2).limit(3).build();
-        let users = dbms.select(query).expect("failed to select users");
 
-        assert_eq!(users.len(), 3);
-        // check if correct users are loaded
-        for (i, user) in users.iter().enumerate() {
-            let expected_index = i + 2;
-            assert_eq!(user.id.expect("should have id").0 as usize, expected_index);
+        // flip the first data byte of the first user
+        let pages = SCHEMA_REGISTRY
+            .with_borrow(|sr| sr.table_registry_page::<User>())
+            .expect("users not registered");
+        let registry = TableRegistry::load(pages).expect("failed to load table registry");
+        let first = registry
+            .read::<User>()
+            .try_next()
+            .expect("failed to read user")
+            .expect("no users");
+        let data_offset = first.offset + 3;
+        MEMORY_MANAGER.with_borrow_mut(|mm| {
+            let mut byte = [0u8; 1];
+            mm.read_at_raw(first.page, data_offset, &mut byte)
+                .expect("failed to read byte");
+            mm.write_at(first.page, data_offset, &Boolean(byte[0] == 0))
+                .expect("failed to corrupt byte");
+        });
+
+        let health = dbms.health_check();
+        assert!(!health.is_healthy());
+        for table in &health.per_table_ok {
             assert_eq!(
-                user.name.as_ref().expect("should have name").0,
-                USERS_FIXTURES[expected_index]
+                table.integrity_ok,
+                table.table_name != User::table_name(),
+                "{}",
+                table.table_name
             );
         }
+        assert_eq!(
+            health.issues,
+            vec![format!(
+                "table {} has corrupted records",
+                User::table_name()
+            )]
+        );
     }
 
     #[test]
-    fn test_should_select_users_with_offset_and_filter() {
+    fn test_should_export_candid_of_registered_tables() {
         load_fixtures();
         let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
-        let query = Query::<User>::builder()
-            .offset(1)
-            .and_where(Filter::gt("id", Value::Uint32(4.into())))
-            .build();
-        let users = dbms.select(query).expect("failed to select users");
 
-        assert_eq!(users.len(), 4);
-        // check if correct users are loaded
-        for (i, user) in users.iter().enumerate() {
-            let expected_index = i + 6;
-            assert_eq!(user.id.expect("should have id").0 as usize, expected_index);
-            assert_eq!(
-                user.name.as_ref().expect("should have name").0,
-                USERS_FIXTURES[expected_index]
-            );
-        }
+        let interface = dbms.export_candid();
+        assert!(interface.contains("get_users : (opt CandidFilter) -> (vec CandidRow) query;"));
+        assert!(interface.contains("insert_posts : (CandidPostsInsert) -> (Result);"));
+        assert!(
+            interface
+                .contains("type CandidRow = vec record { column : text; value : CandidValue };")
+        );
     }
 
     #[test]
-    fn test_should_select_post_with_relation() {
+    fn test_should_count_records_matching_filter() {
         load_fixtures();
         let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
-        let query = Query::<Post>::builder()
-            .all()
-            .with(User::table_name())
-            .build();
-        let posts = dbms.select(query).expect("failed to select posts");
-        assert_eq!(posts.len(), POSTS_FIXTURES.len());
 
-        for (id, post) in posts.into_iter().enumerate() {
-            let (expected_title, expected_content, expected_user_id) = &POSTS_FIXTURES[id];
-            assert_eq!(post.id.expect("should have id").0 as usize, id);
-            assert_eq!(
-                post.title.as_ref().expect("should have title").0,
-                *expected_title
-            );
-            assert_eq!(
-                post.content.as_ref().expect("should have content").0,
-                *expected_content
-            );
-            let user_query = Query::<User>::builder()
-                .and_where(Filter::eq("id", Value::Uint32((*expected_user_id).into())))
-                .build();
-            let author = dbms
-                .select(user_query)
-                .expect("failed to load user")
-                .pop()
-                .expect("should have user");
-            assert_eq!(post.user.expect("should have loaded user"), author);
+        let filters = [
+            None,
+            Some(Filter::eq("user_id", Value::Uint32(1u32.into()))),
+            Some(Filter::gt("id", Value::Uint32(2u32.into()))),
+            Some(Filter::eq("user_id", Value::Uint32(1_000u32.into()))),
+        ];
+        for filter in filters {
+            let query = Query::<Post>::builder().filter(filter.clone());
+            let expected = dbms
+                .select(query.clone().build())
+                .expect("failed to select posts")
+                .len() as u64;
+            let count = dbms
+                .count_query(query.limit(1).offset(1).count_only())
+                .expect("failed to count posts");
+            assert_eq!(count, expected, "count mismatch for filter {filter:?}");
         }
     }
 
     #[test]
-    fn test_should_fail_loading_unexisting_column_on_select() {
-        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
-        let query = Query::<User>::builder().field("unexisting_column").build();
-        let result = dbms.select(query);
-        assert!(result.is_err());
+    fn test_should_count_records_within_transaction() {
+        load_fixtures();
+
+        let transaction_id =
+            TRANSACTION_SESSION.with_borrow_mut(|ts| ts.begin_transaction(Principal::anonymous()));
+        let dbms = IcDbmsDatabase::from_transaction(TestDatabaseSchema, transaction_id);
+        let count = || {
+            dbms.count_query(Query::<User>::builder().count_only())
+                .expect("failed to count users")
+        };
+        assert_eq!(count(), USERS_FIXTURES.len() as u64);
+
+        dbms.insert::<User>(UserInsertRequest {
+            id: Uint32(100),
+            name: Text("TxUser".to_string()),
+        })
+        .expect("failed to insert user");
+        assert_eq!(count(), USERS_FIXTURES.len() as u64 + 1);
+
+        dbms.delete::<User>(
+            DeleteBehavior::Cascade,
+            Some(Filter::lt("id", Value::Uint32(2u32.into()))),
+        )
+        .expect("failed to delete users");
+        assert_eq!(count(), USERS_FIXTURES.len() as u64 - 1);
+        assert_eq!(
+            count(),
+            dbms.select(Query::<User>::builder().all().build())
+                .expect("failed to select users")
+                .len() as u64
+        );
+
+        // outside of the transaction nothing changed
+        let oneshot_dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        assert_eq!(
+            oneshot_dbms
+                .count_query(Query::<User>::builder().count_only())
+                .expect("failed to count users"),
+            USERS_FIXTURES.len() as u64
+        );
     }
 
     #[test]
-    fn test_should_select_queried_fields() {
+    fn test_should_count_empty_table() {
+        load_fixtures();
         let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        dbms.delete::<Customer>(DeleteBehavior::Restrict, None)
+            .expect("failed to delete customers");
 
-        let record_values = User::columns()
-            .iter()
-            .cloned()
-            .zip(vec![
-                Value::Uint32(1.into()),
-                Value::Text("Alice".to_string().into()),
-            ])
-            .collect::<Vec<(ColumnDef, Value)>>();
+        assert_eq!(
+            dbms.aggregate(Query::<Customer>::builder().count())
+                .expect("failed to count customers"),
+            Value::Uint64(0.into())
+        );
+        assert_eq!(
+            dbms.aggregate(Query::<Customer>::builder().sum("age"))
+                .expect("failed to sum customers age"),
+            Value::Null
+        );
+    }
 
-        let query: Query<User> = Query::builder().field("name").build();
-        let selected_fields = dbms
-            .select_queried_fields::<User>(record_values, &query)
-            .expect("failed to select queried fields");
-        let user_fields = selected_fields
-            .into_iter()
-            .find(|(table_name, _)| *table_name == ValuesSource::This)
-            .map(|(_, cols)| cols)
-            .unwrap_or_default();
+    #[test]
+    fn test_should_aggregate_customers_age() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        let ages = CUSTOMERS_FIXTURES
+            .iter()
+            .map(|(_, age)| *age as i64)
+            .collect::<Vec<_>>();
+        let sum = ages.iter().sum::<i64>();
 
-        assert_eq!(user_fields.len(), 1);
-        assert_eq!(user_fields[0].0.name, "name");
-        assert_eq!(user_fields[0].1, Value::Text("Alice".to_string().into()));
+        assert_eq!(
+            dbms.aggregate(Query::<Customer>::builder().count())
+                .expect("failed to count customers"),
+            Value::Uint64((ages.len() as u64).into())
+        );
+        assert_eq!(
+            dbms.aggregate(Query::<Customer>::builder().sum("age"))
+                .expect("failed to sum customers age"),
+            Value::Int64(sum.into())
+        );
+        // (34 + 27 + 61) / 3 = 40.67, truncated
+        assert_eq!(
+            dbms.aggregate(Query::<Customer>::builder().avg("age"))
+                .expect("failed to average customers age"),
+            Value::Int64((sum / ages.len() as i64).into())
+        );
+        assert_eq!(
+            dbms.aggregate(Query::<Customer>::builder().min("age"))
+                .expect("failed to get customers min age"),
+            Value::Int32(27.into())
+        );
+        assert_eq!(
+            dbms.aggregate(Query::<Customer>::builder().max("age"))
+                .expect("failed to get customers max age"),
+            Value::Int32(61.into())
+        );
+        assert_eq!(
+            dbms.aggregate(
+                Query::<Customer>::builder()
+                    .filter(Some(Filter::gt("age", Value::Int32(30.into()))))
+                    .sum("age")
+            )
+            .expect("failed to sum customers age"),
+            Value::Int64(95.into())
+        );
     }
 
     #[test]
-    fn test_should_select_queried_fields_with_relations() {
+    fn test_should_not_aggregate_unknown_column() {
         load_fixtures();
         let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
 
-        let record_values = Post::columns()
-            .iter()
-            .cloned()
-            .zip(vec![
-                Value::Uint32(1.into()),
-                Value::Text("Title".to_string().into()),
-                Value::Text("Content".to_string().into()),
-                Value::Uint32(2.into()), // author_id
-            ])
-            .collect::<Vec<(ColumnDef, Value)>>();
+        assert!(matches!(
+            dbms.aggregate(Query::<Customer>::builder().sum("height")),
+            Err(IcDbmsError::Query(QueryError::UnknownColumn(column))) if column == "height"
+        ));
+        assert!(matches!(
+            dbms.aggregate(Query::<Customer>::builder().all().build()),
+            Err(IcDbmsError::Query(QueryError::InvalidQuery(_)))
+        ));
+    }
 
-        let query: Query<Post> = Query::builder()
-            .field("title")
-            .with(User::table_name())
-            .build();
-        let selected_fields = dbms
-            .select_queried_fields::<Post>(record_values, &query)
-            .expect("failed to select queried fields");
+    #[test]
+    fn test_should_count_posts_per_user() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
 
-        // check post fields
-        let post_fields = selected_fields
-            .iter()
-            .find(|(table_name, _)| *table_name == ValuesSource::This)
-            .map(|(_, cols)| cols)
-            .cloned()
-            .unwrap_or_default();
-        assert_eq!(post_fields.len(), 1);
-        assert_eq!(post_fields[0].0.name, "title");
-        assert_eq!(post_fields[0].1, Value::Text("Title".to_string().into()));
+        let mut expected: Vec<(u32, u64)> = vec![];
+        for (_, _, user_id) in POSTS_FIXTURES {
+            match expected.iter_mut().find(|(id, _)| id == user_id) {
+                Some((_, count)) => *count += 1,
+                None => expected.push((*user_id, 1)),
+            }
+        }
 
-        // check user fields
-        let user_fields = selected_fields
+        let groups = dbms
+            .select_grouped(Query::<Post>::builder().group_by("user_id").count())
+            .expect("failed to count posts per user");
+        let counts = groups
             .iter()
-            .find(|(table_name, _)| {
-                *table_name
-                    == ValuesSource::Foreign {
-                        table: User::table_name(),
-                        column: "user_id",
-                    }
+            .map(|group| {
+                assert_eq!(group.key_values.len(), 1);
+                assert_eq!(group.key_values[0].0.name, "user_id");
+                let Value::Uint32(user_id) = &group.key_values[0].1 else {
+                    panic!("user_id should be a Uint32");
+                };
+                let Value::Uint64(count) = &group.aggregate else {
+                    panic!("count should be a Uint64");
+                };
+                (user_id.0, count.0)
             })
-            .map(|(_, cols)| cols)
-            .cloned()
-            .unwrap_or_default();
+            .collect::<Vec<_>>();
+        assert_eq!(counts, expected);
+    }
 
-        let expected_user = USERS_FIXTURES[2]; // author_id = 2
+    #[test]
+    fn test_should_sum_grades_per_course_ordered_by_aggregate() {
+        let table_pages = SCHEMA_REGISTRY
+            .with_borrow_mut(|sr| sr.register_table::<Enrollment>())
+            .expect("failed to register table");
+        let mut registry = TableRegistry::load(table_pages).expect("failed to load table");
+        registry
+            .insert_batch(
+                [
+                    (1, "math", 30),
+                    (2, "math", 25),
+                    (1, "art", 20),
+                    (3, "art", 28),
+                    (2, "history", 18),
+                ]
+                .into_iter()
+                .map(|(student_id, course, grade)| Enrollment {
+                    student_id: Uint32(student_id),
+                    course: Text(course.to_string()),
+                    grade: Uint32(grade),
+                }),
+            )
+            .expect("failed to insert enrollments");
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        let sums = |query: Query<Enrollment>| {
+            dbms.select_grouped(query)
+                .expect("failed to sum grades per course")
+                .into_iter()
+                .map(|group| {
+                    let Value::Text(course) = group.value("course").cloned().unwrap() else {
+                        panic!("course should be a Text");
+                    };
+                    (course.0, group.aggregate)
+                })
+                .collect::<Vec<_>>()
+        };
+        let sum = |course: &str, grades: u64| (course.to_string(), Value::Uint64(grades.into()));
 
-        assert_eq!(user_fields.len(), 2);
-        assert_eq!(user_fields[0].0.name, "id");
-        assert_eq!(user_fields[0].1, Value::Uint32(2.into()));
-        assert_eq!(user_fields[1].0.name, "name");
         assert_eq!(
-            user_fields[1].1,
-            Value::Text(expected_user.to_string().into())
+            sums(Query::builder().group_by("course").sum("grade")),
+            vec![sum("math", 55), sum("art", 48), sum("history", 18)]
+        );
+        assert_eq!(
+            sums(
+                Query::builder()
+                    .group_by("course")
+                    .order_by_asc(GroupedResult::AGGREGATE_COLUMN)
+                    .sum("grade")
+            ),
+            vec![sum("history", 18), sum("art", 48), sum("math", 55)]
+        );
+        assert_eq!(
+            sums(
+                Query::builder()
+                    .group_by("course")
+                    .order_by_desc(GroupedResult::AGGREGATE_COLUMN)
+                    .limit(2)
+                    .sum("grade")
+            ),
+            vec![sum("math", 55), sum("art", 48)]
+        );
+        assert_eq!(
+            sums(
+                Query::builder()
+                    .group_by("course")
+                    .order_by_asc("course")
+                    .sum("grade")
+            ),
+            vec![sum("art", 48), sum("history", 18), sum("math", 55)]
         );
     }
 
     #[test]
-    fn test_should_select_with_two_fk_on_the_same_table() {
+    fn test_should_not_select_grouped_without_group_by() {
         load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
 
-        let query: Query<Message> = Query::builder()
-            .all()
-            .and_where(Filter::Eq("id", Value::Uint32(0.into())))
-            .with("users")
-            .build();
+        assert!(matches!(
+            dbms.select_grouped(Query::<Post>::builder().count()),
+            Err(IcDbmsError::Query(QueryError::InvalidQuery(_)))
+        ));
+        assert!(matches!(
+            dbms.select_grouped(Query::<Post>::builder().group_by("author").count()),
+            Err(IcDbmsError::Query(QueryError::UnknownColumn(column))) if column == "author"
+        ));
+        assert!(matches!(
+            dbms.select_grouped(
+                Query::<Post>::builder()
+                    .group_by("user_id")
+                    .order_by_asc("title")
+                    .count()
+            ),
+            Err(IcDbmsError::Query(QueryError::UnknownColumn(column))) if column == "title"
+        ));
+    }
 
+    #[test]
+    fn test_should_import_database_json() {
+        load_fixtures();
         let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
-        let messages = dbms.select(query).expect("failed to select messages");
-        assert_eq!(messages.len(), 1);
-        let message = &messages[0];
-        assert_eq!(message.id.expect("should have id").0, 0);
-        assert_eq!(
-            message
-                .sender
-                .as_ref()
-                .expect("should have sender")
-                .name
-                .as_ref()
-                .unwrap()
-                .0,
-            "Alice"
-        );
-        assert_eq!(
-            message
-                .recipient
-                .as_ref()
-                .expect("should have recipient")
-                .name
-                .as_ref()
-                .unwrap()
-                .0,
-            "Bob"
+
+        // export users and posts
+        let users = dbms
+            .select::<User>(Query::builder().all().build())
+            .expect("failed to select users")
+            .into_iter()
+            .map(|user| {
+                serde_json::json!({
+                    "id": user.id.expect("should have id").0,
+                    "name": user.name.expect("should have name").0,
+                })
+            })
+            .collect::<Vec<_>>();
+        let posts = dbms
+            .select::<Post>(Query::builder().all().with(User::table_name()).build())
+            .expect("failed to select posts")
+            .into_iter()
+            .map(|post| {
+                serde_json::json!({
+                    "id": post.id.expect("should have id").0,
+                    "title": post.title.expect("should have title").0,
+                    "content": post.content.expect("should have content").0,
+                    "user_id": post.user.expect("should have user").id.expect("should have id").0,
+                })
+            })
+            .collect::<Vec<_>>();
+        // posts come first, to check they're imported after the users they reference
+        let export = serde_json::json!({ "posts": posts, "users": users }).to_string();
+
+        // wipe the database
+        dbms.delete::<User>(DeleteBehavior::Cascade, None)
+            .expect("failed to delete users");
+        assert!(
+            dbms.select::<Post>(Query::builder().all().build())
+                .expect("failed to select posts")
+                .is_empty()
+        );
+
+        let result = dbms
+            .import_database_json(&export)
+            .expect("failed to import database");
+        assert_eq!(
+            result,
+            ImportResult {
+                tables_imported: 2,
+                rows_imported: (USERS_FIXTURES.len() + POSTS_FIXTURES.len()) as u64,
+                warnings: vec![],
+            }
         );
+
+        let posts = dbms
+            .select::<Post>(Query::builder().all().with(User::table_name()).build())
+            .expect("failed to select posts");
+        assert_eq!(posts.len(), POSTS_FIXTURES.len());
+        for (id, post) in posts.into_iter().enumerate() {
+            let (expected_title, _, expected_user_id) = &POSTS_FIXTURES[id];
+            assert_eq!(post.title.expect("should have title").0, *expected_title);
+            let author = post.user.expect("should have user");
+            assert_eq!(author.id.expect("should have id").0, *expected_user_id);
+            assert_eq!(
+                author.name.expect("should have name").0,
+                USERS_FIXTURES[*expected_user_id as usize]
+            );
+        }
     }
 
     #[test]
-    fn test_should_select_users_sorted_by_name_descending() {
+    fn test_should_skip_invalid_rows_on_import() {
         load_fixtures();
         let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
-        let query = Query::<User>::builder().all().order_by_desc("name").build();
-        let users = dbms.select(query).expect("failed to select users");
 
-        let mut sorted_usernames = USERS_FIXTURES.to_vec();
-        sorted_usernames.sort_by(|a, b| b.cmp(a)); // descending
-
-        assert_eq!(users.len(), USERS_FIXTURES.len());
-        // check if all users all loaded in sorted order
-        for (i, user) in users.iter().enumerate() {
-            assert_eq!(
-                user.name.as_ref().expect("should have name").0,
-                sorted_usernames[i]
-            );
-        }
+        let export = serde_json::json!({
+            "users": [{ "id": 100, "name": "Mallory" }, { "id": 0, "name": "Alice again" }],
+            "posts": [{ "id": 100, "title": "Orphan", "content": "No author", "user_id": 999 }],
+            "comments": [],
+        })
+        .to_string();
+
+        let result = dbms
+            .import_database_json(&export)
+            .expect("failed to import database");
+        assert_eq!(result.tables_imported, 2);
+        assert_eq!(result.rows_imported, 1);
+        assert_eq!(result.warnings.len(), 3);
+
+        let users = dbms
+            .select::<User>(
+                Query::builder()
+                    .and_where(Filter::eq("id", Value::Uint32(100.into())))
+                    .build(),
+            )
+            .expect("failed to select users");
+        assert_eq!(users.len(), 1);
     }
 
     #[test]
-    fn test_should_fail_loading_unexisting_relation() {
+    fn test_should_not_import_malformed_json() {
+        load_fixtures();
         let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
 
-        let record_values = Post::columns()
-            .iter()
-            .cloned()
-            .zip(vec![
-                Value::Uint32(1.into()),
-                Value::Text("Title".to_string().into()),
-                Value::Text("Content".to_string().into()),
-                Value::Uint32(2.into()), // author_id
-            ])
-            .collect::<Vec<(ColumnDef, Value)>>();
+        assert!(matches!(
+            dbms.import_database_json("{ \"users\": "),
+            Err(IcDbmsError::Query(QueryError::SerializationError(_)))
+        ));
+    }
 
-        let query: Query<Post> = Query::builder()
-            .field("title")
-            .with("unexisting_relation")
-            .build();
-        let result = dbms.select_queried_fields::<Post>(record_values, &query);
-        assert!(result.is_err());
+    #[test]
+    fn test_should_not_commit_transaction_of_another_principal() {
+        load_fixtures();
+        let alice = Principal::from_text("ryjl3-tyaaa-aaaaa-aaaba-cai").unwrap();
+        let bob = Principal::from_text("mxzaz-hqaaa-aaaar-qaada-cai").unwrap();
+
+        let transaction_id = TRANSACTION_SESSION.with_borrow_mut(|ts| ts.begin_transaction(alice));
+        let mut dbms = IcDbmsDatabase::from_transaction(TestDatabaseSchema, transaction_id.clone());
+        dbms.insert::<User>(UserInsertRequest {
+            id: Uint32(100),
+            name: Text("Granted User".to_string()),
+        })
+        .expect("failed to insert user");
+
+        // bob can neither commit nor rollback alice's transaction
+        set_caller(bob);
+        assert!(matches!(
+            dbms.commit(),
+            Err(IcDbmsError::Transaction(TransactionError::Unauthorized { expected, caller }))
+                if expected == alice && caller == bob
+        ));
+        assert!(matches!(
+            dbms.rollback(),
+            Err(IcDbmsError::Transaction(
+                TransactionError::Unauthorized { .. }
+            ))
+        ));
+
+        // grant access to bob, who can now commit
+        TRANSACTION_SESSION
+            .with_borrow_mut(|ts| ts.grant_access(&transaction_id, bob))
+            .expect("failed to grant access");
+        dbms.commit().expect("failed to commit transaction");
+
+        let users = IcDbmsDatabase::oneshot(TestDatabaseSchema)
+            .select(
+                Query::<User>::builder()
+                    .and_where(Filter::eq("id", Value::Uint32(Uint32(100))))
+                    .build(),
+            )
+            .expect("failed to select users");
+        assert_eq!(users.len(), 1);
     }
 
     #[test]
-    fn test_should_get_whether_record_matches_filter() {
-        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+    fn test_should_not_apply_any_operation_if_commit_validation_fails() {
+        load_fixtures();
 
-        let record_values = User::columns()
-            .iter()
-            .cloned()
-            .zip(vec![
-                Value::Uint32(1.into()),
-                Value::Text("Alice".to_string().into()),
-            ])
-            .collect::<Vec<(ColumnDef, Value)>>();
-        let filter = Filter::eq("name", Value::Text("Alice".to_string().into()));
+        let transaction_id =
+            TRANSACTION_SESSION.with_borrow_mut(|ts| ts.begin_transaction(Principal::anonymous()));
+        let mut dbms = IcDbmsDatabase::from_transaction(TestDatabaseSchema, transaction_id);
+        for id in [100, 101] {
+            dbms.insert::<User>(UserInsertRequest {
+                id: Uint32(id),
+                name: Text(format!("TxUser{id}")),
+            })
+            .expect("failed to insert user");
+        }
 
-        let matches = dbms
-            .record_matches_filter(&record_values, &filter)
-            .expect("failed to match");
-        assert!(matches);
+        // a conflicting user is inserted outside of the transaction
+        let oneshot_dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        oneshot_dbms
+            .insert::<User>(UserInsertRequest {
+                id: Uint32(101),
+                name: Text("Conflicting".to_string()),
+            })
+            .expect("failed to insert user");
 
-        let non_matching_filter = Filter::eq("name", Value::Text("Bob".to_string().into()));
-        let non_matches = dbms
-            .record_matches_filter(&record_values, &non_matching_filter)
-            .expect("failed to match");
-        assert!(!non_matches);
+        assert!(matches!(
+            dbms.commit(),
+            Err(IcDbmsError::Query(QueryError::PrimaryKeyConflict))
+        ));
+
+        // the preceding valid insert must not have been applied
+        let users = oneshot_dbms
+            .select(
+                Query::<User>::builder()
+                    .and_where(Filter::eq("id", Value::Uint32(Uint32(100))))
+                    .build(),
+            )
+            .expect("failed to select users");
+        assert!(users.is_empty());
     }
 
     #[test]
-    fn test_should_load_table_registry() {
-        init_user_table();
+    fn test_should_restrict_delete_within_transaction() {
+        load_fixtures();
 
-        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
-        let table_registry = dbms.load_table_registry::<User>();
-        assert!(table_registry.is_ok());
+        let transaction_id =
+            TRANSACTION_SESSION.with_borrow_mut(|ts| ts.begin_transaction(Principal::anonymous()));
+        let dbms = IcDbmsDatabase::from_transaction(TestDatabaseSchema, transaction_id);
+
+        // user 1 is referenced by some posts
+        let result = dbms.delete::<User>(
+            DeleteBehavior::Restrict,
+            Some(Filter::eq("id", Value::Uint32(Uint32(1)))),
+        );
+        assert!(matches!(
+            result,
+            Err(IcDbmsError::Query(
+                QueryError::ForeignKeyConstraintViolation { .. }
+            ))
+        ));
+
+        // the referencing posts are still visible within the transaction
+        let posts = dbms
+            .select(
+                Query::<Post>::builder()
+                    .and_where(Filter::eq("user_id", Value::Uint32(Uint32(1))))
+                    .build(),
+            )
+            .expect("failed to select posts");
+        assert!(!posts.is_empty());
     }
 
     #[test]
-    fn test_should_insert_record_without_transaction() {
+    fn test_should_insert_broken_foreign_key_in_lenient_mode() {
         load_fixtures();
+        let post = |id: u32| PostInsertRequest {
+            id: Uint32(id),
+            title: Text("Orphan".to_string()),
+            content: Text("Imported before its author".to_string()),
+            user_id: Uint32(9999),
+        };
 
         let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
-        let new_user = UserInsertRequest {
-            id: Uint32(100u32),
-            name: Text("NewUser".to_string()),
+        assert!(matches!(
+            dbms.insert::<Post>(post(10_000)),
+            Err(IcDbmsError::Query(
+                QueryError::BrokenForeignKeyReference { .. }
+            ))
+        ));
+
+        let ((), warnings) = dbms
+            .insert_lenient::<Post>(post(10_000))
+            .expect("failed to insert in lenient mode");
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].kind, IntegrityWarningKind::ForeignKey);
+        assert_eq!(warnings[0].column, "user_id");
+
+        let lenient = IcDbmsDatabase::oneshot(TestDatabaseSchema)
+            .with_validation_mode(ValidationMode::Lenient);
+        lenient
+            .insert::<Post>(post(10_001))
+            .expect("failed to insert in lenient mode");
+
+        let posts = dbms
+            .select::<Post>(
+                Query::builder()
+                    .all()
+                    .and_where(Filter::ge("id", Value::Uint32(Uint32(10_000))))
+                    .build(),
+            )
+            .expect("failed to select posts");
+        assert_eq!(posts.len(), 2);
+        assert!(
+            posts
+                .iter()
+                .all(|post| post.title == Some(Text("Orphan".to_string())))
+        );
+    }
+
+    /// Begins a transaction upserting the user with the given id through a conditional operation.
+    fn upsert_user_in_transaction(id: u32, name: &str) -> IcDbmsDatabase {
+        let filter = Filter::eq("id", Value::Uint32(Uint32(id)));
+        let update = TransactionOp::Update {
+            table: User::table_name(),
+            behaviour: UpdateBehavior::Restrict,
+            patch: vec![(User::columns()[1], Value::Text(Text(name.to_string())))],
+            filter: Some(filter.clone()),
+        };
+        let insert = TransactionOp::Insert {
+            table: User::table_name(),
+            values: UserInsertRequest {
+                id: Uint32(id),
+                name: Text(name.to_string()),
+            }
+            .into_values(),
         };
 
-        let result = dbms.insert::<User>(new_user);
-        assert!(result.is_ok());
+        let transaction_id =
+            TRANSACTION_SESSION.with_borrow_mut(|ts| ts.begin_transaction(Principal::anonymous()));
+        TRANSACTION_SESSION
+            .with_borrow_mut(|ts| {
+                ts.get_transaction_mut(&transaction_id)?.if_exists(
+                    User::table_name(),
+                    filter,
+                    vec![update],
+                    vec![insert],
+                )
+            })
+            .expect("failed to add conditional operation");
 
-        // find user
-        let query = Query::<User>::builder()
-            .and_where(Filter::eq("id", Value::Uint32(100u32.into())))
-            .build();
-        let users = dbms.select(query).expect("failed to select users");
+        IcDbmsDatabase::from_transaction(TestDatabaseSchema, transaction_id)
+    }
+
+    fn select_user(id: u32) -> Vec<UserRecord> {
+        IcDbmsDatabase::oneshot(TestDatabaseSchema)
+            .select(
+                Query::<User>::builder()
+                    .and_where(Filter::eq("id", Value::Uint32(Uint32(id))))
+                    .build(),
+            )
+            .expect("failed to select users")
+    }
+
+    #[test]
+    fn test_should_apply_then_ops_if_record_exists() {
+        load_fixtures();
+        let users_count = IcDbmsDatabase::oneshot(TestDatabaseSchema)
+            .select(Query::<User>::builder().build())
+            .expect("failed to select users")
+            .len();
+
+        let mut dbms = upsert_user_in_transaction(1, "Upserted");
+        dbms.commit().expect("failed to commit transaction");
+
+        let users = select_user(1);
         assert_eq!(users.len(), 1);
-        let user = &users[0];
-        assert_eq!(user.id.expect("should have id").0, 100);
         assert_eq!(
-            user.name.as_ref().expect("should have name").0,
-            "NewUser".to_string()
+            users[0].name.as_ref().expect("should have name").0,
+            "Upserted"
         );
+        let users_after_commit = IcDbmsDatabase::oneshot(TestDatabaseSchema)
+            .select(Query::<User>::builder().build())
+            .expect("failed to select users")
+            .len();
+        assert_eq!(users_after_commit, users_count);
     }
 
     #[test]
-    fn test_should_validate_user_insert_conflict() {
+    fn test_should_apply_else_ops_if_record_does_not_exist() {
         load_fixtures();
 
+        let mut dbms = upsert_user_in_transaction(100, "Upserted");
+        // the conditional operations are not visible within the transaction
+        let users = dbms
+            .select(
+                Query::<User>::builder()
+                    .and_where(Filter::eq("id", Value::Uint32(Uint32(100))))
+                    .build(),
+            )
+            .expect("failed to select users");
+        assert!(users.is_empty());
+
+        dbms.commit().expect("failed to commit transaction");
+
+        let users = select_user(100);
+        assert_eq!(users.len(), 1);
+        assert_eq!(
+            users[0].name.as_ref().expect("should have name").0,
+            "Upserted"
+        );
+    }
+
+    #[test]
+    fn test_should_get_perf_metrics() {
+        load_fixtures();
+        MEMORY_MANAGER.with_borrow_mut(|mm| mm.reset_stats());
         let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
-        let new_user = UserInsertRequest {
-            id: Uint32(1u32),
-            name: Text("NewUser".to_string()),
-        };
+        for id in 100..200 {
+            dbms.insert::<User>(UserInsertRequest {
+                id: Uint32(id),
+                name: Text(format!("User {id}")),
+            })
+            .expect("failed to insert user");
+        }
 
-        let result = dbms.insert::<User>(new_user);
-        assert!(result.is_err());
+        let metrics = dbms.perf_metrics();
+        assert_eq!(metrics.logical_writes, 100);
+        assert!(metrics.physical_writes > metrics.logical_writes);
+        assert!(metrics.data_bytes > 0);
+    }
+
+    /// Returns the ids of the given users.
+    fn user_ids(users: &[UserRecord]) -> Vec<u32> {
+        users
+            .iter()
+            .map(|user| user.id.expect("should have id").0)
+            .collect()
     }
 
     #[test]
-    fn test_should_insert_within_a_transaction() {
+    fn test_should_select_intersect() {
         load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
 
-        // create a transaction
-        let transaction_id =
-            TRANSACTION_SESSION.with_borrow_mut(|ts| ts.begin_transaction(Principal::anonymous()));
-        let mut dbms = IcDbmsDatabase::from_transaction(TestDatabaseSchema, transaction_id.clone());
+        // Alice, Bob, Charlie, Diana, Eve, Frank
+        let query_a = Query::<User>::builder()
+            .and_where(Filter::lt("id", Value::Uint32(Uint32(6))))
+            .order_by_desc("id")
+            .build();
+        // Alice, Charlie, Diana, Frank, Grace, Ivan
+        let query_b = Query::<User>::builder()
+            .and_where(Filter::ilike("name", "%a%"))
+            .build();
 
-        let new_user = UserInsertRequest {
-            id: Uint32(200u32),
-            name: Text("TxUser".to_string()),
-        };
+        let users = dbms
+            .select_intersect(query_a, query_b)
+            .expect("failed to intersect queries");
+        assert_eq!(user_ids(&users), vec![5, 3, 2, 0]);
+    }
 
-        let result = dbms.insert::<User>(new_user);
-        assert!(result.is_ok());
+    #[test]
+    fn test_should_select_except() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
 
-        // user should not be visible outside the transaction
-        let oneshot_dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
-        let query = Query::<User>::builder()
-            .and_where(Filter::eq("id", Value::Uint32(200u32.into())))
+        let query_a = Query::<User>::builder()
+            .and_where(Filter::lt("id", Value::Uint32(Uint32(6))))
+            .order_by_asc("id")
+            .build();
+        let query_b = Query::<User>::builder()
+            .and_where(Filter::ilike("name", "%a%"))
             .build();
-        let users = oneshot_dbms
-            .select(query.clone())
-            .expect("failed to select users");
-        assert_eq!(users.len(), 0);
 
-        // commit transaction
-        let commit_result = dbms.commit();
-        assert!(commit_result.is_ok());
+        let users = dbms
+            .select_except(query_a, query_b)
+            .expect("failed to subtract queries");
+        assert_eq!(user_ids(&users), vec![1, 4]);
+
+        // no record is left if the second query matches all the records of the first one
+        let users = dbms
+            .select_except(
+                Query::<User>::builder()
+                    .and_where(Filter::eq("id", Value::Uint32(Uint32(0))))
+                    .build(),
+                Query::<User>::builder().build(),
+            )
+            .expect("failed to subtract queries");
+        assert!(users.is_empty());
+    }
 
-        // now user should be visible
-        let users_after_commit = oneshot_dbms.select(query).expect("failed to select users");
-        assert_eq!(users_after_commit.len(), 1);
+    /// Returns the value of `column` among the given joined values.
+    fn joined_value(values: &[(ColumnDef, Value)], column: &str) -> Value {
+        values
+            .iter()
+            .find(|(col_def, _)| col_def.name == column)
+            .map(|(_, value)| value.clone())
+            .expect("column not found")
+    }
 
-        let user = &users_after_commit[0];
-        assert_eq!(user.id.expect("should have id").0, 200);
-        assert_eq!(
-            user.name.as_ref().expect("should have name").0,
-            "TxUser".to_string()
-        );
+    #[test]
+    fn test_should_inner_join_posts_with_users() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
 
-        // transaction should have been removed
-        TRANSACTION_SESSION.with_borrow(|ts| {
-            let tx_res = ts.get_transaction(&transaction_id);
-            assert!(tx_res.is_err());
-        });
+        let joined = dbms
+            .select_join(Query::<Post>::builder().join::<User>("user_id", "id"))
+            .expect("failed to join posts with users");
+        assert_eq!(joined.len(), POSTS_FIXTURES.len());
+
+        for record in joined {
+            let post = record.a().expect("should have post");
+            let user = record.b().expect("should have user");
+            let post_id = post.id.expect("should have id").0 as usize;
+            let (title, _, user_id) = POSTS_FIXTURES[post_id];
+            assert_eq!(post.title.expect("should have title").0, title);
+            assert_eq!(user.id.expect("should have id").0, user_id);
+            assert_eq!(
+                user.name.expect("should have name").0,
+                USERS_FIXTURES[user_id as usize]
+            );
+            assert_eq!(
+                joined_value(&record.a_values, "user_id"),
+                joined_value(&record.b_values, "id")
+            );
+        }
     }
 
     #[test]
-    fn test_should_rollback_transaction() {
+    fn test_should_left_join_users_with_posts() {
         load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        dbms.insert::<User>(UserInsertRequest {
+            id: Uint32(100),
+            name: Text("Lonely".to_string()),
+        })
+        .expect("failed to insert user");
+
+        let joined = dbms
+            .select_join(
+                Query::<User>::builder()
+                    .and_where(Filter::in_list(
+                        "id",
+                        vec![Value::Uint32(Uint32(0)), Value::Uint32(Uint32(100))],
+                    ))
+                    .left_join::<Post>("id", "user_id"),
+            )
+            .expect("failed to join users with posts");
 
-        // create a transaction
-        let transaction_id =
-            TRANSACTION_SESSION.with_borrow_mut(|ts| ts.begin_transaction(Principal::anonymous()));
-        let mut dbms = IcDbmsDatabase::from_transaction(TestDatabaseSchema, transaction_id.clone());
-        let new_user = UserInsertRequest {
-            id: Uint32(300u32),
-            name: Text("RollbackUser".to_string()),
-        };
-        let result = dbms.insert::<User>(new_user);
-        assert!(result.is_ok());
+        let user_posts = POSTS_FIXTURES
+            .iter()
+            .filter(|(_, _, user_id)| *user_id == 0)
+            .count();
+        assert_eq!(joined.len(), user_posts + 1);
+        let lonely = joined
+            .iter()
+            .find(|record| record.b().is_none())
+            .expect("should have user without posts");
+        assert_eq!(lonely.a().expect("should have user").id, Some(Uint32(100)));
+
+        // the inner join drops the user without posts
+        let joined = dbms
+            .select_join(
+                Query::<User>::builder()
+                    .and_where(Filter::eq("id", Value::Uint32(Uint32(100))))
+                    .join::<Post>("id", "user_id"),
+            )
+            .expect("failed to join users with posts");
+        assert!(joined.is_empty());
+    }
 
-        // rollback transaction
-        let rollback_result = dbms.rollback();
-        assert!(rollback_result.is_ok());
+    #[test]
+    fn test_should_right_join_posts_with_users() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
 
-        // user should not be visible
-        let oneshot_dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
-        let query = Query::<User>::builder()
-            .and_where(Filter::eq("id", Value::Uint32(300u32.into())))
-            .build();
-        let users = oneshot_dbms.select(query).expect("failed to select users");
-        assert_eq!(users.len(), 0);
+        let joined = dbms
+            .select_join(Query::<Post>::builder().right_join::<User>("user_id", "id"))
+            .expect("failed to join posts with users");
 
-        // transaction should have been removed
-        TRANSACTION_SESSION.with_borrow(|ts| {
-            let tx_res = ts.get_transaction(&transaction_id);
-            assert!(tx_res.is_err());
-        });
+        // every user is returned, paired with its posts if any
+        for (user_id, _) in USERS_FIXTURES.iter().enumerate() {
+            let user_posts = POSTS_FIXTURES
+                .iter()
+                .filter(|(_, _, post_user_id)| *post_user_id as usize == user_id)
+                .count();
+            let records = joined
+                .iter()
+                .filter(|record| {
+                    joined_value(&record.b_values, "id") == Value::Uint32(Uint32(user_id as u32))
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(records.len(), user_posts.max(1));
+            assert_eq!(
+                records.iter().all(|record| record.a().is_none()),
+                user_posts == 0
+            );
+        }
     }
 
     #[test]
-    fn test_should_delete_one_shot() {
+    fn test_should_not_join_on_unknown_column() {
         load_fixtures();
-
-        // insert user with id 100
-        let new_user = UserInsertRequest {
-            id: Uint32(100u32),
-            name: Text("DeleteUser".to_string()),
-        };
-        assert!(
-            IcDbmsDatabase::oneshot(TestDatabaseSchema)
-                .insert::<User>(new_user)
-                .is_ok()
-        );
-
         let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
-        let query = Query::<User>::builder()
-            .and_where(Filter::eq("id", Value::Uint32(100u32.into())))
-            .build();
-        let delete_count = dbms
-            .delete::<User>(
-                DeleteBehavior::Restrict,
-                Some(Filter::eq("id", Value::Uint32(100u32.into()))),
-            )
-            .expect("failed to delete user");
-        assert_eq!(delete_count, 1);
 
-        // verify user is deleted
-        let users = dbms.select(query).expect("failed to select users");
-        assert_eq!(users.len(), 0);
+        let result = dbms.select_join(Query::<Post>::builder().join::<User>("author_id", "id"));
+        assert!(matches!(
+            result,
+            Err(IcDbmsError::Query(QueryError::UnknownColumn(column))) if column == "author_id"
+        ));
+    }
+
+    fn init_user_table() {
+        SCHEMA_REGISTRY
+            .with_borrow_mut(|sr| sr.register_table::<User>())
+            .expect("failed to register `User` table");
     }
 
     #[test]
-    #[should_panic(expected = "Foreign key constraint violation")]
-    fn test_should_not_delete_with_fk_restrict() {
+    fn test_should_enforce_table_quota() {
         load_fixtures();
-
-        // user 1 has post and messages for sure.
+        ACL.with_borrow_mut(|acl| acl.add_principal(Principal::anonymous()))
+            .expect("failed to add principal");
         let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        dbms.delete::<User>(DeleteBehavior::Cascade, None)
+            .expect("failed to delete users");
+        dbms.set_table_quota::<User>(TableQuota {
+            max_rows: Some(3),
+            ..Default::default()
+        })
+        .expect("failed to set quota");
+
+        let insert = |id: u32| {
+            dbms.insert::<User>(UserInsertRequest {
+                id: Uint32(id),
+                name: Text(format!("User {id}")),
+            })
+        };
+        for id in 1..=3 {
+            insert(id).expect("failed to insert user");
+        }
+        assert!(matches!(
+            insert(4),
+            Err(IcDbmsError::Memory(MemoryError::QuotaExceeded { table_fingerprint, limit: 3, actual: 4 }))
+                if table_fingerprint == User::fingerprint()
+        ));
+
+        // deleting a user frees a row
         dbms.delete::<User>(
-            DeleteBehavior::Restrict,
-            Some(Filter::eq("id", Value::Uint32(1u32.into()))),
+            DeleteBehavior::Cascade,
+            Some(Filter::eq("id", Value::Uint32(Uint32(1)))),
         )
         .expect("failed to delete user");
+        insert(4).expect("failed to insert user");
     }
 
     #[test]
-    fn test_should_delete_with_fk_cascade() {
+    fn test_should_not_set_table_quota_if_not_in_acl() {
         load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
 
-        // user 1 has posts and messages for sure.
+        assert!(matches!(
+            dbms.set_table_quota::<User>(TableQuota::default()),
+            Err(IcDbmsError::Query(QueryError::AccessDenied { caller })) if caller == Principal::anonymous()
+        ));
+    }
+
+    #[test]
+    fn test_should_vacuum_table() {
+        load_fixtures();
         let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
-        let delete_count = dbms
-            .delete::<User>(
-                DeleteBehavior::Cascade,
-                Some(Filter::eq("id", Value::Uint32(1u32.into()))),
-            )
-            .expect("failed to delete user");
-        assert!(delete_count > 0);
+        for id in 100..400u32 {
+            dbms.insert::<User>(UserInsertRequest {
+                id: Uint32(id),
+                name: Text(format!("{id:0>2000}")),
+            })
+            .expect("failed to insert user");
+        }
+        // delete half of the users, spread over all the pages
+        dbms.delete::<User>(
+            DeleteBehavior::Cascade,
+            Some(Filter::in_list(
+                "id",
+                (100..400u32)
+                    .step_by(2)
+                    .map(|id| Value::Uint32(Uint32(id)))
+                    .collect(),
+            )),
+        )
+        .expect("failed to delete users");
 
-        // verify user is deleted
-        let query = Query::<User>::builder()
-            .and_where(Filter::eq("id", Value::Uint32(1u32.into())))
-            .build();
-        let users = dbms.select(query).expect("failed to select users");
-        assert_eq!(users.len(), 0);
+        let stats = dbms.vacuum::<User>().expect("failed to vacuum");
+        assert_eq!(stats.records_moved, USERS_FIXTURES.len() as u64 + 150);
+        assert!(stats.pages_after * 10 <= stats.pages_before * 6);
+        assert!(stats.free_bytes_recovered > 0);
 
-        // check posts are deleted (post ID 2)
-        let post_query = Query::<Post>::builder()
-            .and_where(Filter::eq("user_id", Value::Uint32(1u32.into())))
-            .build();
-        let posts = dbms.select(post_query).expect("failed to select posts");
-        assert_eq!(posts.len(), 0);
+        // all the records are still there
+        let users = dbms
+            .select(Query::<User>::builder().all().build())
+            .expect("failed to select users");
+        assert_eq!(users.len(), USERS_FIXTURES.len() + 150);
+        assert!(
+            users.iter().all(|user| user.id.expect("missing id").0 < 100
+                || user.id.expect("missing id").0 % 2 == 1)
+        );
 
-        // check messages are deleted (message ID 1)
-        let message_query = Query::<Message>::builder()
-            .and_where(Filter::eq("sender_id", Value::Uint32(1u32.into())))
-            .or_where(Filter::eq("recipient_id", Value::Uint32(1u32.into())))
-            .build();
-        let messages = dbms
-            .select(message_query)
-            .expect("failed to select messages");
-        assert_eq!(messages.len(), 0);
+        // new records can be inserted after the vacuum
+        dbms.insert::<User>(UserInsertRequest {
+            id: Uint32(1000),
+            name: Text("After Vacuum".to_string()),
+        })
+        .expect("failed to insert user");
     }
 
     #[test]
-    fn test_should_delete_within_transaction() {
+    fn test_should_not_vacuum_within_transaction() {
         load_fixtures();
-
-        // create a transaction
         let transaction_id =
-            TRANSACTION_SESSION.with_borrow_mut(|ts| ts.begin_transaction(Principal::anonymous()));
-        let mut dbms = IcDbmsDatabase::from_transaction(TestDatabaseSchema, transaction_id.clone());
-
-        let delete_count = dbms
-            .delete::<User>(
-                DeleteBehavior::Cascade,
-                Some(Filter::eq("id", Value::Uint32(2u32.into()))),
-            )
-            .expect("failed to delete user");
-        assert!(delete_count > 0);
-
-        // user should not be visible outside the transaction
-        let oneshot_dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
-        let query = Query::<User>::builder()
-            .and_where(Filter::eq("id", Value::Uint32(2u32.into())))
-            .build();
-        let users = oneshot_dbms
-            .select(query.clone())
-            .expect("failed to select users");
-        assert_eq!(users.len(), 1);
+            TRANSACTION_SESSION.with_borrow_mut(|ts| ts.begin_transaction(caller()));
+        let dbms = IcDbmsDatabase::from_transaction(TestDatabaseSchema, transaction_id);
 
-        // commit transaction
-        let commit_result = dbms.commit();
-        assert!(commit_result.is_ok());
+        assert!(matches!(
+            dbms.vacuum::<User>(),
+            Err(IcDbmsError::Query(QueryError::InvalidQuery(_)))
+        ));
+    }
 
-        // now user should be deleted
-        let users_after_commit = oneshot_dbms.select(query).expect("failed to select users");
-        assert_eq!(users_after_commit.len(), 0);
+    #[test]
+    fn test_should_insert_idempotent() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        let request = || UserInsertRequest {
+            id: Uint32(100),
+            name: Text("Idempotent User".to_string()),
+        };
 
-        // check posts are deleted
-        let post_query = Query::<Post>::builder()
-            .and_where(Filter::eq("user_id", Value::Uint32(2u32.into())))
-            .build();
-        let posts = oneshot_dbms
-            .select(post_query)
-            .expect("failed to select posts");
-        assert_eq!(posts.len(), 0);
+        let first = dbms
+            .insert_idempotent::<User>(request(), [1; 16])
+            .expect("failed to insert user");
+        assert_eq!(
+            first,
+            IdempotentResult {
+                result: Ok(()),
+                cached: false,
+            }
+        );
 
-        // check messages are deleted
-        let message_query = Query::<Message>::builder()
-            .and_where(Filter::eq("sender_id", Value::Uint32(2u32.into())))
-            .or_where(Filter::eq("recipient_id", Value::Uint32(2u32.into())))
-            .build();
-        let messages = oneshot_dbms
-            .select(message_query)
-            .expect("failed to select messages");
-        assert_eq!(messages.len(), 0);
+        // the retry returns the cached result, without a primary key conflict
+        let retry = dbms
+            .insert_idempotent::<User>(request(), [1; 16])
+            .expect("failed to insert user");
+        assert_eq!(
+            retry,
+            IdempotentResult {
+                result: Ok(()),
+                cached: true,
+            }
+        );
+        let users = dbms
+            .select(Query::<User>::builder().all().build())
+            .expect("failed to select users");
+        assert_eq!(users.len(), USERS_FIXTURES.len() + 1);
 
-        // transaction should have been removed
-        TRANSACTION_SESSION.with_borrow(|ts| {
-            let tx_res = ts.get_transaction(&transaction_id);
-            assert!(tx_res.is_err());
-        });
+        // a new token executes the insert again
+        let conflict = dbms
+            .insert_idempotent::<User>(request(), [2; 16])
+            .expect("failed to insert user");
+        assert!(!conflict.cached);
+        assert_eq!(
+            conflict.result.map_err(|err| err.error_code),
+            Err(error_codes::PRIMARY_KEY_CONFLICT)
+        );
     }
 
     #[test]
-    fn test_should_update_one_shot() {
+    fn test_should_select_at_timestamp() {
         load_fixtures();
-
+        ChangeLog::enable().expect("failed to enable change log");
         let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
-        let filter = Filter::eq("id", Value::Uint32(3u32.into()));
-
-        let patch = UserUpdateRequest {
-            id: None,
-            name: Some(Text("UpdatedName".to_string())),
-            where_clause: Some(filter.clone()),
+        let query = || {
+            Query::<User>::builder()
+                .and_where(Filter::eq("id", Value::Uint32(Uint32(100))))
+                .build()
         };
 
-        let update_count = dbms.update::<User>(patch).expect("failed to update user");
-        assert_eq!(update_count, 1);
+        set_time(100);
+        dbms.insert::<User>(UserInsertRequest {
+            id: Uint32(100),
+            name: Text("Before".to_string()),
+        })
+        .expect("failed to insert user");
+        set_time(200);
+        dbms.update::<User>(
+            UpdateBehavior::Restrict,
+            UserUpdateRequest {
+                id: None,
+                name: Some(Text("After".to_string())),
+                where_clause: Some(Filter::eq("id", Value::Uint32(Uint32(100)))),
+            },
+        )
+        .expect("failed to update user");
+
+        let names_at = |timestamp: u64| {
+            dbms.select_at(query(), timestamp)
+                .expect("failed to select")
+                .into_iter()
+                .map(|user| user.name.expect("missing name").0)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names_at(50), Vec::<String>::new());
+        assert_eq!(names_at(150), vec!["Before".to_string()]);
+        assert_eq!(names_at(250), vec!["After".to_string()]);
 
-        // verify user is updated
-        let query = Query::<User>::builder().and_where(filter).build();
-        let users = dbms.select(query).expect("failed to select users");
-        assert_eq!(users.len(), 1);
-        let user = &users[0];
-        assert_eq!(user.id.expect("should have id").0, 3);
+        // the fixtures were inserted before the change log was enabled
         assert_eq!(
-            user.name.as_ref().expect("should have name").0,
-            "UpdatedName".to_string()
+            dbms.select_at(Query::<User>::builder().all().build(), 150)
+                .expect("failed to select")
+                .len(),
+            USERS_FIXTURES.len() + 1
         );
     }
 
     #[test]
-    fn test_should_update_within_transaction() {
+    fn test_should_not_select_at_timestamp_without_change_log() {
         load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
 
-        // create a transaction
-        let transaction_id =
+        assert!(matches!(
+            dbms.select_at(Query::<User>::builder().all().build(), 0),
+            Err(IcDbmsError::Query(QueryError::InvalidQuery(_)))
+        ));
+    }
+
+    #[test]
+    fn test_should_not_see_uncommitted_changes_of_other_transactions() {
+        load_fixtures();
+
+        let transaction_a =
             TRANSACTION_SESSION.with_borrow_mut(|ts| ts.begin_transaction(Principal::anonymous()));
-        let mut dbms = IcDbmsDatabase::from_transaction(TestDatabaseSchema, transaction_id.clone());
+        let transaction_b =
+            TRANSACTION_SESSION.with_borrow_mut(|ts| ts.begin_transaction(Principal::anonymous()));
+        let mut dbms_a = IcDbmsDatabase::from_transaction(TestDatabaseSchema, transaction_a);
+        let dbms_b = IcDbmsDatabase::from_transaction(TestDatabaseSchema, transaction_b);
 
-        let filter = Filter::eq("id", Value::Uint32(4u32.into()));
-        let patch = UserUpdateRequest {
-            id: None,
-            name: Some(Text("TxUpdatedName".to_string())),
-            where_clause: Some(filter.clone()),
-        };
+        dbms_a
+            .insert::<User>(UserInsertRequest {
+                id: Uint32(100),
+                name: Text("Uncommitted".to_string()),
+            })
+            .expect("failed to insert user");
 
-        let update_count = dbms.update::<User>(patch).expect("failed to update user");
-        assert_eq!(update_count, 1);
+        let query = || {
+            Query::<User>::builder()
+                .and_where(Filter::eq("id", Value::Uint32(Uint32(100))))
+                .build()
+        };
+        let count_query = || CountQuery::new(query());
 
-        // user should not be visible outside the transaction
-        let oneshot_dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
-        let query = Query::<User>::builder().and_where(filter.clone()).build();
-        let users = oneshot_dbms
-            .select(query.clone())
-            .expect("failed to select users");
-        let user = &users[0];
+        // the insert is visible only within its transaction
+        assert_eq!(dbms_a.select(query()).expect("failed to select").len(), 1);
+        assert!(dbms_b.select(query()).expect("failed to select").is_empty());
         assert_eq!(
-            user.name.as_ref().expect("should have name").0,
-            USERS_FIXTURES[4]
+            dbms_a.count_query(count_query()).expect("failed to count"),
+            1
         );
-
-        // commit transaction
-        let commit_result = dbms.commit();
-        assert!(commit_result.is_ok());
-
-        // now user should be updated
-        let users_after_commit = oneshot_dbms.select(query).expect("failed to select users");
-        assert_eq!(users_after_commit.len(), 1);
-        let user = &users_after_commit[0];
         assert_eq!(
-            user.name.as_ref().expect("should have name").0,
-            "TxUpdatedName".to_string()
+            dbms_b.count_query(count_query()).expect("failed to count"),
+            0
         );
 
-        // transaction should have been removed
-        TRANSACTION_SESSION.with_borrow(|ts| {
-            let tx_res = ts.get_transaction(&transaction_id);
-            assert!(tx_res.is_err());
-        });
+        // once committed, the insert is visible to the other transaction
+        dbms_a.commit().expect("failed to commit transaction");
+        assert_eq!(dbms_b.select(query()).expect("failed to select").len(), 1);
     }
 
-    fn init_user_table() {
-        SCHEMA_REGISTRY
-            .with_borrow_mut(|sr| sr.register_table::<User>())
-            .expect("failed to register `User` table");
+    #[test]
+    fn test_should_rate_limit_select_as() {
+        load_fixtures();
+        let alice = Principal::from_text("ryjl3-tyaaa-aaaaa-aaaba-cai").unwrap();
+        let admin = Principal::from_text("mxzaz-hqaaa-aaaar-qaada-cai").unwrap();
+        RateLimiter::set_default(Some(RateLimiter::new(5)));
+        RateLimiter::whitelist(admin);
+        set_time(1_000_000_000);
+
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        for call in 1..=10 {
+            let result = dbms.select_as(alice, Query::<User>::builder().all().build());
+            if call <= 5 {
+                assert_eq!(
+                    result.expect("call should be allowed").len(),
+                    USERS_FIXTURES.len()
+                );
+            } else {
+                assert!(matches!(
+                    result,
+                    Err(IcDbmsError::RateLimitExceeded { caller, limit: 5, reset_at })
+                        if caller == alice && reset_at == 2_000_000_000
+                ));
+            }
+
+            dbms.select_as(admin, Query::<User>::builder().all().build())
+                .expect("whitelisted caller should never be rejected");
+        }
+    }
+
+    #[test]
+    fn test_should_log_transaction_operations() {
+        use crate::dbms::logger::{LogLevel, VecLogger, set_logger};
+
+        load_fixtures();
+        let logger = VecLogger::default();
+        set_logger(logger.clone());
+
+        let transaction_id =
+            TRANSACTION_SESSION.with_borrow_mut(|ts| ts.begin_transaction(Principal::anonymous()));
+        let mut dbms = IcDbmsDatabase::from_transaction(TestDatabaseSchema, transaction_id.clone());
+        dbms.insert::<User>(UserInsertRequest {
+            id: Uint32(200u32),
+            name: Text("TxUser".to_string()),
+        })
+        .expect("failed to insert user");
+        dbms.commit().expect("failed to commit");
+
+        // the scans also log the buffer pool accesses
+        let entries = logger
+            .entries()
+            .into_iter()
+            .filter(|entry| !entry.message.starts_with("buffer pool"))
+            .collect::<Vec<_>>();
+        let messages = entries
+            .iter()
+            .map(|entry| (entry.level, entry.message.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            vec![
+                (LogLevel::Info, "transaction begun"),
+                (LogLevel::Debug, "insert added to transaction"),
+                (LogLevel::Debug, "record inserted"),
+                (LogLevel::Info, "transaction committed"),
+            ]
+        );
+        assert_eq!(
+            entries[1].context,
+            vec![("table".to_string(), "users".to_string())]
+        );
+        assert_eq!(
+            entries[3].context,
+            vec![("transaction".to_string(), transaction_id.to_string())]
+        );
     }
 }
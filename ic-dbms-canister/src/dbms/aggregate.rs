@@ -0,0 +1,419 @@
+//! This module contains the [`Accumulator`] of the aggregate queries performed with
+//! [`crate::prelude::IcDbmsDatabase::aggregate`], and the [`GroupedAccumulator`] of the grouped ones
+//! performed with [`crate::prelude::IcDbmsDatabase::select_grouped`].
+
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+
+use ic_dbms_api::prelude::{
+    AggregateOp, ColumnDef, Decimal, Float64, Int64, QueryError, QueryResult, Uint64, Value,
+};
+use rust_decimal::Decimal as RustDecimal;
+
+/// Accumulates the records matched by an aggregate query, one at a time,
+/// so that the records never need to be collected.
+#[derive(Debug)]
+pub struct Accumulator {
+    op: AggregateOp,
+    /// Number of accumulated records; for the aggregates on a column, only non-`NULL` values are counted.
+    count: u64,
+    /// Sum of the accumulated values, for `SUM` and `AVG`.
+    sum: Option<Sum>,
+    /// Minimum or maximum accumulated value, for `MIN` and `MAX`.
+    extreme: Option<Value>,
+}
+
+/// The aggregate computed over a group of records by a grouped aggregate query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupedResult {
+    /// Values of the columns the records are grouped by, in the order of the `group_by` columns of the query.
+    pub key_values: Vec<(ColumnDef, Value)>,
+    /// Result of the aggregate function over the records of the group.
+    pub aggregate: Value,
+}
+
+impl GroupedResult {
+    /// Name referring to the aggregate result in the `ORDER BY` clauses of a grouped aggregate query.
+    pub const AGGREGATE_COLUMN: &'static str = "aggregate";
+
+    /// Returns the value of the given grouping column, or the aggregate result for [`GroupedResult::AGGREGATE_COLUMN`].
+    pub fn value(&self, column: &str) -> Option<&Value> {
+        self.key_values
+            .iter()
+            .find(|(column_def, _)| column_def.name == column)
+            .map(|(_, value)| value)
+            .or_else(|| (column == Self::AGGREGATE_COLUMN).then_some(&self.aggregate))
+    }
+}
+
+/// Accumulates the records matched by a grouped aggregate query, one at a time,
+/// with an [`Accumulator`] for each group of records having the same values of the grouping columns.
+///
+/// `NULL` values are equal to each other, so they form their own group.
+#[derive(Debug)]
+pub struct GroupedAccumulator {
+    op: AggregateOp,
+    group_by: Vec<&'static str>,
+    /// Index of each group in `accumulators`, by the values of the grouping columns.
+    groups: HashMap<Vec<Value>, usize>,
+    /// Grouping column values and accumulator of each group, in order of first occurrence.
+    accumulators: Vec<(Vec<(ColumnDef, Value)>, Accumulator)>,
+}
+
+impl GroupedAccumulator {
+    /// Creates a new [`GroupedAccumulator`] for the given aggregate function, grouping by the given columns.
+    pub fn new(op: AggregateOp, group_by: Vec<&'static str>) -> Self {
+        Self {
+            op,
+            group_by,
+            groups: HashMap::new(),
+            accumulators: Vec::new(),
+        }
+    }
+
+    /// Accumulates the values of a record matched by the query into the accumulator of its group.
+    pub fn accumulate(&mut self, values: &[(ColumnDef, Value)]) -> QueryResult<()> {
+        let key_values: Vec<(ColumnDef, Value)> = self
+            .group_by
+            .iter()
+            .filter_map(|column| {
+                values
+                    .iter()
+                    .find(|(column_def, _)| column_def.name == *column)
+                    .cloned()
+            })
+            .collect();
+        let key = key_values.iter().map(|(_, value)| value.clone()).collect();
+
+        let index = match self.groups.entry(key) {
+            Entry::Occupied(entry) => *entry.get(),
+            Entry::Vacant(entry) => {
+                self.accumulators
+                    .push((key_values, Accumulator::new(self.op)));
+                *entry.insert(self.accumulators.len() - 1)
+            }
+        };
+        self.accumulators[index].1.accumulate(values)
+    }
+
+    /// Returns the [`GroupedResult`] of each group, in order of first occurrence.
+    pub fn finish(self) -> QueryResult<Vec<GroupedResult>> {
+        self.accumulators
+            .into_iter()
+            .map(|(key_values, accumulator)| {
+                Ok(GroupedResult {
+                    key_values,
+                    aggregate: accumulator.finish()?,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Sum of numeric values, in a type wide enough to not overflow while accumulating.
+#[derive(Debug, Clone, Copy)]
+enum Sum {
+    Signed(i128),
+    Unsigned(u128),
+    Decimal(RustDecimal),
+    Float(f64),
+}
+
+impl Accumulator {
+    /// Creates a new [`Accumulator`] for the given aggregate function.
+    pub fn new(op: AggregateOp) -> Self {
+        Self {
+            op,
+            count: 0,
+            sum: None,
+            extreme: None,
+        }
+    }
+
+    /// Accumulates the values of a record matched by the query.
+    pub fn accumulate(&mut self, values: &[(ColumnDef, Value)]) -> QueryResult<()> {
+        let Some(column) = self.op.column() else {
+            self.count += 1;
+            return Ok(());
+        };
+        let Some(value) = values
+            .iter()
+            .find(|(column_def, _)| column_def.name == column)
+            .map(|(_, value)| value)
+            .filter(|value| !value.is_null())
+        else {
+            return Ok(());
+        };
+        self.count += 1;
+
+        match self.op {
+            AggregateOp::Count => {}
+            AggregateOp::Sum(_) | AggregateOp::Avg(_) => {
+                self.sum = Some(Sum::add(self.sum, column, value)?);
+            }
+            AggregateOp::Min(_) => {
+                if self.extreme.as_ref().is_none_or(|min| value < min) {
+                    self.extreme = Some(value.clone());
+                }
+            }
+            AggregateOp::Max(_) => {
+                if self.extreme.as_ref().is_none_or(|max| value > max) {
+                    self.extreme = Some(value.clone());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the result of the aggregate function.
+    ///
+    /// The aggregates on a column return [`Value::Null`] if no non-`NULL` value was accumulated.
+    pub fn finish(self) -> QueryResult<Value> {
+        match self.op {
+            AggregateOp::Count => Ok(Value::Uint64(Uint64(self.count))),
+            AggregateOp::Sum(column) => match self.sum {
+                Some(sum) => sum.into_value(column),
+                None => Ok(Value::Null),
+            },
+            AggregateOp::Avg(column) => match self.sum {
+                Some(sum) => sum.average(self.count).into_value(column),
+                None => Ok(Value::Null),
+            },
+            AggregateOp::Min(_) | AggregateOp::Max(_) => Ok(self.extreme.unwrap_or(Value::Null)),
+        }
+    }
+}
+
+impl Sum {
+    /// Adds the given value of `column` to the sum.
+    fn add(sum: Option<Sum>, column: &'static str, value: &Value) -> QueryResult<Sum> {
+        let addend = match value {
+            Value::Int32(v) => Sum::Signed(v.0.into()),
+            Value::Int64(v) => Sum::Signed(v.0.into()),
+            Value::Uint32(v) => Sum::Unsigned(v.0.into()),
+            Value::Uint64(v) => Sum::Unsigned(v.0.into()),
+            Value::Decimal(v) => Sum::Decimal(v.0),
+            Value::Float64(v) => Sum::Float(v.0),
+            value => {
+                return Err(QueryError::TypeMismatch {
+                    column,
+                    expected: "numeric",
+                    found: value.type_name(),
+                });
+            }
+        };
+
+        let sum = match (sum, addend) {
+            (None, addend) => Some(addend),
+            (Some(Sum::Signed(a)), Sum::Signed(b)) => a.checked_add(b).map(Sum::Signed),
+            (Some(Sum::Unsigned(a)), Sum::Unsigned(b)) => a.checked_add(b).map(Sum::Unsigned),
+            (Some(Sum::Decimal(a)), Sum::Decimal(b)) => a.checked_add(b).map(Sum::Decimal),
+            // floats don't overflow, but saturate to infinity
+            (Some(Sum::Float(a)), Sum::Float(b)) => Some(Sum::Float(a + b)),
+            (Some(_), _) => {
+                return Err(QueryError::TypeMismatch {
+                    column,
+                    expected: "values of the same numeric type",
+                    found: value.type_name(),
+                });
+            }
+        };
+
+        sum.ok_or_else(|| Self::overflow(column))
+    }
+
+    /// Divides the sum by the number of accumulated values, truncating integers towards zero.
+    fn average(self, count: u64) -> Sum {
+        match self {
+            Sum::Signed(sum) => Sum::Signed(sum / count as i128),
+            Sum::Unsigned(sum) => Sum::Unsigned(sum / count as u128),
+            Sum::Decimal(sum) => Sum::Decimal(sum / RustDecimal::from(count)),
+            Sum::Float(sum) => Sum::Float(sum / count as f64),
+        }
+    }
+
+    /// Converts the sum into a [`Value`]: [`Value::Int64`] for signed integers,
+    /// [`Value::Uint64`] for unsigned integers, [`Value::Decimal`] for decimals and [`Value::Float64`] for floats.
+    fn into_value(self, column: &'static str) -> QueryResult<Value> {
+        match self {
+            Sum::Signed(sum) => i64::try_from(sum)
+                .map(|sum| Value::Int64(Int64(sum)))
+                .map_err(|_| Self::overflow(column)),
+            Sum::Unsigned(sum) => u64::try_from(sum)
+                .map(|sum| Value::Uint64(Uint64(sum)))
+                .map_err(|_| Self::overflow(column)),
+            Sum::Decimal(sum) => Ok(Value::Decimal(Decimal(sum))),
+            Sum::Float(sum) => Ok(Value::Float64(Float64(sum))),
+        }
+    }
+
+    fn overflow(column: &'static str) -> QueryError {
+        QueryError::InvalidQuery(format!("the sum of column '{column}' overflows"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use ic_dbms_api::prelude::{Int32, TableSchema as _, Text};
+
+    use super::*;
+    use crate::tests::User;
+
+    fn user_values(id: Value, name: Option<&str>) -> Vec<(ColumnDef, Value)> {
+        let columns = User::columns();
+        vec![
+            (columns[0], id),
+            (
+                columns[1],
+                name.map(|name| Value::Text(Text(name.to_string())))
+                    .unwrap_or(Value::Null),
+            ),
+        ]
+    }
+
+    fn aggregate(op: AggregateOp, records: &[Vec<(ColumnDef, Value)>]) -> QueryResult<Value> {
+        let mut accumulator = Accumulator::new(op);
+        for values in records {
+            accumulator.accumulate(values)?;
+        }
+        accumulator.finish()
+    }
+
+    #[test]
+    fn test_should_count_records() {
+        assert_eq!(
+            aggregate(AggregateOp::Count, &[]).unwrap(),
+            Value::Uint64(Uint64(0))
+        );
+        let records = [
+            user_values(Value::Uint32(1.into()), None),
+            user_values(Value::Uint32(2.into()), Some("bob")),
+        ];
+        assert_eq!(
+            aggregate(AggregateOp::Count, &records).unwrap(),
+            Value::Uint64(Uint64(2))
+        );
+    }
+
+    #[test]
+    fn test_should_skip_nulls_in_column_aggregates() {
+        let records = [
+            user_values(Value::Uint32(1.into()), None),
+            user_values(Value::Uint32(2.into()), Some("bob")),
+            user_values(Value::Uint32(3.into()), Some("alice")),
+        ];
+        assert_eq!(
+            aggregate(AggregateOp::Min("name"), &records).unwrap(),
+            Value::Text(Text("alice".to_string()))
+        );
+        assert_eq!(
+            aggregate(AggregateOp::Max("name"), &records).unwrap(),
+            Value::Text(Text("bob".to_string()))
+        );
+        assert_eq!(
+            aggregate(AggregateOp::Min("name"), &records[..1]).unwrap(),
+            Value::Null
+        );
+        assert_eq!(aggregate(AggregateOp::Sum("id"), &[]).unwrap(), Value::Null);
+    }
+
+    #[test]
+    fn test_should_truncate_signed_average_towards_zero() {
+        let records = [
+            user_values(Value::Int32(Int32(-3)), None),
+            user_values(Value::Int32(Int32(-4)), None),
+        ];
+        assert_eq!(
+            aggregate(AggregateOp::Sum("id"), &records).unwrap(),
+            Value::Int64(Int64(-7))
+        );
+        assert_eq!(
+            aggregate(AggregateOp::Avg("id"), &records).unwrap(),
+            Value::Int64(Int64(-3))
+        );
+    }
+
+    #[test]
+    fn test_should_average_decimals() {
+        let records = [
+            user_values(Value::Decimal(Decimal(RustDecimal::new(150, 2))), None),
+            user_values(Value::Decimal(Decimal(RustDecimal::new(200, 2))), None),
+        ];
+        assert_eq!(
+            aggregate(AggregateOp::Avg("id"), &records).unwrap(),
+            Value::Decimal(Decimal(RustDecimal::new(175, 2)))
+        );
+    }
+
+    #[test]
+    fn test_should_sum_and_average_floats() {
+        let records = [
+            user_values(Value::Float64(Float64(1.5)), None),
+            user_values(Value::Float64(Float64(-0.5)), None),
+            user_values(Value::Float64(Float64(2.0)), None),
+        ];
+        assert_eq!(
+            aggregate(AggregateOp::Sum("id"), &records).unwrap(),
+            Value::Float64(Float64(3.0))
+        );
+        assert_eq!(
+            aggregate(AggregateOp::Avg("id"), &records).unwrap(),
+            Value::Float64(Float64(1.0))
+        );
+        assert_eq!(
+            aggregate(AggregateOp::Max("id"), &records).unwrap(),
+            Value::Float64(Float64(2.0))
+        );
+    }
+
+    #[test]
+    fn test_should_not_sum_non_numeric_column() {
+        let records = [user_values(Value::Uint32(1.into()), Some("bob"))];
+        assert!(matches!(
+            aggregate(AggregateOp::Sum("name"), &records),
+            Err(QueryError::TypeMismatch {
+                column: "name",
+                expected: "numeric",
+                found: "Text",
+            })
+        ));
+    }
+
+    #[test]
+    fn test_should_group_null_keys_together() {
+        let mut accumulator = GroupedAccumulator::new(AggregateOp::Count, vec!["name"]);
+        for (id, name) in [(1, None), (2, Some("bob")), (3, None), (4, Some("bob"))] {
+            accumulator
+                .accumulate(&user_values(Value::Uint32(id.into()), name))
+                .unwrap();
+        }
+
+        let results = accumulator.finish().unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].value("name"), Some(&Value::Null));
+        assert_eq!(results[0].aggregate, Value::Uint64(Uint64(2)));
+        assert_eq!(
+            results[1].value("name"),
+            Some(&Value::Text(Text("bob".to_string())))
+        );
+        assert_eq!(
+            results[1].value(GroupedResult::AGGREGATE_COLUMN),
+            Some(&Value::Uint64(Uint64(2)))
+        );
+        assert_eq!(results[1].value("id"), None);
+    }
+
+    #[test]
+    fn test_should_not_sum_overflowing_values() {
+        let records = [
+            user_values(Value::Uint64(Uint64(u64::MAX)), None),
+            user_values(Value::Uint64(Uint64(1)), None),
+        ];
+        assert!(matches!(
+            aggregate(AggregateOp::Sum("id"), &records),
+            Err(QueryError::InvalidQuery(_))
+        ));
+    }
+}
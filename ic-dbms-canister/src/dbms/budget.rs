@@ -0,0 +1,87 @@
+//! This module contains the instructions budget of the SELECT queries performed with
+//! [`crate::prelude::IcDbmsDatabase::select_with_budget`].
+
+use ic_dbms_api::prelude::TableSchema;
+
+/// Amount of scanned records between two checks of the instructions budget.
+pub const BUDGET_CHECK_INTERVAL: u64 = 100;
+
+/// Instructions budget of a SELECT query.
+///
+/// Cycles are charged for the executed instructions, so the budget is expressed as the amount of
+/// instructions executed in the current message, as returned by the performance counter `0`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CycleBudget {
+    /// Once exceeded, the scan stops and returns the results collected so far, with a [`Cursor`] to resume it.
+    pub soft_limit: u64,
+    /// Once exceeded, the canister traps.
+    pub hard_limit: u64,
+}
+
+/// The outcome of checking the instructions executed against a [`CycleBudget`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BudgetStatus {
+    /// The budget has not been exceeded.
+    Available,
+    /// The soft limit has been exceeded.
+    SoftLimitExceeded,
+    /// The hard limit has been exceeded.
+    HardLimitExceeded,
+}
+
+impl CycleBudget {
+    /// Checks the given amount of executed instructions against the budget.
+    pub fn check(&self, instructions: u64) -> BudgetStatus {
+        if instructions >= self.hard_limit {
+            BudgetStatus::HardLimitExceeded
+        } else if instructions >= self.soft_limit {
+            BudgetStatus::SoftLimitExceeded
+        } else {
+            BudgetStatus::Available
+        }
+    }
+}
+
+/// Position where a truncated scan stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Cursor {
+    /// Amount of records scanned before stopping; the scan resumes from the record at this position.
+    pub position: u64,
+    /// Column of the index whose located records were scanned, or `None` if the whole table was scanned.
+    ///
+    /// The resumed scan must read the records the same way, so that the position refers to the same records.
+    pub index: Option<&'static str>,
+}
+
+/// The result of a SELECT query performed with a [`CycleBudget`].
+pub struct PartialSelectResult<T>
+where
+    T: TableSchema,
+{
+    /// The selected records.
+    pub records: Vec<T::Record>,
+    /// The [`Cursor`] to resume the scan from, if truncated.
+    pub cursor: Option<Cursor>,
+    /// Whether the scan was stopped before the end of the table because the soft limit was exceeded.
+    pub truncated: bool,
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_should_check_budget() {
+        let budget = CycleBudget {
+            soft_limit: 1_000,
+            hard_limit: 2_000,
+        };
+
+        assert_eq!(budget.check(0), BudgetStatus::Available);
+        assert_eq!(budget.check(999), BudgetStatus::Available);
+        assert_eq!(budget.check(1_000), BudgetStatus::SoftLimitExceeded);
+        assert_eq!(budget.check(1_999), BudgetStatus::SoftLimitExceeded);
+        assert_eq!(budget.check(2_000), BudgetStatus::HardLimitExceeded);
+    }
+}
@@ -0,0 +1,261 @@
+//! This module contains the query results cache for read-heavy workloads.
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash as _, Hasher as _};
+
+use ic_dbms_api::prelude::{Database as _, IcDbmsResult, Query, TableSchema};
+
+use crate::dbms::IcDbmsDatabase;
+use crate::utils::time;
+
+thread_local! {
+    /// The [`QueryCache`] of each table, keyed by table name.
+    ///
+    /// Since thread locals can't be generic, each cache is stored as a `Box<dyn Any>`
+    /// and downcasted to the [`QueryCache`] of the table schema.
+    pub static QUERY_CACHE: RefCell<HashMap<&'static str, Box<dyn Any>>> = RefCell::new(HashMap::new());
+}
+
+/// Expiry of a cached result, as an IC timestamp in nanoseconds.
+pub type Expiry = u64;
+
+/// Cache of the results of the queries performed on the table `T`.
+///
+/// Results are keyed by the hash of the [`Query`] and are kept until their [`Expiry`]
+/// or until any write is performed on the table through [`IcDbmsDatabase`].
+pub struct QueryCache<T>
+where
+    T: TableSchema,
+{
+    entries: HashMap<u64, (Vec<T::Record>, Expiry)>,
+}
+
+impl<T> Default for QueryCache<T>
+where
+    T: TableSchema,
+{
+    fn default() -> Self {
+        Self {
+            entries: HashMap::new(),
+        }
+    }
+}
+
+impl<T> QueryCache<T>
+where
+    T: TableSchema,
+    T::Record: Clone,
+{
+    /// Returns the cached results of the given query if still fresh,
+    /// otherwise executes the query on `db` and caches its results for `ttl_ns` nanoseconds.
+    ///
+    /// Queries executed within a transaction are never cached,
+    /// since their results depend on the uncommitted changes of the transaction.
+    pub fn get_or_execute(
+        db: &IcDbmsDatabase,
+        query: Query<T>,
+        ttl_ns: u64,
+    ) -> IcDbmsResult<Vec<T::Record>> {
+        if db.transaction.is_some() {
+            return db.select(query);
+        }
+
+        let key = Self::key(&query);
+        let now = time();
+        if let Some(records) = Self::with_cache(|cache| cache.get(key, now)) {
+            return Ok(records);
+        }
+
+        let records = db.select(query)?;
+        Self::with_cache(|cache| {
+            cache
+                .entries
+                .insert(key, (records.clone(), now.saturating_add(ttl_ns)))
+        });
+
+        Ok(records)
+    }
+
+    /// Returns the cached records for the given key, if not expired at `now`.
+    fn get(&self, key: u64, now: u64) -> Option<Vec<T::Record>> {
+        self.entries
+            .get(&key)
+            .filter(|(_, expiry)| now < *expiry)
+            .map(|(records, _)| records.clone())
+    }
+
+    /// Executes a closure with a mutable reference to the [`QueryCache`] of the table `T`.
+    fn with_cache<F, R>(f: F) -> R
+    where
+        F: FnOnce(&mut Self) -> R,
+    {
+        QUERY_CACHE.with_borrow_mut(|caches| {
+            let cache = caches
+                .entry(T::table_name())
+                .or_insert_with(|| Box::new(Self::default()))
+                .downcast_mut::<Self>()
+                .expect("query cache type mismatch");
+            f(cache)
+        })
+    }
+
+    /// Computes the cache key of the given query.
+    fn key(query: &Query<T>) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        query.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+impl<T> QueryCache<T>
+where
+    T: TableSchema,
+{
+    /// Invalidates all the cached results for the table `T`.
+    pub fn invalidate_all() {
+        QUERY_CACHE.with_borrow_mut(|caches| {
+            caches.remove(T::table_name());
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use ic_dbms_api::prelude::{
+        DeleteBehavior, Filter, Text, Uint32, UpdateBehavior, UpdateRecord as _, Value,
+    };
+
+    use super::*;
+    use crate::memory::{SCHEMA_REGISTRY, TableRegistry};
+    use crate::tests::{
+        TestDatabaseSchema, User, UserInsertRequest, UserUpdateRequest, load_fixtures,
+    };
+
+    const TTL: u64 = 60_000_000_000;
+
+    /// Inserts a user directly into the table registry, bypassing the cache invalidation.
+    fn insert_user_bypassing_dbms(id: u32) {
+        let pages = SCHEMA_REGISTRY
+            .with_borrow(|sr| sr.table_registry_page::<User>())
+            .expect("user table not registered");
+        let mut registry = TableRegistry::load(pages).expect("failed to load user table");
+        registry
+            .insert(User {
+                id: Uint32(id),
+                name: Text(format!("User {id}")),
+            })
+            .expect("failed to insert user");
+    }
+
+    #[test]
+    fn test_should_return_cached_results() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+
+        let users = QueryCache::<User>::get_or_execute(&dbms, Query::builder().build(), TTL)
+            .expect("failed to select users");
+
+        // the table changes without going through the dbms, so the cache is still valid
+        insert_user_bypassing_dbms(1000);
+
+        let cached = QueryCache::<User>::get_or_execute(&dbms, Query::builder().build(), TTL)
+            .expect("failed to select users");
+        assert_eq!(cached, users);
+
+        // a different query is not cached
+        let all = QueryCache::<User>::get_or_execute(
+            &dbms,
+            Query::builder().and_where(Filter::not_null("id")).build(),
+            TTL,
+        )
+        .expect("failed to select users");
+        assert_eq!(all.len(), users.len() + 1);
+    }
+
+    #[test]
+    fn test_should_execute_query_when_expired() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+
+        let users = QueryCache::<User>::get_or_execute(&dbms, Query::builder().build(), 0)
+            .expect("failed to select users");
+
+        insert_user_bypassing_dbms(1000);
+
+        let fresh = QueryCache::<User>::get_or_execute(&dbms, Query::builder().build(), 0)
+            .expect("failed to select users");
+        assert_eq!(fresh.len(), users.len() + 1);
+    }
+
+    #[test]
+    fn test_should_invalidate_cache_on_insert() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+
+        let users = QueryCache::<User>::get_or_execute(&dbms, Query::builder().build(), TTL)
+            .expect("failed to select users");
+
+        dbms.insert::<User>(UserInsertRequest {
+            id: Uint32(1000),
+            name: Text("New User".to_string()),
+        })
+        .expect("failed to insert user");
+
+        let fresh = QueryCache::<User>::get_or_execute(&dbms, Query::builder().build(), TTL)
+            .expect("failed to select users");
+        assert_eq!(fresh.len(), users.len() + 1);
+    }
+
+    #[test]
+    fn test_should_invalidate_cache_on_update() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        let query = || {
+            Query::<User>::builder()
+                .and_where(Filter::eq("id", Value::Uint32(Uint32(0))))
+                .build()
+        };
+
+        QueryCache::<User>::get_or_execute(&dbms, query(), TTL).expect("failed to select users");
+
+        let patch = UserUpdateRequest::from_values(
+            &[(User::columns()[1], Value::Text(Text("Renamed".to_string())))],
+            Some(Filter::eq("id", Value::Uint32(Uint32(0)))),
+        );
+        dbms.update::<User>(UpdateBehavior::Restrict, patch)
+            .expect("failed to update user");
+
+        let fresh = QueryCache::<User>::get_or_execute(&dbms, query(), TTL)
+            .expect("failed to select users");
+        assert_eq!(fresh[0].name, Some(Text("Renamed".to_string())));
+    }
+
+    #[test]
+    fn test_should_invalidate_cache_on_delete() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        insert_user_bypassing_dbms(1000);
+        let query = || {
+            Query::<User>::builder()
+                .and_where(Filter::eq("id", Value::Uint32(Uint32(1000))))
+                .build()
+        };
+
+        let users = QueryCache::<User>::get_or_execute(&dbms, query(), TTL)
+            .expect("failed to select users");
+        assert_eq!(users.len(), 1);
+
+        dbms.delete::<User>(
+            DeleteBehavior::Restrict,
+            Some(Filter::eq("id", Value::Uint32(Uint32(1000)))),
+        )
+        .expect("failed to delete user");
+
+        let fresh = QueryCache::<User>::get_or_execute(&dbms, query(), TTL)
+            .expect("failed to select users");
+        assert!(fresh.is_empty());
+    }
+}
@@ -0,0 +1,266 @@
+//! This module exposes the change log, which records the committed changes to query past states of the tables.
+
+use std::borrow::Cow;
+
+use ic_dbms_api::prelude::{
+    DataSize, DecodeError, Encode, IcDbmsResult, MSize, MemoryError, MemoryResult,
+    TableFingerprint, TableSchema,
+};
+
+use crate::memory::{SCHEMA_REGISTRY, TableRegistry};
+use crate::utils::time;
+
+/// Fingerprint under which the change log is registered in the schema registry.
+const CHANGE_LOG_FINGERPRINT: TableFingerprint = 0xC4A6_E106_C4A6_E106;
+
+/// A change of a single record, described by the encoded record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LogOp {
+    /// The record has been inserted.
+    Insert(Vec<u8>),
+    /// The record has been deleted.
+    Delete(Vec<u8>),
+}
+
+/// An entry of the [`ChangeLog`].
+///
+/// Updates are logged as the delete of the old record followed by the insert of the new one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogEntry {
+    /// Time of the change, in nanoseconds since the UNIX epoch.
+    pub timestamp: u64,
+    /// Fingerprint of the changed table.
+    pub table: TableFingerprint,
+    /// The change.
+    pub op: LogOp,
+}
+
+impl Encode for LogEntry {
+    const SIZE: DataSize = DataSize::Dynamic;
+
+    fn size(&self) -> MSize {
+        // 8 bytes for timestamp + 8 bytes for table + 1 byte for op + record bytes
+        let (LogOp::Insert(record) | LogOp::Delete(record)) = &self.op;
+        8 + 8 + 1 + record.len() as MSize
+    }
+
+    fn encode(&'_ self) -> Cow<'_, [u8]> {
+        let (tag, record) = match &self.op {
+            LogOp::Insert(record) => (0u8, record),
+            LogOp::Delete(record) => (1u8, record),
+        };
+        let mut bytes = Vec::with_capacity(self.size() as usize);
+        bytes.extend_from_slice(&self.timestamp.to_le_bytes());
+        bytes.extend_from_slice(&self.table.to_le_bytes());
+        bytes.push(tag);
+        bytes.extend_from_slice(record);
+        Cow::Owned(bytes)
+    }
+
+    fn decode(data: Cow<[u8]>) -> MemoryResult<Self>
+    where
+        Self: Sized,
+    {
+        if data.len() < 17 {
+            return Err(MemoryError::DecodeError(DecodeError::TooShort));
+        }
+        let timestamp = u64::from_le_bytes(data[0..8].try_into()?);
+        let table = u64::from_le_bytes(data[8..16].try_into()?);
+        let record = data[17..].to_vec();
+        let op = match data[16] {
+            0 => LogOp::Insert(record),
+            _ => LogOp::Delete(record),
+        };
+
+        Ok(Self {
+            timestamp,
+            table,
+            op,
+        })
+    }
+}
+
+/// The change log records each committed insert and delete, to restore the past states of the tables.
+///
+/// The log is stored as an append-only table, registered in the schema registry under a reserved fingerprint.
+/// It's disabled until [`ChangeLog::enable`] is called; since then, every change is logged,
+/// so [`ChangeLog::purge_before`] should be called periodically to limit its growth.
+pub struct ChangeLog;
+
+impl ChangeLog {
+    /// Enables the change log; changes performed before are not logged.
+    pub fn enable() -> IcDbmsResult<()> {
+        SCHEMA_REGISTRY.with_borrow_mut(|sr| sr.register_fingerprint(CHANGE_LOG_FINGERPRINT))?;
+        Ok(())
+    }
+
+    /// Returns whether the change log is enabled.
+    pub fn is_enabled() -> bool {
+        SCHEMA_REGISTRY
+            .with_borrow(|sr| sr.table_registry_page_by_fingerprint(CHANGE_LOG_FINGERPRINT))
+            .is_some()
+    }
+
+    /// Logs the insert of the given record of `T`, if the change log is enabled.
+    pub fn record_insert<T>(record: &T) -> IcDbmsResult<()>
+    where
+        T: TableSchema,
+    {
+        Self::append::<T>(LogOp::Insert, record)
+    }
+
+    /// Logs the delete of the given record of `T`, if the change log is enabled.
+    pub fn record_delete<T>(record: &T) -> IcDbmsResult<()>
+    where
+        T: TableSchema,
+    {
+        Self::append::<T>(LogOp::Delete, record)
+    }
+
+    /// Returns the entries of the given table logged after the given timestamp, oldest first.
+    pub fn entries_after(table: TableFingerprint, timestamp: u64) -> IcDbmsResult<Vec<LogEntry>> {
+        let Some(registry) = Self::load()? else {
+            return Ok(vec![]);
+        };
+
+        let mut entries = vec![];
+        let mut reader = registry.read::<LogEntry>();
+        while let Some(next) = reader.try_next()? {
+            if next.record.table == table && next.record.timestamp > timestamp {
+                entries.push(next.record);
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// Removes the entries logged before the given timestamp, releasing the pages left empty.
+    ///
+    /// The states of the tables before the given timestamp can't be restored anymore.
+    ///
+    /// Returns the amount of removed entries.
+    pub fn purge_before(timestamp: u64) -> IcDbmsResult<u64> {
+        let Some(mut registry) = Self::load()? else {
+            return Ok(0);
+        };
+
+        let mut expired = vec![];
+        {
+            let mut reader = registry.read::<LogEntry>();
+            while let Some(next) = reader.try_next()? {
+                if next.record.timestamp < timestamp {
+                    expired.push(next);
+                }
+            }
+        }
+        let count = expired.len() as u64;
+        for next in expired {
+            registry.delete(next.record, next.page, next.offset)?;
+        }
+        registry.gc_empty_pages()?;
+
+        Ok(count)
+    }
+
+    /// Appends the entry made by `op` for the given record, if the change log is enabled.
+    fn append<T>(op: impl FnOnce(Vec<u8>) -> LogOp, record: &T) -> IcDbmsResult<()>
+    where
+        T: TableSchema,
+    {
+        let Some(mut registry) = Self::load()? else {
+            return Ok(());
+        };
+
+        registry.insert(LogEntry {
+            timestamp: time(),
+            table: T::fingerprint(),
+            op: op(record.encode().into_owned()),
+        })?;
+
+        Ok(())
+    }
+
+    /// Loads the table registry of the change log, if enabled.
+    fn load() -> IcDbmsResult<Option<TableRegistry>> {
+        let Some(pages) = SCHEMA_REGISTRY
+            .with_borrow(|sr| sr.table_registry_page_by_fingerprint(CHANGE_LOG_FINGERPRINT))
+        else {
+            return Ok(None);
+        };
+
+        Ok(Some(TableRegistry::load(pages)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::tests::User;
+    use crate::utils::set_time;
+
+    #[test]
+    fn test_should_encode_and_decode_log_entry() {
+        let entry = LogEntry {
+            timestamp: 100,
+            table: 42,
+            op: LogOp::Delete(vec![1, 2, 3]),
+        };
+
+        let encoded = entry.encode();
+        assert_eq!(encoded.len(), entry.size() as usize);
+        let decoded = LogEntry::decode(encoded).expect("failed to decode");
+        assert_eq!(decoded, entry);
+    }
+
+    #[test]
+    fn test_should_not_log_if_disabled() {
+        let user = User {
+            id: 1u32.into(),
+            name: "Alice".to_string().into(),
+        };
+        ChangeLog::record_insert(&user).expect("failed to log insert");
+
+        assert!(!ChangeLog::is_enabled());
+        assert!(
+            ChangeLog::entries_after(User::fingerprint(), 0)
+                .expect("failed to read log")
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_should_log_and_purge_entries() {
+        ChangeLog::enable().expect("failed to enable change log");
+        let user = User {
+            id: 1u32.into(),
+            name: "Alice".to_string().into(),
+        };
+        set_time(100);
+        ChangeLog::record_insert(&user).expect("failed to log insert");
+        set_time(200);
+        ChangeLog::record_delete(&user).expect("failed to log delete");
+
+        let entries =
+            ChangeLog::entries_after(User::fingerprint(), 100).expect("failed to read log");
+        assert_eq!(
+            entries,
+            vec![LogEntry {
+                timestamp: 200,
+                table: User::fingerprint(),
+                op: LogOp::Delete(user.encode().into_owned()),
+            }]
+        );
+
+        assert_eq!(
+            ChangeLog::purge_before(150).expect("failed to purge log"),
+            1
+        );
+        assert_eq!(
+            ChangeLog::entries_after(User::fingerprint(), 0)
+                .expect("failed to read log")
+                .len(),
+            1
+        );
+    }
+}
@@ -0,0 +1,46 @@
+//! This module contains the result of the self-diagnostic performed by
+//! [`crate::prelude::IcDbmsDatabase::health_check`].
+
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+/// Amount of records verified from the beginning of each table by
+/// [`crate::prelude::IcDbmsDatabase::health_check`], besides the records of its last page.
+pub const DEFAULT_HEALTH_CHECK_SAMPLE_SIZE: usize = 1;
+
+/// Result of [`crate::prelude::IcDbmsDatabase::health_check`].
+///
+/// It is a Candid type, so that it can be returned as is by a canister query endpoint.
+#[derive(Debug, Clone, Default, PartialEq, Eq, CandidType, Serialize, Deserialize)]
+pub struct HealthCheckResult {
+    /// Whether the schema registry can be loaded from memory.
+    pub schema_registry_ok: bool,
+    /// Whether the access control list can be loaded from memory.
+    pub acl_ok: bool,
+    /// Health of each table of the database schema.
+    pub per_table_ok: Vec<TableHealth>,
+    /// Description of each problem found.
+    pub issues: Vec<String>,
+}
+
+impl HealthCheckResult {
+    /// Returns whether no problem has been found.
+    pub fn is_healthy(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Health of a table, as reported by [`crate::prelude::IcDbmsDatabase::health_check`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, CandidType, Serialize, Deserialize)]
+pub struct TableHealth {
+    /// Name of the table.
+    pub table_name: String,
+    /// Estimated amount of stored records, see [`crate::memory::TableStats::estimated_records`].
+    pub row_count: u64,
+    /// Amount of free segments left by the deleted records.
+    pub free_segments: u32,
+    /// Amount of pages allocated to store the records.
+    pub page_count: u32,
+    /// Whether the sampled records are intact, see [`crate::memory::TableRegistry::verify_integrity`].
+    pub integrity_ok: bool,
+}
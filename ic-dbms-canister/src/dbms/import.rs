@@ -0,0 +1,248 @@
+//! Import of the database state from JSON.
+
+use ic_dbms_api::prelude::{
+    Blob, Boolean, ColumnDef, DataTypeKind, Date, DateTime, Decimal, Float64, IcDbmsError,
+    IcDbmsResult, Int32, Int64, Principal, QueryError, Text, Uint32, Uint64, Uuid, Value,
+};
+use serde::de::DeserializeOwned;
+use serde_json::Value as JsonValue;
+
+use crate::dbms::schema::TableSchemaFingerprint;
+
+/// Result of [`crate::prelude::IcDbmsDatabase::import_database_json`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportResult {
+    /// Amount of tables imported.
+    pub tables_imported: u64,
+    /// Amount of rows imported.
+    pub rows_imported: u64,
+    /// Rows and tables which have been skipped, with the reason.
+    pub warnings: Vec<String>,
+}
+
+/// Parses the JSON object of the database state, mapping each table name to its rows.
+pub fn parse_database_json(json: &str) -> IcDbmsResult<serde_json::Map<String, JsonValue>> {
+    serde_json::from_str(json)
+        .map_err(|err| IcDbmsError::Query(QueryError::SerializationError(err.to_string())))
+}
+
+/// Sorts the given tables so that each table comes after the tables it references with a foreign key.
+///
+/// Self-references are ignored. Tables without dependencies keep their relative order.
+pub fn import_order(
+    tables: &[TableSchemaFingerprint],
+) -> IcDbmsResult<Vec<TableSchemaFingerprint>> {
+    let mut pending = tables.to_vec();
+    let mut ordered: Vec<TableSchemaFingerprint> = Vec::with_capacity(tables.len());
+
+    while !pending.is_empty() {
+        let Some(index) = pending.iter().position(|table| {
+            table
+                .columns
+                .iter()
+                .filter_map(|column| column.foreign_key)
+                .filter(|fk| fk.foreign_table != table.table)
+                .all(|fk| {
+                    ordered.iter().any(|t| t.table == fk.foreign_table)
+                        || !pending.iter().any(|t| t.table == fk.foreign_table)
+                })
+        }) else {
+            return Err(IcDbmsError::Query(QueryError::InvalidQuery(format!(
+                "circular foreign key references between tables: {}",
+                pending
+                    .iter()
+                    .map(|table| table.table)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))));
+        };
+        ordered.push(pending.remove(index));
+    }
+
+    Ok(ordered)
+}
+
+/// Converts a JSON row object into the record values of the table with the given columns.
+///
+/// Missing nullable columns are set to [`Value::Null`].
+pub fn json_to_record_values(
+    columns: &'static [ColumnDef],
+    row: &JsonValue,
+) -> IcDbmsResult<Vec<(ColumnDef, Value)>> {
+    let JsonValue::Object(row) = row else {
+        return Err(IcDbmsError::Query(QueryError::SerializationError(
+            "row is not a JSON object".to_string(),
+        )));
+    };
+    if let Some(unknown) = row
+        .keys()
+        .find(|key| !columns.iter().any(|column| column.name == key.as_str()))
+    {
+        return Err(IcDbmsError::Query(QueryError::UnknownColumn(
+            unknown.clone(),
+        )));
+    }
+
+    columns
+        .iter()
+        .filter_map(|column| match row.get(column.name) {
+            Some(value) => Some(json_to_value(column, value).map(|value| (*column, value))),
+            None if column.nullable => Some(Ok((*column, Value::Null))),
+            None => None,
+        })
+        .collect()
+}
+
+/// Converts a JSON value into the [`Value`] of the given column.
+///
+/// Values are represented as their serde representation, except for [`Uuid`]s, which are strings,
+/// and arrays, which are JSON arrays of their non-null elements.
+fn json_to_value(column: &ColumnDef, value: &JsonValue) -> IcDbmsResult<Value> {
+    if value.is_null() {
+        return Ok(Value::Null);
+    }
+
+    match column.data_type {
+        DataTypeKind::Array(element) => {
+            let element_column = ColumnDef {
+                data_type: *element,
+                ..*column
+            };
+            value
+                .as_array()
+                .ok_or_else(|| type_mismatch(column, value))?
+                .iter()
+                .map(|element| match element {
+                    JsonValue::Null => Err(type_mismatch(column, value)),
+                    element => json_to_value(&element_column, element),
+                })
+                .collect::<IcDbmsResult<Vec<_>>>()
+                .map(Value::Array)
+        }
+        DataTypeKind::Blob => deserialize::<Blob>(column, value).map(Value::Blob),
+        DataTypeKind::Boolean => deserialize::<Boolean>(column, value).map(Value::Boolean),
+        DataTypeKind::Date => deserialize::<Date>(column, value).map(Value::Date),
+        DataTypeKind::DateTime => deserialize::<DateTime>(column, value).map(Value::DateTime),
+        DataTypeKind::Decimal => deserialize::<Decimal>(column, value).map(Value::Decimal),
+        DataTypeKind::Float64 => deserialize::<Float64>(column, value).map(Value::Float64),
+        DataTypeKind::Int32 => deserialize::<Int32>(column, value).map(Value::Int32),
+        DataTypeKind::Int64 => deserialize::<Int64>(column, value).map(Value::Int64),
+        DataTypeKind::Principal => deserialize::<Principal>(column, value).map(Value::Principal),
+        DataTypeKind::Text => deserialize::<Text>(column, value).map(Value::Text),
+        DataTypeKind::Uint32 => deserialize::<Uint32>(column, value).map(Value::Uint32),
+        DataTypeKind::Uint64 | DataTypeKind::AutoIncrement => {
+            deserialize::<Uint64>(column, value).map(Value::Uint64)
+        }
+        DataTypeKind::Uuid => value
+            .as_str()
+            .and_then(|uuid| uuid::Uuid::parse_str(uuid).ok())
+            .map(|uuid| Value::Uuid(Uuid(uuid)))
+            .ok_or_else(|| type_mismatch(column, value)),
+    }
+}
+
+/// Deserializes the JSON value of the given column.
+fn deserialize<T>(column: &ColumnDef, value: &JsonValue) -> IcDbmsResult<T>
+where
+    T: DeserializeOwned,
+{
+    serde_json::from_value(value.clone()).map_err(|_| type_mismatch(column, value))
+}
+
+/// Returns the error for a JSON value which can't be converted to the type of the column.
+fn type_mismatch(column: &ColumnDef, value: &JsonValue) -> IcDbmsError {
+    IcDbmsError::Query(QueryError::SerializationError(format!(
+        "invalid value for column '{}' of type {:?}: {value}",
+        column.name, column.data_type
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use ic_dbms_api::prelude::TableSchema;
+    use serde_json::json;
+
+    use super::*;
+    use crate::tests::{Message, Post, User};
+
+    #[test]
+    fn test_should_sort_tables_by_dependencies() {
+        let order = import_order(&[
+            TableSchemaFingerprint::of::<Post>(),
+            TableSchemaFingerprint::of::<Message>(),
+            TableSchemaFingerprint::of::<User>(),
+        ])
+        .expect("failed to sort tables");
+
+        assert_eq!(
+            order.iter().map(|table| table.table).collect::<Vec<_>>(),
+            vec!["users", "posts", "messages"]
+        );
+    }
+
+    #[test]
+    fn test_should_convert_json_row() {
+        let values = json_to_record_values(User::columns(), &json!({ "id": 1, "name": "Alice" }))
+            .expect("failed to convert row");
+        assert_eq!(
+            values
+                .into_iter()
+                .map(|(column, value)| (column.name, value))
+                .collect::<Vec<_>>(),
+            vec![
+                ("id", Value::Uint32(Uint32(1))),
+                ("name", Value::Text(Text("Alice".to_string()))),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_should_not_convert_invalid_json_row() {
+        assert!(matches!(
+            json_to_record_values(User::columns(), &json!({ "id": "one", "name": "Alice" })),
+            Err(IcDbmsError::Query(QueryError::SerializationError(_)))
+        ));
+        assert!(matches!(
+            json_to_record_values(User::columns(), &json!({ "id": 1, "email": "alice@example.com" })),
+            Err(IcDbmsError::Query(QueryError::UnknownColumn(column))) if column == "email"
+        ));
+        assert!(matches!(
+            json_to_record_values(User::columns(), &json!([1, "Alice"])),
+            Err(IcDbmsError::Query(QueryError::SerializationError(_)))
+        ));
+    }
+
+    #[test]
+    fn test_should_convert_json_array() {
+        const TAGS: ColumnDef = ColumnDef {
+            name: "tags",
+            data_type: DataTypeKind::Array(&DataTypeKind::Uint32),
+            nullable: true,
+            primary_key: false,
+            unique: false,
+            auto_increment: false,
+            foreign_key: None,
+            check: None,
+        };
+
+        assert_eq!(
+            json_to_value(&TAGS, &json!([1, 2])).expect("failed to convert array"),
+            Value::Array(vec![Value::Uint32(Uint32(1)), Value::Uint32(Uint32(2))])
+        );
+        assert_eq!(
+            json_to_value(&TAGS, &json!([])).expect("failed to convert array"),
+            Value::Array(vec![])
+        );
+        assert_eq!(
+            json_to_value(&TAGS, &JsonValue::Null).expect("failed to convert array"),
+            Value::Null
+        );
+        for invalid in [json!(1), json!([1, null]), json!([1, "two"])] {
+            assert!(matches!(
+                json_to_value(&TAGS, &invalid),
+                Err(IcDbmsError::Query(QueryError::SerializationError(_)))
+            ));
+        }
+    }
+}
@@ -1,5 +1,167 @@
 //! This module exposes all the integrity validators for the DBMS.
 
 mod insert;
+mod update;
+
+use ic_dbms_api::prelude::{
+    ColumnDef, Database as _, Filter, ForeignFetcher as _, ForeignKeyDef, IcDbmsError,
+    IcDbmsResult, Query, QueryError, TableSchema, Value,
+};
 
 pub use self::insert::InsertIntegrityValidator;
+pub use self::update::UpdateIntegrityValidator;
+use crate::dbms::IcDbmsDatabase;
+
+/// Determines how the integrity violations of an insert are handled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ValidationMode {
+    /// Integrity violations are returned as errors, and the insert is rejected.
+    #[default]
+    Strict,
+    /// Integrity violations are collected as [`IntegrityWarning`]s, and the insert proceeds.
+    ///
+    /// Meant for migrations which import data violating the constraints, such as records
+    /// referencing records which are not loaded yet.
+    Lenient,
+}
+
+/// Kind of an integrity violation reported by an [`IntegrityWarning`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntegrityWarningKind {
+    /// A foreign key references a non-existing record.
+    ForeignKey,
+    /// A non-nullable column is missing.
+    NonNullable,
+    /// A CHECK constraint is not satisfied.
+    CheckConstraint,
+}
+
+/// An integrity violation tolerated by a [`ValidationMode::Lenient`] insert.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntegrityWarning {
+    /// Kind of the violation.
+    pub kind: IntegrityWarningKind,
+    /// Column violating the constraint.
+    pub column: &'static str,
+    /// Description of the violation.
+    pub message: String,
+}
+
+impl IntegrityWarning {
+    /// Creates a new [`IntegrityWarning`] describing the given error.
+    fn new(kind: IntegrityWarningKind, column: &'static str, error: &IcDbmsError) -> Self {
+        Self {
+            kind,
+            column,
+            message: error.to_string(),
+        }
+    }
+}
+
+/// Checks whether all the foreign keys reference existing records.
+fn check_foreign_keys<T>(
+    database: &IcDbmsDatabase,
+    record_values: &[(ColumnDef, Value)],
+) -> IcDbmsResult<()>
+where
+    T: TableSchema,
+{
+    record_values
+        .iter()
+        .filter_map(|(col, value)| col.foreign_key.as_ref().map(|fk| (fk, value)))
+        .try_for_each(|(fk, value)| check_foreign_key_existence::<T>(database, fk, value))
+}
+
+/// Checks whether a foreign key references an existing record.
+fn check_foreign_key_existence<T>(
+    database: &IcDbmsDatabase,
+    foreign_key: &ForeignKeyDef,
+    value: &Value,
+) -> IcDbmsResult<()>
+where
+    T: TableSchema,
+{
+    let res = T::foreign_fetcher().fetch(
+        database,
+        foreign_key.foreign_table,
+        foreign_key.local_column,
+        value.clone(),
+    )?;
+    if res.is_empty() {
+        Err(IcDbmsError::Query(
+            QueryError::ForeignKeyConstraintViolation {
+                field: foreign_key.local_column,
+                referencing_table: foreign_key.foreign_table,
+            },
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Checks whether any record of `T` has the given value in the unique `column`,
+/// ignoring the records matching `exclude`, if any.
+///
+/// Soft-deleted records are checked as well, since they're still stored.
+fn unique_value_exists<T>(
+    database: &IcDbmsDatabase,
+    column: &'static str,
+    value: Value,
+    exclude: Option<Filter>,
+) -> IcDbmsResult<bool>
+where
+    T: TableSchema,
+{
+    let mut filter = Filter::eq(column, value);
+    if let Some(exclude) = exclude {
+        filter = filter.and(exclude.not());
+    }
+    let query: Query<T> = Query::builder()
+        .field(T::primary_key())
+        .and_where(filter)
+        .include_deleted()
+        .limit(1)
+        .build();
+
+    Ok(!database.select(query)?.is_empty())
+}
+
+/// Checks whether the given record values satisfy all the CHECK constraints of the table.
+///
+/// Constraints on columns which are missing or `NULL` in the record are skipped,
+/// as in SQL a CHECK constraint evaluating to unknown is not a violation.
+fn check_constraints<T>(record_values: &[(ColumnDef, Value)]) -> IcDbmsResult<()>
+where
+    T: TableSchema,
+{
+    T::columns()
+        .iter()
+        .try_for_each(|column| check_column_constraint(column, record_values))
+}
+
+/// Checks whether the given record values satisfy the CHECK constraint of the given column, if any.
+fn check_column_constraint(
+    column: &ColumnDef,
+    record_values: &[(ColumnDef, Value)],
+) -> IcDbmsResult<()> {
+    let Some(check) = column.check.as_ref() else {
+        return Ok(());
+    };
+    let Some(value) = record_values
+        .iter()
+        .find(|(col_def, value)| col_def.name == column.name && !value.is_null())
+        .map(|(_, value)| value)
+    else {
+        return Ok(());
+    };
+
+    if !check.check(value, record_values)? {
+        return Err(IcDbmsError::Query(QueryError::CheckConstraintViolation {
+            column: column.name,
+            constraint_desc: check.description,
+            value: value.clone(),
+        }));
+    }
+
+    Ok(())
+}
@@ -1,9 +1,10 @@
 use ic_dbms_api::prelude::{
-    ColumnDef, Database as _, Filter, ForeignFetcher, ForeignKeyDef, IcDbmsError, IcDbmsResult,
-    Query, QueryError, TableSchema, Value,
+    ColumnDef, Database as _, Filter, IcDbmsError, IcDbmsResult, Query, QueryError, TableSchema,
+    Value,
 };
 
 use crate::dbms::IcDbmsDatabase;
+use crate::dbms::integrity::{IntegrityWarning, IntegrityWarningKind};
 
 /// Integrity validator for insert operations.
 pub struct InsertIntegrityValidator<'a, T>
@@ -35,31 +36,105 @@ where
     ///
     /// An insert is valid when:
     /// - No primary key conflicts with existing records.
+    /// - No value of a unique column is already used by an existing record.
     /// - All foreign keys reference existing records.
     /// - All non-nullable columns are provided.
+    /// - All the CHECK constraints are satisfied.
     pub fn validate(&self, record_values: &[(ColumnDef, Value)]) -> IcDbmsResult<()> {
         self.check_primary_key_conflict(record_values)?;
+        self.check_unique_columns(record_values)?;
         self.check_foreign_keys(record_values)?;
         self.check_non_nullable_fields(record_values)?;
+        super::check_constraints::<T>(record_values)?;
 
         Ok(())
     }
 
+    /// Verify whether the given insert record is valid, collecting the integrity violations
+    /// as [`IntegrityWarning`]s instead of failing.
+    ///
+    /// This is the validation of a [`crate::prelude::ValidationMode::Lenient`] insert:
+    /// foreign keys, non-nullable columns and CHECK constraints violations are all reported as warnings,
+    /// while primary key and unique conflicts are still errors, since the record would clash with the existing one.
+    pub fn validate_lenient(
+        &self,
+        record_values: &[(ColumnDef, Value)],
+    ) -> IcDbmsResult<Vec<IntegrityWarning>> {
+        self.check_primary_key_conflict(record_values)?;
+        self.check_unique_columns(record_values)?;
+
+        let mut warnings = vec![];
+        for (column, value) in record_values {
+            let Some(foreign_key) = column.foreign_key.as_ref() else {
+                continue;
+            };
+            match super::check_foreign_key_existence::<T>(self.database, foreign_key, value) {
+                Err(
+                    err @ IcDbmsError::Query(
+                        QueryError::BrokenForeignKeyReference { .. }
+                        | QueryError::ForeignKeyConstraintViolation { .. },
+                    ),
+                ) => warnings.push(IntegrityWarning::new(
+                    IntegrityWarningKind::ForeignKey,
+                    column.name,
+                    &err,
+                )),
+                result => result?,
+            }
+        }
+
+        for column in T::columns().iter().filter(|col| !col.nullable) {
+            if !record_values
+                .iter()
+                .any(|(col_def, _)| col_def.name == column.name)
+            {
+                warnings.push(IntegrityWarning::new(
+                    IntegrityWarningKind::NonNullable,
+                    column.name,
+                    &IcDbmsError::Query(QueryError::MissingNonNullableField(column.name)),
+                ));
+            }
+        }
+
+        for column in T::columns() {
+            match super::check_column_constraint(column, record_values) {
+                Err(err @ IcDbmsError::Query(QueryError::CheckConstraintViolation { .. })) => {
+                    warnings.push(IntegrityWarning::new(
+                        IntegrityWarningKind::CheckConstraint,
+                        column.name,
+                        &err,
+                    ))
+                }
+                result => result?,
+            }
+        }
+
+        Ok(warnings)
+    }
+
     /// Checks for primary key conflicts.
+    ///
+    /// With a composite primary key, a conflict requires all the primary key columns to match.
     fn check_primary_key_conflict(&self, record_values: &[(ColumnDef, Value)]) -> IcDbmsResult<()> {
-        let pk_name = T::primary_key();
-        let pk = record_values
+        let pk_names = T::primary_keys();
+        let pk = pk_names
             .iter()
-            .find(|(col_def, _)| col_def.name == pk_name)
-            .map(|(_, value)| value.clone())
-            .ok_or(IcDbmsError::Query(QueryError::MissingNonNullableField(
-                pk_name,
-            )))?;
+            .map(|pk_name| {
+                record_values
+                    .iter()
+                    .find(|(col_def, _)| col_def.name == *pk_name)
+                    .map(|(_, value)| value.clone())
+                    .ok_or(IcDbmsError::Query(QueryError::MissingNonNullableField(
+                        *pk_name,
+                    )))
+            })
+            .collect::<IcDbmsResult<Vec<Value>>>()?;
 
         // select
         let query: Query<T> = Query::builder()
-            .field(pk_name)
-            .and_where(Filter::Eq(pk_name, pk))
+            .field(T::primary_key())
+            .and_where(Filter::eq_composite(pk_names, pk))
+            .include_deleted()
             .build();
 
         let res = self.database.select(query)?;
@@ -70,36 +145,31 @@ where
         }
     }
 
-    /// Checks whether all the foreign keys reference existing records.
-    fn check_foreign_keys(&self, record_values: &[(ColumnDef, Value)]) -> IcDbmsResult<()> {
-        record_values
-            .iter()
-            .filter_map(|(col, value)| col.foreign_key.as_ref().map(|fk| (fk, value)))
-            .try_for_each(|(col, value)| self.check_foreign_key_existence(col, value))
+    /// Checks that the values of the unique columns are not used by any existing record.
+    ///
+    /// `NULL` values are never in conflict, as in SQL.
+    fn check_unique_columns(&self, record_values: &[(ColumnDef, Value)]) -> IcDbmsResult<()> {
+        for column in T::columns().iter().filter(|col| col.unique) {
+            let Some(value) = record_values
+                .iter()
+                .find(|(col_def, value)| col_def.name == column.name && !value.is_null())
+                .map(|(_, value)| value.clone())
+            else {
+                continue;
+            };
+            if super::unique_value_exists::<T>(self.database, column.name, value, None)? {
+                return Err(IcDbmsError::Query(QueryError::UniqueConstraintViolation {
+                    column: column.name,
+                }));
+            }
+        }
+
+        Ok(())
     }
 
-    /// Checks whether a foreign key references an existing record.
-    fn check_foreign_key_existence(
-        &self,
-        foreign_key: &ForeignKeyDef,
-        value: &Value,
-    ) -> IcDbmsResult<()> {
-        let res = T::foreign_fetcher().fetch(
-            self.database,
-            foreign_key.foreign_table,
-            foreign_key.local_column,
-            value.clone(),
-        )?;
-        if res.is_empty() {
-            Err(IcDbmsError::Query(
-                QueryError::ForeignKeyConstraintViolation {
-                    field: foreign_key.local_column,
-                    referencing_table: foreign_key.foreign_table,
-                },
-            ))
-        } else {
-            Ok(())
-        }
+    /// Checks whether all the foreign keys reference existing records.
+    fn check_foreign_keys(&self, record_values: &[(ColumnDef, Value)]) -> IcDbmsResult<()> {
+        super::check_foreign_keys::<T>(self.database, record_values)
     }
 
     /// Check whether all non-nullable fields are provided.
@@ -125,7 +195,7 @@ mod tests {
     use ic_dbms_api::prelude::DateTime;
 
     use super::*;
-    use crate::tests::{Message, Post, TestDatabaseSchema, User, load_fixtures};
+    use crate::tests::{Enrollment, Message, Post, TestDatabaseSchema, User, load_fixtures};
 
     #[test]
     fn test_should_not_pass_check_for_pk_conflict() {
@@ -168,6 +238,45 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_should_check_composite_pk_conflict() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        let validator = InsertIntegrityValidator::<Enrollment>::new(&dbms);
+        let enrollment = |student_id: u32, course: &str| {
+            Enrollment::columns()
+                .iter()
+                .cloned()
+                .zip(vec![
+                    Value::Uint32(student_id.into()),
+                    Value::Text(course.to_string().into()),
+                    Value::Uint32(27.into()),
+                ])
+                .collect::<Vec<(ColumnDef, Value)>>()
+        };
+
+        // both primary key columns match
+        assert!(matches!(
+            validator.validate(&enrollment(0, "math")),
+            Err(IcDbmsError::Query(QueryError::PrimaryKeyConflict))
+        ));
+        // only one of the primary key columns matches
+        assert!(validator.validate(&enrollment(1, "physics")).is_ok());
+        assert!(validator.validate(&enrollment(2, "math")).is_ok());
+
+        // missing primary key column
+        let values = enrollment(2, "math")
+            .into_iter()
+            .filter(|(col_def, _)| col_def.name != "course")
+            .collect::<Vec<_>>();
+        assert!(matches!(
+            validator.validate(&values),
+            Err(IcDbmsError::Query(QueryError::MissingNonNullableField(
+                "course"
+            )))
+        ));
+    }
+
     #[test]
     fn test_should_not_pass_check_for_fk_conflict() {
         load_fixtures();
@@ -0,0 +1,82 @@
+use ic_dbms_api::prelude::{
+    ColumnDef, Filter, IcDbmsError, IcDbmsResult, QueryError, TableSchema, Value,
+};
+
+use crate::dbms::IcDbmsDatabase;
+
+/// Integrity validator for update operations.
+pub struct UpdateIntegrityValidator<'a, T>
+where
+    T: TableSchema,
+{
+    database: &'a IcDbmsDatabase,
+    _marker: std::marker::PhantomData<T>,
+}
+
+impl<'a, T> UpdateIntegrityValidator<'a, T>
+where
+    T: TableSchema,
+{
+    /// Creates a new update integrity validator.
+    pub fn new(dbms: &'a IcDbmsDatabase) -> Self {
+        Self {
+            database: dbms,
+            _marker: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<T> UpdateIntegrityValidator<'_, T>
+where
+    T: TableSchema,
+{
+    /// Verify whether the given updated record is valid.
+    ///
+    /// `record_values` are the values of the record after the patch has been applied.
+    ///
+    /// An update is valid when:
+    /// - All foreign keys reference existing records.
+    /// - All the CHECK constraints are satisfied.
+    pub fn validate(&self, record_values: &[(ColumnDef, Value)]) -> IcDbmsResult<()> {
+        super::check_foreign_keys::<T>(self.database, record_values)?;
+        super::check_constraints::<T>(record_values)?;
+
+        Ok(())
+    }
+
+    /// Verify whether the patch of an update keeps the values of the unique columns unique.
+    ///
+    /// Only the unique columns set by the patch are checked: their value must not be used
+    /// by any record other than the `updated_records` ones, selected by `filter`,
+    /// and at most one record can be updated, since they would all get the same value.
+    pub fn validate_unique(
+        &self,
+        patch_values: &[(ColumnDef, Value)],
+        updated_records: usize,
+        filter: Option<Filter>,
+    ) -> IcDbmsResult<()> {
+        for (column, value) in patch_values {
+            let is_unique = T::columns()
+                .iter()
+                .any(|col| col.name == column.name && col.unique);
+            if !is_unique || value.is_null() || updated_records == 0 {
+                continue;
+            }
+
+            if updated_records > 1
+                || super::unique_value_exists::<T>(
+                    self.database,
+                    column.name,
+                    value.clone(),
+                    filter.clone(),
+                )?
+            {
+                return Err(IcDbmsError::Query(QueryError::UniqueConstraintViolation {
+                    column: column.name,
+                }));
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,206 @@
+//! This module exposes the logger used by the DBMS to report its activity.
+
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+thread_local! {
+    /// The logger of the DBMS.
+    ///
+    /// It defaults to a [`NoopLogger`]; install another logger with [`set_logger`].
+    pub static DBMS_LOGGER: RefCell<Box<dyn DbmsLogger>> = RefCell::new(Box::new(NoopLogger));
+}
+
+/// The severity of a log entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LogLevel::Debug => write!(f, "DEBUG"),
+            LogLevel::Info => write!(f, "INFO"),
+            LogLevel::Warn => write!(f, "WARN"),
+            LogLevel::Error => write!(f, "ERROR"),
+        }
+    }
+}
+
+/// A logger receiving the log entries of the DBMS.
+pub trait DbmsLogger {
+    /// Logs `message` with the given `level` and `context` key-value pairs.
+    fn log(&self, level: LogLevel, message: &str, context: &[(&str, &str)]);
+
+    /// Returns whether entries with the given `level` are logged.
+    ///
+    /// When it returns `false`, the entries are not even formatted.
+    fn enabled(&self, _level: LogLevel) -> bool {
+        true
+    }
+}
+
+/// A [`DbmsLogger`] discarding all the entries.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoopLogger;
+
+impl DbmsLogger for NoopLogger {
+    fn log(&self, _level: LogLevel, _message: &str, _context: &[(&str, &str)]) {}
+
+    fn enabled(&self, _level: LogLevel) -> bool {
+        false
+    }
+}
+
+/// A [`DbmsLogger`] printing the entries to the canister debug log.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct IcPrintLogger;
+
+impl DbmsLogger for IcPrintLogger {
+    fn log(&self, level: LogLevel, message: &str, context: &[(&str, &str)]) {
+        let mut line = format!("[{level}] {message}");
+        for (key, value) in context {
+            line.push_str(&format!(" {key}={value}"));
+        }
+
+        #[cfg(target_family = "wasm")]
+        ic_cdk::println!("{line}");
+        #[cfg(not(target_family = "wasm"))]
+        eprintln!("{line}");
+    }
+}
+
+/// An entry logged by a [`VecLogger`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DbmsLogEntry {
+    pub level: LogLevel,
+    pub message: String,
+    pub context: Vec<(String, String)>,
+}
+
+/// A [`DbmsLogger`] accumulating the entries in memory, mostly useful for testing.
+///
+/// Clones share the same entries, so a clone can be installed with [`set_logger`]
+/// while the original is kept to inspect them.
+#[derive(Debug, Default, Clone)]
+pub struct VecLogger {
+    entries: Rc<RefCell<Vec<DbmsLogEntry>>>,
+}
+
+impl VecLogger {
+    /// Returns the entries logged so far, in order.
+    pub fn entries(&self) -> Vec<DbmsLogEntry> {
+        self.entries.borrow().clone()
+    }
+}
+
+impl DbmsLogger for VecLogger {
+    fn log(&self, level: LogLevel, message: &str, context: &[(&str, &str)]) {
+        self.entries.borrow_mut().push(DbmsLogEntry {
+            level,
+            message: message.to_string(),
+            context: context
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+        });
+    }
+}
+
+/// Installs `logger` as the [`DBMS_LOGGER`].
+pub fn set_logger(logger: impl DbmsLogger + 'static) {
+    DBMS_LOGGER.with_borrow_mut(|current| *current = Box::new(logger));
+}
+
+/// Returns whether the [`DBMS_LOGGER`] logs entries with the given `level`.
+pub fn enabled(level: LogLevel) -> bool {
+    DBMS_LOGGER.with_borrow(|logger| logger.enabled(level))
+}
+
+/// Logs an entry with the [`DBMS_LOGGER`].
+pub fn log(level: LogLevel, message: &str, context: &[(&str, &str)]) {
+    DBMS_LOGGER.with_borrow(|logger| logger.log(level, message, context));
+}
+
+/// A macro for logging with the [`DBMS_LOGGER`].
+///
+/// The level is one of `DEBUG`, `INFO`, `WARN` and `ERROR`, followed by the format arguments of
+/// the message and optionally, after a `;`, the context as `key => value` pairs.
+/// Nothing is formatted if the logger is not enabled for the level.
+///
+/// # Examples
+///
+/// ```rust
+/// use ic_dbms_canister::dbms_log;
+/// let table = "users";
+/// dbms_log!(INFO, "record inserted");
+/// dbms_log!(WARN, "table {table} is almost full");
+/// dbms_log!(DEBUG, "record inserted"; "table" => table, "id" => 42);
+/// ```
+#[macro_export]
+macro_rules! dbms_log {
+    (@level DEBUG) => { $crate::dbms::logger::LogLevel::Debug };
+    (@level INFO) => { $crate::dbms::logger::LogLevel::Info };
+    (@level WARN) => { $crate::dbms::logger::LogLevel::Warn };
+    (@level ERROR) => { $crate::dbms::logger::LogLevel::Error };
+
+    ($level:ident, $fmt:literal $(, $arg:expr)* $(,)? $(; $($key:literal => $value:expr),+ $(,)?)?) => {{
+        let level = $crate::dbms_log!(@level $level);
+        if $crate::dbms::logger::enabled(level) {
+            let context: Vec<(&str, String)> = vec![$($(($key, $value.to_string())),+)?];
+            let context = context
+                .iter()
+                .map(|(key, value)| (*key, value.as_str()))
+                .collect::<Vec<_>>();
+            $crate::dbms::logger::log(level, &format!($fmt $(, $arg)*), &context);
+        }
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_should_log_entries_with_context() {
+        let logger = VecLogger::default();
+        set_logger(logger.clone());
+
+        let table = "users";
+        crate::dbms_log!(INFO, "inserted into {table}"; "id" => 1);
+        crate::dbms_log!(ERROR, "failed");
+
+        assert_eq!(
+            logger.entries(),
+            vec![
+                DbmsLogEntry {
+                    level: LogLevel::Info,
+                    message: "inserted into users".to_string(),
+                    context: vec![("id".to_string(), "1".to_string())],
+                },
+                DbmsLogEntry {
+                    level: LogLevel::Error,
+                    message: "failed".to_string(),
+                    context: vec![],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_should_not_log_with_noop_logger() {
+        let logger = VecLogger::default();
+        set_logger(logger.clone());
+        set_logger(NoopLogger);
+
+        crate::dbms_log!(INFO, "discarded");
+
+        assert!(!enabled(LogLevel::Error));
+        assert!(logger.entries().is_empty());
+    }
+}
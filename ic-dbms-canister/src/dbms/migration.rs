@@ -0,0 +1,616 @@
+//! This module exposes the online schema migrations, which change the columns of a table
+//! while the canister keeps serving reads and writes.
+//!
+//! A migration walks the [`MigrationPhase`]s in order:
+//!
+//! 1. [`MigrationPhase::DualWrite`]: the records of the table are copied to new pages with the new columns,
+//!    and the records inserted since then are written with both the old and the new columns;
+//!    reads are served from the old pages.
+//! 2. [`MigrationPhase::CutOver`]: records are still written with both the columns, but reads are served
+//!    from the new pages.
+//! 3. [`MigrationPhase::Cleanup`]: the new pages replace the old ones, which are dropped.
+//!
+//! Records with the new columns are encoded as a record with the same fields encoded with `#[derive(Encode)]`,
+//! so after the migration they can be read by the upgraded [`TableSchema`].
+//!
+//! Versioned migrations, which are run once when the canister is upgraded, are run by the [`MigrationRunner`].
+
+mod runner;
+
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use ic_dbms_api::prelude::{
+    Blob, Boolean, ColumnDef, DataSize, DataTypeKind, Date, DateTime, Decimal, DecodeError, Encode,
+    Float64, IcDbmsError, IcDbmsResult, Int32, Int64, MSize, MemoryError, MemoryResult, Principal,
+    QueryError, TableError, TableFingerprint, TableSchema, Text, Uint32, Uint64, Uuid, Value,
+};
+
+pub use self::runner::{Migration, MigrationRunner};
+use crate::memory::{MigrationPhase, SCHEMA_REGISTRY, TableRegistry};
+
+thread_local! {
+    /// The new columns of the tables being migrated.
+    ///
+    /// Columns can't be stored in stable memory, so a migration interrupted by an upgrade must be begun again.
+    static MIGRATION_COLUMNS: RefCell<HashMap<TableFingerprint, Vec<ColumnDef>>> = RefCell::new(HashMap::new());
+}
+
+/// Mask applied to a table fingerprint to derive the fingerprint of the table storing its migrated records.
+const MIGRATION_FINGERPRINT_MASK: TableFingerprint = 0x3164_3164_3164_3164;
+
+/// The records of a table, as the values of their columns.
+type Rows = Vec<Vec<(ColumnDef, Value)>>;
+
+/// Handle of an online schema migration begun with [`crate::prelude::IcDbmsDatabase::begin_migration`].
+#[derive(Debug, Clone)]
+pub struct MigrationHandle {
+    /// Fingerprint of the migrated table.
+    table: TableFingerprint,
+    /// The columns the table is migrated to.
+    new_columns: Vec<ColumnDef>,
+    /// Reads the records of the table with the old columns.
+    read_old: fn() -> IcDbmsResult<Rows>,
+}
+
+impl MigrationHandle {
+    /// Begins the migration of the table `T` to the given columns, copying its records to the new pages.
+    ///
+    /// Values are copied by column name; integers are widened if the type of the column changed
+    /// (e.g. from `Uint32` to `Uint64`), while added nullable columns are set to `NULL`.
+    /// A migration already in progress for `T` is begun again.
+    pub(crate) fn begin<T>(new_columns: &[ColumnDef]) -> IcDbmsResult<Self>
+    where
+        T: TableSchema,
+    {
+        if let Some(pk) = T::primary_keys().iter().find(|pk| {
+            !new_columns
+                .iter()
+                .any(|column| column.name == **pk && column.primary_key)
+        }) {
+            return Err(IcDbmsError::Query(QueryError::MissingNonNullableField(*pk)));
+        }
+
+        // encode all the records before writing anything, since encoding may fail
+        let rows = read_records::<T>()?
+            .iter()
+            .map(|values| EncodedRow::encode(new_columns, values))
+            .collect::<IcDbmsResult<Vec<_>>>()?;
+
+        let mut new_registry = migration_registry(T::fingerprint())?;
+        new_registry.drop_pages()?;
+        new_registry.insert_batch(rows)?;
+
+        SCHEMA_REGISTRY.with_borrow_mut(|sr| {
+            let old_schema_version = sr.schema_version(T::fingerprint());
+            sr.set_migration_phase(
+                T::fingerprint(),
+                Some(MigrationPhase::DualWrite {
+                    old_schema_version,
+                    new_schema_version: old_schema_version.next(),
+                }),
+            )
+        })?;
+        MIGRATION_COLUMNS
+            .with_borrow_mut(|columns| columns.insert(T::fingerprint(), new_columns.to_vec()));
+
+        Ok(Self {
+            table: T::fingerprint(),
+            new_columns: new_columns.to_vec(),
+            read_old: read_records::<T>,
+        })
+    }
+
+    /// Returns the current [`MigrationPhase`], or `None` if the migration is complete.
+    pub fn phase(&self) -> Option<MigrationPhase> {
+        SCHEMA_REGISTRY.with_borrow(|sr| sr.migration_phase(self.table))
+    }
+
+    /// Reads the records of the migrated table.
+    ///
+    /// Until [`MigrationHandle::cut_over`], records are read from the old pages with the old columns;
+    /// since then, they're read from the new pages with the new columns.
+    pub fn read(&self) -> IcDbmsResult<Rows> {
+        match self.phase() {
+            Some(MigrationPhase::DualWrite { .. }) => (self.read_old)(),
+            _ => {
+                let registry = migration_registry(self.table)?;
+                let mut reader = registry.read::<EncodedRow>();
+                let mut rows = vec![];
+                while let Some(next) = reader.try_next()? {
+                    rows.push(next.record.decode_values(&self.new_columns)?);
+                }
+                Ok(rows)
+            }
+        }
+    }
+
+    /// Switches the reads of [`MigrationHandle::read`] to the new pages.
+    pub fn cut_over(&self) -> IcDbmsResult<()> {
+        let Some(MigrationPhase::DualWrite {
+            old_schema_version,
+            new_schema_version,
+        }) = self.phase()
+        else {
+            return Ok(());
+        };
+
+        SCHEMA_REGISTRY.with_borrow_mut(|sr| {
+            sr.set_migration_phase(
+                self.table,
+                Some(MigrationPhase::CutOver {
+                    old_schema_version,
+                    new_schema_version,
+                }),
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Completes the migration, cutting over if needed and dropping the old pages.
+    ///
+    /// The new pages become the pages of the table, so the canister must then be upgraded
+    /// with the [`TableSchema`] matching the new columns.
+    pub fn complete(self) -> IcDbmsResult<()> {
+        self.cut_over()?;
+        let Some(phase) = self.phase() else {
+            return Ok(());
+        };
+        let (old_schema_version, new_schema_version) = phase.schema_versions();
+        let migration_fingerprint = self.table ^ MIGRATION_FINGERPRINT_MASK;
+
+        SCHEMA_REGISTRY.with_borrow_mut(|sr| {
+            sr.set_migration_phase(
+                self.table,
+                Some(MigrationPhase::Cleanup {
+                    old_schema_version,
+                    new_schema_version,
+                }),
+            )?;
+            sr.swap_table_pages(self.table, migration_fingerprint)
+        })?;
+        // the old pages now belong to the migration table
+        migration_registry(self.table)?.drop_pages()?;
+
+        SCHEMA_REGISTRY.with_borrow_mut(|sr| {
+            sr.set_table_columns(self.table, &self.new_columns)?;
+            sr.set_schema_version(self.table, new_schema_version)?;
+            sr.set_migration_phase(self.table, None)
+        })?;
+        MIGRATION_COLUMNS.with_borrow_mut(|columns| columns.remove(&self.table));
+
+        Ok(())
+    }
+}
+
+/// Encodes the record with the given values with the new columns of the table, if the table is being dual-written.
+///
+/// Fails with [`QueryError::InvalidQuery`] if the migration has been interrupted by an upgrade.
+pub(crate) fn prepare_dual_write(
+    table: TableFingerprint,
+    record_values: &[(ColumnDef, Value)],
+) -> IcDbmsResult<Option<EncodedRow>> {
+    let is_dual_write = SCHEMA_REGISTRY
+        .with_borrow(|sr| sr.migration_phase(table))
+        .is_some_and(|phase| phase.is_dual_write());
+    if !is_dual_write {
+        return Ok(None);
+    }
+
+    MIGRATION_COLUMNS.with_borrow(|columns| {
+        let new_columns = columns.get(&table).ok_or_else(|| {
+            IcDbmsError::Query(QueryError::InvalidQuery(
+                "the migration of the table has been interrupted by an upgrade and must be begun again"
+                    .to_string(),
+            ))
+        })?;
+        EncodedRow::encode(new_columns, record_values).map(Some)
+    })
+}
+
+/// Writes the record encoded by [`prepare_dual_write`] to the new pages of the table.
+pub(crate) fn dual_write(table: TableFingerprint, row: EncodedRow) -> IcDbmsResult<()> {
+    migration_registry(table)?.insert(row)?;
+    Ok(())
+}
+
+/// Fails with [`QueryError::InvalidQuery`] if the table is being migrated,
+/// since updates and deletes are not propagated to the new pages.
+pub(crate) fn check_not_migrating(table: TableFingerprint) -> IcDbmsResult<()> {
+    if SCHEMA_REGISTRY
+        .with_borrow(|sr| sr.migration_phase(table))
+        .is_some()
+    {
+        return Err(IcDbmsError::Query(QueryError::InvalidQuery(
+            "records can't be updated or deleted while the table is being migrated".to_string(),
+        )));
+    }
+
+    Ok(())
+}
+
+/// Reads all the records of the table `T` from its pages.
+fn read_records<T>() -> IcDbmsResult<Rows>
+where
+    T: TableSchema,
+{
+    let pages = SCHEMA_REGISTRY
+        .with_borrow(|sr| sr.table_registry_page::<T>())
+        .ok_or(IcDbmsError::Table(TableError::TableNotFound))?;
+    let registry = TableRegistry::load(pages)?;
+    let mut reader = registry.read::<T>();
+    let mut rows = vec![];
+    while let Some(next) = reader.try_next()? {
+        rows.push(next.record.to_values());
+    }
+
+    Ok(rows)
+}
+
+/// Loads the registry storing the records of the given table with the new columns.
+fn migration_registry(table: TableFingerprint) -> IcDbmsResult<TableRegistry> {
+    let pages = SCHEMA_REGISTRY
+        .with_borrow_mut(|sr| sr.register_fingerprint(table ^ MIGRATION_FINGERPRINT_MASK))?;
+    Ok(TableRegistry::load(pages)?)
+}
+
+/// A record encoded with the new columns of a migrated table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct EncodedRow(Vec<u8>);
+
+impl EncodedRow {
+    /// Encodes the given values with the given columns.
+    ///
+    /// Each value is encoded as a field of a record with `#[derive(Encode)]`,
+    /// so nullable columns are encoded as a [`ic_dbms_api::prelude::Nullable`].
+    fn encode(columns: &[ColumnDef], values: &[(ColumnDef, Value)]) -> IcDbmsResult<Self> {
+        let mut encoded = vec![];
+        for column in columns {
+            let value = values
+                .iter()
+                .find(|(col_def, _)| col_def.name == column.name)
+                .map_or(&Value::Null, |(_, value)| value);
+            match (value.is_null(), column.nullable) {
+                (true, true) => encoded.push(0),
+                (true, false) => {
+                    return Err(IcDbmsError::Query(QueryError::MissingNonNullableField(
+                        column.name,
+                    )));
+                }
+                (false, nullable) => {
+                    if nullable {
+                        encoded.push(1);
+                    }
+                    encoded.extend_from_slice(&encode_value(column, value)?);
+                }
+            }
+        }
+
+        Ok(Self(encoded))
+    }
+
+    /// Decodes the values of the given columns.
+    fn decode_values(&self, columns: &[ColumnDef]) -> MemoryResult<Vec<(ColumnDef, Value)>> {
+        let mut offset = 0;
+        let mut values = Vec::with_capacity(columns.len());
+        for column in columns {
+            if column.nullable {
+                let is_set = *self
+                    .0
+                    .get(offset)
+                    .ok_or(MemoryError::DecodeError(DecodeError::TooShort))?;
+                offset += 1;
+                if is_set == 0 {
+                    values.push((*column, Value::Null));
+                    continue;
+                }
+            }
+            let data = self
+                .0
+                .get(offset..)
+                .ok_or(MemoryError::DecodeError(DecodeError::TooShort))?;
+            let (value, size) = decode_value(column.data_type, data)?;
+            offset += size as usize;
+            values.push((*column, value));
+        }
+
+        Ok(values)
+    }
+}
+
+impl Encode for EncodedRow {
+    const SIZE: DataSize = DataSize::Dynamic;
+
+    fn size(&self) -> MSize {
+        self.0.len() as MSize
+    }
+
+    fn encode(&'_ self) -> Cow<'_, [u8]> {
+        Cow::Borrowed(&self.0)
+    }
+
+    fn decode(data: Cow<[u8]>) -> MemoryResult<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self(data.into_owned()))
+    }
+}
+
+/// Encodes the given non-`NULL` value as a value of the given column, widening integers if needed.
+fn encode_value(column: &ColumnDef, value: &Value) -> IcDbmsResult<Vec<u8>> {
+    let encoded = match (column.data_type, value) {
+        // arrays are encoded as the amount of elements followed by the elements
+        (DataTypeKind::Array(element), Value::Array(values)) => {
+            let element_column = ColumnDef {
+                data_type: *element,
+                ..*column
+            };
+            let mut encoded = (values.len() as u32).to_le_bytes().to_vec();
+            for value in values {
+                encoded.extend(encode_value(&element_column, value)?);
+            }
+            encoded
+        }
+        (DataTypeKind::Blob, Value::Blob(v)) => v.encode().into_owned(),
+        (DataTypeKind::Boolean, Value::Boolean(v)) => v.encode().into_owned(),
+        (DataTypeKind::Date, Value::Date(v)) => v.encode().into_owned(),
+        (DataTypeKind::DateTime, Value::DateTime(v)) => v.encode().into_owned(),
+        (DataTypeKind::Decimal, Value::Decimal(v)) => v.encode().into_owned(),
+        (DataTypeKind::Float64, Value::Float64(v)) => v.encode().into_owned(),
+        (DataTypeKind::Float64, Value::Int32(v)) => Float64(v.0.into()).encode().into_owned(),
+        (DataTypeKind::Float64, Value::Uint32(v)) => Float64(v.0.into()).encode().into_owned(),
+        (DataTypeKind::Int32, Value::Int32(v)) => v.encode().into_owned(),
+        (DataTypeKind::Int64, Value::Int64(v)) => v.encode().into_owned(),
+        (DataTypeKind::Int64, Value::Int32(v)) => Int64(v.0.into()).encode().into_owned(),
+        (DataTypeKind::Int64, Value::Uint32(v)) => Int64(v.0.into()).encode().into_owned(),
+        (DataTypeKind::Principal, Value::Principal(v)) => v.encode().into_owned(),
+        (DataTypeKind::Text, Value::Text(v)) => v.encode().into_owned(),
+        (DataTypeKind::Uint32, Value::Uint32(v)) => v.encode().into_owned(),
+        (DataTypeKind::Uint64 | DataTypeKind::AutoIncrement, Value::Uint64(v)) => {
+            v.encode().into_owned()
+        }
+        (DataTypeKind::Uint64 | DataTypeKind::AutoIncrement, Value::Uint32(v)) => {
+            Uint64(v.0.into()).encode().into_owned()
+        }
+        (DataTypeKind::Uuid, Value::Uuid(v)) => v.encode().into_owned(),
+        (data_type, value) => {
+            return Err(IcDbmsError::Query(QueryError::TypeMismatch {
+                column: column.name,
+                expected: data_type.to_value_type_name(),
+                found: value.type_name(),
+            }));
+        }
+    };
+
+    Ok(encoded)
+}
+
+/// Decodes a non-`NULL` value of the given type, returning it along with its encoded size.
+fn decode_value(data_type: DataTypeKind, data: &[u8]) -> MemoryResult<(Value, MSize)> {
+    fn decode<E>(data: &[u8], into_value: fn(E) -> Value) -> MemoryResult<(Value, MSize)>
+    where
+        E: Encode,
+    {
+        let value = E::decode_ref(data)?;
+        let size = value.size();
+        Ok((into_value(value), size))
+    }
+
+    match data_type {
+        DataTypeKind::Array(element) => {
+            let len = data
+                .get(..4)
+                .ok_or(MemoryError::DecodeError(DecodeError::TooShort))?;
+            let len = u32::from_le_bytes(len.try_into()?);
+            let mut size = 4;
+            let mut values = Vec::with_capacity(len as usize);
+            for _ in 0..len {
+                let (value, value_size) = decode_value(
+                    *element,
+                    data.get(size as usize..)
+                        .ok_or(MemoryError::DecodeError(DecodeError::TooShort))?,
+                )?;
+                values.push(value);
+                size += value_size;
+            }
+            Ok((Value::Array(values), size))
+        }
+        DataTypeKind::Blob => decode::<Blob>(data, Value::Blob),
+        DataTypeKind::Boolean => decode::<Boolean>(data, Value::Boolean),
+        DataTypeKind::Date => decode::<Date>(data, Value::Date),
+        DataTypeKind::DateTime => decode::<DateTime>(data, Value::DateTime),
+        DataTypeKind::Decimal => decode::<Decimal>(data, Value::Decimal),
+        DataTypeKind::Float64 => decode::<Float64>(data, Value::Float64),
+        DataTypeKind::Int32 => decode::<Int32>(data, Value::Int32),
+        DataTypeKind::Int64 => decode::<Int64>(data, Value::Int64),
+        DataTypeKind::Principal => decode::<Principal>(data, Value::Principal),
+        DataTypeKind::Text => decode::<Text>(data, Value::Text),
+        DataTypeKind::Uint32 => decode::<Uint32>(data, Value::Uint32),
+        DataTypeKind::Uint64 | DataTypeKind::AutoIncrement => decode::<Uint64>(data, Value::Uint64),
+        DataTypeKind::Uuid => decode::<Uuid>(data, Value::Uuid),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use ic_dbms_api::prelude::{Array, Database as _, columns_fingerprint};
+
+    use super::*;
+    use crate::memory::SchemaVersion;
+    use crate::prelude::IcDbmsDatabase;
+    use crate::tests::{
+        TestDatabaseSchema, USERS_FIXTURES, User, UserInsertRequest, load_fixtures,
+    };
+
+    const EMAIL: ColumnDef = ColumnDef::builder()
+        .name("email")
+        .data_type(DataTypeKind::Text)
+        .nullable()
+        .build();
+
+    fn new_columns() -> Vec<ColumnDef> {
+        let mut columns = User::columns().to_vec();
+        columns.push(EMAIL);
+        columns
+    }
+
+    fn ids(rows: &Rows) -> Vec<u32> {
+        let mut ids = rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .find(|(column, _)| column.name == "id")
+                    .and_then(|(_, value)| value.as_uint32())
+                    .expect("row should have an id")
+                    .0
+            })
+            .collect::<Vec<_>>();
+        ids.sort_unstable();
+        ids
+    }
+
+    #[test]
+    fn test_should_migrate_table_online() {
+        load_fixtures();
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        let new_columns = new_columns();
+        let handle = dbms
+            .begin_migration::<User>(&new_columns)
+            .expect("failed to begin migration");
+        assert!(matches!(
+            handle.phase(),
+            Some(MigrationPhase::DualWrite {
+                old_schema_version: SchemaVersion(1),
+                new_schema_version: SchemaVersion(2)
+            })
+        ));
+
+        // during dual-write, reads are served with the old columns
+        dbms.insert::<User>(UserInsertRequest {
+            id: Uint32(1000),
+            name: Text("Dual".to_string()),
+        })
+        .expect("failed to insert user");
+        let rows = handle.read().expect("failed to read");
+        assert_eq!(rows.len(), USERS_FIXTURES.len() + 1);
+        assert!(rows.iter().all(|row| row.len() == 2));
+        assert!(ids(&rows).contains(&1000));
+        // updates and deletes are not propagated to the new pages
+        assert!(check_not_migrating(User::fingerprint()).is_err());
+
+        // after cut over, reads are served with the new columns
+        handle.cut_over().expect("failed to cut over");
+        dbms.insert::<User>(UserInsertRequest {
+            id: Uint32(1001),
+            name: Text("Cut over".to_string()),
+        })
+        .expect("failed to insert user");
+        let new_rows = handle.read().expect("failed to read");
+        assert_eq!(new_rows.len(), USERS_FIXTURES.len() + 2);
+        assert!(
+            new_rows
+                .iter()
+                .all(|row| row.len() == 3 && row[2] == (EMAIL, Value::Null))
+        );
+        assert!(ids(&new_rows).ends_with(&[1000, 1001]));
+
+        // cleanup replaces the old pages with the new ones
+        handle.complete().expect("failed to complete migration");
+        SCHEMA_REGISTRY.with_borrow(|sr| {
+            assert_eq!(sr.migration_phase(User::fingerprint()), None);
+            assert_eq!(sr.schema_version(User::fingerprint()), SchemaVersion(2));
+            assert_eq!(
+                sr.columns_fingerprint(User::fingerprint()),
+                Some(columns_fingerprint(&new_columns))
+            );
+            assert_eq!(
+                sr.table_columns(User::fingerprint()).map(<[_]>::len),
+                Some(3)
+            );
+        });
+        let pages = SCHEMA_REGISTRY
+            .with_borrow(|sr| sr.table_registry_page::<User>())
+            .expect("table should be registered");
+        let registry = TableRegistry::load(pages).expect("failed to load registry");
+        let mut reader = registry.read::<EncodedRow>();
+        let mut migrated = vec![];
+        while let Some(next) = reader.try_next().expect("failed to read") {
+            migrated.push(
+                next.record
+                    .decode_values(&new_columns)
+                    .expect("failed to decode row"),
+            );
+        }
+        assert_eq!(ids(&migrated), ids(&new_rows));
+    }
+
+    #[test]
+    fn test_should_encode_rows_with_new_columns() {
+        const ID: ColumnDef = ColumnDef::builder()
+            .name("id")
+            .data_type(DataTypeKind::Uint64)
+            .primary_key()
+            .build();
+        let values = User::columns()
+            .iter()
+            .copied()
+            .zip([
+                Value::Uint32(Uint32(7)),
+                Value::Text(Text("Alice".to_string())),
+            ])
+            .collect::<Vec<_>>();
+
+        let columns = [ID, EMAIL];
+        let row = EncodedRow::encode(&columns, &values).expect("failed to encode row");
+        assert_eq!(
+            row.decode_values(&columns).expect("failed to decode row"),
+            vec![(ID, Value::Uint64(Uint64(7))), (EMAIL, Value::Null)]
+        );
+
+        // required columns must have a value
+        let required_email = ColumnDef {
+            nullable: false,
+            ..EMAIL
+        };
+        assert!(matches!(
+            EncodedRow::encode(&[ID, required_email], &values),
+            Err(IcDbmsError::Query(QueryError::MissingNonNullableField(
+                "email"
+            )))
+        ));
+    }
+
+    #[test]
+    fn test_should_encode_array_values_as_their_type() {
+        const TAGS: ColumnDef = ColumnDef::builder()
+            .name("tags")
+            .data_type(DataTypeKind::Array(&DataTypeKind::Uint64))
+            .build();
+        let tags = [Uint32(1), Uint32(2)];
+
+        // elements are widened as the other values
+        let row = EncodedRow::encode(
+            &[TAGS],
+            &[(
+                TAGS,
+                Value::Array(tags.iter().copied().map(Value::Uint32).collect()),
+            )],
+        )
+        .expect("failed to encode row");
+        assert_eq!(
+            row.0,
+            Array(vec![Uint64(1), Uint64(2)]).encode().into_owned()
+        );
+        assert_eq!(
+            row.decode_values(&[TAGS]).expect("failed to decode row"),
+            vec![(
+                TAGS,
+                Value::Array(vec![Value::Uint64(Uint64(1)), Value::Uint64(Uint64(2))])
+            )]
+        );
+    }
+}
@@ -0,0 +1,371 @@
+use ic_dbms_api::prelude::{IcDbmsError, IcDbmsResult, MemoryResult, QueryError};
+
+use crate::dbms::IcDbmsDatabase;
+use crate::memory::{SCHEMA_REGISTRY, SchemaRegistry};
+use crate::utils::trap;
+
+/// A versioned migration of the database, run once by the [`MigrationRunner`].
+///
+/// A migration changes the schema with [`Migration::up`], e.g. applying [`crate::memory::AlterTableOp`]s
+/// to match the upgraded [`ic_dbms_api::prelude::TableSchema`]s, and then the records with [`Migration::run`].
+pub trait Migration {
+    /// Version of the database after the migration; the first migration has version `1`.
+    fn version(&self) -> u32;
+
+    /// Changes the schema stored in the [`SchemaRegistry`]; nothing is changed by default.
+    fn up(&self, _registry: &mut SchemaRegistry) -> MemoryResult<()> {
+        Ok(())
+    }
+
+    /// Runs the migration on the database, after [`Migration::up`]; nothing is run by default.
+    fn run(&self, _db: &mut IcDbmsDatabase) -> IcDbmsResult<()> {
+        Ok(())
+    }
+}
+
+/// Runs the [`Migration`]s which have not been run yet, usually at `post_upgrade`.
+///
+/// The version of the database is stored in the [`crate::memory::SchemaRegistry`]
+/// and increased after each migration.
+#[derive(Default)]
+pub struct MigrationRunner {
+    migrations: Vec<Box<dyn Migration>>,
+}
+
+impl std::fmt::Debug for MigrationRunner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MigrationRunner")
+            .field(
+                "migrations",
+                &self
+                    .migrations
+                    .iter()
+                    .map(|migration| migration.version())
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl MigrationRunner {
+    /// Registers a migration; migrations can be registered in any order.
+    pub fn register(&mut self, migration: Box<dyn Migration>) {
+        self.migrations.push(migration);
+    }
+
+    /// Runs the migrations with a version greater than the version of the database, in ascending order.
+    ///
+    /// The version of the database is stored after each migration, so if a migration fails,
+    /// the following ones are not run and the database stays at the version of the last successful one.
+    /// If [`Migration::up`] fails, the canister traps instead, since the registry may have been partially changed,
+    /// so the schema changes of the migration are rolled back.
+    ///
+    /// Returns the versions of the migrations which have been run.
+    pub fn run_pending(&self, db: &mut IcDbmsDatabase) -> IcDbmsResult<Vec<u32>> {
+        let current = SCHEMA_REGISTRY.with_borrow(|sr| sr.database_version());
+        let mut pending = self
+            .migrations
+            .iter()
+            .filter(|migration| migration.version() > current)
+            .collect::<Vec<_>>();
+        pending.sort_by_key(|migration| migration.version());
+        if let Some(duplicate) = pending
+            .windows(2)
+            .find(|pair| pair[0].version() == pair[1].version())
+        {
+            return Err(IcDbmsError::Query(QueryError::InvalidQuery(format!(
+                "migration version {} registered twice",
+                duplicate[0].version()
+            ))));
+        }
+
+        let mut run = Vec::with_capacity(pending.len());
+        for migration in pending {
+            let version = migration.version();
+            if let Err(err) = SCHEMA_REGISTRY.with_borrow_mut(|sr| migration.up(sr)) {
+                crate::dbms_log!(ERROR, "migration schema change failed: {err}"; "version" => version);
+                trap(format!(
+                    "migration {version} failed to change the schema: {err}"
+                ));
+            }
+            if let Err(err) = migration.run(db) {
+                crate::dbms_log!(ERROR, "migration failed: {err}"; "version" => version);
+                return Err(err);
+            }
+            SCHEMA_REGISTRY.with_borrow_mut(|sr| sr.set_database_version(version))?;
+            crate::dbms_log!(INFO, "migration run"; "version" => version);
+            run.push(version);
+        }
+
+        Ok(run)
+    }
+}
+
+/// Makes a [`MigrationRunner`] with the given [`Migration`]s registered.
+///
+/// ```rust,ignore
+/// #[ic_cdk::post_upgrade]
+/// fn post_upgrade() {
+///     let mut db = IcDbmsDatabase::oneshot(MySchema);
+///     ic_dbms_canister::migrations![AddUsersEmail, SplitPostsBody]
+///         .run_pending(&mut db)
+///         .expect("failed to run migrations");
+/// }
+/// ```
+#[macro_export]
+macro_rules! migrations {
+    ($($migration:expr),* $(,)?) => {{
+        #[allow(unused_mut)]
+        let mut runner = $crate::dbms::migration::MigrationRunner::default();
+        $(runner.register(Box::new($migration));)*
+        runner
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use ic_dbms_api::prelude::{
+        ColumnDef, DataTypeKind, Database as _, MemoryError, TableSchema as _, columns_fingerprint,
+    };
+
+    use super::*;
+    use crate::memory::{AlterTableOp, ColumnLayout};
+    use crate::tests::{TestDatabaseSchema, User, UserInsertRequest, load_fixtures};
+
+    /// A migration inserting a user with its version as id, and recording its version in `log`.
+    struct InsertUser {
+        version: u32,
+        log: Rc<RefCell<Vec<u32>>>,
+    }
+
+    impl Migration for InsertUser {
+        fn version(&self) -> u32 {
+            self.version
+        }
+
+        fn run(&self, db: &mut IcDbmsDatabase) -> IcDbmsResult<()> {
+            self.log.borrow_mut().push(self.version);
+            db.insert::<User>(UserInsertRequest {
+                id: (1000 + self.version).into(),
+                name: format!("Migrated {}", self.version).into(),
+            })
+        }
+    }
+
+    /// A migration which always fails.
+    struct Failing(u32);
+
+    impl Migration for Failing {
+        fn version(&self) -> u32 {
+            self.0
+        }
+
+        fn run(&self, _db: &mut IcDbmsDatabase) -> IcDbmsResult<()> {
+            Err(IcDbmsError::Query(QueryError::InvalidQuery(
+                "failing migration".to_string(),
+            )))
+        }
+    }
+
+    /// A migration applying an [`AlterTableOp`] to the users table.
+    struct AlterUsers(u32, AlterTableOp);
+
+    impl Migration for AlterUsers {
+        fn version(&self) -> u32 {
+            self.0
+        }
+
+        fn up(&self, registry: &mut SchemaRegistry) -> MemoryResult<()> {
+            // any error makes the runner trap
+            if !registry.alter_table(User::fingerprint(), self.1.clone())? {
+                return Err(MemoryError::OutOfBounds);
+            }
+            Ok(())
+        }
+    }
+
+    const EMAIL: ColumnDef = ColumnDef::builder()
+        .name("email")
+        .data_type(DataTypeKind::Text)
+        .nullable()
+        .build();
+
+    const CONTACT: ColumnDef = ColumnDef::builder()
+        .name("contact")
+        .data_type(DataTypeKind::Text)
+        .nullable()
+        .build();
+
+    /// Returns the columns of the users table with the given column appended.
+    fn user_columns_with(column: ColumnDef) -> Vec<ColumnDef> {
+        let mut columns = User::columns().to_vec();
+        columns.push(column);
+        columns
+    }
+
+    #[test]
+    fn test_should_alter_table_in_migrations() {
+        load_fixtures();
+        SCHEMA_REGISTRY
+            .with_borrow_mut(|sr| sr.set_database_version(0))
+            .expect("failed to set database version");
+        let initial_version =
+            SCHEMA_REGISTRY.with_borrow(|sr| sr.schema_version(User::fingerprint()));
+
+        let add = crate::migrations![AlterUsers(1, AlterTableOp::AddColumn(EMAIL))];
+        let mut db = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        assert_eq!(
+            add.run_pending(&mut db).expect("failed to run migrations"),
+            vec![1]
+        );
+        SCHEMA_REGISTRY.with_borrow(|sr| {
+            let columns = user_columns_with(EMAIL);
+            assert_eq!(
+                sr.table_columns(User::fingerprint()),
+                Some(
+                    columns
+                        .iter()
+                        .map(ColumnLayout::from)
+                        .collect::<Vec<_>>()
+                        .as_slice()
+                )
+            );
+            assert_eq!(
+                sr.columns_fingerprint(User::fingerprint()),
+                Some(columns_fingerprint(&columns))
+            );
+            assert_eq!(
+                sr.schema_version(User::fingerprint()),
+                initial_version.next()
+            );
+        });
+
+        let rename = crate::migrations![
+            AlterUsers(1, AlterTableOp::AddColumn(EMAIL)),
+            AlterUsers(
+                2,
+                AlterTableOp::RenameColumn {
+                    from: "email",
+                    to: "contact"
+                }
+            )
+        ];
+        assert_eq!(
+            rename
+                .run_pending(&mut db)
+                .expect("failed to run migrations"),
+            vec![2]
+        );
+        let registry = SchemaRegistry::load().expect("failed to load schema registry");
+        let columns = user_columns_with(CONTACT);
+        assert_eq!(
+            registry.table_columns(User::fingerprint()),
+            Some(
+                columns
+                    .iter()
+                    .map(ColumnLayout::from)
+                    .collect::<Vec<_>>()
+                    .as_slice()
+            )
+        );
+        assert_eq!(
+            registry.columns_fingerprint(User::fingerprint()),
+            Some(columns_fingerprint(&columns))
+        );
+        assert_eq!(
+            registry.schema_version(User::fingerprint()),
+            initial_version.next().next()
+        );
+        assert_eq!(registry.database_version(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "migration 1 failed to change the schema")]
+    fn test_should_trap_on_failing_schema_change() {
+        load_fixtures();
+        SCHEMA_REGISTRY
+            .with_borrow_mut(|sr| sr.set_database_version(0))
+            .expect("failed to set database version");
+        let runner = crate::migrations![AlterUsers(1, AlterTableOp::DropColumn("missing"))];
+
+        let mut db = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        let _ = runner.run_pending(&mut db);
+    }
+
+    #[test]
+    fn test_should_run_pending_migrations_in_order() {
+        load_fixtures();
+        SCHEMA_REGISTRY
+            .with_borrow_mut(|sr| sr.set_database_version(0))
+            .expect("failed to set database version");
+        let log = Rc::new(RefCell::new(vec![]));
+        let migration = |version| InsertUser {
+            version,
+            log: log.clone(),
+        };
+        let runner = crate::migrations![migration(3), migration(1), migration(2)];
+
+        let mut db = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        assert_eq!(
+            runner
+                .run_pending(&mut db)
+                .expect("failed to run migrations"),
+            vec![1, 2, 3]
+        );
+        assert_eq!(*log.borrow(), vec![1, 2, 3]);
+        assert_eq!(SCHEMA_REGISTRY.with_borrow(|sr| sr.database_version()), 3);
+
+        // migrations are run once
+        assert!(
+            runner
+                .run_pending(&mut db)
+                .expect("failed to run migrations")
+                .is_empty()
+        );
+        assert_eq!(log.borrow().len(), 3);
+    }
+
+    #[test]
+    fn test_should_stop_at_failing_migration() {
+        load_fixtures();
+        SCHEMA_REGISTRY
+            .with_borrow_mut(|sr| sr.set_database_version(0))
+            .expect("failed to set database version");
+        let log = Rc::new(RefCell::new(vec![]));
+        let mut runner = MigrationRunner::default();
+        runner.register(Box::new(InsertUser {
+            version: 1,
+            log: log.clone(),
+        }));
+        runner.register(Box::new(Failing(2)));
+        runner.register(Box::new(InsertUser {
+            version: 3,
+            log: log.clone(),
+        }));
+
+        let mut db = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        assert!(runner.run_pending(&mut db).is_err());
+        assert_eq!(*log.borrow(), vec![1]);
+        assert_eq!(SCHEMA_REGISTRY.with_borrow(|sr| sr.database_version()), 1);
+    }
+
+    #[test]
+    fn test_should_not_run_duplicate_migration_versions() {
+        load_fixtures();
+        SCHEMA_REGISTRY
+            .with_borrow_mut(|sr| sr.set_database_version(0))
+            .expect("failed to set database version");
+        let runner = crate::migrations![Failing(1), Failing(1)];
+
+        let mut db = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        assert!(matches!(
+            runner.run_pending(&mut db),
+            Err(IcDbmsError::Query(QueryError::InvalidQuery(_)))
+        ));
+        assert_eq!(SCHEMA_REGISTRY.with_borrow(|sr| sr.database_version()), 0);
+    }
+}
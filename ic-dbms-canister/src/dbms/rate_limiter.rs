@@ -0,0 +1,183 @@
+//! This module contains the per-caller rate limiter of the SELECT queries performed with
+//! [`crate::prelude::IcDbmsDatabase::select_as`].
+
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+
+use candid::Principal;
+use ic_dbms_api::prelude::{IcDbmsError, IcDbmsResult};
+
+use crate::utils::time;
+
+thread_local! {
+    /// The current [`CallWindow`] of each caller.
+    pub static RATE_LIMITER: RefCell<HashMap<Principal, CallWindow>> = RefCell::new(HashMap::new());
+
+    /// The configured rate limits.
+    static RATE_LIMITS: RefCell<RateLimits> = RefCell::new(RateLimits::default());
+}
+
+/// Length of the default rate limit window: one second, in nanoseconds.
+const ONE_SECOND_NS: u64 = 1_000_000_000;
+
+/// A rate limit, allowing at most `max_calls_per_second` calls to each caller within each window of `window_ns`.
+///
+/// Rate limits are applied with [`RateLimiter::set_default`] to all the callers,
+/// or with [`RateLimiter::set_limit`] to a single caller; by default, no rate limit is applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimiter {
+    /// Maximum amount of calls allowed within a window.
+    pub max_calls_per_second: u32,
+    /// Length of the window in nanoseconds.
+    pub window_ns: u64,
+}
+
+/// The calls performed by a caller within the current window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CallWindow {
+    /// Amount of calls performed within the window.
+    pub calls: u32,
+    /// Time in nanoseconds since the UNIX epoch when the window started.
+    pub started_at: u64,
+}
+
+/// The configured rate limits.
+#[derive(Debug, Default)]
+struct RateLimits {
+    /// Rate limit applied to the callers without a specific one.
+    default: Option<RateLimiter>,
+    /// Rate limits of specific callers.
+    principals: HashMap<Principal, RateLimiter>,
+    /// Callers which are never rate limited, such as the admins.
+    whitelist: HashSet<Principal>,
+}
+
+impl RateLimiter {
+    /// Creates a rate limit allowing at most `max_calls_per_second` calls per second.
+    pub fn new(max_calls_per_second: u32) -> Self {
+        Self {
+            max_calls_per_second,
+            window_ns: ONE_SECOND_NS,
+        }
+    }
+
+    /// Sets the rate limit applied to the callers without a specific one; `None` removes it.
+    pub fn set_default(limit: Option<RateLimiter>) {
+        RATE_LIMITS.with_borrow_mut(|limits| limits.default = limit);
+    }
+
+    /// Sets the rate limit of the given caller, overriding the default one; `None` removes it.
+    pub fn set_limit(principal: Principal, limit: Option<RateLimiter>) {
+        RATE_LIMITS.with_borrow_mut(|limits| match limit {
+            Some(limit) => {
+                limits.principals.insert(principal, limit);
+            }
+            None => {
+                limits.principals.remove(&principal);
+            }
+        });
+    }
+
+    /// Exempts the given caller, such as an admin, from any rate limit.
+    pub fn whitelist(principal: Principal) {
+        RATE_LIMITS.with_borrow_mut(|limits| limits.whitelist.insert(principal));
+    }
+
+    /// Removes the given caller from the whitelist.
+    pub fn remove_from_whitelist(principal: &Principal) {
+        RATE_LIMITS.with_borrow_mut(|limits| limits.whitelist.remove(principal));
+    }
+
+    /// Registers a call performed by the given caller.
+    ///
+    /// Fails with [`IcDbmsError::RateLimitExceeded`] if the caller already performed all the calls
+    /// allowed by its rate limit within the current window; rejected calls are not counted.
+    pub fn check(caller: Principal) -> IcDbmsResult<()> {
+        let limit = RATE_LIMITS.with_borrow(|limits| {
+            if limits.whitelist.contains(&caller) {
+                None
+            } else {
+                limits
+                    .principals
+                    .get(&caller)
+                    .or(limits.default.as_ref())
+                    .copied()
+            }
+        });
+        let Some(limit) = limit else {
+            return Ok(());
+        };
+
+        let now = time();
+        RATE_LIMITER.with_borrow_mut(|windows| {
+            let window = windows.entry(caller).or_insert(CallWindow {
+                calls: 0,
+                started_at: now,
+            });
+            if now >= window.started_at.saturating_add(limit.window_ns) {
+                *window = CallWindow {
+                    calls: 0,
+                    started_at: now,
+                };
+            }
+
+            if window.calls >= limit.max_calls_per_second {
+                return Err(IcDbmsError::RateLimitExceeded {
+                    caller,
+                    limit: limit.max_calls_per_second,
+                    reset_at: window.started_at.saturating_add(limit.window_ns),
+                });
+            }
+            window.calls += 1;
+
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::utils::set_time;
+
+    #[test]
+    fn test_should_not_limit_without_rate_limit() {
+        let alice = Principal::from_text("ryjl3-tyaaa-aaaaa-aaaba-cai").unwrap();
+        for _ in 0..100 {
+            RateLimiter::check(alice).expect("call should be allowed");
+        }
+    }
+
+    #[test]
+    fn test_should_reset_window() {
+        let alice = Principal::from_text("ryjl3-tyaaa-aaaaa-aaaba-cai").unwrap();
+        RateLimiter::set_default(Some(RateLimiter::new(2)));
+
+        set_time(ONE_SECOND_NS);
+        RateLimiter::check(alice).expect("call should be allowed");
+        RateLimiter::check(alice).expect("call should be allowed");
+        assert!(matches!(
+            RateLimiter::check(alice),
+            Err(IcDbmsError::RateLimitExceeded { reset_at, .. }) if reset_at == 2 * ONE_SECOND_NS
+        ));
+
+        set_time(2 * ONE_SECOND_NS);
+        RateLimiter::check(alice).expect("call should be allowed after the window reset");
+    }
+
+    #[test]
+    fn test_should_apply_caller_limit_over_default() {
+        let alice = Principal::from_text("ryjl3-tyaaa-aaaaa-aaaba-cai").unwrap();
+        let bob = Principal::from_text("mxzaz-hqaaa-aaaar-qaada-cai").unwrap();
+        RateLimiter::set_default(Some(RateLimiter::new(1)));
+        RateLimiter::set_limit(bob, Some(RateLimiter::new(3)));
+
+        RateLimiter::check(alice).expect("call should be allowed");
+        assert!(RateLimiter::check(alice).is_err());
+        for _ in 0..3 {
+            RateLimiter::check(bob).expect("call should be allowed");
+        }
+        assert!(RateLimiter::check(bob).is_err());
+    }
+}
@@ -1,6 +1,16 @@
-use ic_dbms_api::prelude::{ColumnDef, DeleteBehavior, Filter, IcDbmsResult, Value};
+mod candid_interface;
+mod validation;
 
-use crate::dbms::IcDbmsDatabase;
+use ic_dbms_api::prelude::{
+    ColumnDef, DeleteBehavior, Filter, IcDbmsResult, UpdateBehavior, Value,
+};
+
+pub use self::candid_interface::export_candid;
+pub use self::validation::{
+    SCHEMA_VALIDATION_MODE, SchemaMismatch, SchemaValidationMode, TableSchemaFingerprint,
+    post_upgrade_check, validate_schemas,
+};
+use crate::dbms::{IcDbmsDatabase, UpsertResult};
 
 /// This trait provides the schema operation for the current database.
 ///
@@ -40,17 +50,48 @@ pub trait DatabaseSchema {
         filter: Option<Filter>,
     ) -> IcDbmsResult<u64>;
 
-    /// Performs an update operation for the given table name, patch values, and optional filter.
+    /// Performs an update operation for the given table name, update behavior, patch values, and optional filter.
     ///
     /// Use [`Database::update`] internally to perform the operation.
     fn update(
         &self,
         dbms: &IcDbmsDatabase,
         table_name: &'static str,
+        update_behavior: UpdateBehavior,
         patch_values: &[(ColumnDef, Value)],
         filter: Option<Filter>,
     ) -> IcDbmsResult<u64>;
 
+    /// Performs an upsert operation for the given table name and record values.
+    ///
+    /// Use [`IcDbmsDatabase::upsert`] internally to perform the operation.
+    fn upsert(
+        &self,
+        dbms: &IcDbmsDatabase,
+        table_name: &'static str,
+        record_values: &[(ColumnDef, Value)],
+    ) -> IcDbmsResult<UpsertResult>;
+
+    /// Selects the column values of the records of the given table name matching the filter.
+    ///
+    /// Use [`IcDbmsDatabase::select_raw`] internally to perform the query.
+    fn select_raw(
+        &self,
+        dbms: &IcDbmsDatabase,
+        table_name: &'static str,
+        filter: Filter,
+    ) -> IcDbmsResult<Vec<Vec<(ColumnDef, Value)>>>;
+
+    /// Returns whether any record of the given table name matches the filter.
+    ///
+    /// Use [`Database::select`] internally to perform the query.
+    fn exists(
+        &self,
+        dbms: &IcDbmsDatabase,
+        table_name: &'static str,
+        filter: Filter,
+    ) -> IcDbmsResult<bool>;
+
     /// Validates an insert operation for the given table name and record values.
     ///
     /// Use a [`crate::prelude::InsertIntegrityValidator`] to perform the validation.
@@ -60,4 +101,14 @@ pub trait DatabaseSchema {
         table_name: &'static str,
         record_values: &[(ColumnDef, Value)],
     ) -> IcDbmsResult<()>;
+
+    /// Returns the name of the type implementing the schema, used to debug the [`IcDbmsDatabase`].
+    fn type_name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+
+    /// Returns the [`TableSchemaFingerprint`] of each table of the database.
+    ///
+    /// Use [`TableSchemaFingerprint::of`] to get the fingerprints of a table.
+    fn table_fingerprints(&self) -> Vec<TableSchemaFingerprint>;
 }
@@ -0,0 +1,238 @@
+//! Generation of the Candid interface exposing the tables of a database.
+
+use candid::CandidType;
+use candid::pretty::candid::{compile, pp_text, pp_ty};
+use candid::types::internal::TypeContainer;
+use ic_dbms_api::prelude::{CandidIcDbmsError, ColumnDef, DataTypeKind};
+
+use super::TableSchemaFingerprint;
+
+/// Candid definitions of the types shared by all the tables.
+const SHARED_TYPES: &str = "type CandidRow = vec record { column : text; value : CandidValue };
+type CandidFilter = variant {
+  Eq : record { text; CandidValue };
+  Ne : record { text; CandidValue };
+  Gt : record { text; CandidValue };
+  Lt : record { text; CandidValue };
+  Ge : record { text; CandidValue };
+  Le : record { text; CandidValue };
+  In : record { text; vec CandidValue };
+  Like : record { text; text };
+  ILike : record { text; text };
+  NotNull : text;
+  IsNull : text;
+  And : record { CandidFilter; CandidFilter };
+  Or : record { CandidFilter; CandidFilter };
+  Not : CandidFilter;
+};
+type Result = variant { Ok; Err : CandidIcDbmsError };
+";
+
+/// Width used to render types, large enough to render each of them on a single line.
+const LINE_WIDTH: usize = 1024;
+
+/// Every scalar [`DataTypeKind`], in the order of the `CandidValue` variant.
+const DATA_TYPES: [DataTypeKind; 13] = [
+    DataTypeKind::Blob,
+    DataTypeKind::Boolean,
+    DataTypeKind::Date,
+    DataTypeKind::DateTime,
+    DataTypeKind::Decimal,
+    DataTypeKind::Float64,
+    DataTypeKind::Int32,
+    DataTypeKind::Int64,
+    DataTypeKind::Principal,
+    DataTypeKind::Text,
+    DataTypeKind::Uint32,
+    DataTypeKind::Uint64,
+    DataTypeKind::Uuid,
+];
+
+/// Generates the Candid interface of the given tables.
+///
+/// For each table, the service exposes:
+///
+/// - `get_{table}: (opt CandidFilter) -> (vec CandidRow) query`
+/// - `insert_{table}: (Candid{Table}Insert) -> (Result)`
+///
+/// Tables are sorted by name, so the output is deterministic.
+pub fn export_candid(tables: &[TableSchemaFingerprint]) -> String {
+    let mut tables = tables.to_vec();
+    tables.sort_by_key(|table| table.table);
+
+    // error types are rendered from their rust definitions
+    let mut container = TypeContainer::new();
+    container.add::<CandidIcDbmsError>();
+    let mut interface = compile(&container.env, &None);
+
+    interface.push_str(&candid_value_type());
+    interface.push_str(SHARED_TYPES);
+    for table in &tables {
+        interface.push_str(&insert_record_type(table));
+    }
+
+    interface.push_str("service : {\n");
+    for table in &tables {
+        let name = table.table;
+        interface.push_str(&format!(
+            "  {} : (opt CandidFilter) -> (vec CandidRow) query;\n",
+            candid_id(&format!("get_{name}"))
+        ));
+        interface.push_str(&format!(
+            "  {} : ({}) -> (Result);\n",
+            candid_id(&format!("insert_{name}")),
+            insert_record_name(name)
+        ));
+    }
+    interface.push_str("}\n");
+
+    interface
+}
+
+/// Returns the definition of the `CandidValue` variant, with a case for each [`DataTypeKind`] and `Null`.
+///
+/// Arrays share a single case, holding the values of their elements.
+fn candid_value_type() -> String {
+    let mut definition = String::from("type CandidValue = variant {\n  Array : vec CandidValue;\n");
+    for data_type in DATA_TYPES {
+        definition.push_str(&format!("  {data_type:?} : {};\n", candid_type(data_type)));
+    }
+    definition.push_str("  Null;\n};\n");
+    definition
+}
+
+/// Returns the definition of the insert record of the given table.
+///
+/// Nullable columns are optional.
+fn insert_record_type(table: &TableSchemaFingerprint) -> String {
+    let mut definition = format!("type {} = record {{\n", insert_record_name(table.table));
+    for column in table.columns {
+        definition.push_str(&format!(
+            "  {} : {};\n",
+            candid_id(column.name),
+            column_type(column)
+        ));
+    }
+    definition.push_str("};\n");
+    definition
+}
+
+/// Returns the name of the insert record of the given table, e.g. `CandidUserPostsInsert` for `user_posts`.
+fn insert_record_name(table: &str) -> String {
+    let name = table
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect::<String>();
+    format!("Candid{name}Insert")
+}
+
+/// Returns the Candid type of the given column.
+fn column_type(column: &ColumnDef) -> String {
+    let ty = candid_type(column.data_type);
+    if column.nullable {
+        format!("opt {ty}")
+    } else {
+        ty
+    }
+}
+
+/// Returns the Candid type of the given [`DataTypeKind`], as defined by its rust type.
+fn candid_type(data_type: DataTypeKind) -> String {
+    use ic_dbms_api::prelude::{
+        Blob, Boolean, Date, DateTime, Decimal, Float64, Int32, Int64, Principal, Text, Uint32,
+        Uint64, Uuid,
+    };
+
+    let ty = match data_type {
+        DataTypeKind::Array(element) => return format!("vec {}", candid_type(*element)),
+        DataTypeKind::Blob => Blob::ty(),
+        DataTypeKind::Boolean => Boolean::ty(),
+        DataTypeKind::Date => Date::ty(),
+        DataTypeKind::DateTime => DateTime::ty(),
+        DataTypeKind::Decimal => Decimal::ty(),
+        DataTypeKind::Float64 => Float64::ty(),
+        DataTypeKind::Int32 => Int32::ty(),
+        DataTypeKind::Int64 => Int64::ty(),
+        DataTypeKind::Principal => Principal::ty(),
+        DataTypeKind::Text => Text::ty(),
+        DataTypeKind::Uint32 => Uint32::ty(),
+        DataTypeKind::Uint64 | DataTypeKind::AutoIncrement => Uint64::ty(),
+        DataTypeKind::Uuid => Uuid::ty(),
+    };
+    pp_ty(&ty).pretty(LINE_WIDTH).to_string()
+}
+
+/// Returns the given name as a Candid identifier, quoting it if it's not a valid one or a keyword.
+fn candid_id(name: &str) -> String {
+    pp_text(name).pretty(LINE_WIDTH).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::tests::{Message, User};
+
+    #[test]
+    fn test_should_export_candid_of_tables() {
+        let interface = export_candid(&[
+            TableSchemaFingerprint::of::<User>(),
+            TableSchemaFingerprint::of::<Message>(),
+        ]);
+
+        assert!(interface.contains("get_users : (opt CandidFilter) -> (vec CandidRow) query;"));
+        assert!(interface.contains("insert_users : (CandidUsersInsert) -> (Result);"));
+        assert!(interface.contains("get_messages : (opt CandidFilter) -> (vec CandidRow) query;"));
+        assert!(interface.contains("insert_messages : (CandidMessagesInsert) -> (Result);"));
+        assert!(
+            interface
+                .contains("type CandidUsersInsert = record {\n  id : nat32;\n  name : text;\n};")
+        );
+        assert!(interface.contains("  read_at : opt record {"));
+        assert!(interface.contains("type CandidIcDbmsError = record {"));
+        assert!(interface.contains("error_code : nat32"));
+        assert!(interface.contains("type CandidValue = variant {\n  Array : vec CandidValue;\n"));
+        assert!(interface.contains("  Float64 : float64;\n"));
+        assert!(interface.contains("  Uuid : blob;\n  Null;\n};"));
+
+        // tables are sorted by name
+        assert!(
+            interface
+                .find("get_messages")
+                .expect("get_messages not found")
+                < interface.find("get_users").expect("get_users not found")
+        );
+    }
+
+    #[test]
+    fn test_should_name_insert_record() {
+        assert_eq!(insert_record_name("users"), "CandidUsersInsert");
+        assert_eq!(insert_record_name("user_posts"), "CandidUserPostsInsert");
+    }
+
+    #[test]
+    fn test_should_render_array_candid_type() {
+        assert_eq!(
+            candid_type(DataTypeKind::Array(&DataTypeKind::Uint32)),
+            "vec nat32"
+        );
+        assert_eq!(
+            candid_type(DataTypeKind::Array(&DataTypeKind::Text)),
+            "vec text"
+        );
+    }
+
+    #[test]
+    fn test_should_quote_invalid_identifiers() {
+        assert_eq!(candid_id("name"), "name");
+        assert_eq!(candid_id("created-at"), "\"created-at\"");
+        assert_eq!(candid_id("text"), "\"text\"");
+    }
+}
@@ -0,0 +1,233 @@
+//! Validation of the stored table schemas against the current code, to be performed after an upgrade.
+
+use ic_dbms_api::prelude::{ColumnDef, TableFingerprint, TableSchema};
+
+use crate::memory::SCHEMA_REGISTRY;
+
+/// Default [`SchemaValidationMode`] used by [`crate::post_upgrade_check`].
+pub const SCHEMA_VALIDATION_MODE: SchemaValidationMode = SchemaValidationMode::Strict;
+
+/// Defines how schema mismatches are handled by [`post_upgrade_check`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchemaValidationMode {
+    /// Trap on any mismatch, so that the upgrade is rolled back.
+    Strict,
+    /// Only log a warning for each mismatch.
+    Lenient,
+}
+
+/// Fingerprints of a table schema in the current code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TableSchemaFingerprint {
+    /// Name of the table.
+    pub table: &'static str,
+    /// [`TableSchema::fingerprint`] of the table.
+    pub fingerprint: TableFingerprint,
+    /// [`TableSchema::columns_fingerprint`] of the table.
+    pub columns_fingerprint: u64,
+    /// [`TableSchema::columns`] of the table.
+    pub columns: &'static [ColumnDef],
+}
+
+impl TableSchemaFingerprint {
+    /// Returns the fingerprints of the table schema `T`.
+    pub fn of<T>() -> Self
+    where
+        T: TableSchema,
+    {
+        Self {
+            table: T::table_name(),
+            fingerprint: T::fingerprint(),
+            columns_fingerprint: T::columns_fingerprint(),
+            columns: T::columns(),
+        }
+    }
+}
+
+/// A mismatch between a table stored in the [`crate::memory::SchemaRegistry`] and the current code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaMismatch {
+    /// Fingerprint of the stored table.
+    pub fingerprint: TableFingerprint,
+    /// Name of the table, if it still exists in the current code.
+    pub table: Option<&'static str>,
+    /// Description of the mismatch.
+    pub description: String,
+}
+
+/// Validates all the tables stored in the [`crate::memory::SchemaRegistry`] against the `current` table schemas.
+///
+/// Only tables whose columns have been recorded on registration are validated.
+/// A mismatch is reported when the columns of a table have changed,
+/// or when a stored table doesn't exist anymore in the current code.
+pub fn validate_schemas(current: &[TableSchemaFingerprint]) -> Vec<SchemaMismatch> {
+    SCHEMA_REGISTRY.with_borrow(|sr| {
+        let mut mismatches = sr
+            .fingerprints()
+            .filter_map(|fingerprint| {
+                let stored_columns = sr.columns_fingerprint(fingerprint)?;
+                match current.iter().find(|t| t.fingerprint == fingerprint) {
+                    None => Some(SchemaMismatch {
+                        fingerprint,
+                        table: None,
+                        description: "table does not exist in the current schema".to_string(),
+                    }),
+                    Some(table) if table.columns_fingerprint != stored_columns => {
+                        Some(SchemaMismatch {
+                            fingerprint,
+                            table: Some(table.table),
+                            description: format!(
+                                "columns of table '{}' differ from the stored ones",
+                                table.table
+                            ),
+                        })
+                    }
+                    Some(_) => None,
+                }
+            })
+            .collect::<Vec<_>>();
+        mismatches.sort_by_key(|mismatch| mismatch.fingerprint);
+        mismatches
+    })
+}
+
+/// Validates the stored tables against the `current` table schemas and handles the mismatches
+/// according to the given [`SchemaValidationMode`].
+///
+/// Every mismatch is logged; in [`SchemaValidationMode::Strict`] mode the canister traps if any mismatch is found.
+///
+/// Returns the found mismatches.
+pub fn post_upgrade_check(
+    current: &[TableSchemaFingerprint],
+    mode: SchemaValidationMode,
+) -> Vec<SchemaMismatch> {
+    let mismatches = validate_schemas(current);
+    for mismatch in &mismatches {
+        crate::dbms_log!(
+            WARN,
+            "schema mismatch: {}",
+            mismatch.description;
+            "table" => mismatch.table.unwrap_or("<unknown>"),
+            "fingerprint" => mismatch.fingerprint
+        );
+    }
+
+    if mode == SchemaValidationMode::Strict && !mismatches.is_empty() {
+        crate::trap!(
+            "post upgrade check failed: {} schema mismatches found",
+            mismatches.len()
+        );
+    }
+
+    mismatches
+}
+
+/// Validates the stored tables against the given table schemas after a canister upgrade.
+///
+/// By default mismatches are handled according to [`SCHEMA_VALIDATION_MODE`];
+/// the mode can be overridden with `mode = ...;` before the list of tables.
+///
+/// ```rust,ignore
+/// #[ic_cdk::post_upgrade]
+/// fn post_upgrade() {
+///     ic_dbms_canister::post_upgrade_check!(User, Post);
+///     // or
+///     ic_dbms_canister::post_upgrade_check!(mode = SchemaValidationMode::Lenient; User, Post);
+/// }
+/// ```
+#[macro_export]
+macro_rules! post_upgrade_check {
+    (mode = $mode:expr; $($table:ty),+ $(,)?) => {
+        $crate::dbms::schema::post_upgrade_check(
+            &[$($crate::dbms::schema::TableSchemaFingerprint::of::<$table>()),+],
+            $mode,
+        )
+    };
+    ($($table:ty),+ $(,)?) => {
+        $crate::post_upgrade_check!(mode = $crate::dbms::schema::SCHEMA_VALIDATION_MODE; $($table),+)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+
+    use ic_dbms_api::prelude::{ColumnDef, DataTypeKind, columns_fingerprint};
+
+    use super::*;
+    use crate::tests::{Post, User, load_fixtures};
+
+    /// Returns the fingerprints of [`User`] as if a column had been added to it.
+    fn user_with_new_column() -> TableSchemaFingerprint {
+        let mut columns = User::columns().to_vec();
+        columns.push(ColumnDef {
+            name: "email",
+            data_type: DataTypeKind::Text,
+            nullable: true,
+            primary_key: false,
+            unique: false,
+            auto_increment: false,
+            foreign_key: None,
+            check: None,
+        });
+
+        TableSchemaFingerprint {
+            columns_fingerprint: columns_fingerprint(&columns),
+            ..TableSchemaFingerprint::of::<User>()
+        }
+    }
+
+    #[test]
+    fn test_should_validate_unchanged_schemas() {
+        load_fixtures();
+
+        let mismatches = validate_schemas(&[
+            TableSchemaFingerprint::of::<User>(),
+            TableSchemaFingerprint::of::<Post>(),
+        ]);
+        // only tables missing from the given list are reported
+        assert!(mismatches.iter().all(|mismatch| mismatch.table.is_none()
+            && mismatch.fingerprint != User::fingerprint()
+            && mismatch.fingerprint != Post::fingerprint()));
+    }
+
+    #[test]
+    fn test_should_report_changed_columns() {
+        load_fixtures();
+
+        let mismatches =
+            validate_schemas(&[user_with_new_column(), TableSchemaFingerprint::of::<Post>()]);
+        let user_mismatches = mismatches
+            .iter()
+            .filter(|mismatch| mismatch.table.is_some())
+            .collect::<Vec<_>>();
+        assert_eq!(user_mismatches.len(), 1);
+        assert_eq!(user_mismatches[0].table, Some(User::table_name()));
+        assert_eq!(user_mismatches[0].fingerprint, User::fingerprint());
+    }
+
+    #[test]
+    fn test_should_report_removed_table() {
+        load_fixtures();
+
+        let mismatches = validate_schemas(&[TableSchemaFingerprint::of::<User>()]);
+        assert!(mismatches.iter().any(|mismatch| {
+            mismatch.fingerprint == Post::fingerprint() && mismatch.table.is_none()
+        }));
+    }
+
+    #[test]
+    fn test_should_only_warn_in_lenient_mode() {
+        load_fixtures();
+
+        let mismatches = crate::post_upgrade_check!(mode = SchemaValidationMode::Lenient; User);
+        assert!(!mismatches.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "post upgrade check failed")]
+    fn test_should_trap_in_strict_mode() {
+        load_fixtures();
+
+        crate::post_upgrade_check!(User);
+    }
+}
@@ -3,28 +3,144 @@
 mod overlay;
 mod session;
 
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use candid::Principal;
 use ic_dbms_api::prelude::{
-    ColumnDef, DeleteBehavior, Filter, IcDbmsResult, TableSchema, UpdateRecord as _, Value,
+    ColumnDef, DeleteBehavior, Filter, IcDbmsError, IcDbmsResult, SavepointId, TableSchema,
+    TransactionError, UpdateBehavior, UpdateRecord as _, Value,
 };
 
-pub use self::overlay::DatabaseOverlay;
-pub use self::session::{TRANSACTION_SESSION, TransactionSession};
+pub use self::overlay::{DatabaseOverlay, OverlayCheckpoint};
+pub use self::session::{TRANSACTION_SESSION, TransactionSession, expire_stale_transactions};
+use crate::dbms::IcDbmsDatabase;
+use crate::dbms::integrity::ValidationMode;
+use crate::dbms::logger::{DBMS_LOGGER, NoopLogger};
+use crate::dbms::schema::DatabaseSchema;
+use crate::utils::{caller, time};
 
 /// A transaction represents a sequence of operations performed as a single logical unit of work.
-#[derive(Debug, Default)]
+#[derive(Debug, Clone)]
 pub struct Transaction {
     /// Stack of operations performed in this transaction.
     pub(super) operations: Vec<TransactionOp>,
     /// Overlay to track uncommitted changes.
     overlay: DatabaseOverlay,
+    /// Savepoints taken within this transaction, by their ID.
+    savepoints: BTreeMap<SavepointId, Savepoint>,
+    /// Next savepoint ID
+    next_savepoint_id: SavepointId,
+    /// Principal which began the transaction.
+    caller: Principal,
+    /// Time the transaction was begun at, in nanoseconds since the UNIX epoch.
+    created_at_nanos: u64,
+    /// Whether the transaction is read-only, rejecting write operations.
+    read_only: bool,
+}
+
+/// Metadata of a [`Transaction`], for auditing purposes.
+///
+/// See [`TransactionSession::transaction_info`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransactionInfo {
+    /// Principal which began the transaction.
+    pub caller: Principal,
+    /// Time the transaction was begun at, in nanoseconds since the UNIX epoch.
+    pub created_at_nanos: u64,
+    /// Amount of operations performed in the transaction.
+    pub operations: usize,
+    /// Whether the transaction can perform write operations.
+    pub mode: TransactionMode,
+}
+
+/// Whether a [`Transaction`] can perform write operations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionMode {
+    /// The transaction can both read and write.
+    ReadWrite,
+    /// The transaction can only read; see [`Transaction::read_only`].
+    ReadOnly,
+}
+
+/// The state of a [`Transaction`] recorded by [`Transaction::savepoint`].
+#[derive(Debug, Clone)]
+struct Savepoint {
+    /// Amount of operations performed before the savepoint.
+    operations: usize,
+    /// State of the overlay at the savepoint.
+    overlay: OverlayCheckpoint,
 }
 
 impl Transaction {
+    /// Creates a new transaction begun by the given [`Principal`] at the current time.
+    pub fn new(caller: Principal) -> Self {
+        Self {
+            operations: Vec::new(),
+            overlay: DatabaseOverlay::default(),
+            savepoints: BTreeMap::new(),
+            next_savepoint_id: 0,
+            caller,
+            created_at_nanos: time(),
+            read_only: false,
+        }
+    }
+
+    /// Creates a new read-only transaction begun by the given [`Principal`] at the current time,
+    /// whose write operations fail with [`TransactionError::ReadOnlyTransaction`].
+    pub fn read_only(caller: Principal) -> Self {
+        Self {
+            read_only: true,
+            ..Self::new(caller)
+        }
+    }
+
+    /// Returns whether the transaction is read-only.
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Returns the [`Principal`] which began the transaction.
+    pub fn caller(&self) -> Principal {
+        self.caller
+    }
+
+    /// Returns the time the transaction was begun at, in nanoseconds since the UNIX epoch.
+    pub fn created_at_nanos(&self) -> u64 {
+        self.created_at_nanos
+    }
+
+    /// Returns the [`TransactionInfo`] of the transaction.
+    pub fn info(&self) -> TransactionInfo {
+        TransactionInfo {
+            caller: self.caller,
+            created_at_nanos: self.created_at_nanos,
+            operations: self.operations.len(),
+            mode: if self.read_only {
+                TransactionMode::ReadOnly
+            } else {
+                TransactionMode::ReadWrite
+            },
+        }
+    }
+
+    /// Fails with [`TransactionError::ReadOnlyTransaction`] if the transaction is read-only.
+    fn check_writable(&self) -> IcDbmsResult<()> {
+        if self.read_only {
+            return Err(IcDbmsError::Transaction(
+                TransactionError::ReadOnlyTransaction,
+            ));
+        }
+
+        Ok(())
+    }
+
     /// Insert a new `insert` operation into the transaction.
     pub fn insert<T>(&mut self, values: Vec<(ColumnDef, Value)>) -> IcDbmsResult<()>
     where
         T: TableSchema,
     {
+        self.check_writable()?;
         self.overlay.insert::<T>(values.clone())?;
         self.operations.push(TransactionOp::Insert {
             table: T::table_name(),
@@ -36,13 +152,15 @@ impl Transaction {
     /// Insert a new `update` operation into the transaction.
     pub fn update<T>(
         &mut self,
+        behaviour: UpdateBehavior,
         patch: T::Update,
         filter: Option<Filter>,
-        primary_keys: Vec<Value>,
+        primary_keys: Vec<Vec<Value>>,
     ) -> IcDbmsResult<()>
     where
         T: TableSchema,
     {
+        self.check_writable()?;
         let patch_values = patch.update_values();
         let overlay_patch: Vec<_> = patch_values
             .iter()
@@ -55,22 +173,56 @@ impl Transaction {
 
         self.operations.push(TransactionOp::Update {
             table: T::table_name(),
+            behaviour,
             patch: patch_values,
             filter,
         });
         Ok(())
     }
 
+    /// Insert a new `upsert` operation into the transaction.
+    ///
+    /// If `primary_key` is `None` the record is inserted into the overlay,
+    /// otherwise the non-primary key columns of the record with the given primary key are updated.
+    pub fn upsert<T>(
+        &mut self,
+        values: Vec<(ColumnDef, Value)>,
+        primary_key: Option<Vec<Value>>,
+    ) -> IcDbmsResult<()>
+    where
+        T: TableSchema,
+    {
+        self.check_writable()?;
+        match primary_key {
+            None => self.overlay.insert::<T>(values.clone())?,
+            Some(pk) => {
+                let overlay_patch = values
+                    .iter()
+                    .filter(|(col, _)| !T::primary_keys().contains(&col.name))
+                    .map(|(col, val)| (col.name, val.clone()))
+                    .collect();
+                self.overlay.update::<T>(pk, overlay_patch);
+            }
+        }
+
+        self.operations.push(TransactionOp::Upsert {
+            table: T::table_name(),
+            values,
+        });
+        Ok(())
+    }
+
     /// Insert a new `delete` operation into the transaction.
     pub fn delete<T>(
         &mut self,
         behaviour: DeleteBehavior,
         filter: Option<Filter>,
-        primary_keys: Vec<Value>,
+        primary_keys: Vec<Vec<Value>>,
     ) -> IcDbmsResult<()>
     where
         T: TableSchema,
     {
+        self.check_writable()?;
         for pk in primary_keys {
             self.overlay.delete::<T>(pk);
         }
@@ -83,6 +235,143 @@ impl Transaction {
         Ok(())
     }
 
+    /// Insert a new conditional operation into the transaction.
+    ///
+    /// When the transaction is committed, `then_ops` are applied if any record of `table` matches the filter,
+    /// otherwise `else_ops` are applied.
+    /// The filter is evaluated against the committed state, including the effects of the preceding operations.
+    ///
+    /// The conditional operations are not visible within the transaction, since the branch to apply is unknown until commit.
+    pub fn if_exists(
+        &mut self,
+        table: &'static str,
+        filter: Filter,
+        then_ops: Vec<TransactionOp>,
+        else_ops: Vec<TransactionOp>,
+    ) -> IcDbmsResult<()> {
+        self.check_writable()?;
+        self.operations.push(TransactionOp::IfExists {
+            table,
+            filter,
+            then_ops,
+            else_ops,
+        });
+        Ok(())
+    }
+
+    /// Takes a savepoint of the current state of the transaction and returns its [`SavepointId`].
+    ///
+    /// The transaction can be rolled back to the savepoint with [`Transaction::rollback_to_savepoint`].
+    pub fn savepoint(&mut self) -> SavepointId {
+        let savepoint_id = self.next_savepoint_id;
+        self.next_savepoint_id += 1;
+        self.savepoints.insert(
+            savepoint_id,
+            Savepoint {
+                operations: self.operations.len(),
+                overlay: self.overlay.checkpoint(),
+            },
+        );
+
+        savepoint_id
+    }
+
+    /// Rolls the transaction back to the savepoint with the given [`SavepointId`],
+    /// discarding the operations performed after it, while keeping the transaction open.
+    ///
+    /// The savepoint can be rolled back to again, while the savepoints taken after it are released.
+    /// Fails with [`TransactionError::SavepointNotFound`] if the savepoint doesn't exist or has been released.
+    pub fn rollback_to_savepoint(&mut self, savepoint_id: SavepointId) -> IcDbmsResult<()> {
+        let savepoint =
+            self.savepoints
+                .get(&savepoint_id)
+                .cloned()
+                .ok_or(IcDbmsError::Transaction(
+                    TransactionError::SavepointNotFound(savepoint_id),
+                ))?;
+
+        self.operations.truncate(savepoint.operations);
+        self.overlay.rewind(&savepoint.overlay);
+        self.savepoints.retain(|id, _| *id <= savepoint_id);
+
+        Ok(())
+    }
+
+    /// Validates all the operations of the transaction, without writing anything to memory.
+    ///
+    /// The operations are replayed in order within a scratch transaction, which is discarded afterwards,
+    /// so each operation is validated against the committed state and the effects of the preceding operations:
+    ///
+    /// - inserts are checked by the [`crate::prelude::InsertIntegrityValidator`];
+    /// - updates are applied to the matching records and checked by the [`crate::prelude::UpdateIntegrityValidator`];
+    /// - upserts are checked as inserts or updates, depending on whether a record with the same primary key exists;
+    /// - deletes with [`DeleteBehavior::Restrict`] are checked for records referencing the deleted ones;
+    /// - conditional operations validate the branch selected by their filter.
+    pub fn pre_validate(
+        &self,
+        schema: &dyn DatabaseSchema,
+        db: &IcDbmsDatabase,
+    ) -> IcDbmsResult<()> {
+        // the replay on the scratch transaction is an implementation detail, so it's not logged
+        let logger = DBMS_LOGGER.replace(Box::new(NoopLogger));
+        let scratch_id = TRANSACTION_SESSION.with_borrow_mut(|ts| ts.begin_transaction(caller()));
+        let scratch = IcDbmsDatabase {
+            schema: Rc::clone(&db.schema),
+            transaction: Some(scratch_id.clone()),
+            validation_mode: ValidationMode::Strict,
+            read_only: false,
+        };
+
+        let result = self
+            .operations
+            .iter()
+            .try_for_each(|op| Self::replay(schema, &scratch, op));
+        TRANSACTION_SESSION.with_borrow_mut(|ts| ts.close_transaction(&scratch_id));
+        DBMS_LOGGER.set(logger);
+
+        result
+    }
+
+    /// Replays the given operation on `db`.
+    fn replay(
+        schema: &dyn DatabaseSchema,
+        db: &IcDbmsDatabase,
+        op: &TransactionOp,
+    ) -> IcDbmsResult<()> {
+        match op {
+            TransactionOp::Insert { table, values } => schema.insert(db, table, values),
+            TransactionOp::Delete {
+                table,
+                behaviour,
+                filter,
+            } => schema
+                .delete(db, table, *behaviour, filter.clone())
+                .map(|_| ()),
+            TransactionOp::Update {
+                table,
+                behaviour,
+                patch,
+                filter,
+            } => schema
+                .update(db, table, *behaviour, patch, filter.clone())
+                .map(|_| ()),
+            TransactionOp::Upsert { table, values } => schema.upsert(db, table, values).map(|_| ()),
+            TransactionOp::IfExists {
+                table,
+                filter,
+                then_ops,
+                else_ops,
+            } => {
+                let ops = if schema.exists(db, table, filter.clone())? {
+                    then_ops
+                } else {
+                    else_ops
+                };
+                ops.iter().try_for_each(|op| Self::replay(schema, db, op))
+            }
+        }
+    }
+
     /// Get a reference to the [`DatabaseOverlay`] associated with this transaction.
     pub fn overlay(&self) -> &DatabaseOverlay {
         &self.overlay
@@ -95,7 +384,7 @@ impl Transaction {
 }
 
 /// An enum representing the different types of operations that can be performed within a transaction.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum TransactionOp {
     Insert {
         table: &'static str,
@@ -108,7 +397,20 @@ pub enum TransactionOp {
     },
     Update {
         table: &'static str,
+        behaviour: UpdateBehavior,
         patch: Vec<(ColumnDef, Value)>,
         filter: Option<Filter>,
     },
+    /// Inserts the record, or updates the record with the same primary key if it exists.
+    Upsert {
+        table: &'static str,
+        values: Vec<(ColumnDef, Value)>,
+    },
+    /// Applies `then_ops` if any record of `table` matches the filter, otherwise `else_ops`.
+    IfExists {
+        table: &'static str,
+        filter: Filter,
+        then_ops: Vec<TransactionOp>,
+        else_ops: Vec<TransactionOp>,
+    },
 }
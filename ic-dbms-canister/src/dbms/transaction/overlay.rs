@@ -14,11 +14,22 @@ use crate::memory::TableReader;
 /// The database overlay is used to manage uncommitted changes during a transaction.
 ///
 /// Basically it provides an overlay over the existing database state to track uncommitted changes.
+///
+/// Each [`super::Transaction`] owns its overlay, so the uncommitted changes of a transaction
+/// are never visible to the other transactions, even when they are interleaved.
 #[derive(Debug, Default, Clone)]
 pub struct DatabaseOverlay {
     tables: HashMap<TableName, TableOverlay>,
 }
 
+/// A state of a [`DatabaseOverlay`], taken with [`DatabaseOverlay::checkpoint`].
+///
+/// Since the overlay of each table is a stack of operations, the checkpoint just records the size of each stack.
+#[derive(Debug, Default, Clone)]
+pub struct OverlayCheckpoint {
+    operations: HashMap<TableName, usize>,
+}
+
 impl DatabaseOverlay {
     /// Get a [`DatabaseOverlayReader`] for the specified table.
     pub fn reader<'a, T>(
@@ -39,16 +50,15 @@ impl DatabaseOverlay {
         T: TableSchema,
     {
         let table_name = T::table_name();
-        let pk = T::primary_key();
-        let pk = Self::primary_key(pk, &values)?;
+        let pk = Self::primary_key(T::primary_keys(), &values)?;
         let overlay = self.tables.entry(table_name).or_default();
         overlay.insert(pk, values);
 
         Ok(())
     }
 
-    /// Update a record in the overlay for the specified table.
-    pub fn update<T>(&mut self, pk: Value, updates: Vec<(&'static str, Value)>)
+    /// Update the record with the given primary key values in the overlay for the specified table.
+    pub fn update<T>(&mut self, pk: Vec<Value>, updates: Vec<(&'static str, Value)>)
     where
         T: TableSchema,
     {
@@ -57,8 +67,8 @@ impl DatabaseOverlay {
         overlay.update(pk, updates);
     }
 
-    /// Delete a record in the overlay for the specified table.
-    pub fn delete<T>(&mut self, pk: Value)
+    /// Delete the record with the given primary key values in the overlay for the specified table.
+    pub fn delete<T>(&mut self, pk: Vec<Value>)
     where
         T: TableSchema,
     {
@@ -67,13 +77,47 @@ impl DatabaseOverlay {
         overlay.delete(pk);
     }
 
-    fn primary_key(pk: &'static str, values: &[(ColumnDef, Value)]) -> IcDbmsResult<Value> {
-        for (col_def, value) in values {
-            if col_def.name == pk {
-                return Ok(value.clone());
-            }
+    /// Returns an [`OverlayCheckpoint`] of the current state of the overlay,
+    /// which can be restored later with [`DatabaseOverlay::rewind`].
+    pub fn checkpoint(&self) -> OverlayCheckpoint {
+        OverlayCheckpoint {
+            operations: self
+                .tables
+                .iter()
+                .map(|(table, overlay)| (*table, overlay.operations.len()))
+                .collect(),
+        }
+    }
+
+    /// Rewinds the overlay to the given [`OverlayCheckpoint`],
+    /// discarding the changes tracked after it was taken.
+    pub fn rewind(&mut self, checkpoint: &OverlayCheckpoint) {
+        for (table, overlay) in self.tables.iter_mut() {
+            overlay.truncate(
+                checkpoint
+                    .operations
+                    .get(table)
+                    .copied()
+                    .unwrap_or_default(),
+            );
         }
-        Err(IcDbmsError::Query(QueryError::MissingNonNullableField(pk)))
+    }
+
+    /// Returns the values of the given primary key columns, in the same order.
+    fn primary_key(
+        primary_keys: &[&'static str],
+        values: &[(ColumnDef, Value)],
+    ) -> IcDbmsResult<Vec<Value>> {
+        primary_keys
+            .iter()
+            .map(|pk| {
+                values
+                    .iter()
+                    .find(|(col_def, _)| col_def.name == *pk)
+                    .map(|(_, value)| value.clone())
+                    .ok_or(IcDbmsError::Query(QueryError::MissingNonNullableField(*pk)))
+            })
+            .collect()
     }
 }
 
@@ -83,7 +127,7 @@ mod tests {
     use ic_dbms_api::prelude::DataTypeKind;
 
     use super::*;
-    use crate::tests::User;
+    use crate::tests::{Post, User};
 
     #[test]
     fn test_should_insert() {
@@ -96,7 +140,10 @@ mod tests {
                     data_type: DataTypeKind::Uint32,
                     nullable: false,
                     primary_key: true,
+                    unique: false,
+                    auto_increment: false,
                     foreign_key: None,
+                    check: None,
                 },
                 pk.clone(),
             ),
@@ -106,7 +153,10 @@ mod tests {
                     data_type: DataTypeKind::Text,
                     nullable: false,
                     primary_key: false,
+                    unique: false,
+                    auto_increment: false,
                     foreign_key: None,
+                    check: None,
                 },
                 Value::Text("Alice".to_string().into()),
             ),
@@ -124,7 +174,7 @@ mod tests {
             .expect("no operations found");
         assert!(matches!(
             record,
-            table::Operation::Insert(pk_value, _values) if pk_value == &pk
+            table::Operation::Insert(pk_value, _values) if pk_value == &[pk.clone()]
         ));
     }
 
@@ -137,7 +187,10 @@ mod tests {
                 data_type: DataTypeKind::Text,
                 nullable: false,
                 primary_key: false,
+                unique: false,
+                auto_increment: false,
                 foreign_key: None,
+                check: None,
             },
             Value::Text("Alice".to_string().into()),
         )];
@@ -150,7 +203,7 @@ mod tests {
         let mut overlay = DatabaseOverlay::default();
         let pk = Value::Uint32(1.into());
         let updates = vec![("name", Value::Text("Bob".to_string().into()))];
-        overlay.update::<User>(pk.clone(), updates.clone());
+        overlay.update::<User>(vec![pk.clone()], updates.clone());
 
         let table_overlay = overlay
             .tables
@@ -163,15 +216,41 @@ mod tests {
         assert!(matches!(
             record,
             table::Operation::Update(pk_value, update_values)
-                if pk_value == &pk && update_values == &updates
+                if pk_value == &[pk.clone()] && update_values == &updates
         ));
     }
 
+    #[test]
+    fn test_should_rewind_to_checkpoint() {
+        let mut overlay = DatabaseOverlay::default();
+        overlay.delete::<User>(vec![Value::Uint32(1.into())]);
+        let checkpoint = overlay.checkpoint();
+
+        overlay.delete::<User>(vec![Value::Uint32(2.into())]);
+        overlay.delete::<Post>(vec![Value::Uint32(1.into())]);
+        overlay.rewind(&checkpoint);
+
+        let user_overlay = overlay
+            .tables
+            .get(&User::table_name())
+            .expect("table not found");
+        assert_eq!(user_overlay.operations.len(), 1);
+        assert!(matches!(
+            user_overlay.operations.first(),
+            Some(table::Operation::Delete(pk)) if pk == &[Value::Uint32(1.into())]
+        ));
+        let post_overlay = overlay
+            .tables
+            .get(&Post::table_name())
+            .expect("table not found");
+        assert!(post_overlay.operations.is_empty());
+    }
+
     #[test]
     fn test_should_delete() {
         let mut overlay = DatabaseOverlay::default();
         let pk = Value::Uint32(1.into());
-        overlay.delete::<User>(pk.clone());
+        overlay.delete::<User>(vec![pk.clone()]);
 
         let table_overlay = overlay
             .tables
@@ -184,7 +263,7 @@ mod tests {
             .expect("no operations found");
         assert!(matches!(
             record,
-            table::Operation::Delete(pk_value) if pk_value == &pk
+            table::Operation::Delete(pk_value) if pk_value == &[pk.clone()]
         ));
     }
 }
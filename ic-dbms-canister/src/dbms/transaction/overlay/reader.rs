@@ -1,3 +1,5 @@
+use std::iter::FusedIterator;
+
 use ic_dbms_api::prelude::{ColumnDef, IcDbmsResult, TableSchema, Value};
 
 use crate::dbms::transaction::overlay::table::TableOverlay;
@@ -14,6 +16,8 @@ where
     table_overlay: &'a TableOverlay,
     /// The underlying table reader.
     table_reader: TableReader<'a, T>,
+    /// Whether reading a row failed, so that the iterator returns no more rows.
+    failed: bool,
     _marker: std::marker::PhantomData<T>,
 }
 
@@ -32,6 +36,7 @@ where
             new_rows_cursor: 0,
             table_overlay,
             table_reader,
+            failed: false,
             _marker: std::marker::PhantomData,
         }
     }
@@ -53,7 +58,7 @@ where
 
             // patch row;
             // NOTE: here if it gets None, it means it was deleted not that we finished reading, so we need to continue!
-            if let Some(patched) = self.table_overlay.patch_row(next_row) {
+            if let Some(patched) = self.table_overlay.patch_row(T::primary_keys(), next_row) {
                 return Ok(Some(patched));
             }
             // keep reading
@@ -64,10 +69,37 @@ where
     fn next_overlay_row(&mut self) -> Option<Vec<(ColumnDef, Value)>> {
         let row_to_get = self.new_rows_cursor;
         self.new_rows_cursor += 1;
-        self.table_overlay.iter_inserted().nth(row_to_get)
+        self.table_overlay
+            .iter_inserted(T::primary_keys())
+            .nth(row_to_get)
+    }
+}
+
+/// Iterates over the rows with the overlay changes applied, as [`DatabaseOverlayReader::try_next`] does.
+///
+/// After an error the reader is exhausted, so no more rows are returned.
+impl<T> Iterator for DatabaseOverlayReader<'_, T>
+where
+    T: TableSchema,
+{
+    type Item = IcDbmsResult<Vec<(ColumnDef, Value)>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        match self.try_next() {
+            Ok(row) => row.map(Ok),
+            Err(err) => {
+                self.failed = true;
+                Some(Err(err))
+            }
+        }
     }
 }
 
+impl<T> FusedIterator for DatabaseOverlayReader<'_, T> where T: TableSchema {}
+
 #[cfg(test)]
 mod tests {
 
@@ -116,8 +148,8 @@ mod tests {
         load_fixtures();
         let mut table_overlay = TableOverlay::default();
         let registry = registry();
-        table_overlay.delete(Value::Uint32(1.into()));
-        table_overlay.delete(Value::Uint32(9.into()));
+        table_overlay.delete(vec![Value::Uint32(1.into())]);
+        table_overlay.delete(vec![Value::Uint32(9.into())]);
 
         let table_reader = registry.read::<User>();
         let mut overlay_reader = DatabaseOverlayReader::new(&table_overlay, table_reader);
@@ -129,6 +161,27 @@ mod tests {
         assert_eq!(all.len(), USERS_FIXTURES.len() - 2);
     }
 
+    #[test]
+    fn test_should_iterate_over_rows() {
+        load_fixtures();
+        let mut table_overlay = TableOverlay::default();
+        let registry = registry();
+        table_overlay.delete(vec![Value::Uint32(1.into())]);
+
+        let table_reader = registry.read::<User>();
+        let ids = DatabaseOverlayReader::new(&table_overlay, table_reader)
+            .map(|row| row.expect("failed to read row"))
+            .filter_map(|row| match &row[0].1 {
+                Value::Uint32(id) => Some(id.0),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let expected = (0..USERS_FIXTURES.len() as u32)
+            .filter(|id| *id != 1)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, expected);
+    }
+
     #[test]
     fn test_should_return_inserted_records() {
         load_fixtures();
@@ -143,7 +196,10 @@ mod tests {
                     data_type: DataTypeKind::Uint32,
                     nullable: false,
                     primary_key: true,
+                    unique: false,
+                    auto_increment: false,
                     foreign_key: None,
+                    check: None,
                 },
                 first_pk.clone(),
             ),
@@ -153,7 +209,10 @@ mod tests {
                     data_type: DataTypeKind::Text,
                     nullable: false,
                     primary_key: false,
+                    unique: false,
+                    auto_increment: false,
                     foreign_key: None,
+                    check: None,
                 },
                 Value::Text("NewUser1".to_string().into()),
             ),
@@ -166,7 +225,10 @@ mod tests {
                     data_type: DataTypeKind::Uint32,
                     nullable: false,
                     primary_key: true,
+                    unique: false,
+                    auto_increment: false,
                     foreign_key: None,
+                    check: None,
                 },
                 second_pk.clone(),
             ),
@@ -176,13 +238,16 @@ mod tests {
                     data_type: DataTypeKind::Text,
                     nullable: false,
                     primary_key: false,
+                    unique: false,
+                    auto_increment: false,
                     foreign_key: None,
+                    check: None,
                 },
                 Value::Text("NewUser2".to_string().into()),
             ),
         ];
-        table_overlay.insert(first_pk.clone(), new_user_1.clone());
-        table_overlay.insert(second_pk.clone(), new_user_2.clone());
+        table_overlay.insert(vec![first_pk.clone()], new_user_1.clone());
+        table_overlay.insert(vec![second_pk.clone()], new_user_2.clone());
 
         let table_reader = registry.read::<User>();
         let mut overlay_reader = DatabaseOverlayReader::new(&table_overlay, table_reader);
@@ -227,7 +292,10 @@ mod tests {
                     data_type: DataTypeKind::Uint32,
                     nullable: false,
                     primary_key: true,
+                    unique: false,
+                    auto_increment: false,
                     foreign_key: None,
+                    check: None,
                 },
                 first_pk.clone(),
             ),
@@ -237,13 +305,16 @@ mod tests {
                     data_type: DataTypeKind::Text,
                     nullable: false,
                     primary_key: false,
+                    unique: false,
+                    auto_increment: false,
                     foreign_key: None,
+                    check: None,
                 },
                 Value::Text("NewUser1".to_string().into()),
             ),
         ];
-        table_overlay.insert(first_pk.clone(), new_user_1.clone());
-        table_overlay.delete(first_pk.clone());
+        table_overlay.insert(vec![first_pk.clone()], new_user_1.clone());
+        table_overlay.delete(vec![first_pk.clone()]);
 
         let table_reader = registry.read::<User>();
         let mut overlay_reader = DatabaseOverlayReader::new(&table_overlay, table_reader);
@@ -264,7 +335,7 @@ mod tests {
         let pk_to_update = Value::Uint32(1.into());
         let updated_name = "UpdatedName".to_string();
         let updates = vec![("name", Value::Text(updated_name.clone().into()))];
-        table_overlay.update(pk_to_update.clone(), updates);
+        table_overlay.update(vec![pk_to_update.clone()], updates);
 
         let table_reader = registry.read::<User>();
         let mut overlay_reader = DatabaseOverlayReader::new(&table_overlay, table_reader);
@@ -9,17 +9,18 @@ pub struct TableOverlay {
 
 /// An operation within a [`TableOverlay`].
 ///
-/// All operations are indexed by a primary key value.
+/// All operations are indexed by the values of the primary key columns,
+/// in the order of [`ic_dbms_api::prelude::TableSchema::primary_keys`].
 #[derive(Debug, Clone)]
 pub(super) enum Operation {
-    Insert(Value, Vec<(ColumnDef, Value)>),
-    Update(Value, Vec<(&'static str, Value)>),
-    Delete(Value),
+    Insert(Vec<Value>, Vec<(ColumnDef, Value)>),
+    Update(Vec<Value>, Vec<(&'static str, Value)>),
+    Delete(Vec<Value>),
 }
 
 impl Operation {
-    /// Get the primary key value associated with the operation.
-    fn primary_key_value(&self) -> &Value {
+    /// Get the primary key values associated with the operation.
+    fn primary_key_value(&self) -> &[Value] {
         match self {
             Operation::Insert(pk, _) => pk,
             Operation::Update(pk, _) => pk,
@@ -30,25 +31,35 @@ impl Operation {
 
 impl TableOverlay {
     /// Inserts a new record into the overlay.
-    pub fn insert(&mut self, pk: Value, record: Vec<(ColumnDef, Value)>) {
+    pub fn insert(&mut self, pk: Vec<Value>, record: Vec<(ColumnDef, Value)>) {
         self.operations.push(Operation::Insert(pk, record));
     }
 
     /// Updates a record in the overlay.
-    pub fn update(&mut self, pk: Value, updates: Vec<(&'static str, Value)>) {
+    pub fn update(&mut self, pk: Vec<Value>, updates: Vec<(&'static str, Value)>) {
         self.operations.push(Operation::Update(pk, updates));
     }
 
     /// Marks a record as deleted in the overlay.
-    pub fn delete(&mut self, pk: Value) {
+    pub fn delete(&mut self, pk: Vec<Value>) {
         self.operations.push(Operation::Delete(pk));
     }
 
+    /// Discards the operations applied after the first `len` ones.
+    pub fn truncate(&mut self, len: usize) {
+        self.operations.truncate(len);
+    }
+
     /// Returns an iterator over the inserted records which are still valid after the operation stack.
-    pub fn iter_inserted(&self) -> impl Iterator<Item = Vec<(ColumnDef, Value)>> {
-        self.operations.iter().filter_map(|op| {
+    ///
+    /// `primary_keys` are the names of the primary key columns of the table.
+    pub fn iter_inserted(
+        &self,
+        primary_keys: &[&'static str],
+    ) -> impl Iterator<Item = Vec<(ColumnDef, Value)>> {
+        self.operations.iter().filter_map(move |op| {
             if let Operation::Insert(_, record) = op {
-                self.patch_row(record.clone())
+                self.patch_row(primary_keys, record.clone())
             } else {
                 None
             }
@@ -58,22 +69,30 @@ impl TableOverlay {
     /// Patches a row with the overlay changes.
     ///
     /// The return may be [`None`] if the row has been deleted in the overlay.
+    /// `primary_keys` are the names of the primary key columns of the table.
     ///
     /// NOTE: `clippy::manual_try_fold`
     /// this lint is TOTALLY WRONG HERE. We may have a row which first becomes None (deleted), then an insert again returns Some.
     #[allow(clippy::manual_try_fold)]
-    pub fn patch_row(&self, row: Vec<(ColumnDef, Value)>) -> Option<Vec<(ColumnDef, Value)>> {
-        // get primary key value
-        let pk = row
+    pub fn patch_row(
+        &self,
+        primary_keys: &[&'static str],
+        row: Vec<(ColumnDef, Value)>,
+    ) -> Option<Vec<(ColumnDef, Value)>> {
+        // get primary key values
+        let pk = primary_keys
             .iter()
-            .find(|(col_def, _)| col_def.primary_key)
-            .map(|(_, value)| value)
-            .cloned()?;
+            .map(|pk| {
+                row.iter()
+                    .find(|(col_def, _)| col_def.name == *pk)
+                    .map(|(_, value)| value.clone())
+            })
+            .collect::<Option<Vec<Value>>>()?;
 
         // apply all operations for this primary key to the row
         self.operations
             .iter()
-            .filter(|op| op.primary_key_value() == &pk)
+            .filter(|op| op.primary_key_value() == pk.as_slice())
             .fold(Some(row), |acc, op| self.apply_operation(acc, op))
     }
 
@@ -111,15 +130,24 @@ mod tests {
 
     #[test]
     fn test_should_get_op_pk() {
-        let op = Operation::Insert(Value::Int32(1.into()), vec![]);
-        assert_eq!(op.primary_key_value(), &Value::Int32(1.into()));
-        let op = Operation::Update(Value::Text("key".to_string().into()), vec![]);
+        let op = Operation::Insert(vec![Value::Int32(1.into())], vec![]);
+        assert_eq!(op.primary_key_value(), &[Value::Int32(1.into())]);
+        let op = Operation::Update(
+            vec![
+                Value::Text("key".to_string().into()),
+                Value::Uint32(2.into()),
+            ],
+            vec![],
+        );
         assert_eq!(
             op.primary_key_value(),
-            &Value::Text("key".to_string().into())
+            &[
+                Value::Text("key".to_string().into()),
+                Value::Uint32(2.into())
+            ]
         );
-        let op = Operation::Delete(Value::Null);
-        assert_eq!(op.primary_key_value(), &Value::Null);
+        let op = Operation::Delete(vec![Value::Null]);
+        assert_eq!(op.primary_key_value(), &[Value::Null]);
     }
 
     #[test]
@@ -134,7 +162,10 @@ mod tests {
                     data_type: DataTypeKind::Uint32,
                     nullable: false,
                     primary_key: true,
+                    unique: false,
+                    auto_increment: false,
                     foreign_key: None,
+                    check: None,
                 },
                 pk.clone(),
             ),
@@ -144,7 +175,10 @@ mod tests {
                     data_type: DataTypeKind::Text,
                     nullable: false,
                     primary_key: false,
+                    unique: false,
+                    auto_increment: false,
                     foreign_key: None,
+                    check: None,
                 },
                 Value::Text("Alice".to_string().into()),
             ),
@@ -154,21 +188,24 @@ mod tests {
                     data_type: DataTypeKind::Uint32,
                     nullable: false,
                     primary_key: false,
+                    unique: false,
+                    auto_increment: false,
                     foreign_key: None,
+                    check: None,
                 },
                 Value::Uint32(24.into()),
             ),
         ];
         // update name
         overlay.update(
-            pk.clone(),
+            vec![pk.clone()],
             vec![("name", Value::Text("Bob".to_string().into()))],
         );
         // update age
-        overlay.update(pk.clone(), vec![("age", Value::Uint32(30.into()))]);
+        overlay.update(vec![pk.clone()], vec![("age", Value::Uint32(30.into()))]);
 
         // get patched row
-        let row = overlay.patch_row(row).expect("should be Some");
+        let row = overlay.patch_row(&["id"], row).expect("should be Some");
         assert_eq!(
             row,
             vec![
@@ -178,7 +215,10 @@ mod tests {
                         data_type: DataTypeKind::Uint32,
                         nullable: false,
                         primary_key: true,
+                        unique: false,
+                        auto_increment: false,
                         foreign_key: None,
+                        check: None,
                     },
                     pk.clone(),
                 ),
@@ -188,7 +228,10 @@ mod tests {
                         data_type: DataTypeKind::Text,
                         nullable: false,
                         primary_key: false,
+                        unique: false,
+                        auto_increment: false,
                         foreign_key: None,
+                        check: None,
                     },
                     Value::Text("Bob".to_string().into()),
                 ),
@@ -198,7 +241,10 @@ mod tests {
                         data_type: DataTypeKind::Uint32,
                         nullable: false,
                         primary_key: false,
+                        unique: false,
+                        auto_increment: false,
                         foreign_key: None,
+                        check: None,
                     },
                     Value::Uint32(30.into()),
                 ),
@@ -211,7 +257,7 @@ mod tests {
         let mut overlay = TableOverlay::default();
         let first_pk = Value::Uint32(1.into());
         overlay.insert(
-            first_pk.clone(),
+            vec![first_pk.clone()],
             vec![
                 (
                     ColumnDef {
@@ -219,7 +265,10 @@ mod tests {
                         data_type: DataTypeKind::Uint32,
                         nullable: false,
                         primary_key: true,
+                        unique: false,
+                        auto_increment: false,
                         foreign_key: None,
+                        check: None,
                     },
                     first_pk.clone(),
                 ),
@@ -229,7 +278,10 @@ mod tests {
                         data_type: DataTypeKind::Text,
                         nullable: false,
                         primary_key: false,
+                        unique: false,
+                        auto_increment: false,
                         foreign_key: None,
+                        check: None,
                     },
                     Value::Text("Alice".to_string().into()),
                 ),
@@ -239,7 +291,10 @@ mod tests {
                         data_type: DataTypeKind::Uint32,
                         nullable: false,
                         primary_key: false,
+                        unique: false,
+                        auto_increment: false,
                         foreign_key: None,
+                        check: None,
                     },
                     Value::Uint32(24.into()),
                 ),
@@ -247,7 +302,7 @@ mod tests {
         );
         let second_pk = Value::Uint32(2.into());
         overlay.insert(
-            second_pk.clone(),
+            vec![second_pk.clone()],
             vec![
                 (
                     ColumnDef {
@@ -255,7 +310,10 @@ mod tests {
                         data_type: DataTypeKind::Uint32,
                         nullable: false,
                         primary_key: true,
+                        unique: false,
+                        auto_increment: false,
                         foreign_key: None,
+                        check: None,
                     },
                     second_pk.clone(),
                 ),
@@ -265,7 +323,10 @@ mod tests {
                         data_type: DataTypeKind::Text,
                         nullable: false,
                         primary_key: false,
+                        unique: false,
+                        auto_increment: false,
                         foreign_key: None,
+                        check: None,
                     },
                     Value::Text("Bob".to_string().into()),
                 ),
@@ -275,7 +336,10 @@ mod tests {
                         data_type: DataTypeKind::Uint32,
                         nullable: false,
                         primary_key: false,
+                        unique: false,
+                        auto_increment: false,
                         foreign_key: None,
+                        check: None,
                     },
                     Value::Uint32(32.into()),
                 ),
@@ -283,12 +347,15 @@ mod tests {
         );
 
         // update second row
-        overlay.update(second_pk.clone(), vec![("age", Value::Uint32(33.into()))]);
+        overlay.update(
+            vec![second_pk.clone()],
+            vec![("age", Value::Uint32(33.into()))],
+        );
 
         // insert a third
         let third_pk = Value::Uint32(3.into());
         overlay.insert(
-            third_pk.clone(),
+            vec![third_pk.clone()],
             vec![
                 (
                     ColumnDef {
@@ -296,7 +363,10 @@ mod tests {
                         data_type: DataTypeKind::Uint32,
                         nullable: false,
                         primary_key: true,
+                        unique: false,
+                        auto_increment: false,
                         foreign_key: None,
+                        check: None,
                     },
                     third_pk.clone(),
                 ),
@@ -306,7 +376,10 @@ mod tests {
                         data_type: DataTypeKind::Text,
                         nullable: false,
                         primary_key: false,
+                        unique: false,
+                        auto_increment: false,
                         foreign_key: None,
+                        check: None,
                     },
                     Value::Text("Charlie".to_string().into()),
                 ),
@@ -316,7 +389,10 @@ mod tests {
                         data_type: DataTypeKind::Uint32,
                         nullable: false,
                         primary_key: false,
+                        unique: false,
+                        auto_increment: false,
                         foreign_key: None,
+                        check: None,
                     },
                     Value::Uint32(28.into()),
                 ),
@@ -324,15 +400,15 @@ mod tests {
         );
 
         // delete third
-        overlay.delete(third_pk.clone());
+        overlay.delete(vec![third_pk.clone()]);
 
         // update second row (again)
         overlay.update(
-            second_pk.clone(),
+            vec![second_pk.clone()],
             vec![("name", Value::Text("Robert".to_string().into()))],
         );
 
-        let inserted_rows: Vec<_> = overlay.iter_inserted().collect();
+        let inserted_rows: Vec<_> = overlay.iter_inserted(&["id"]).collect();
         assert_eq!(inserted_rows.len(), 2); // third should be deleted
         assert_eq!(
             inserted_rows[0],
@@ -343,7 +419,10 @@ mod tests {
                         data_type: DataTypeKind::Uint32,
                         nullable: false,
                         primary_key: true,
+                        unique: false,
+                        auto_increment: false,
                         foreign_key: None,
+                        check: None,
                     },
                     first_pk.clone(),
                 ),
@@ -353,7 +432,10 @@ mod tests {
                         data_type: DataTypeKind::Text,
                         nullable: false,
                         primary_key: false,
+                        unique: false,
+                        auto_increment: false,
                         foreign_key: None,
+                        check: None,
                     },
                     Value::Text("Alice".to_string().into()),
                 ),
@@ -363,7 +445,10 @@ mod tests {
                         data_type: DataTypeKind::Uint32,
                         nullable: false,
                         primary_key: false,
+                        unique: false,
+                        auto_increment: false,
                         foreign_key: None,
+                        check: None,
                     },
                     Value::Uint32(24.into()),
                 ),
@@ -378,7 +463,10 @@ mod tests {
                         data_type: DataTypeKind::Uint32,
                         nullable: false,
                         primary_key: true,
+                        unique: false,
+                        auto_increment: false,
                         foreign_key: None,
+                        check: None,
                     },
                     second_pk.clone(),
                 ),
@@ -388,7 +476,10 @@ mod tests {
                         data_type: DataTypeKind::Text,
                         nullable: false,
                         primary_key: false,
+                        unique: false,
+                        auto_increment: false,
                         foreign_key: None,
+                        check: None,
                     },
                     Value::Text("Robert".to_string().into()), // patched name
                 ),
@@ -398,11 +489,57 @@ mod tests {
                         data_type: DataTypeKind::Uint32,
                         nullable: false,
                         primary_key: false,
+                        unique: false,
+                        auto_increment: false,
                         foreign_key: None,
+                        check: None,
                     },
                     Value::Uint32(33.into()), // patched age
                 ),
             ]
         );
     }
+
+    #[test]
+    fn test_should_patch_row_by_composite_primary_key() {
+        let column = |name, primary_key| ColumnDef {
+            name,
+            data_type: DataTypeKind::Uint32,
+            nullable: false,
+            primary_key,
+            unique: false,
+            auto_increment: false,
+            foreign_key: None,
+            check: None,
+        };
+        let row = |student_id: u32, course: u32, grade: u32| {
+            vec![
+                (column("student_id", true), Value::Uint32(student_id.into())),
+                (column("course", true), Value::Uint32(course.into())),
+                (column("grade", false), Value::Uint32(grade.into())),
+            ]
+        };
+        let pk = |student_id: u32, course: u32| {
+            vec![
+                Value::Uint32(student_id.into()),
+                Value::Uint32(course.into()),
+            ]
+        };
+        let primary_keys = &["student_id", "course"];
+
+        let mut overlay = TableOverlay::default();
+        overlay.update(pk(1, 1), vec![("grade", Value::Uint32(30.into()))]);
+        overlay.delete(pk(1, 2));
+
+        // only the rows matching all the primary key values are patched
+        assert_eq!(
+            overlay.patch_row(primary_keys, row(1, 1, 18)),
+            Some(row(1, 1, 30))
+        );
+        assert_eq!(overlay.patch_row(primary_keys, row(1, 2, 18)), None);
+        assert_eq!(
+            overlay.patch_row(primary_keys, row(2, 1, 18)),
+            Some(row(2, 1, 18))
+        );
+    }
 }
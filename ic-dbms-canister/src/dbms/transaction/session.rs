@@ -1,15 +1,35 @@
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use candid::{Nat, Principal};
-use ic_dbms_api::prelude::{IcDbmsError, IcDbmsResult, QueryError, TransactionId};
+use ic_dbms_api::prelude::{
+    IcDbmsError, IcDbmsResult, QueryError, SavepointId, TransactionError, TransactionId,
+};
 
-use super::Transaction;
+use super::{Transaction, TransactionInfo};
+use crate::utils::time;
 
 thread_local! {
     pub static TRANSACTION_SESSION: RefCell<TransactionSession> = RefCell::new(TransactionSession::default());
 }
 
+/// Closes the transactions in the [`TRANSACTION_SESSION`] past their TTL at the current time,
+/// returning their [`TransactionId`]s.
+///
+/// It should be called periodically, such as from the canister heartbeat,
+/// to clean up the transactions begun with [`TransactionSession::begin_transaction_with_ttl`]
+/// which have been abandoned by their callers.
+///
+/// ```rust,ignore
+/// #[ic_cdk::heartbeat]
+/// fn heartbeat() {
+///     ic_dbms_canister::prelude::expire_stale_transactions();
+/// }
+/// ```
+pub fn expire_stale_transactions() -> Vec<TransactionId> {
+    TRANSACTION_SESSION.with_borrow_mut(|ts| ts.expire_stale_transactions(time()))
+}
+
 /// The [`Transaction`] session storage
 #[derive(Default, Debug)]
 pub struct TransactionSession {
@@ -17,28 +37,262 @@ pub struct TransactionSession {
     transactions: HashMap<TransactionId, Transaction>,
     /// Map between transaction IDs and their owner ([`Principal`]).
     owners: HashMap<TransactionId, Principal>,
+    /// Map between transaction IDs and the principals granted access to them besides the owner.
+    grants: HashMap<TransactionId, HashSet<Principal>>,
+    /// Map between nested transaction IDs and the ID of their parent transaction.
+    parents: HashMap<TransactionId, TransactionId>,
+    /// Map between transaction IDs and their TTL, for the transactions begun with a TTL.
+    ttls: HashMap<TransactionId, TransactionTtl>,
     /// Next transaction ID
     next_transaction_id: TransactionId,
 }
 
+/// The time to live of a transaction, after which it's closed by [`TransactionSession::expire_stale_transactions`].
+#[derive(Debug, Clone, Copy)]
+struct TransactionTtl {
+    /// Time the transaction was begun at, in nanoseconds since the UNIX epoch.
+    created_at: u64,
+    /// Time to live of the transaction in nanoseconds.
+    ttl: u64,
+}
+
+impl TransactionTtl {
+    /// Returns the nanoseconds left before the transaction expires at the given time.
+    fn remaining(&self, now: u64) -> u64 {
+        self.ttl.saturating_sub(now.saturating_sub(self.created_at))
+    }
+}
+
 impl TransactionSession {
     /// Begins a new transaction for the given owner ([`Principal`]) and returns its [`TransactionId`].
     pub fn begin_transaction(&mut self, owner: Principal) -> TransactionId {
+        self.insert_transaction(owner, Transaction::new(owner))
+    }
+
+    /// Begins a new read-only transaction for the given owner ([`Principal`]) and returns its [`TransactionId`].
+    ///
+    /// Write operations on a read-only transaction fail with [`TransactionError::ReadOnlyTransaction`],
+    /// so committing it doesn't write anything to memory.
+    pub fn begin_read_only_transaction(&mut self, owner: Principal) -> TransactionId {
+        self.insert_transaction(owner, Transaction::read_only(owner))
+    }
+
+    /// Inserts the given [`Transaction`] owned by the given [`Principal`] and returns its [`TransactionId`].
+    fn insert_transaction(&mut self, owner: Principal, transaction: Transaction) -> TransactionId {
         let transaction_id = self.next_transaction_id.clone();
         self.next_transaction_id += Nat::from(1u64);
 
+        let read_only = transaction.is_read_only();
         self.transactions
-            .insert(transaction_id.clone(), Transaction::default());
+            .insert(transaction_id.clone(), transaction);
         self.owners.insert(transaction_id.clone(), owner);
+        crate::dbms_log!(INFO, "transaction begun"; "transaction" => transaction_id, "owner" => owner, "read_only" => read_only);
+
+        transaction_id
+    }
+
+    /// Begins a new transaction for the given owner ([`Principal`]) which expires after `ttl_nanos` nanoseconds,
+    /// and returns its [`TransactionId`].
+    ///
+    /// Once expired, the transaction is closed by [`TransactionSession::expire_stale_transactions`],
+    /// so that transactions abandoned by their callers don't stay in memory indefinitely.
+    pub fn begin_transaction_with_ttl(
+        &mut self,
+        owner: Principal,
+        ttl_nanos: u64,
+    ) -> TransactionId {
+        let transaction_id = self.begin_transaction(owner);
+        self.ttls.insert(
+            transaction_id.clone(),
+            TransactionTtl {
+                created_at: time(),
+                ttl: ttl_nanos,
+            },
+        );
 
         transaction_id
     }
 
-    /// Checks if a transaction with the given [`TransactionId`] exists and is owned by the given [`Principal`].
+    /// Returns the nanoseconds left at the given time before the transaction with the given [`TransactionId`] expires.
+    ///
+    /// Returns [`None`] if the transaction doesn't exist or has been begun without a TTL.
+    pub fn remaining_ttl(&self, transaction_id: &TransactionId, now_nanos: u64) -> Option<u64> {
+        self.ttls
+            .get(transaction_id)
+            .map(|ttl| ttl.remaining(now_nanos))
+    }
+
+    /// Closes the transactions past their TTL at the given time, returning their [`TransactionId`]s.
+    ///
+    /// The transactions nested in an expired transaction are closed along with it,
+    /// since they can't be merged into their parent anymore.
+    pub fn expire_stale_transactions(&mut self, now_nanos: u64) -> Vec<TransactionId> {
+        let mut expired: Vec<TransactionId> = self
+            .ttls
+            .iter()
+            .filter(|(_, ttl)| ttl.remaining(now_nanos) == 0)
+            .map(|(transaction_id, _)| transaction_id.clone())
+            .collect();
+
+        // close the nested transactions of the expired ones too
+        let mut index = 0;
+        while index < expired.len() {
+            let nested = self
+                .parents
+                .iter()
+                .filter(|(_, parent_id)| **parent_id == expired[index])
+                .map(|(transaction_id, _)| transaction_id.clone())
+                .filter(|transaction_id| !expired.contains(transaction_id))
+                .collect::<Vec<_>>();
+            expired.extend(nested);
+            index += 1;
+        }
+
+        for transaction_id in &expired {
+            self.close_transaction(transaction_id);
+            crate::dbms_log!(WARN, "transaction expired"; "transaction" => transaction_id);
+        }
+
+        expired
+    }
+
+    /// Begins a new transaction nested in the transaction with the given `parent_id` and returns its [`TransactionId`].
+    ///
+    /// The nested transaction starts from the state of its parent, and has the same owner and grants.
+    /// On commit, it is merged into its parent with [`TransactionSession::merge_into_parent`],
+    /// rather than being applied to memory; on rollback, the parent is left as is.
+    pub fn begin_nested(&mut self, parent_id: &TransactionId) -> IcDbmsResult<TransactionId> {
+        let mut transaction = self.get_transaction(parent_id)?.clone();
+        transaction.created_at_nanos = time();
+        let owner = *self
+            .owners
+            .get(parent_id)
+            .ok_or(IcDbmsError::Query(QueryError::TransactionNotFound))?;
+
+        let transaction_id = self.next_transaction_id.clone();
+        self.next_transaction_id += Nat::from(1u64);
+
+        self.transactions
+            .insert(transaction_id.clone(), transaction);
+        self.owners.insert(transaction_id.clone(), owner);
+        if let Some(grants) = self.grants.get(parent_id).cloned() {
+            self.grants.insert(transaction_id.clone(), grants);
+        }
+        self.parents
+            .insert(transaction_id.clone(), parent_id.clone());
+        crate::dbms_log!(INFO, "nested transaction begun"; "transaction" => transaction_id, "parent" => parent_id);
+
+        Ok(transaction_id)
+    }
+
+    /// Returns the [`TransactionId`] of the parent of the given transaction, if it is nested.
+    pub fn parent(&self, transaction_id: &TransactionId) -> Option<&TransactionId> {
+        self.parents.get(transaction_id)
+    }
+
+    /// Merges the nested transaction with the given [`TransactionId`] into its parent, closing it.
+    ///
+    /// Since the nested transaction started from the state of its parent, the parent takes its operations and overlay.
+    /// Returns the [`TransactionId`] of the parent.
+    pub fn merge_into_parent(
+        &mut self,
+        transaction_id: &TransactionId,
+    ) -> IcDbmsResult<TransactionId> {
+        let parent_id = self
+            .parents
+            .get(transaction_id)
+            .cloned()
+            .ok_or(IcDbmsError::Query(QueryError::TransactionNotFound))?;
+        // check the parent is still open before closing the nested transaction
+        self.get_transaction(&parent_id)?;
+
+        let mut transaction = self.take_transaction(transaction_id)?;
+        let parent = self.get_transaction_mut(&parent_id)?;
+        // the parent keeps its own creation time
+        transaction.created_at_nanos = parent.created_at_nanos;
+        *parent = transaction;
+
+        Ok(parent_id)
+    }
+
+    /// Takes a savepoint of the transaction with the given [`TransactionId`] and returns its [`SavepointId`].
+    ///
+    /// See [`Transaction::savepoint`].
+    pub fn savepoint(&mut self, transaction_id: &TransactionId) -> IcDbmsResult<SavepointId> {
+        let savepoint_id = self.get_transaction_mut(transaction_id)?.savepoint();
+        crate::dbms_log!(INFO, "savepoint taken"; "transaction" => transaction_id, "savepoint" => savepoint_id);
+
+        Ok(savepoint_id)
+    }
+
+    /// Rolls the transaction with the given [`TransactionId`] back to the savepoint with the given [`SavepointId`],
+    /// without closing it.
+    ///
+    /// See [`Transaction::rollback_to_savepoint`].
+    pub fn rollback_to_savepoint(
+        &mut self,
+        transaction_id: &TransactionId,
+        savepoint_id: SavepointId,
+    ) -> IcDbmsResult<()> {
+        self.get_transaction_mut(transaction_id)?
+            .rollback_to_savepoint(savepoint_id)?;
+        crate::dbms_log!(INFO, "transaction rolled back to savepoint"; "transaction" => transaction_id, "savepoint" => savepoint_id);
+
+        Ok(())
+    }
+
+    /// Checks if a transaction with the given [`TransactionId`] exists and is accessible by the given [`Principal`],
+    /// either because it owns the transaction or because it has been granted access to it.
     pub fn has_transaction(&self, transaction_id: &TransactionId, caller: Principal) -> bool {
-        self.owners
+        self.check_access(transaction_id, caller).is_ok()
+    }
+
+    /// Checks whether the given [`Principal`] can operate on the transaction with the given [`TransactionId`].
+    ///
+    /// Fails with [`TransactionError::Unauthorized`] if the caller is neither the owner of the transaction,
+    /// nor has been granted access to it with [`TransactionSession::grant_access`].
+    pub fn check_access(
+        &self,
+        transaction_id: &TransactionId,
+        caller: Principal,
+    ) -> IcDbmsResult<()> {
+        let owner = *self
+            .owners
             .get(transaction_id)
-            .is_some_and(|owner| *owner == caller)
+            .ok_or(IcDbmsError::Query(QueryError::TransactionNotFound))?;
+
+        let granted = self
+            .grants
+            .get(transaction_id)
+            .is_some_and(|grants| grants.contains(&caller));
+        if owner != caller && !granted {
+            return Err(IcDbmsError::Transaction(TransactionError::Unauthorized {
+                expected: owner,
+                caller,
+            }));
+        }
+
+        Ok(())
+    }
+
+    /// Grants the given [`Principal`] access to the transaction with the given [`TransactionId`].
+    ///
+    /// This allows a secondary principal, such as another canister, to operate on the transaction.
+    pub fn grant_access(
+        &mut self,
+        transaction_id: &TransactionId,
+        principal: Principal,
+    ) -> IcDbmsResult<()> {
+        if !self.owners.contains_key(transaction_id) {
+            return Err(IcDbmsError::Query(QueryError::TransactionNotFound));
+        }
+
+        self.grants
+            .entry(transaction_id.clone())
+            .or_default()
+            .insert(principal);
+
+        Ok(())
     }
 
     /// Retrieves the [`Transaction`] associated with the given [`TransactionId`].
@@ -51,6 +305,15 @@ impl TransactionSession {
         Ok(transaction)
     }
 
+    /// Returns the [`TransactionInfo`] of the transaction with the given [`TransactionId`],
+    /// such as the [`Principal`] which began it and when.
+    pub fn transaction_info(
+        &self,
+        transaction_id: &TransactionId,
+    ) -> IcDbmsResult<TransactionInfo> {
+        self.get_transaction(transaction_id).map(Transaction::info)
+    }
+
     /// Removes and returns the [`Transaction`] associated with the given [`TransactionId`].
     ///
     /// This is usually done when committing a transaction.
@@ -63,6 +326,9 @@ impl TransactionSession {
             .remove(transaction_id)
             .ok_or(IcDbmsError::Query(QueryError::TransactionNotFound))?;
         self.owners.remove(transaction_id);
+        self.grants.remove(transaction_id);
+        self.parents.remove(transaction_id);
+        self.ttls.remove(transaction_id);
 
         Ok(transaction)
     }
@@ -71,6 +337,9 @@ impl TransactionSession {
     pub fn close_transaction(&mut self, transaction_id: &TransactionId) {
         self.transactions.remove(transaction_id);
         self.owners.remove(transaction_id);
+        self.grants.remove(transaction_id);
+        self.parents.remove(transaction_id);
+        self.ttls.remove(transaction_id);
     }
 
     /// Retrieves a mutable reference to the [`Transaction`] associated with the given [`TransactionId`].
@@ -87,7 +356,11 @@ impl TransactionSession {
 #[cfg(test)]
 mod tests {
 
+    use ic_dbms_api::prelude::{Filter, Value};
+
     use super::*;
+    use crate::dbms::transaction::{TransactionMode, TransactionOp};
+    use crate::utils::set_time;
 
     #[test]
     fn test_should_begin_transaction() {
@@ -101,6 +374,123 @@ mod tests {
         assert!(transaction.is_ok());
     }
 
+    #[test]
+    fn test_should_begin_read_only_transaction() {
+        let mut session = TransactionSession::default();
+        let transaction_id = session.begin_read_only_transaction(alice());
+
+        let transaction = session
+            .get_transaction_mut(&transaction_id)
+            .expect("failed to get tx");
+        assert!(transaction.is_read_only());
+        assert!(matches!(
+            transaction.if_exists(
+                "users",
+                Filter::eq("id", Value::Uint32(1u32.into())),
+                vec![],
+                vec![]
+            ),
+            Err(IcDbmsError::Transaction(
+                TransactionError::ReadOnlyTransaction
+            ))
+        ));
+        assert!(transaction.operations.is_empty());
+
+        let nested_id = session
+            .begin_nested(&transaction_id)
+            .expect("failed to begin nested transaction");
+        assert!(
+            session
+                .get_transaction(&nested_id)
+                .expect("failed to get tx")
+                .is_read_only()
+        );
+        let writable_id = session.begin_transaction(alice());
+        assert!(
+            !session
+                .get_transaction(&writable_id)
+                .expect("failed to get tx")
+                .is_read_only()
+        );
+    }
+
+    #[test]
+    fn test_should_get_transaction_info() {
+        set_time(1_000);
+        let mut session = TransactionSession::default();
+        let transaction_id = session.begin_transaction(alice());
+        session
+            .get_transaction_mut(&transaction_id)
+            .expect("failed to get tx")
+            .operations
+            .push(TransactionOp::IfExists {
+                table: "users",
+                filter: Filter::eq("id", Value::Uint32(1u32.into())),
+                then_ops: vec![],
+                else_ops: vec![],
+            });
+
+        let info = session
+            .transaction_info(&transaction_id)
+            .expect("failed to get tx info");
+        assert_eq!(
+            info,
+            TransactionInfo {
+                caller: alice(),
+                created_at_nanos: 1_000,
+                operations: 1,
+                mode: TransactionMode::ReadWrite,
+            }
+        );
+
+        set_time(2_000);
+        let nested_id = session
+            .begin_nested(&transaction_id)
+            .expect("failed to begin nested transaction");
+        let read_only_id = session.begin_read_only_transaction(bob());
+        let nested_info = session
+            .transaction_info(&nested_id)
+            .expect("failed to get tx info");
+        assert_eq!(nested_info.caller, alice());
+        assert_eq!(nested_info.created_at_nanos, 2_000);
+        let read_only_info = session
+            .transaction_info(&read_only_id)
+            .expect("failed to get tx info");
+        assert_eq!(read_only_info.caller, bob());
+        assert_eq!(read_only_info.mode, TransactionMode::ReadOnly);
+
+        // the parent keeps its creation time after the merge
+        session
+            .merge_into_parent(&nested_id)
+            .expect("failed to merge");
+        assert_eq!(
+            session
+                .transaction_info(&transaction_id)
+                .expect("failed to get tx info")
+                .created_at_nanos,
+            1_000
+        );
+
+        assert!(matches!(
+            session.transaction_info(&TransactionId::from(42u64)),
+            Err(IcDbmsError::Query(QueryError::TransactionNotFound))
+        ));
+    }
+
+    #[test]
+    fn test_should_record_transaction_caller_and_creation_time() {
+        let mut session = TransactionSession::default();
+        let transaction_id = session.begin_transaction(bob());
+
+        let info = session
+            .transaction_info(&transaction_id)
+            .expect("failed to get tx info");
+        assert_eq!(info.caller, bob());
+        assert!(info.created_at_nanos > 0);
+        assert_eq!(info.operations, 0);
+        assert_eq!(info.mode, TransactionMode::ReadWrite);
+    }
+
     #[test]
     fn test_should_close_transaction() {
         let mut session = TransactionSession::default();
@@ -143,6 +533,240 @@ mod tests {
             .expect("failed to get tx");
     }
 
+    #[test]
+    fn test_should_grant_access_to_transaction() {
+        let mut session = TransactionSession::default();
+        let transaction_id = session.begin_transaction(alice());
+
+        assert!(matches!(
+            session.check_access(&transaction_id, bob()),
+            Err(IcDbmsError::Transaction(TransactionError::Unauthorized { expected, caller }))
+                if expected == alice() && caller == bob()
+        ));
+
+        session
+            .grant_access(&transaction_id, bob())
+            .expect("failed to grant access");
+        assert!(session.check_access(&transaction_id, bob()).is_ok());
+        assert!(session.has_transaction(&transaction_id, bob()));
+        assert!(session.has_transaction(&transaction_id, alice()));
+
+        session.close_transaction(&transaction_id);
+        assert!(!session.grants.contains_key(&transaction_id));
+    }
+
+    #[test]
+    fn test_should_not_grant_access_to_unexisting_transaction() {
+        let mut session = TransactionSession::default();
+
+        assert!(matches!(
+            session.grant_access(&TransactionId::from(42u64), bob()),
+            Err(IcDbmsError::Query(QueryError::TransactionNotFound))
+        ));
+    }
+
+    #[test]
+    fn test_should_begin_nested_transaction() {
+        let mut session = TransactionSession::default();
+        let parent_id = session.begin_transaction(alice());
+        session
+            .grant_access(&parent_id, bob())
+            .expect("failed to grant access");
+
+        let nested_id = session
+            .begin_nested(&parent_id)
+            .expect("failed to begin nested transaction");
+        assert_ne!(nested_id, parent_id);
+        assert_eq!(session.parent(&nested_id), Some(&parent_id));
+        assert!(session.parent(&parent_id).is_none());
+        assert!(session.has_transaction(&nested_id, alice()));
+        assert!(session.has_transaction(&nested_id, bob()));
+
+        assert!(matches!(
+            session.begin_nested(&TransactionId::from(42u64)),
+            Err(IcDbmsError::Query(QueryError::TransactionNotFound))
+        ));
+    }
+
+    #[test]
+    fn test_should_merge_nested_transaction_into_parent() {
+        let mut session = TransactionSession::default();
+        let parent_id = session.begin_transaction(alice());
+        let nested_id = session
+            .begin_nested(&parent_id)
+            .expect("failed to begin nested transaction");
+        session
+            .get_transaction_mut(&nested_id)
+            .expect("failed to get tx")
+            .operations
+            .push(TransactionOp::IfExists {
+                table: "users",
+                filter: Filter::eq("id", Value::Uint32(1u32.into())),
+                then_ops: vec![],
+                else_ops: vec![],
+            });
+
+        assert_eq!(
+            session
+                .merge_into_parent(&nested_id)
+                .expect("failed to merge"),
+            parent_id
+        );
+        assert_eq!(
+            session
+                .get_transaction(&parent_id)
+                .expect("failed to get tx")
+                .operations
+                .len(),
+            1
+        );
+        assert!(session.get_transaction(&nested_id).is_err());
+        assert!(session.parent(&nested_id).is_none());
+
+        // top-level transactions can't be merged
+        assert!(matches!(
+            session.merge_into_parent(&parent_id),
+            Err(IcDbmsError::Query(QueryError::TransactionNotFound))
+        ));
+    }
+
+    #[test]
+    fn test_should_close_nested_transaction_leaving_parent() {
+        let mut session = TransactionSession::default();
+        let parent_id = session.begin_transaction(alice());
+        let nested_id = session
+            .begin_nested(&parent_id)
+            .expect("failed to begin nested transaction");
+        session
+            .get_transaction_mut(&nested_id)
+            .expect("failed to get tx")
+            .operations
+            .push(TransactionOp::IfExists {
+                table: "users",
+                filter: Filter::eq("id", Value::Uint32(1u32.into())),
+                then_ops: vec![],
+                else_ops: vec![],
+            });
+
+        session.close_transaction(&nested_id);
+        assert!(session.parent(&nested_id).is_none());
+        assert!(
+            session
+                .get_transaction(&parent_id)
+                .expect("failed to get tx")
+                .operations
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_should_rollback_to_savepoint() {
+        let mut session = TransactionSession::default();
+        let transaction_id = session.begin_transaction(alice());
+        let op = TransactionOp::IfExists {
+            table: "users",
+            filter: Filter::eq("id", Value::Uint32(1u32.into())),
+            then_ops: vec![],
+            else_ops: vec![],
+        };
+        session
+            .get_transaction_mut(&transaction_id)
+            .expect("failed to get tx")
+            .operations
+            .push(op.clone());
+
+        let first = session
+            .savepoint(&transaction_id)
+            .expect("failed to take savepoint");
+        session
+            .get_transaction_mut(&transaction_id)
+            .expect("failed to get tx")
+            .operations
+            .push(op.clone());
+        let second = session
+            .savepoint(&transaction_id)
+            .expect("failed to take savepoint");
+        assert_ne!(first, second);
+        session
+            .get_transaction_mut(&transaction_id)
+            .expect("failed to get tx")
+            .operations
+            .push(op);
+
+        session
+            .rollback_to_savepoint(&transaction_id, first)
+            .expect("failed to rollback to savepoint");
+        assert_eq!(
+            session
+                .get_transaction(&transaction_id)
+                .expect("failed to get tx")
+                .operations
+                .len(),
+            1
+        );
+        assert!(session.has_transaction(&transaction_id, alice()));
+
+        // the savepoints taken after the restored one are released
+        assert!(matches!(
+            session.rollback_to_savepoint(&transaction_id, second),
+            Err(IcDbmsError::Transaction(TransactionError::SavepointNotFound(id))) if id == second
+        ));
+        assert!(
+            session
+                .rollback_to_savepoint(&transaction_id, first)
+                .is_ok()
+        );
+        assert!(matches!(
+            session.savepoint(&TransactionId::from(42u64)),
+            Err(IcDbmsError::Query(QueryError::TransactionNotFound))
+        ));
+    }
+
+    #[test]
+    fn test_should_expire_stale_transactions() {
+        set_time(1_000);
+        let mut session = TransactionSession::default();
+        let transaction_id = session.begin_transaction_with_ttl(alice(), 1);
+        let nested_id = session
+            .begin_nested(&transaction_id)
+            .expect("failed to begin nested transaction");
+        let lasting_id = session.begin_transaction_with_ttl(alice(), 1_000);
+        let untimed_id = session.begin_transaction(alice());
+
+        assert_eq!(session.remaining_ttl(&transaction_id, 1_000), Some(1));
+        assert_eq!(session.remaining_ttl(&lasting_id, 1_500), Some(500));
+        assert_eq!(session.remaining_ttl(&untimed_id, 1_500), None);
+        assert!(session.expire_stale_transactions(1_000).is_empty());
+
+        let mut expired = session.expire_stale_transactions(1_001);
+        expired.sort();
+        assert_eq!(expired, vec![transaction_id.clone(), nested_id.clone()]);
+        assert!(!session.has_transaction(&transaction_id, alice()));
+        assert!(!session.has_transaction(&nested_id, alice()));
+        assert!(session.parent(&nested_id).is_none());
+        assert!(session.remaining_ttl(&transaction_id, 1_001).is_none());
+        assert!(session.has_transaction(&lasting_id, alice()));
+        assert!(session.has_transaction(&untimed_id, alice()));
+
+        assert_eq!(
+            session.expire_stale_transactions(u64::MAX),
+            vec![lasting_id.clone()]
+        );
+        assert!(session.has_transaction(&untimed_id, alice()));
+    }
+
+    #[test]
+    fn test_should_forget_ttl_of_closed_transaction() {
+        let mut session = TransactionSession::default();
+        let transaction_id = session.begin_transaction_with_ttl(alice(), 1);
+        session
+            .take_transaction(&transaction_id)
+            .expect("failed to take tx");
+
+        assert!(session.ttls.is_empty());
+        assert!(session.expire_stale_transactions(u64::MAX).is_empty());
+    }
+
     fn alice() -> Principal {
         Principal::from_text("ryjl3-tyaaa-aaaaa-aaaba-cai").unwrap()
     }
@@ -0,0 +1,339 @@
+//! This module exposes the watchers notified of the changes performed on the tables.
+
+use std::cell::RefCell;
+use std::marker::PhantomData;
+
+use ic_dbms_api::prelude::{ColumnDef, TableRecord as _, TableSchema, Value, ValuesSource};
+
+thread_local! {
+    /// The registered watchers of all the tables.
+    ///
+    /// Since thread locals can't be generic, each watcher is stored as a `Box<dyn AnyWatcher>`
+    /// and notified only of the changes performed on its table.
+    pub static WATCHER_REGISTRY: RefCell<Vec<Box<dyn AnyWatcher>>> = RefCell::new(Vec::new());
+}
+
+/// A watcher notified of the changes performed on the table `T`.
+///
+/// Watchers are notified after each successful INSERT, UPDATE and DELETE performed through
+/// [`crate::prelude::IcDbmsDatabase`]. The changes performed within a transaction are notified
+/// once the transaction is committed, while rolled back transactions are never notified.
+///
+/// All the callbacks do nothing by default.
+pub trait TableWatcher<T>
+where
+    T: TableSchema,
+{
+    /// Called after `record` has been inserted.
+    fn on_insert(&self, _record: &T::Record) {}
+
+    /// Called after the `old` record has been updated to `new`.
+    fn on_update(&self, _old: &T::Record, _new: &T::Record) {}
+
+    /// Called after `record` has been deleted.
+    fn on_delete(&self, _record: &T::Record) {}
+}
+
+/// A change performed on a table, described by the column values of the affected record.
+#[derive(Debug, Clone, Copy)]
+pub enum TableChange<'a> {
+    Insert(&'a [(ColumnDef, Value)]),
+    Update {
+        old: &'a [(ColumnDef, Value)],
+        new: &'a [(ColumnDef, Value)],
+    },
+    Delete(&'a [(ColumnDef, Value)]),
+}
+
+/// A type-erased [`TableWatcher`], which can be stored in the [`WATCHER_REGISTRY`].
+pub trait AnyWatcher {
+    /// Name of the table watched.
+    fn table_name(&self) -> &'static str;
+
+    /// Notifies the watcher of a change performed on its table.
+    fn notify(&self, change: TableChange<'_>);
+}
+
+/// [`AnyWatcher`] implementation for a [`TableWatcher`] of the table `T`.
+struct TypedWatcher<T, W>
+where
+    T: TableSchema,
+    W: TableWatcher<T>,
+{
+    watcher: W,
+    _table: PhantomData<T>,
+}
+
+impl<T, W> TypedWatcher<T, W>
+where
+    T: TableSchema,
+    W: TableWatcher<T>,
+{
+    /// Builds the [`TableRecord`](ic_dbms_api::prelude::TableRecord) of `T` from its column values.
+    fn record(values: &[(ColumnDef, Value)]) -> T::Record {
+        T::Record::from_values(vec![(ValuesSource::This, values.to_vec())])
+    }
+}
+
+impl<T, W> AnyWatcher for TypedWatcher<T, W>
+where
+    T: TableSchema,
+    W: TableWatcher<T>,
+{
+    fn table_name(&self) -> &'static str {
+        T::table_name()
+    }
+
+    fn notify(&self, change: TableChange<'_>) {
+        match change {
+            TableChange::Insert(values) => self.watcher.on_insert(&Self::record(values)),
+            TableChange::Update { old, new } => self
+                .watcher
+                .on_update(&Self::record(old), &Self::record(new)),
+            TableChange::Delete(values) => self.watcher.on_delete(&Self::record(values)),
+        }
+    }
+}
+
+/// Registers a [`TableWatcher`] for the table `T` into the [`WATCHER_REGISTRY`].
+pub fn register_watcher<T, W>(watcher: W)
+where
+    T: TableSchema,
+    W: TableWatcher<T> + 'static,
+{
+    WATCHER_REGISTRY.with_borrow_mut(|registry| {
+        registry.push(Box::new(TypedWatcher {
+            watcher,
+            _table: PhantomData,
+        }))
+    });
+}
+
+/// Notifies all the watchers of `table` of the given change.
+pub(crate) fn notify_watchers(table: &'static str, change: TableChange<'_>) {
+    WATCHER_REGISTRY.with_borrow(|registry| {
+        registry
+            .iter()
+            .filter(|watcher| watcher.table_name() == table)
+            .for_each(|watcher| watcher.notify(change));
+    });
+}
+
+/// Registers a [`TableWatcher`] into the [`WATCHER_REGISTRY`].
+///
+/// The watched table is inferred from the [`TableWatcher`] implementation,
+/// but it can be given explicitly with `table = ...;` before the watcher.
+///
+/// ```rust,ignore
+/// struct UserCache;
+///
+/// impl TableWatcher<User> for UserCache {
+///     fn on_update(&self, _old: &UserRecord, new: &UserRecord) {
+///         // refresh the cached user
+///     }
+/// }
+///
+/// #[ic_cdk::init]
+/// fn init() {
+///     ic_dbms_canister::register_watcher!(UserCache);
+///     // or
+///     ic_dbms_canister::register_watcher!(table = User; UserCache);
+/// }
+/// ```
+#[macro_export]
+macro_rules! register_watcher {
+    (table = $table:ty; $watcher:expr) => {
+        $crate::dbms::watcher::register_watcher::<$table, _>($watcher)
+    };
+    ($watcher:expr) => {
+        $crate::dbms::watcher::register_watcher($watcher)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::rc::Rc;
+
+    use candid::Principal;
+    use ic_dbms_api::prelude::{
+        Database as _, DeleteBehavior, Filter, Query, Text, Uint32, UpdateBehavior,
+        Value as DbmsValue,
+    };
+
+    use super::*;
+    use crate::dbms::IcDbmsDatabase;
+    use crate::dbms::transaction::TRANSACTION_SESSION;
+    use crate::tests::{
+        Post, PostRecord, TestDatabaseSchema, User, UserInsertRequest, UserRecord,
+        UserUpdateRequest, load_fixtures,
+    };
+
+    /// A watcher which logs the notifications it receives.
+    #[derive(Default, Clone)]
+    struct LogWatcher {
+        events: Rc<RefCell<Vec<String>>>,
+    }
+
+    impl LogWatcher {
+        fn events(&self) -> Vec<String> {
+            self.events.borrow().clone()
+        }
+
+        fn log(&self, event: String) {
+            self.events.borrow_mut().push(event);
+        }
+    }
+
+    fn user_id(record: &UserRecord) -> u32 {
+        record.id.expect("should have id").0
+    }
+
+    impl TableWatcher<User> for LogWatcher {
+        fn on_insert(&self, record: &UserRecord) {
+            self.log(format!("insert {}", user_id(record)));
+        }
+
+        fn on_update(&self, old: &UserRecord, new: &UserRecord) {
+            self.log(format!(
+                "update {} {} -> {}",
+                user_id(new),
+                old.name.as_ref().expect("should have name").0,
+                new.name.as_ref().expect("should have name").0
+            ));
+        }
+
+        fn on_delete(&self, record: &UserRecord) {
+            self.log(format!("delete {}", user_id(record)));
+        }
+    }
+
+    /// A [`LogWatcher`] of the [`Post`] table.
+    struct PostLogWatcher(LogWatcher);
+
+    impl TableWatcher<Post> for PostLogWatcher {
+        fn on_delete(&self, record: &PostRecord) {
+            self.0.log(format!(
+                "delete post {}",
+                record.id.expect("should have id").0
+            ));
+        }
+    }
+
+    fn new_user(id: u32, name: &str) -> UserInsertRequest {
+        UserInsertRequest {
+            id: Uint32(id),
+            name: Text(name.to_string()),
+        }
+    }
+
+    fn rename_user(id: u32, name: &str) -> UserUpdateRequest {
+        UserUpdateRequest {
+            id: None,
+            name: Some(Text(name.to_string())),
+            where_clause: Some(Filter::eq("id", DbmsValue::Uint32(Uint32(id)))),
+        }
+    }
+
+    #[test]
+    fn test_should_notify_each_oneshot_operation_once() {
+        load_fixtures();
+        let watcher = LogWatcher::default();
+        crate::register_watcher!(table = User; watcher.clone());
+
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        dbms.insert::<User>(new_user(100, "Watched"))
+            .expect("failed to insert user");
+        dbms.update::<User>(UpdateBehavior::Restrict, rename_user(100, "Renamed"))
+            .expect("failed to update user");
+        dbms.delete::<User>(
+            DeleteBehavior::Break,
+            Some(Filter::eq("id", DbmsValue::Uint32(Uint32(100)))),
+        )
+        .expect("failed to delete user");
+
+        assert_eq!(
+            watcher.events(),
+            vec![
+                "insert 100".to_string(),
+                "update 100 Watched -> Renamed".to_string(),
+                "delete 100".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_should_notify_only_the_watchers_of_the_changed_table() {
+        load_fixtures();
+        let watcher = LogWatcher::default();
+        crate::register_watcher!(PostLogWatcher(watcher.clone()));
+
+        let dbms = IcDbmsDatabase::oneshot(TestDatabaseSchema);
+        dbms.insert::<User>(new_user(100, "Unwatched"))
+            .expect("failed to insert user");
+        assert!(watcher.events().is_empty());
+
+        // posts deleted by cascade are notified too
+        let posts = dbms
+            .select::<Post>(
+                Query::builder()
+                    .and_where(Filter::eq("user_id", DbmsValue::Uint32(Uint32(1))))
+                    .build(),
+            )
+            .expect("failed to select posts");
+        assert!(!posts.is_empty());
+        dbms.delete::<User>(
+            DeleteBehavior::Cascade,
+            Some(Filter::eq("id", DbmsValue::Uint32(Uint32(1)))),
+        )
+        .expect("failed to delete user");
+        assert_eq!(watcher.events().len(), posts.len());
+        assert!(
+            watcher
+                .events()
+                .iter()
+                .all(|event| event.starts_with("delete post"))
+        );
+    }
+
+    #[test]
+    fn test_should_notify_transaction_operations_on_commit() {
+        load_fixtures();
+        let watcher = LogWatcher::default();
+        crate::register_watcher!(watcher.clone());
+
+        let transaction_id =
+            TRANSACTION_SESSION.with_borrow_mut(|ts| ts.begin_transaction(Principal::anonymous()));
+        let mut dbms = IcDbmsDatabase::from_transaction(TestDatabaseSchema, transaction_id);
+        dbms.insert::<User>(new_user(100, "Watched"))
+            .expect("failed to insert user");
+        dbms.update::<User>(UpdateBehavior::Restrict, rename_user(100, "Renamed"))
+            .expect("failed to update user");
+        assert!(watcher.events().is_empty());
+
+        dbms.commit().expect("failed to commit transaction");
+        assert_eq!(
+            watcher.events(),
+            vec![
+                "insert 100".to_string(),
+                "update 100 Watched -> Renamed".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_should_not_notify_rolled_back_transaction_operations() {
+        load_fixtures();
+        let watcher = LogWatcher::default();
+        crate::register_watcher!(watcher.clone());
+
+        let transaction_id =
+            TRANSACTION_SESSION.with_borrow_mut(|ts| ts.begin_transaction(Principal::anonymous()));
+        let mut dbms = IcDbmsDatabase::from_transaction(TestDatabaseSchema, transaction_id);
+        dbms.insert::<User>(new_user(100, "Watched"))
+            .expect("failed to insert user");
+        dbms.rollback().expect("failed to rollback transaction");
+
+        assert!(watcher.events().is_empty());
+    }
+}
@@ -5,7 +5,25 @@
 //! # IC DBMS Canister
 //!
 //! TODO
-
+//!
+//! ## Features
+//!
+//! The optional features of the DBMS are enabled with the following feature flags:
+//!
+//! - `indexes`: tracks the indexes of the tables in the `IndexRegistry`, keeping the B-tree indexes up to date on every write.
+//!   A build without it marks the indexes of the tables it writes as stale, so they are rebuilt once it's enabled again.
+//! - `compression`: LZ4 compression of the records of the tables whose `TableSchema::use_compression` returns `true`.
+//! - `full`: enables all the features above.
+//!
+//! No feature is enabled by default.
+//!
+//! The DBMS has no write-ahead log, audit trail, page encryption nor full-text search,
+//! so there are no `wal`, `audit-log`, `encryption` and `full-text-search` flags,
+//! and `ic-dbms-api` has no feature flags, since none of its code is optional.
+#![cfg_attr(
+    not(feature = "indexes"),
+    doc = "\n\nWithout the `indexes` feature, the index registry is not available:\n\n```compile_fail\nuse ic_dbms_canister::memory::IndexRegistry;\n```"
+)]
 #![doc(html_playground_url = "https://play.rust-lang.org")]
 #![doc(
     html_favicon_url = "https://raw.githubusercontent.com/veeso/ic-dbms-canister/main/assets/images/cargo/logo-128.png"
@@ -1,18 +1,45 @@
 //! Memory module provides stable memory management for the IC DBMS Canister.
 
 mod acl;
+mod buffer_pool;
+mod idempotency_registry;
+#[cfg(feature = "indexes")]
+mod index_registry;
+mod page_checksum;
 mod provider;
 mod schema_registry;
 mod table_registry;
+mod write_stats;
 
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 
-use ic_dbms_api::prelude::{DataSize, Encode, MSize, MemoryError, MemoryResult, Page, PageOffset};
+use ic_dbms_api::prelude::{
+    DataSize, DecodeError, Encode, MSize, MemoryError, MemoryResult, Page, PageOffset,
+};
 
 pub use self::acl::{ACL, AccessControlList};
+pub use self::buffer_pool::{BUFFER_POOL, BufferPool, DEFAULT_BUFFER_POOL_CAPACITY};
+pub use self::idempotency_registry::{
+    CandidResult, IDEMPOTENCY_REGISTRY, IdempotencyEntry, IdempotencyRegistry, IdempotencyToken,
+    IdempotentResult,
+};
+#[cfg(feature = "indexes")]
+#[cfg_attr(docsrs, doc(cfg(feature = "indexes")))]
+pub use self::index_registry::{
+    BTreeIndex, IndexEntry, IndexId, IndexInfo, IndexMaintainer, IndexRange, IndexRegistry,
+    TableIndexes,
+};
+pub use self::page_checksum::PageChecksum;
 use self::provider::MemoryProvider;
-pub use self::schema_registry::{SCHEMA_REGISTRY, SchemaRegistry, TableRegistryPage};
-pub use self::table_registry::{NextRecord, TableReader, TableRegistry};
+pub use self::schema_registry::{
+    AlterTableOp, ColumnLayout, INITIAL_SCHEMA_VERSION, MigrationPhase, SCHEMA_REGISTRY,
+    SchemaRegistry, SchemaVersion, SequenceRegistry, TableQuota, TableRegistryPage,
+};
+pub use self::table_registry::{
+    CompactionStats, InfallibleTableReader, NextRecord, TableReader, TableRegistry, TableStats,
+    VacuumStats,
+};
+pub use self::write_stats::{WriteAmpStats, WriteCategory};
 
 // instantiate a static memory manager with the stable memory provider
 thread_local! {
@@ -32,12 +59,32 @@ const SCHEMA_PAGE: Page = 0;
 /// The page for ACL
 const ACL_PAGE: Page = 1;
 
+/// A snapshot of the memory, taken with [`MemoryManager::snapshot`].
+///
+/// On the heap memory provider the snapshot contains a copy of the whole memory,
+/// while on the IC stable memory it only records the amount of allocated pages.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemorySnapshot {
+    /// Amount of pages allocated when the snapshot was taken.
+    pages: u64,
+    /// Copy of the memory, if the provider supports it.
+    data: Option<Vec<u8>>,
+}
+
 /// The memory manager is the main struct responsible for handling the stable memory operations.
 pub struct MemoryManager<P>
 where
     P: MemoryProvider,
 {
     provider: P,
+    /// Write amplification statistics since the initialization or the last reset.
+    stats: WriteAmpStats,
+    /// Amount of reads performed on the memory provider since the initialization or the last reset.
+    physical_reads: Cell<u64>,
+    /// Whether the pages end with a [`PageChecksum`].
+    ///
+    /// Memories written before page checksums were introduced are left unchecksummed.
+    checksums: bool,
 }
 
 impl<P> MemoryManager<P>
@@ -48,10 +95,31 @@ where
     ///
     /// Panics if the memory provider fails to initialize.
     fn init(provider: P) -> Self {
-        let mut manager = MemoryManager { provider };
+        let mut manager = MemoryManager {
+            provider,
+            stats: WriteAmpStats::default(),
+            physical_reads: Cell::new(0),
+            checksums: true,
+        };
 
         // check whether two pages are already allocated
         if manager.provider.pages() >= 2 {
+            // the memory is checksummed only if the schema page has the checksum trailer
+            let mut trailer = [0u8; PageChecksum::LEN];
+            let trailer_offset = P::PAGE_SIZE - PageChecksum::LEN as u64;
+            if let Err(err) = manager.provider.read(
+                manager.absolute_offset(SCHEMA_PAGE, 0) + trailer_offset,
+                &mut trailer,
+            ) {
+                crate::trap!("Failed to read schema page during initialization: {err}");
+            }
+            manager.checksums = PageChecksum::has_trailer(&trailer);
+            if !manager.checksums {
+                crate::dbms_log!(
+                    WARN,
+                    "memory has no page checksums; checksum verification is disabled"
+                );
+            }
             return manager;
         }
 
@@ -60,12 +128,34 @@ where
             crate::trap!("Failed to grow stable memory during initialization: {err}");
         }
 
+        // the reserved pages are checksummed as any other page
+        for page in [SCHEMA_PAGE, ACL_PAGE] {
+            if let Err(err) = manager
+                .provider
+                .write(manager.absolute_offset(page, 0), &manager.zeroed_page())
+            {
+                crate::trap!(
+                    "Failed to write checksum of page {page} during initialization: {err}"
+                );
+            }
+        }
+
         manager
     }
 
-    /// Returns the size of a memory page.
+    /// Returns the usable size of a memory page,
+    /// which excludes the [`PageChecksum`] stored in the last bytes of the page, if the memory is checksummed.
     pub const fn page_size(&self) -> u64 {
-        P::PAGE_SIZE
+        if self.checksums {
+            P::PAGE_SIZE - PageChecksum::LEN as u64
+        } else {
+            P::PAGE_SIZE
+        }
+    }
+
+    /// Returns whether the pages of the memory are checksummed.
+    pub const fn checksums(&self) -> bool {
+        self.checksums
     }
 
     /// Returns the ACL page number.
@@ -78,6 +168,47 @@ where
         SCHEMA_PAGE
     }
 
+    /// Takes a [`MemorySnapshot`] of the current memory state.
+    ///
+    /// Mostly useful to isolate tests, see `ic_dbms_macros::memory_test`.
+    pub fn snapshot(&self) -> MemorySnapshot {
+        self.provider.snapshot()
+    }
+
+    /// Restores the memory to the state of the given [`MemorySnapshot`].
+    ///
+    /// Panics if the memory provider fails to restore the snapshot.
+    pub fn restore_snapshot(&mut self, snapshot: MemorySnapshot) {
+        if let Err(err) = self.provider.restore(snapshot) {
+            crate::trap!("Failed to restore memory snapshot: {err}");
+        }
+        BUFFER_POOL.with_borrow_mut(|pool| pool.clear());
+    }
+
+    /// Returns the [`WriteAmpStats`] since the initialization or the last [`MemoryManager::reset_stats`].
+    pub fn write_amplification_tracker(&self) -> WriteAmpStats {
+        self.stats.with_ratio()
+    }
+
+    /// Returns the amount of reads performed on the memory provider
+    /// since the initialization or the last [`MemoryManager::reset_stats`].
+    ///
+    /// Reads served by the [`BUFFER_POOL`] are not counted.
+    pub fn physical_reads(&self) -> u64 {
+        self.physical_reads.get()
+    }
+
+    /// Resets the [`WriteAmpStats`] and the amount of physical reads.
+    pub fn reset_stats(&mut self) {
+        self.stats = WriteAmpStats::default();
+        self.physical_reads.set(0);
+    }
+
+    /// Records a logical write, performed when a record is inserted into a table.
+    pub fn record_logical_write(&mut self) {
+        self.stats.record_logical_write();
+    }
+
     /// Allocates an additional page in memory.
     ///
     /// In case of success returns the [`Page`] number.
@@ -87,36 +218,62 @@ where
         // zero page CHECK: is it really necessary?
         self.provider.write(
             self.absolute_offset(self.last_page().unwrap_or(0), 0),
-            &vec![0u8; P::PAGE_SIZE as usize],
+            &self.zeroed_page(),
         )?;
+        self.stats.record_physical_write(None, P::PAGE_SIZE);
 
         match self.last_page() {
-            Some(page) => Ok(page),
+            Some(page) => {
+                // the page may be cached from before the memory was restored to a snapshot
+                BUFFER_POOL.with_borrow_mut(|pool| pool.invalidate(page));
+                Ok(page)
+            }
             None => Err(MemoryError::FailedToAllocatePage),
         }
     }
 
-    /// Read data as a [`Encode`] impl at the specified page and offset.
+    /// Read data as a [`Encode`] impl at the specified page and offset, through the [`BUFFER_POOL`].
+    ///
+    /// As in [`MemoryManager::read_at_cached`], the [`PageChecksum`] of the page is verified when it enters the pool,
+    /// returning [`MemoryError::ChecksumMismatch`] if the page is corrupted.
     pub fn read_at<D>(&self, page: Page, offset: PageOffset) -> MemoryResult<D>
     where
         D: Encode,
     {
-        // read until end of the page (or fixed size)
-        let mut buf = vec![
-            0u8;
-            match D::SIZE {
-                DataSize::Fixed(size) => size as usize,
-                DataSize::Dynamic => (P::PAGE_SIZE as usize) - (offset as usize),
-            }
-        ];
+        // decode until end of the page (or fixed size)
+        let start = offset as usize;
+        let end = match D::SIZE {
+            DataSize::Fixed(size) => start + size as usize,
+            DataSize::Dynamic => self.page_size() as usize,
+        };
 
-        self.read_at_raw(page, offset, &mut buf)?;
+        // the data is copied out of the pool, since decoding may read other pages
+        let data = self
+            .with_cached_page(page, |data| data.get(start..end).map(<[u8]>::to_vec))?
+            .ok_or(MemoryError::DecodeError(DecodeError::TooShort))?;
 
-        D::decode(std::borrow::Cow::Owned(buf))
+        D::decode_ref(&data)
     }
 
     /// Write data as a [`Encode`] impl at the specified page and offset.
+    ///
+    /// The write is accounted as [`WriteCategory::Data`].
     pub fn write_at<E>(&mut self, page: Page, offset: PageOffset, data: &E) -> MemoryResult<()>
+    where
+        E: Encode,
+    {
+        self.write_metadata_at(WriteCategory::Data, page, offset, data)
+    }
+
+    /// Write data as a [`Encode`] impl at the specified page and offset,
+    /// accounting it as the given [`WriteCategory`].
+    pub fn write_metadata_at<E>(
+        &mut self,
+        category: WriteCategory,
+        page: Page,
+        offset: PageOffset,
+        data: &E,
+    ) -> MemoryResult<()>
     where
         E: Encode,
     {
@@ -126,25 +283,65 @@ where
                 page,
                 offset,
                 data_size: data.size(),
-                page_size: P::PAGE_SIZE,
+                page_size: self.page_size(),
             });
         }
 
         let encoded = data.encode();
 
-        // if page exists, the write must be within bounds
-        if offset as u64 + encoded.len() as u64 > P::PAGE_SIZE {
+        // if page exists, the write must be within bounds and not overwrite the checksum
+        if offset as u64 + encoded.len() as u64 > self.page_size() {
             return Err(MemoryError::SegmentationFault {
                 page,
                 offset,
                 data_size: data.size(),
-                page_size: P::PAGE_SIZE,
+                page_size: self.page_size(),
             });
         }
 
+        // the overwritten bytes are needed to update the checksum
+        let old = if self.checksums {
+            self.read_region(page, offset as usize, encoded.len())?
+        } else {
+            vec![]
+        };
+
         // get absolute offset
         let absolute_offset = self.absolute_offset(page, offset);
-        self.provider.write(absolute_offset, encoded.as_ref())
+        self.provider.write(absolute_offset, encoded.as_ref())?;
+        self.stats
+            .record_physical_write(Some(category), encoded.len() as u64);
+        // keep the cached page, if any, in sync
+        BUFFER_POOL.with_borrow_mut(|pool| pool.update(page, offset as usize, encoded.as_ref()));
+
+        self.update_checksum(page, offset, &old, encoded.as_ref())
+    }
+
+    /// Write data as a [`Encode`] impl at the specified page and offset, caching the page in the [`BUFFER_POOL`].
+    ///
+    /// The write goes through to memory as well, so the cached page is never dirty.
+    /// The write is accounted as [`WriteCategory::Data`].
+    pub fn write_at_cached<E>(
+        &mut self,
+        page: Page,
+        offset: PageOffset,
+        data: &E,
+    ) -> MemoryResult<()>
+    where
+        E: Encode,
+    {
+        self.write_at(page, offset, data)?;
+        if BUFFER_POOL.with_borrow(|pool| pool.capacity_pages() == 0) {
+            return Ok(());
+        }
+
+        // cache the whole page, unless it's already cached and has been updated by the write
+        if !BUFFER_POOL.with_borrow_mut(|pool| pool.get(page).is_some()) {
+            let buf = self.read_page(page)?;
+            BUFFER_POOL.with_borrow_mut(|pool| pool.insert(page, buf));
+        }
+
+        Ok(())
     }
 
     /// Zeros out data at the specified page and offset.
@@ -158,28 +355,64 @@ where
                 page,
                 offset,
                 data_size: data.size(),
-                page_size: P::PAGE_SIZE,
+                page_size: self.page_size(),
             });
         }
 
         let length = data.size() as usize;
 
-        if offset as u64 + (length as u64) > P::PAGE_SIZE {
+        if offset as u64 + (length as u64) > self.page_size() {
             return Err(MemoryError::SegmentationFault {
                 page,
                 offset,
                 data_size: data.size(),
-                page_size: P::PAGE_SIZE,
+                page_size: self.page_size(),
             });
         }
 
+        // the zeroed bytes are needed to update the checksum
+        let old = if self.checksums {
+            self.read_region(page, offset as usize, length)?
+        } else {
+            vec![]
+        };
+
         // get absolute offset
         let absolute_offset = self.absolute_offset(page, offset);
         let buffer = vec![0u8; length];
-        self.provider.write(absolute_offset, buffer.as_ref())
+        self.provider.write(absolute_offset, buffer.as_ref())?;
+        self.stats
+            .record_physical_write(Some(WriteCategory::Data), length as u64);
+        BUFFER_POOL.with_borrow_mut(|pool| pool.update(page, offset as usize, &buffer));
+
+        self.update_checksum(page, offset, &old, &buffer)
+    }
+
+    /// Zeroes the whole given page.
+    pub fn zero_page(&mut self, page: Page) -> MemoryResult<()> {
+        // can't zero unallocated page
+        if self.last_page().is_none_or(|last_page| page > last_page) {
+            return Err(MemoryError::SegmentationFault {
+                page,
+                offset: 0,
+                data_size: 0,
+                page_size: P::PAGE_SIZE,
+            });
+        }
+
+        let absolute_offset = self.absolute_offset(page, 0);
+        let buffer = self.zeroed_page();
+        self.provider.write(absolute_offset, buffer.as_ref())?;
+        self.stats
+            .record_physical_write(Some(WriteCategory::Data), P::PAGE_SIZE);
+        BUFFER_POOL.with_borrow_mut(|pool| pool.update(page, 0, &buffer));
+
+        Ok(())
     }
 
     /// Reads raw bytes into the provided buffer at the specified page and offset.
+    ///
+    /// Unlike [`MemoryManager::read_at`], the [`PageChecksum`] of the page is not verified.
     pub fn read_at_raw(
         &self,
         page: Page,
@@ -203,10 +436,123 @@ where
         let absolute_offset = self.absolute_offset(page, offset);
         self.provider
             .read(absolute_offset, buf[..read_len].as_mut())?;
+        self.physical_reads.set(self.physical_reads.get() + 1);
 
         Ok(read_len)
     }
 
+    /// Reads bytes into the provided buffer at the specified page and offset through the [`BUFFER_POOL`].
+    ///
+    /// On a miss, the whole page is read from memory, verifying its [`PageChecksum`], and cached,
+    /// so that the reads served by the pool are verified as well.
+    /// Returns [`MemoryError::ChecksumMismatch`] if the page is corrupted.
+    pub fn read_at_cached(
+        &self,
+        page: Page,
+        offset: PageOffset,
+        buf: &mut [u8],
+    ) -> MemoryResult<usize> {
+        let read_len = ((P::PAGE_SIZE.saturating_sub(offset as u64)) as usize).min(buf.len());
+        let start = offset as usize;
+        self.with_cached_page(page, |data| {
+            buf[..read_len].copy_from_slice(&data[start..start + read_len])
+        })?;
+
+        Ok(read_len)
+    }
+
+    /// Calls `f` with the data of the given page, served by the [`BUFFER_POOL`] if cached.
+    ///
+    /// On a miss, the whole page is read from memory, verifying its [`PageChecksum`], and cached,
+    /// so that the checksum of a page is computed once, when it enters the pool.
+    fn with_cached_page<R>(&self, page: Page, mut f: impl FnMut(&[u8]) -> R) -> MemoryResult<R> {
+        if let Some(result) = BUFFER_POOL.with_borrow_mut(|pool| pool.get(page).map(&mut f)) {
+            crate::dbms_log!(DEBUG, "buffer pool hit"; "page" => page);
+            return Ok(result);
+        }
+
+        crate::dbms_log!(DEBUG, "buffer pool miss"; "page" => page);
+        let data = self.read_page(page)?;
+        let result = f(&data);
+        BUFFER_POOL.with_borrow_mut(|pool| pool.insert(page, data));
+
+        Ok(result)
+    }
+
+    /// Reads the whole given page, verifying its [`PageChecksum`] if the memory is checksummed.
+    fn read_page(&self, page: Page) -> MemoryResult<Vec<u8>> {
+        let mut data = vec![0u8; P::PAGE_SIZE as usize];
+        self.read_at_raw(page, 0, &mut data)?;
+        if self.checksums {
+            PageChecksum::verify(page, &data)?;
+        }
+
+        Ok(data)
+    }
+
+    /// Reads `len` bytes at the given offset of the page, from the [`BUFFER_POOL`] if cached.
+    ///
+    /// The lookup doesn't count as an access to the pool.
+    fn read_region(&self, page: Page, offset: usize, len: usize) -> MemoryResult<Vec<u8>> {
+        if let Some(region) = BUFFER_POOL.with_borrow(|pool| {
+            pool.peek(page)
+                .and_then(|data| data.get(offset..offset + len))
+                .map(<[u8]>::to_vec)
+        }) {
+            return Ok(region);
+        }
+
+        let mut region = vec![0u8; len];
+        self.read_at_raw(page, offset as PageOffset, &mut region)?;
+        Ok(region)
+    }
+
+    /// Updates the [`PageChecksum`] of the given page after `old` has been overwritten with `new` at `offset`,
+    /// without reading the rest of the page.
+    ///
+    /// Does nothing if the memory is not checksummed.
+    fn update_checksum(
+        &mut self,
+        page: Page,
+        offset: PageOffset,
+        old: &[u8],
+        new: &[u8],
+    ) -> MemoryResult<()> {
+        if !self.checksums {
+            return Ok(());
+        }
+
+        let checksum_offset = self.page_size() as usize + PageChecksum::CHECKSUM_OFFSET;
+        let stored = PageChecksum::decode_ref(&self.read_region(
+            page,
+            checksum_offset,
+            PageChecksum::LEN - PageChecksum::CHECKSUM_OFFSET,
+        )?)?;
+        let trailing_len = self.page_size() - offset as u64 - new.len() as u64;
+        let encoded = stored.update(old, new, trailing_len).encode();
+
+        self.provider.write(
+            self.absolute_offset(page, checksum_offset as PageOffset),
+            encoded.as_ref(),
+        )?;
+        self.stats
+            .record_physical_write(Some(WriteCategory::PageChecksum), encoded.len() as u64);
+        BUFFER_POOL.with_borrow_mut(|pool| pool.update(page, checksum_offset, encoded.as_ref()));
+
+        Ok(())
+    }
+
+    /// Returns a zeroed page, with the [`PageChecksum`] of its zeroed data if the memory is checksummed.
+    fn zeroed_page(&self) -> Vec<u8> {
+        let mut data = vec![0u8; P::PAGE_SIZE as usize];
+        if self.checksums {
+            let (payload, trailer) = data.split_at_mut(self.page_size() as usize);
+            trailer.copy_from_slice(&PageChecksum::of(payload).trailer());
+        }
+
+        data
+    }
+
     /// Gets the last allocated page number.
     fn last_page(&self) -> Option<Page> {
         match self.provider.pages() {
@@ -229,6 +575,8 @@ mod tests {
 
     use std::borrow::Cow;
 
+    use ic_dbms_api::prelude::Uint32;
+
     use super::*;
     use crate::memory::provider::HeapMemoryProvider;
     use crate::tests::User;
@@ -238,6 +586,52 @@ mod tests {
         MEMORY_MANAGER.with_borrow(|manager| assert_eq!(manager.last_page(), Some(1)));
     }
 
+    #[test]
+    fn test_should_snapshot_and_restore_memory() {
+        let snapshot = MEMORY_MANAGER.with_borrow(|manager| manager.snapshot());
+
+        MEMORY_MANAGER.with_borrow_mut(|manager| {
+            let page = manager.allocate_page().expect("failed to allocate page");
+            manager
+                .write_at(page, 0, &Uint32(42))
+                .expect("failed to write");
+        });
+        MEMORY_MANAGER.with_borrow(|manager| assert_eq!(manager.last_page(), Some(2)));
+
+        MEMORY_MANAGER.with_borrow_mut(|manager| manager.restore_snapshot(snapshot));
+        MEMORY_MANAGER.with_borrow(|manager| assert_eq!(manager.last_page(), Some(1)));
+    }
+
+    #[test]
+    fn test_should_not_leak_memory_test_state() {
+        let pages = MEMORY_MANAGER.with_borrow(|manager| manager.last_page());
+        let acl_page = MEMORY_MANAGER.with_borrow(|manager| manager.acl_page());
+
+        // run the memory test on the same thread
+        write_in_memory_test();
+
+        MEMORY_MANAGER.with_borrow(|manager| {
+            assert_eq!(manager.last_page(), pages);
+            let mut buf = [0u8; 4];
+            manager
+                .read_at_raw(acl_page, 0, &mut buf)
+                .expect("failed to read ACL page");
+            assert_eq!(buf, [0u8; 4]);
+        });
+    }
+
+    #[ic_dbms_macros::memory_test]
+    fn write_in_memory_test() {
+        MEMORY_MANAGER.with_borrow_mut(|manager| {
+            let acl_page = manager.acl_page();
+            manager
+                .write_at(acl_page, 0, &Uint32(0xdeadbeef))
+                .expect("failed to write");
+            manager.allocate_page().expect("failed to allocate page");
+        });
+        MEMORY_MANAGER.with_borrow(|manager| assert_eq!(manager.last_page(), Some(2)));
+    }
+
     #[test]
     fn test_should_get_last_page() {
         MEMORY_MANAGER.with_borrow(|manager| {
@@ -250,7 +644,10 @@ mod tests {
     fn test_should_get_memory_page_size() {
         MEMORY_MANAGER.with_borrow(|manager| {
             let page_size = manager.page_size();
-            assert_eq!(page_size, HeapMemoryProvider::PAGE_SIZE);
+            assert_eq!(
+                page_size,
+                HeapMemoryProvider::PAGE_SIZE - PageChecksum::LEN as u64
+            );
         });
     }
 
@@ -309,6 +706,34 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_should_zero_page() {
+        MEMORY_MANAGER.with_borrow_mut(|manager| {
+            let page = manager.allocate_page().expect("Failed to allocate page");
+            let data_to_write = FixedSizeData { a: 100, b: 200 };
+            manager
+                .write_at(page, 50, &data_to_write)
+                .expect("Failed to write data");
+
+            manager.zero_page(page).expect("Failed to zero page");
+
+            let mut buffer = vec![1; manager.page_size() as usize];
+            manager
+                .read_at_raw(page, 0, &mut buffer)
+                .expect("Failed to read page");
+            assert!(buffer.iter().all(|&b| b == 0));
+            let out: Uint32 = manager
+                .read_at(page, 50)
+                .expect("Failed to read zeroed page");
+            assert_eq!(out, Uint32(0));
+
+            assert!(matches!(
+                manager.zero_page(page + 1),
+                Err(MemoryError::SegmentationFault { .. })
+            ));
+        });
+    }
+
     #[test]
     fn test_should_not_zero_unallocated_page() {
         MEMORY_MANAGER.with_borrow_mut(|manager| {
@@ -350,6 +775,42 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_should_count_physical_reads() {
+        BUFFER_POOL.with_borrow_mut(|pool| pool.clear());
+        MEMORY_MANAGER.with_borrow_mut(|manager| {
+            manager.reset_stats();
+            let mut buf = [0u8; 4];
+            manager
+                .read_at_raw(ACL_PAGE, 0, &mut buf)
+                .expect("failed to read");
+            let _: Uint32 = manager.read_at(ACL_PAGE, 0).expect("failed to read");
+            assert_eq!(manager.physical_reads(), 2);
+
+            manager.reset_stats();
+            assert_eq!(manager.physical_reads(), 0);
+        });
+    }
+
+    #[test]
+    fn test_should_verify_checksum_once_per_cached_page() {
+        MEMORY_MANAGER.with_borrow_mut(|manager| {
+            let page = manager.allocate_page().expect("failed to allocate page");
+            manager
+                .write_at(page, 8, &Uint32(42))
+                .expect("failed to write");
+            BUFFER_POOL.with_borrow_mut(|pool| pool.clear());
+            manager.reset_stats();
+
+            // the page is read and verified when it enters the pool, then served by it
+            for _ in 0..3 {
+                let out: Uint32 = manager.read_at(page, 8).expect("failed to read");
+                assert_eq!(out, Uint32(42));
+            }
+            assert_eq!(manager.physical_reads(), 1);
+        });
+    }
+
     #[test]
     fn test_should_fail_out_of_bounds_access() {
         MEMORY_MANAGER.with_borrow_mut(|manager| {
@@ -382,6 +843,231 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_should_read_cached_page_once() {
+        use crate::dbms::logger::{LogLevel, VecLogger, set_logger};
+
+        let logger = VecLogger::default();
+        set_logger(logger.clone());
+
+        MEMORY_MANAGER.with_borrow_mut(|manager| {
+            manager
+                .write_at(ACL_PAGE, 8, &Uint32(42))
+                .expect("failed to write");
+
+            let mut first = [0u8; 4];
+            let mut second = [0u8; 4];
+            manager
+                .read_at_cached(ACL_PAGE, 8, &mut first)
+                .expect("failed to read");
+            manager
+                .read_at_cached(ACL_PAGE, 8, &mut second)
+                .expect("failed to read");
+            assert_eq!(first, 42u32.to_le_bytes());
+            assert_eq!(second, first);
+        });
+
+        let messages = logger
+            .entries()
+            .into_iter()
+            .map(|entry| (entry.level, entry.message))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            vec![
+                (LogLevel::Debug, "buffer pool miss".to_string()),
+                (LogLevel::Debug, "buffer pool hit".to_string()),
+            ]
+        );
+        BUFFER_POOL.with_borrow(|pool| {
+            assert_eq!(pool.hits(), 1);
+            assert_eq!(pool.misses(), 1);
+        });
+    }
+
+    #[test]
+    fn test_should_keep_cached_page_in_sync_with_writes() {
+        MEMORY_MANAGER.with_borrow_mut(|manager| {
+            let mut buf = [0u8; 4];
+            manager
+                .read_at_cached(ACL_PAGE, 0, &mut buf)
+                .expect("failed to read");
+            assert_eq!(buf, [0u8; 4]);
+
+            manager
+                .write_at(ACL_PAGE, 0, &Uint32(7))
+                .expect("failed to write");
+            manager
+                .read_at_cached(ACL_PAGE, 0, &mut buf)
+                .expect("failed to read");
+            assert_eq!(buf, 7u32.to_le_bytes());
+
+            manager
+                .zero(ACL_PAGE, 0, &Uint32(7))
+                .expect("failed to zero");
+            manager
+                .read_at_cached(ACL_PAGE, 0, &mut buf)
+                .expect("failed to read");
+            assert_eq!(buf, [0u8; 4]);
+        });
+    }
+
+    #[test]
+    fn test_should_detect_corrupted_page() {
+        MEMORY_MANAGER.with_borrow_mut(|manager| {
+            let page = manager.allocate_page().expect("failed to allocate page");
+            let data_to_write = User {
+                id: 30u32.into(),
+                name: "Alice".to_string().into(),
+            };
+            manager
+                .write_at(page, 8, &data_to_write)
+                .expect("failed to write");
+            let out: User = manager.read_at(page, 8).expect("failed to read");
+            assert_eq!(out, data_to_write);
+
+            // flip a bit of the record in the underlying memory
+            let absolute_offset = manager.absolute_offset(page, 12);
+            let mut byte = [0u8; 1];
+            manager
+                .provider
+                .read(absolute_offset, &mut byte)
+                .expect("failed to read raw memory");
+            byte[0] ^= 0x01;
+            manager
+                .provider
+                .write(absolute_offset, &byte)
+                .expect("failed to write raw memory");
+
+            // the page is verified when it enters the pool
+            BUFFER_POOL.with_borrow_mut(|pool| pool.clear());
+            let result: MemoryResult<User> = manager.read_at(page, 8);
+            assert!(matches!(
+                result,
+                Err(MemoryError::ChecksumMismatch { page: corrupted, expected, actual })
+                    if corrupted == page && expected != actual
+            ));
+            // the corrupted page is not cached
+            BUFFER_POOL.with_borrow(|pool| assert!(pool.peek(page).is_none()));
+            let mut buf = [0u8; 4];
+            assert!(matches!(
+                manager.read_at_cached(page, 8, &mut buf),
+                Err(MemoryError::ChecksumMismatch { page: corrupted, .. }) if corrupted == page
+            ));
+        });
+    }
+
+    #[test]
+    fn test_should_update_checksum_without_reading_page() {
+        MEMORY_MANAGER.with_borrow_mut(|manager| {
+            let page = manager.allocate_page().expect("failed to allocate page");
+            let last = (manager.page_size() - 4) as PageOffset;
+            for offset in [0, 1, 100, last] {
+                // the page is cached by the previous read, which would serve the overwritten bytes
+                BUFFER_POOL.with_borrow_mut(|pool| pool.invalidate(page));
+                manager.reset_stats();
+                manager
+                    .write_at(page, offset, &Uint32(offset as u32 + 1))
+                    .expect("failed to write");
+                // the overwritten bytes and the stored checksum only
+                assert_eq!(manager.physical_reads(), 2);
+
+                let out: Uint32 = manager.read_at(page, offset).expect("failed to read");
+                assert_eq!(out, Uint32(offset as u32 + 1));
+            }
+
+            // the cached page is used instead of memory
+            let mut buf = [0u8; 4];
+            manager
+                .read_at_cached(page, 0, &mut buf)
+                .expect("failed to read");
+            manager.reset_stats();
+            manager.zero(page, 0, &Uint32(0)).expect("failed to zero");
+            assert_eq!(manager.physical_reads(), 0);
+            let out: Uint32 = manager.read_at(page, 0).expect("failed to read");
+            assert_eq!(out, Uint32(0));
+        });
+    }
+
+    #[test]
+    fn test_should_checksum_new_memory() {
+        let manager = MemoryManager::init(HeapMemoryProvider::default());
+        assert!(manager.checksums());
+        let out: Uint32 = manager.read_at(ACL_PAGE, 0).expect("failed to read");
+        assert_eq!(out, Uint32(0));
+
+        // an initialized memory stays checksummed
+        let manager = MemoryManager::init(manager.provider);
+        assert!(manager.checksums());
+    }
+
+    #[test]
+    fn test_should_not_checksum_legacy_memory() {
+        // a memory written before page checksums were introduced, with data up to the end of the pages
+        let mut provider = HeapMemoryProvider::default();
+        provider.grow(3).expect("failed to grow memory");
+        let end_of_page = HeapMemoryProvider::PAGE_SIZE - 4;
+        for page in 0..3 {
+            provider
+                .write(
+                    page * HeapMemoryProvider::PAGE_SIZE + end_of_page,
+                    &42u32.to_le_bytes(),
+                )
+                .expect("failed to write raw memory");
+        }
+
+        let mut manager = MemoryManager::init(provider);
+        assert!(!manager.checksums());
+        assert_eq!(manager.page_size(), HeapMemoryProvider::PAGE_SIZE);
+        for page in [SCHEMA_PAGE, ACL_PAGE, 2] {
+            let out: Uint32 = manager
+                .read_at(page, end_of_page as PageOffset)
+                .expect("failed to read legacy page");
+            assert_eq!(out, Uint32(42));
+        }
+
+        manager
+            .write_at(2, 0, &Uint32(7))
+            .expect("failed to write legacy page");
+        let out: Uint32 = manager.read_at(2, 0).expect("failed to read");
+        assert_eq!(out, Uint32(7));
+        let out: Uint32 = manager
+            .read_at(2, end_of_page as PageOffset)
+            .expect("failed to read");
+        assert_eq!(out, Uint32(42));
+
+        // new pages are not checksummed either
+        let page = manager.allocate_page().expect("failed to allocate page");
+        let mut buf = vec![1u8; HeapMemoryProvider::PAGE_SIZE as usize];
+        manager
+            .read_at_raw(page, 0, &mut buf)
+            .expect("failed to read");
+        assert!(buf.iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn test_should_checksum_schema_and_acl_pages() {
+        MEMORY_MANAGER.with_borrow_mut(|manager| {
+            for page in [SCHEMA_PAGE, ACL_PAGE] {
+                manager
+                    .write_at(page, 0, &Uint32(42))
+                    .expect("failed to write");
+                let out: Uint32 = manager.read_at(page, 0).expect("failed to read");
+                assert_eq!(out, Uint32(42));
+
+                manager
+                    .provider
+                    .write(manager.absolute_offset(page, 0), &[43])
+                    .expect("failed to write raw memory");
+                let result: MemoryResult<Uint32> = manager.read_at(page, 0);
+                assert!(matches!(
+                    result,
+                    Err(MemoryError::ChecksumMismatch { page: corrupted, .. }) if corrupted == page
+                ));
+            }
+        });
+    }
+
     #[derive(Debug, Clone, PartialEq)]
     struct FixedSizeData {
         a: u16,
@@ -3,7 +3,7 @@ use std::cell::RefCell;
 use candid::Principal;
 
 use super::MEMORY_MANAGER;
-use crate::memory::{DataSize, Encode, MSize, MemoryResult};
+use crate::memory::{DataSize, Encode, MSize, MemoryResult, WriteCategory};
 
 thread_local! {
     /// The global ACL.
@@ -66,7 +66,8 @@ impl AccessControlList {
     /// Write [`AccessControlList`] to memory.
     fn write(&self) -> MemoryResult<()> {
         // write to memory location from MEMORY_MANAGER
-        MEMORY_MANAGER.with_borrow_mut(|m| m.write_at(m.acl_page(), 0, self))
+        MEMORY_MANAGER
+            .with_borrow_mut(|m| m.write_metadata_at(WriteCategory::Acl, m.acl_page(), 0, self))
     }
 }
 
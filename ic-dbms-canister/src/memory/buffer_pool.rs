@@ -0,0 +1,204 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use ic_dbms_api::prelude::Page;
+
+/// Default amount of pages cached by the [`BUFFER_POOL`].
+pub const DEFAULT_BUFFER_POOL_CAPACITY: usize = 16;
+
+thread_local! {
+    /// The buffer pool caching the pages read with [`super::MemoryManager::read_at_cached`].
+    ///
+    /// Its capacity can be changed with [`BufferPool::set_capacity`].
+    pub static BUFFER_POOL: RefCell<BufferPool> = RefCell::new(BufferPool::new(DEFAULT_BUFFER_POOL_CAPACITY));
+}
+
+/// A page cached by the [`BufferPool`].
+#[derive(Debug)]
+struct PoolEntry {
+    data: Vec<u8>,
+    /// Tick of the last access, used to evict the least recently used page.
+    last_used: u64,
+}
+
+/// A cache of whole memory pages with least recently used eviction.
+///
+/// Reading from stable memory is expensive, so scans read the pages through the pool,
+/// while every write goes through to memory and updates or invalidates the cached page,
+/// so that a cached page is never newer than memory.
+#[derive(Debug)]
+pub struct BufferPool {
+    capacity_pages: usize,
+    entries: HashMap<Page, PoolEntry>,
+    /// Incremented at each access.
+    tick: u64,
+    hits: u64,
+    misses: u64,
+}
+
+impl BufferPool {
+    /// Creates an empty pool caching up to `capacity_pages` pages; `0` disables the cache.
+    pub fn new(capacity_pages: usize) -> Self {
+        Self {
+            capacity_pages,
+            entries: HashMap::with_capacity(capacity_pages),
+            tick: 0,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Returns the maximum amount of cached pages.
+    pub fn capacity_pages(&self) -> usize {
+        self.capacity_pages
+    }
+
+    /// Sets the maximum amount of cached pages, evicting the least recently used pages if needed.
+    pub fn set_capacity(&mut self, capacity_pages: usize) {
+        self.capacity_pages = capacity_pages;
+        while self.entries.len() > self.capacity_pages {
+            self.evict_lru();
+        }
+    }
+
+    /// Returns the amount of cached pages.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns whether no page is cached.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the amount of lookups which found the page in the pool.
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    /// Returns the amount of lookups which didn't find the page in the pool.
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+
+    /// Returns the data of the given page, if cached, marking it as the most recently used.
+    pub fn get(&mut self, page: Page) -> Option<&[u8]> {
+        self.tick += 1;
+        match self.entries.get_mut(&page) {
+            Some(entry) => {
+                self.hits += 1;
+                entry.last_used = self.tick;
+                Some(&entry.data)
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Returns the data of the given page, if cached, without counting it as an access.
+    pub fn peek(&self, page: Page) -> Option<&[u8]> {
+        self.entries.get(&page).map(|entry| entry.data.as_slice())
+    }
+
+    /// Caches the data of the given page, as read from memory, replacing the cached one, if any.
+    ///
+    /// If the pool is full, the least recently used page is evicted.
+    pub fn insert(&mut self, page: Page, data: Vec<u8>) {
+        if self.capacity_pages == 0 {
+            return;
+        }
+
+        if self.is_full_without(page) {
+            self.evict_lru();
+        }
+        self.tick += 1;
+        self.entries.insert(
+            page,
+            PoolEntry {
+                data,
+                last_used: self.tick,
+            },
+        );
+    }
+
+    /// Writes `bytes` at `offset` of the given page, if cached, after they have been written to memory.
+    ///
+    /// Returns whether the page was cached.
+    pub fn update(&mut self, page: Page, offset: usize, bytes: &[u8]) -> bool {
+        let Some(entry) = self.entries.get_mut(&page) else {
+            return false;
+        };
+        let end = (offset + bytes.len()).min(entry.data.len());
+        if offset < end {
+            entry.data[offset..end].copy_from_slice(&bytes[..end - offset]);
+        }
+
+        true
+    }
+
+    /// Removes the given page from the pool.
+    pub fn invalidate(&mut self, page: Page) {
+        self.entries.remove(&page);
+    }
+
+    /// Removes all the pages from the pool.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Returns whether the pool is full and the given page is not cached.
+    fn is_full_without(&self, page: Page) -> bool {
+        !self.entries.contains_key(&page) && self.entries.len() >= self.capacity_pages
+    }
+
+    /// Evicts the least recently used page, if any.
+    fn evict_lru(&mut self) {
+        if let Some(page) = self
+            .entries
+            .iter()
+            .min_by_key(|(_, entry)| entry.last_used)
+            .map(|(page, _)| *page)
+        {
+            self.invalidate(page);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_should_evict_least_recently_used_page() {
+        let mut pool = BufferPool::new(2);
+        pool.insert(1, vec![1]);
+        pool.insert(2, vec![2]);
+        assert_eq!(pool.get(1), Some([1u8].as_slice()));
+
+        // page 2 is the least recently used
+        pool.insert(3, vec![3]);
+        assert!(pool.get(2).is_none());
+        assert_eq!(pool.get(1), Some([1u8].as_slice()));
+        assert_eq!(pool.get(3), Some([3u8].as_slice()));
+        assert_eq!(pool.len(), 2);
+        assert_eq!(pool.hits(), 3);
+        assert_eq!(pool.misses(), 1);
+    }
+
+    #[test]
+    fn test_should_update_cached_page() {
+        let mut pool = BufferPool::new(1);
+        pool.insert(1, vec![0, 0]);
+        assert!(pool.update(1, 1, &[7]));
+        assert!(!pool.update(2, 0, &[7]));
+        assert_eq!(pool.peek(1), Some([0u8, 7].as_slice()));
+
+        pool.set_capacity(0);
+        assert!(pool.is_empty());
+        pool.insert(1, vec![1]);
+        assert!(pool.is_empty());
+    }
+}
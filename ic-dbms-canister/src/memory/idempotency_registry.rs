@@ -0,0 +1,269 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use ic_dbms_api::prelude::{CandidIcDbmsError, DecodeError};
+
+use crate::memory::{
+    DataSize, Encode, MEMORY_MANAGER, MSize, MemoryError, MemoryResult, SCHEMA_REGISTRY,
+    WriteCategory,
+};
+use crate::utils::time;
+
+thread_local! {
+    /// The global idempotency registry.
+    ///
+    /// We allow failing because on first initialization the idempotency registry might not be present yet.
+    pub static IDEMPOTENCY_REGISTRY: RefCell<IdempotencyRegistry> = RefCell::new(IdempotencyRegistry::load().unwrap_or_default());
+}
+
+/// Token provided by the client to identify an operation, so that retries are not executed twice.
+pub type IdempotencyToken = [u8; 16];
+
+/// Result of an operation cached in the [`IdempotencyRegistry`].
+pub type CandidResult = Result<(), CandidIcDbmsError>;
+
+/// Result of [`crate::prelude::IcDbmsDatabase::insert_idempotent`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdempotentResult {
+    /// Result of the operation.
+    pub result: CandidResult,
+    /// Whether the result comes from the [`IdempotencyRegistry`], so the operation has not been executed again.
+    pub cached: bool,
+}
+
+/// An operation result cached in the [`IdempotencyRegistry`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdempotencyEntry {
+    /// Result of the operation.
+    pub result: CandidResult,
+    /// Time in nanoseconds since the UNIX epoch after which the entry expires.
+    pub expires_at: u64,
+}
+
+impl IdempotencyEntry {
+    /// Returns whether the entry is expired at the given time.
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expires_at <= now
+    }
+}
+
+/// The idempotency registry stores the results of the operations executed with an [`IdempotencyToken`].
+///
+/// The registry is stored in a single page, allocated on first write and tracked by the schema registry.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct IdempotencyRegistry {
+    entries: HashMap<IdempotencyToken, IdempotencyEntry>,
+}
+
+impl IdempotencyRegistry {
+    /// Default time to live of the entries: 24 hours.
+    pub const DEFAULT_TTL: u64 = 24 * 60 * 60 * 1_000_000_000;
+
+    /// Load the idempotency registry from memory.
+    ///
+    /// If the page of the registry has not been allocated yet, the registry is empty.
+    pub fn load() -> MemoryResult<Self> {
+        let Some(page) = SCHEMA_REGISTRY.with_borrow(|sr| sr.idempotency_page()) else {
+            return Ok(Self::default());
+        };
+        MEMORY_MANAGER.with_borrow(|m| m.read_at(page, 0))
+    }
+
+    /// Returns the entry of the given token, if it exists and is not expired.
+    pub fn get(&self, token: &IdempotencyToken) -> Option<&IdempotencyEntry> {
+        let now = time();
+        self.entries
+            .get(token)
+            .filter(|entry| !entry.is_expired(now))
+    }
+
+    /// Caches the result of the operation identified by the given token until `expires_at`.
+    ///
+    /// Expired entries are evicted before writing the registry to memory.
+    pub fn insert(
+        &mut self,
+        token: IdempotencyToken,
+        result: CandidResult,
+        expires_at: u64,
+    ) -> MemoryResult<()> {
+        let now = time();
+        self.entries.retain(|_, entry| !entry.is_expired(now));
+        self.entries
+            .insert(token, IdempotencyEntry { result, expires_at });
+        self.write()
+    }
+
+    /// Removes the expired entries and writes the registry to memory.
+    ///
+    /// Returns the amount of evicted entries.
+    pub fn evict_expired(&mut self) -> MemoryResult<u64> {
+        let now = time();
+        let len = self.entries.len();
+        self.entries.retain(|_, entry| !entry.is_expired(now));
+        let evicted = (len - self.entries.len()) as u64;
+        if evicted > 0 {
+            self.write()?;
+        }
+
+        Ok(evicted)
+    }
+
+    /// Returns the amount of cached entries, including the expired ones not evicted yet.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns whether the registry has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Writes the idempotency registry to its page, allocating it if needed.
+    fn write(&self) -> MemoryResult<()> {
+        let page = SCHEMA_REGISTRY.with_borrow_mut(|sr| sr.get_or_allocate_idempotency_page())?;
+        MEMORY_MANAGER
+            .with_borrow_mut(|m| m.write_metadata_at(WriteCategory::Idempotency, page, 0, self))
+    }
+
+    /// Encodes a result; errors are encoded as JSON.
+    fn encode_result(result: &CandidResult) -> Vec<u8> {
+        match result {
+            Ok(()) => vec![],
+            Err(err) => serde_json::to_vec(err).expect("failed to serialize error"),
+        }
+    }
+}
+
+impl Encode for IdempotencyRegistry {
+    const SIZE: DataSize = DataSize::Dynamic;
+
+    fn size(&self) -> MSize {
+        // 4 bytes for len + for each entry: 16 bytes for token, 8 bytes for expiration,
+        // 1 byte for the result tag, 4 bytes for the error len and the error bytes
+        4 + self
+            .entries
+            .values()
+            .map(|entry| 16 + 8 + 1 + 4 + Self::encode_result(&entry.result).len() as MSize)
+            .sum::<MSize>()
+    }
+
+    fn encode(&'_ self) -> std::borrow::Cow<'_, [u8]> {
+        let mut bytes = Vec::with_capacity(self.size() as usize);
+        bytes.extend_from_slice(&(self.entries.len() as u32).to_le_bytes());
+        for (token, entry) in &self.entries {
+            bytes.extend_from_slice(token);
+            bytes.extend_from_slice(&entry.expires_at.to_le_bytes());
+            bytes.push(entry.result.is_ok() as u8);
+            let result = Self::encode_result(&entry.result);
+            bytes.extend_from_slice(&(result.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(&result);
+        }
+        std::borrow::Cow::Owned(bytes)
+    }
+
+    fn decode(data: std::borrow::Cow<[u8]>) -> MemoryResult<Self>
+    where
+        Self: Sized,
+    {
+        let mut offset = 0;
+        let len = u32::from_le_bytes(data[offset..offset + 4].try_into()?) as usize;
+        offset += 4;
+
+        let mut entries = HashMap::with_capacity(len);
+        for _ in 0..len {
+            let token: IdempotencyToken = data[offset..offset + 16].try_into()?;
+            offset += 16;
+            let expires_at = u64::from_le_bytes(data[offset..offset + 8].try_into()?);
+            offset += 8;
+            let is_ok = data[offset] != 0;
+            offset += 1;
+            let result_len = u32::from_le_bytes(data[offset..offset + 4].try_into()?) as usize;
+            offset += 4;
+            let result = if is_ok {
+                Ok(())
+            } else {
+                Err(
+                    serde_json::from_slice(&data[offset..offset + result_len]).map_err(|err| {
+                        MemoryError::DecodeError(DecodeError::InvalidJson(err.to_string()))
+                    })?,
+                )
+            };
+            offset += result_len;
+            entries.insert(token, IdempotencyEntry { result, expires_at });
+        }
+
+        Ok(Self { entries })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use ic_dbms_api::prelude::{IcDbmsError, QueryError};
+
+    use super::*;
+
+    #[test]
+    fn test_should_encode_and_decode_idempotency_registry() {
+        let mut registry = IdempotencyRegistry::default();
+        registry.entries.insert(
+            [1; 16],
+            IdempotencyEntry {
+                result: Ok(()),
+                expires_at: 100,
+            },
+        );
+        registry.entries.insert(
+            [2; 16],
+            IdempotencyEntry {
+                result: Err(IcDbmsError::Query(QueryError::PrimaryKeyConflict).into()),
+                expires_at: 200,
+            },
+        );
+
+        let encoded = registry.encode();
+        assert_eq!(encoded.len(), registry.size() as usize);
+        let decoded = IdempotencyRegistry::decode(encoded).expect("failed to decode");
+        assert_eq!(decoded, registry);
+    }
+
+    #[test]
+    fn test_should_store_and_reload_entries() {
+        let mut registry = IdempotencyRegistry::load().expect("failed to load registry");
+        assert!(registry.is_empty());
+
+        registry
+            .insert([1; 16], Ok(()), time() + IdempotencyRegistry::DEFAULT_TTL)
+            .expect("failed to insert entry");
+        assert_eq!(
+            registry.get(&[1; 16]).map(|entry| &entry.result),
+            Some(&Ok(()))
+        );
+        assert!(registry.get(&[2; 16]).is_none());
+
+        let reloaded = IdempotencyRegistry::load().expect("failed to reload registry");
+        assert_eq!(reloaded, registry);
+    }
+
+    #[test]
+    fn test_should_evict_expired_entries() {
+        let mut registry = IdempotencyRegistry::default();
+        registry
+            .insert([2; 16], Ok(()), time() + IdempotencyRegistry::DEFAULT_TTL)
+            .expect("failed to insert entry");
+        registry.entries.insert(
+            [1; 16],
+            IdempotencyEntry {
+                result: Ok(()),
+                expires_at: 0,
+            },
+        );
+
+        // expired entries are not returned
+        assert!(registry.get(&[1; 16]).is_none());
+
+        assert_eq!(registry.evict_expired().expect("failed to evict"), 1);
+        assert_eq!(registry.len(), 1);
+        assert!(registry.get(&[2; 16]).is_some());
+    }
+}
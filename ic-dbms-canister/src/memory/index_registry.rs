@@ -0,0 +1,332 @@
+mod btree_index;
+mod index_maintainer;
+
+use ic_dbms_api::prelude::{IndexType, TableFingerprint};
+
+pub use self::btree_index::{BTreeIndex, IndexEntry, IndexRange};
+pub use self::index_maintainer::{IndexMaintainer, TableIndexes};
+use crate::memory::{
+    DataSize, Encode, MEMORY_MANAGER, MSize, MemoryError, MemoryResult, Page, WriteCategory,
+};
+
+/// Identifier of an index in the [`IndexRegistry`].
+pub type IndexId = u64;
+
+/// An index tracked by the [`IndexRegistry`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexInfo {
+    /// Identifier of the index.
+    pub id: IndexId,
+    /// Fingerprint of the indexed table.
+    pub table: TableFingerprint,
+    /// The indexed column; columns of multi-column indexes are comma separated, in order.
+    pub column: String,
+    /// The [`IndexType`] of the index.
+    pub index_type: IndexType,
+    /// Pages dedicated to the index data.
+    pub pages: Vec<Page>,
+}
+
+/// The index registry keeps track of all the indexes of the tables.
+///
+/// The registry is stored in a single page, allocated on first write and tracked by the
+/// [`super::SchemaRegistry`], which owns the registry.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct IndexRegistry {
+    /// Page of the registry, allocated on first write.
+    page: Option<Page>,
+    /// Identifier of the next index created.
+    next_id: IndexId,
+    indexes: Vec<IndexInfo>,
+}
+
+impl IndexRegistry {
+    /// Load the index registry from the given page.
+    pub fn load(page: Page) -> MemoryResult<Self> {
+        let registry: Self = MEMORY_MANAGER.with_borrow(|m| m.read_at(page, 0))?;
+        Ok(Self {
+            page: Some(page),
+            ..registry
+        })
+    }
+
+    /// Returns the page of the index registry, if it has been allocated.
+    pub fn page(&self) -> Option<Page> {
+        self.page
+    }
+
+    /// Registers an index on the `column` of the given table, whose data is stored in `dedicated_pages`,
+    /// and writes the registry to memory.
+    ///
+    /// Returns the [`IndexId`] of the new index.
+    pub fn create_index(
+        &mut self,
+        fingerprint: TableFingerprint,
+        column: &str,
+        index_type: IndexType,
+        dedicated_pages: Vec<Page>,
+    ) -> MemoryResult<IndexId> {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.indexes.push(IndexInfo {
+            id,
+            table: fingerprint,
+            column: column.to_string(),
+            index_type,
+            pages: dedicated_pages,
+        });
+        self.write()?;
+
+        Ok(id)
+    }
+
+    /// Returns the indexes of the given table.
+    pub fn indexes_for_table(&self, fingerprint: TableFingerprint) -> Vec<IndexInfo> {
+        self.indexes
+            .iter()
+            .filter(|index| index.table == fingerprint)
+            .cloned()
+            .collect()
+    }
+
+    /// Sets the pages dedicated to the data of the index with the given id,
+    /// and writes the registry to memory.
+    ///
+    /// Returns `false` if there is no such index.
+    pub fn set_index_pages(&mut self, id: IndexId, pages: Vec<Page>) -> MemoryResult<bool> {
+        let Some(index) = self.indexes.iter_mut().find(|index| index.id == id) else {
+            return Ok(false);
+        };
+        index.pages = pages;
+        self.write()?;
+
+        Ok(true)
+    }
+
+    /// Moves the indexes of the table `a` to the table `b` and vice versa,
+    /// and writes the registry to memory if any index has been moved.
+    pub fn swap_tables(&mut self, a: TableFingerprint, b: TableFingerprint) -> MemoryResult<()> {
+        let mut moved = false;
+        for index in &mut self.indexes {
+            if index.table == a {
+                index.table = b;
+            } else if index.table == b {
+                index.table = a;
+            } else {
+                continue;
+            }
+            moved = true;
+        }
+
+        if moved { self.write() } else { Ok(()) }
+    }
+
+    /// Removes the indexes of the given table, zeroing their dedicated pages,
+    /// and writes the registry to memory.
+    ///
+    /// Returns the removed indexes.
+    pub fn drop_indexes_for_table(
+        &mut self,
+        fingerprint: TableFingerprint,
+    ) -> MemoryResult<Vec<IndexInfo>> {
+        let (dropped, indexes) = std::mem::take(&mut self.indexes)
+            .into_iter()
+            .partition::<Vec<_>, _>(|index| index.table == fingerprint);
+        self.indexes = indexes;
+        if dropped.is_empty() {
+            return Ok(dropped);
+        }
+
+        MEMORY_MANAGER.with_borrow_mut(|m| {
+            dropped
+                .iter()
+                .flat_map(|index| &index.pages)
+                .try_for_each(|page| m.zero_page(*page))
+        })?;
+        self.write()?;
+
+        Ok(dropped)
+    }
+
+    /// Writes the index registry to its page, allocating it if needed.
+    fn write(&mut self) -> MemoryResult<()> {
+        let page = match self.page {
+            Some(page) => page,
+            None => {
+                let page = MEMORY_MANAGER.with_borrow_mut(|m| m.allocate_page())?;
+                self.page = Some(page);
+                page
+            }
+        };
+        MEMORY_MANAGER
+            .with_borrow_mut(|m| m.write_metadata_at(WriteCategory::IndexRegistry, page, 0, self))
+    }
+}
+
+impl Encode for IndexRegistry {
+    const SIZE: DataSize = DataSize::Dynamic;
+
+    fn size(&self) -> MSize {
+        // 8 bytes for the next id + 4 bytes for len + for each index: 8 bytes for the id,
+        // 8 bytes for the table, 2 bytes for the column len and the column bytes,
+        // 1 byte for the index type, 4 bytes for the pages len and 4 bytes for each page
+        8 + 4
+            + self
+                .indexes
+                .iter()
+                .map(|index| {
+                    8 + 8 + 2 + index.column.len() as MSize + 1 + 4 + 4 * index.pages.len() as MSize
+                })
+                .sum::<MSize>()
+    }
+
+    fn encode(&'_ self) -> std::borrow::Cow<'_, [u8]> {
+        let mut bytes = Vec::with_capacity(self.size() as usize);
+        bytes.extend_from_slice(&self.next_id.to_le_bytes());
+        bytes.extend_from_slice(&(self.indexes.len() as u32).to_le_bytes());
+        for index in &self.indexes {
+            bytes.extend_from_slice(&index.id.to_le_bytes());
+            bytes.extend_from_slice(&index.table.to_le_bytes());
+            bytes.extend_from_slice(&(index.column.len() as u16).to_le_bytes());
+            bytes.extend_from_slice(index.column.as_bytes());
+            bytes.push(index_type_tag(index.index_type));
+            bytes.extend_from_slice(&(index.pages.len() as u32).to_le_bytes());
+            for page in &index.pages {
+                bytes.extend_from_slice(&page.to_le_bytes());
+            }
+        }
+        std::borrow::Cow::Owned(bytes)
+    }
+
+    fn decode(data: std::borrow::Cow<[u8]>) -> MemoryResult<Self>
+    where
+        Self: Sized,
+    {
+        let mut offset = 0;
+        let next_id = IndexId::from_le_bytes(data[offset..offset + 8].try_into()?);
+        offset += 8;
+        let len = u32::from_le_bytes(data[offset..offset + 4].try_into()?) as usize;
+        offset += 4;
+
+        let mut indexes = Vec::with_capacity(len);
+        for _ in 0..len {
+            let id = IndexId::from_le_bytes(data[offset..offset + 8].try_into()?);
+            offset += 8;
+            let table = TableFingerprint::from_le_bytes(data[offset..offset + 8].try_into()?);
+            offset += 8;
+            let column_len = u16::from_le_bytes(data[offset..offset + 2].try_into()?) as usize;
+            offset += 2;
+            let column = String::from_utf8(data[offset..offset + column_len].to_vec())?;
+            offset += column_len;
+            let index_type = index_type_from_tag(data[offset])?;
+            offset += 1;
+            let pages_len = u32::from_le_bytes(data[offset..offset + 4].try_into()?) as usize;
+            offset += 4;
+            let mut pages = Vec::with_capacity(pages_len);
+            for _ in 0..pages_len {
+                pages.push(Page::from_le_bytes(data[offset..offset + 4].try_into()?));
+                offset += 4;
+            }
+            indexes.push(IndexInfo {
+                id,
+                table,
+                column,
+                index_type,
+                pages,
+            });
+        }
+
+        Ok(Self {
+            page: None,
+            next_id,
+            indexes,
+        })
+    }
+}
+
+/// Returns the tag encoding the given [`IndexType`].
+fn index_type_tag(index_type: IndexType) -> u8 {
+    match index_type {
+        IndexType::BTree => 0,
+        IndexType::Hash => 1,
+        IndexType::FullText => 2,
+    }
+}
+
+/// Makes the [`IndexType`] with the given tag.
+fn index_type_from_tag(tag: u8) -> MemoryResult<IndexType> {
+    match tag {
+        0 => Ok(IndexType::BTree),
+        1 => Ok(IndexType::Hash),
+        2 => Ok(IndexType::FullText),
+        // an index type written by a newer version of the registry
+        tag => Err(MemoryError::DecodeError(
+            ic_dbms_api::prelude::DecodeError::UnsupportedFormatVersion(tag),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_should_encode_and_decode_index_registry() {
+        let mut registry = IndexRegistry::default();
+        registry
+            .create_index(1, "name", IndexType::Hash, vec![10])
+            .expect("failed to create index");
+        registry
+            .create_index(2, "start_date,end_date", IndexType::BTree, vec![11, 12])
+            .expect("failed to create index");
+
+        let page = registry.page().expect("page should be allocated");
+        let reloaded = IndexRegistry::load(page).expect("failed to load index registry");
+        assert_eq!(reloaded, registry);
+        assert_eq!(reloaded.indexes_for_table(2)[0].pages, vec![11, 12]);
+    }
+
+    #[test]
+    fn test_should_set_index_pages() {
+        let mut registry = IndexRegistry::default();
+        let id = registry
+            .create_index(1, "name", IndexType::BTree, vec![10])
+            .expect("failed to create index");
+
+        assert!(
+            registry
+                .set_index_pages(id, vec![10, 11])
+                .expect("failed to set pages")
+        );
+        assert!(
+            !registry
+                .set_index_pages(id + 1, vec![12])
+                .expect("failed to set pages")
+        );
+
+        let page = registry.page().expect("page should be allocated");
+        let reloaded = IndexRegistry::load(page).expect("failed to load index registry");
+        assert_eq!(reloaded.indexes_for_table(1)[0].pages, vec![10, 11]);
+    }
+
+    #[test]
+    fn test_should_swap_indexed_tables() {
+        let mut registry = IndexRegistry::default();
+        registry
+            .create_index(1, "name", IndexType::BTree, vec![10])
+            .expect("failed to create index");
+        registry
+            .create_index(3, "email", IndexType::BTree, vec![11])
+            .expect("failed to create index");
+
+        registry.swap_tables(1, 2).expect("failed to swap tables");
+        assert!(registry.indexes_for_table(1).is_empty());
+        assert_eq!(registry.indexes_for_table(2)[0].column, "name");
+        assert_eq!(registry.indexes_for_table(3)[0].column, "email");
+
+        let page = registry.page().expect("page should be allocated");
+        let reloaded = IndexRegistry::load(page).expect("failed to load index registry");
+        assert_eq!(reloaded, registry);
+    }
+}
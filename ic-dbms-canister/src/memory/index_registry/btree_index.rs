@@ -0,0 +1,998 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::ops::{Bound, RangeBounds};
+
+use ic_dbms_api::prelude::{
+    Blob, Boolean, DataSize, DataTypeKind, Date, DateTime, Decimal, DecodeError, Encode, Float64,
+    Int32, Int64, MSize, MemoryError, MemoryResult, Page, PageOffset, Principal, TableSchema, Text,
+    Uint32, Uint64, Uuid, Value,
+};
+
+use super::index_maintainer::column_value;
+use crate::memory::{MEMORY_MANAGER, TableRegistry, WriteCategory};
+
+/// Size of the header of the root page of a [`BTreeIndex`]: the length of the encoded directory
+/// and the amount of its continuation pages, whose numbers follow the header.
+const ROOT_HEADER_SIZE: usize = 8;
+
+/// Size of the header of a leaf page of a [`BTreeIndex`], storing the amount of its items.
+const LEAF_HEADER_SIZE: usize = 4;
+
+/// Tag of the encoded [`Value::Array`]s, whose elements are encoded with their own tags.
+const ARRAY_TAG: u8 = 14;
+
+/// Key of an item of a [`BTreeIndex`]: the indexed value followed by the primary key of the record,
+/// so that the items are unique even if many records store the same value.
+type IndexKey = (Value, Value);
+
+/// Items of a leaf of a [`BTreeIndex`], mapping each [`IndexKey`] to the location of the record.
+type LeafItems = BTreeMap<IndexKey, (Page, PageOffset)>;
+
+/// Encoded items of a leaf of a [`BTreeIndex`], along with their amount.
+type EncodedLeaf = (u32, Vec<u8>);
+
+/// An entry of a [`BTreeIndex`], locating a record storing an indexed value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexEntry {
+    /// Primary key of the record.
+    pub pk: Value,
+    /// Page where the record is stored.
+    pub page: Page,
+    /// Offset of the record in its page.
+    pub offset: PageOffset,
+}
+
+/// A leaf of a [`BTreeIndex`], stored in its own page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Leaf {
+    /// Smallest key stored in the leaf; the first leaf stores all the keys smaller than the second leaf,
+    /// so its first key is not used.
+    first: IndexKey,
+    page: Page,
+}
+
+/// A B-tree index on a column of a table, mapping each value of the column to the records storing it.
+///
+/// The index is a two-level B+ tree stored in the pages dedicated to it in the [`super::IndexRegistry`].
+/// The items, sorted by value and primary key, are stored in leaf pages,
+/// while the root page stores the directory of the leaves, with the first key of each leaf,
+/// continuing in additional pages if it doesn't fit.
+///
+/// Only the directory is loaded: lookups read the leaves covering the looked up values,
+/// and writes rewrite the changed leaf, along with the directory when a full leaf is split.
+/// Leaves emptied by removals are kept, to store the next items of their range.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BTreeIndex {
+    /// Pages dedicated to the index, starting with the root page.
+    ///
+    /// Pages not used by the directory or by a leaf, such as those left by a rebuild, are used first.
+    pages: Vec<Page>,
+    /// Pages storing the directory after the root page.
+    directory_pages: Vec<Page>,
+    /// Leaves sorted by their first key.
+    leaves: Vec<Leaf>,
+}
+
+impl BTreeIndex {
+    /// Loads the directory of the index stored in the given pages, starting with the root page.
+    ///
+    /// A zeroed root page is loaded as an empty index.
+    pub fn load(pages: &[Page]) -> MemoryResult<Self> {
+        let Some(root) = pages.first() else {
+            return Ok(Self::default());
+        };
+
+        let mut data = read_page(*root)?;
+        let len = read_u32(&data, 0)? as usize;
+        let continuation_pages = read_u32(&data, 4)? as usize;
+        let directory_pages = (0..continuation_pages)
+            .map(|i| read_u32(&data, ROOT_HEADER_SIZE + 4 * i))
+            .collect::<MemoryResult<Vec<_>>>()?;
+        data.drain(..ROOT_HEADER_SIZE + 4 * continuation_pages);
+        for page in &directory_pages {
+            data.extend_from_slice(&read_page(*page)?);
+        }
+        let data = data
+            .get(..len)
+            .ok_or(MemoryError::DecodeError(DecodeError::TooShort))?;
+
+        Ok(Self {
+            pages: pages.to_vec(),
+            directory_pages,
+            leaves: decode_directory(data)?,
+        })
+    }
+
+    /// Builds the index on the `column` of the records stored in the table of `T`,
+    /// and writes it to the given root page, allocating the leaf pages.
+    pub fn build<T>(table_registry: &TableRegistry, column: &str, page: Page) -> MemoryResult<Self>
+    where
+        T: TableSchema,
+    {
+        Self::rebuild::<T>(table_registry, column, vec![page])
+    }
+
+    /// Builds the index on the `column` of the records stored in the table of `T` again,
+    /// overwriting the index stored in the given pages, and allocating new pages if they are not enough.
+    pub fn rebuild<T>(
+        table_registry: &TableRegistry,
+        column: &str,
+        pages: Vec<Page>,
+    ) -> MemoryResult<Self>
+    where
+        T: TableSchema,
+    {
+        let mut items = LeafItems::new();
+        for next in table_registry.read::<T>() {
+            let next = next?;
+            let values = next.record.to_values();
+            items.insert(
+                (
+                    column_value(&values, column),
+                    column_value(&values, T::primary_key()),
+                ),
+                (next.page, next.offset),
+            );
+        }
+
+        let mut index = Self {
+            pages,
+            directory_pages: vec![],
+            leaves: vec![],
+        };
+        index.root()?;
+        if !items.is_empty() {
+            let page = index.take_page()?;
+            index.leaves.push(Leaf {
+                first: first_key(&items),
+                page,
+            });
+            index.store_leaf(0, items)?;
+        }
+        index.write_directory()?;
+
+        Ok(index)
+    }
+
+    /// Returns the pages dedicated to the index.
+    pub fn pages(&self) -> &[Page] {
+        &self.pages
+    }
+
+    /// Returns the amount of indexed records, reading all the leaves.
+    pub fn len(&self) -> MemoryResult<usize> {
+        (0..self.leaves.len())
+            .map(|position| self.read_leaf(position).map(|items| items.len()))
+            .sum()
+    }
+
+    /// Returns whether no record is indexed, reading all the leaves.
+    pub fn is_empty(&self) -> MemoryResult<bool> {
+        self.len().map(|len| len == 0)
+    }
+
+    /// Returns the entries of the records storing the given value, in primary key order.
+    pub fn lookup(&self, value: &Value) -> MemoryResult<Vec<IndexEntry>> {
+        self.range(Bound::Included(value), Bound::Included(value))
+    }
+
+    /// Returns the entries of the records storing a value within the given bounds,
+    /// in ascending value order, then in primary key order.
+    ///
+    /// Only the leaves covering the bounds are read.
+    /// An empty range, such as a lower bound greater than the upper bound, has no entries.
+    pub fn range(
+        &self,
+        lower: Bound<&Value>,
+        upper: Bound<&Value>,
+    ) -> MemoryResult<Vec<IndexEntry>> {
+        self.clone()
+            .into_range(lower.cloned(), upper.cloned())
+            .collect()
+    }
+
+    /// Returns an iterator over the entries of the records storing a value within the given bounds,
+    /// as [`BTreeIndex::range`] does, reading the leaves one at a time as the iterator advances.
+    pub fn into_range(self, lower: Bound<Value>, upper: Bound<Value>) -> IndexRange {
+        let empty = match (&lower, &upper) {
+            (Bound::Included(low), Bound::Included(high)) => low > high,
+            (Bound::Included(low) | Bound::Excluded(low), Bound::Excluded(high))
+            | (Bound::Excluded(low), Bound::Included(high)) => low >= high,
+            _ => false,
+        };
+        // the last leaf whose first value may be within the bounds, which may store values within them
+        let start = match &lower {
+            _ if empty || self.leaves.is_empty() => self.leaves.len(),
+            Bound::Included(low) => self.leaves[1..].partition_point(|leaf| leaf.first.0 < *low),
+            Bound::Excluded(low) => self.leaves[1..].partition_point(|leaf| leaf.first.0 <= *low),
+            Bound::Unbounded => 0,
+        };
+
+        IndexRange {
+            index: self,
+            lower,
+            upper,
+            start,
+            next_leaf: start,
+            entries: vec![].into_iter(),
+        }
+    }
+
+    /// Adds the given entry for the given value, writing the changed leaf to memory.
+    ///
+    /// An entry with the same primary key already stored for the value is replaced,
+    /// so re-inserting a moved record updates its location.
+    /// A full leaf is split, allocating a new page if needed; use [`BTreeIndex::pages`] to get the pages after the write.
+    pub fn insert(&mut self, value: Value, entry: IndexEntry) -> MemoryResult<()> {
+        self.root()?;
+        let key = (value, entry.pk);
+        let mut directory_changed = false;
+        if self.leaves.is_empty() {
+            let page = self.take_page()?;
+            self.leaves.push(Leaf {
+                first: key.clone(),
+                page,
+            });
+            directory_changed = true;
+        }
+
+        let position = self.leaf_position(&key);
+        let mut items = self.read_leaf(position)?;
+        items.insert(key, (entry.page, entry.offset));
+        directory_changed |= self.store_leaf(position, items)?;
+        if directory_changed {
+            self.write_directory()?;
+        }
+
+        Ok(())
+    }
+
+    /// Removes the entry with the given primary key for the given value, writing the changed leaf to memory.
+    ///
+    /// Returns whether the entry was found.
+    pub fn remove(&mut self, value: &Value, pk: &Value) -> MemoryResult<bool> {
+        if self.leaves.is_empty() {
+            return Ok(false);
+        }
+
+        let key = (value.clone(), pk.clone());
+        let position = self.leaf_position(&key);
+        let mut items = self.read_leaf(position)?;
+        if items.remove(&key).is_none() {
+            return Ok(false);
+        }
+        self.store_leaf(position, items)?;
+
+        Ok(true)
+    }
+
+    /// Returns the position of the leaf storing the given key.
+    fn leaf_position(&self, key: &IndexKey) -> usize {
+        self.leaves[1..].partition_point(|leaf| leaf.first <= *key)
+    }
+
+    /// Reads the items of the leaf at the given position.
+    fn read_leaf(&self, position: usize) -> MemoryResult<LeafItems> {
+        let data = read_page(self.leaves[position].page)?;
+        let len = read_u32(&data, 0)?;
+        let mut offset = LEAF_HEADER_SIZE;
+        let mut items = LeafItems::new();
+        for _ in 0..len {
+            let (value, size) = decode_value_at(&data, offset)?;
+            offset += size;
+            let (pk, size) = decode_value_at(&data, offset)?;
+            offset += size;
+            let page = read_u32(&data, offset)?;
+            let record_offset = PageOffset::from_le_bytes(
+                data.get(offset + 4..offset + 6)
+                    .ok_or(MemoryError::DecodeError(DecodeError::TooShort))?
+                    .try_into()?,
+            );
+            offset += 6;
+            items.insert((value, pk), (page, record_offset));
+        }
+
+        Ok(items)
+    }
+
+    /// Writes the given items to the leaf at the given position,
+    /// splitting them among new leaves, following it, if they don't fit in a page.
+    ///
+    /// Returns whether leaves have been added, so that the directory must be written.
+    fn store_leaf(&mut self, position: usize, items: LeafItems) -> MemoryResult<bool> {
+        let capacity = page_size() - LEAF_HEADER_SIZE;
+        let mut chunks = split_leaf(items, capacity)?.into_iter();
+        let (_, first_chunk) = chunks
+            .next()
+            .expect("a leaf is split in at least one chunk");
+        write_leaf(self.leaves[position].page, &first_chunk)?;
+
+        let mut split = false;
+        for (offset, (first, chunk)) in chunks.enumerate() {
+            let page = self.take_page()?;
+            write_leaf(page, &chunk)?;
+            self.leaves
+                .insert(position + offset + 1, Leaf { first, page });
+            split = true;
+        }
+
+        Ok(split)
+    }
+
+    /// Writes the directory to the root page, allocating continuation pages if it doesn't fit.
+    fn write_directory(&mut self) -> MemoryResult<()> {
+        let mut directory = vec![];
+        directory.extend_from_slice(&(self.leaves.len() as u32).to_le_bytes());
+        for leaf in &self.leaves {
+            directory.extend_from_slice(&leaf.page.to_le_bytes());
+            encode_value(&leaf.first.0, &mut directory);
+            encode_value(&leaf.first.1, &mut directory);
+        }
+
+        let page_size = page_size();
+        while ROOT_HEADER_SIZE + 4 * self.directory_pages.len() + directory.len()
+            > page_size * (1 + self.directory_pages.len())
+        {
+            let page = self.take_page()?;
+            self.directory_pages.push(page);
+        }
+
+        let mut data =
+            Vec::with_capacity(ROOT_HEADER_SIZE + 4 * self.directory_pages.len() + directory.len());
+        data.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+        data.extend_from_slice(&(self.directory_pages.len() as u32).to_le_bytes());
+        for page in &self.directory_pages {
+            data.extend_from_slice(&page.to_le_bytes());
+        }
+        data.extend_from_slice(&directory);
+
+        let root = self.root()?;
+        MEMORY_MANAGER.with_borrow_mut(|mm| {
+            data.chunks(page_size)
+                .zip(std::iter::once(&root).chain(&self.directory_pages))
+                .try_for_each(|(chunk, page)| {
+                    mm.write_metadata_at(
+                        WriteCategory::IndexRegistry,
+                        *page,
+                        0,
+                        &IndexChunk(chunk.to_vec()),
+                    )
+                })
+        })
+    }
+
+    /// Returns the root page, allocating it if the index has no page.
+    fn root(&mut self) -> MemoryResult<Page> {
+        if let Some(root) = self.pages.first() {
+            return Ok(*root);
+        }
+
+        let page = MEMORY_MANAGER.with_borrow_mut(|mm| mm.allocate_page())?;
+        self.pages.push(page);
+        Ok(page)
+    }
+
+    /// Returns a page of the index not used by the directory or by a leaf, allocating a new page if there is none.
+    fn take_page(&mut self) -> MemoryResult<Page> {
+        let used = |page: &Page| {
+            self.pages.first() == Some(page)
+                || self.directory_pages.contains(page)
+                || self.leaves.iter().any(|leaf| leaf.page == *page)
+        };
+        if let Some(page) = self.pages.iter().find(|page| !used(page)) {
+            return Ok(*page);
+        }
+
+        let page = MEMORY_MANAGER.with_borrow_mut(|mm| mm.allocate_page())?;
+        self.pages.push(page);
+        Ok(page)
+    }
+}
+
+/// Iterator over the entries of a [`BTreeIndex`] within bounds, returned by [`BTreeIndex::into_range`].
+#[derive(Debug)]
+pub struct IndexRange {
+    index: BTreeIndex,
+    lower: Bound<Value>,
+    upper: Bound<Value>,
+    /// Position of the first leaf covering the bounds.
+    start: usize,
+    /// Position of the next leaf to read.
+    next_leaf: usize,
+    /// Entries of the last read leaf not returned yet.
+    entries: std::vec::IntoIter<IndexEntry>,
+}
+
+impl Iterator for IndexRange {
+    type Item = MemoryResult<IndexEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(entry) = self.entries.next() {
+                return Some(Ok(entry));
+            }
+
+            let leaf = self.index.leaves.get(self.next_leaf)?;
+            let past_upper = match &self.upper {
+                Bound::Included(high) => leaf.first.0 > *high,
+                Bound::Excluded(high) => leaf.first.0 >= *high,
+                Bound::Unbounded => false,
+            };
+            if self.next_leaf > self.start && past_upper {
+                return None;
+            }
+
+            let items = match self.index.read_leaf(self.next_leaf) {
+                Ok(items) => items,
+                Err(err) => {
+                    // stop at the first error
+                    self.next_leaf = self.index.leaves.len();
+                    return Some(Err(err));
+                }
+            };
+            self.next_leaf += 1;
+            let bounds = (self.lower.as_ref(), self.upper.as_ref());
+            self.entries = items
+                .into_iter()
+                .filter(|((value, _), _)| bounds.contains(value))
+                .map(|((_, pk), (page, offset))| IndexEntry { pk, page, offset })
+                .collect::<Vec<_>>()
+                .into_iter();
+        }
+    }
+}
+
+/// Returns the usable size of a page.
+fn page_size() -> usize {
+    MEMORY_MANAGER.with_borrow(|mm| mm.page_size()) as usize
+}
+
+/// Reads the usable bytes of the given page.
+fn read_page(page: Page) -> MemoryResult<Vec<u8>> {
+    let mut data = vec![0u8; page_size()];
+    MEMORY_MANAGER.with_borrow(|mm| mm.read_at_cached(page, 0, &mut data))?;
+    Ok(data)
+}
+
+/// Reads the little endian `u32` at the given offset of the data.
+fn read_u32(data: &[u8], offset: usize) -> MemoryResult<u32> {
+    let bytes = data
+        .get(offset..offset + 4)
+        .ok_or(MemoryError::DecodeError(DecodeError::TooShort))?;
+    Ok(u32::from_le_bytes(bytes.try_into()?))
+}
+
+/// Decodes the value encoded with [`encode_value`] at the given offset of the data,
+/// returning it along with its encoded size.
+fn decode_value_at(data: &[u8], offset: usize) -> MemoryResult<(Value, usize)> {
+    decode_value(
+        data.get(offset..)
+            .ok_or(MemoryError::DecodeError(DecodeError::TooShort))?,
+    )
+}
+
+/// Returns the first key of the given non-empty items.
+fn first_key(items: &LeafItems) -> IndexKey {
+    items
+        .keys()
+        .next()
+        .cloned()
+        .expect("the items are not empty")
+}
+
+/// Writes the given encoded items to the given leaf page, prefixed with their amount.
+fn write_leaf(page: Page, (len, items): &EncodedLeaf) -> MemoryResult<()> {
+    let mut data = Vec::with_capacity(LEAF_HEADER_SIZE + items.len());
+    data.extend_from_slice(&len.to_le_bytes());
+    data.extend_from_slice(items);
+    MEMORY_MANAGER.with_borrow_mut(|mm| {
+        mm.write_metadata_at(WriteCategory::IndexRegistry, page, 0, &IndexChunk(data))
+    })
+}
+
+/// Encodes the given items, splitting them evenly in the least amount of chunks of up to `capacity` bytes.
+///
+/// Returns the first key of each chunk along with the amount of its items and their encoding;
+/// empty items make one empty chunk.
+/// Returns [`MemoryError::DataTooLarge`] if an item doesn't fit in a chunk.
+fn split_leaf(items: LeafItems, capacity: usize) -> MemoryResult<Vec<(IndexKey, EncodedLeaf)>> {
+    let encoded = items
+        .into_iter()
+        .map(|(key, (page, offset))| {
+            let mut bytes = vec![];
+            encode_value(&key.0, &mut bytes);
+            encode_value(&key.1, &mut bytes);
+            bytes.extend_from_slice(&page.to_le_bytes());
+            bytes.extend_from_slice(&offset.to_le_bytes());
+            (key, bytes)
+        })
+        .collect::<Vec<_>>();
+    let total = encoded.iter().map(|(_, bytes)| bytes.len()).sum::<usize>();
+    let target = total.div_ceil(total.div_ceil(capacity).max(1));
+
+    let mut chunks: Vec<(IndexKey, EncodedLeaf)> = vec![];
+    for (key, bytes) in encoded {
+        if bytes.len() > capacity {
+            return Err(MemoryError::DataTooLarge {
+                page_size: capacity as u64,
+                requested: bytes.len() as u64,
+            });
+        }
+        match chunks.last_mut() {
+            Some((_, (len, chunk)))
+                if chunk.len() < target && chunk.len() + bytes.len() <= capacity =>
+            {
+                *len += 1;
+                chunk.extend_from_slice(&bytes);
+            }
+            _ => chunks.push((key, (1, bytes))),
+        }
+    }
+    if chunks.is_empty() {
+        chunks.push(((Value::Null, Value::Null), (0, vec![])));
+    }
+
+    Ok(chunks)
+}
+
+/// Decodes the leaves of the directory of a [`BTreeIndex`]; empty data has no leaves.
+fn decode_directory(data: &[u8]) -> MemoryResult<Vec<Leaf>> {
+    if data.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let len = read_u32(data, 0)? as usize;
+    let mut offset = 4;
+    let mut leaves = Vec::with_capacity(len);
+    for _ in 0..len {
+        let page = read_u32(data, offset)?;
+        offset += 4;
+        let (value, size) = decode_value_at(data, offset)?;
+        offset += size;
+        let (pk, size) = decode_value_at(data, offset)?;
+        offset += size;
+        leaves.push(Leaf {
+            first: (value, pk),
+            page,
+        });
+    }
+
+    Ok(leaves)
+}
+
+/// A chunk of a page of a [`BTreeIndex`], written as is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct IndexChunk(Vec<u8>);
+
+impl Encode for IndexChunk {
+    const SIZE: DataSize = DataSize::Dynamic;
+
+    fn size(&self) -> MSize {
+        self.0.len() as MSize
+    }
+
+    fn encode(&'_ self) -> Cow<'_, [u8]> {
+        Cow::Borrowed(&self.0)
+    }
+
+    fn decode(data: Cow<[u8]>) -> MemoryResult<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self(data.into_owned()))
+    }
+}
+
+/// Encodes the given value, prefixed with the tag of its type.
+///
+/// Arrays are encoded as the amount of elements followed by the elements, each with its own tag,
+/// so that arrays without non-`NULL` elements keep their type.
+fn encode_value(value: &Value, bytes: &mut Vec<u8>) {
+    if let Value::Array(values) = value {
+        bytes.push(ARRAY_TAG);
+        bytes.extend_from_slice(&(values.len() as u32).to_le_bytes());
+        for value in values {
+            encode_value(value, bytes);
+        }
+        return;
+    }
+
+    let Some(data_type) = value.kind_opt() else {
+        bytes.push(0);
+        return;
+    };
+    bytes.push(data_type_tag(data_type));
+    let encoded = match value {
+        Value::Blob(v) => v.encode(),
+        Value::Boolean(v) => v.encode(),
+        Value::Date(v) => v.encode(),
+        Value::DateTime(v) => v.encode(),
+        Value::Decimal(v) => v.encode(),
+        Value::Float64(v) => v.encode(),
+        Value::Int32(v) => v.encode(),
+        Value::Int64(v) => v.encode(),
+        Value::AutoIncrement | Value::Null => unreachable!("NULL values have no data type"),
+        Value::Array(_) => unreachable!("arrays are encoded element by element"),
+        Value::Principal(v) => v.encode(),
+        Value::Text(v) => v.encode(),
+        Value::Uint32(v) => v.encode(),
+        Value::Uint64(v) => v.encode(),
+        Value::Uuid(v) => v.encode(),
+    };
+    bytes.extend_from_slice(&encoded);
+}
+
+/// Decodes a value encoded with [`encode_value`], returning it along with its encoded size.
+fn decode_value(data: &[u8]) -> MemoryResult<(Value, usize)> {
+    fn decode<E>(data: &[u8], into_value: fn(E) -> Value) -> MemoryResult<(Value, usize)>
+    where
+        E: Encode,
+    {
+        let value = E::decode_ref(data)?;
+        let size = value.size() as usize;
+        Ok((into_value(value), size + 1))
+    }
+
+    let tag = *data
+        .first()
+        .ok_or(MemoryError::DecodeError(DecodeError::TooShort))?;
+    let data = &data[1..];
+    match tag {
+        0 => Ok((Value::Null, 1)),
+        1 => decode::<Blob>(data, Value::Blob),
+        2 => decode::<Boolean>(data, Value::Boolean),
+        3 => decode::<Date>(data, Value::Date),
+        4 => decode::<DateTime>(data, Value::DateTime),
+        5 => decode::<Decimal>(data, Value::Decimal),
+        6 => decode::<Int32>(data, Value::Int32),
+        7 => decode::<Int64>(data, Value::Int64),
+        8 => decode::<Principal>(data, Value::Principal),
+        9 => decode::<Text>(data, Value::Text),
+        10 => decode::<Uint32>(data, Value::Uint32),
+        11 => decode::<Uint64>(data, Value::Uint64),
+        12 => decode::<Uuid>(data, Value::Uuid),
+        13 => decode::<Float64>(data, Value::Float64),
+        ARRAY_TAG => {
+            let len = data
+                .get(..4)
+                .ok_or(MemoryError::DecodeError(DecodeError::TooShort))?;
+            let len = u32::from_le_bytes(len.try_into()?) as usize;
+            let mut offset = 4;
+            let mut values = Vec::with_capacity(len);
+            for _ in 0..len {
+                let (value, size) = decode_value(
+                    data.get(offset..)
+                        .ok_or(MemoryError::DecodeError(DecodeError::TooShort))?,
+                )?;
+                values.push(value);
+                offset += size;
+            }
+            Ok((Value::Array(values), offset + 1))
+        }
+        // a type added by a newer version of the index
+        tag => Err(MemoryError::DecodeError(
+            DecodeError::UnsupportedFormatVersion(tag),
+        )),
+    }
+}
+
+/// Returns the tag encoding the given [`DataTypeKind`], as decoded by [`decode_value`].
+fn data_type_tag(data_type: DataTypeKind) -> u8 {
+    match data_type {
+        DataTypeKind::Array(_) => ARRAY_TAG,
+        DataTypeKind::Blob => 1,
+        DataTypeKind::Boolean => 2,
+        DataTypeKind::Date => 3,
+        DataTypeKind::DateTime => 4,
+        DataTypeKind::Decimal => 5,
+        DataTypeKind::Float64 => 13,
+        DataTypeKind::Int32 => 6,
+        DataTypeKind::Int64 => 7,
+        DataTypeKind::Principal => 8,
+        DataTypeKind::Text => 9,
+        DataTypeKind::Uint32 => 10,
+        DataTypeKind::Uint64 | DataTypeKind::AutoIncrement => 11,
+        DataTypeKind::Uuid => 12,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::memory::TableRegistryPage;
+    use crate::tests::User;
+
+    fn entry(pk: u32, page: Page, offset: PageOffset) -> IndexEntry {
+        IndexEntry {
+            pk: Value::Uint32(pk.into()),
+            page,
+            offset,
+        }
+    }
+
+    fn allocate_index() -> BTreeIndex {
+        let page = MEMORY_MANAGER
+            .with_borrow_mut(|mm| mm.allocate_page())
+            .expect("failed to allocate page");
+        BTreeIndex::load(&[page]).expect("failed to load index")
+    }
+
+    #[test]
+    fn test_should_write_and_load_btree_index() {
+        let mut index = allocate_index();
+        assert!(index.is_empty().expect("failed to read index"));
+
+        let alice = Value::Text("alice".to_string().into());
+        let bob = Value::Text("bob".to_string().into());
+        index
+            .insert(bob.clone(), entry(1, 10, 0))
+            .expect("failed to insert");
+        index
+            .insert(alice.clone(), entry(2, 10, 32))
+            .expect("failed to insert");
+        index
+            .insert(alice.clone(), entry(3, 11, 0))
+            .expect("failed to insert");
+
+        let reloaded = BTreeIndex::load(index.pages()).expect("failed to load index");
+        assert_eq!(reloaded, index);
+        assert_eq!(reloaded.len().expect("failed to read index"), 3);
+        assert_eq!(
+            reloaded.lookup(&alice).expect("failed to lookup"),
+            vec![entry(2, 10, 32), entry(3, 11, 0)]
+        );
+        assert_eq!(
+            reloaded.lookup(&bob).expect("failed to lookup"),
+            vec![entry(1, 10, 0)]
+        );
+        assert!(
+            reloaded
+                .lookup(&Value::Null)
+                .expect("failed to lookup")
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_should_replace_and_remove_btree_index_entries() {
+        let mut index = BTreeIndex::default();
+        let value = Value::Int64(Int64(42));
+        index
+            .insert(value.clone(), entry(1, 10, 0))
+            .expect("failed to insert");
+        index
+            .insert(value.clone(), entry(1, 12, 64))
+            .expect("failed to insert");
+        assert_eq!(
+            index.lookup(&value).expect("failed to lookup"),
+            vec![entry(1, 12, 64)]
+        );
+
+        assert!(
+            !index
+                .remove(&value, &Value::Uint32(2u32.into()))
+                .expect("failed to remove")
+        );
+        assert!(
+            index
+                .remove(&value, &Value::Uint32(1u32.into()))
+                .expect("failed to remove")
+        );
+        assert!(index.is_empty().expect("failed to read index"));
+    }
+
+    #[test]
+    fn test_should_encode_and_decode_float_keys() {
+        for value in [0.5, -0.0, f64::INFINITY, f64::NEG_INFINITY, f64::NAN] {
+            let value = Value::Float64(Float64(value));
+            let mut bytes = vec![];
+            encode_value(&value, &mut bytes);
+            let (decoded, size) = decode_value(&bytes).expect("failed to decode value");
+            assert_eq!(decoded, value);
+            assert_eq!(size, bytes.len());
+        }
+    }
+
+    #[test]
+    fn test_should_encode_and_decode_array_keys() {
+        for value in [
+            Value::Array(vec![]),
+            Value::Array(vec![Value::Null]),
+            Value::Array(vec![Value::Uint32(1u32.into())]),
+            Value::Array((0..100u32).map(|i| Value::Uint32(i.into())).collect()),
+            Value::Array(vec![Value::Text("a".to_string().into()), Value::Null]),
+        ] {
+            let mut bytes = vec![];
+            encode_value(&value, &mut bytes);
+            let (decoded, size) = decode_value(&bytes).expect("failed to decode value");
+            assert_eq!(decoded, value);
+            assert_eq!(size, bytes.len());
+        }
+    }
+
+    #[test]
+    fn test_should_build_btree_index_from_stored_records() {
+        let (pages_list_page, free_segments_page, index_page) =
+            MEMORY_MANAGER.with_borrow_mut(|mm| {
+                (
+                    mm.allocate_page().expect("failed to allocate page"),
+                    mm.allocate_page().expect("failed to allocate page"),
+                    mm.allocate_page().expect("failed to allocate page"),
+                )
+            });
+        let mut registry = TableRegistry::load(TableRegistryPage {
+            pages_list_page,
+            free_segments_page,
+        })
+        .expect("failed to load table registry");
+        for (id, name) in [(1u32, "alice"), (2, "bob"), (3, "alice")] {
+            registry
+                .insert(User {
+                    id: id.into(),
+                    name: name.to_string().into(),
+                })
+                .expect("failed to insert");
+        }
+
+        let index = BTreeIndex::build::<User>(&registry, "name", index_page)
+            .expect("failed to build index");
+        assert_eq!(index.len().expect("failed to read index"), 3);
+        let pks = index
+            .lookup(&Value::Text("alice".to_string().into()))
+            .expect("failed to lookup")
+            .into_iter()
+            .map(|entry| entry.pk)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            pks,
+            vec![Value::Uint32(1u32.into()), Value::Uint32(3u32.into())]
+        );
+        assert_eq!(
+            BTreeIndex::load(index.pages()).expect("failed to load index"),
+            index
+        );
+    }
+
+    #[test]
+    fn test_should_get_btree_index_range() {
+        let mut index = BTreeIndex::default();
+        for pk in 0..10u32 {
+            index
+                .insert(Value::Int64(Int64(pk as i64 % 5)), entry(pk, 10, 0))
+                .expect("failed to insert");
+        }
+        let value = |v: i64| Value::Int64(Int64(v));
+        let range = |lower: Bound<&Value>, upper: Bound<&Value>| {
+            index
+                .range(lower, upper)
+                .expect("failed to read range")
+                .into_iter()
+                .map(|entry| entry.pk)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            range(Bound::Included(&value(1)), Bound::Excluded(&value(3))),
+            [1u32, 6, 2, 7].map(|pk| Value::Uint32(pk.into())).to_vec()
+        );
+        assert_eq!(range(Bound::Excluded(&value(3)), Bound::Unbounded).len(), 2);
+        assert_eq!(range(Bound::Unbounded, Bound::Included(&value(0))).len(), 2);
+        // empty ranges
+        assert!(range(Bound::Excluded(&value(2)), Bound::Excluded(&value(2))).is_empty());
+        assert!(range(Bound::Included(&value(4)), Bound::Included(&value(1))).is_empty());
+    }
+
+    #[test]
+    fn test_should_split_btree_index_leaves() {
+        let mut index = allocate_index();
+        let root = index.pages()[0];
+        let name = "x".repeat(1024);
+        let value = |pk: u32| Value::Text(format!("{name}{pk:03}").into());
+        // inserted out of order, so that leaves in the middle are split too
+        for pk in (0..100u32).rev().step_by(2).chain((0..100).step_by(2)) {
+            index
+                .insert(value(pk), entry(pk, 10, pk as PageOffset))
+                .expect("failed to insert");
+        }
+        assert!(index.leaves.len() > 1);
+        assert_eq!(index.pages()[0], root);
+
+        let reloaded = BTreeIndex::load(index.pages()).expect("failed to load index");
+        assert_eq!(reloaded, index);
+        assert_eq!(reloaded.len().expect("failed to read index"), 100);
+        for pk in 0..100u32 {
+            assert_eq!(
+                reloaded.lookup(&value(pk)).expect("failed to lookup"),
+                vec![entry(pk, 10, pk as PageOffset)]
+            );
+        }
+        let pks = reloaded
+            .range(Bound::Excluded(&value(10)), Bound::Included(&value(90)))
+            .expect("failed to read range")
+            .into_iter()
+            .map(|entry| entry.pk)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            pks,
+            (11..=90u32)
+                .map(|pk| Value::Uint32(pk.into()))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_should_continue_btree_index_directory() {
+        // keys so large that a leaf stores up to two of them and the directory doesn't fit in the root page
+        let mut index = allocate_index();
+        let name = "x".repeat(30_000);
+        let value = |pk: u32| Value::Text(format!("{name}{pk:03}").into());
+        for pk in 0..10u32 {
+            index
+                .insert(value(pk), entry(pk, 10, 0))
+                .expect("failed to insert");
+        }
+        assert!(!index.directory_pages.is_empty());
+
+        let reloaded = BTreeIndex::load(index.pages()).expect("failed to load index");
+        assert_eq!(reloaded, index);
+        assert_eq!(
+            reloaded.lookup(&value(7)).expect("failed to lookup"),
+            vec![entry(7, 10, 0)]
+        );
+    }
+
+    #[test]
+    fn test_should_index_many_records_with_the_same_value() {
+        let mut index = allocate_index();
+        let value = Value::Boolean(Boolean(true));
+        for pk in 0..10_000u32 {
+            index
+                .insert(value.clone(), entry(pk, 10, 0))
+                .expect("failed to insert");
+        }
+        index
+            .insert(Value::Boolean(Boolean(false)), entry(0, 11, 0))
+            .expect("failed to insert");
+        assert!(index.leaves.len() > 1);
+
+        let entries = index.lookup(&value).expect("failed to lookup");
+        assert_eq!(entries.len(), 10_000);
+        assert_eq!(entries[9_999], entry(9_999, 10, 0));
+        assert_eq!(
+            index
+                .lookup(&Value::Boolean(Boolean(false)))
+                .expect("failed to lookup"),
+            vec![entry(0, 11, 0)]
+        );
+    }
+
+    #[test]
+    fn test_should_rewrite_only_the_changed_leaf() {
+        let mut index = allocate_index();
+        for pk in 0..10_000u32 {
+            index
+                .insert(Value::Uint32(pk.into()), entry(pk, 10, 0))
+                .expect("failed to insert");
+        }
+        assert!(index.leaves.len() > 2);
+
+        let page_size = MEMORY_MANAGER.with_borrow_mut(|mm| {
+            mm.reset_stats();
+            mm.page_size()
+        });
+        assert!(
+            index
+                .remove(
+                    &Value::Uint32(2_500u32.into()),
+                    &Value::Uint32(2_500u32.into())
+                )
+                .expect("failed to remove")
+        );
+        index
+            .insert(Value::Uint32(2_500u32.into()), entry(2_500, 12, 0))
+            .expect("failed to insert");
+        let stats = MEMORY_MANAGER.with_borrow(|mm| mm.write_amplification_tracker());
+        assert!(stats.index_registry_bytes <= 2 * page_size);
+    }
+}
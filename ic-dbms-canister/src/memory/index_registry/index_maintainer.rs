@@ -0,0 +1,536 @@
+use std::borrow::Cow;
+use std::ops::Bound;
+
+use ic_dbms_api::prelude::{
+    BoundaryKind, ColumnDef, Filter, IndexType, MemoryResult, Page, PageOffset, TableSchema, Value,
+};
+
+use super::IndexInfo;
+use super::btree_index::{BTreeIndex, IndexEntry, IndexRange};
+use crate::memory::SCHEMA_REGISTRY;
+
+/// Decodes a stored record of a table into the values of its columns.
+type RecordValues = fn(&[u8]) -> MemoryResult<Vec<(ColumnDef, Value)>>;
+
+/// Keeps a B-tree index up to date with the writes on the indexed table.
+///
+/// Each operation loads the directory of the [`BTreeIndex`] and rewrites the changed leaf only,
+/// allocating new pages, which are recorded in the [`super::IndexRegistry`], if a leaf is split.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexMaintainer {
+    index: IndexInfo,
+}
+
+impl IndexMaintainer {
+    /// Creates the maintainer of the given index.
+    pub fn new(index: IndexInfo) -> Self {
+        Self { index }
+    }
+
+    /// Returns the indexed column.
+    pub fn column(&self) -> &str {
+        &self.index.column
+    }
+
+    /// Loads the maintained [`BTreeIndex`].
+    pub fn load(&self) -> MemoryResult<BTreeIndex> {
+        BTreeIndex::load(&self.index.pages)
+    }
+
+    /// Returns the entries of the records storing the given value.
+    pub fn lookup(&self, value: &Value) -> MemoryResult<Vec<IndexEntry>> {
+        self.load()?.lookup(value)
+    }
+
+    /// Returns the entries of the records storing a value within the given bounds, in ascending value order.
+    pub fn lookup_range(
+        &self,
+        lower: Bound<&Value>,
+        upper: Bound<&Value>,
+    ) -> MemoryResult<Vec<IndexEntry>> {
+        self.load()?.range(lower, upper)
+    }
+
+    /// Returns an iterator over the entries of the records storing a value within the given bounds,
+    /// in ascending value order, reading the index as the iterator advances.
+    pub fn scan(&self, lower: Bound<Value>, upper: Bound<Value>) -> MemoryResult<IndexRange> {
+        Ok(self.load()?.into_range(lower, upper))
+    }
+
+    /// Adds the record with the given primary key and indexed value, stored at the given location, to the index.
+    pub fn on_insert(
+        &mut self,
+        pk: Value,
+        indexed_value: Value,
+        location: (Page, PageOffset),
+    ) -> MemoryResult<()> {
+        let mut index = self.load()?;
+        index.insert(indexed_value, entry(pk, location))?;
+        self.sync_pages(&index)
+    }
+
+    /// Removes the record with the given primary key and indexed value from the index.
+    pub fn on_delete(&mut self, pk: Value, indexed_value: Value) -> MemoryResult<()> {
+        let mut index = self.load()?;
+        index.remove(&indexed_value, &pk)?;
+        Ok(())
+    }
+
+    /// Moves the record with the given primary key from its old indexed value
+    /// to its new indexed value, stored at the given location.
+    pub fn on_update(
+        &mut self,
+        pk: Value,
+        old_indexed_value: Value,
+        new_indexed_value: Value,
+        new_location: (Page, PageOffset),
+    ) -> MemoryResult<()> {
+        let mut index = self.load()?;
+        index.remove(&old_indexed_value, &pk)?;
+        index.insert(new_indexed_value, entry(pk, new_location))?;
+        self.sync_pages(&index)
+    }
+
+    /// Records the pages of the given written index in the [`super::IndexRegistry`] if new pages have been allocated.
+    fn sync_pages(&mut self, index: &BTreeIndex) -> MemoryResult<()> {
+        if index.pages() != self.index.pages {
+            self.index.pages = index.pages().to_vec();
+            SCHEMA_REGISTRY.with_borrow_mut(|sr| {
+                sr.set_index_pages(self.index.id, self.index.pages.clone())
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The [`IndexMaintainer`]s of the B-tree indexes of a table.
+///
+/// Set on a [`crate::memory::TableRegistry`] with [`crate::memory::TableRegistry::with_indexes`],
+/// the indexes are updated on every insert, update and delete, right after the record is written;
+/// since the DBMS runs writes atomically, a failing index update reverts the data write as well.
+#[derive(Debug, Clone)]
+pub struct TableIndexes {
+    primary_key: &'static str,
+    columns: &'static [ColumnDef],
+    record_values: RecordValues,
+    maintainers: Vec<IndexMaintainer>,
+}
+
+impl TableIndexes {
+    /// Loads the B-tree indexes of the table `T` from the [`super::IndexRegistry`].
+    ///
+    /// Returns `None` if the table has no single column B-tree index.
+    pub fn load<T>() -> Option<Self>
+    where
+        T: TableSchema,
+    {
+        let indexes = SCHEMA_REGISTRY
+            .with_borrow(|sr| sr.index_registry().indexes_for_table(T::fingerprint()));
+        let indexes = Self::new::<T>(indexes);
+        (!indexes.maintainers.is_empty()).then_some(indexes)
+    }
+
+    /// Makes the [`TableIndexes`] of the table `T` with the given indexes.
+    ///
+    /// Indexes other than single column B-tree indexes are ignored.
+    pub fn new<T>(indexes: Vec<IndexInfo>) -> Self
+    where
+        T: TableSchema,
+    {
+        let maintainers = indexes
+            .into_iter()
+            .filter(|index| index.index_type == IndexType::BTree && !index.column.contains(','))
+            .map(IndexMaintainer::new)
+            .collect();
+
+        Self {
+            primary_key: T::primary_key(),
+            columns: T::columns(),
+            record_values: record_values::<T>,
+            maintainers,
+        }
+    }
+
+    /// Returns the [`IndexMaintainer`] of the index on the given column.
+    pub fn maintainer(&self, column: &str) -> Option<&IndexMaintainer> {
+        self.maintainers
+            .iter()
+            .find(|maintainer| maintainer.column() == column)
+    }
+
+    /// Looks up the entries of the records which may match the given filter, in the index on a column
+    /// compared by the filter with `=`, `>`, `<`, `>=`, `<=` or `BETWEEN`, also as an operand of an `AND`.
+    ///
+    /// The records must still be checked against the whole filter, since only one comparison is looked up.
+    /// Returns the indexed column along with the entries,
+    /// or `None` if no index applies, as for comparisons with values of another type than the column.
+    pub fn lookup_filter(
+        &self,
+        filter: &Filter,
+    ) -> MemoryResult<Option<(&'static str, Vec<IndexEntry>)>> {
+        let Some((column, lower, upper)) = self.filter_range(filter) else {
+            return Ok(None);
+        };
+        let Some(maintainer) = self.maintainer(column) else {
+            return Ok(None);
+        };
+
+        maintainer
+            .lookup_range(lower, upper)
+            .map(|entries| Some((column, entries)))
+    }
+
+    /// Returns the indexed column compared by the filter and the bounds of the values matching the comparison.
+    fn filter_range<'f>(
+        &self,
+        filter: &'f Filter,
+    ) -> Option<(&'static str, Bound<&'f Value>, Bound<&'f Value>)> {
+        fn bound(value: &Value, kind: BoundaryKind) -> Bound<&Value> {
+            match kind {
+                BoundaryKind::Inclusive => Bound::Included(value),
+                BoundaryKind::Exclusive => Bound::Excluded(value),
+            }
+        }
+
+        let (column, lower, upper) = match filter {
+            Filter::Eq(column, value) => (*column, Bound::Included(value), Bound::Included(value)),
+            Filter::Gt(column, value) => (*column, Bound::Excluded(value), Bound::Unbounded),
+            Filter::Ge(column, value) => (*column, Bound::Included(value), Bound::Unbounded),
+            Filter::Lt(column, value) => (*column, Bound::Unbounded, Bound::Excluded(value)),
+            Filter::Le(column, value) => (*column, Bound::Unbounded, Bound::Included(value)),
+            Filter::Between(column, low, low_kind, high, high_kind) => {
+                (*column, bound(low, *low_kind), bound(high, *high_kind))
+            }
+            Filter::And(left, right) => {
+                return self.filter_range(left).or_else(|| self.filter_range(right));
+            }
+            _ => return None,
+        };
+
+        // comparisons with NULL or with values of another type don't match the indexed values
+        let data_type = self
+            .columns
+            .iter()
+            .find(|col_def| col_def.name == column)?
+            .data_type;
+        let comparable = |bound: Bound<&Value>| match bound {
+            Bound::Included(value) | Bound::Excluded(value) => {
+                value.kind_opt() == Some(data_type.value_kind())
+            }
+            Bound::Unbounded => true,
+        };
+
+        (self.maintainer(column).is_some() && comparable(lower) && comparable(upper))
+            .then_some((column, lower, upper))
+    }
+
+    /// Indexes the given encoded record, stored at the given location.
+    pub(crate) fn on_insert(
+        &mut self,
+        record: &[u8],
+        location: (Page, PageOffset),
+    ) -> MemoryResult<()> {
+        let values = (self.record_values)(record)?;
+        let pk = column_value(&values, self.primary_key);
+        for maintainer in &mut self.maintainers {
+            let indexed_value = column_value(&values, &maintainer.index.column);
+            maintainer.on_insert(pk.clone(), indexed_value, location)?;
+        }
+
+        Ok(())
+    }
+
+    /// Removes the given encoded record from the indexes.
+    pub(crate) fn on_delete(&mut self, record: &[u8]) -> MemoryResult<()> {
+        let values = (self.record_values)(record)?;
+        let pk = column_value(&values, self.primary_key);
+        for maintainer in &mut self.maintainers {
+            let indexed_value = column_value(&values, &maintainer.index.column);
+            maintainer.on_delete(pk.clone(), indexed_value)?;
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the given old encoded record, stored at `old_location`,
+    /// with the new encoded record, stored at `new_location`, in the indexes.
+    ///
+    /// Indexes whose value and location are unchanged are not written.
+    pub(crate) fn on_update(
+        &mut self,
+        old_record: &[u8],
+        new_record: &[u8],
+        old_location: (Page, PageOffset),
+        new_location: (Page, PageOffset),
+    ) -> MemoryResult<()> {
+        let old_values = (self.record_values)(old_record)?;
+        let new_values = (self.record_values)(new_record)?;
+        let old_pk = column_value(&old_values, self.primary_key);
+        let new_pk = column_value(&new_values, self.primary_key);
+        for maintainer in &mut self.maintainers {
+            let old_indexed_value = column_value(&old_values, &maintainer.index.column);
+            let new_indexed_value = column_value(&new_values, &maintainer.index.column);
+            if old_pk != new_pk {
+                maintainer.on_delete(old_pk.clone(), old_indexed_value)?;
+                maintainer.on_insert(new_pk.clone(), new_indexed_value, new_location)?;
+            } else if old_indexed_value != new_indexed_value || old_location != new_location {
+                maintainer.on_update(
+                    new_pk.clone(),
+                    old_indexed_value,
+                    new_indexed_value,
+                    new_location,
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Makes the [`IndexEntry`] of the record with the given primary key, stored at the given location.
+fn entry(pk: Value, (page, offset): (Page, PageOffset)) -> IndexEntry {
+    IndexEntry { pk, page, offset }
+}
+
+/// Decodes a stored record of the table `T`, as the [`RecordValues`] of [`TableIndexes`].
+fn record_values<T>(record: &[u8]) -> MemoryResult<Vec<(ColumnDef, Value)>>
+where
+    T: TableSchema,
+{
+    T::decode(Cow::Borrowed(record)).map(T::to_values)
+}
+
+/// Returns the value of the given column, or [`Value::Null`] if the record has no such column.
+pub(super) fn column_value(values: &[(ColumnDef, Value)], column: &str) -> Value {
+    values
+        .iter()
+        .find(|(col_def, _)| col_def.name == column)
+        .map_or(Value::Null, |(_, value)| value.clone())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use ic_dbms_api::prelude::TableSchema as _;
+
+    use super::*;
+    use crate::memory::{MEMORY_MANAGER, TableRegistry, TableRegistryPage};
+    use crate::tests::User;
+
+    fn user(id: u32, name: &str) -> User {
+        User {
+            id: id.into(),
+            name: name.to_string().into(),
+        }
+    }
+
+    fn name(name: &str) -> Value {
+        Value::Text(name.to_string().into())
+    }
+
+    /// Makes a table registry for [`User`], with a B-tree index on the name.
+    fn indexed_registry() -> TableRegistry {
+        let (pages_list_page, free_segments_page, index_page) =
+            MEMORY_MANAGER.with_borrow_mut(|mm| {
+                (
+                    mm.allocate_page().expect("failed to allocate page"),
+                    mm.allocate_page().expect("failed to allocate page"),
+                    mm.allocate_page().expect("failed to allocate page"),
+                )
+            });
+        let index = IndexInfo {
+            id: 0,
+            table: User::fingerprint(),
+            column: "name".to_string(),
+            index_type: IndexType::BTree,
+            pages: vec![index_page],
+        };
+
+        TableRegistry::load(TableRegistryPage {
+            pages_list_page,
+            free_segments_page,
+        })
+        .expect("failed to load table registry")
+        .with_indexes(TableIndexes::new::<User>(vec![index]))
+    }
+
+    fn lookup(registry: &TableRegistry, value: &Value) -> Vec<IndexEntry> {
+        registry
+            .indexes()
+            .and_then(|indexes| indexes.maintainer("name"))
+            .expect("index not found")
+            .lookup(value)
+            .expect("failed to lookup index")
+    }
+
+    /// Returns the location of the record of the user with the given id.
+    fn location(registry: &TableRegistry, id: u32) -> (Page, PageOffset) {
+        let mut reader = registry.read::<User>();
+        while let Some(next) = reader.try_next().expect("failed to read user") {
+            if next.record.id.0 == id {
+                return (next.page, next.offset);
+            }
+        }
+        panic!("user {id} not found");
+    }
+
+    #[test]
+    fn test_should_ignore_unsupported_indexes() {
+        let index = IndexInfo {
+            id: 0,
+            table: User::fingerprint(),
+            column: "id,name".to_string(),
+            index_type: IndexType::BTree,
+            pages: vec![],
+        };
+        let hash_index = IndexInfo {
+            column: "name".to_string(),
+            index_type: IndexType::Hash,
+            ..index.clone()
+        };
+
+        let indexes = TableIndexes::new::<User>(vec![index, hash_index]);
+        assert!(indexes.maintainers.is_empty());
+    }
+
+    #[test]
+    fn test_should_index_inserted_records() {
+        let mut registry = indexed_registry();
+        registry.insert(user(1, "alice")).expect("failed to insert");
+        registry.insert(user(2, "bob")).expect("failed to insert");
+        registry.insert(user(3, "alice")).expect("failed to insert");
+
+        let (page, offset) = location(&registry, 1);
+        let (other_page, other_offset) = location(&registry, 3);
+        assert_eq!(
+            lookup(&registry, &name("alice")),
+            vec![
+                IndexEntry {
+                    pk: Value::Uint32(1u32.into()),
+                    page,
+                    offset,
+                },
+                IndexEntry {
+                    pk: Value::Uint32(3u32.into()),
+                    page: other_page,
+                    offset: other_offset,
+                },
+            ]
+        );
+        assert_eq!(lookup(&registry, &name("bob")).len(), 1);
+        assert!(lookup(&registry, &name("carol")).is_empty());
+    }
+
+    #[test]
+    fn test_should_lookup_filter_in_index() {
+        let mut registry = indexed_registry();
+        registry.insert(user(1, "alice")).expect("failed to insert");
+        registry.insert(user(2, "bob")).expect("failed to insert");
+        registry.insert(user(3, "carol")).expect("failed to insert");
+        let indexes = registry.indexes().expect("indexes not set");
+        let pks = |filter: Filter| {
+            indexes
+                .lookup_filter(&filter)
+                .expect("failed to lookup filter")
+                .map(|(column, entries)| {
+                    assert_eq!(column, "name");
+                    entries
+                        .into_iter()
+                        .map(|entry| entry.pk)
+                        .collect::<Vec<_>>()
+                })
+        };
+        let ids = |ids: &[u32]| -> Option<Vec<Value>> {
+            Some(ids.iter().map(|id| Value::Uint32((*id).into())).collect())
+        };
+
+        assert_eq!(pks(Filter::eq("name", name("bob"))), ids(&[2]));
+        assert_eq!(pks(Filter::gt("name", name("alice"))), ids(&[2, 3]));
+        assert_eq!(pks(Filter::le("name", name("bob"))), ids(&[1, 2]));
+        assert_eq!(
+            pks(Filter::between_exclusive(
+                "name",
+                name("alice"),
+                name("carol")
+            )),
+            ids(&[2])
+        );
+        // the comparison on the indexed column is looked up among the operands of an AND
+        assert_eq!(
+            pks(Filter::eq("id", Value::Uint32(1u32.into())).and(Filter::lt("name", name("bob")))),
+            ids(&[1])
+        );
+
+        // no index on the column, other filters and values of another type
+        assert_eq!(pks(Filter::eq("id", Value::Uint32(1u32.into()))), None);
+        assert_eq!(pks(Filter::like("name", "a%")), None);
+        assert_eq!(
+            pks(Filter::eq("name", name("bob")).or(Filter::eq("name", name("carol")))),
+            None
+        );
+        assert_eq!(pks(Filter::gt("name", Value::Uint32(1u32.into()))), None);
+        assert_eq!(pks(Filter::eq("name", Value::Null)), None);
+    }
+
+    #[test]
+    fn test_should_update_index_on_update() {
+        let mut registry = indexed_registry();
+        registry.insert(user(1, "alice")).expect("failed to insert");
+        registry.insert(user(2, "bob")).expect("failed to insert");
+
+        // same size, updated in place
+        let (page, offset) = location(&registry, 1);
+        registry
+            .update(user(1, "carol"), user(1, "alice"), page, offset)
+            .expect("failed to update");
+        assert!(lookup(&registry, &name("alice")).is_empty());
+        assert_eq!(
+            lookup(&registry, &name("carol")),
+            vec![IndexEntry {
+                pk: Value::Uint32(1u32.into()),
+                page,
+                offset,
+            }]
+        );
+
+        // larger, reallocated
+        registry
+            .update(user(1, "caroline"), user(1, "carol"), page, offset)
+            .expect("failed to update");
+        let (new_page, new_offset) = location(&registry, 1);
+        assert_ne!((new_page, new_offset), (page, offset));
+        assert!(lookup(&registry, &name("carol")).is_empty());
+        assert_eq!(
+            lookup(&registry, &name("caroline")),
+            vec![IndexEntry {
+                pk: Value::Uint32(1u32.into()),
+                page: new_page,
+                offset: new_offset,
+            }]
+        );
+        assert_eq!(lookup(&registry, &name("bob")).len(), 1);
+    }
+
+    #[test]
+    fn test_should_remove_deleted_records_from_index() {
+        let mut registry = indexed_registry();
+        registry.insert(user(1, "alice")).expect("failed to insert");
+        registry.insert(user(2, "alice")).expect("failed to insert");
+
+        let (page, offset) = location(&registry, 1);
+        registry
+            .delete(user(1, "alice"), page, offset)
+            .expect("failed to delete");
+
+        let entries = lookup(&registry, &name("alice"));
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].pk, Value::Uint32(2u32.into()));
+
+        let (page, offset) = location(&registry, 2);
+        registry
+            .delete(user(2, "alice"), page, offset)
+            .expect("failed to delete");
+        assert!(lookup(&registry, &name("alice")).is_empty());
+    }
+}
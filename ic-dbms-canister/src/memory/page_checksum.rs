@@ -0,0 +1,179 @@
+use std::borrow::Cow;
+
+use ic_dbms_api::prelude::{DataSize, DecodeError, Encode, MSize, MemoryError, MemoryResult, Page};
+
+/// Magic number stored before the [`PageChecksum`] in the page trailer,
+/// which marks the memory as checksummed.
+///
+/// Memories written before page checksums were introduced have no magic on their pages,
+/// so they are left unchecksummed, with the whole page usable.
+pub const PAGE_CHECKSUM_MAGIC: u32 = 0x4B43_5243;
+
+/// The CRC32 checksum of a memory page, stored in the last [`PageChecksum::LEN`] bytes of the page,
+/// after the [`PAGE_CHECKSUM_MAGIC`].
+///
+/// It covers the whole page but the trailer,
+/// so that any corruption of the stable memory is detected when the page is read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageChecksum(pub u32);
+
+impl PageChecksum {
+    /// Length of the page trailer in bytes: the [`PAGE_CHECKSUM_MAGIC`] followed by the checksum.
+    pub const LEN: usize = 8;
+    /// Offset of the checksum in the page trailer.
+    pub const CHECKSUM_OFFSET: usize = 4;
+
+    /// Computes the checksum of the given page data, which must not include the trailer.
+    pub fn of(data: &[u8]) -> Self {
+        Self(crc32fast::hash(data))
+    }
+
+    /// Computes the checksum of a page after a write, from the checksum before the write,
+    /// without reading the rest of the page.
+    ///
+    /// `old` and `new` are the bytes of the written region before and after the write,
+    /// and `trailing_len` is the amount of checksummed bytes following the region.
+    ///
+    /// CRC32 is affine over XOR, so the checksum changes by the checksum of the changed bytes
+    /// shifted by the bytes following them.
+    pub fn update(self, old: &[u8], new: &[u8], trailing_len: u64) -> Self {
+        debug_assert_eq!(old.len(), new.len());
+        let delta = crc32fast::hash(old) ^ crc32fast::hash(new);
+        let mut hasher = crc32fast::Hasher::new_with_initial_len(delta, 0);
+        hasher.combine(&crc32fast::Hasher::new_with_initial_len(0, trailing_len));
+
+        Self(self.0 ^ hasher.finalize())
+    }
+
+    /// Encodes the page trailer, made of the [`PAGE_CHECKSUM_MAGIC`] and the checksum.
+    pub fn trailer(&self) -> [u8; Self::LEN] {
+        let mut trailer = [0u8; Self::LEN];
+        trailer[..Self::CHECKSUM_OFFSET].copy_from_slice(&PAGE_CHECKSUM_MAGIC.to_le_bytes());
+        trailer[Self::CHECKSUM_OFFSET..].copy_from_slice(&self.0.to_le_bytes());
+        trailer
+    }
+
+    /// Returns whether the given page ends with a trailer, i.e. with the [`PAGE_CHECKSUM_MAGIC`].
+    pub fn has_trailer(data: &[u8]) -> bool {
+        data.len() >= Self::LEN
+            && data[data.len() - Self::LEN..data.len() - Self::CHECKSUM_OFFSET]
+                == PAGE_CHECKSUM_MAGIC.to_le_bytes()
+    }
+
+    /// Verifies the given page, which must include the trailer in its last bytes.
+    ///
+    /// Returns [`MemoryError::ChecksumMismatch`] if the trailer is missing
+    /// or the stored checksum doesn't match the page data.
+    pub fn verify(page: Page, data: &[u8]) -> MemoryResult<()> {
+        let (data, trailer) = data.split_at(data.len() - Self::LEN);
+        let expected = Self::decode_ref(&trailer[Self::CHECKSUM_OFFSET..])?;
+        let actual = Self::of(data);
+        if trailer[..Self::CHECKSUM_OFFSET] != PAGE_CHECKSUM_MAGIC.to_le_bytes()
+            || expected != actual
+        {
+            return Err(MemoryError::ChecksumMismatch {
+                page,
+                expected: expected.0,
+                actual: actual.0,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl Encode for PageChecksum {
+    const SIZE: DataSize = DataSize::Fixed(4);
+
+    fn size(&self) -> MSize {
+        4
+    }
+
+    fn encode(&'_ self) -> Cow<'_, [u8]> {
+        Cow::Owned(self.0.to_le_bytes().to_vec())
+    }
+
+    fn decode(data: Cow<[u8]>) -> MemoryResult<Self>
+    where
+        Self: Sized,
+    {
+        Self::decode_ref(&data)
+    }
+
+    fn decode_ref(data: &[u8]) -> MemoryResult<Self>
+    where
+        Self: Sized,
+    {
+        let bytes: [u8; 4] = data
+            .get(..4)
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or(MemoryError::DecodeError(DecodeError::TooShort))?;
+
+        Ok(Self(u32::from_le_bytes(bytes)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_should_encode_decode_page_checksum() {
+        let checksum = PageChecksum(0xdeadbeef);
+        let encoded = checksum.encode();
+        assert_eq!(encoded.as_ref(), &[0xef, 0xbe, 0xad, 0xde]);
+        assert_eq!(PageChecksum::decode(encoded).unwrap(), checksum);
+        assert!(PageChecksum::decode_ref(&[0u8; 3]).is_err());
+    }
+
+    #[test]
+    fn test_should_verify_page_checksum() {
+        let mut page = vec![7u8; 64];
+        let checksum = PageChecksum::of(&page[..56]);
+        page[56..].copy_from_slice(&checksum.trailer());
+        assert!(PageChecksum::has_trailer(&page));
+        assert!(PageChecksum::verify(3, &page).is_ok());
+
+        page[10] ^= 0x01;
+        let actual = PageChecksum::of(&page[..56]).0;
+        assert!(matches!(
+            PageChecksum::verify(3, &page),
+            Err(MemoryError::ChecksumMismatch { page: 3, expected, actual: mismatch })
+                if expected == checksum.0 && mismatch == actual
+        ));
+    }
+
+    #[test]
+    fn test_should_not_verify_page_without_magic() {
+        let mut page = vec![0u8; 64];
+        let checksum = PageChecksum::of(&page[..56]);
+        page[60..].copy_from_slice(&checksum.encode());
+        assert!(!PageChecksum::has_trailer(&page));
+        assert!(matches!(
+            PageChecksum::verify(3, &page),
+            Err(MemoryError::ChecksumMismatch { page: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn test_should_update_page_checksum_incrementally() {
+        let mut page = (0..256).map(|i| (i * 31 % 251) as u8).collect::<Vec<_>>();
+        for (offset, len) in [(0, 4), (17, 9), (100, 1), (250, 6), (0, 256)] {
+            let checksum = PageChecksum::of(&page);
+            let old = page[offset..offset + len].to_vec();
+            let new = old
+                .iter()
+                .map(|byte| byte.wrapping_add(0x5a))
+                .collect::<Vec<_>>();
+            page[offset..offset + len].copy_from_slice(&new);
+
+            let trailing_len = (page.len() - offset - len) as u64;
+            assert_eq!(
+                checksum.update(&old, &new, trailing_len),
+                PageChecksum::of(&page),
+                "offset {offset}, len {len}"
+            );
+        }
+    }
+}
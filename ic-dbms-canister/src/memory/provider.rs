@@ -1,4 +1,4 @@
-use crate::memory::{MemoryError, MemoryResult};
+use crate::memory::{MemoryError, MemoryResult, MemorySnapshot};
 
 /// Memory Provider trait defines the interface for interacting with the underlying memory.
 ///
@@ -30,6 +30,12 @@ pub trait MemoryProvider {
     ///
     /// Returns an error if `offset + buf.len()` exceeds the current memory size.
     fn write(&mut self, offset: u64, buf: &[u8]) -> MemoryResult<()>;
+
+    /// Takes a [`MemorySnapshot`] of the memory, which can be restored with [`MemoryProvider::restore`].
+    fn snapshot(&self) -> MemorySnapshot;
+
+    /// Restores the memory to the state of the given [`MemorySnapshot`].
+    fn restore(&mut self, snapshot: MemorySnapshot) -> MemoryResult<()>;
 }
 
 /// An implementation of [`MemoryProvider`] that uses the Internet Computer's stable memory.
@@ -72,10 +78,29 @@ impl MemoryProvider for IcMemoryProvider {
         ic_cdk::stable::stable_write(offset, buf);
         Ok(())
     }
+
+    /// Stable memory is not copied; the snapshot just records the amount of allocated pages as a restore point.
+    fn snapshot(&self) -> MemorySnapshot {
+        MemorySnapshot {
+            pages: self.pages(),
+            data: None,
+        }
+    }
+
+    /// Stable memory can't shrink, so the pages allocated after the snapshot are zeroed.
+    fn restore(&mut self, snapshot: MemorySnapshot) -> MemoryResult<()> {
+        let from = snapshot.pages * Self::PAGE_SIZE;
+        let len = self.size().saturating_sub(from);
+        if len > 0 {
+            self.write(from, &vec![0u8; len as usize])?;
+        }
+
+        Ok(())
+    }
 }
 
 /// An implementation of [`MemoryProvider`] that uses heap memory for testing purposes.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 #[allow(dead_code)]
 pub struct HeapMemoryProvider {
     memory: Vec<u8>,
@@ -119,6 +144,20 @@ impl MemoryProvider for HeapMemoryProvider {
         self.memory[offset as usize..(offset as usize + buf.len())].copy_from_slice(buf);
         Ok(())
     }
+
+    fn snapshot(&self) -> MemorySnapshot {
+        MemorySnapshot {
+            pages: self.pages(),
+            data: Some(self.memory.clone()),
+        }
+    }
+
+    fn restore(&mut self, snapshot: MemorySnapshot) -> MemoryResult<()> {
+        self.memory = snapshot.data.unwrap_or_default();
+        self.memory
+            .resize((snapshot.pages * Self::PAGE_SIZE) as usize, 0);
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -140,6 +179,24 @@ mod tests {
         assert_eq!(provider.size(), 3 * HeapMemoryProvider::PAGE_SIZE);
     }
 
+    #[test]
+    fn test_should_snapshot_and_restore_heap_memory() {
+        let mut provider = HeapMemoryProvider::default();
+        provider.grow(1).unwrap();
+        provider.write(0, &[1, 2, 3]).unwrap();
+
+        let snapshot = provider.snapshot();
+
+        provider.write(0, &[4, 5, 6]).unwrap();
+        provider.grow(2).unwrap();
+
+        provider.restore(snapshot).unwrap();
+        assert_eq!(provider.pages(), 1);
+        let mut buffer = vec![0; 3];
+        provider.read(0, &mut buffer).unwrap();
+        assert_eq!(buffer, vec![1, 2, 3]);
+    }
+
     #[test]
     fn test_should_read_and_write_heap_memory() {
         let mut provider = HeapMemoryProvider::default();
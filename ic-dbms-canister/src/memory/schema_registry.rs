@@ -1,9 +1,25 @@
+mod column_layout;
+mod sequence_registry;
+
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
 
-use ic_dbms_api::prelude::{TableFingerprint, TableSchema};
+#[cfg(feature = "indexes")]
+use ic_dbms_api::prelude::IndexType;
+use ic_dbms_api::prelude::{
+    ColumnDef, DecodeError, TableFingerprint, TableSchema, columns_fingerprint,
+};
 
-use crate::memory::{DataSize, Encode, MEMORY_MANAGER, MSize, MemoryError, MemoryResult, Page};
+use self::column_layout::layout_fingerprint;
+pub use self::column_layout::{AlterTableOp, ColumnLayout};
+pub use self::sequence_registry::SequenceRegistry;
+#[cfg(feature = "indexes")]
+use crate::memory::{BTreeIndex, IndexId, IndexRegistry};
+use crate::memory::{
+    DataSize, Encode, MEMORY_MANAGER, MSize, MemoryError, MemoryResult, Page, TableRegistry,
+    WriteCategory,
+};
 
 thread_local! {
     /// The global schema registry.
@@ -12,6 +28,33 @@ thread_local! {
     pub static SCHEMA_REGISTRY: RefCell<SchemaRegistry> = RefCell::new(SchemaRegistry::load().unwrap_or_default());
 }
 
+/// Size of an encoded [`TableQuota`]: each limit is prefixed with a 1 byte presence flag.
+const QUOTA_SIZE: MSize = (1 + 4) + (1 + 8) + (1 + 8);
+
+/// Size of an encoded [`MigrationPhase`]: 1 byte for the phase and 4 bytes for each schema version.
+const MIGRATION_PHASE_SIZE: MSize = 1 + 4 + 4;
+
+/// Schema version of the tables which have never been migrated.
+pub const INITIAL_SCHEMA_VERSION: SchemaVersion = SchemaVersion(1);
+
+/// Version of the schema of a table, increased each time its columns are changed
+/// by a migration or by [`SchemaRegistry::alter_table`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SchemaVersion(pub u32);
+
+impl SchemaVersion {
+    /// Returns the version following this one.
+    pub fn next(self) -> Self {
+        Self(self.0 + 1)
+    }
+}
+
+impl std::fmt::Display for SchemaVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
 /// Data regarding the table registry page.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct TableRegistryPage {
@@ -19,10 +62,141 @@ pub struct TableRegistryPage {
     pub free_segments_page: Page,
 }
 
+/// Limits on the memory a table can take; `None` means unlimited.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TableQuota {
+    /// Maximum amount of pages allocated to store the records.
+    pub max_pages: Option<u32>,
+    /// Maximum amount of stored records.
+    pub max_rows: Option<u64>,
+    /// Maximum bytes taken by the stored records, including their headers.
+    pub max_bytes: Option<u64>,
+}
+
+impl TableQuota {
+    /// Returns whether the quota doesn't limit anything.
+    pub fn is_unlimited(&self) -> bool {
+        self.max_pages.is_none() && self.max_rows.is_none() && self.max_bytes.is_none()
+    }
+}
+
+/// The phase of an online schema migration of a table.
+///
+/// The phases are walked in order: the records written during the migration are written
+/// with both the old and the new schema, until the old records are dropped on cleanup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationPhase {
+    /// Records are written with both schemas, and read with the old one.
+    DualWrite {
+        old_schema_version: SchemaVersion,
+        new_schema_version: SchemaVersion,
+    },
+    /// Records are written with both schemas, and read with the new one.
+    CutOver {
+        old_schema_version: SchemaVersion,
+        new_schema_version: SchemaVersion,
+    },
+    /// The records written with the old schema are being dropped.
+    Cleanup {
+        old_schema_version: SchemaVersion,
+        new_schema_version: SchemaVersion,
+    },
+}
+
+impl MigrationPhase {
+    /// Returns the schema versions the table is migrated from and to.
+    pub fn schema_versions(&self) -> (SchemaVersion, SchemaVersion) {
+        match *self {
+            MigrationPhase::DualWrite {
+                old_schema_version,
+                new_schema_version,
+            }
+            | MigrationPhase::CutOver {
+                old_schema_version,
+                new_schema_version,
+            }
+            | MigrationPhase::Cleanup {
+                old_schema_version,
+                new_schema_version,
+            } => (old_schema_version, new_schema_version),
+        }
+    }
+
+    /// Returns whether the records are still written with both schemas.
+    pub fn is_dual_write(&self) -> bool {
+        matches!(
+            self,
+            MigrationPhase::DualWrite { .. } | MigrationPhase::CutOver { .. }
+        )
+    }
+
+    /// Returns the tag encoding the phase.
+    fn tag(&self) -> u8 {
+        match self {
+            MigrationPhase::DualWrite { .. } => 0,
+            MigrationPhase::CutOver { .. } => 1,
+            MigrationPhase::Cleanup { .. } => 2,
+        }
+    }
+
+    /// Makes the phase with the given tag and schema versions.
+    fn from_tag(
+        tag: u8,
+        old_schema_version: SchemaVersion,
+        new_schema_version: SchemaVersion,
+    ) -> MemoryResult<Self> {
+        match tag {
+            0 => Ok(MigrationPhase::DualWrite {
+                old_schema_version,
+                new_schema_version,
+            }),
+            1 => Ok(MigrationPhase::CutOver {
+                old_schema_version,
+                new_schema_version,
+            }),
+            2 => Ok(MigrationPhase::Cleanup {
+                old_schema_version,
+                new_schema_version,
+            }),
+            // a phase written by a newer version of the registry
+            tag => Err(MemoryError::DecodeError(
+                DecodeError::UnsupportedFormatVersion(tag),
+            )),
+        }
+    }
+}
+
 /// The schema registry takes care of storing and retrieving table schemas from memory.
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct SchemaRegistry {
     tables: HashMap<TableFingerprint, TableRegistryPage>,
+    /// Fingerprint of the column definitions each table has been registered with.
+    columns: HashMap<TableFingerprint, u64>,
+    /// Quotas of the tables which have one.
+    quotas: HashMap<TableFingerprint, TableQuota>,
+    /// Page of the idempotency registry, allocated on first use.
+    idempotency_page: Option<Page>,
+    /// Schema versions of the tables which have been migrated.
+    schema_versions: HashMap<TableFingerprint, SchemaVersion>,
+    /// Phases of the migrations in progress.
+    migrations: HashMap<TableFingerprint, MigrationPhase>,
+    /// Indexes of the tables, stored in their own page.
+    #[cfg(feature = "indexes")]
+    index_registry: IndexRegistry,
+    /// Page of the index registry, kept as is when indexes are disabled,
+    /// so that the registry is not lost if they are enabled again.
+    #[cfg(not(feature = "indexes"))]
+    index_registry_page: Option<Page>,
+    /// Version of the database, increased by each migration run by the [`crate::prelude::MigrationRunner`].
+    database_version: u32,
+    /// Names the tables have been registered with, changed by [`SchemaRegistry::rename_table`].
+    names: HashMap<TableFingerprint, String>,
+    /// Columns the tables have been registered with, changed by [`SchemaRegistry::alter_table`].
+    layouts: HashMap<TableFingerprint, Vec<ColumnLayout>>,
+    /// Sequences of the tables with an auto-increment column, stored in their own page.
+    sequences: SequenceRegistry,
+    /// Tables written by a build without the `indexes` feature, whose indexes must be rebuilt.
+    stale_indexes: HashSet<TableFingerprint>,
 }
 
 impl SchemaRegistry {
@@ -36,12 +210,209 @@ impl SchemaRegistry {
     /// Registers a table and allocates it registry page.
     ///
     /// The [`TableSchema`] type parameter is used to get the [`TableSchema::fingerprint`] of the table schema.
+    /// With the `indexes` feature, the [`TableSchema::indexes`] of the table are created in the `IndexRegistry`,
+    /// each with a dedicated page; B-tree indexes are built from the records already stored, if any.
     pub fn register_table<TS>(&mut self) -> MemoryResult<TableRegistryPage>
     where
         TS: TableSchema,
     {
-        // check if already registered
+        let pages = self.register_fingerprint(TS::fingerprint())?;
+
+        // tables registered before columns were tracked adopt the current columns
+        if let Entry::Vacant(entry) = self.columns.entry(TS::fingerprint()) {
+            entry.insert(TS::columns_fingerprint());
+            self.save()?;
+        }
+
+        // tables registered before names were tracked adopt the current name, while renamed tables keep theirs
+        if let Entry::Vacant(entry) = self.names.entry(TS::fingerprint()) {
+            entry.insert(TS::table_name().to_string());
+            self.save()?;
+        }
+
+        // tables registered before layouts were tracked adopt the current columns, while altered tables keep theirs
+        if let Entry::Vacant(entry) = self.layouts.entry(TS::fingerprint()) {
+            entry.insert(TS::columns().iter().map(ColumnLayout::from).collect());
+            self.save()?;
+        }
+
+        #[cfg(feature = "indexes")]
+        self.rebuild_stale_indexes::<TS>()?;
+
+        // tables registered before indexes were tracked create them as well
+        #[cfg(feature = "indexes")]
+        if !TS::indexes().is_empty()
+            && self
+                .index_registry
+                .indexes_for_table(TS::fingerprint())
+                .is_empty()
+        {
+            for index in TS::indexes() {
+                let column = index.columns.join(",");
+                let index_pages = Self::build_index::<TS>(pages, &column, index.index_type)?;
+                self.index_registry.create_index(
+                    TS::fingerprint(),
+                    &column,
+                    index.index_type,
+                    index_pages,
+                )?;
+            }
+            // the index registry page may have been allocated
+            self.save()?;
+        }
+
+        Ok(pages)
+    }
+
+    /// Deregisters a table, dropping its indexes, and writes the registry to memory.
+    ///
+    /// The pages of the table are not reclaimed, while the pages of its indexes are zeroed.
+    /// Returns whether the table was registered.
+    pub fn deregister_table<TS>(&mut self) -> MemoryResult<bool>
+    where
+        TS: TableSchema,
+    {
         let fingerprint = TS::fingerprint();
+        if self.tables.remove(&fingerprint).is_none() {
+            return Ok(false);
+        }
+        self.columns.remove(&fingerprint);
+        self.quotas.remove(&fingerprint);
+        self.schema_versions.remove(&fingerprint);
+        self.migrations.remove(&fingerprint);
+        self.names.remove(&fingerprint);
+        self.layouts.remove(&fingerprint);
+        self.sequences.remove(fingerprint)?;
+        self.stale_indexes.remove(&fingerprint);
+        #[cfg(feature = "indexes")]
+        self.index_registry.drop_indexes_for_table(fingerprint)?;
+        self.save()?;
+
+        Ok(true)
+    }
+
+    /// Returns the [`IndexRegistry`] tracking the indexes of the tables.
+    #[cfg(feature = "indexes")]
+    pub fn index_registry(&self) -> &IndexRegistry {
+        &self.index_registry
+    }
+
+    /// Creates a B-tree index on the `column` of the table, built from the records already stored,
+    /// and writes the registry to memory.
+    ///
+    /// The table is registered if needed. Returns the [`IndexId`] of the new index,
+    /// or of the existing B-tree index on the column, if any.
+    #[cfg(feature = "indexes")]
+    pub fn create_index<TS>(&mut self, column: &str) -> MemoryResult<IndexId>
+    where
+        TS: TableSchema,
+    {
+        let pages = self.register_table::<TS>()?;
+        if let Some(index) = self
+            .index_registry
+            .indexes_for_table(TS::fingerprint())
+            .into_iter()
+            .find(|index| index.column == column && index.index_type == IndexType::BTree)
+        {
+            return Ok(index.id);
+        }
+
+        let index_pages = Self::build_index::<TS>(pages, column, IndexType::BTree)?;
+        let id = self.index_registry.create_index(
+            TS::fingerprint(),
+            column,
+            IndexType::BTree,
+            index_pages,
+        )?;
+        // the index registry page may have been allocated
+        self.save()?;
+
+        Ok(id)
+    }
+
+    /// Allocates the pages of an index on the `column` of the table stored in the given pages,
+    /// building single column B-tree indexes from the stored records, which are the indexes kept up to date.
+    #[cfg(feature = "indexes")]
+    fn build_index<TS>(
+        table_pages: TableRegistryPage,
+        column: &str,
+        index_type: IndexType,
+    ) -> MemoryResult<Vec<Page>>
+    where
+        TS: TableSchema,
+    {
+        let page = MEMORY_MANAGER.with_borrow_mut(|m| m.allocate_page())?;
+        if index_type != IndexType::BTree || column.contains(',') {
+            return Ok(vec![page]);
+        }
+
+        let table_registry = TableRegistry::load(table_pages)?;
+        Ok(BTreeIndex::build::<TS>(&table_registry, column, page)?
+            .pages()
+            .to_vec())
+    }
+
+    /// Rebuilds the B-tree indexes of the table from the stored records,
+    /// if the table has been written by a build without the `indexes` feature, and writes the registry to memory.
+    ///
+    /// Returns whether the indexes were stale.
+    #[cfg(feature = "indexes")]
+    pub fn rebuild_stale_indexes<TS>(&mut self) -> MemoryResult<bool>
+    where
+        TS: TableSchema,
+    {
+        if !self.stale_indexes.contains(&TS::fingerprint()) {
+            return Ok(false);
+        }
+
+        if let Some(pages) = self.table_registry_page::<TS>() {
+            let table_registry = TableRegistry::load(pages)?;
+            for index in self.index_registry.indexes_for_table(TS::fingerprint()) {
+                if index.index_type != IndexType::BTree || index.column.contains(',') {
+                    continue;
+                }
+                let index_pages =
+                    BTreeIndex::rebuild::<TS>(&table_registry, &index.column, index.pages)?
+                        .pages()
+                        .to_vec();
+                self.index_registry.set_index_pages(index.id, index_pages)?;
+            }
+        }
+        crate::dbms_log!(INFO, "rebuilt stale indexes"; "table" => TS::table_name());
+        self.stale_indexes.remove(&TS::fingerprint());
+        self.save()?;
+
+        Ok(true)
+    }
+
+    /// Marks the indexes of the given table as stale, since a build without the `indexes` feature
+    /// doesn't maintain them, and writes the registry to memory.
+    ///
+    /// They are rebuilt by [`SchemaRegistry::register_table`] once the feature is enabled again.
+    /// Nothing is marked if no index has ever been created.
+    #[cfg(not(feature = "indexes"))]
+    pub fn mark_indexes_stale(&mut self, fingerprint: TableFingerprint) -> MemoryResult<()> {
+        if self.index_registry_page.is_none() || !self.stale_indexes.insert(fingerprint) {
+            return Ok(());
+        }
+
+        self.save()
+    }
+
+    /// Sets the pages dedicated to the data of the index with the given id, see [`IndexRegistry::set_index_pages`].
+    #[cfg(feature = "indexes")]
+    pub fn set_index_pages(&mut self, id: IndexId, pages: Vec<Page>) -> MemoryResult<bool> {
+        self.index_registry.set_index_pages(id, pages)
+    }
+
+    /// Registers a table by its fingerprint and allocates it registry page.
+    ///
+    /// If the fingerprint is already registered, the existing registry page is returned.
+    pub fn register_fingerprint(
+        &mut self,
+        fingerprint: TableFingerprint,
+    ) -> MemoryResult<TableRegistryPage> {
+        // check if already registered
         if let Some(pages) = self.tables.get(&fingerprint) {
             return Ok(*pages);
         }
@@ -57,11 +428,7 @@ impl SchemaRegistry {
             free_segments_page,
         };
         self.tables.insert(fingerprint, pages);
-
-        // get schema page
-        let page = MEMORY_MANAGER.with_borrow(|m| m.schema_page());
-        // write self to schema page
-        MEMORY_MANAGER.with_borrow_mut(|m| m.write_at(page, 0, self))?;
+        self.save()?;
 
         Ok(pages)
     }
@@ -71,7 +438,393 @@ impl SchemaRegistry {
     where
         TS: TableSchema,
     {
-        self.tables.get(&TS::fingerprint()).copied()
+        self.table_registry_page_by_fingerprint(TS::fingerprint())
+    }
+
+    /// Returns the table registry page for a given table fingerprint.
+    pub fn table_registry_page_by_fingerprint(
+        &self,
+        fingerprint: TableFingerprint,
+    ) -> Option<TableRegistryPage> {
+        self.tables.get(&fingerprint).copied()
+    }
+
+    /// Returns the table registry page for the table registered with the given name.
+    pub fn table_registry_page_by_name(&self, name: &str) -> Option<TableRegistryPage> {
+        self.fingerprint_by_name(name)
+            .and_then(|fingerprint| self.table_registry_page_by_fingerprint(fingerprint))
+    }
+
+    /// Returns the fingerprint of the table registered with the given name, if any.
+    pub fn fingerprint_by_name(&self, name: &str) -> Option<TableFingerprint> {
+        self.names
+            .iter()
+            .find(|(_, table_name)| table_name.as_str() == name)
+            .map(|(fingerprint, _)| *fingerprint)
+    }
+
+    /// Returns the name the given table has been registered with, if any.
+    pub fn table_name(&self, fingerprint: TableFingerprint) -> Option<&str> {
+        self.names.get(&fingerprint).map(String::as_str)
+    }
+
+    /// Renames the table registered as `old_name` to `new_name` and writes it to memory.
+    ///
+    /// The fingerprint doesn't depend on the name, so the pages of the table are kept as they are.
+    /// Nothing is renamed and `false` is returned if no table is registered as `old_name`
+    /// or another table is already registered as `new_name`.
+    pub fn rename_table(&mut self, old_name: &str, new_name: &'static str) -> MemoryResult<bool> {
+        let Some(fingerprint) = self.fingerprint_by_name(old_name) else {
+            return Ok(false);
+        };
+        if old_name == new_name {
+            return Ok(true);
+        }
+        if self.fingerprint_by_name(new_name).is_some() {
+            return Ok(false);
+        }
+
+        self.names.insert(fingerprint, new_name.to_string());
+        self.save()?;
+
+        Ok(true)
+    }
+
+    /// Estimates how many records of the table fit in the pages currently allocated to it,
+    /// based on [`TableRegistry::estimate_max_record_size`].
+    ///
+    /// Returns `0` if the table is not registered.
+    pub fn estimated_table_capacity<TS>(&self) -> MemoryResult<u64>
+    where
+        TS: TableSchema,
+    {
+        let Some(pages) = self.table_registry_page::<TS>() else {
+            return Ok(0);
+        };
+        let registry = TableRegistry::load(pages)?;
+        let record_size = u64::from(TableRegistry::estimate_max_record_size(TS::columns()).max(1));
+        let page_size = MEMORY_MANAGER.with_borrow(|mm| mm.page_size());
+
+        Ok(registry.page_count() * (page_size / record_size))
+    }
+
+    /// Returns the fingerprint of the column definitions the table has been registered with, if any.
+    pub fn columns_fingerprint(&self, fingerprint: TableFingerprint) -> Option<u64> {
+        self.columns.get(&fingerprint).copied()
+    }
+
+    /// Returns the [`TableQuota`] of the given table, if any.
+    pub fn table_quota(&self, fingerprint: TableFingerprint) -> Option<TableQuota> {
+        self.quotas.get(&fingerprint).copied()
+    }
+
+    /// Sets the [`TableQuota`] of the given table and writes it to memory.
+    ///
+    /// An unlimited quota removes the quota of the table.
+    pub fn set_table_quota(
+        &mut self,
+        fingerprint: TableFingerprint,
+        quota: TableQuota,
+    ) -> MemoryResult<()> {
+        if quota.is_unlimited() {
+            self.quotas.remove(&fingerprint);
+        } else {
+            self.quotas.insert(fingerprint, quota);
+        }
+        self.save()
+    }
+
+    /// Sets the fingerprint of the column definitions of the given table and writes it to memory.
+    pub fn set_columns_fingerprint(
+        &mut self,
+        fingerprint: TableFingerprint,
+        columns_fingerprint: u64,
+    ) -> MemoryResult<()> {
+        self.columns.insert(fingerprint, columns_fingerprint);
+        self.save()
+    }
+
+    /// Returns the schema version of the given table, which is increased by each completed migration.
+    pub fn schema_version(&self, fingerprint: TableFingerprint) -> SchemaVersion {
+        self.schema_versions
+            .get(&fingerprint)
+            .copied()
+            .unwrap_or(INITIAL_SCHEMA_VERSION)
+    }
+
+    /// Sets the schema version of the given table and writes it to memory.
+    pub fn set_schema_version(
+        &mut self,
+        fingerprint: TableFingerprint,
+        version: SchemaVersion,
+    ) -> MemoryResult<()> {
+        self.schema_versions.insert(fingerprint, version);
+        self.save()
+    }
+
+    /// Returns the columns of the given table, as registered and changed by [`SchemaRegistry::alter_table`].
+    pub fn table_columns(&self, fingerprint: TableFingerprint) -> Option<&[ColumnLayout]> {
+        self.layouts.get(&fingerprint).map(Vec::as_slice)
+    }
+
+    /// Sets the columns of the given table, along with their fingerprint, and writes the registry to memory.
+    pub fn set_table_columns(
+        &mut self,
+        fingerprint: TableFingerprint,
+        columns: &[ColumnDef],
+    ) -> MemoryResult<()> {
+        self.layouts.insert(
+            fingerprint,
+            columns.iter().map(ColumnLayout::from).collect(),
+        );
+        self.columns
+            .insert(fingerprint, columns_fingerprint(columns));
+        self.save()
+    }
+
+    /// Applies the given [`AlterTableOp`] to the columns of the given table and writes the registry to memory.
+    ///
+    /// The columns fingerprint of the table is updated to the new columns, and its schema version is increased,
+    /// so that after the migration the registry matches the [`TableSchema`] with the new columns.
+    /// The records of the table are not rewritten.
+    ///
+    /// Returns `false`, leaving the registry as is, if the table is not registered
+    /// or the operation can't be applied, see [`AlterTableOp::apply`].
+    pub fn alter_table(
+        &mut self,
+        fingerprint: TableFingerprint,
+        op: AlterTableOp,
+    ) -> MemoryResult<bool> {
+        let Some(columns) = self.layouts.get_mut(&fingerprint) else {
+            return Ok(false);
+        };
+        if !op.apply(columns) {
+            return Ok(false);
+        }
+
+        let columns_fingerprint = layout_fingerprint(columns);
+        self.columns.insert(fingerprint, columns_fingerprint);
+        let schema_version = self.schema_version(fingerprint).next();
+        self.schema_versions.insert(fingerprint, schema_version);
+        self.save()?;
+
+        Ok(true)
+    }
+
+    /// Returns the version of the database, which is `0` until a migration is run.
+    pub fn database_version(&self) -> u32 {
+        self.database_version
+    }
+
+    /// Sets the version of the database and writes it to memory.
+    pub fn set_database_version(&mut self, version: u32) -> MemoryResult<()> {
+        self.database_version = version;
+        self.save()
+    }
+
+    /// Returns the [`MigrationPhase`] of the migration of the given table, if any.
+    pub fn migration_phase(&self, fingerprint: TableFingerprint) -> Option<MigrationPhase> {
+        self.migrations.get(&fingerprint).copied()
+    }
+
+    /// Sets the [`MigrationPhase`] of the migration of the given table and writes it to memory;
+    /// `None` ends the migration.
+    pub fn set_migration_phase(
+        &mut self,
+        fingerprint: TableFingerprint,
+        phase: Option<MigrationPhase>,
+    ) -> MemoryResult<()> {
+        match phase {
+            Some(phase) => {
+                self.migrations.insert(fingerprint, phase);
+            }
+            None => {
+                self.migrations.remove(&fingerprint);
+            }
+        }
+        self.save()
+    }
+
+    /// Returns the last value generated by the sequence of the given table, if any.
+    pub fn sequence_value(&self, fingerprint: TableFingerprint) -> Option<u64> {
+        self.sequences.value(fingerprint)
+    }
+
+    /// Advances the sequence of the given table and writes it to memory, returning the new value.
+    ///
+    /// Sequences start at `1`, and values are never reused, even if the insert they were generated for fails.
+    /// Only the value is written to the [`SequenceRegistry`] page, unless the page has just been allocated,
+    /// in which case the schema registry is written as well.
+    pub fn next_sequence_value(&mut self, fingerprint: TableFingerprint) -> MemoryResult<u64> {
+        let allocated = self.sequences.page().is_some();
+        let value = self.sequences.next_value(fingerprint)?;
+        if !allocated {
+            self.save()?;
+        }
+
+        Ok(value)
+    }
+
+    /// Returns the page of the idempotency registry, if it has been allocated.
+    pub fn idempotency_page(&self) -> Option<Page> {
+        self.idempotency_page
+    }
+
+    /// Returns the page of the idempotency registry, allocating it if it doesn't exist yet.
+    pub fn get_or_allocate_idempotency_page(&mut self) -> MemoryResult<Page> {
+        if let Some(page) = self.idempotency_page {
+            return Ok(page);
+        }
+
+        let page = MEMORY_MANAGER.with_borrow_mut(|m| m.allocate_page())?;
+        self.idempotency_page = Some(page);
+        self.save()?;
+
+        Ok(page)
+    }
+
+    /// Returns the fingerprints of all the registered tables.
+    pub fn fingerprints(&self) -> impl Iterator<Item = TableFingerprint> + '_ {
+        self.tables.keys().copied()
+    }
+
+    /// Returns the fingerprints of all the registered tables, sorted.
+    pub fn list_tables(&self) -> Vec<TableFingerprint> {
+        let mut tables = self.fingerprints().collect::<Vec<_>>();
+        tables.sort_unstable();
+        tables
+    }
+
+    /// Registers a clone of the table `TS` named `name` under the given fingerprint, and writes the registry to memory.
+    ///
+    /// The clone starts with the columns, the schema version and the sequence of `TS`,
+    /// and, with the `indexes` feature, with empty indexes on the same columns as `TS`,
+    /// to be filled as the records are copied; the quota of `TS` is not copied.
+    ///
+    /// Returns `None`, registering nothing, if a table is already registered under the fingerprint or the name.
+    pub fn register_clone<TS>(
+        &mut self,
+        fingerprint: TableFingerprint,
+        name: &str,
+    ) -> MemoryResult<Option<TableRegistryPage>>
+    where
+        TS: TableSchema,
+    {
+        if self.tables.contains_key(&fingerprint) || self.fingerprint_by_name(name).is_some() {
+            return Ok(None);
+        }
+
+        let source = TS::fingerprint();
+        let pages = self.register_fingerprint(fingerprint)?;
+        self.names.insert(fingerprint, name.to_string());
+        if let Some(columns) = self.columns.get(&source).copied() {
+            self.columns.insert(fingerprint, columns);
+        }
+        if let Some(layout) = self.layouts.get(&source).cloned() {
+            self.layouts.insert(fingerprint, layout);
+        }
+        if let Some(schema_version) = self.schema_versions.get(&source).copied() {
+            self.schema_versions.insert(fingerprint, schema_version);
+        }
+        self.sequences.copy(source, fingerprint)?;
+        #[cfg(feature = "indexes")]
+        for index in self.index_registry.indexes_for_table(source) {
+            let index_pages = Self::build_index::<TS>(pages, &index.column, index.index_type)?;
+            self.index_registry.create_index(
+                fingerprint,
+                &index.column,
+                index.index_type,
+                index_pages,
+            )?;
+        }
+        self.save()?;
+
+        Ok(Some(pages))
+    }
+
+    /// Swaps the two given tables, along with all their metadata: columns, names, quotas, schema versions,
+    /// migrations, sequences and indexes, and writes the registry to memory.
+    ///
+    /// Afterwards, each fingerprint refers to the table previously registered under the other one.
+    /// Without the `indexes` feature, the index registry can't be changed,
+    /// so the indexes of both tables are marked as stale, to be rebuilt on the swapped records.
+    ///
+    /// Both fingerprints must be registered, otherwise nothing is swapped and `false` is returned.
+    pub fn swap_tables(&mut self, a: TableFingerprint, b: TableFingerprint) -> MemoryResult<bool> {
+        if !self.tables.contains_key(&a) || !self.tables.contains_key(&b) {
+            return Ok(false);
+        }
+
+        swap_entries(&mut self.tables, a, b);
+        swap_entries(&mut self.columns, a, b);
+        swap_entries(&mut self.quotas, a, b);
+        swap_entries(&mut self.schema_versions, a, b);
+        swap_entries(&mut self.migrations, a, b);
+        swap_entries(&mut self.names, a, b);
+        swap_entries(&mut self.layouts, a, b);
+        self.sequences.swap_tables(a, b)?;
+        let (a_stale, b_stale) = (self.stale_indexes.remove(&a), self.stale_indexes.remove(&b));
+        if a_stale {
+            self.stale_indexes.insert(b);
+        }
+        if b_stale {
+            self.stale_indexes.insert(a);
+        }
+        #[cfg(feature = "indexes")]
+        self.index_registry.swap_tables(a, b)?;
+        #[cfg(not(feature = "indexes"))]
+        if self.index_registry_page.is_some() {
+            self.stale_indexes.extend([a, b]);
+        }
+        self.save()?;
+
+        Ok(true)
+    }
+
+    /// Swaps the table registry pages of the two given table fingerprints, keeping the rest of their metadata.
+    ///
+    /// Used to replace the records of a table with those written to a staging table.
+    /// Both fingerprints must be registered, otherwise nothing is swapped and `false` is returned.
+    pub fn swap_table_pages(
+        &mut self,
+        a: TableFingerprint,
+        b: TableFingerprint,
+    ) -> MemoryResult<bool> {
+        let (Some(a_pages), Some(b_pages)) = (
+            self.table_registry_page_by_fingerprint(a),
+            self.table_registry_page_by_fingerprint(b),
+        ) else {
+            return Ok(false);
+        };
+
+        self.tables.insert(a, b_pages);
+        self.tables.insert(b, a_pages);
+        // a single write, so the swap is atomic
+        self.save()?;
+
+        Ok(true)
+    }
+
+    /// Writes the schema registry to the schema page.
+    fn save(&self) -> MemoryResult<()> {
+        let page = MEMORY_MANAGER.with_borrow(|m| m.schema_page());
+        MEMORY_MANAGER
+            .with_borrow_mut(|m| m.write_metadata_at(WriteCategory::SchemaRegistry, page, 0, self))
+    }
+}
+
+/// Swaps the values of the given keys of the map, moving a value to the other key if only one of them has one.
+fn swap_entries<V>(
+    map: &mut HashMap<TableFingerprint, V>,
+    a: TableFingerprint,
+    b: TableFingerprint,
+) {
+    let a_value = map.remove(&a);
+    if let Some(b_value) = map.remove(&b) {
+        map.insert(a, b_value);
+    }
+    if let Some(a_value) = a_value {
+        map.insert(b, a_value);
     }
 }
 
@@ -80,7 +833,47 @@ impl Encode for SchemaRegistry {
 
     fn size(&self) -> MSize {
         // 8 bytes for len + (8 + (4 * 2)) bytes for each entry
+        // + 8 bytes for columns len + (8 + 8) bytes for each columns entry
+        // + 8 bytes for quotas len + (8 + QUOTA_SIZE) bytes for each quota entry
+        // + 1 byte presence flag + 4 bytes for the idempotency page
+        // + 8 bytes for schema versions len + (8 + 4) bytes for each schema version entry
+        // + 8 bytes for migrations len + (8 + MIGRATION_PHASE_SIZE) bytes for each migration entry
+        // + 1 byte presence flag + 4 bytes for the index registry page
+        // + 4 bytes for the database version
+        // + 8 bytes for names len + (8 + 2 + name length) bytes for each name entry
+        // + 8 bytes for layouts len + (8 + 2 + columns size) bytes for each layout entry
+        // + 1 byte presence flag + 4 bytes for the sequence registry page
+        // + 8 bytes for stale indexes len + 8 bytes for each table with stale indexes
         8 + (self.tables.len() as MSize * (4 * 2 + 8))
+            + 8
+            + (self.columns.len() as MSize * 16)
+            + 8
+            + (self.quotas.len() as MSize * (8 + QUOTA_SIZE))
+            + 1
+            + 4
+            + 8
+            + (self.schema_versions.len() as MSize * 12)
+            + 8
+            + (self.migrations.len() as MSize * (8 + MIGRATION_PHASE_SIZE))
+            + 1
+            + 4
+            + 4
+            + 8
+            + self
+                .names
+                .values()
+                .map(|name| 8 + 2 + name.len() as MSize)
+                .sum::<MSize>()
+            + 8
+            + self
+                .layouts
+                .values()
+                .map(|columns| 8 + 2 + columns.iter().map(Encode::size).sum::<MSize>())
+                .sum::<MSize>()
+            + 1
+            + 4
+            + 8
+            + (self.stale_indexes.len() as MSize * 8)
     }
 
     fn encode(&'_ self) -> std::borrow::Cow<'_, [u8]> {
@@ -94,6 +887,75 @@ impl Encode for SchemaRegistry {
             buffer.extend_from_slice(&page.pages_list_page.to_le_bytes());
             buffer.extend_from_slice(&page.free_segments_page.to_le_bytes());
         }
+        // write 8 bytes len of columns map and each entry
+        buffer.extend_from_slice(&(self.columns.len() as u64).to_le_bytes());
+        for (fingerprint, columns_fingerprint) in &self.columns {
+            buffer.extend_from_slice(&fingerprint.to_le_bytes());
+            buffer.extend_from_slice(&columns_fingerprint.to_le_bytes());
+        }
+        // write 8 bytes len of quotas map and each entry; each limit is prefixed with a presence flag
+        buffer.extend_from_slice(&(self.quotas.len() as u64).to_le_bytes());
+        for (fingerprint, quota) in &self.quotas {
+            buffer.extend_from_slice(&fingerprint.to_le_bytes());
+            buffer.push(quota.max_pages.is_some() as u8);
+            buffer.extend_from_slice(&quota.max_pages.unwrap_or_default().to_le_bytes());
+            buffer.push(quota.max_rows.is_some() as u8);
+            buffer.extend_from_slice(&quota.max_rows.unwrap_or_default().to_le_bytes());
+            buffer.push(quota.max_bytes.is_some() as u8);
+            buffer.extend_from_slice(&quota.max_bytes.unwrap_or_default().to_le_bytes());
+        }
+        // write the idempotency page, prefixed with a presence flag
+        buffer.push(self.idempotency_page.is_some() as u8);
+        buffer.extend_from_slice(&self.idempotency_page.unwrap_or_default().to_le_bytes());
+        // write 8 bytes len of schema versions map and each entry
+        buffer.extend_from_slice(&(self.schema_versions.len() as u64).to_le_bytes());
+        for (fingerprint, version) in &self.schema_versions {
+            buffer.extend_from_slice(&fingerprint.to_le_bytes());
+            buffer.extend_from_slice(&version.0.to_le_bytes());
+        }
+        // write 8 bytes len of migrations map and each entry
+        buffer.extend_from_slice(&(self.migrations.len() as u64).to_le_bytes());
+        for (fingerprint, phase) in &self.migrations {
+            let (old_schema_version, new_schema_version) = phase.schema_versions();
+            buffer.extend_from_slice(&fingerprint.to_le_bytes());
+            buffer.push(phase.tag());
+            buffer.extend_from_slice(&old_schema_version.0.to_le_bytes());
+            buffer.extend_from_slice(&new_schema_version.0.to_le_bytes());
+        }
+        // write the index registry page, prefixed with a presence flag
+        #[cfg(feature = "indexes")]
+        let index_page = self.index_registry.page();
+        #[cfg(not(feature = "indexes"))]
+        let index_page = self.index_registry_page;
+        buffer.push(index_page.is_some() as u8);
+        buffer.extend_from_slice(&index_page.unwrap_or_default().to_le_bytes());
+        // write the database version
+        buffer.extend_from_slice(&self.database_version.to_le_bytes());
+        // write 8 bytes len of names map and each entry; each name is prefixed with its 2 bytes length
+        buffer.extend_from_slice(&(self.names.len() as u64).to_le_bytes());
+        for (fingerprint, name) in &self.names {
+            buffer.extend_from_slice(&fingerprint.to_le_bytes());
+            buffer.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            buffer.extend_from_slice(name.as_bytes());
+        }
+        // write 8 bytes len of layouts map and each entry; each layout is prefixed with its 2 bytes columns count
+        buffer.extend_from_slice(&(self.layouts.len() as u64).to_le_bytes());
+        for (fingerprint, columns) in &self.layouts {
+            buffer.extend_from_slice(&fingerprint.to_le_bytes());
+            buffer.extend_from_slice(&(columns.len() as u16).to_le_bytes());
+            for column in columns {
+                buffer.extend_from_slice(&column.encode());
+            }
+        }
+        // write the sequence registry page, prefixed with a presence flag
+        let sequences_page = self.sequences.page();
+        buffer.push(sequences_page.is_some() as u8);
+        buffer.extend_from_slice(&sequences_page.unwrap_or_default().to_le_bytes());
+        // write 8 bytes len of the tables with stale indexes and their fingerprints
+        buffer.extend_from_slice(&(self.stale_indexes.len() as u64).to_le_bytes());
+        for fingerprint in &self.stale_indexes {
+            buffer.extend_from_slice(&fingerprint.to_le_bytes());
+        }
         std::borrow::Cow::Owned(buffer)
     }
 
@@ -126,7 +988,189 @@ impl Encode for SchemaRegistry {
                 },
             );
         }
-        Ok(Self { tables })
+        // read columns fingerprints; registries written before columns were tracked don't have them
+        let mut columns = HashMap::new();
+        if data.len() >= offset + 8 {
+            let len = u64::from_le_bytes(data[offset..offset + 8].try_into()?) as usize;
+            offset += 8;
+            columns.reserve(len);
+            for _ in 0..len {
+                let fingerprint = u64::from_le_bytes(data[offset..offset + 8].try_into()?);
+                offset += 8;
+                let columns_fingerprint = u64::from_le_bytes(data[offset..offset + 8].try_into()?);
+                offset += 8;
+                columns.insert(fingerprint, columns_fingerprint);
+            }
+        }
+        // read quotas; registries written before quotas were introduced don't have them
+        let mut quotas = HashMap::new();
+        if data.len() >= offset + 8 {
+            let len = u64::from_le_bytes(data[offset..offset + 8].try_into()?) as usize;
+            offset += 8;
+            quotas.reserve(len);
+            for _ in 0..len {
+                let fingerprint = u64::from_le_bytes(data[offset..offset + 8].try_into()?);
+                offset += 8;
+                let max_pages = u32::from_le_bytes(data[offset + 1..offset + 5].try_into()?);
+                let max_pages = (data[offset] != 0).then_some(max_pages);
+                offset += 5;
+                let max_rows = u64::from_le_bytes(data[offset + 1..offset + 9].try_into()?);
+                let max_rows = (data[offset] != 0).then_some(max_rows);
+                offset += 9;
+                let max_bytes = u64::from_le_bytes(data[offset + 1..offset + 9].try_into()?);
+                let max_bytes = (data[offset] != 0).then_some(max_bytes);
+                offset += 9;
+                quotas.insert(
+                    fingerprint,
+                    TableQuota {
+                        max_pages,
+                        max_rows,
+                        max_bytes,
+                    },
+                );
+            }
+        }
+        // read the idempotency page; registries written before it was introduced don't have it
+        let mut idempotency_page = None;
+        if data.len() >= offset + 5 {
+            let page = Page::from_le_bytes(data[offset + 1..offset + 5].try_into()?);
+            idempotency_page = (data[offset] != 0).then_some(page);
+            offset += 5;
+        }
+        // read schema versions and migrations; registries written before migrations were introduced don't have them
+        let mut schema_versions = HashMap::new();
+        if data.len() >= offset + 8 {
+            let len = u64::from_le_bytes(data[offset..offset + 8].try_into()?) as usize;
+            offset += 8;
+            schema_versions.reserve(len);
+            for _ in 0..len {
+                let fingerprint = u64::from_le_bytes(data[offset..offset + 8].try_into()?);
+                let version = u32::from_le_bytes(data[offset + 8..offset + 12].try_into()?);
+                offset += 12;
+                schema_versions.insert(fingerprint, SchemaVersion(version));
+            }
+        }
+        let mut migrations = HashMap::new();
+        if data.len() >= offset + 8 {
+            let len = u64::from_le_bytes(data[offset..offset + 8].try_into()?) as usize;
+            offset += 8;
+            migrations.reserve(len);
+            for _ in 0..len {
+                let fingerprint = u64::from_le_bytes(data[offset..offset + 8].try_into()?);
+                let old_schema_version = SchemaVersion(u32::from_le_bytes(
+                    data[offset + 9..offset + 13].try_into()?,
+                ));
+                let new_schema_version = SchemaVersion(u32::from_le_bytes(
+                    data[offset + 13..offset + 17].try_into()?,
+                ));
+                let phase = MigrationPhase::from_tag(
+                    data[offset + 8],
+                    old_schema_version,
+                    new_schema_version,
+                )?;
+                offset += 17;
+                migrations.insert(fingerprint, phase);
+            }
+        }
+        // read the index registry from its page; registries written before indexes were tracked don't have it
+        let mut index_registry_page = None;
+        if data.len() >= offset + 5 {
+            if data[offset] != 0 {
+                index_registry_page = Some(Page::from_le_bytes(
+                    data[offset + 1..offset + 5].try_into()?,
+                ));
+            }
+            offset += 5;
+        }
+        #[cfg(feature = "indexes")]
+        let index_registry = index_registry_page
+            .map(IndexRegistry::load)
+            .transpose()?
+            .unwrap_or_default();
+        // read the database version; registries written before migrations were run are at version 0
+        let mut database_version = 0;
+        if data.len() >= offset + 4 {
+            database_version = u32::from_le_bytes(data[offset..offset + 4].try_into()?);
+            offset += 4;
+        }
+        // read the table names; registries written before names were tracked don't have them
+        let mut names = HashMap::new();
+        if data.len() >= offset + 8 {
+            let len = u64::from_le_bytes(data[offset..offset + 8].try_into()?) as usize;
+            offset += 8;
+            names.reserve(len);
+            for _ in 0..len {
+                let fingerprint = u64::from_le_bytes(data[offset..offset + 8].try_into()?);
+                let name_len =
+                    u16::from_le_bytes(data[offset + 8..offset + 10].try_into()?) as usize;
+                offset += 10;
+                let name = String::from_utf8(data[offset..offset + name_len].to_vec())?;
+                offset += name_len;
+                names.insert(fingerprint, name);
+            }
+        }
+        // read the table layouts; registries written before layouts were tracked don't have them
+        let mut layouts = HashMap::new();
+        if data.len() >= offset + 8 {
+            let len = u64::from_le_bytes(data[offset..offset + 8].try_into()?) as usize;
+            offset += 8;
+            layouts.reserve(len);
+            for _ in 0..len {
+                let fingerprint = u64::from_le_bytes(data[offset..offset + 8].try_into()?);
+                let count = u16::from_le_bytes(data[offset + 8..offset + 10].try_into()?) as usize;
+                offset += 10;
+                let mut columns = Vec::with_capacity(count);
+                for _ in 0..count {
+                    let column = ColumnLayout::decode(std::borrow::Cow::Borrowed(&data[offset..]))?;
+                    offset += column.size() as usize;
+                    columns.push(column);
+                }
+                layouts.insert(fingerprint, columns);
+            }
+        }
+        // read the sequence registry from its page; registries written before auto-increment columns
+        // were introduced don't have it
+        let mut sequences_page = None;
+        if data.len() >= offset + 5 {
+            if data[offset] != 0 {
+                sequences_page = Some(Page::from_le_bytes(
+                    data[offset + 1..offset + 5].try_into()?,
+                ));
+            }
+            offset += 5;
+        }
+        let sequences = sequences_page
+            .map(SequenceRegistry::load)
+            .transpose()?
+            .unwrap_or_default();
+        // read the tables with stale indexes; registries written before they were tracked don't have them
+        let mut stale_indexes = HashSet::new();
+        if data.len() >= offset + 8 {
+            let len = u64::from_le_bytes(data[offset..offset + 8].try_into()?) as usize;
+            offset += 8;
+            stale_indexes.reserve(len);
+            for _ in 0..len {
+                stale_indexes.insert(u64::from_le_bytes(data[offset..offset + 8].try_into()?));
+                offset += 8;
+            }
+        }
+        Ok(Self {
+            tables,
+            columns,
+            quotas,
+            idempotency_page,
+            schema_versions,
+            migrations,
+            #[cfg(feature = "indexes")]
+            index_registry,
+            #[cfg(not(feature = "indexes"))]
+            index_registry_page,
+            database_version,
+            names,
+            layouts,
+            sequences,
+            stale_indexes,
+        })
     }
 }
 
@@ -134,8 +1178,8 @@ impl Encode for SchemaRegistry {
 mod tests {
 
     use ic_dbms_api::prelude::{
-        ColumnDef, IcDbmsResult, InsertRecord, NoForeignFetcher, TableColumns, TableRecord,
-        UpdateRecord,
+        ColumnDef, IcDbmsResult, IndexDef, IndexType, InsertRecord, NoForeignFetcher, TableColumns,
+        TableRecord, UpdateRecord,
     };
 
     use super::*;
@@ -191,6 +1235,149 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_should_store_columns_fingerprint() {
+        let mut registry = SchemaRegistry::default();
+        registry
+            .register_table::<User>()
+            .expect("failed to register table");
+
+        assert_eq!(
+            registry.columns_fingerprint(User::fingerprint()),
+            Some(User::columns_fingerprint())
+        );
+        let reloaded = SchemaRegistry::load().expect("failed to reload schema registry");
+        assert_eq!(
+            reloaded.columns_fingerprint(User::fingerprint()),
+            Some(User::columns_fingerprint())
+        );
+    }
+
+    #[test]
+    fn test_should_store_sequence_values() {
+        let mut registry = SchemaRegistry::default();
+        registry
+            .register_table::<User>()
+            .expect("failed to register table");
+        assert_eq!(registry.sequence_value(User::fingerprint()), None);
+
+        for expected in 1..=3 {
+            assert_eq!(
+                registry
+                    .next_sequence_value(User::fingerprint())
+                    .expect("failed to advance sequence"),
+                expected
+            );
+        }
+
+        let reloaded = SchemaRegistry::load().expect("failed to reload schema registry");
+        assert_eq!(reloaded.sequence_value(User::fingerprint()), Some(3));
+        assert_eq!(reloaded, registry);
+
+        registry
+            .deregister_table::<User>()
+            .expect("failed to deregister table");
+        assert_eq!(registry.sequence_value(User::fingerprint()), None);
+    }
+
+    #[test]
+    fn test_should_decode_schema_registry_without_columns() {
+        let mut registry = SchemaRegistry::default();
+        registry
+            .register_fingerprint(User::fingerprint())
+            .expect("failed to register table");
+
+        // encode without the columns, quotas, idempotency, schema versions, migrations, index,
+        // database version and names sections, as written by previous versions
+        let encoded = registry.encode();
+        let legacy = encoded[..encoded.len() - 54].to_vec();
+        let decoded =
+            SchemaRegistry::decode(std::borrow::Cow::Owned(legacy)).expect("failed to decode");
+        assert_eq!(decoded, registry);
+    }
+
+    #[test]
+    fn test_should_store_table_quota() {
+        let mut registry = SchemaRegistry::default();
+        registry
+            .register_table::<User>()
+            .expect("failed to register table");
+        let quota = TableQuota {
+            max_pages: Some(4),
+            max_rows: None,
+            max_bytes: Some(1024),
+        };
+        registry
+            .set_table_quota(User::fingerprint(), quota)
+            .expect("failed to set quota");
+        assert_eq!(registry.table_quota(User::fingerprint()), Some(quota));
+
+        let reloaded = SchemaRegistry::load().expect("failed to reload schema registry");
+        assert_eq!(reloaded.table_quota(User::fingerprint()), Some(quota));
+        assert_eq!(reloaded, registry);
+
+        // unlimited quota removes it
+        registry
+            .set_table_quota(User::fingerprint(), TableQuota::default())
+            .expect("failed to remove quota");
+        assert_eq!(registry.table_quota(User::fingerprint()), None);
+    }
+
+    #[test]
+    fn test_should_store_migration_phase() {
+        let mut registry = SchemaRegistry::default();
+        registry
+            .register_table::<User>()
+            .expect("failed to register table");
+        assert_eq!(
+            registry.schema_version(User::fingerprint()),
+            INITIAL_SCHEMA_VERSION
+        );
+
+        let phase = MigrationPhase::CutOver {
+            old_schema_version: SchemaVersion(2),
+            new_schema_version: SchemaVersion(3),
+        };
+        registry
+            .set_schema_version(User::fingerprint(), SchemaVersion(2))
+            .expect("failed to set schema version");
+        registry
+            .set_migration_phase(User::fingerprint(), Some(phase))
+            .expect("failed to set migration phase");
+
+        let reloaded = SchemaRegistry::load().expect("failed to reload schema registry");
+        assert_eq!(reloaded.migration_phase(User::fingerprint()), Some(phase));
+        assert_eq!(
+            reloaded.schema_version(User::fingerprint()),
+            SchemaVersion(2)
+        );
+        assert_eq!(reloaded, registry);
+
+        registry
+            .set_migration_phase(User::fingerprint(), None)
+            .expect("failed to end migration");
+        assert_eq!(registry.migration_phase(User::fingerprint()), None);
+    }
+
+    #[test]
+    fn test_should_allocate_idempotency_page_once() {
+        let mut registry = SchemaRegistry::default();
+        assert_eq!(registry.idempotency_page(), None);
+
+        let page = registry
+            .get_or_allocate_idempotency_page()
+            .expect("failed to allocate page");
+        assert_eq!(
+            registry
+                .get_or_allocate_idempotency_page()
+                .expect("failed to get page"),
+            page
+        );
+
+        let reloaded = SchemaRegistry::load().expect("failed to reload schema registry");
+        assert_eq!(reloaded.idempotency_page(), Some(page));
+    }
+
     #[test]
     fn test_should_not_register_same_table_twice() {
         let mut registry = SchemaRegistry::default();
@@ -206,6 +1393,471 @@ mod tests {
         assert_eq!(registry.tables.len(), 1);
     }
 
+    #[test]
+    fn test_should_estimate_table_capacity() {
+        let mut registry = SchemaRegistry::default();
+        assert_eq!(
+            registry
+                .estimated_table_capacity::<User>()
+                .expect("failed to estimate capacity"),
+            0
+        );
+
+        let pages = registry
+            .register_table::<User>()
+            .expect("failed to register table");
+        let mut table_registry = TableRegistry::load(pages).expect("failed to load table registry");
+        table_registry
+            .insert(User {
+                id: 1.into(),
+                name: "Alice".to_string().into(),
+            })
+            .expect("failed to insert record");
+
+        let record_size = TableRegistry::estimate_max_record_size(User::columns()) as u64;
+        let page_size = MEMORY_MANAGER.with_borrow(|mm| mm.page_size());
+        assert_eq!(
+            registry
+                .estimated_table_capacity::<User>()
+                .expect("failed to estimate capacity"),
+            page_size / record_size
+        );
+    }
+
+    #[test]
+    fn test_should_swap_table_with_its_clone() {
+        let mut registry = SchemaRegistry::default();
+        let user_pages = registry
+            .register_table::<User>()
+            .expect("failed to register table");
+        let quota = TableQuota {
+            max_pages: Some(4),
+            max_rows: None,
+            max_bytes: None,
+        };
+        registry
+            .set_table_quota(User::fingerprint(), quota)
+            .expect("failed to set quota");
+        registry
+            .next_sequence_value(User::fingerprint())
+            .expect("failed to advance sequence");
+
+        let clone = User::fingerprint() ^ 1;
+        let clone_pages = registry
+            .register_clone::<User>(clone, "users_backup")
+            .expect("failed to register clone")
+            .expect("clone should be registered");
+        assert_eq!(registry.table_name(clone), Some("users_backup"));
+        assert_eq!(registry.table_quota(clone), None);
+        assert_eq!(registry.sequence_value(clone), Some(1));
+        assert_eq!(
+            registry.table_columns(clone),
+            registry.table_columns(User::fingerprint())
+        );
+        // the name and the fingerprint are taken
+        assert!(
+            registry
+                .register_clone::<User>(clone ^ 2, "users_backup")
+                .expect("failed to register clone")
+                .is_none()
+        );
+        assert!(
+            registry
+                .register_clone::<User>(clone, "users_copy")
+                .expect("failed to register clone")
+                .is_none()
+        );
+
+        registry
+            .next_sequence_value(User::fingerprint())
+            .expect("failed to advance sequence");
+        assert!(
+            registry
+                .swap_tables(User::fingerprint(), clone)
+                .expect("failed to swap tables")
+        );
+        assert_eq!(registry.table_registry_page::<User>(), Some(clone_pages));
+        assert_eq!(
+            registry.table_registry_page_by_fingerprint(clone),
+            Some(user_pages)
+        );
+        assert_eq!(
+            registry.table_name(User::fingerprint()),
+            Some("users_backup")
+        );
+        assert_eq!(registry.table_name(clone), Some("users"));
+        assert_eq!(registry.table_quota(User::fingerprint()), None);
+        assert_eq!(registry.table_quota(clone), Some(quota));
+        assert_eq!(registry.sequence_value(User::fingerprint()), Some(1));
+        assert_eq!(registry.sequence_value(clone), Some(2));
+        let reloaded = SchemaRegistry::load().expect("failed to reload schema registry");
+        assert_eq!(registry, reloaded);
+
+        // swapping them again restores them
+        assert!(
+            registry
+                .swap_tables(clone, User::fingerprint())
+                .expect("failed to swap tables")
+        );
+        assert_eq!(registry.table_registry_page::<User>(), Some(user_pages));
+        assert_eq!(registry.table_name(User::fingerprint()), Some("users"));
+        assert_eq!(registry.table_quota(User::fingerprint()), Some(quota));
+        assert_eq!(registry.sequence_value(User::fingerprint()), Some(2));
+    }
+
+    #[test]
+    fn test_should_swap_tables() {
+        let mut registry = SchemaRegistry::default();
+
+        let user_pages = registry
+            .register_table::<User>()
+            .expect("failed to register table");
+        let another_pages = registry
+            .register_table::<AnotherTable>()
+            .expect("failed to register table");
+
+        assert!(
+            registry
+                .swap_table_pages(User::fingerprint(), AnotherTable::fingerprint())
+                .expect("failed to swap tables")
+        );
+        assert_eq!(registry.table_registry_page::<User>(), Some(another_pages));
+        assert_eq!(
+            registry.table_registry_page::<AnotherTable>(),
+            Some(user_pages)
+        );
+
+        // should be persisted
+        let reloaded = SchemaRegistry::load().expect("failed to reload schema registry");
+        assert_eq!(registry, reloaded);
+    }
+
+    #[cfg(feature = "indexes")]
+    #[test]
+    fn test_should_create_index_on_stored_records() {
+        let mut registry = SchemaRegistry::default();
+        let pages = registry
+            .register_table::<User>()
+            .expect("failed to register table");
+        let mut table_registry = TableRegistry::load(pages).expect("failed to load table");
+        for (id, name) in [(1u32, "alice"), (2, "bob")] {
+            table_registry
+                .insert(User {
+                    id: id.into(),
+                    name: name.to_string().into(),
+                })
+                .expect("failed to insert");
+        }
+
+        let id = registry
+            .create_index::<User>("name")
+            .expect("failed to create index");
+        let indexes = registry
+            .index_registry()
+            .indexes_for_table(User::fingerprint());
+        assert_eq!(indexes.len(), 1);
+        assert_eq!(indexes[0].id, id);
+        assert_eq!(indexes[0].column, "name");
+        assert_eq!(indexes[0].index_type, IndexType::BTree);
+        let index = BTreeIndex::load(&indexes[0].pages).expect("failed to load index");
+        assert_eq!(index.len().expect("failed to read index"), 2);
+        assert_eq!(
+            index
+                .lookup(&ic_dbms_api::prelude::Value::Text("bob".to_string().into()))
+                .expect("failed to lookup index")[0]
+                .pk,
+            ic_dbms_api::prelude::Value::Uint32(2u32.into())
+        );
+
+        // creating it again returns the existing index
+        assert_eq!(
+            registry
+                .create_index::<User>("name")
+                .expect("failed to create index"),
+            id
+        );
+        let reloaded = SchemaRegistry::load().expect("failed to reload schema registry");
+        assert_eq!(reloaded, registry);
+    }
+
+    #[cfg(feature = "indexes")]
+    #[test]
+    fn test_should_rebuild_stale_indexes() {
+        let mut registry = SchemaRegistry::default();
+        let pages = registry
+            .register_table::<User>()
+            .expect("failed to register table");
+        registry
+            .create_index::<User>("name")
+            .expect("failed to create index");
+
+        // records written by a build without the `indexes` feature are not indexed
+        let mut table_registry = TableRegistry::load(pages).expect("failed to load table");
+        table_registry
+            .insert(User {
+                id: 1u32.into(),
+                name: "alice".to_string().into(),
+            })
+            .expect("failed to insert");
+        registry.stale_indexes.insert(User::fingerprint());
+        registry.save().expect("failed to save schema registry");
+        let reloaded = SchemaRegistry::load().expect("failed to reload schema registry");
+        assert_eq!(reloaded, registry);
+
+        assert!(
+            registry
+                .rebuild_stale_indexes::<User>()
+                .expect("failed to rebuild indexes")
+        );
+        let indexes = registry
+            .index_registry()
+            .indexes_for_table(User::fingerprint());
+        let index = BTreeIndex::load(&indexes[0].pages).expect("failed to load index");
+        assert_eq!(index.len().expect("failed to read index"), 1);
+        assert!(registry.stale_indexes.is_empty());
+        assert!(
+            !registry
+                .rebuild_stale_indexes::<User>()
+                .expect("failed to rebuild indexes")
+        );
+        let reloaded = SchemaRegistry::load().expect("failed to reload schema registry");
+        assert_eq!(reloaded, registry);
+    }
+
+    #[cfg(not(feature = "indexes"))]
+    #[test]
+    fn test_should_mark_indexes_stale() {
+        let mut registry = SchemaRegistry::default();
+        registry
+            .register_table::<User>()
+            .expect("failed to register table");
+
+        // no index has ever been created
+        registry
+            .mark_indexes_stale(User::fingerprint())
+            .expect("failed to mark indexes stale");
+        assert!(registry.stale_indexes.is_empty());
+
+        let page = MEMORY_MANAGER
+            .with_borrow_mut(|m| m.allocate_page())
+            .expect("failed to allocate page");
+        registry.index_registry_page = Some(page);
+        registry
+            .mark_indexes_stale(User::fingerprint())
+            .expect("failed to mark indexes stale");
+        assert!(registry.stale_indexes.contains(&User::fingerprint()));
+        let reloaded = SchemaRegistry::load().expect("failed to reload schema registry");
+        assert_eq!(reloaded, registry);
+    }
+
+    #[cfg(feature = "indexes")]
+    #[test]
+    fn test_should_register_and_drop_table_indexes() {
+        let mut registry = SchemaRegistry::default();
+        registry
+            .register_table::<User>()
+            .expect("failed to register table");
+        registry
+            .register_table::<AnotherTable>()
+            .expect("failed to register table");
+
+        let indexes = registry
+            .index_registry()
+            .indexes_for_table(AnotherTable::fingerprint());
+        assert_eq!(indexes.len(), 2);
+        assert_eq!(indexes[0].column, "name");
+        assert_eq!(indexes[0].index_type, IndexType::Hash);
+        assert_eq!(indexes[1].column, "start_date,end_date");
+        assert_eq!(indexes[1].index_type, IndexType::BTree);
+        assert_ne!(indexes[0].pages, indexes[1].pages);
+        assert!(
+            registry
+                .index_registry()
+                .indexes_for_table(User::fingerprint())
+                .is_empty()
+        );
+
+        // registering again doesn't create the indexes twice
+        registry
+            .register_table::<AnotherTable>()
+            .expect("failed to register table");
+        let reloaded = SchemaRegistry::load().expect("failed to reload schema registry");
+        assert_eq!(
+            reloaded
+                .index_registry()
+                .indexes_for_table(AnotherTable::fingerprint()),
+            indexes
+        );
+
+        assert!(
+            registry
+                .deregister_table::<AnotherTable>()
+                .expect("failed to deregister table")
+        );
+        assert!(
+            registry
+                .index_registry()
+                .indexes_for_table(AnotherTable::fingerprint())
+                .is_empty()
+        );
+        assert!(registry.table_registry_page::<AnotherTable>().is_none());
+        let reloaded = SchemaRegistry::load().expect("failed to reload schema registry");
+        assert_eq!(reloaded, registry);
+        assert!(
+            !registry
+                .deregister_table::<AnotherTable>()
+                .expect("failed to deregister table")
+        );
+    }
+
+    #[test]
+    fn test_should_rename_table() {
+        let mut registry = SchemaRegistry::default();
+        let user_pages = registry
+            .register_table::<User>()
+            .expect("failed to register table");
+        registry
+            .register_table::<AnotherTable>()
+            .expect("failed to register table");
+        assert_eq!(registry.table_name(User::fingerprint()), Some("users"));
+
+        assert!(
+            registry
+                .rename_table("users", "members")
+                .expect("failed to rename table")
+        );
+        assert_eq!(registry.table_name(User::fingerprint()), Some("members"));
+        assert_eq!(
+            registry.table_registry_page_by_name("members"),
+            Some(user_pages)
+        );
+        assert!(registry.table_registry_page_by_name("users").is_none());
+        assert_eq!(registry.table_registry_page::<User>(), Some(user_pages));
+
+        // registering again keeps the new name
+        registry
+            .register_table::<User>()
+            .expect("failed to register table");
+        assert_eq!(registry.table_name(User::fingerprint()), Some("members"));
+
+        // should be persisted
+        let reloaded = SchemaRegistry::load().expect("failed to reload schema registry");
+        assert_eq!(registry, reloaded);
+    }
+
+    #[test]
+    fn test_should_not_rename_unregistered_table_or_to_taken_name() {
+        let mut registry = SchemaRegistry::default();
+        registry
+            .register_table::<User>()
+            .expect("failed to register table");
+        registry
+            .register_table::<AnotherTable>()
+            .expect("failed to register table");
+
+        assert!(
+            !registry
+                .rename_table("unknown", "members")
+                .expect("failed to rename table")
+        );
+        assert!(
+            !registry
+                .rename_table("users", AnotherTable::table_name())
+                .expect("failed to rename table")
+        );
+        assert_eq!(registry.table_name(User::fingerprint()), Some("users"));
+    }
+
+    #[test]
+    fn test_should_alter_table() {
+        let mut registry = SchemaRegistry::default();
+        registry
+            .register_table::<User>()
+            .expect("failed to register table");
+        let layout =
+            |columns: &[ColumnDef]| columns.iter().map(ColumnLayout::from).collect::<Vec<_>>();
+        assert_eq!(
+            registry.table_columns(User::fingerprint()),
+            Some(layout(User::columns()).as_slice())
+        );
+
+        const EMAIL: ColumnDef = ColumnDef::builder()
+            .name("email")
+            .data_type(ic_dbms_api::prelude::DataTypeKind::Text)
+            .nullable()
+            .build();
+        assert!(
+            registry
+                .alter_table(User::fingerprint(), AlterTableOp::AddColumn(EMAIL))
+                .expect("failed to alter table")
+        );
+        let mut columns = User::columns().to_vec();
+        columns.push(EMAIL);
+        assert_eq!(
+            registry.table_columns(User::fingerprint()),
+            Some(layout(&columns).as_slice())
+        );
+        assert_eq!(
+            registry.columns_fingerprint(User::fingerprint()),
+            Some(columns_fingerprint(&columns))
+        );
+        assert_eq!(
+            registry.schema_version(User::fingerprint()),
+            INITIAL_SCHEMA_VERSION.next()
+        );
+
+        // failed operations leave the registry as is
+        assert!(
+            !registry
+                .alter_table(User::fingerprint(), AlterTableOp::DropColumn("unknown"))
+                .expect("failed to alter table")
+        );
+        assert!(
+            !registry
+                .alter_table(
+                    AnotherTable::fingerprint(),
+                    AlterTableOp::DropColumn("email")
+                )
+                .expect("failed to alter table")
+        );
+        assert_eq!(
+            registry.schema_version(User::fingerprint()),
+            INITIAL_SCHEMA_VERSION.next()
+        );
+
+        // registering again keeps the altered columns
+        registry
+            .register_table::<User>()
+            .expect("failed to register table");
+        let reloaded = SchemaRegistry::load().expect("failed to reload schema registry");
+        assert_eq!(
+            reloaded.table_columns(User::fingerprint()),
+            Some(layout(&columns).as_slice())
+        );
+        assert_eq!(registry, reloaded);
+    }
+
+    #[test]
+    fn test_should_not_swap_unregistered_tables() {
+        let mut registry = SchemaRegistry::default();
+
+        let user_pages = registry
+            .register_table::<User>()
+            .expect("failed to register table");
+
+        assert!(
+            !registry
+                .swap_tables(User::fingerprint(), AnotherTable::fingerprint())
+                .expect("failed to swap tables")
+        );
+        assert!(
+            !registry
+                .swap_table_pages(User::fingerprint(), AnotherTable::fingerprint())
+                .expect("failed to swap tables")
+        );
+        assert_eq!(registry.table_registry_page::<User>(), Some(user_pages));
+    }
+
     #[derive(Clone)]
     struct AnotherTable;
 
@@ -257,8 +1909,8 @@ mod tests {
             vec![]
         }
 
-        fn into_record(self) -> Self::Schema {
-            AnotherTable
+        fn into_record(self) -> IcDbmsResult<Self::Schema> {
+            Ok(AnotherTable)
         }
     }
 
@@ -298,8 +1950,27 @@ mod tests {
             &[]
         }
 
-        fn primary_key() -> &'static str {
-            ""
+        fn primary_keys() -> &'static [&'static str] {
+            &[""]
+        }
+
+        fn indexes() -> &'static [IndexDef] {
+            &[
+                IndexDef {
+                    name: "idx_another_table_name",
+                    columns: &["name"],
+                    unique: false,
+                    auto_increment: false,
+                    index_type: IndexType::Hash,
+                },
+                IndexDef {
+                    name: "idx_another_table_dates",
+                    columns: &["start_date", "end_date"],
+                    unique: false,
+                    auto_increment: false,
+                    index_type: IndexType::BTree,
+                },
+            ]
         }
 
         fn to_values(self) -> Vec<(ColumnDef, ic_dbms_api::prelude::Value)> {
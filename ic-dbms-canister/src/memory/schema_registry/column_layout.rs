@@ -0,0 +1,391 @@
+use ic_dbms_api::prelude::{ColumnDef, ColumnsFingerprint, DataTypeKind, DecodeError};
+
+use crate::memory::{DataSize, Encode, MSize, MemoryError, MemoryResult};
+
+/// Flag set when the column is nullable.
+const NULLABLE_FLAG: u8 = 0b0001;
+/// Flag set when the column is the primary key.
+const PRIMARY_KEY_FLAG: u8 = 0b0010;
+/// Flag set when the column has a foreign key.
+const FOREIGN_KEY_FLAG: u8 = 0b0100;
+/// Flag set when the column has a check constraint.
+const CHECK_FLAG: u8 = 0b1000;
+/// Flag of the data type tag set when the column is an array of the type of the other bits.
+const ARRAY_TAG_FLAG: u8 = 0b1000_0000;
+
+/// A column of a table, as stored by the [`super::SchemaRegistry`].
+///
+/// Unlike a [`ColumnDef`], it owns its strings, so it can be decoded from memory;
+/// it keeps the properties hashed by [`ic_dbms_api::prelude::columns_fingerprint`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnLayout {
+    /// The name of the column.
+    pub name: String,
+    /// The data type of the column.
+    pub data_type: DataTypeKind,
+    /// Whether the column can contain NULL values.
+    pub nullable: bool,
+    /// Whether the column is the primary key.
+    pub primary_key: bool,
+    /// Local column, foreign table and foreign column of the foreign key, if any.
+    pub foreign_key: Option<(String, String, String)>,
+    /// Description of the check constraint, if any.
+    pub check: Option<String>,
+}
+
+impl From<&ColumnDef> for ColumnLayout {
+    fn from(column: &ColumnDef) -> Self {
+        Self {
+            name: column.name.to_string(),
+            data_type: column.data_type,
+            nullable: column.nullable,
+            primary_key: column.primary_key,
+            foreign_key: column.foreign_key.map(|fk| {
+                (
+                    fk.local_column.to_string(),
+                    fk.foreign_table.to_string(),
+                    fk.foreign_column.to_string(),
+                )
+            }),
+            check: column.check.map(|check| check.description.to_string()),
+        }
+    }
+}
+
+/// Computes the fingerprint of the given columns, which equals the [`ic_dbms_api::prelude::columns_fingerprint`]
+/// of the [`ColumnDef`]s they've been made from.
+pub fn layout_fingerprint(columns: &[ColumnLayout]) -> u64 {
+    let mut fingerprint = ColumnsFingerprint::default();
+    for column in columns {
+        fingerprint.column(
+            &column.name,
+            column.data_type,
+            column.nullable,
+            column.primary_key,
+        );
+        if let Some((local_column, foreign_table, foreign_column)) = &column.foreign_key {
+            fingerprint.foreign_key(local_column, foreign_table, foreign_column);
+        }
+        if let Some(check) = &column.check {
+            fingerprint.check(check);
+        }
+    }
+
+    fingerprint.finish()
+}
+
+/// A change to the columns of a table, applied by [`super::SchemaRegistry::alter_table`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AlterTableOp {
+    /// Adds the column after the existing ones.
+    AddColumn(ColumnDef),
+    /// Renames the column `from` to `to`.
+    RenameColumn {
+        from: &'static str,
+        to: &'static str,
+    },
+    /// Drops the column with the given name; the primary key can't be dropped.
+    DropColumn(&'static str),
+}
+
+impl AlterTableOp {
+    /// Applies the operation to the given columns.
+    ///
+    /// Returns `false`, leaving the columns as they are, if the operation refers to a missing column,
+    /// would make two columns with the same name or would drop the primary key.
+    pub fn apply(&self, columns: &mut Vec<ColumnLayout>) -> bool {
+        let position = |name: &str| columns.iter().position(|column| column.name == name);
+        match self {
+            AlterTableOp::AddColumn(column) => {
+                if position(column.name).is_some() {
+                    return false;
+                }
+                columns.push(ColumnLayout::from(column));
+            }
+            AlterTableOp::RenameColumn { from, to } => {
+                let (Some(index), None) = (position(*from), position(*to)) else {
+                    return false;
+                };
+                let column = &mut columns[index];
+                column.name = to.to_string();
+                // the foreign key refers to the column by name as well
+                if let Some((local_column, _, _)) = column
+                    .foreign_key
+                    .as_mut()
+                    .filter(|(local_column, _, _)| local_column == from)
+                {
+                    *local_column = to.to_string();
+                }
+            }
+            AlterTableOp::DropColumn(name) => {
+                let Some(index) = position(*name).filter(|index| !columns[*index].primary_key)
+                else {
+                    return false;
+                };
+                columns.remove(index);
+            }
+        }
+
+        true
+    }
+}
+
+impl Encode for ColumnLayout {
+    const SIZE: DataSize = DataSize::Dynamic;
+
+    fn size(&self) -> MSize {
+        // 2 bytes length prefix for each string + 1 byte for the data type + 1 byte for the flags
+        let string_size = |s: &str| 2 + s.len() as MSize;
+        string_size(&self.name)
+            + 1
+            + 1
+            + self
+                .foreign_key
+                .as_ref()
+                .map(|(local_column, foreign_table, foreign_column)| {
+                    string_size(local_column)
+                        + string_size(foreign_table)
+                        + string_size(foreign_column)
+                })
+                .unwrap_or_default()
+            + self.check.as_deref().map(string_size).unwrap_or_default()
+    }
+
+    fn encode(&'_ self) -> std::borrow::Cow<'_, [u8]> {
+        let mut buffer = Vec::with_capacity(self.size() as usize);
+        let write_string = |buffer: &mut Vec<u8>, s: &str| {
+            buffer.extend_from_slice(&(s.len() as u16).to_le_bytes());
+            buffer.extend_from_slice(s.as_bytes());
+        };
+
+        write_string(&mut buffer, &self.name);
+        buffer.push(data_type_tag(self.data_type));
+        let mut flags = 0;
+        if self.nullable {
+            flags |= NULLABLE_FLAG;
+        }
+        if self.primary_key {
+            flags |= PRIMARY_KEY_FLAG;
+        }
+        if self.foreign_key.is_some() {
+            flags |= FOREIGN_KEY_FLAG;
+        }
+        if self.check.is_some() {
+            flags |= CHECK_FLAG;
+        }
+        buffer.push(flags);
+        if let Some((local_column, foreign_table, foreign_column)) = &self.foreign_key {
+            write_string(&mut buffer, local_column);
+            write_string(&mut buffer, foreign_table);
+            write_string(&mut buffer, foreign_column);
+        }
+        if let Some(check) = &self.check {
+            write_string(&mut buffer, check);
+        }
+
+        std::borrow::Cow::Owned(buffer)
+    }
+
+    fn decode(data: std::borrow::Cow<[u8]>) -> MemoryResult<Self>
+    where
+        Self: Sized,
+    {
+        let mut offset = 0;
+        let read_string = |offset: &mut usize| -> MemoryResult<String> {
+            let len = u16::from_le_bytes(
+                data.get(*offset..*offset + 2)
+                    .ok_or(MemoryError::DecodeError(DecodeError::TooShort))?
+                    .try_into()?,
+            ) as usize;
+            let bytes = data
+                .get(*offset + 2..*offset + 2 + len)
+                .ok_or(MemoryError::DecodeError(DecodeError::TooShort))?;
+            *offset += 2 + len;
+            Ok(String::from_utf8(bytes.to_vec())?)
+        };
+
+        let name = read_string(&mut offset)?;
+        let (tag, flags) = match data.get(offset..offset + 2) {
+            Some(bytes) => (bytes[0], bytes[1]),
+            None => return Err(MemoryError::DecodeError(DecodeError::TooShort)),
+        };
+        offset += 2;
+        let foreign_key = if flags & FOREIGN_KEY_FLAG != 0 {
+            Some((
+                read_string(&mut offset)?,
+                read_string(&mut offset)?,
+                read_string(&mut offset)?,
+            ))
+        } else {
+            None
+        };
+        let check = if flags & CHECK_FLAG != 0 {
+            Some(read_string(&mut offset)?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            name,
+            data_type: data_type_from_tag(tag)?,
+            nullable: flags & NULLABLE_FLAG != 0,
+            primary_key: flags & PRIMARY_KEY_FLAG != 0,
+            foreign_key,
+            check,
+        })
+    }
+}
+
+/// Returns the tag encoding the given [`DataTypeKind`].
+fn data_type_tag(data_type: DataTypeKind) -> u8 {
+    match data_type {
+        DataTypeKind::Array(element) => ARRAY_TAG_FLAG | data_type_tag(*element),
+        DataTypeKind::Blob => 0,
+        DataTypeKind::Boolean => 1,
+        DataTypeKind::Date => 2,
+        DataTypeKind::DateTime => 3,
+        DataTypeKind::Decimal => 4,
+        DataTypeKind::Float64 => 12,
+        DataTypeKind::Int32 => 5,
+        DataTypeKind::Int64 => 6,
+        DataTypeKind::Principal => 7,
+        DataTypeKind::Text => 8,
+        DataTypeKind::Uint32 => 9,
+        DataTypeKind::Uint64 => 10,
+        DataTypeKind::Uuid => 11,
+        DataTypeKind::AutoIncrement => 13,
+    }
+}
+
+/// Returns the [`DataTypeKind`] encoded by the given tag.
+fn data_type_from_tag(tag: u8) -> MemoryResult<DataTypeKind> {
+    if tag & ARRAY_TAG_FLAG != 0 {
+        return DataTypeKind::array_of(data_type_from_tag(tag & !ARRAY_TAG_FLAG)?).ok_or(
+            MemoryError::DecodeError(DecodeError::UnsupportedFormatVersion(tag)),
+        );
+    }
+
+    match tag {
+        0 => Ok(DataTypeKind::Blob),
+        1 => Ok(DataTypeKind::Boolean),
+        2 => Ok(DataTypeKind::Date),
+        3 => Ok(DataTypeKind::DateTime),
+        4 => Ok(DataTypeKind::Decimal),
+        5 => Ok(DataTypeKind::Int32),
+        6 => Ok(DataTypeKind::Int64),
+        7 => Ok(DataTypeKind::Principal),
+        8 => Ok(DataTypeKind::Text),
+        9 => Ok(DataTypeKind::Uint32),
+        10 => Ok(DataTypeKind::Uint64),
+        11 => Ok(DataTypeKind::Uuid),
+        12 => Ok(DataTypeKind::Float64),
+        13 => Ok(DataTypeKind::AutoIncrement),
+        // a type introduced by a newer version of the registry
+        tag => Err(MemoryError::DecodeError(
+            DecodeError::UnsupportedFormatVersion(tag),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use ic_dbms_api::prelude::{TableSchema as _, columns_fingerprint};
+
+    use super::*;
+    use crate::tests::{Post, Ticket, User};
+
+    #[test]
+    fn test_should_encode_and_decode_column_layout() {
+        for column in User::columns()
+            .iter()
+            .chain(Post::columns())
+            .chain(Ticket::columns())
+        {
+            let layout = ColumnLayout::from(column);
+            let encoded = layout.encode();
+            assert_eq!(encoded.len(), layout.size() as usize);
+            let decoded = ColumnLayout::decode(encoded).expect("failed to decode");
+            assert_eq!(decoded, layout);
+        }
+    }
+
+    #[test]
+    fn test_should_encode_and_decode_array_column_layout() {
+        let column = ColumnDef::builder()
+            .name("tags")
+            .data_type(DataTypeKind::Array(&DataTypeKind::Uint32))
+            .nullable()
+            .build();
+        let layout = ColumnLayout::from(&column);
+        let decoded = ColumnLayout::decode(layout.encode()).expect("failed to decode");
+        assert_eq!(decoded, layout);
+        assert!(matches!(
+            data_type_from_tag(ARRAY_TAG_FLAG | 0x7f),
+            Err(MemoryError::DecodeError(
+                DecodeError::UnsupportedFormatVersion(0x7f)
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_should_compute_layout_fingerprint() {
+        let layouts = Post::columns()
+            .iter()
+            .map(ColumnLayout::from)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            layout_fingerprint(&layouts),
+            columns_fingerprint(Post::columns())
+        );
+    }
+
+    #[test]
+    fn test_should_apply_alter_table_ops() {
+        let mut columns = User::columns()
+            .iter()
+            .map(ColumnLayout::from)
+            .collect::<Vec<_>>();
+        let email = ColumnDef::builder()
+            .name("email")
+            .data_type(DataTypeKind::Text)
+            .nullable()
+            .build();
+
+        assert!(AlterTableOp::AddColumn(email).apply(&mut columns));
+        assert!(!AlterTableOp::AddColumn(email).apply(&mut columns));
+        assert!(
+            AlterTableOp::RenameColumn {
+                from: "email",
+                to: "contact"
+            }
+            .apply(&mut columns)
+        );
+        assert!(
+            !AlterTableOp::RenameColumn {
+                from: "email",
+                to: "mail"
+            }
+            .apply(&mut columns)
+        );
+        assert!(
+            !AlterTableOp::RenameColumn {
+                from: "contact",
+                to: "name"
+            }
+            .apply(&mut columns)
+        );
+        assert_eq!(
+            columns
+                .iter()
+                .map(|column| column.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["id", "name", "contact"]
+        );
+
+        assert!(!AlterTableOp::DropColumn(User::primary_key()).apply(&mut columns));
+        assert!(!AlterTableOp::DropColumn("email").apply(&mut columns));
+        assert!(AlterTableOp::DropColumn("contact").apply(&mut columns));
+        assert_eq!(layout_fingerprint(&columns), User::columns_fingerprint());
+    }
+}
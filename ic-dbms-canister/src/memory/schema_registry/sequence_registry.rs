@@ -0,0 +1,222 @@
+use std::collections::BTreeMap;
+
+use ic_dbms_api::prelude::{TableFingerprint, Uint64};
+
+use crate::memory::{
+    DataSize, Encode, MEMORY_MANAGER, MSize, MemoryResult, Page, PageOffset, WriteCategory,
+};
+
+/// The sequence registry keeps the last values generated by the sequences of the tables
+/// with an auto-increment column.
+///
+/// The registry is stored in a single page, allocated on first write and tracked by the
+/// [`super::SchemaRegistry`], which owns the registry.
+/// The entries are stored sorted by table fingerprint, so that advancing an existing sequence
+/// only writes its value.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SequenceRegistry {
+    /// Page of the registry, allocated on first write.
+    page: Option<Page>,
+    values: BTreeMap<TableFingerprint, u64>,
+}
+
+impl SequenceRegistry {
+    /// Load the sequence registry from the given page.
+    pub fn load(page: Page) -> MemoryResult<Self> {
+        let registry: Self = MEMORY_MANAGER.with_borrow(|m| m.read_at(page, 0))?;
+        Ok(Self {
+            page: Some(page),
+            ..registry
+        })
+    }
+
+    /// Returns the page of the sequence registry, if it has been allocated.
+    pub fn page(&self) -> Option<Page> {
+        self.page
+    }
+
+    /// Returns the last value generated by the sequence of the given table, if any.
+    pub fn value(&self, fingerprint: TableFingerprint) -> Option<u64> {
+        self.values.get(&fingerprint).copied()
+    }
+
+    /// Advances the sequence of the given table and writes it to memory, returning the new value.
+    ///
+    /// The first value of a sequence writes the whole registry, while the next ones only write the value.
+    pub fn next_value(&mut self, fingerprint: TableFingerprint) -> MemoryResult<u64> {
+        let value = self.value(fingerprint).unwrap_or_default() + 1;
+        let (Some(_), Some(page)) = (self.values.insert(fingerprint, value), self.page) else {
+            self.write()?;
+            return Ok(value);
+        };
+
+        // 8 bytes for len + (8 + 8) bytes for each preceding entry + 8 bytes for the fingerprint
+        let position = self.values.range(..fingerprint).count();
+        let offset = (8 + position * 16 + 8) as PageOffset;
+        MEMORY_MANAGER.with_borrow_mut(|m| {
+            m.write_metadata_at(WriteCategory::SchemaRegistry, page, offset, &Uint64(value))
+        })?;
+
+        Ok(value)
+    }
+
+    /// Starts the sequence of the table `target` at the value of the sequence of `source`,
+    /// and writes the registry to memory if `source` has a sequence.
+    pub fn copy(&mut self, source: TableFingerprint, target: TableFingerprint) -> MemoryResult<()> {
+        match self.value(source) {
+            Some(value) => {
+                self.values.insert(target, value);
+                self.write()
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Moves the sequence of the table `a` to the table `b` and vice versa,
+    /// and writes the registry to memory if any sequence has been moved.
+    pub fn swap_tables(&mut self, a: TableFingerprint, b: TableFingerprint) -> MemoryResult<()> {
+        let a_value = self.values.remove(&a);
+        let b_value = self.values.remove(&b);
+        if a_value.is_none() && b_value.is_none() {
+            return Ok(());
+        }
+
+        if let Some(value) = b_value {
+            self.values.insert(a, value);
+        }
+        if let Some(value) = a_value {
+            self.values.insert(b, value);
+        }
+        self.write()
+    }
+
+    /// Removes the sequence of the given table, and writes the registry to memory if it had one.
+    pub fn remove(&mut self, fingerprint: TableFingerprint) -> MemoryResult<()> {
+        if self.values.remove(&fingerprint).is_some() {
+            self.write()
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Writes the sequence registry to its page, allocating it if needed.
+    fn write(&mut self) -> MemoryResult<()> {
+        let page = match self.page {
+            Some(page) => page,
+            None => {
+                let page = MEMORY_MANAGER.with_borrow_mut(|m| m.allocate_page())?;
+                self.page = Some(page);
+                page
+            }
+        };
+        MEMORY_MANAGER
+            .with_borrow_mut(|m| m.write_metadata_at(WriteCategory::SchemaRegistry, page, 0, self))
+    }
+}
+
+impl Encode for SequenceRegistry {
+    const SIZE: DataSize = DataSize::Dynamic;
+
+    fn size(&self) -> MSize {
+        // 8 bytes for len + (8 + 8) bytes for each entry
+        8 + self.values.len() as MSize * 16
+    }
+
+    fn encode(&'_ self) -> std::borrow::Cow<'_, [u8]> {
+        let mut bytes = Vec::with_capacity(self.size() as usize);
+        bytes.extend_from_slice(&(self.values.len() as u64).to_le_bytes());
+        for (fingerprint, value) in &self.values {
+            bytes.extend_from_slice(&fingerprint.to_le_bytes());
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        std::borrow::Cow::Owned(bytes)
+    }
+
+    fn decode(data: std::borrow::Cow<[u8]>) -> MemoryResult<Self>
+    where
+        Self: Sized,
+    {
+        let len = u64::from_le_bytes(data[0..8].try_into()?) as usize;
+        let mut values = BTreeMap::new();
+        for entry in 0..len {
+            let offset = 8 + entry * 16;
+            let fingerprint = TableFingerprint::from_le_bytes(data[offset..offset + 8].try_into()?);
+            let value = u64::from_le_bytes(data[offset + 8..offset + 16].try_into()?);
+            values.insert(fingerprint, value);
+        }
+
+        Ok(Self { page: None, values })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_should_advance_sequences() {
+        let mut registry = SequenceRegistry::default();
+        assert_eq!(registry.value(2), None);
+        assert_eq!(registry.page(), None);
+
+        for expected in 1..=3 {
+            assert_eq!(registry.next_value(2).expect("failed to advance"), expected);
+        }
+        assert_eq!(registry.next_value(1).expect("failed to advance"), 1);
+        assert_eq!(registry.next_value(3).expect("failed to advance"), 1);
+        assert_eq!(registry.next_value(2).expect("failed to advance"), 4);
+
+        let page = registry.page().expect("page should be allocated");
+        let reloaded = SequenceRegistry::load(page).expect("failed to load");
+        assert_eq!(reloaded, registry);
+        assert_eq!(reloaded.value(2), Some(4));
+    }
+
+    #[test]
+    fn test_should_write_only_the_value_when_advancing() {
+        let mut registry = SequenceRegistry::default();
+        for fingerprint in 1..=3 {
+            registry.next_value(fingerprint).expect("failed to advance");
+        }
+
+        MEMORY_MANAGER.with_borrow_mut(|m| m.reset_stats());
+        assert_eq!(registry.next_value(2).expect("failed to advance"), 2);
+        let stats = MEMORY_MANAGER.with_borrow(|m| m.write_amplification_tracker());
+        assert_eq!(stats.schema_registry_bytes, 8);
+
+        let page = registry.page().expect("page should be allocated");
+        let reloaded = SequenceRegistry::load(page).expect("failed to load");
+        assert_eq!(reloaded.value(1), Some(1));
+        assert_eq!(reloaded.value(2), Some(2));
+        assert_eq!(reloaded.value(3), Some(1));
+    }
+
+    #[test]
+    fn test_should_copy_swap_and_remove_sequences() {
+        let mut registry = SequenceRegistry::default();
+        registry.next_value(1).expect("failed to advance");
+        registry.next_value(1).expect("failed to advance");
+
+        registry.copy(1, 2).expect("failed to copy");
+        registry.copy(5, 6).expect("failed to copy");
+        assert_eq!(registry.value(2), Some(2));
+        assert_eq!(registry.value(6), None);
+
+        registry.next_value(2).expect("failed to advance");
+        registry.swap_tables(1, 2).expect("failed to swap");
+        assert_eq!(registry.value(1), Some(3));
+        assert_eq!(registry.value(2), Some(2));
+
+        registry.swap_tables(2, 4).expect("failed to swap");
+        assert_eq!(registry.value(2), None);
+        assert_eq!(registry.value(4), Some(2));
+
+        registry.remove(4).expect("failed to remove");
+        assert_eq!(registry.value(4), None);
+
+        let page = registry.page().expect("page should be allocated");
+        let reloaded = SequenceRegistry::load(page).expect("failed to load");
+        assert_eq!(reloaded, registry);
+    }
+}
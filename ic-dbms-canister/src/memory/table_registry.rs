@@ -4,17 +4,27 @@ mod raw_record;
 mod table_reader;
 mod write_at;
 
+use ic_dbms_api::prelude::{Blob, ColumnDef, TableFingerprint};
+
 use self::free_segments_ledger::FreeSegmentsLedger;
-use self::page_ledger::PageLedger;
-pub use self::table_reader::{NextRecord, TableReader};
+use self::page_ledger::{PageLedger, PageRecord};
+pub use self::table_reader::{InfallibleTableReader, NextRecord, TableReader};
 use self::write_at::WriteAt;
-use crate::memory::table_registry::raw_record::RawRecord;
+#[cfg(feature = "indexes")]
+use crate::memory::TableIndexes;
+use crate::memory::table_registry::raw_record::{
+    EncodedRawRecord, RAW_RECORD_FORMAT_VERSION, RawRecord, stored_record_size,
+};
 use crate::memory::{
-    Encode, MEMORY_MANAGER, MSize, MemoryResult, Page, PageOffset, TableRegistryPage,
+    Encode, MEMORY_MANAGER, MSize, MemoryError, MemoryResult, Page, PageOffset, TableQuota,
+    TableRegistryPage,
 };
 
-/// Each record is prefixed with its length encoded in 2 bytes and a magic header byte.
-const RAW_RECORD_HEADER_SIZE: MSize = 3;
+/// Amount of records read to estimate the average size of the records in [`TableRegistry::stats`].
+const RECORD_SIZE_SAMPLE: usize = 16;
+
+/// Size in bytes assumed for the values of dynamic size columns by [`TableRegistry::estimate_max_record_size`].
+pub const DEFAULT_DYNAMIC_FIELD_ESTIMATE: MSize = 64;
 
 /// The table registry takes care of storing the records for each table,
 /// using the [`FreeSegmentsLedger`] and [`PageLedger`] to derive exactly where to read/write.
@@ -24,9 +34,55 @@ const RAW_RECORD_HEADER_SIZE: MSize = 3;
 /// The CRUD operations provided by the table registry do NOT perform any logical checks,
 /// but just allow to read/write records from/to memory.
 /// So CRUD checks must be performed by a higher layer, prior to calling these methods.
+#[derive(Debug)]
 pub struct TableRegistry {
     free_segments_ledger: FreeSegmentsLedger,
     page_ledger: PageLedger,
+    /// Quota checked on insert, along with the fingerprint of the table it belongs to.
+    quota: Option<(TableFingerprint, TableQuota)>,
+    /// Indexes updated on every write.
+    #[cfg(feature = "indexes")]
+    indexes: Option<TableIndexes>,
+    /// Whether the written records are compressed.
+    #[cfg(feature = "compression")]
+    compression: bool,
+}
+
+/// Storage statistics of a [`TableRegistry`], derived from its ledgers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TableStats {
+    /// Amount of pages allocated to store the records.
+    pub pages: u64,
+    /// Bytes taken by the stored records, including their headers.
+    pub used_bytes: u64,
+    /// Average size in bytes of a stored record, including its header.
+    pub average_record_size: u64,
+    /// Estimated amount of stored records.
+    pub estimated_records: u64,
+}
+
+/// Result of a vacuum of a table, which packs its records into fresh pages.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct VacuumStats {
+    /// Amount of pages allocated to the table before the vacuum.
+    pub pages_before: u64,
+    /// Amount of pages allocated to the table after the vacuum.
+    pub pages_after: u64,
+    /// Amount of records moved to the new pages.
+    pub records_moved: u64,
+    /// Bytes of the table pages not taken by records anymore.
+    pub free_bytes_recovered: u64,
+}
+
+/// Result of a compaction of a table registry, which packs its records at the beginning of its pages.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CompactionStats {
+    /// Amount of pages allocated to the table before the compaction.
+    pub pages_before: u64,
+    /// Amount of pages allocated to the table after the compaction.
+    pub pages_after: u64,
+    /// Bytes of the free segments left by deleted records, which are now free space at the end of the pages.
+    pub bytes_reclaimed: u64,
 }
 
 impl TableRegistry {
@@ -35,28 +91,168 @@ impl TableRegistry {
         Ok(Self {
             free_segments_ledger: FreeSegmentsLedger::load(table_pages.free_segments_page)?,
             page_ledger: PageLedger::load(table_pages.pages_list_page)?,
+            quota: None,
+            #[cfg(feature = "indexes")]
+            indexes: None,
+            #[cfg(feature = "compression")]
+            compression: false,
         })
     }
 
+    /// Sets the estimated size of the records of the table, see [`TableRegistry::estimate_max_record_size`].
+    ///
+    /// Inserting a record much larger than the estimate logs a warning.
+    pub fn with_record_size_estimate(mut self, estimate: MSize) -> Self {
+        self.page_ledger.set_record_size_estimate(estimate);
+        self
+    }
+
+    /// Estimates the size of a stored record of a table with the given columns, before any record is inserted.
+    ///
+    /// Fixed size columns take their exact size, while dynamic size columns, such as text and blobs,
+    /// are assumed to take [`DEFAULT_DYNAMIC_FIELD_ESTIMATE`] bytes.
+    /// Nullable columns take an additional byte, so the estimate is exact for tables with fixed size columns.
+    pub fn estimate_max_record_size(schema: &[ColumnDef]) -> MSize {
+        let data_size = schema
+            .iter()
+            .map(|column| {
+                let size = column
+                    .data_type
+                    .encoded_size()
+                    .get_fixed_size()
+                    .unwrap_or(DEFAULT_DYNAMIC_FIELD_ESTIMATE);
+                size.saturating_add(MSize::from(column.nullable))
+            })
+            .fold(0, MSize::saturating_add);
+
+        stored_record_size(data_size)
+    }
+
+    /// Estimates the size of a stored record as the average stored size of the given sample records.
+    ///
+    /// Returns `0` if there are no samples.
+    pub fn estimate_record_size_with_samples<E>(samples: &[E]) -> MSize
+    where
+        E: Encode,
+    {
+        let total = samples
+            .iter()
+            .map(|sample| u64::from(stored_record_size(sample.size())))
+            .sum::<u64>();
+
+        total.checked_div(samples.len() as u64).unwrap_or_default() as MSize
+    }
+
+    /// Returns the amount of pages allocated to store the records.
+    pub fn page_count(&self) -> u64 {
+        self.page_ledger.page_count() as u64
+    }
+
+    /// Returns the amount of free segments left by the deleted records.
+    pub fn free_segment_count(&self) -> u64 {
+        self.free_segments_ledger.segment_count() as u64
+    }
+
+    /// Verifies the integrity of a sample of the stored records, without scanning the table.
+    ///
+    /// The first `sample_size` records and the records of the last page are read,
+    /// checking their headers and, for records stored with a checksum, that it matches their data.
+    /// The records are not decoded, so the check doesn't depend on the table schema.
+    ///
+    /// Returns `false` if any sampled record is corrupted.
+    pub fn verify_integrity(&self, sample_size: usize) -> MemoryResult<bool> {
+        match self.read_integrity_sample(sample_size) {
+            Ok(()) => Ok(true),
+            Err(MemoryError::DecodeError(_)) => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Reads the records sampled by [`TableRegistry::verify_integrity`].
+    fn read_integrity_sample(&self, sample_size: usize) -> MemoryResult<()> {
+        // read the first records
+        let mut reader = self.read::<EncodedRawRecord>();
+        for _ in 0..sample_size {
+            if reader.try_next()?.is_none() {
+                break;
+            }
+        }
+
+        // read the records of the last page
+        if let Some(last_page) = self.page_ledger.pages().last() {
+            let mut reader = self.read::<EncodedRawRecord>();
+            reader.seek_to_page(last_page.page);
+            while reader.try_next()?.is_some() {}
+        }
+
+        Ok(())
+    }
+
+    /// Sets the [`TableQuota`] of the table with the given fingerprint, which is checked on insert.
+    pub fn with_quota(mut self, table_fingerprint: TableFingerprint, quota: TableQuota) -> Self {
+        self.quota = (!quota.is_unlimited()).then_some((table_fingerprint, quota));
+        self
+    }
+
+    /// Sets the [`TableIndexes`] of the table, which are updated on every insert, update and delete.
+    #[cfg(feature = "indexes")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "indexes")))]
+    pub fn with_indexes(mut self, indexes: TableIndexes) -> Self {
+        self.indexes = Some(indexes);
+        self
+    }
+
+    /// Returns the [`TableIndexes`] of the table, if set with [`TableRegistry::with_indexes`].
+    #[cfg(feature = "indexes")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "indexes")))]
+    pub fn indexes(&self) -> Option<&TableIndexes> {
+        self.indexes.as_ref()
+    }
+
+    /// Sets whether the records written to the table registry are compressed, see [`RawRecord::with_compression`].
+    ///
+    /// Records already stored are read regardless of their compression, which is changed only when they are rewritten.
+    #[cfg(feature = "compression")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "compression")))]
+    pub fn with_compression(mut self, compression: bool) -> Self {
+        self.compression = compression;
+        self
+    }
+
     /// Inserts a new record into the table registry.
     ///
+    /// Returns [`MemoryError::QuotaExceeded`] if the record would exceed the [`TableQuota`] of the table.
+    /// Note that when the quota limits the rows, the stored records are counted by scanning the table.
+    ///
     /// NOTE: this function does NOT make any logical checks on the record being inserted.
     pub fn insert(&mut self, record: impl Encode) -> MemoryResult<()> {
-        // get position to write the record
-        let raw_record = RawRecord::new(record);
-        let write_at = self.get_write_position(&raw_record)?;
+        let records = self.quota_records()?;
+        self.insert_within_quota(self.raw_record(record)?, records)
+    }
 
-        // write record
-        MEMORY_MANAGER
-            .with_borrow_mut(|mm| mm.write_at(write_at.page(), write_at.offset(), &raw_record))?;
+    /// Inserts all the given records into the table registry.
+    ///
+    /// Returns [`MemoryError::QuotaExceeded`] as soon as a record would exceed the [`TableQuota`] of the table;
+    /// the records inserted before are kept.
+    ///
+    /// NOTE: this function does NOT make any logical checks on the records being inserted.
+    pub fn insert_batch<E>(&mut self, records: impl IntoIterator<Item = E>) -> MemoryResult<()>
+    where
+        E: Encode,
+    {
+        // count the stored records once for the whole batch
+        let mut stored_records = self.quota_records()?;
+        for record in records {
+            self.insert_within_quota(self.raw_record(record)?, stored_records)?;
+            stored_records = stored_records.map(|count| count + 1);
+        }
 
-        // commit post-write actions
-        self.post_write(write_at, &raw_record)
+        Ok(())
     }
 
     /// Creates a [`TableReader`] to read records from the table registry.
     ///
-    /// Use [`TableReader::try_next`] to read records one by one.
+    /// Use [`TableReader::try_next`] to read records one by one, or iterate over the reader.
     pub fn read<E>(&self) -> TableReader<'_, E>
     where
         E: Encode,
@@ -64,6 +260,45 @@ impl TableRegistry {
         TableReader::new(&self.page_ledger)
     }
 
+    /// Returns the [`TableStats`] of the table registry.
+    ///
+    /// The amount of records is estimated from the bytes in use according to the ledgers
+    /// and the average size of the first [`RECORD_SIZE_SAMPLE`] records, so the table is not scanned.
+    pub fn stats<E>(&self) -> MemoryResult<TableStats>
+    where
+        E: Encode,
+    {
+        let used_bytes = self.used_bytes();
+
+        // sample the size of the first records
+        let mut reader = self.read::<E>();
+        let mut sampled = 0u64;
+        let mut sampled_bytes = 0u64;
+        while sampled < RECORD_SIZE_SAMPLE as u64 {
+            let Some(next) = reader.try_next()? else {
+                break;
+            };
+            sampled += 1;
+            sampled_bytes += self.raw_record(next.record)?.size() as u64;
+        }
+        let average_record_size = sampled_bytes.checked_div(sampled).unwrap_or_default();
+
+        Ok(TableStats {
+            pages: self.page_ledger.page_count() as u64,
+            used_bytes,
+            average_record_size,
+            estimated_records: used_bytes
+                .checked_div(average_record_size)
+                .unwrap_or_default(),
+        })
+    }
+
+    /// Returns the [`TableStats`] of the table registry as [`TableRegistry::stats`],
+    /// without decoding the sampled records, so without knowing their type.
+    pub fn raw_stats(&self) -> MemoryResult<TableStats> {
+        self.stats::<EncodedRawRecord>()
+    }
+
     /// Deletes a record at the given page and offset.
     ///
     /// The space occupied by the record is marked as free and zeroed.
@@ -73,14 +308,15 @@ impl TableRegistry {
         page: Page,
         offset: PageOffset,
     ) -> MemoryResult<()> {
-        let raw_record = RawRecord::new(record);
+        let raw_record = Self::stored_raw_record(record, page, offset)?;
+        self.delete_raw_record(&raw_record, page, offset)?;
 
-        // zero the record in memory
-        MEMORY_MANAGER.with_borrow_mut(|mm| mm.zero(page, offset, &raw_record))?;
+        #[cfg(feature = "indexes")]
+        if let Some(indexes) = self.indexes.as_mut() {
+            indexes.on_delete(&raw_record.data.encode())?;
+        }
 
-        // insert a free segment for the deleted record
-        self.free_segments_ledger
-            .insert_free_segment(page, offset, &raw_record)
+        Ok(())
     }
 
     /// Updates a record at the given page and offset.
@@ -96,41 +332,458 @@ impl TableRegistry {
         old_page: Page,
         old_offset: PageOffset,
     ) -> MemoryResult<()> {
-        if new_record.size() == old_record.size() {
-            self.update_in_place(new_record, old_page, old_offset)
+        let new_raw_record = self.raw_record(new_record)?;
+        let old_raw_record = Self::stored_raw_record(old_record, old_page, old_offset)?;
+        #[cfg(feature = "indexes")]
+        let indexed_records = self.indexes.is_some().then(|| {
+            (
+                old_raw_record.data.encode().into_owned(),
+                new_raw_record.data.encode().into_owned(),
+            )
+        });
+
+        #[cfg_attr(not(feature = "indexes"), allow(unused_variables))]
+        let new_location = if new_raw_record.size() == old_raw_record.size() {
+            self.update_in_place(new_raw_record, old_page, old_offset)?;
+            (old_page, old_offset)
         } else {
-            self.update_by_realloc(new_record, old_record, old_page, old_offset)
+            self.update_by_realloc(new_raw_record, old_raw_record, old_page, old_offset)?
+        };
+
+        #[cfg(feature = "indexes")]
+        if let (Some(indexes), Some((old_record, new_record))) =
+            (self.indexes.as_mut(), indexed_records)
+        {
+            indexes.on_update(
+                &old_record,
+                &new_record,
+                (old_page, old_offset),
+                new_location,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Rewrites all the records stored with an older format version using the latest format version.
+    ///
+    /// Records which need to grow are reallocated, as in [`TableRegistry::update`].
+    ///
+    /// Returns the amount of migrated records.
+    pub fn migrate_format<E>(&mut self) -> MemoryResult<u64>
+    where
+        E: Encode + Clone,
+    {
+        // collect records to migrate first, since migrating may move them
+        let mut outdated = vec![];
+        {
+            let mut reader = self.read::<E>();
+            while let Some(next_record) = reader.try_next()? {
+                if next_record.version != RAW_RECORD_FORMAT_VERSION {
+                    outdated.push(next_record);
+                }
+            }
+        }
+
+        let count = outdated.len() as u64;
+        for next_record in outdated {
+            self.update(
+                next_record.record.clone(),
+                next_record.record,
+                next_record.page,
+                next_record.offset,
+            )?;
+        }
+
+        Ok(count)
+    }
+
+    /// Releases the pages whose records have all been deleted.
+    ///
+    /// A page is entirely free when its free segments cover all the bytes committed in the [`PageLedger`].
+    /// Its free segments are removed, the page is removed from the [`PageLedger`] and zeroed.
+    ///
+    /// The page is not returned to the memory provider, since stable memory can't shrink.
+    ///
+    /// Returns the amount of freed pages.
+    pub fn gc_empty_pages(&mut self) -> MemoryResult<u64> {
+        let page_size = MEMORY_MANAGER.with_borrow(|mm| mm.page_size());
+        let empty_pages: Vec<Page> = self
+            .page_ledger
+            .pages()
+            .filter(|page_record| {
+                let used_bytes = page_size.saturating_sub(page_record.free);
+                self.free_segments_ledger.page_free_bytes(page_record.page) == used_bytes
+            })
+            .map(|page_record| page_record.page)
+            .collect();
+
+        for page in &empty_pages {
+            self.free_segments_ledger.remove_page_segments(*page)?;
+            self.page_ledger.remove_page(*page)?;
+            MEMORY_MANAGER.with_borrow_mut(|mm| mm.zero_page(*page))?;
+        }
+
+        Ok(empty_pages.len() as u64)
+    }
+
+    /// Compacts the table registry, rewriting all the live records contiguously from the beginning of its first page.
+    ///
+    /// Records keep their order and their format version, and each page is filled before moving to the next one,
+    /// so records never take more pages than before.
+    /// The [`PageLedger`] is updated with the new usage of the pages, the pages left without records are removed
+    /// from it and zeroed, and the [`FreeSegmentsLedger`] is cleared.
+    /// As in [`TableRegistry::gc_empty_pages`], the pages are not returned to the memory provider.
+    ///
+    /// Since it borrows the registry mutably, no [`TableReader`] can be used during the compaction.
+    /// Compacting a compacted table doesn't write anything.
+    pub fn compact(&mut self) -> MemoryResult<CompactionStats> {
+        let page_size = MEMORY_MANAGER.with_borrow(|mm| mm.page_size());
+        let pages: Vec<PageRecord> = self.page_ledger.pages().collect();
+        let bytes_reclaimed = self.free_segments_ledger.free_bytes();
+        // read all the records first, since rewriting the pages overwrites them
+        let records = self
+            .read::<EncodedRawRecord>()
+            .collect::<MemoryResult<Vec<_>>>()?;
+
+        // pack the records in reading order, moving to the next page when a record doesn't fit
+        let mut used_bytes = vec![0u64; pages.len()];
+        let mut page_index = 0;
+        let mut moves = Vec::with_capacity(records.len());
+        for next_record in records {
+            let raw_record = RawRecord::with_version(next_record.record, next_record.version)?;
+            #[cfg(feature = "compression")]
+            let raw_record = raw_record.with_compression(next_record.compressed);
+            let size = raw_record.size() as u64;
+            if used_bytes[page_index] + size > page_size {
+                page_index += 1;
+            }
+            // records took these pages before, so they always fit them
+            let page = pages.get(page_index).ok_or(MemoryError::OutOfBounds)?.page;
+            moves.push((
+                (next_record.page, next_record.offset),
+                (page, used_bytes[page_index] as PageOffset),
+                raw_record,
+            ));
+            used_bytes[page_index] += size;
+        }
+
+        let unchanged = moves.iter().all(|(old, new, _)| old == new)
+            && pages
+                .iter()
+                .zip(&used_bytes)
+                .all(|(page_record, used)| page_size.saturating_sub(page_record.free) == *used);
+        if unchanged {
+            return Ok(CompactionStats {
+                pages_before: pages.len() as u64,
+                pages_after: pages.len() as u64,
+                bytes_reclaimed: 0,
+            });
+        }
+
+        // rewrite the pages
+        for page_record in &pages {
+            MEMORY_MANAGER.with_borrow_mut(|mm| mm.zero_page(page_record.page))?;
         }
+        #[cfg_attr(not(feature = "indexes"), allow(unused_variables))]
+        for (old_location, new_location, raw_record) in &moves {
+            MEMORY_MANAGER
+                .with_borrow_mut(|mm| mm.write_at(new_location.0, new_location.1, raw_record))?;
+
+            #[cfg(feature = "indexes")]
+            if let Some(indexes) = self
+                .indexes
+                .as_mut()
+                .filter(|_| old_location != new_location)
+            {
+                let record = raw_record.data.encode();
+                indexes.on_update(&record, &record, *old_location, *new_location)?;
+            }
+        }
+
+        // update the ledgers
+        for (page_record, used) in pages.iter().zip(used_bytes) {
+            if used == 0 {
+                self.page_ledger.remove_page(page_record.page)?;
+            } else {
+                self.page_ledger.set_used(page_record.page, used)?;
+            }
+        }
+        self.free_segments_ledger.clear()?;
+
+        Ok(CompactionStats {
+            pages_before: pages.len() as u64,
+            pages_after: self.page_ledger.page_count() as u64,
+            bytes_reclaimed,
+        })
+    }
+
+    /// Returns the bytes taken by the stored records according to the ledgers, including their headers.
+    fn used_bytes(&self) -> u64 {
+        let page_size = MEMORY_MANAGER.with_borrow(|mm| mm.page_size());
+        let taken_bytes: u64 = self
+            .page_ledger
+            .pages()
+            .map(|page_record| page_size.saturating_sub(page_record.free))
+            .sum();
+        taken_bytes.saturating_sub(self.free_segments_ledger.free_bytes())
+    }
+
+    /// Returns the amount of stored records if the quota limits the rows, since counting them requires a scan.
+    fn quota_records(&self) -> MemoryResult<Option<u64>> {
+        if self.quota.is_none_or(|(_, quota)| quota.max_rows.is_none()) {
+            return Ok(None);
+        }
+
+        // the record type doesn't matter, since records are not decoded
+        TableReader::<Blob>::new(&self.page_ledger)
+            .count_remaining()
+            .map(Some)
+    }
+
+    /// Inserts a [`RawRecord`] after checking it doesn't exceed the [`TableQuota`] of the table.
+    ///
+    /// `stored_records` is the amount of stored records, as returned by [`Self::quota_records`].
+    fn insert_within_quota<E>(
+        &mut self,
+        raw_record: RawRecord<E>,
+        stored_records: Option<u64>,
+    ) -> MemoryResult<()>
+    where
+        E: Encode,
+    {
+        self.check_quota(&raw_record, stored_records)?;
+        #[cfg(feature = "indexes")]
+        let indexed_record = self
+            .indexes
+            .is_some()
+            .then(|| raw_record.data.encode().into_owned());
+
+        #[cfg_attr(not(feature = "indexes"), allow(unused_variables))]
+        let location = self.insert_raw_record(raw_record)?;
+        MEMORY_MANAGER.with_borrow_mut(|mm| mm.record_logical_write());
+
+        #[cfg(feature = "indexes")]
+        if let (Some(indexes), Some(record)) = (self.indexes.as_mut(), indexed_record) {
+            indexes.on_insert(&record, location)?;
+        }
+
+        Ok(())
+    }
+
+    /// Checks whether inserting the given [`RawRecord`] would exceed the [`TableQuota`] of the table.
+    fn check_quota<E>(
+        &self,
+        raw_record: &RawRecord<E>,
+        stored_records: Option<u64>,
+    ) -> MemoryResult<()>
+    where
+        E: Encode,
+    {
+        let Some((table_fingerprint, quota)) = self.quota else {
+            return Ok(());
+        };
+        let exceeded = |limit: u64, actual: u64| {
+            if actual > limit {
+                Err(MemoryError::QuotaExceeded {
+                    table_fingerprint,
+                    limit,
+                    actual,
+                })
+            } else {
+                Ok(())
+            }
+        };
+
+        if let (Some(max_rows), Some(stored_records)) = (quota.max_rows, stored_records) {
+            exceeded(max_rows, stored_records + 1)?;
+        }
+        if let Some(max_bytes) = quota.max_bytes {
+            exceeded(max_bytes, self.used_bytes() + raw_record.size() as u64)?;
+        }
+        if let Some(max_pages) = quota.max_pages {
+            // a new page is allocated only if the record fits neither a free segment nor a page
+            let allocates_page = self
+                .free_segments_ledger
+                .find_reusable_segment(raw_record)
+                .is_none()
+                && !self.page_ledger.has_space_for(raw_record);
+            let pages = self.page_ledger.page_count() as u64 + allocates_page as u64;
+            exceeded(max_pages as u64, pages)?;
+        }
+
+        Ok(())
+    }
+
+    /// Drops all the pages of the table registry, removing all the records.
+    ///
+    /// The pages are removed from the [`PageLedger`] and zeroed, and the free segments are cleared.
+    /// As in [`TableRegistry::gc_empty_pages`], the pages are not returned to the memory provider.
+    ///
+    /// Returns the amount of dropped pages.
+    pub fn drop_pages(&mut self) -> MemoryResult<u64> {
+        let pages: Vec<Page> = self
+            .page_ledger
+            .pages()
+            .map(|page_record| page_record.page)
+            .collect();
+
+        for page in &pages {
+            self.free_segments_ledger.remove_page_segments(*page)?;
+            self.page_ledger.remove_page(*page)?;
+            MEMORY_MANAGER.with_borrow_mut(|mm| mm.zero_page(*page))?;
+        }
+
+        Ok(pages.len() as u64)
+    }
+
+    /// Dumps all the records of the table registry as raw bytes.
+    ///
+    /// Records are dumped in reading order, as the sequence of their encoded [`RawRecord`]s,
+    /// header included; the result can be loaded back with [`TableRegistry::bulk_load`].
+    pub fn bulk_export(&self) -> MemoryResult<Vec<u8>> {
+        let mut dump = vec![];
+        let mut reader = TableReader::<Blob>::new(&self.page_ledger);
+        while let Some(record) = reader.try_next_encoded()? {
+            dump.extend_from_slice(record.as_bytes());
+        }
+
+        Ok(dump)
+    }
+
+    /// Loads the records dumped by [`TableRegistry::bulk_export`] into the table registry.
+    ///
+    /// The input is the sequence of the encoded [`RawRecord`]s, whose headers are all validated
+    /// before writing anything; records are then written as they are, without re-encoding them.
+    /// Records are always appended at the end of the table, so the free segments are never reused,
+    /// and the [`TableQuota`] is not checked.
+    ///
+    /// Returns the amount of loaded records.
+    ///
+    /// NOTE: this function does NOT make any logical checks on the records being loaded.
+    pub fn bulk_load(&mut self, raw_bytes: &[u8]) -> MemoryResult<u64> {
+        let records = EncodedRawRecord::split(raw_bytes)?;
+        for record in &records {
+            let (page, offset) = self.page_ledger.get_page_and_offset_for_record(record)?;
+            MEMORY_MANAGER.with_borrow_mut(|mm| mm.write_at(page, offset, record))?;
+            self.page_ledger.commit(page, record)?;
+        }
+
+        Ok(records.len() as u64)
+    }
+
+    /// Makes the [`RawRecord`] to write the given record, compressed if the table registry uses compression.
+    ///
+    /// Fails with [`MemoryError::DataTooLarge`] if the record is too large to be stored.
+    fn raw_record<E>(&self, record: E) -> MemoryResult<RawRecord<E>>
+    where
+        E: Encode,
+    {
+        #[cfg(feature = "compression")]
+        {
+            Ok(RawRecord::new(record)?.with_compression(self.compression))
+        }
+        #[cfg(not(feature = "compression"))]
+        {
+            RawRecord::new(record)
+        }
+    }
+
+    /// Makes the [`RawRecord`] for a record stored at the given page and offset.
+    ///
+    /// The record may be stored with an older format version or compressed, so the header byte is read from memory
+    /// to get the actual size of the stored record.
+    fn stored_raw_record<E>(record: E, page: Page, offset: PageOffset) -> MemoryResult<RawRecord<E>>
+    where
+        E: Encode,
+    {
+        let mut header = [0u8; 1];
+        MEMORY_MANAGER.with_borrow(|mm| mm.read_at_cached(page, offset, &mut header))?;
+
+        let raw_record =
+            RawRecord::with_version(record, RawRecord::<E>::format_version(header[0]))?;
+        #[cfg(feature = "compression")]
+        let raw_record =
+            raw_record.with_compression(RawRecord::<E>::is_compressed_header(header[0]));
+
+        Ok(raw_record)
+    }
+
+    /// Deletes a [`RawRecord`] at the given page and offset.
+    fn delete_raw_record<E>(
+        &mut self,
+        raw_record: &RawRecord<E>,
+        page: Page,
+        offset: PageOffset,
+    ) -> MemoryResult<()>
+    where
+        E: Encode,
+    {
+        // zero the record in memory
+        MEMORY_MANAGER.with_borrow_mut(|mm| mm.zero(page, offset, raw_record))?;
+
+        // insert a free segment for the deleted record
+        self.free_segments_ledger
+            .insert_free_segment(page, offset, raw_record)
+    }
+
+    /// Inserts a [`RawRecord`] into the table registry.
+    ///
+    /// Returns the page and offset where the record has been written.
+    fn insert_raw_record<E>(&mut self, raw_record: RawRecord<E>) -> MemoryResult<(Page, PageOffset)>
+    where
+        E: Encode,
+    {
+        // get position to write the record
+        let write_at = self.get_write_position(&raw_record)?;
+        let location = (write_at.page(), write_at.offset());
+
+        // write record
+        MEMORY_MANAGER
+            .with_borrow_mut(|mm| mm.write_at(write_at.page(), write_at.offset(), &raw_record))?;
+
+        // commit post-write actions
+        self.post_write(write_at, &raw_record)?;
+
+        Ok(location)
     }
 
     /// Update a [`RawRecord`] in place at the given page and offset.
     ///
     /// This must be used IF AND ONLY if the new record has the SAME size as the old record.
-    fn update_in_place(
+    fn update_in_place<E>(
         &mut self,
-        record: impl Encode,
+        raw_record: RawRecord<E>,
         page: Page,
         offset: PageOffset,
-    ) -> MemoryResult<()> {
-        let raw_record = RawRecord::new(record);
+    ) -> MemoryResult<()>
+    where
+        E: Encode,
+    {
         MEMORY_MANAGER.with_borrow_mut(|mm| mm.write_at(page, offset, &raw_record))
     }
 
     /// Updates a record by reallocating it.
     ///
     /// The old record is deleted and the new record is inserted.
-    fn update_by_realloc(
+    ///
+    /// Returns the page and offset where the new record has been written.
+    fn update_by_realloc<N, O>(
         &mut self,
-        new_record: impl Encode,
-        old_record: impl Encode,
+        new_raw_record: RawRecord<N>,
+        old_raw_record: RawRecord<O>,
         old_page: Page,
         old_offset: PageOffset,
-    ) -> MemoryResult<()> {
+    ) -> MemoryResult<(Page, PageOffset)>
+    where
+        N: Encode,
+        O: Encode,
+    {
         // delete old record
-        self.delete(old_record, old_page, old_offset)?;
+        self.delete_raw_record(&old_raw_record, old_page, old_offset)?;
 
         // insert new record
-        self.insert(new_record)
+        self.insert_raw_record(new_raw_record)
     }
 
     /// Gets the position where to write a record of the given size.
@@ -174,9 +827,14 @@ impl TableRegistry {
 #[cfg(test)]
 mod tests {
 
+    use ic_dbms_api::prelude::{Date, DecodeError, TableSchema as _};
+
     use super::*;
+    use crate::memory::provider::MemoryProvider as _;
     use crate::memory::table_registry::free_segments_ledger::FreeSegment;
-    use crate::tests::User;
+    use crate::memory::table_registry::raw_record::{RAW_RECORD_FORMAT_V0, RAW_RECORD_FORMAT_V1};
+    use crate::memory::{MemoryManager, SCHEMA_REGISTRY, SchemaRegistry, WriteAmpStats};
+    use crate::tests::{Booking, User};
 
     #[test]
     fn test_should_create_table_registry() {
@@ -202,7 +860,8 @@ mod tests {
         let record = RawRecord::new(User {
             id: 1u32.into(),
             name: "Test".to_string().into(),
-        });
+        })
+        .expect("failed to create raw record");
         let write_at = registry
             .get_write_position(&record)
             .expect("failed to get write at");
@@ -217,7 +876,8 @@ mod tests {
         let record = RawRecord::new(User {
             id: 1u32.into(),
             name: "Test".to_string().into(),
-        });
+        })
+        .expect("failed to create raw record");
         // allocate a page to insert a free segment
         let (page, _) = registry
             .page_ledger
@@ -259,6 +919,89 @@ mod tests {
         assert!(registry.insert(record).is_ok());
     }
 
+    #[test]
+    fn test_should_track_write_amplification_of_inserts() {
+        let mut registry = registry();
+        MEMORY_MANAGER.with_borrow_mut(|mm| mm.reset_stats());
+
+        let records = (0..100u32)
+            .map(|id| User {
+                id: id.into(),
+                name: format!("User {id}").into(),
+            })
+            .collect::<Vec<_>>();
+        let records_size = records
+            .iter()
+            .map(|record| {
+                RawRecord::new(record.clone())
+                    .expect("failed to create raw record")
+                    .size() as u64
+            })
+            .sum::<u64>();
+        registry
+            .insert_batch(records)
+            .expect("failed to insert records");
+
+        let stats = MEMORY_MANAGER.with_borrow(|mm| mm.write_amplification_tracker());
+        assert_eq!(stats.logical_writes, 100);
+        assert!(stats.physical_writes > stats.logical_writes);
+        assert!(stats.amplification_ratio > 1.0);
+        assert_eq!(stats.data_bytes, records_size);
+        assert!(stats.page_ledger_bytes > 0);
+
+        // deleting a record zeroes it and updates the free segments ledger
+        let record = User {
+            id: 0u32.into(),
+            name: "User 0".to_string().into(),
+        };
+        let record_size = RawRecord::new(record.clone())
+            .expect("failed to create raw record")
+            .size() as u64;
+        let (page, offset) = {
+            let mut reader = registry.read::<User>();
+            let next = reader
+                .try_next()
+                .expect("failed to read")
+                .expect("should have record");
+            (next.page, next.offset)
+        };
+        registry
+            .delete(record, page, offset)
+            .expect("failed to delete record");
+
+        let stats = MEMORY_MANAGER.with_borrow(|mm| mm.write_amplification_tracker());
+        assert_eq!(stats.logical_writes, 100);
+        assert_eq!(stats.data_bytes, records_size + record_size);
+        assert!(stats.free_segments_bytes > 0);
+
+        MEMORY_MANAGER.with_borrow_mut(|mm| mm.reset_stats());
+        assert_eq!(
+            MEMORY_MANAGER.with_borrow(|mm| mm.write_amplification_tracker()),
+            WriteAmpStats::default()
+        );
+    }
+
+    #[test]
+    fn test_should_insert_batch_into_table_registry() {
+        let mut registry = registry();
+
+        let records = (0..10u32).map(|id| User {
+            id: id.into(),
+            name: format!("User {}", id).into(),
+        });
+        registry
+            .insert_batch(records)
+            .expect("failed to insert batch");
+
+        let mut reader = registry.read::<User>();
+        let mut count = 0u32;
+        while let Some(next) = reader.try_next().expect("failed to read record") {
+            assert_eq!(next.record.id, count.into());
+            count += 1;
+        }
+        assert_eq!(count, 10);
+    }
+
     #[test]
     fn test_should_manage_to_insert_users_to_exceed_one_page() {
         let mut registry = registry();
@@ -293,7 +1036,7 @@ mod tests {
         let page = next_record.page;
         let offset = next_record.offset;
         let record = next_record.record;
-        let raw_user = RawRecord::new(record.clone());
+        let raw_user = RawRecord::new(record.clone()).expect("failed to create raw record");
         let raw_user_size = raw_user.size();
 
         // delete record
@@ -323,6 +1066,57 @@ mod tests {
         assert!(buffer.iter().all(|&b| b == 0));
     }
 
+    #[test]
+    fn test_should_coalesce_free_segments_of_deleted_records() {
+        let mut registry = registry();
+        let users: Vec<User> = (0..10u32)
+            .map(|id| User {
+                id: id.into(),
+                name: format!("User {id}").into(),
+            })
+            .collect();
+        registry
+            .insert_batch(users.clone())
+            .expect("failed to insert");
+        let locations: Vec<(Page, PageOffset)> = {
+            let mut reader = registry.read::<User>();
+            let mut locations = vec![];
+            while let Some(next) = reader.try_next().expect("failed to read") {
+                locations.push((next.page, next.offset));
+            }
+            locations
+        };
+
+        // deleting every other record leaves non-adjacent segments
+        for (user, (page, offset)) in users.iter().zip(&locations).step_by(2) {
+            registry
+                .delete(user.clone(), *page, *offset)
+                .expect("failed to delete");
+        }
+        assert_eq!(registry.free_segment_count(), 5);
+
+        // deleting the others merges all the segments
+        for (user, (page, offset)) in users.iter().zip(&locations).skip(1).step_by(2) {
+            registry
+                .delete(user.clone(), *page, *offset)
+                .expect("failed to delete");
+        }
+        assert_eq!(registry.free_segment_count(), 1);
+
+        // a record larger than each deleted record reuses the merged space
+        let large_user = User {
+            id: 10u32.into(),
+            name: "User 10".repeat(4).into(),
+        };
+        let segment = registry
+            .free_segments_ledger
+            .find_reusable_segment(
+                &RawRecord::new(large_user).expect("failed to create raw record"),
+            )
+            .expect("should find the merged segment");
+        assert_eq!((segment.page, segment.offset), locations[0]);
+    }
+
     #[test]
     fn test_should_update_record_in_place() {
         let mut registry = registry();
@@ -428,6 +1222,553 @@ mod tests {
         assert_eq!(updated_record.record, new_record);
     }
 
+    #[test]
+    fn test_should_migrate_records_format() {
+        let mut registry = registry();
+
+        // write v0 records
+        for id in 0..10u32 {
+            let record = User {
+                id: id.into(),
+                name: format!("User {id}").into(),
+            };
+            registry
+                .insert_raw_record(
+                    RawRecord::with_version(record, RAW_RECORD_FORMAT_V0)
+                        .expect("failed to create raw record"),
+                )
+                .expect("failed to insert");
+        }
+
+        let mut reader = registry.read::<User>();
+        while let Some(next_record) = reader.try_next().expect("failed to read") {
+            assert_eq!(next_record.version, RAW_RECORD_FORMAT_V0);
+        }
+
+        // migrate
+        let migrated = registry
+            .migrate_format::<User>()
+            .expect("failed to migrate");
+        assert_eq!(migrated, 10);
+
+        // all records should be v1 now
+        let mut reader = registry.read::<User>();
+        let mut users = vec![];
+        while let Some(next_record) = reader.try_next().expect("failed to read") {
+            assert_eq!(next_record.version, RAW_RECORD_FORMAT_V1);
+            users.push(next_record.record);
+        }
+        users.sort_by_key(|user| user.id);
+        assert_eq!(users.len(), 10);
+        for (id, user) in users.into_iter().enumerate() {
+            assert_eq!(user.id.0, id as u32);
+            assert_eq!(user.name.0, format!("User {id}"));
+        }
+
+        // nothing left to migrate
+        let migrated = registry
+            .migrate_format::<User>()
+            .expect("failed to migrate");
+        assert_eq!(migrated, 0);
+    }
+
+    #[test]
+    fn test_should_get_table_stats() {
+        let table_pages = SCHEMA_REGISTRY
+            .with_borrow_mut(|sr| sr.register_table::<User>())
+            .expect("failed to register table");
+        let mut registry = TableRegistry::load(table_pages).expect("failed to load");
+
+        let stats = registry.stats::<User>().expect("failed to get stats");
+        assert_eq!(
+            stats,
+            TableStats {
+                pages: 0,
+                used_bytes: 0,
+                average_record_size: 0,
+                estimated_records: 0,
+            }
+        );
+
+        // records with the same size
+        for id in 100..150u32 {
+            registry
+                .insert(User {
+                    id: id.into(),
+                    name: format!("User {id}").into(),
+                })
+                .expect("failed to insert");
+        }
+        let record_size = RawRecord::new(User {
+            id: 100u32.into(),
+            name: "User 100".to_string().into(),
+        })
+        .expect("failed to create raw record")
+        .size() as u64;
+
+        let stats = registry.stats::<User>().expect("failed to get stats");
+        assert_eq!(stats.pages, 1);
+        assert_eq!(stats.average_record_size, record_size);
+        assert_eq!(stats.used_bytes, record_size * 50);
+        assert_eq!(stats.estimated_records, 50);
+
+        // deleted records are not counted
+        let next = registry
+            .read::<User>()
+            .try_next()
+            .expect("failed to read")
+            .expect("no record");
+        registry
+            .delete(next.record, next.page, next.offset)
+            .expect("failed to delete");
+        let stats = registry.stats::<User>().expect("failed to get stats");
+        assert_eq!(stats.estimated_records, 49);
+    }
+
+    #[test]
+    fn test_should_reuse_free_segments_after_restart() {
+        let table_pages = SCHEMA_REGISTRY
+            .with_borrow_mut(|sr| sr.register_table::<User>())
+            .expect("failed to register table");
+        let mut registry = TableRegistry::load(table_pages).expect("failed to load");
+
+        // insert records
+        for id in 0..10u32 {
+            registry
+                .insert(User {
+                    id: id.into(),
+                    name: format!("User {id}").into(),
+                })
+                .expect("failed to insert");
+        }
+
+        // delete some of them
+        let mut deleted = vec![];
+        let mut reader = registry.read::<User>();
+        while let Some(next) = reader.try_next().expect("failed to read") {
+            if next.record.id.0 % 2 == 0 {
+                deleted.push(next);
+            }
+        }
+        for next in &deleted {
+            registry
+                .delete(next.record.clone(), next.page, next.offset)
+                .expect("failed to delete");
+        }
+        drop(registry);
+
+        // simulate a restart by reloading the memory manager and the schema registry from the same memory
+        let provider = MEMORY_MANAGER.with_borrow(|mm| mm.provider.clone());
+        MEMORY_MANAGER.with_borrow_mut(|mm| *mm = MemoryManager::init(provider));
+        SCHEMA_REGISTRY.with_borrow_mut(|sr| {
+            *sr = SchemaRegistry::load().expect("failed to reload schema registry")
+        });
+        let pages_before = MEMORY_MANAGER.with_borrow(|mm| mm.provider.pages());
+
+        let table_pages = SCHEMA_REGISTRY
+            .with_borrow(|sr| sr.table_registry_page::<User>())
+            .expect("table should be registered after restart");
+        let mut registry = TableRegistry::load(table_pages).expect("failed to reload");
+
+        // insert records with the same size as the deleted ones
+        for next in &deleted {
+            let id = next.record.id.0 + 10;
+            registry
+                .insert(User {
+                    id: id.into(),
+                    name: format!("User {}", id - 10).into(),
+                })
+                .expect("failed to insert");
+        }
+
+        // no new page must have been allocated and the freed segments must have been reused
+        assert_eq!(
+            MEMORY_MANAGER.with_borrow(|mm| mm.provider.pages()),
+            pages_before
+        );
+        let mut reader = registry.read::<User>();
+        let mut reused = vec![];
+        while let Some(next) = reader.try_next().expect("failed to read") {
+            if next.record.id.0 >= 10 {
+                reused.push((next.page, next.offset));
+            }
+        }
+        let mut freed = deleted
+            .iter()
+            .map(|next| (next.page, next.offset))
+            .collect::<Vec<_>>();
+        freed.sort();
+        reused.sort();
+        assert_eq!(reused, freed);
+    }
+
+    #[test]
+    fn test_should_gc_empty_pages() {
+        let mut registry = registry();
+
+        // fill the first page
+        let mut id = 0u32;
+        while registry.page_ledger.page_count() < 2 {
+            registry
+                .insert(User {
+                    id: id.into(),
+                    name: format!("User {id}").into(),
+                })
+                .expect("failed to insert record");
+            id += 1;
+        }
+        let first_page = registry
+            .page_ledger
+            .pages()
+            .next()
+            .expect("should have a page")
+            .page;
+
+        // nothing to collect yet
+        assert_eq!(registry.gc_empty_pages().expect("failed to gc"), 0);
+
+        // delete all the records of the first page
+        let mut to_delete = vec![];
+        let mut reader = registry.read::<User>();
+        while let Some(next) = reader.try_next().expect("failed to read") {
+            if next.page == first_page {
+                to_delete.push(next);
+            }
+        }
+        for next in to_delete {
+            registry
+                .delete(next.record, next.page, next.offset)
+                .expect("failed to delete record");
+        }
+
+        assert_eq!(registry.gc_empty_pages().expect("failed to gc"), 1);
+        assert!(registry.page_ledger.page(first_page).is_none());
+        assert_eq!(registry.page_ledger.page_count(), 1);
+        assert_eq!(registry.free_segments_ledger.page_free_bytes(first_page), 0);
+
+        // the records of the other page are still readable
+        let mut reader = registry.read::<User>();
+        let mut count = 0;
+        while let Some(next) = reader.try_next().expect("failed to read") {
+            assert_ne!(next.page, first_page);
+            count += 1;
+        }
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn test_should_compact_table_registry() {
+        let mut registry = registry();
+        let user = |id: u32| User {
+            id: id.into(),
+            name: format!("User {id} {}", "x".repeat(200)).into(),
+        };
+        registry
+            .insert_batch((0..500u32).map(user))
+            .expect("failed to insert records");
+
+        // delete every other record
+        let to_delete = registry
+            .read::<User>()
+            .filter_map(|next| {
+                next.map(|next| (next.record.id.0 % 2 == 0).then_some(next))
+                    .transpose()
+            })
+            .collect::<MemoryResult<Vec<_>>>()
+            .expect("failed to read");
+        assert_eq!(to_delete.len(), 250);
+        for next in to_delete {
+            registry
+                .delete(next.record, next.page, next.offset)
+                .expect("failed to delete record");
+        }
+        let pages_before = registry.page_count();
+        let free_bytes = registry.free_segments_ledger.free_bytes();
+
+        let stats = registry.compact().expect("failed to compact");
+        assert_eq!(stats.pages_before, pages_before);
+        assert!(stats.pages_after < stats.pages_before);
+        assert_eq!(stats.pages_after, registry.page_count());
+        assert_eq!(stats.bytes_reclaimed, free_bytes);
+        assert_eq!(registry.free_segment_count(), 0);
+
+        // the remaining records are all there, undamaged and packed
+        let read_all = |registry: &TableRegistry| {
+            registry
+                .read::<User>()
+                .collect::<MemoryResult<Vec<_>>>()
+                .expect("failed to read")
+        };
+        let records = read_all(&registry);
+        assert_eq!(
+            records
+                .iter()
+                .map(|next| next.record.clone())
+                .collect::<Vec<_>>(),
+            (0..500u32)
+                .filter(|id| id % 2 == 1)
+                .map(user)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(records[0].offset, 0);
+        assert_eq!(registry.used_bytes(), {
+            let page_size = MEMORY_MANAGER.with_borrow(|mm| mm.page_size());
+            registry
+                .page_ledger
+                .pages()
+                .map(|page_record| page_size - page_record.free)
+                .sum::<u64>()
+        });
+
+        // compacting again doesn't move anything
+        assert_eq!(
+            registry.compact().expect("failed to compact"),
+            CompactionStats {
+                pages_before: stats.pages_after,
+                pages_after: stats.pages_after,
+                bytes_reclaimed: 0,
+            }
+        );
+        assert_eq!(read_all(&registry), records);
+
+        // new records are appended after the compacted ones
+        registry.insert(user(500)).expect("failed to insert record");
+        assert_eq!(read_all(&registry).len(), 251);
+    }
+
+    #[test]
+    fn test_should_compact_empty_table_registry() {
+        let mut registry = registry();
+        registry
+            .insert(User {
+                id: 1u32.into(),
+                name: "Alice".to_string().into(),
+            })
+            .expect("failed to insert record");
+        let next = registry
+            .read::<User>()
+            .try_next()
+            .expect("failed to read")
+            .expect("should have a record");
+        registry
+            .delete(next.record, next.page, next.offset)
+            .expect("failed to delete record");
+
+        let stats = registry.compact().expect("failed to compact");
+        assert_eq!(stats.pages_before, 1);
+        assert_eq!(stats.pages_after, 0);
+        assert_eq!(registry.page_count(), 0);
+        assert_eq!(registry.free_segment_count(), 0);
+        assert!(
+            registry
+                .read::<User>()
+                .try_next()
+                .expect("failed to read")
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_should_drop_pages() {
+        let mut registry = registry();
+
+        let mut id = 0u32;
+        while registry.page_ledger.page_count() < 2 {
+            registry
+                .insert(User {
+                    id: id.into(),
+                    name: format!("User {id}").into(),
+                })
+                .expect("failed to insert record");
+            id += 1;
+        }
+        let mut reader = registry.read::<User>();
+        let next = reader
+            .try_next()
+            .expect("failed to read")
+            .expect("should have a record");
+        registry
+            .delete(next.record, next.page, next.offset)
+            .expect("failed to delete record");
+
+        assert_eq!(registry.drop_pages().expect("failed to drop pages"), 2);
+        assert_eq!(registry.page_ledger.page_count(), 0);
+        assert_eq!(registry.free_segments_ledger.free_bytes(), 0);
+        assert!(
+            registry
+                .read::<User>()
+                .try_next()
+                .expect("failed to read")
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_should_bulk_export_and_load_records() {
+        let mut registry = registry();
+        registry
+            .insert_batch((0..1000u32).map(|id| User {
+                id: id.into(),
+                name: format!("User {id}").into(),
+            }))
+            .expect("failed to insert records");
+        let read_all = |registry: &TableRegistry| {
+            let mut reader = registry.read::<User>();
+            let mut users = vec![];
+            while let Some(next) = reader.try_next().expect("failed to read") {
+                users.push(next.record);
+            }
+            users
+        };
+        let users = read_all(&registry);
+
+        let dump = registry.bulk_export().expect("failed to export");
+        registry.drop_pages().expect("failed to drop pages");
+        assert!(read_all(&registry).is_empty());
+
+        assert_eq!(registry.bulk_load(&dump).expect("failed to load"), 1000);
+        assert_eq!(read_all(&registry), users);
+        assert_eq!(registry.bulk_export().expect("failed to export"), dump);
+    }
+
+    #[test]
+    fn test_should_estimate_exact_record_size_of_fixed_schema() {
+        let booking = Booking {
+            id: 1.into(),
+            start_date: Date::from_ymd(2025, 1, 1).expect("invalid date"),
+            end_date: Date::from_ymd(2025, 1, 7).expect("invalid date"),
+        };
+
+        assert_eq!(
+            TableRegistry::estimate_max_record_size(Booking::columns()),
+            RawRecord::new(booking)
+                .expect("failed to create raw record")
+                .size()
+        );
+    }
+
+    #[test]
+    fn test_should_estimate_record_size_with_samples() {
+        let users = (0..1000u32)
+            .map(|id| User {
+                id: id.into(),
+                name: format!("User {id}").into(),
+            })
+            .collect::<Vec<_>>();
+        let average = users
+            .iter()
+            .map(|user| {
+                RawRecord::new(user.clone())
+                    .expect("failed to create raw record")
+                    .size() as f64
+            })
+            .sum::<f64>()
+            / users.len() as f64;
+
+        let samples = users.iter().step_by(64).cloned().collect::<Vec<_>>();
+        let estimate = TableRegistry::estimate_record_size_with_samples(&samples) as f64;
+        assert!((estimate - average).abs() / average < 0.1);
+        assert_eq!(
+            TableRegistry::estimate_record_size_with_samples::<User>(&[]),
+            0
+        );
+    }
+
+    #[test]
+    fn test_should_not_bulk_load_invalid_records() {
+        let mut registry = registry();
+        registry
+            .insert(User {
+                id: 1u32.into(),
+                name: "Alice".to_string().into(),
+            })
+            .expect("failed to insert record");
+        let dump = registry.bulk_export().expect("failed to export");
+
+        let mut bad_header = dump.clone();
+        bad_header[0] = 0x42;
+        assert!(matches!(
+            registry.bulk_load(&bad_header),
+            Err(MemoryError::DecodeError(DecodeError::BadRawRecordHeader))
+        ));
+        assert!(matches!(
+            registry.bulk_load(&dump[..dump.len() - 1]),
+            Err(MemoryError::DecodeError(DecodeError::TooShort))
+        ));
+        assert_eq!(
+            registry
+                .read::<User>()
+                .count_remaining()
+                .expect("failed to count"),
+            1
+        );
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_should_compress_records() {
+        let users = (0..100u32)
+            .map(|id| User {
+                id: id.into(),
+                name: format!("User {id} ").repeat(20).into(),
+            })
+            .collect::<Vec<_>>();
+
+        let mut plain_registry = registry();
+        plain_registry
+            .insert_batch(users.clone())
+            .expect("failed to insert");
+        let mut compressed_registry = registry().with_compression(true);
+        compressed_registry
+            .insert_batch(users.clone())
+            .expect("failed to insert");
+
+        // the ledgers account for the compressed size of the records
+        let plain_bytes = plain_registry.used_bytes();
+        let compressed_bytes = compressed_registry.used_bytes();
+        assert!(
+            compressed_bytes < plain_bytes,
+            "compressed records take {compressed_bytes} bytes, uncompressed {plain_bytes}"
+        );
+        let expected_bytes = users
+            .iter()
+            .map(|user| {
+                RawRecord::new(user.clone())
+                    .expect("failed to create raw record")
+                    .with_compression(true)
+                    .size() as u64
+            })
+            .sum::<u64>();
+        assert_eq!(compressed_bytes, expected_bytes);
+
+        let records = compressed_registry
+            .read::<User>()
+            .collect::<MemoryResult<Vec<_>>>()
+            .expect("failed to read");
+        assert!(records.iter().all(|next| next.compressed));
+        assert_eq!(
+            records
+                .iter()
+                .map(|next| next.record.clone())
+                .collect::<Vec<_>>(),
+            users
+        );
+
+        // deleting a compressed record frees its compressed size
+        let first = &records[0];
+        compressed_registry
+            .delete(first.record.clone(), first.page, first.offset)
+            .expect("failed to delete");
+        let first_size = RawRecord::new(first.record.clone())
+            .expect("failed to create raw record")
+            .with_compression(true)
+            .size() as u64;
+        assert_eq!(
+            compressed_registry.used_bytes(),
+            compressed_bytes - first_size
+        );
+    }
+
     fn registry() -> TableRegistry {
         let page_ledger_page = MEMORY_MANAGER
             .with_borrow_mut(|mm| mm.allocate_page())
@@ -2,7 +2,7 @@ mod free_segment;
 
 pub use self::free_segment::FreeSegment;
 use self::free_segment::FreeSegmentsTable;
-use crate::memory::{Encode, MEMORY_MANAGER, MemoryResult, Page, PageOffset};
+use crate::memory::{Encode, MEMORY_MANAGER, MemoryResult, Page, PageOffset, WriteCategory};
 
 /// The free segments ledger keeps track of free segments in the [`FreeSegmentsTable`] registry.
 ///
@@ -19,6 +19,7 @@ use crate::memory::{Encode, MEMORY_MANAGER, MemoryResult, Page, PageOffset};
 ///
 /// - Storing metadata about free segments whenever a record is deleted or moved
 /// - Find a suitable location for new records by reusing space from free segments
+#[derive(Debug)]
 pub struct FreeSegmentsLedger {
     /// The page where the free segments ledger is stored in memory.
     free_segments_page: Page,
@@ -83,9 +84,52 @@ impl FreeSegmentsLedger {
         self.write()
     }
 
+    /// Returns the total size in bytes of the free segments.
+    pub fn free_bytes(&self) -> u64 {
+        self.table
+            .records
+            .iter()
+            .map(|segment| segment.size as u64)
+            .sum()
+    }
+
+    /// Returns the amount of free segments.
+    pub fn segment_count(&self) -> usize {
+        self.table.records.len()
+    }
+
+    /// Returns the total size in bytes of the free segments in the given page.
+    pub fn page_free_bytes(&self, page: Page) -> u64 {
+        self.table
+            .records
+            .iter()
+            .filter(|segment| segment.page == page)
+            .map(|segment| segment.size as u64)
+            .sum()
+    }
+
+    /// Removes all the free segments in the given page and writes the table back to memory.
+    pub fn remove_page_segments(&mut self, page: Page) -> MemoryResult<()> {
+        self.table.records.retain(|segment| segment.page != page);
+        self.write()
+    }
+
+    /// Removes all the free segments and writes the table back to memory.
+    pub fn clear(&mut self) -> MemoryResult<()> {
+        self.table.records.clear();
+        self.write()
+    }
+
     /// Writes the current state of the free segments table back to memory.
     fn write(&self) -> MemoryResult<()> {
-        MEMORY_MANAGER.with_borrow_mut(|mm| mm.write_at(self.free_segments_page, 0, &self.table))
+        MEMORY_MANAGER.with_borrow_mut(|mm| {
+            mm.write_metadata_at(
+                WriteCategory::FreeSegments,
+                self.free_segments_page,
+                0,
+                &self.table,
+            )
+        })
     }
 }
 
@@ -222,6 +266,30 @@ mod tests {
         assert!(record.is_none());
     }
 
+    #[test]
+    fn test_should_clear_free_segments() {
+        let page = MEMORY_MANAGER
+            .with_borrow_mut(|mm| mm.allocate_page())
+            .expect("Failed to allocate page");
+        let mut ledger =
+            FreeSegmentsLedger::load(page).expect("Failed to load DeletedRecordsLedger");
+        let record = TestRecord { data: [0; 100] };
+        ledger
+            .insert_free_segment(4, 0, &record)
+            .expect("Failed to insert deleted record");
+        ledger
+            .insert_free_segment(5, 100, &record)
+            .expect("Failed to insert deleted record");
+
+        ledger.clear().expect("Failed to clear free segments");
+        assert_eq!(ledger.segment_count(), 0);
+
+        // verify it's written (reload)
+        let reloaded_ledger =
+            FreeSegmentsLedger::load(page).expect("Failed to load DeletedRecordsLedger");
+        assert_eq!(reloaded_ledger.free_bytes(), 0);
+    }
+
     #[test]
     fn test_should_commit_reused_space_creating_a_new_record() {
         let page = MEMORY_MANAGER
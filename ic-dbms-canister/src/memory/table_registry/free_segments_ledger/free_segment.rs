@@ -17,37 +17,37 @@ pub struct FreeSegment {
     pub size: MSize,
 }
 
-/// Represents an adjacent free segment, either before or after a given segment.
-#[derive(Debug)]
-enum AdjacentSegment {
-    Before(FreeSegment),
-    After(FreeSegment),
-}
-
 impl FreeSegmentsTable {
-    /// Inserts a new [`FreeSegment`] into the table.
+    /// Inserts a new [`FreeSegment`] into the table, merging it with the adjacent free segments.
     pub fn insert_free_segment(&mut self, page: Page, offset: PageOffset, size: MSize) {
-        // check for adjacent segments and merge if found
-        if let Some(adjacent) = self.has_adjacent_segment(page, offset, size) {
-            match adjacent {
-                AdjacentSegment::Before(seg) => {
-                    // Merge with the segment before
-                    let new_size = seg.size.saturating_add(size);
-                    self.remove(seg.page, seg.offset, seg.size, seg.size);
-                    self.insert_free_segment(page, seg.offset, new_size);
-                }
-                AdjacentSegment::After(seg) => {
-                    // Merge with the segment after
-                    let new_size = size.saturating_add(seg.size);
-                    self.remove(seg.page, seg.offset, seg.size, seg.size);
-                    self.insert_free_segment(page, offset, new_size);
-                }
+        self.records.push(FreeSegment { page, offset, size });
+        self.coalesce();
+    }
+
+    /// Sorts the free segments by page and offset, merging the adjacent segments of the same page,
+    /// so that a record larger than each of them can reuse their space.
+    ///
+    /// Segments whose merged size would overflow [`MSize`] are kept apart.
+    pub fn coalesce(&mut self) {
+        self.records
+            .sort_unstable_by_key(|segment| (segment.page, segment.offset));
+
+        let mut coalesced: Vec<FreeSegment> = Vec::with_capacity(self.records.len());
+        for segment in self.records.drain(..) {
+            let merged_size = coalesced
+                .last()
+                .filter(|last| {
+                    last.page == segment.page
+                        && u32::from(last.offset) + u32::from(last.size)
+                            == u32::from(segment.offset)
+                })
+                .and_then(|last| last.size.checked_add(segment.size));
+            match (coalesced.last_mut(), merged_size) {
+                (Some(last), Some(size)) => last.size = size,
+                _ => coalesced.push(segment),
             }
-        } else {
-            // No adjacent segments found, insert as is
-            let record = FreeSegment { page, offset, size };
-            self.records.push(record);
         }
+        self.records = coalesced;
     }
 
     /// Finds a free segment that matches the given predicate.
@@ -61,60 +61,25 @@ impl FreeSegmentsTable {
     /// Removes a free segment that matches the given parameters.
     ///
     /// If `used_size` is less than `size`, the old record is removed, but a new record is added
-    /// for the remaining free space.
+    /// for the remaining free space, keeping the segments sorted.
     pub fn remove(&mut self, page: Page, offset: PageOffset, size: MSize, used_size: MSize) {
         if let Some(pos) = self
             .records
             .iter()
             .position(|r| r.page == page && r.offset == offset && r.size == size)
         {
-            self.records.swap_remove(pos);
-
-            // If there is remaining space, add a new record for it.
+            // If there is remaining space, replace the record with the remaining space.
             if used_size < size {
-                let remaining_size = size.saturating_sub(used_size);
-                let new_offset = offset.saturating_add(used_size);
-                let new_record = FreeSegment {
+                self.records[pos] = FreeSegment {
                     page,
-                    offset: new_offset,
-                    size: remaining_size,
+                    offset: offset.saturating_add(used_size),
+                    size: size.saturating_sub(used_size),
                 };
-                self.records.push(new_record);
+            } else {
+                self.records.remove(pos);
             }
         }
     }
-
-    /// Checks for adjacent free segments before or after the given segment.
-    fn has_adjacent_segment(
-        &self,
-        page: Page,
-        offset: PageOffset,
-        size: MSize,
-    ) -> Option<AdjacentSegment> {
-        self.has_adjacent_segment_before(page, offset)
-            .or_else(|| self.has_adjacent_segment_after(page, offset, size))
-    }
-
-    /// Checks for an adjacent free segment before the given segment.
-    fn has_adjacent_segment_before(
-        &self,
-        page: Page,
-        offset: PageOffset,
-    ) -> Option<AdjacentSegment> {
-        self.find(|r| r.page == page && r.offset.saturating_add(r.size) == offset)
-            .map(AdjacentSegment::Before)
-    }
-
-    /// Checks for an adjacent free segment after the given segment.
-    fn has_adjacent_segment_after(
-        &self,
-        page: Page,
-        offset: PageOffset,
-        size: MSize,
-    ) -> Option<AdjacentSegment> {
-        self.find(|r| r.page == page && r.offset == offset.saturating_add(size))
-            .map(AdjacentSegment::After)
-    }
 }
 
 impl Encode for FreeSegmentsTable {
@@ -280,48 +245,107 @@ mod tests {
     }
 
     #[test]
-    fn test_should_find_adjacent_segment_before() {
+    fn test_should_insert_adjacent_segment() {
         let mut table = FreeSegmentsTable::default();
         table.insert_free_segment(1, 100, 50);
+        table.insert_free_segment(1, 150, 50); // Adjacent to the first
 
-        let adjacent = table.has_adjacent_segment_before(1, 150);
-        assert!(adjacent.is_some());
-        match adjacent.unwrap() {
-            AdjacentSegment::Before(seg) => {
-                assert_eq!(seg.page, 1);
-                assert_eq!(seg.offset, 100);
-                assert_eq!(seg.size, 50);
-            }
-            _ => panic!("Expected AdjacentSegment::Before"),
-        }
+        assert_eq!(table.records.len(), 1);
+        assert_eq!(table.records[0].page, 1);
+        assert_eq!(table.records[0].offset, 100);
+        assert_eq!(table.records[0].size, 100); // Merged size
     }
 
     #[test]
-    fn test_should_find_adjacent_segment_after() {
+    fn test_should_merge_segment_between_two_segments() {
         let mut table = FreeSegmentsTable::default();
+        table.insert_free_segment(1, 200, 50);
         table.insert_free_segment(1, 100, 50);
+        table.insert_free_segment(2, 150, 50); // same offset, but another page
+        assert_eq!(table.records.len(), 3);
 
-        let adjacent = table.has_adjacent_segment_after(1, 0, 100);
-        assert!(adjacent.is_some());
-        match adjacent.unwrap() {
-            AdjacentSegment::After(seg) => {
-                assert_eq!(seg.page, 1);
-                assert_eq!(seg.offset, 100);
-                assert_eq!(seg.size, 50);
-            }
-            _ => panic!("Expected AdjacentSegment::After"),
-        }
+        table.insert_free_segment(1, 150, 50);
+        assert_eq!(
+            table.records,
+            vec![
+                FreeSegment {
+                    page: 1,
+                    offset: 100,
+                    size: 150,
+                },
+                FreeSegment {
+                    page: 2,
+                    offset: 150,
+                    size: 50,
+                },
+            ]
+        );
     }
 
     #[test]
-    fn test_should_insert_adjacent_segment() {
-        let mut table = FreeSegmentsTable::default();
-        table.insert_free_segment(1, 100, 50);
-        table.insert_free_segment(1, 150, 50); // Adjacent to the first
+    fn test_should_coalesce_free_segments() {
+        let mut table = FreeSegmentsTable {
+            records: vec![
+                FreeSegment {
+                    page: 2,
+                    offset: 0,
+                    size: 10,
+                },
+                FreeSegment {
+                    page: 1,
+                    offset: 20,
+                    size: 10,
+                },
+                FreeSegment {
+                    page: 1,
+                    offset: 0,
+                    size: 10,
+                },
+                FreeSegment {
+                    page: 1,
+                    offset: 10,
+                    size: 10,
+                },
+                FreeSegment {
+                    page: 1,
+                    offset: 40,
+                    size: 10,
+                },
+            ],
+        };
 
+        table.coalesce();
+        assert_eq!(
+            table.records,
+            vec![
+                FreeSegment {
+                    page: 1,
+                    offset: 0,
+                    size: 30,
+                },
+                FreeSegment {
+                    page: 1,
+                    offset: 40,
+                    size: 10,
+                },
+                FreeSegment {
+                    page: 2,
+                    offset: 0,
+                    size: 10,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_should_not_coalesce_segments_overflowing_size() {
+        let mut table = FreeSegmentsTable::default();
+        table.insert_free_segment(1, 0, MSize::MAX - 10);
+        table.insert_free_segment(1, MSize::MAX - 10, 10);
         assert_eq!(table.records.len(), 1);
-        assert_eq!(table.records[0].page, 1);
-        assert_eq!(table.records[0].offset, 100);
-        assert_eq!(table.records[0].size, 100); // Merged size
+        assert_eq!(table.records[0].size, MSize::MAX);
+
+        table.insert_free_segment(1, MSize::MAX, 1);
+        assert_eq!(table.records.len(), 2);
     }
 }
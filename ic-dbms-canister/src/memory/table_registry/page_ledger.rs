@@ -1,8 +1,11 @@
-mod page_table;
+mod compact_page_table;
 
-use self::page_table::PageTable;
-use crate::memory::table_registry::page_ledger::page_table::PageRecord;
-use crate::memory::{Encode, MEMORY_MANAGER, MemoryResult, Page, PageOffset};
+use self::compact_page_table::CompactPageTable;
+pub use self::compact_page_table::PageRecord;
+use crate::memory::{Encode, MEMORY_MANAGER, MSize, MemoryResult, Page, PageOffset, WriteCategory};
+
+/// A record larger than the record size estimate times this factor logs a warning.
+const RECORD_SIZE_WARNING_FACTOR: u64 = 4;
 
 /// Takes care of storing the pages for each table
 #[derive(Debug)]
@@ -10,7 +13,9 @@ pub struct PageLedger {
     /// The page where the ledger is stored in memory.
     ledger_page: Page,
     /// The pages table.
-    pages: PageTable,
+    pages: CompactPageTable,
+    /// Estimated size of the records, if known.
+    record_size_estimate: Option<MSize>,
 }
 
 impl PageLedger {
@@ -19,9 +24,15 @@ impl PageLedger {
         Ok(Self {
             pages: MEMORY_MANAGER.with_borrow(|mm| mm.read_at(page, 0))?,
             ledger_page: page,
+            record_size_estimate: None,
         })
     }
 
+    /// Sets the estimated size of the records, used to warn about records much larger than expected.
+    pub fn set_record_size_estimate(&mut self, estimate: MSize) {
+        self.record_size_estimate = Some(estimate);
+    }
+
     /// Get the page number and the offset to store the next record.
     ///
     /// It usually returns the first page with enough free space.
@@ -36,7 +47,18 @@ impl PageLedger {
     {
         let required_size = record.size() as u64;
         let page_size = MEMORY_MANAGER.with_borrow(|mm| mm.page_size());
-        // check if record can fit in a page
+        if let Some(estimate) = self
+            .record_size_estimate
+            .filter(|estimate| required_size > u64::from(*estimate) * RECORD_SIZE_WARNING_FACTOR)
+        {
+            crate::dbms_log!(
+                WARN,
+                "record much larger than estimated";
+                "size" => required_size,
+                "estimate" => estimate
+            );
+        }
+        // check if record can fit in the usable size of a page
         if required_size > page_size {
             return Err(ic_dbms_api::prelude::MemoryError::DataTooLarge {
                 page_size,
@@ -44,28 +66,28 @@ impl PageLedger {
             });
         }
 
-        // iter ledger pages to find a page with enough free space
-        let next_page = self.pages.pages.iter().find(|page_record| {
-            let taken = page_size.saturating_sub(page_record.free);
-            taken + required_size <= page_size
-        });
-        // if page found, return it
-        if let Some(page_record) = next_page {
+        // scan ledger runs to find a page with enough free space
+        if let Some(page_record) = self.pages.find_free(required_size) {
             let offset = page_size.saturating_sub(page_record.free) as PageOffset;
             return Ok((page_record.page, offset));
         }
 
         // otherwise allocate a new one
         let new_page = MEMORY_MANAGER.with_borrow_mut(|mm| mm.allocate_page())?;
-        // add to ledger
-        self.pages.pages.push(self::page_table::PageRecord {
-            page: new_page,
-            free: page_size, // NOTE: we commit later, so full free space
-        });
+        // add to ledger; NOTE: we commit later, so full free space
+        self.pages.push(new_page, page_size);
 
         Ok((new_page, 0))
     }
 
+    /// Returns whether the given record fits in the free space of a page, without allocating a new one.
+    pub fn has_space_for<R>(&self, record: &R) -> bool
+    where
+        R: Encode,
+    {
+        self.pages.find_free(record.size() as u64).is_some()
+    }
+
     /// Commits the allocation of a record in the given page.
     ///
     /// This will commit the eventual allocated page
@@ -74,7 +96,7 @@ impl PageLedger {
     where
         R: Encode,
     {
-        if let Some(page_record) = self.pages.pages.iter_mut().find(|pr| pr.page == page) {
+        if let Some(page_record) = self.pages.get(page) {
             let record_size = record.size() as u64;
             if page_record.free < record_size {
                 return Err(ic_dbms_api::prelude::MemoryError::DataTooLarge {
@@ -82,7 +104,8 @@ impl PageLedger {
                     requested: record_size,
                 });
             }
-            page_record.free = page_record.free.saturating_sub(record_size);
+            self.pages
+                .set_free(page, page_record.free.saturating_sub(record_size));
             self.write()?;
             return Ok(());
         }
@@ -90,14 +113,63 @@ impl PageLedger {
         Err(ic_dbms_api::prelude::MemoryError::OutOfBounds)
     }
 
-    /// Returns the list of pages in the ledger.
-    pub fn pages(&self) -> &[PageRecord] {
-        &self.pages.pages
+    /// Sets the bytes committed in the given page, as if records taking `used` bytes were written
+    /// from its beginning, and writes the updated ledger to memory.
+    ///
+    /// Returns whether the page belonged to the ledger.
+    pub fn set_used(&mut self, page: Page, used: u64) -> MemoryResult<bool> {
+        let page_size = MEMORY_MANAGER.with_borrow(|mm| mm.page_size());
+        if !self.pages.set_free(page, page_size.saturating_sub(used)) {
+            return Ok(false);
+        }
+        self.write()?;
+
+        Ok(true)
+    }
+
+    /// Removes the given page from the ledger and writes the updated ledger to memory.
+    ///
+    /// Returns whether the page belonged to the ledger.
+    pub fn remove_page(&mut self, page: Page) -> MemoryResult<bool> {
+        if !self.pages.remove(page) {
+            return Ok(false);
+        }
+        self.write()?;
+
+        Ok(true)
+    }
+
+    /// Iterates over the pages in the ledger, sorted by page.
+    pub fn pages(&self) -> impl Iterator<Item = PageRecord> + '_ {
+        self.pages.iter()
+    }
+
+    /// Returns the given page, if it belongs to the ledger.
+    pub fn page(&self, page: Page) -> Option<PageRecord> {
+        self.pages.get(page)
+    }
+
+    /// Returns the first page of the ledger after the given page.
+    pub fn next_page(&self, page: Page) -> Option<PageRecord> {
+        self.pages.next_after(page)
+    }
+
+    /// Returns the amount of pages in the ledger.
+    pub fn page_count(&self) -> usize {
+        self.pages.pages_len()
+    }
+
+    /// Returns the amount of entries stored by the ledger, which is smaller than the amount of pages
+    /// when consecutive pages have the same free space.
+    pub fn estimated_entry_count(&self) -> usize {
+        self.pages.ranges_len()
     }
 
     /// Write the page ledger to memory.
     fn write(&self) -> MemoryResult<()> {
-        MEMORY_MANAGER.with_borrow_mut(|mm| mm.write_at(self.ledger_page, 0, &self.pages))
+        MEMORY_MANAGER.with_borrow_mut(|mm| {
+            mm.write_metadata_at(WriteCategory::PageLedger, self.ledger_page, 0, &self.pages)
+        })
     }
 }
 
@@ -106,7 +178,6 @@ mod tests {
 
     use super::*;
     use crate::memory::provider::{HeapMemoryProvider, MemoryProvider};
-    use crate::memory::table_registry::page_ledger::page_table::PageRecord;
     use crate::memory::{DataSize, MSize};
 
     #[test]
@@ -114,28 +185,18 @@ mod tests {
         let page = MEMORY_MANAGER
             .with_borrow_mut(|mm| mm.allocate_page())
             .unwrap();
+        let mut pages = CompactPageTable::default();
+        pages.push(10, 100);
+        pages.push(11, 200);
+        pages.push(12, 300);
         let page_ledger = PageLedger {
-            pages: PageTable {
-                pages: vec![
-                    PageRecord {
-                        page: 10,
-                        free: 100,
-                    },
-                    PageRecord {
-                        page: 11,
-                        free: 200,
-                    },
-                    PageRecord {
-                        page: 12,
-                        free: 300,
-                    },
-                ],
-            },
+            pages,
             ledger_page: page,
+            record_size_estimate: None,
         };
         page_ledger.write().expect("failed to write page ledger");
         let loaded_ledger = PageLedger::load(page).expect("failed to load page ledger");
-        assert_eq!(page_ledger.pages.pages, loaded_ledger.pages.pages);
+        assert_eq!(page_ledger.pages, loaded_ledger.pages);
     }
 
     #[test]
@@ -145,7 +206,7 @@ mod tests {
             .with_borrow_mut(|mm| mm.allocate_page())
             .expect("failed to allocate ledger page");
         let mut page_ledger = PageLedger::load(ledger_page).expect("failed to load page ledger");
-        assert!(page_ledger.pages.pages.is_empty());
+        assert_eq!(page_ledger.page_count(), 0);
 
         // create test record
         let record = TestRecord { data: [1; 100] };
@@ -153,25 +214,19 @@ mod tests {
         let (page, offset) = page_ledger
             .get_page_and_offset_for_record(&record)
             .expect("failed to get page for record");
-        assert_eq!(page_ledger.pages.pages.len(), 1);
-        assert_eq!((page_ledger.pages.pages[0].page, 0), (page, offset));
-        assert_eq!(
-            page_ledger.pages.pages[0].free,
-            HeapMemoryProvider::PAGE_SIZE
-        );
+        assert_eq!(page_ledger.page_count(), 1);
+        assert_eq!((first_page(&page_ledger).page, 0), (page, offset));
+        assert_eq!(first_page(&page_ledger).free, page_size());
 
         // commit record allocation
         page_ledger
             .commit(page, &record)
             .expect("failed to commit record allocation");
-        assert_eq!(
-            page_ledger.pages.pages[0].free,
-            HeapMemoryProvider::PAGE_SIZE - 100
-        );
+        assert_eq!(first_page(&page_ledger).free, page_size() - 100);
 
         // reload
         let reloaded_ledger = PageLedger::load(ledger_page).expect("failed to load page ledger");
-        assert_eq!(page_ledger.pages.pages, reloaded_ledger.pages.pages);
+        assert_eq!(page_ledger.pages, reloaded_ledger.pages);
     }
 
     #[test]
@@ -181,7 +236,7 @@ mod tests {
             .with_borrow_mut(|mm| mm.allocate_page())
             .expect("failed to allocate ledger page");
         let mut page_ledger = PageLedger::load(ledger_page).expect("failed to load page ledger");
-        assert!(page_ledger.pages.pages.is_empty());
+        assert_eq!(page_ledger.page_count(), 0);
 
         // create test record
         let record = TestRecord { data: [1; 100] };
@@ -189,32 +244,98 @@ mod tests {
         let (page, offset) = page_ledger
             .get_page_and_offset_for_record(&record)
             .expect("failed to get page for record");
-        assert_eq!(page_ledger.pages.pages.len(), 1);
-        assert_eq!((page_ledger.pages.pages[0].page, 0), (page, offset));
-        assert_eq!(
-            page_ledger.pages.pages[0].free,
-            HeapMemoryProvider::PAGE_SIZE
-        );
+        assert_eq!(page_ledger.page_count(), 1);
+        assert_eq!((first_page(&page_ledger).page, 0), (page, offset));
+        assert_eq!(first_page(&page_ledger).free, page_size());
 
         // commit record allocation
         page_ledger
             .commit(page, &record)
             .expect("failed to commit record allocation");
-        assert_eq!(
-            page_ledger.pages.pages[0].free,
-            HeapMemoryProvider::PAGE_SIZE - 100
-        );
+        assert_eq!(first_page(&page_ledger).free, page_size() - 100);
 
         // get page for another record
         let (page, offset) = page_ledger
             .get_page_and_offset_for_record(&record)
             .expect("failed to get page for record");
-        assert_eq!(page_ledger.pages.pages.len(), 1);
-        assert_eq!((page_ledger.pages.pages[0].page, 100), (page, offset));
+        assert_eq!(page_ledger.page_count(), 1);
+        assert_eq!((first_page(&page_ledger).page, 100), (page, offset));
+        assert_eq!(first_page(&page_ledger).free, page_size() - 100);
+    }
+
+    #[test]
+    fn test_should_track_many_pages_in_a_single_ledger_page() {
+        let ledger_page = MEMORY_MANAGER
+            .with_borrow_mut(|mm| mm.allocate_page())
+            .expect("failed to allocate ledger page");
+        let mut page_ledger = PageLedger::load(ledger_page).expect("failed to load page ledger");
+
+        // each record takes almost a whole page
+        let record = LargeRecord;
+        let mut allocated_pages = Vec::with_capacity(200);
+        for _ in 0..200 {
+            let (page, offset) = page_ledger
+                .get_page_and_offset_for_record(&record)
+                .expect("failed to get page for record");
+            assert_eq!(offset, 0);
+            page_ledger
+                .commit(page, &record)
+                .expect("failed to commit record allocation");
+            allocated_pages.push(page);
+        }
+
+        assert_eq!(page_ledger.page_count(), 200);
+        assert_eq!(page_ledger.estimated_entry_count(), 1);
+        assert!(page_ledger.pages.size() as u64 <= page_size());
+
+        // reload and check all the pages are tracked
+        let reloaded_ledger = PageLedger::load(ledger_page).expect("failed to load page ledger");
+        let pages: Vec<PageRecord> = reloaded_ledger.pages().collect();
         assert_eq!(
-            page_ledger.pages.pages[0].free,
-            HeapMemoryProvider::PAGE_SIZE - 100
+            pages,
+            allocated_pages
+                .iter()
+                .map(|page| PageRecord {
+                    page: *page,
+                    free: page_size() - LargeRecord::LEN as u64,
+                })
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_should_set_used_bytes_of_page() {
+        let ledger_page = MEMORY_MANAGER
+            .with_borrow_mut(|mm| mm.allocate_page())
+            .expect("failed to allocate ledger page");
+        let mut page_ledger = PageLedger::load(ledger_page).expect("failed to load page ledger");
+        let record = TestRecord { data: [1; 100] };
+        let (page, _) = page_ledger
+            .get_page_and_offset_for_record(&record)
+            .expect("failed to get page for record");
+        page_ledger
+            .commit(page, &record)
+            .expect("failed to commit record allocation");
+
+        assert!(page_ledger.set_used(page, 40).expect("failed to set used"));
+        assert_eq!(first_page(&page_ledger).free, page_size() - 40);
+        assert!(
+            !page_ledger
+                .set_used(page + 1, 40)
+                .expect("failed to set used")
         );
+
+        // reload
+        let reloaded_ledger = PageLedger::load(ledger_page).expect("failed to load page ledger");
+        assert_eq!(page_ledger.pages, reloaded_ledger.pages);
+    }
+
+    fn page_size() -> u64 {
+        MEMORY_MANAGER.with_borrow(|mm| mm.page_size())
+    }
+
+    fn first_page(page_ledger: &PageLedger) -> PageRecord {
+        page_ledger.pages().next().expect("should have a page")
     }
 
     #[derive(Debug, Clone)]
@@ -242,4 +363,31 @@ mod tests {
             Ok(record)
         }
     }
+
+    /// A record taking almost a whole page.
+    #[derive(Debug, Clone)]
+    struct LargeRecord;
+
+    impl LargeRecord {
+        const LEN: MSize = (HeapMemoryProvider::PAGE_SIZE - 100) as MSize;
+    }
+
+    impl Encode for LargeRecord {
+        const SIZE: DataSize = DataSize::Fixed(Self::LEN);
+
+        fn size(&self) -> MSize {
+            Self::LEN
+        }
+
+        fn encode(&'_ self) -> std::borrow::Cow<'_, [u8]> {
+            std::borrow::Cow::Owned(vec![1; Self::LEN as usize])
+        }
+
+        fn decode(_data: std::borrow::Cow<[u8]>) -> crate::memory::MemoryResult<Self>
+        where
+            Self: Sized,
+        {
+            Ok(LargeRecord)
+        }
+    }
 }
@@ -0,0 +1,366 @@
+use crate::memory::{DataSize, Encode, MSize, Page};
+
+/// The list of pages in the page ledger, run-length encoded as [`PageRange`]s.
+///
+/// Consecutive pages with the same free space are stored as a single [`PageRange`],
+/// so a table with thousands of full pages only takes a few entries.
+/// Ranges are sorted by page and never overlap.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CompactPageTable {
+    ranges: Vec<PageRange>,
+}
+
+/// A run of consecutive pages with the same free space.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageRange {
+    /// First page of the run.
+    pub start_page: Page,
+    /// Amount of pages in the run.
+    pub count: u32,
+    /// Free space of each page of the run.
+    ///
+    /// Pages are merged only when their free space is equal, so this is exact for each page.
+    pub free: u64,
+}
+
+/// A page in the page ledger with its free space.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageRecord {
+    pub page: Page,
+    pub free: u64,
+}
+
+impl PageRange {
+    /// Creates a run made of a single page.
+    fn single(page: Page, free: u64) -> Self {
+        Self {
+            start_page: page,
+            count: 1,
+            free,
+        }
+    }
+
+    /// Returns the last page of the run.
+    fn end_page(&self) -> Page {
+        self.start_page + self.count - 1
+    }
+
+    /// Returns whether the run contains the given page.
+    fn contains(&self, page: Page) -> bool {
+        page >= self.start_page && page <= self.end_page()
+    }
+
+    /// Returns whether `other` immediately follows this run with the same free space.
+    fn can_merge(&self, other: &PageRange) -> bool {
+        self.free == other.free && self.end_page() + 1 == other.start_page
+    }
+
+    /// Returns the pages of the run.
+    fn pages(&self) -> impl Iterator<Item = PageRecord> + '_ {
+        (self.start_page..=self.end_page()).map(|page| PageRecord {
+            page,
+            free: self.free,
+        })
+    }
+}
+
+impl CompactPageTable {
+    /// Returns the amount of [`PageRange`]s stored.
+    pub fn ranges_len(&self) -> usize {
+        self.ranges.len()
+    }
+
+    /// Returns the amount of pages tracked.
+    pub fn pages_len(&self) -> usize {
+        self.ranges.iter().map(|range| range.count as usize).sum()
+    }
+
+    /// Iterates over the pages tracked, sorted by page.
+    pub fn iter(&self) -> impl Iterator<Item = PageRecord> + '_ {
+        self.ranges.iter().flat_map(PageRange::pages)
+    }
+
+    /// Returns the given page, if tracked.
+    pub fn get(&self, page: Page) -> Option<PageRecord> {
+        self.ranges
+            .iter()
+            .find(|range| range.contains(page))
+            .map(|range| PageRecord {
+                page,
+                free: range.free,
+            })
+    }
+
+    /// Returns the first tracked page after the given page.
+    pub fn next_after(&self, page: Page) -> Option<PageRecord> {
+        self.ranges
+            .iter()
+            .find(|range| range.end_page() > page)
+            .map(|range| PageRecord {
+                page: range.start_page.max(page + 1),
+                free: range.free,
+            })
+    }
+
+    /// Returns the first page with at least `required_size` bytes of free space.
+    pub fn find_free(&self, required_size: u64) -> Option<PageRecord> {
+        self.ranges
+            .iter()
+            .find(|range| range.free >= required_size)
+            .map(|range| PageRecord {
+                page: range.start_page,
+                free: range.free,
+            })
+    }
+
+    /// Appends a new page, which must follow all the tracked pages.
+    pub fn push(&mut self, page: Page, free: u64) {
+        let range = PageRange::single(page, free);
+        match self.ranges.last_mut() {
+            Some(last) if last.can_merge(&range) => last.count += 1,
+            _ => self.ranges.push(range),
+        }
+    }
+
+    /// Sets the free space of the given page, splitting and merging the runs as needed.
+    ///
+    /// Returns `false` if the page is not tracked.
+    pub fn set_free(&mut self, page: Page, free: u64) -> bool {
+        let Some(index) = self.ranges.iter().position(|range| range.contains(page)) else {
+            return false;
+        };
+        let range = self.ranges[index];
+        if range.free == free {
+            return true;
+        }
+
+        // split the run into the pages before, the page itself and the pages after
+        let mut replacement = Vec::with_capacity(3);
+        if page > range.start_page {
+            replacement.push(PageRange {
+                start_page: range.start_page,
+                count: page - range.start_page,
+                free: range.free,
+            });
+        }
+        replacement.push(PageRange::single(page, free));
+        if page < range.end_page() {
+            replacement.push(PageRange {
+                start_page: page + 1,
+                count: range.end_page() - page,
+                free: range.free,
+            });
+        }
+        let page_index = index + usize::from(page > range.start_page);
+        self.ranges.splice(index..=index, replacement);
+
+        // merge the page with the adjacent runs
+        if page_index + 1 < self.ranges.len()
+            && self.ranges[page_index].can_merge(&self.ranges[page_index + 1])
+        {
+            let next = self.ranges.remove(page_index + 1);
+            self.ranges[page_index].count += next.count;
+        }
+        if page_index > 0 && self.ranges[page_index - 1].can_merge(&self.ranges[page_index]) {
+            let current = self.ranges.remove(page_index);
+            self.ranges[page_index - 1].count += current.count;
+        }
+
+        true
+    }
+
+    /// Removes the given page, splitting its run as needed.
+    ///
+    /// Returns `false` if the page is not tracked.
+    pub fn remove(&mut self, page: Page) -> bool {
+        let Some(index) = self.ranges.iter().position(|range| range.contains(page)) else {
+            return false;
+        };
+        let range = self.ranges[index];
+
+        // keep the pages before and after the removed one
+        let mut replacement = Vec::with_capacity(2);
+        if page > range.start_page {
+            replacement.push(PageRange {
+                start_page: range.start_page,
+                count: page - range.start_page,
+                free: range.free,
+            });
+        }
+        if page < range.end_page() {
+            replacement.push(PageRange {
+                start_page: page + 1,
+                count: range.end_page() - page,
+                free: range.free,
+            });
+        }
+        self.ranges.splice(index..=index, replacement);
+
+        true
+    }
+}
+
+impl Encode for CompactPageTable {
+    const SIZE: DataSize = DataSize::Dynamic;
+
+    fn size(&self) -> MSize {
+        // 4 bytes for len + (16 bytes per range)
+        std::mem::size_of::<u32>() as MSize
+            + (self.ranges.len() as MSize
+                * PageRange::SIZE
+                    .get_fixed_size()
+                    .expect("Should be fixed size"))
+    }
+
+    fn encode(&'_ self) -> std::borrow::Cow<'_, [u8]> {
+        // write length of ranges
+        let size = self.ranges.len() as u32;
+        let mut encoded = Vec::with_capacity(self.size() as usize);
+        encoded.extend_from_slice(&size.to_le_bytes());
+        for range in &self.ranges {
+            encoded.extend_from_slice(&range.encode());
+        }
+        std::borrow::Cow::Owned(encoded)
+    }
+
+    fn decode(data: std::borrow::Cow<[u8]>) -> crate::memory::MemoryResult<Self>
+    where
+        Self: Sized,
+    {
+        let size_bytes = &data[0..4];
+        let size = u32::from_le_bytes(size_bytes.try_into()?) as usize;
+        let range_size = PageRange::SIZE
+            .get_fixed_size()
+            .expect("Should be fixed size") as usize;
+        let mut ranges = Vec::with_capacity(size);
+
+        for i in 0..size {
+            let start = std::mem::size_of::<u32>() + (i * range_size);
+            let end = start + range_size;
+            let range = PageRange::decode(std::borrow::Cow::Borrowed(&data[start..end]))?;
+            ranges.push(range);
+        }
+        Ok(CompactPageTable { ranges })
+    }
+}
+
+impl Encode for PageRange {
+    const SIZE: DataSize = DataSize::Fixed(
+        std::mem::size_of::<Page>() as MSize
+            + std::mem::size_of::<u32>() as MSize
+            + std::mem::size_of::<u64>() as MSize,
+    );
+
+    fn size(&self) -> MSize {
+        Self::SIZE
+            .get_fixed_size()
+            .expect("PageRange size should be fixed")
+    }
+
+    fn encode(&'_ self) -> std::borrow::Cow<'_, [u8]> {
+        let mut encoded = Vec::with_capacity(self.size() as usize);
+        encoded.extend_from_slice(&self.start_page.to_le_bytes());
+        encoded.extend_from_slice(&self.count.to_le_bytes());
+        encoded.extend_from_slice(&self.free.to_le_bytes());
+        std::borrow::Cow::Owned(encoded)
+    }
+
+    fn decode(data: std::borrow::Cow<[u8]>) -> crate::memory::MemoryResult<Self>
+    where
+        Self: Sized,
+    {
+        let page_len = std::mem::size_of::<Page>();
+        let start_page = Page::from_le_bytes(data[0..page_len].try_into()?);
+        let count = u32::from_le_bytes(data[page_len..page_len + 4].try_into()?);
+        let free = u64::from_le_bytes(data[page_len + 4..page_len + 12].try_into()?);
+        Ok(PageRange {
+            start_page,
+            count,
+            free,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn table(pages: &[(Page, u64)]) -> CompactPageTable {
+        let mut table = CompactPageTable::default();
+        for (page, free) in pages {
+            table.push(*page, *free);
+        }
+        table
+    }
+
+    #[test]
+    fn test_should_encode_and_decode_compact_page_table() {
+        let original_table = table(&[(10, 100), (11, 100), (12, 300), (20, 300)]);
+        assert_eq!(original_table.ranges_len(), 3);
+
+        let encoded = original_table.encode();
+        let decoded_table = CompactPageTable::decode(encoded).unwrap();
+
+        assert_eq!(original_table, decoded_table);
+    }
+
+    #[test]
+    fn test_should_merge_consecutive_pages_with_same_free_space() {
+        let table = table(&[(1, 0), (2, 0), (3, 0), (4, 10), (6, 10)]);
+        assert_eq!(table.ranges_len(), 3);
+        assert_eq!(table.pages_len(), 5);
+        assert_eq!(
+            table.iter().map(|record| record.page).collect::<Vec<_>>(),
+            vec![1, 2, 3, 4, 6]
+        );
+        assert_eq!(table.get(2), Some(PageRecord { page: 2, free: 0 }));
+        assert_eq!(table.get(5), None);
+        assert_eq!(table.next_after(2), Some(PageRecord { page: 3, free: 0 }));
+        assert_eq!(table.next_after(4), Some(PageRecord { page: 6, free: 10 }));
+        assert_eq!(table.next_after(6), None);
+        assert_eq!(table.find_free(5), Some(PageRecord { page: 4, free: 10 }));
+    }
+
+    #[test]
+    fn test_should_split_and_merge_runs_on_set_free() {
+        let mut table = table(&[(1, 0), (2, 0), (3, 0), (4, 10)]);
+
+        // split in the middle of a run
+        assert!(table.set_free(2, 5));
+        assert_eq!(table.ranges_len(), 4);
+        assert_eq!(table.get(2), Some(PageRecord { page: 2, free: 5 }));
+        assert_eq!(table.get(3), Some(PageRecord { page: 3, free: 0 }));
+
+        // merge back
+        assert!(table.set_free(2, 0));
+        assert_eq!(table.ranges_len(), 2);
+
+        // merge the last page of a run with the next run
+        assert!(table.set_free(3, 10));
+        assert_eq!(table.ranges_len(), 2);
+        assert_eq!(table.get(3), Some(PageRecord { page: 3, free: 10 }));
+        assert_eq!(table.pages_len(), 4);
+
+        assert!(!table.set_free(100, 0));
+    }
+
+    #[test]
+    fn test_should_remove_page() {
+        let mut table = table(&[(1, 0), (2, 0), (3, 0), (4, 10)]);
+
+        assert!(table.remove(2));
+        assert_eq!(table.ranges_len(), 3);
+        assert_eq!(
+            table.iter().map(|record| record.page).collect::<Vec<_>>(),
+            vec![1, 3, 4]
+        );
+
+        assert!(table.remove(4));
+        assert!(table.remove(1));
+        assert_eq!(table.ranges_len(), 1);
+        assert_eq!(table.get(3), Some(PageRecord { page: 3, free: 0 }));
+
+        assert!(!table.remove(2));
+    }
+}
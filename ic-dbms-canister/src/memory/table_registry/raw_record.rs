@@ -1,17 +1,73 @@
+//! Raw records, which wrap the encoded records stored in the table pages.
+//!
+//! The layout of a raw record is:
+//!
+//! - 1 byte: header byte
+//! - 2 bytes: length of the record body (little-endian)
+//! - N bytes: record body, whose layout depends on the format version
+//!
+//! The header byte is laid out as follows:
+//!
+//! | bits | value                                                          |
+//! |------|----------------------------------------------------------------|
+//! | 7..4 | magic prefix, always `0xA`                                     |
+//! | 3    | [`RAW_RECORD_COMPRESSED_FLAG`], set if the data is compressed  |
+//! | 2..0 | format version, [`RAW_RECORD_FORMAT_V0`] or [`RAW_RECORD_FORMAT_V1`] |
+//!
+//! Records written before format versions were introduced have the [`RAW_RECORD_LEGACY_HEADER`]
+//! instead, and are read as uncompressed [`RAW_RECORD_FORMAT_V0`] records.
+
+use std::borrow::Cow;
+
 use ic_dbms_api::prelude::DecodeError;
 
-use crate::memory::table_registry::RAW_RECORD_HEADER_SIZE;
-use crate::memory::{Encode, MSize, MemoryError};
+use crate::memory::{Encode, MSize, MemoryError, MemoryResult};
+
+/// Size of the raw record header: the header byte followed by the length of the record body.
+pub const RAW_RECORD_HEADER_SIZE: MSize = 3;
+
+/// Magic prefix of the raw record header byte.
+pub const RAW_RECORD_HEADER_MAGIC_PREFIX: u8 = 0xA0;
+/// Bit of the raw record header byte set when the encoded data of the record is compressed with LZ4.
+pub const RAW_RECORD_COMPRESSED_FLAG: u8 = 0x08;
+/// Header byte of the records written before format versions were introduced.
+pub const RAW_RECORD_LEGACY_HEADER: u8 = 0xFF;
+/// Mask to get the magic prefix out of the raw record header byte.
+const RAW_RECORD_HEADER_MAGIC_MASK: u8 = 0xF0;
+/// Mask to get the format version out of the raw record header byte.
+const RAW_RECORD_HEADER_VERSION_MASK: u8 = 0x07;
+
+/// Format version 0: the record body is the encoded data.
+pub const RAW_RECORD_FORMAT_V0: u8 = 0;
+/// Format version 1: the record body is the encoded data followed by its CRC32 checksum.
+pub const RAW_RECORD_FORMAT_V1: u8 = 1;
+/// The format version used to write new records.
+pub const RAW_RECORD_FORMAT_VERSION: u8 = RAW_RECORD_FORMAT_V1;
 
-pub const RAW_RECORD_HEADER_MAGIC_NUMBER: u8 = 0xFF;
+/// Size of the CRC32 checksum appended to v1 records.
+const CRC32_SIZE: MSize = 4;
 
-/// A raw record stored in memory, consisting of its length and data.
+/// Returns the size of a record stored with the latest format version, whose data takes `data_size` bytes.
+pub fn stored_record_size(data_size: MSize) -> MSize {
+    RAW_RECORD_HEADER_SIZE
+        .saturating_add(data_size)
+        .saturating_add(CRC32_SIZE)
+}
+
+/// A raw record stored in memory, consisting of its header, length and data,
+/// laid out as described in the [module documentation](self).
+///
+/// When the record is compressed, the encoded data in the record body is replaced by
+/// the LZ4 compressed encoded data, prepended with its uncompressed size.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct RawRecord<E>
 where
     E: Encode,
 {
+    version: u8,
     length: MSize,
+    /// The compressed encoded data stored in the record body, if the record is compressed.
+    compressed: Option<Vec<u8>>,
     pub data: E,
 }
 
@@ -19,13 +75,199 @@ impl<E> RawRecord<E>
 where
     E: Encode,
 {
-    /// Creates a new raw record from the given data.
-    pub fn new(data: E) -> Self {
-        let length = data.size();
-        Self { length, data }
+    /// Creates a new raw record from the given data, using the latest format version.
+    ///
+    /// Fails with [`MemoryError::DataTooLarge`] if the record size doesn't fit an [`MSize`].
+    pub fn new(data: E) -> MemoryResult<Self> {
+        Self::with_version(data, RAW_RECORD_FORMAT_VERSION)
+    }
+
+    /// Creates a new raw record from the given data, using the given format version.
+    ///
+    /// Fails with [`MemoryError::DataTooLarge`] if the record size, header included, doesn't fit an [`MSize`].
+    pub fn with_version(data: E, version: u8) -> MemoryResult<Self> {
+        let checksum_size = match version {
+            RAW_RECORD_FORMAT_V0 => 0,
+            RAW_RECORD_FORMAT_V1 => CRC32_SIZE,
+            version => {
+                return Err(MemoryError::DecodeError(
+                    DecodeError::UnsupportedFormatVersion(version),
+                ));
+            }
+        };
+        let data_size = data.size();
+        let length = data_size
+            .checked_add(checksum_size)
+            .filter(|length| length.checked_add(RAW_RECORD_HEADER_SIZE).is_some())
+            .ok_or(MemoryError::DataTooLarge {
+                page_size: MSize::MAX as u64,
+                requested: RAW_RECORD_HEADER_SIZE as u64 + data_size as u64 + checksum_size as u64,
+            })?;
+
+        Ok(Self {
+            version,
+            length,
+            compressed: None,
+            data,
+        })
+    }
+
+    /// Compresses the encoded data of the record with LZ4 if `compress` is `true`.
+    ///
+    /// The record is left uncompressed if compressing doesn't make it smaller,
+    /// as it happens for small records.
+    #[cfg(feature = "compression")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "compression")))]
+    pub fn with_compression(mut self, compress: bool) -> Self {
+        if !compress || self.compressed.is_some() {
+            return self;
+        }
+
+        let data_size = self.data.size();
+        // the compressed data is kept, so that it's not compressed again when the record is encoded
+        let compressed = lz4_flex::compress_prepend_size(&self.data.encode());
+        if compressed.len() < data_size as usize {
+            self.length = self.length - data_size + compressed.len() as MSize;
+            self.compressed = Some(compressed);
+        }
+
+        self
+    }
+
+    /// Returns whether the encoded data of the record is compressed.
+    pub fn is_compressed(&self) -> bool {
+        self.compressed.is_some()
+    }
+
+    /// Returns whether the given format version is supported.
+    pub fn is_supported_version(version: u8) -> bool {
+        matches!(version, RAW_RECORD_FORMAT_V0 | RAW_RECORD_FORMAT_V1)
+    }
+
+    /// Returns the format version encoded in the given raw record header byte.
+    ///
+    /// The [`RAW_RECORD_LEGACY_HEADER`] is read as [`RAW_RECORD_FORMAT_V0`].
+    pub fn format_version(header_byte: u8) -> u8 {
+        if header_byte == RAW_RECORD_LEGACY_HEADER {
+            return RAW_RECORD_FORMAT_V0;
+        }
+        header_byte & RAW_RECORD_HEADER_VERSION_MASK
+    }
+
+    /// Returns whether the given raw record header byte has the [`RAW_RECORD_COMPRESSED_FLAG`] set.
+    pub fn is_compressed_header(header_byte: u8) -> bool {
+        header_byte != RAW_RECORD_LEGACY_HEADER && header_byte & RAW_RECORD_COMPRESSED_FLAG != 0
+    }
+
+    /// Returns whether the given byte is a valid raw record header byte,
+    /// including the [`RAW_RECORD_LEGACY_HEADER`].
+    pub fn is_header_byte(byte: u8) -> bool {
+        byte == RAW_RECORD_LEGACY_HEADER
+            || byte & RAW_RECORD_HEADER_MAGIC_MASK == RAW_RECORD_HEADER_MAGIC_PREFIX
+    }
+
+    /// Returns the encoded data stored in the record body, compressed if the record is compressed.
+    fn encode_data(&self) -> Cow<'_, [u8]> {
+        match &self.compressed {
+            Some(compressed) => Cow::Borrowed(compressed),
+            None => self.data.encode(),
+        }
+    }
+
+    /// Returns the raw record header byte for the given format version and compression.
+    fn header_byte(version: u8, compress: bool) -> u8 {
+        let compressed_flag = if compress {
+            RAW_RECORD_COMPRESSED_FLAG
+        } else {
+            0
+        };
+        RAW_RECORD_HEADER_MAGIC_PREFIX
+            | compressed_flag
+            | (version & RAW_RECORD_HEADER_VERSION_MASK)
+    }
+}
+
+/// Decodes the body of a [`RawRecord`] according to its format version.
+pub trait FormatDecoder: Sized {
+    /// Decodes a v0 record body, which is the encoded data.
+    fn decode_v0(body: Cow<[u8]>) -> MemoryResult<Self>;
+
+    /// Decodes a v1 record body, which is the encoded data followed by its CRC32 checksum.
+    fn decode_v1(body: Cow<[u8]>) -> MemoryResult<Self>;
+
+    /// Decodes the LZ4 compressed encoded data of a compressed record, whose checksum has been verified.
+    fn decode_compressed(compressed: &[u8]) -> MemoryResult<Self>;
+
+    /// Decodes a record body, dispatching to the decoder for the given format version.
+    fn decode_version(version: u8, body: Cow<[u8]>) -> MemoryResult<Self> {
+        match version {
+            RAW_RECORD_FORMAT_V0 => Self::decode_v0(body),
+            RAW_RECORD_FORMAT_V1 => Self::decode_v1(body),
+            version => Err(MemoryError::DecodeError(
+                DecodeError::UnsupportedFormatVersion(version),
+            )),
+        }
+    }
+}
+
+impl<E> FormatDecoder for E
+where
+    E: Encode,
+{
+    fn decode_v0(body: Cow<[u8]>) -> MemoryResult<Self> {
+        E::decode(body)
+    }
+
+    fn decode_v1(body: Cow<[u8]>) -> MemoryResult<Self> {
+        E::decode_ref(verify_checksum(&body)?)
+    }
+
+    fn decode_compressed(compressed: &[u8]) -> MemoryResult<Self> {
+        E::decode(Cow::Owned(decompress(compressed)?))
+    }
+}
+
+/// Returns the compressed encoded data in the body of a compressed record of the given format version,
+/// verifying its checksum for v1 records.
+fn compressed_data(version: u8, body: &[u8]) -> MemoryResult<&[u8]> {
+    match version {
+        RAW_RECORD_FORMAT_V0 => Ok(body),
+        RAW_RECORD_FORMAT_V1 => verify_checksum(body),
+        version => Err(MemoryError::DecodeError(
+            DecodeError::UnsupportedFormatVersion(version),
+        )),
     }
 }
 
+/// Verifies the CRC32 checksum at the end of a v1 record body, returning the data it covers.
+fn verify_checksum(body: &[u8]) -> MemoryResult<&[u8]> {
+    if body.len() < CRC32_SIZE as usize {
+        return Err(MemoryError::DecodeError(DecodeError::TooShort));
+    }
+    let (data, checksum) = body.split_at(body.len() - CRC32_SIZE as usize);
+    let checksum = u32::from_le_bytes(checksum.try_into()?);
+    if crc32fast::hash(data) != checksum {
+        return Err(MemoryError::DecodeError(DecodeError::ChecksumMismatch));
+    }
+
+    Ok(data)
+}
+
+/// Decompresses the LZ4 compressed encoded data of a record, prepended with its uncompressed size.
+#[cfg(feature = "compression")]
+fn decompress(compressed: &[u8]) -> MemoryResult<Vec<u8>> {
+    lz4_flex::decompress_size_prepended(compressed)
+        .map_err(|err| MemoryError::DecodeError(DecodeError::DecompressionError(err.to_string())))
+}
+
+/// Compressed records can't be decoded without the `compression` feature.
+#[cfg(not(feature = "compression"))]
+fn decompress(_compressed: &[u8]) -> MemoryResult<Vec<u8>> {
+    Err(MemoryError::DecodeError(DecodeError::DecompressionError(
+        "the `compression` feature is not enabled".to_string(),
+    )))
+}
+
 impl<E> Encode for RawRecord<E>
 where
     E: Encode,
@@ -33,57 +275,332 @@ where
     const SIZE: crate::memory::DataSize = crate::memory::DataSize::Dynamic;
 
     fn size(&self) -> MSize {
-        super::RAW_RECORD_HEADER_SIZE + self.length // 1 (start) + 2 bytes for length + data size
+        // 1 (start) + 2 bytes for length + body size; the sum is checked when the record is created
+        RAW_RECORD_HEADER_SIZE.saturating_add(self.length)
     }
 
-    fn encode(&'_ self) -> std::borrow::Cow<'_, [u8]> {
+    fn encode(&'_ self) -> Cow<'_, [u8]> {
         let mut encoded = Vec::with_capacity(self.size() as usize);
-        encoded.push(RAW_RECORD_HEADER_MAGIC_NUMBER); // start byte
+        encoded.push(Self::header_byte(self.version, self.is_compressed())); // start byte
         encoded.extend_from_slice(&self.length.to_le_bytes());
-        encoded.extend_from_slice(&self.data.encode());
-        std::borrow::Cow::Owned(encoded)
+        let data = self.encode_data();
+        encoded.extend_from_slice(&data);
+        if self.version == RAW_RECORD_FORMAT_V1 {
+            encoded.extend_from_slice(&crc32fast::hash(&data).to_le_bytes());
+        }
+        Cow::Owned(encoded)
     }
 
-    fn decode(data: std::borrow::Cow<[u8]>) -> crate::memory::MemoryResult<Self>
+    fn decode(data: Cow<[u8]>) -> MemoryResult<Self>
     where
         Self: Sized,
     {
-        if data.len() < 3 {
+        if data.len() < RAW_RECORD_HEADER_SIZE as usize {
             return Err(MemoryError::DecodeError(DecodeError::TooShort));
         }
-        if data[0] != RAW_RECORD_HEADER_MAGIC_NUMBER {
+        if !Self::is_header_byte(data[0]) {
             return Err(MemoryError::DecodeError(DecodeError::BadRawRecordHeader));
         }
+        let version = Self::format_version(data[0]);
+        let compress = Self::is_compressed_header(data[0]);
         let length = u16::from_le_bytes([data[1], data[2]]) as MSize;
+        if length.checked_add(RAW_RECORD_HEADER_SIZE).is_none() {
+            return Err(MemoryError::DataTooLarge {
+                page_size: MSize::MAX as u64,
+                requested: RAW_RECORD_HEADER_SIZE as u64 + length as u64,
+            });
+        }
         if data.len() < (RAW_RECORD_HEADER_SIZE as usize) + length as usize {
             return Err(MemoryError::DecodeError(DecodeError::TooShort));
         }
-        let data_slice = &data[(RAW_RECORD_HEADER_SIZE as usize)
+        let body = &data[(RAW_RECORD_HEADER_SIZE as usize)
             ..(RAW_RECORD_HEADER_SIZE as usize) + length as usize];
-        let data_cow = std::borrow::Cow::Borrowed(data_slice);
-        let data_decoded = E::decode(data_cow)?;
+        let (data_decoded, compressed) = if compress {
+            let compressed = compressed_data(version, body)?;
+            (E::decode_compressed(compressed)?, Some(compressed.to_vec()))
+        } else {
+            (E::decode_version(version, Cow::Borrowed(body))?, None)
+        };
         Ok(Self {
+            version,
             length,
+            compressed,
             data: data_decoded,
         })
     }
 }
 
+/// A [`RawRecord`] kept as its encoded bytes, header included, so it can be moved without decoding it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncodedRawRecord(Vec<u8>);
+
+impl EncodedRawRecord {
+    /// Splits the given bytes into the sequence of encoded raw records they contain.
+    ///
+    /// The header of each record is validated: the header byte must carry the magic prefix
+    /// and a supported format version, and the record body must fit within the given bytes.
+    /// Record bodies are not decoded.
+    pub fn split(mut data: &[u8]) -> MemoryResult<Vec<Self>> {
+        let mut records = vec![];
+        while let Some(&header) = data.first() {
+            if !RawRecord::<Self>::is_header_byte(header) {
+                return Err(MemoryError::DecodeError(DecodeError::BadRawRecordHeader));
+            }
+            let version = RawRecord::<Self>::format_version(header);
+            if !RawRecord::<Self>::is_supported_version(version) {
+                return Err(MemoryError::DecodeError(
+                    DecodeError::UnsupportedFormatVersion(version),
+                ));
+            }
+            if data.len() < RAW_RECORD_HEADER_SIZE as usize {
+                return Err(MemoryError::DecodeError(DecodeError::TooShort));
+            }
+            let record_size =
+                RAW_RECORD_HEADER_SIZE as usize + u16::from_le_bytes([data[1], data[2]]) as usize;
+            let record = data
+                .get(..record_size)
+                .ok_or(MemoryError::DecodeError(DecodeError::TooShort))?;
+            records.push(Self(record.to_vec()));
+            data = &data[record_size..];
+        }
+
+        Ok(records)
+    }
+
+    /// Returns the encoded bytes of the record.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl Encode for EncodedRawRecord {
+    const SIZE: crate::memory::DataSize = crate::memory::DataSize::Dynamic;
+
+    fn size(&self) -> MSize {
+        self.0.len() as MSize
+    }
+
+    fn encode(&'_ self) -> Cow<'_, [u8]> {
+        Cow::Borrowed(&self.0)
+    }
+
+    fn decode(data: Cow<[u8]>) -> MemoryResult<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self(data.into_owned()))
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
+    use ic_dbms_api::prelude::Blob;
+    #[cfg(feature = "compression")]
+    use ic_dbms_api::prelude::Text;
+
     use super::*;
 
     #[test]
     fn test_raw_record_encode_decode() {
         let record = TestRecord { a: 42, b: 65535 };
-        let raw_record = RawRecord::new(record);
+        let raw_record = RawRecord::new(record).expect("should create record");
         let encoded = raw_record.encode();
         let decoded = RawRecord::<TestRecord>::decode(encoded).unwrap();
+        assert_eq!(raw_record.version, RAW_RECORD_FORMAT_VERSION);
         assert_eq!(raw_record.length, decoded.length);
         assert_eq!(raw_record.data, decoded.data);
     }
 
+    #[test]
+    fn test_raw_record_v0_encode_decode() {
+        let record = TestRecord { a: 42, b: 65535 };
+        let raw_record =
+            RawRecord::with_version(record, RAW_RECORD_FORMAT_V0).expect("should create record");
+        let encoded = raw_record.encode();
+        assert_eq!(encoded[0], 0xA0);
+        assert_eq!(encoded.len(), 3 + 3);
+
+        let decoded = RawRecord::<TestRecord>::decode(encoded).unwrap();
+        assert_eq!(decoded.version, RAW_RECORD_FORMAT_V0);
+        assert_eq!(raw_record.data, decoded.data);
+    }
+
+    #[test]
+    fn test_raw_record_v1_encode_decode() {
+        let record = TestRecord { a: 42, b: 65535 };
+        let raw_record =
+            RawRecord::with_version(record, RAW_RECORD_FORMAT_V1).expect("should create record");
+        let encoded = raw_record.encode();
+        assert_eq!(encoded[0], 0xA1);
+        assert_eq!(encoded.len(), 3 + 3 + 4);
+
+        let decoded = RawRecord::<TestRecord>::decode(encoded).unwrap();
+        assert_eq!(decoded.version, RAW_RECORD_FORMAT_V1);
+        assert_eq!(raw_record.data, decoded.data);
+    }
+
+    #[test]
+    fn test_should_not_decode_v1_with_bad_checksum() {
+        let record = TestRecord { a: 42, b: 65535 };
+        let raw_record = RawRecord::new(record).expect("should create record");
+        let mut encoded = raw_record.encode().into_owned();
+        encoded[3] = 43;
+
+        let result = RawRecord::<TestRecord>::decode(Cow::Owned(encoded));
+        assert!(matches!(
+            result,
+            Err(MemoryError::DecodeError(DecodeError::ChecksumMismatch))
+        ));
+    }
+
+    #[test]
+    fn test_should_not_decode_unsupported_version() {
+        let buf = vec![0xA2, 3, 0, 42, 0xFF, 0xFF];
+        let result = RawRecord::<TestRecord>::decode(Cow::Owned(buf));
+        assert!(matches!(
+            result,
+            Err(MemoryError::DecodeError(
+                DecodeError::UnsupportedFormatVersion(2)
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_should_decode_legacy_raw_record() {
+        // a record as written before format versions were introduced: 0xFF, length, data
+        let buf = vec![0xFF, 3, 0, 42, 0xFF, 0xFF];
+        let decoded = RawRecord::<TestRecord>::decode(Cow::Owned(buf.clone())).unwrap();
+        assert_eq!(decoded.version, RAW_RECORD_FORMAT_V0);
+        assert!(!decoded.is_compressed());
+        assert_eq!(decoded.data, TestRecord { a: 42, b: 65535 });
+        assert_eq!(decoded.size() as usize, buf.len());
+
+        let records = EncodedRawRecord::split(&buf).expect("should split legacy record");
+        assert_eq!(records, vec![EncodedRawRecord(buf)]);
+    }
+
+    #[test]
+    fn test_should_not_create_raw_record_larger_than_msize() {
+        // the largest data whose v0 record still fits an MSize
+        let max_v0 = Blob(vec![0; (MSize::MAX - RAW_RECORD_HEADER_SIZE - 2) as usize]);
+        let raw_record = RawRecord::with_version(max_v0.clone(), RAW_RECORD_FORMAT_V0)
+            .expect("should create record");
+        assert_eq!(raw_record.size(), MSize::MAX);
+
+        // the checksum doesn't fit anymore
+        assert!(matches!(
+            RawRecord::with_version(max_v0, RAW_RECORD_FORMAT_V1),
+            Err(MemoryError::DataTooLarge { page_size, requested })
+                if page_size == MSize::MAX as u64 && requested == MSize::MAX as u64 + 4
+        ));
+        assert!(matches!(
+            RawRecord::new(Blob(vec![0; (MSize::MAX - 2) as usize])),
+            Err(MemoryError::DataTooLarge { .. })
+        ));
+
+        // a corrupted length can't overflow the record size
+        let buf = vec![0xA0, 0xFF, 0xFF, 42];
+        assert!(matches!(
+            RawRecord::<TestRecord>::decode(Cow::Owned(buf)),
+            Err(MemoryError::DataTooLarge { .. })
+        ));
+    }
+
+    #[test]
+    fn test_should_get_format_version() {
+        assert!(RawRecord::<TestRecord>::is_header_byte(0xA0));
+        assert!(RawRecord::<TestRecord>::is_header_byte(0xA1));
+        assert!(RawRecord::<TestRecord>::is_header_byte(0xFF));
+        assert!(!RawRecord::<TestRecord>::is_header_byte(0x00));
+        assert!(!RawRecord::<TestRecord>::is_header_byte(0xF0));
+        assert_eq!(RawRecord::<TestRecord>::format_version(0xFF), 0);
+        assert!(!RawRecord::<TestRecord>::is_compressed_header(0xFF));
+        assert_eq!(RawRecord::<TestRecord>::format_version(0xA0), 0);
+        assert_eq!(RawRecord::<TestRecord>::format_version(0xA1), 1);
+        assert_eq!(RawRecord::<TestRecord>::format_version(0xA9), 1);
+        assert!(RawRecord::<TestRecord>::is_compressed_header(0xA9));
+        assert!(!RawRecord::<TestRecord>::is_compressed_header(0xA1));
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_should_encode_decode_compressed_raw_record() {
+        let text = Text("compress me ".repeat(100));
+        let raw_record = RawRecord::new(text.clone())
+            .expect("should create record")
+            .with_compression(true);
+        assert!(raw_record.is_compressed());
+        assert!(
+            raw_record.size()
+                < RawRecord::new(text.clone())
+                    .expect("should create record")
+                    .size()
+        );
+
+        // the data is compressed once, when the compression is enabled
+        let compressed = lz4_flex::compress_prepend_size(&text.encode());
+        assert_eq!(
+            raw_record.compressed.as_deref(),
+            Some(compressed.as_slice())
+        );
+
+        let encoded = raw_record.encode();
+        assert_eq!(encoded[0], 0xA9);
+        assert_eq!(encoded.len(), raw_record.size() as usize);
+        assert_eq!(&encoded[3..3 + compressed.len()], compressed.as_slice());
+
+        let decoded = RawRecord::<Text>::decode(encoded.clone()).unwrap();
+        // the decoded record keeps the compressed data, so it's encoded as it was read
+        assert_eq!(decoded, raw_record);
+        assert_eq!(decoded.encode(), encoded);
+        assert_eq!(decoded.version, RAW_RECORD_FORMAT_VERSION);
+        assert!(decoded.is_compressed());
+        assert_eq!(decoded.length, raw_record.length);
+        assert_eq!(decoded.data, text);
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_should_not_compress_raw_record_if_not_smaller() {
+        let record = TestRecord { a: 42, b: 65535 };
+        let raw_record = RawRecord::new(record)
+            .expect("should create record")
+            .with_compression(true);
+        assert!(!raw_record.is_compressed());
+        assert_eq!(
+            raw_record,
+            RawRecord::new(record).expect("should create record")
+        );
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_should_not_decode_compressed_raw_record_with_bad_checksum() {
+        let raw_record = RawRecord::new(Text("compress me ".repeat(100)))
+            .expect("should create record")
+            .with_compression(true);
+        let mut encoded = raw_record.encode().into_owned();
+        encoded[8] ^= 0x01;
+
+        let result = RawRecord::<Text>::decode(Cow::Owned(encoded));
+        assert!(matches!(
+            result,
+            Err(MemoryError::DecodeError(DecodeError::ChecksumMismatch))
+        ));
+    }
+
+    #[cfg(not(feature = "compression"))]
+    #[test]
+    fn test_should_not_decode_compressed_raw_record_without_compression() {
+        let buf = vec![0xA8, 3, 0, 42, 0xFF, 0xFF];
+        let result = RawRecord::<TestRecord>::decode(Cow::Owned(buf));
+        assert!(matches!(
+            result,
+            Err(MemoryError::DecodeError(DecodeError::DecompressionError(_)))
+        ));
+    }
+
     #[derive(Debug, PartialEq, Clone, Copy)]
     struct TestRecord {
         a: u8,
@@ -1,11 +1,18 @@
+use std::iter::FusedIterator;
 use std::marker::PhantomData;
 
 use ic_dbms_api::prelude::DecodeError;
+#[cfg(feature = "indexes")]
+use ic_dbms_api::prelude::Value;
 
-use crate::memory::table_registry::RAW_RECORD_HEADER_SIZE;
+#[cfg(feature = "indexes")]
+use crate::memory::BTreeIndex;
 use crate::memory::table_registry::page_ledger::PageLedger;
-use crate::memory::table_registry::raw_record::{RAW_RECORD_HEADER_MAGIC_NUMBER, RawRecord};
+use crate::memory::table_registry::raw_record::{
+    EncodedRawRecord, RAW_RECORD_HEADER_SIZE, RawRecord,
+};
 use crate::memory::{Encode, MEMORY_MANAGER, MSize, MemoryError, MemoryResult, Page, PageOffset};
+use crate::utils::trap;
 
 /// Stores the current position to read/write in memory.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -22,6 +29,7 @@ struct FoundRecord {
     page: Page,
     offset: PageOffset,
     length: MSize,
+    version: u8,
     new_position: Option<Position>,
 }
 
@@ -34,6 +42,10 @@ where
     pub record: E,
     pub page: Page,
     pub offset: PageOffset,
+    /// Format version the record is stored with.
+    pub version: u8,
+    /// Whether the record is stored compressed.
+    pub compressed: bool,
 }
 
 /// A reader for the table registry that allows reading records from memory.
@@ -61,7 +73,7 @@ where
     /// Creates a new table reader starting from the beginning of the table registry.
     pub fn new(page_ledger: &'a PageLedger) -> Self {
         // init position
-        let position = page_ledger.pages().first().map(|page_record| Position {
+        let position = page_ledger.pages().next().map(|page_record| Position {
             page: page_record.page,
             offset: 0,
             size: MEMORY_MANAGER
@@ -78,6 +90,11 @@ where
         }
     }
 
+    /// Wraps the reader into an [`InfallibleTableReader`], which traps on read errors instead of returning them.
+    pub fn infallible(self) -> InfallibleTableReader<'a, E> {
+        InfallibleTableReader { reader: self }
+    }
+
     /// Reads the next record from the table registry.
     pub fn try_next(&mut self) -> MemoryResult<Option<NextRecord<E>>> {
         let Some(Position { page, offset, size }) = self.position else {
@@ -91,20 +108,178 @@ where
             return Ok(None);
         };
 
-        // read raw record
-        let record: RawRecord<E> =
-            MEMORY_MANAGER.with_borrow(|mm| mm.read_at(next_record.page, next_record.offset))?;
+        // decode raw record from the page buffer; decoding is dispatched on the record format version
+        let record = self.decode_buffered_record(next_record.offset, next_record.length)?;
 
         // update position
         self.position = next_record.new_position;
 
         Ok(Some(NextRecord {
+            compressed: record.is_compressed(),
             record: record.data,
             page: next_record.page,
             offset: next_record.offset,
+            version: next_record.version,
+        }))
+    }
+
+    /// Reads the next record from the table registry as its encoded bytes, header included,
+    /// without decoding it.
+    pub fn try_next_encoded(&mut self) -> MemoryResult<Option<EncodedRawRecord>> {
+        let Some(Position { page, offset, size }) = self.position else {
+            return Ok(None);
+        };
+
+        let Some(next_record) = self.find_next_record(page, offset, size)? else {
+            self.position = None;
+            return Ok(None);
+        };
+
+        let start = next_record.offset as usize;
+        let end = start + RAW_RECORD_HEADER_SIZE as usize + next_record.length as usize;
+        let record = EncodedRawRecord::decode_ref(
+            self.buffer
+                .get(start..end)
+                .ok_or(MemoryError::DecodeError(DecodeError::TooShort))?,
+        )?;
+        self.position = next_record.new_position;
+
+        Ok(Some(record))
+    }
+
+    /// Counts the records left to read, without decoding them.
+    pub fn count_remaining(&mut self) -> MemoryResult<u64> {
+        let mut count = 0;
+        while let Some(Position { page, offset, size }) = self.position {
+            let Some(next_record) = self.find_next_record(page, offset, size)? else {
+                self.position = None;
+                break;
+            };
+            self.position = next_record.new_position;
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
+    /// Moves the reader to the beginning of the given page, so that [`TableReader::try_next`] continues from there.
+    ///
+    /// Returns `false`, leaving the reader as is, if the page doesn't belong to the table.
+    pub fn seek_to_page(&mut self, page: Page) -> bool {
+        let Some(page_record) = self.page_ledger.page(page) else {
+            return false;
+        };
+        self.position = Some(Position {
+            page,
+            offset: 0,
+            size: (self.page_size as u64).saturating_sub(page_record.free),
+        });
+
+        true
+    }
+
+    /// Reads the record stored at the given page and offset, without scanning the table.
+    ///
+    /// The reader position is moved after the sought record,
+    /// so that [`TableReader::try_next`] continues from there.
+    ///
+    /// Returns [`None`] if the page doesn't belong to the table or if there is no record at the given offset.
+    pub fn seek_to_offset(
+        &mut self,
+        page: Page,
+        offset: PageOffset,
+    ) -> MemoryResult<Option<NextRecord<E>>> {
+        let Some(page_record) = self.page_ledger.page(page) else {
+            return Ok(None);
+        };
+        let page_size = (self.page_size as u64).saturating_sub(page_record.free);
+        if offset as u64 >= page_size {
+            return Ok(None);
+        }
+
+        // read the whole page, since `try_next` reuses the buffer when continuing from a non-zero offset
+        MEMORY_MANAGER
+            .with_borrow(|mm| mm.read_at_cached(page, 0, &mut self.buffer[..page_size as usize]))?;
+
+        // the record header must be exactly at the given offset
+        let Some((0, length, version)) =
+            self.find_next_record_position(&self.buffer[(offset as usize)..(page_size as usize)])?
+        else {
+            return Ok(None);
+        };
+
+        let record = self.decode_buffered_record(offset, length)?;
+        self.position = self.position_after(page, offset, length, page_size);
+
+        Ok(Some(NextRecord {
+            compressed: record.is_compressed(),
+            record: record.data,
+            page,
+            offset,
+            version,
         }))
     }
 
+    /// Reads the record with the given primary key, looking up its location in the given [`BTreeIndex`]
+    /// on the primary key column, without scanning the table.
+    ///
+    /// Only the index leaf covering the key and the page of the record are read.
+    /// The reader position is moved after the sought record, as in [`TableReader::seek_to_offset`].
+    ///
+    /// Returns [`None`] if no record with the given primary key is indexed.
+    #[cfg(feature = "indexes")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "indexes")))]
+    pub fn seek_to_pk(
+        &mut self,
+        pk: &Value,
+        index: &BTreeIndex,
+    ) -> MemoryResult<Option<NextRecord<E>>> {
+        // the primary key is unique, so it is stored by one record at most
+        let Some(entry) = index.lookup(pk)?.into_iter().next() else {
+            return Ok(None);
+        };
+
+        self.seek_to_offset(entry.page, entry.offset)
+    }
+
+    /// Decodes the [`RawRecord`] with the given body length at the given offset of the page buffer,
+    /// without reading it again from memory.
+    fn decode_buffered_record(
+        &self,
+        offset: PageOffset,
+        length: MSize,
+    ) -> MemoryResult<RawRecord<E>> {
+        let start = offset as usize;
+        let end = start + RAW_RECORD_HEADER_SIZE as usize + length as usize;
+        let data = self
+            .buffer
+            .get(start..end)
+            .ok_or(MemoryError::DecodeError(DecodeError::TooShort))?;
+
+        RawRecord::decode_ref(data)
+    }
+
+    /// Gets the [`Position`] following the record at the given page and offset.
+    fn position_after(
+        &self,
+        page: Page,
+        offset: PageOffset,
+        length: MSize,
+        page_size: u64,
+    ) -> Option<Position> {
+        let new_offset = offset + length as PageOffset;
+        if new_offset as u64 >= page_size {
+            // move to next page
+            self.next_page(page)
+        } else {
+            Some(Position {
+                page,
+                offset: new_offset + RAW_RECORD_HEADER_SIZE,
+                size: page_size,
+            })
+        }
+    }
+
     /// Finds the next record starting from the given position.
     ///
     /// If a record is found, returns [`Some<NextRecord>`], otherwise returns [`None`].
@@ -112,7 +287,7 @@ where
     fn find_next_record(
         &mut self,
         mut page: Page,
-        offset: PageOffset,
+        mut offset: PageOffset,
         mut page_size: u64,
     ) -> MemoryResult<Option<FoundRecord>> {
         loop {
@@ -122,32 +297,24 @@ where
             // if offset is zero, read page; otherwise, just reuse buffer
             if offset == 0 {
                 MEMORY_MANAGER
-                    .with_borrow(|mm| mm.read_at_raw(page, 0, &mut self.buffer[..read_len]))?;
+                    .with_borrow(|mm| mm.read_at_cached(page, 0, &mut self.buffer[..read_len]))?;
             }
 
             // find next record in buffer; if found, return it
             let buf_end = (page_size as usize).max(offset as usize);
-            if let Some((next_segment_offset, next_segment_size)) =
+            if let Some((next_segment_offset, next_segment_size, version)) =
                 self.find_next_record_position(&self.buffer[(offset as usize)..buf_end])?
             {
                 // found a record; return it
                 // sum the buffer offset to the current page offset to get the absolute offset
                 let next_segment_offset = offset + next_segment_offset as PageOffset;
-                let new_offset = next_segment_offset + next_segment_size as PageOffset;
-                let new_position = if new_offset as u64 >= page_size {
-                    // move to next page
-                    self.next_page(page)
-                } else {
-                    Some(Position {
-                        page,
-                        offset: new_offset + RAW_RECORD_HEADER_SIZE,
-                        size: page_size,
-                    })
-                };
+                let new_position =
+                    self.position_after(page, next_segment_offset, next_segment_size, page_size);
                 return Ok(Some(FoundRecord {
                     page,
                     offset: next_segment_offset,
                     length: next_segment_size,
+                    version,
                     new_position,
                 }));
             }
@@ -156,6 +323,7 @@ where
             match self.next_page(page) {
                 Some(pos) => {
                     page = pos.page;
+                    offset = pos.offset;
                     page_size = pos.size;
                 }
                 None => break,
@@ -168,9 +336,7 @@ where
     /// Gets the next page after the given current page.
     fn next_page(&self, current_page: Page) -> Option<Position> {
         self.page_ledger
-            .pages()
-            .iter()
-            .find(|p| p.page > current_page)
+            .next_page(current_page)
             .map(|page_record| Position {
                 page: page_record.page,
                 offset: 0,
@@ -180,36 +346,90 @@ where
 
     /// Finds the next record segment position.
     ///
-    /// Returns the offset and size of the next record segment if found.
-    fn find_next_record_position(&self, buf: &[u8]) -> MemoryResult<Option<(PageOffset, MSize)>> {
-        // iter until we find a byte that is not 0
-        let offset = match buf
-            .iter()
-            .position(|b| *b == RAW_RECORD_HEADER_MAGIC_NUMBER)
-        {
+    /// Returns the offset, the size and the format version of the next record segment if found.
+    fn find_next_record_position(
+        &self,
+        buf: &[u8],
+    ) -> MemoryResult<Option<(PageOffset, MSize, u8)>> {
+        // iter until we find a raw record header byte
+        let offset = match buf.iter().position(|b| RawRecord::<E>::is_header_byte(*b)) {
             Some(offset) => offset,
             None => return Ok(None),
         };
 
         // get length
-        if buf.len() < offset + 3 {
+        let data_offset = offset + RAW_RECORD_HEADER_SIZE as usize;
+        if buf.len() < data_offset {
             return Err(MemoryError::DecodeError(DecodeError::TooShort));
         }
 
         let data_len = u16::from_le_bytes([buf[offset + 1], buf[offset + 2]]) as MSize;
-        let data_offset = offset + 3;
         if buf.len() < data_offset + data_len as usize {
             return Err(MemoryError::DecodeError(DecodeError::TooShort));
         }
 
-        Ok(Some((offset as PageOffset, data_len)))
+        let version = RawRecord::<E>::format_version(buf[offset]);
+
+        Ok(Some((offset as PageOffset, data_len, version)))
+    }
+}
+
+/// Iterates over the records of the table, as [`TableReader::try_next`] does.
+///
+/// Since reading from memory can fail, each item is a [`MemoryResult`];
+/// after an error the reader is exhausted, so no more items are returned.
+impl<E> Iterator for TableReader<'_, E>
+where
+    E: Encode,
+{
+    type Item = MemoryResult<NextRecord<E>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.try_next() {
+            Ok(next) => next.map(Ok),
+            Err(err) => {
+                self.position = None;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+impl<E> FusedIterator for TableReader<'_, E> where E: Encode {}
+
+/// A [`TableReader`] iterating over the records of the table, which traps on read errors.
+///
+/// Use it when a record which can't be read means that the memory is corrupted,
+/// so there's no way to recover from the error anyway.
+pub struct InfallibleTableReader<'a, E>
+where
+    E: Encode,
+{
+    reader: TableReader<'a, E>,
+}
+
+impl<E> Iterator for InfallibleTableReader<'_, E>
+where
+    E: Encode,
+{
+    type Item = NextRecord<E>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.reader
+            .next()
+            .map(|next| next.unwrap_or_else(|err| trap(err.to_string())))
     }
 }
 
+impl<E> FusedIterator for InfallibleTableReader<'_, E> where E: Encode {}
+
 #[cfg(test)]
 mod tests {
 
     use super::*;
+    use crate::memory::table_registry::raw_record::{
+        RAW_RECORD_FORMAT_V1, RAW_RECORD_HEADER_MAGIC_PREFIX,
+    };
     use crate::memory::{TableRegistry, TableRegistryPage};
     use crate::tests::User;
 
@@ -234,6 +454,131 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_should_iterate_over_records() {
+        let table_registry = mock_table_registry(100);
+
+        let ids = mocked(&table_registry)
+            .filter_map(|next| next.ok())
+            .map(|next| next.record.id.0)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, (0..100).collect::<Vec<_>>());
+
+        let ids = mocked(&table_registry)
+            .infallible()
+            .map(|next| next.record.id.0)
+            .take_while(|id| *id < 10)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, (0..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_should_seek_to_offset() {
+        let table_registry = mock_table_registry(1_000);
+
+        // collect the position of the record in the middle of the table
+        let mut reader = mocked(&table_registry);
+        let mut target = None;
+        while let Some(next) = reader.try_next().expect("failed to read user") {
+            if next.record.id.0 == 500 {
+                target = Some(next);
+                break;
+            }
+        }
+        let target = target.expect("should have found the target record");
+
+        // seek on a fresh reader
+        let mut reader = mocked(&table_registry);
+        let sought = reader
+            .seek_to_offset(target.page, target.offset)
+            .expect("failed to seek")
+            .expect("should have found the record");
+        assert_eq!(sought, target);
+
+        // should continue from the sought record
+        let next = reader
+            .try_next()
+            .expect("failed to read user")
+            .expect("should have next record");
+        assert_eq!(next.record.id.0, 501);
+    }
+
+    #[cfg(feature = "indexes")]
+    #[test]
+    fn test_should_seek_to_pk() {
+        use crate::memory::BUFFER_POOL;
+
+        // the table takes two pages
+        let table_registry = mock_table_registry(4_000);
+        let index_page = MEMORY_MANAGER
+            .with_borrow_mut(|mm| mm.allocate_page())
+            .expect("failed to get page");
+        let index = BTreeIndex::build::<User>(&table_registry, "id", index_page)
+            .expect("failed to build index");
+        let pk = Value::Uint32(3_000u32.into());
+
+        // physical reads of the index lookup alone
+        BUFFER_POOL.with_borrow_mut(|pool| pool.clear());
+        MEMORY_MANAGER.with_borrow_mut(|mm| mm.reset_stats());
+        index.lookup(&pk).expect("failed to look up pk");
+        let index_reads = MEMORY_MANAGER.with_borrow(|mm| mm.physical_reads());
+        assert!(index_reads > 0);
+
+        BUFFER_POOL.with_borrow_mut(|pool| pool.clear());
+        MEMORY_MANAGER.with_borrow_mut(|mm| mm.reset_stats());
+        let mut reader = mocked(&table_registry);
+        let sought = reader
+            .seek_to_pk(&pk, &index)
+            .expect("failed to seek")
+            .expect("should have found the record");
+        assert_eq!(sought.record.id.0, 3_000);
+        // only the page of the record is read besides the index
+        assert_eq!(
+            MEMORY_MANAGER.with_borrow(|mm| mm.physical_reads()),
+            index_reads + 1
+        );
+
+        // should continue from the sought record
+        let next = reader
+            .try_next()
+            .expect("failed to read user")
+            .expect("should have next record");
+        assert_eq!(next.record.id.0, 3_001);
+
+        // not indexed
+        assert!(
+            reader
+                .seek_to_pk(&Value::Uint32(4_000u32.into()), &index)
+                .expect("failed to seek")
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_should_not_seek_to_invalid_offset() {
+        let table_registry = mock_table_registry(10);
+        let mut reader = mocked(&table_registry);
+        let first = reader
+            .try_next()
+            .expect("failed to read user")
+            .expect("should have a record");
+
+        // not a record header
+        assert!(
+            reader
+                .seek_to_offset(first.page, first.offset + 1)
+                .expect("failed to seek")
+                .is_none()
+        );
+        // page not belonging to the table
+        assert!(
+            reader
+                .seek_to_offset(first.page + 1_000, 0)
+                .expect("failed to seek")
+                .is_none()
+        );
+    }
+
     #[test]
     fn test_should_find_next_page() {
         let table_registry = mock_table_registry(4_000);
@@ -255,7 +600,7 @@ mod tests {
         let buf = [
             0u8,
             0u8,
-            RAW_RECORD_HEADER_MAGIC_NUMBER,
+            RAW_RECORD_HEADER_MAGIC_PREFIX | RAW_RECORD_FORMAT_V1,
             5u8,
             0u8,
             0u8,
@@ -266,13 +611,14 @@ mod tests {
             0,
             0,
         ];
-        let (offset, size) = reader
+        let (offset, size, version) = reader
             .find_next_record_position(&buf)
             .expect("failed to get next record")
             .expect("should have next record");
 
         assert_eq!(offset, 2);
         assert_eq!(size, 5);
+        assert_eq!(version, RAW_RECORD_FORMAT_V1);
     }
 
     #[test]
@@ -293,7 +639,11 @@ mod tests {
         let table_registry = mock_table_registry(1);
         let reader = mocked(&table_registry);
 
-        let buf = [0u8, RAW_RECORD_HEADER_MAGIC_NUMBER, 5u8];
+        let buf = [
+            0u8,
+            RAW_RECORD_HEADER_MAGIC_PREFIX | RAW_RECORD_FORMAT_V1,
+            5u8,
+        ];
         let result = reader.find_next_record_position(&buf);
 
         assert!(matches!(
@@ -310,7 +660,7 @@ mod tests {
         let buf = [
             0u8,
             0u8,
-            RAW_RECORD_HEADER_MAGIC_NUMBER,
+            RAW_RECORD_HEADER_MAGIC_PREFIX | RAW_RECORD_FORMAT_V1,
             5u8,
             0u8,
             0u8,
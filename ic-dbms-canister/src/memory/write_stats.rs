@@ -0,0 +1,118 @@
+/// The category of the data written to memory by the [`super::MemoryManager`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WriteCategory {
+    /// Records of the tables.
+    Data,
+    /// Page ledgers of the tables.
+    PageLedger,
+    /// Free segments ledgers of the tables.
+    FreeSegments,
+    /// The schema registry.
+    SchemaRegistry,
+    /// The access control list.
+    Acl,
+    /// The idempotency registry.
+    Idempotency,
+    /// The index registry.
+    IndexRegistry,
+    /// The checksums stored at the end of the pages.
+    PageChecksum,
+}
+
+/// Write amplification statistics of the [`super::MemoryManager`].
+///
+/// Logical writes are the records inserted into the tables,
+/// while physical writes are the writes performed on the memory provider.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct WriteAmpStats {
+    /// Amount of records inserted into the tables.
+    pub logical_writes: u64,
+    /// Amount of writes performed on the memory provider.
+    pub physical_writes: u64,
+    /// Physical writes per logical write; `0.0` if no logical write has been performed.
+    pub amplification_ratio: f64,
+    /// Bytes written for the records of the tables, including the zeroed deleted records.
+    pub data_bytes: u64,
+    /// Bytes written for the page ledgers.
+    pub page_ledger_bytes: u64,
+    /// Bytes written for the free segments ledgers.
+    pub free_segments_bytes: u64,
+    /// Bytes written for the schema registry.
+    pub schema_registry_bytes: u64,
+    /// Bytes written for the access control list.
+    pub acl_bytes: u64,
+    /// Bytes written for the idempotency registry.
+    pub idempotency_bytes: u64,
+    /// Bytes written for the index registry.
+    pub index_registry_bytes: u64,
+    /// Bytes written for the page checksums.
+    pub page_checksum_bytes: u64,
+}
+
+impl WriteAmpStats {
+    /// Records a physical write of `bytes` of the given [`WriteCategory`].
+    pub(super) fn record_physical_write(&mut self, category: Option<WriteCategory>, bytes: u64) {
+        self.physical_writes += 1;
+        let category_bytes = match category {
+            Some(WriteCategory::Data) => &mut self.data_bytes,
+            Some(WriteCategory::PageLedger) => &mut self.page_ledger_bytes,
+            Some(WriteCategory::FreeSegments) => &mut self.free_segments_bytes,
+            Some(WriteCategory::SchemaRegistry) => &mut self.schema_registry_bytes,
+            Some(WriteCategory::Acl) => &mut self.acl_bytes,
+            Some(WriteCategory::Idempotency) => &mut self.idempotency_bytes,
+            Some(WriteCategory::IndexRegistry) => &mut self.index_registry_bytes,
+            Some(WriteCategory::PageChecksum) => &mut self.page_checksum_bytes,
+            None => return,
+        };
+        *category_bytes += bytes;
+    }
+
+    /// Records a logical write.
+    pub(super) fn record_logical_write(&mut self) {
+        self.logical_writes += 1;
+    }
+
+    /// Returns the stats with the amplification ratio computed.
+    pub(super) fn with_ratio(mut self) -> Self {
+        self.amplification_ratio = if self.logical_writes == 0 {
+            0.0
+        } else {
+            self.physical_writes as f64 / self.logical_writes as f64
+        };
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_should_record_writes() {
+        let mut stats = WriteAmpStats::default();
+        assert_eq!(stats.with_ratio().amplification_ratio, 0.0);
+
+        stats.record_logical_write();
+        stats.record_physical_write(Some(WriteCategory::Data), 10);
+        stats.record_physical_write(Some(WriteCategory::PageLedger), 4);
+        stats.record_physical_write(Some(WriteCategory::FreeSegments), 3);
+        stats.record_physical_write(Some(WriteCategory::SchemaRegistry), 2);
+        stats.record_physical_write(Some(WriteCategory::Acl), 1);
+        stats.record_physical_write(Some(WriteCategory::Idempotency), 5);
+        stats.record_physical_write(Some(WriteCategory::PageChecksum), 4);
+        stats.record_physical_write(None, 100);
+
+        let stats = stats.with_ratio();
+        assert_eq!(stats.logical_writes, 1);
+        assert_eq!(stats.physical_writes, 8);
+        assert_eq!(stats.amplification_ratio, 8.0);
+        assert_eq!(stats.data_bytes, 10);
+        assert_eq!(stats.page_ledger_bytes, 4);
+        assert_eq!(stats.free_segments_bytes, 3);
+        assert_eq!(stats.schema_registry_bytes, 2);
+        assert_eq!(stats.acl_bytes, 1);
+        assert_eq!(stats.idempotency_bytes, 5);
+        assert_eq!(stats.page_checksum_bytes, 4);
+    }
+}
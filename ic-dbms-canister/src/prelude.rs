@@ -1,6 +1,36 @@
 //! Re-exports all the most commonly used items from this crate.
 
-pub use crate::dbms::IcDbmsDatabase;
-pub use crate::dbms::integrity::InsertIntegrityValidator;
-pub use crate::dbms::schema::DatabaseSchema;
-pub use crate::dbms::transaction::TRANSACTION_SESSION;
+pub use crate::dbms::aggregate::GroupedResult;
+pub use crate::dbms::budget::{Cursor, CycleBudget, PartialSelectResult};
+pub use crate::dbms::cache::{QUERY_CACHE, QueryCache};
+pub use crate::dbms::change_log::{ChangeLog, LogEntry, LogOp};
+pub use crate::dbms::health::{DEFAULT_HEALTH_CHECK_SAMPLE_SIZE, HealthCheckResult, TableHealth};
+pub use crate::dbms::import::ImportResult;
+pub use crate::dbms::integrity::{
+    InsertIntegrityValidator, IntegrityWarning, IntegrityWarningKind, UpdateIntegrityValidator,
+    ValidationMode,
+};
+pub use crate::dbms::logger::{
+    DBMS_LOGGER, DbmsLogEntry, DbmsLogger, IcPrintLogger, LogLevel, NoopLogger, VecLogger,
+    set_logger,
+};
+pub use crate::dbms::migration::{Migration, MigrationHandle, MigrationRunner};
+pub use crate::dbms::rate_limiter::{CallWindow, RATE_LIMITER, RateLimiter};
+pub use crate::dbms::schema::{
+    DatabaseSchema, SchemaMismatch, SchemaValidationMode, TableSchemaFingerprint,
+};
+pub use crate::dbms::transaction::{
+    TRANSACTION_SESSION, TransactionInfo, TransactionMode, expire_stale_transactions,
+};
+pub use crate::dbms::watcher::{AnyWatcher, TableWatcher, WATCHER_REGISTRY};
+pub use crate::dbms::{IcDbmsDatabase, SelectPage, UpsertResult};
+pub use crate::memory::{
+    AlterTableOp, BUFFER_POOL, BufferPool, IDEMPOTENCY_REGISTRY, IdempotencyRegistry,
+    IdempotencyToken, IdempotentResult, MigrationPhase, SchemaRegistry, SchemaVersion, TableQuota,
+    VacuumStats,
+};
+#[cfg(feature = "indexes")]
+pub use crate::memory::{
+    BTreeIndex, IndexEntry, IndexId, IndexInfo, IndexMaintainer, IndexRange, IndexRegistry,
+    TableIndexes,
+};
@@ -1,24 +1,56 @@
 //! Test types, fixtures and mocks.
 
+mod booking;
+mod customer;
+mod document;
+mod enrollment;
 mod message;
+mod person;
 mod post;
+mod product;
+mod ticket;
 mod user;
 
 use ic_dbms_api::prelude::{
-    ColumnDef, Database as _, InsertRecord as _, QueryError, TableSchema as _, UpdateRecord as _,
-    Value, ValuesSource,
+    ColumnDef, Database as _, InsertRecord as _, Query, QueryError, TableSchema as _,
+    UpdateRecord as _, Value, ValuesSource,
 };
 
+#[allow(unused_imports)]
+pub use self::booking::{
+    BOOKINGS_FIXTURES, Booking, BookingInsertRequest, BookingRecord, BookingUpdateRequest,
+};
+#[allow(unused_imports)]
+pub use self::customer::{
+    CUSTOMERS_FIXTURES, Customer, CustomerInsertRequest, CustomerRecord, CustomerUpdateRequest,
+};
+#[allow(unused_imports)]
+pub use self::document::{
+    DOCUMENTS_FIXTURES, Document, DocumentInsertRequest, DocumentRecord, DocumentUpdateRequest,
+};
+#[allow(unused_imports)]
+pub use self::enrollment::{
+    ENROLLMENTS_FIXTURES, Enrollment, EnrollmentInsertRequest, EnrollmentRecord,
+    EnrollmentUpdateRequest,
+};
 #[allow(unused_imports)]
 pub use self::message::{
     MESSAGES_FIXTURES, Message, MessageInsertRequest, MessageRecord, MessageUpdateRequest,
 };
 #[allow(unused_imports)]
+pub use self::person::{
+    PERSONS_FIXTURES, Person, PersonInsertRequest, PersonRecord, PersonUpdateRequest,
+};
+#[allow(unused_imports)]
 pub use self::post::{POSTS_FIXTURES, Post, PostInsertRequest, PostRecord, PostUpdateRequest};
 #[allow(unused_imports)]
+pub use self::product::{Product, ProductRecord, ProductUpdateRequest};
+#[allow(unused_imports)]
+pub use self::ticket::{Ticket, TicketInsertRequest, TicketRecord, TicketUpdateRequest};
+#[allow(unused_imports)]
 pub use self::user::{USERS_FIXTURES, User, UserInsertRequest, UserRecord, UserUpdateRequest};
-use crate::dbms::IcDbmsDatabase;
-use crate::prelude::{DatabaseSchema, InsertIntegrityValidator};
+use crate::dbms::{IcDbmsDatabase, UpsertResult};
+use crate::prelude::{DatabaseSchema, InsertIntegrityValidator, TableSchemaFingerprint};
 
 /// Loads fixtures into the database for testing purposes.
 ///
@@ -29,6 +61,13 @@ pub fn load_fixtures() {
     user::load_fixtures();
     post::load_fixtures();
     message::load_fixtures();
+    document::load_fixtures();
+    customer::load_fixtures();
+    booking::load_fixtures();
+    person::load_fixtures();
+    enrollment::load_fixtures();
+    ticket::load_fixtures();
+    product::load_fixtures();
 }
 
 /// Helper function which takes a list of `(ValuesSource, Value)` tuples, take only those with
@@ -64,6 +103,20 @@ impl DatabaseSchema for TestDatabaseSchema {
             &[]
         } else if table == Message::table_name() {
             &[]
+        } else if table == Document::table_name() {
+            &[]
+        } else if table == Customer::table_name() {
+            &[]
+        } else if table == Booking::table_name() {
+            &[]
+        } else if table == Person::table_name() {
+            &[]
+        } else if table == Enrollment::table_name() {
+            &[]
+        } else if table == Ticket::table_name() {
+            &[]
+        } else if table == Product::table_name() {
+            &[]
         } else {
             &[]
         }
@@ -84,6 +137,27 @@ impl DatabaseSchema for TestDatabaseSchema {
         } else if table_name == Message::table_name() {
             let insert_request = MessageInsertRequest::from_values(record_values)?;
             dbms.insert::<Message>(insert_request)
+        } else if table_name == Document::table_name() {
+            let insert_request = DocumentInsertRequest::from_values(record_values)?;
+            dbms.insert::<Document>(insert_request)
+        } else if table_name == Customer::table_name() {
+            let insert_request = CustomerInsertRequest::from_values(record_values)?;
+            dbms.insert::<Customer>(insert_request)
+        } else if table_name == Booking::table_name() {
+            let insert_request = BookingInsertRequest::from_values(record_values)?;
+            dbms.insert::<Booking>(insert_request)
+        } else if table_name == Person::table_name() {
+            let insert_request = PersonInsertRequest::from_values(record_values)?;
+            dbms.insert::<Person>(insert_request)
+        } else if table_name == Enrollment::table_name() {
+            let insert_request = EnrollmentInsertRequest::from_values(record_values)?;
+            dbms.insert::<Enrollment>(insert_request)
+        } else if table_name == Ticket::table_name() {
+            let insert_request = TicketInsertRequest::from_values(record_values)?;
+            dbms.insert::<Ticket>(insert_request)
+        } else if table_name == Product::table_name() {
+            let insert_request = Product::from_values(record_values)?;
+            dbms.insert::<Product>(insert_request)
         } else {
             Err(ic_dbms_api::prelude::IcDbmsError::Query(
                 QueryError::TableNotFound(table_name),
@@ -104,6 +178,20 @@ impl DatabaseSchema for TestDatabaseSchema {
             dbms.delete::<Post>(delete_behavior, filter)
         } else if table_name == Message::table_name() {
             dbms.delete::<Message>(delete_behavior, filter)
+        } else if table_name == Document::table_name() {
+            dbms.delete::<Document>(delete_behavior, filter)
+        } else if table_name == Customer::table_name() {
+            dbms.delete::<Customer>(delete_behavior, filter)
+        } else if table_name == Booking::table_name() {
+            dbms.delete::<Booking>(delete_behavior, filter)
+        } else if table_name == Person::table_name() {
+            dbms.delete::<Person>(delete_behavior, filter)
+        } else if table_name == Enrollment::table_name() {
+            dbms.delete::<Enrollment>(delete_behavior, filter)
+        } else if table_name == Ticket::table_name() {
+            dbms.delete::<Ticket>(delete_behavior, filter)
+        } else if table_name == Product::table_name() {
+            dbms.delete::<Product>(delete_behavior, filter)
         } else {
             Err(ic_dbms_api::prelude::IcDbmsError::Query(
                 QueryError::TableNotFound(table_name),
@@ -115,18 +203,159 @@ impl DatabaseSchema for TestDatabaseSchema {
         &self,
         dbms: &IcDbmsDatabase,
         table_name: &'static str,
+        update_behavior: ic_dbms_api::prelude::UpdateBehavior,
         patch_values: &[(ColumnDef, Value)],
         filter: Option<ic_dbms_api::prelude::Filter>,
     ) -> ic_dbms_api::prelude::IcDbmsResult<u64> {
         if table_name == User::table_name() {
             let update_request = UserUpdateRequest::from_values(patch_values, filter);
-            dbms.update::<User>(update_request)
+            dbms.update::<User>(update_behavior, update_request)
         } else if table_name == Post::table_name() {
             let update_request = PostUpdateRequest::from_values(patch_values, filter);
-            dbms.update::<Post>(update_request)
+            dbms.update::<Post>(update_behavior, update_request)
         } else if table_name == Message::table_name() {
             let update_request = MessageUpdateRequest::from_values(patch_values, filter);
-            dbms.update::<Message>(update_request)
+            dbms.update::<Message>(update_behavior, update_request)
+        } else if table_name == Document::table_name() {
+            let update_request = DocumentUpdateRequest::from_values(patch_values, filter);
+            dbms.update::<Document>(update_behavior, update_request)
+        } else if table_name == Customer::table_name() {
+            let update_request = CustomerUpdateRequest::from_values(patch_values, filter);
+            dbms.update::<Customer>(update_behavior, update_request)
+        } else if table_name == Booking::table_name() {
+            let update_request = BookingUpdateRequest::from_values(patch_values, filter);
+            dbms.update::<Booking>(update_behavior, update_request)
+        } else if table_name == Person::table_name() {
+            let update_request = PersonUpdateRequest::from_values(patch_values, filter);
+            dbms.update::<Person>(update_behavior, update_request)
+        } else if table_name == Enrollment::table_name() {
+            let update_request = EnrollmentUpdateRequest::from_values(patch_values, filter);
+            dbms.update::<Enrollment>(update_behavior, update_request)
+        } else if table_name == Ticket::table_name() {
+            let update_request = TicketUpdateRequest::from_values(patch_values, filter);
+            dbms.update::<Ticket>(update_behavior, update_request)
+        } else if table_name == Product::table_name() {
+            let update_request = ProductUpdateRequest::from_values(patch_values, filter);
+            dbms.update::<Product>(update_behavior, update_request)
+        } else {
+            Err(ic_dbms_api::prelude::IcDbmsError::Query(
+                QueryError::TableNotFound(table_name),
+            ))
+        }
+    }
+
+    fn upsert(
+        &self,
+        dbms: &IcDbmsDatabase,
+        table_name: &'static str,
+        record_values: &[(ColumnDef, Value)],
+    ) -> ic_dbms_api::prelude::IcDbmsResult<UpsertResult> {
+        if table_name == User::table_name() {
+            let insert_request = UserInsertRequest::from_values(record_values)?;
+            dbms.upsert::<User>(insert_request)
+        } else if table_name == Post::table_name() {
+            let insert_request = PostInsertRequest::from_values(record_values)?;
+            dbms.upsert::<Post>(insert_request)
+        } else if table_name == Message::table_name() {
+            let insert_request = MessageInsertRequest::from_values(record_values)?;
+            dbms.upsert::<Message>(insert_request)
+        } else if table_name == Document::table_name() {
+            let insert_request = DocumentInsertRequest::from_values(record_values)?;
+            dbms.upsert::<Document>(insert_request)
+        } else if table_name == Customer::table_name() {
+            let insert_request = CustomerInsertRequest::from_values(record_values)?;
+            dbms.upsert::<Customer>(insert_request)
+        } else if table_name == Booking::table_name() {
+            let insert_request = BookingInsertRequest::from_values(record_values)?;
+            dbms.upsert::<Booking>(insert_request)
+        } else if table_name == Person::table_name() {
+            let insert_request = PersonInsertRequest::from_values(record_values)?;
+            dbms.upsert::<Person>(insert_request)
+        } else if table_name == Enrollment::table_name() {
+            let insert_request = EnrollmentInsertRequest::from_values(record_values)?;
+            dbms.upsert::<Enrollment>(insert_request)
+        } else if table_name == Ticket::table_name() {
+            let insert_request = TicketInsertRequest::from_values(record_values)?;
+            dbms.upsert::<Ticket>(insert_request)
+        } else if table_name == Product::table_name() {
+            let insert_request = Product::from_values(record_values)?;
+            dbms.upsert::<Product>(insert_request)
+        } else {
+            Err(ic_dbms_api::prelude::IcDbmsError::Query(
+                QueryError::TableNotFound(table_name),
+            ))
+        }
+    }
+
+    fn select_raw(
+        &self,
+        dbms: &IcDbmsDatabase,
+        table_name: &'static str,
+        filter: ic_dbms_api::prelude::Filter,
+    ) -> ic_dbms_api::prelude::IcDbmsResult<Vec<Vec<(ColumnDef, Value)>>> {
+        if table_name == User::table_name() {
+            dbms.select_raw::<User>(Query::builder().and_where(filter).build())
+        } else if table_name == Post::table_name() {
+            dbms.select_raw::<Post>(Query::builder().and_where(filter).build())
+        } else if table_name == Message::table_name() {
+            dbms.select_raw::<Message>(Query::builder().and_where(filter).build())
+        } else if table_name == Document::table_name() {
+            dbms.select_raw::<Document>(Query::builder().and_where(filter).build())
+        } else if table_name == Customer::table_name() {
+            dbms.select_raw::<Customer>(Query::builder().and_where(filter).build())
+        } else if table_name == Booking::table_name() {
+            dbms.select_raw::<Booking>(Query::builder().and_where(filter).build())
+        } else if table_name == Person::table_name() {
+            dbms.select_raw::<Person>(Query::builder().and_where(filter).build())
+        } else if table_name == Enrollment::table_name() {
+            dbms.select_raw::<Enrollment>(Query::builder().and_where(filter).build())
+        } else if table_name == Ticket::table_name() {
+            dbms.select_raw::<Ticket>(Query::builder().and_where(filter).build())
+        } else if table_name == Product::table_name() {
+            dbms.select_raw::<Product>(Query::builder().and_where(filter).build())
+        } else {
+            Err(ic_dbms_api::prelude::IcDbmsError::Query(
+                QueryError::TableNotFound(table_name),
+            ))
+        }
+    }
+
+    fn exists(
+        &self,
+        dbms: &IcDbmsDatabase,
+        table_name: &'static str,
+        filter: ic_dbms_api::prelude::Filter,
+    ) -> ic_dbms_api::prelude::IcDbmsResult<bool> {
+        if table_name == User::table_name() {
+            dbms.select::<User>(Query::builder().and_where(filter).limit(1).build())
+                .map(|records| !records.is_empty())
+        } else if table_name == Post::table_name() {
+            dbms.select::<Post>(Query::builder().and_where(filter).limit(1).build())
+                .map(|records| !records.is_empty())
+        } else if table_name == Message::table_name() {
+            dbms.select::<Message>(Query::builder().and_where(filter).limit(1).build())
+                .map(|records| !records.is_empty())
+        } else if table_name == Document::table_name() {
+            dbms.select::<Document>(Query::builder().and_where(filter).limit(1).build())
+                .map(|records| !records.is_empty())
+        } else if table_name == Customer::table_name() {
+            dbms.select::<Customer>(Query::builder().and_where(filter).limit(1).build())
+                .map(|records| !records.is_empty())
+        } else if table_name == Booking::table_name() {
+            dbms.select::<Booking>(Query::builder().and_where(filter).limit(1).build())
+                .map(|records| !records.is_empty())
+        } else if table_name == Person::table_name() {
+            dbms.select::<Person>(Query::builder().and_where(filter).limit(1).build())
+                .map(|records| !records.is_empty())
+        } else if table_name == Enrollment::table_name() {
+            dbms.select::<Enrollment>(Query::builder().and_where(filter).limit(1).build())
+                .map(|records| !records.is_empty())
+        } else if table_name == Ticket::table_name() {
+            dbms.select::<Ticket>(Query::builder().and_where(filter).limit(1).build())
+                .map(|records| !records.is_empty())
+        } else if table_name == Product::table_name() {
+            dbms.select::<Product>(Query::builder().and_where(filter).limit(1).build())
+                .map(|records| !records.is_empty())
         } else {
             Err(ic_dbms_api::prelude::IcDbmsError::Query(
                 QueryError::TableNotFound(table_name),
@@ -146,10 +375,39 @@ impl DatabaseSchema for TestDatabaseSchema {
             InsertIntegrityValidator::<Post>::new(dbms).validate(record_values)
         } else if table_name == Message::table_name() {
             InsertIntegrityValidator::<Message>::new(dbms).validate(record_values)
+        } else if table_name == Document::table_name() {
+            InsertIntegrityValidator::<Document>::new(dbms).validate(record_values)
+        } else if table_name == Customer::table_name() {
+            InsertIntegrityValidator::<Customer>::new(dbms).validate(record_values)
+        } else if table_name == Booking::table_name() {
+            InsertIntegrityValidator::<Booking>::new(dbms).validate(record_values)
+        } else if table_name == Person::table_name() {
+            InsertIntegrityValidator::<Person>::new(dbms).validate(record_values)
+        } else if table_name == Enrollment::table_name() {
+            InsertIntegrityValidator::<Enrollment>::new(dbms).validate(record_values)
+        } else if table_name == Ticket::table_name() {
+            InsertIntegrityValidator::<Ticket>::new(dbms).validate(record_values)
+        } else if table_name == Product::table_name() {
+            InsertIntegrityValidator::<Product>::new(dbms).validate(record_values)
         } else {
             Err(ic_dbms_api::prelude::IcDbmsError::Query(
                 QueryError::TableNotFound(table_name),
             ))
         }
     }
+
+    fn table_fingerprints(&self) -> Vec<TableSchemaFingerprint> {
+        vec![
+            TableSchemaFingerprint::of::<User>(),
+            TableSchemaFingerprint::of::<Post>(),
+            TableSchemaFingerprint::of::<Message>(),
+            TableSchemaFingerprint::of::<Document>(),
+            TableSchemaFingerprint::of::<Customer>(),
+            TableSchemaFingerprint::of::<Booking>(),
+            TableSchemaFingerprint::of::<Person>(),
+            TableSchemaFingerprint::of::<Enrollment>(),
+            TableSchemaFingerprint::of::<Ticket>(),
+            TableSchemaFingerprint::of::<Product>(),
+        ]
+    }
 }
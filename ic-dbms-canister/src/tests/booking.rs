@@ -0,0 +1,72 @@
+use ic_dbms_api::prelude::{Date, Encode, Uint32};
+use ic_dbms_macros::{Encode, TableSchema};
+
+use crate::memory::{SCHEMA_REGISTRY, TableRegistry};
+
+/// A simple booking struct with a date range for testing purposes.
+#[derive(Debug, Encode, Clone, PartialEq, Eq, TableSchema)]
+#[table(name = "bookings")]
+pub struct Booking {
+    #[column(primary_key)]
+    pub id: Uint32,
+    pub start_date: Date,
+    pub end_date: Date,
+}
+
+const fn date(year: u16, month: u8, day: u8) -> Date {
+    Date { year, month, day }
+}
+
+/// Booking date ranges as `(start_date, end_date)`.
+///
+/// The last booking is invalid, since it ends before it starts.
+pub const BOOKINGS_FIXTURES: &[(Date, Date)] = &[
+    (date(2024, 1, 10), date(2024, 1, 15)),
+    (date(2024, 3, 1), date(2024, 3, 1)),
+    (date(2024, 6, 20), date(2024, 7, 2)),
+    (date(2024, 9, 5), date(2024, 8, 30)),
+];
+
+/// Loads fixtures into the database for testing purposes.
+///
+/// # Panics
+///
+/// Panics if any operation fails.
+pub fn load_fixtures() {
+    // register tables
+    let booking_pages = SCHEMA_REGISTRY
+        .with_borrow_mut(|sr| sr.register_table::<Booking>())
+        .expect("failed to register `Booking` table");
+
+    let mut booking_table: TableRegistry =
+        TableRegistry::load(booking_pages).expect("failed to load `Booking` table registry");
+
+    // insert bookings
+    for (id, (start_date, end_date)) in BOOKINGS_FIXTURES.iter().enumerate() {
+        let booking = Booking {
+            id: Uint32(id as u32),
+            start_date: *start_date,
+            end_date: *end_date,
+        };
+        booking_table
+            .insert(booking)
+            .expect("failed to insert booking");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_booking_encode_decode() {
+        let booking = Booking {
+            id: 42u32.into(),
+            start_date: date(2024, 1, 10),
+            end_date: date(2024, 1, 15),
+        };
+        let encoded = booking.encode();
+        let decoded = Booking::decode(encoded).unwrap();
+        assert_eq!(booking, decoded);
+    }
+}
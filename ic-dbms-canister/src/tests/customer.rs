@@ -0,0 +1,77 @@
+use ic_dbms_api::prelude::{Encode, Int32, Text, Uint32, Value};
+use ic_dbms_macros::{Encode, TableSchema};
+
+use crate::memory::{SCHEMA_REGISTRY, TableRegistry};
+
+/// A simple customer struct with a CHECK constraint for testing purposes.
+#[derive(Debug, Encode, Clone, PartialEq, Eq, TableSchema)]
+#[table(name = "customers")]
+pub struct Customer {
+    #[column(primary_key)]
+    pub id: Uint32,
+    #[column(unique)]
+    pub name: Text,
+    #[column(check(description = "age > 0 AND age < 150", predicate = age_check))]
+    pub age: Int32,
+}
+
+/// CHECK constraint for the `age` column: `age > 0 AND age < 150`.
+fn age_check(value: &Value) -> bool {
+    matches!(value, Value::Int32(Int32(age)) if *age > 0 && *age < 150)
+}
+
+pub const CUSTOMERS_FIXTURES: &[(&str, i32)] = &[("Alice", 34), ("Bob", 27), ("Charlie", 61)];
+
+/// Loads fixtures into the database for testing purposes.
+///
+/// # Panics
+///
+/// Panics if any operation fails.
+pub fn load_fixtures() {
+    // register tables
+    let customer_pages = SCHEMA_REGISTRY
+        .with_borrow_mut(|sr| sr.register_table::<Customer>())
+        .expect("failed to register `Customer` table");
+
+    let mut customer_table: TableRegistry =
+        TableRegistry::load(customer_pages).expect("failed to load `Customer` table registry");
+
+    // insert customers
+    for (id, (name, age)) in CUSTOMERS_FIXTURES.iter().enumerate() {
+        let customer = Customer {
+            id: Uint32(id as u32),
+            name: Text(name.to_string()),
+            age: Int32(*age),
+        };
+        customer_table
+            .insert(customer)
+            .expect("failed to insert customer");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ic_dbms_api::prelude::TableSchema as _;
+
+    use super::*;
+
+    #[test]
+    fn test_customer_encode_decode() {
+        let customer = Customer {
+            id: 42u32.into(),
+            name: "Alice".to_string().into(),
+            age: 34.into(),
+        };
+        let encoded = customer.encode();
+        let decoded = Customer::decode(encoded).unwrap();
+        assert_eq!(customer, decoded);
+    }
+
+    #[test]
+    fn test_should_derive_check_constraint() {
+        let check = Customer::columns()[2]
+            .check
+            .expect("age should have a check constraint");
+        assert_eq!(check.description, "age > 0 AND age < 150");
+    }
+}
@@ -0,0 +1,73 @@
+use ic_dbms_api::prelude::{DateTime, Encode, Nullable, Text, Uint32};
+use ic_dbms_macros::{Encode, TableSchema};
+
+use crate::memory::{SCHEMA_REGISTRY, TableRegistry};
+
+/// A simple soft-deletable document struct for testing purposes.
+#[derive(Debug, Encode, Clone, PartialEq, Eq, TableSchema)]
+#[table(name = "documents", soft_delete = "deleted_at")]
+pub struct Document {
+    #[column(primary_key)]
+    pub id: Uint32,
+    pub title: Text,
+    pub deleted_at: Nullable<DateTime>,
+}
+
+pub const DOCUMENTS_FIXTURES: &[&str] = &["Invoice", "Receipt", "Contract", "Report"];
+
+/// Loads fixtures into the database for testing purposes.
+///
+/// # Panics
+///
+/// Panics if any operation fails.
+pub fn load_fixtures() {
+    // register tables
+    let document_pages = SCHEMA_REGISTRY
+        .with_borrow_mut(|sr| sr.register_table::<Document>())
+        .expect("failed to register `Document` table");
+
+    let mut document_table: TableRegistry =
+        TableRegistry::load(document_pages).expect("failed to load `Document` table registry");
+
+    // insert documents
+    for (id, title) in DOCUMENTS_FIXTURES.iter().enumerate() {
+        let document = Document {
+            id: Uint32(id as u32),
+            title: Text(title.to_string()),
+            deleted_at: Nullable::Null,
+        };
+        document_table
+            .insert(document)
+            .expect("failed to insert document");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ic_dbms_api::prelude::{Filter, Filters, SoftDeleteSchema, TableSchema as _};
+
+    use super::*;
+
+    #[test]
+    fn test_document_encode_decode() {
+        let document = Document {
+            id: 42u32.into(),
+            title: "Invoice".to_string().into(),
+            deleted_at: Nullable::Null,
+        };
+        let encoded = document.encode();
+        let decoded = Document::decode(encoded).unwrap();
+        assert_eq!(document, decoded);
+    }
+
+    #[test]
+    fn test_should_derive_soft_delete_column() {
+        assert_eq!(Document::SOFT_DELETE_COLUMN, Some("deleted_at"));
+        assert_eq!(Document::deleted_at_column(), "deleted_at");
+    }
+
+    #[test]
+    fn test_should_filter_active_documents() {
+        assert_eq!(Filters::active::<Document>(), Filter::IsNull("deleted_at"));
+    }
+}
@@ -0,0 +1,71 @@
+use ic_dbms_api::prelude::{Encode, Text, Uint32};
+use ic_dbms_macros::{Encode, TableSchema};
+
+use crate::memory::{SCHEMA_REGISTRY, TableRegistry};
+
+/// A simple enrollment struct with a composite primary key for testing purposes.
+#[derive(Debug, Encode, Clone, PartialEq, Eq, TableSchema)]
+#[table(name = "enrollments")]
+pub struct Enrollment {
+    #[column(primary_key)]
+    pub student_id: Uint32,
+    #[column(primary_key)]
+    pub course: Text,
+    pub grade: Uint32,
+}
+
+/// Student id, course and grade of the enrollments fixtures.
+pub const ENROLLMENTS_FIXTURES: &[(u32, &str, u32)] =
+    &[(0, "math", 28), (0, "physics", 30), (1, "math", 24)];
+
+/// Loads fixtures into the database for testing purposes.
+///
+/// # Panics
+///
+/// Panics if any operation fails.
+pub fn load_fixtures() {
+    // register tables
+    let enrollment_pages = SCHEMA_REGISTRY
+        .with_borrow_mut(|sr| sr.register_table::<Enrollment>())
+        .expect("failed to register `Enrollment` table");
+
+    let mut enrollment_table: TableRegistry =
+        TableRegistry::load(enrollment_pages).expect("failed to load `Enrollment` table registry");
+
+    // insert enrollments
+    for (student_id, course, grade) in ENROLLMENTS_FIXTURES {
+        let enrollment = Enrollment {
+            student_id: Uint32(*student_id),
+            course: Text(course.to_string()),
+            grade: Uint32(*grade),
+        };
+        enrollment_table
+            .insert(enrollment)
+            .expect("failed to insert enrollment");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ic_dbms_api::prelude::TableSchema as _;
+
+    use super::*;
+
+    #[test]
+    fn test_enrollment_encode_decode() {
+        let enrollment = Enrollment {
+            student_id: 42u32.into(),
+            course: "math".to_string().into(),
+            grade: 30u32.into(),
+        };
+        let encoded = enrollment.encode();
+        let decoded = Enrollment::decode(encoded).unwrap();
+        assert_eq!(enrollment, decoded);
+    }
+
+    #[test]
+    fn test_should_have_composite_primary_key() {
+        assert_eq!(Enrollment::primary_keys(), &["student_id", "course"]);
+        assert_eq!(Enrollment::primary_key(), "student_id");
+    }
+}
@@ -112,43 +112,58 @@ impl TableSchema for Message {
                 data_type: DataTypeKind::Uint32,
                 nullable: false,
                 primary_key: true,
+                unique: false,
+                auto_increment: false,
                 foreign_key: None,
+                check: None,
             },
             ColumnDef {
                 name: "text",
                 data_type: DataTypeKind::Text,
                 nullable: false,
                 primary_key: false,
+                unique: false,
+                auto_increment: false,
                 foreign_key: None,
+                check: None,
             },
             ColumnDef {
                 name: "sender_id",
                 data_type: DataTypeKind::Uint32,
                 nullable: false,
                 primary_key: false,
+                unique: false,
+                auto_increment: false,
                 foreign_key: Some(ForeignKeyDef {
                     local_column: "sender_id",
                     foreign_table: "users",
                     foreign_column: "id",
                 }),
+                check: None,
             },
             ColumnDef {
                 name: "recipient_id",
                 data_type: DataTypeKind::Uint32,
                 nullable: false,
                 primary_key: false,
+                unique: false,
+                auto_increment: false,
                 foreign_key: Some(ForeignKeyDef {
                     local_column: "recipient_id",
                     foreign_table: "users",
                     foreign_column: "id",
                 }),
+                check: None,
             },
             ColumnDef {
                 name: "read_at",
                 data_type: DataTypeKind::DateTime,
                 nullable: true,
                 primary_key: false,
+                unique: false,
+                auto_increment: false,
                 foreign_key: None,
+                check: None,
             },
         ]
     }
@@ -157,8 +172,8 @@ impl TableSchema for Message {
         "messages"
     }
 
-    fn primary_key() -> &'static str {
-        "id"
+    fn primary_keys() -> &'static [&'static str] {
+        &["id"]
     }
 
     fn to_values(self) -> Vec<(ColumnDef, Value)> {
@@ -355,14 +370,14 @@ impl InsertRecord for MessageInsertRequest {
         ]
     }
 
-    fn into_record(self) -> Self::Schema {
-        Message {
+    fn into_record(self) -> IcDbmsResult<Self::Schema> {
+        Ok(Message {
             id: self.id,
             text: self.text,
             sender_id: self.sender_id,
             recipient_id: self.recipient_id,
             read_at: self.read_at,
-        }
+        })
     }
 }
 
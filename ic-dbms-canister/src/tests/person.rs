@@ -0,0 +1,99 @@
+use ic_dbms_api::prelude::{ColumnDef, Encode, Text, Uint32, Value};
+use ic_dbms_macros::{Encode, TableSchema};
+
+use crate::memory::{SCHEMA_REGISTRY, TableRegistry};
+
+/// A simple person struct with a computed column for testing purposes.
+#[derive(Debug, Encode, Clone, PartialEq, Eq, TableSchema)]
+#[table(
+    name = "persons",
+    computed(name = "full_name", kind = Text, expression = compute_full_name)
+)]
+pub struct Person {
+    #[column(primary_key)]
+    pub id: Uint32,
+    pub first_name: Text,
+    pub last_name: Text,
+}
+
+/// Computes the `full_name` column: `first_name || ' ' || last_name`.
+fn compute_full_name(values: &[(ColumnDef, Value)]) -> Value {
+    let text = |column: &str| {
+        values
+            .iter()
+            .find(|(col_def, _)| col_def.name == column)
+            .and_then(|(_, value)| value.as_text())
+    };
+
+    match (text("first_name"), text("last_name")) {
+        (Some(first_name), Some(last_name)) => {
+            Value::Text(Text(format!("{} {}", first_name.0, last_name.0)))
+        }
+        _ => Value::Null,
+    }
+}
+
+pub const PERSONS_FIXTURES: &[(&str, &str)] = &[("Ada", "Lovelace"), ("Alan", "Turing")];
+
+/// Loads fixtures into the database for testing purposes.
+///
+/// # Panics
+///
+/// Panics if any operation fails.
+pub fn load_fixtures() {
+    // register tables
+    let person_pages = SCHEMA_REGISTRY
+        .with_borrow_mut(|sr| sr.register_table::<Person>())
+        .expect("failed to register `Person` table");
+
+    let mut person_table: TableRegistry =
+        TableRegistry::load(person_pages).expect("failed to load `Person` table registry");
+
+    // insert persons
+    for (id, (first_name, last_name)) in PERSONS_FIXTURES.iter().enumerate() {
+        let person = Person {
+            id: Uint32(id as u32),
+            first_name: Text(first_name.to_string()),
+            last_name: Text(last_name.to_string()),
+        };
+        person_table
+            .insert(person)
+            .expect("failed to insert person");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ic_dbms_api::prelude::TableSchema as _;
+
+    use super::*;
+
+    #[test]
+    fn test_person_encode_decode() {
+        let person = Person {
+            id: 42u32.into(),
+            first_name: "Ada".to_string().into(),
+            last_name: "Lovelace".to_string().into(),
+        };
+        let encoded = person.encode();
+        let decoded = Person::decode(encoded).unwrap();
+        assert_eq!(person, decoded);
+    }
+
+    #[test]
+    fn test_should_derive_computed_column() {
+        let computed = Person::computed_columns();
+        assert_eq!(computed.len(), 1);
+        assert_eq!(computed[0].name, "full_name");
+        let values = Person {
+            id: 1u32.into(),
+            first_name: "Ada".to_string().into(),
+            last_name: "Lovelace".to_string().into(),
+        }
+        .to_values();
+        assert_eq!(
+            (computed[0].expression)(&values),
+            Value::Text("Ada Lovelace".to_string().into())
+        );
+    }
+}
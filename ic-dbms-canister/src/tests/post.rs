@@ -107,32 +107,44 @@ impl TableSchema for Post {
                 data_type: DataTypeKind::Uint32,
                 nullable: false,
                 primary_key: true,
+                unique: false,
+                auto_increment: false,
                 foreign_key: None,
+                check: None,
             },
             ColumnDef {
                 name: "title",
                 data_type: DataTypeKind::Text,
                 nullable: false,
                 primary_key: false,
+                unique: false,
+                auto_increment: false,
                 foreign_key: None,
+                check: None,
             },
             ColumnDef {
                 name: "content",
                 data_type: DataTypeKind::Text,
                 nullable: false,
                 primary_key: false,
+                unique: false,
+                auto_increment: false,
                 foreign_key: None,
+                check: None,
             },
             ColumnDef {
                 name: "user_id",
                 data_type: DataTypeKind::Uint32,
                 nullable: false,
                 primary_key: false,
+                unique: false,
+                auto_increment: false,
                 foreign_key: Some(ForeignKeyDef {
                     local_column: "user_id",
                     foreign_table: "users",
                     foreign_column: "id",
                 }),
+                check: None,
             },
         ]
     }
@@ -141,8 +153,8 @@ impl TableSchema for Post {
         "posts"
     }
 
-    fn primary_key() -> &'static str {
-        "id"
+    fn primary_keys() -> &'static [&'static str] {
+        &["id"]
     }
 
     fn to_values(self) -> Vec<(ColumnDef, Value)> {
@@ -297,13 +309,13 @@ impl InsertRecord for PostInsertRequest {
         ]
     }
 
-    fn into_record(self) -> Self::Schema {
-        Post {
+    fn into_record(self) -> ic_dbms_api::prelude::IcDbmsResult<Self::Schema> {
+        Ok(Post {
             id: self.id,
             title: self.title,
             content: self.content,
             user_id: self.user_id,
-        }
+        })
     }
 }
 
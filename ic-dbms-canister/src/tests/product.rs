@@ -0,0 +1,48 @@
+use ic_dbms_api::prelude::{Array, Encode, Nullable, Text, Uint32, Uint64};
+use ic_dbms_macros::{Encode, InsertRecord, TableSchema};
+
+use crate::memory::SCHEMA_REGISTRY;
+
+/// A product table whose schema and insert record are derived, for testing purposes.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, TableSchema, InsertRecord)]
+#[table(name = "products", insert = Self)]
+pub struct Product {
+    #[column(primary_key)]
+    pub id: Uint32,
+    #[column(not_null, unique)]
+    pub name: Text,
+    pub price: Nullable<Uint64>,
+    pub tags: Array<Text>,
+}
+
+/// Loads fixtures into the database for testing purposes.
+///
+/// # Panics
+///
+/// Panics if any operation fails.
+pub fn load_fixtures() {
+    SCHEMA_REGISTRY
+        .with_borrow_mut(|sr| sr.register_table::<Product>())
+        .expect("failed to register `Product` table");
+}
+
+#[cfg(test)]
+mod tests {
+    use ic_dbms_api::prelude::TableSchema as _;
+
+    use super::*;
+
+    #[test]
+    fn test_product_encode_decode() {
+        let product = Product {
+            id: 1u32.into(),
+            name: "keyboard".to_string().into(),
+            price: Nullable::Value(4999u64.into()),
+            tags: Array(vec!["peripheral".to_string().into()]),
+        };
+        let encoded = product.encode();
+        let decoded = Product::decode(encoded).unwrap();
+        assert_eq!(product, decoded);
+        assert_eq!(Product::table_name(), "products");
+    }
+}
@@ -0,0 +1,138 @@
+use ic_dbms_api::prelude::{
+    ColumnDef, Encode, IcDbmsError, InsertRecord, QueryError, TableSchema as _, Text, Uint64, Value,
+};
+use ic_dbms_macros::{Encode, TableSchema};
+
+use crate::memory::SCHEMA_REGISTRY;
+
+/// A simple ticket struct with an [`ic_dbms_api::prelude::DataTypeKind::AutoIncrement`] primary key
+/// for testing purposes.
+#[derive(Debug, Encode, Clone, PartialEq, Eq, TableSchema)]
+#[table(name = "tickets", insert = TicketInsertRequest)]
+pub struct Ticket {
+    #[column(primary_key, auto_increment)]
+    pub id: Uint64,
+    pub title: Text,
+}
+
+/// Insert request of a [`Ticket`]; the `id` is assigned by the sequence of the table when `None`.
+#[derive(Clone)]
+pub struct TicketInsertRequest {
+    pub id: Option<Uint64>,
+    pub title: Text,
+}
+
+impl InsertRecord for TicketInsertRequest {
+    type Record = TicketRecord;
+    type Schema = Ticket;
+
+    fn from_values(values: &[(ColumnDef, Value)]) -> ic_dbms_api::prelude::IcDbmsResult<Self> {
+        let mut id = None;
+        let mut title = None;
+
+        for (col_def, value) in values {
+            match col_def.name {
+                "id" => {
+                    if let Value::Uint64(v) = value {
+                        id = Some(*v);
+                    }
+                }
+                "title" => {
+                    if let Value::Text(v) = value {
+                        title = Some(v.clone());
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(TicketInsertRequest {
+            id,
+            title: title.ok_or(IcDbmsError::Query(QueryError::MissingNonNullableField(
+                "title",
+            )))?,
+        })
+    }
+
+    fn into_values(self) -> Vec<(ColumnDef, Value)> {
+        vec![
+            (
+                Self::Schema::columns()[0],
+                self.id.map_or(Value::AutoIncrement, Value::Uint64),
+            ),
+            (Self::Schema::columns()[1], Value::Text(self.title)),
+        ]
+    }
+
+    fn into_record(self) -> ic_dbms_api::prelude::IcDbmsResult<Self::Schema> {
+        Ok(Ticket {
+            id: self
+                .id
+                .ok_or(IcDbmsError::Query(QueryError::MissingNonNullableField(
+                    "id",
+                )))?,
+            title: self.title,
+        })
+    }
+}
+
+/// Loads fixtures into the database for testing purposes.
+///
+/// The table is registered empty, so that its sequence starts from `1`.
+///
+/// # Panics
+///
+/// Panics if any operation fails.
+pub fn load_fixtures() {
+    SCHEMA_REGISTRY
+        .with_borrow_mut(|sr| sr.register_table::<Ticket>())
+        .expect("failed to register `Ticket` table");
+}
+
+#[cfg(test)]
+mod tests {
+    use ic_dbms_api::prelude::DataTypeKind;
+
+    use super::*;
+
+    #[test]
+    fn test_ticket_encode_decode() {
+        let ticket = Ticket {
+            id: 42u64.into(),
+            title: "printer is on fire".to_string().into(),
+        };
+        let encoded = ticket.encode();
+        let decoded = Ticket::decode(encoded).unwrap();
+        assert_eq!(ticket, decoded);
+    }
+
+    #[test]
+    fn test_ticket_id_should_be_auto_increment() {
+        let id = Ticket::columns()[0];
+        assert_eq!(id.data_type, DataTypeKind::AutoIncrement);
+        assert!(id.auto_increment);
+        assert_eq!(id.data_type.value_kind(), DataTypeKind::Uint64);
+    }
+
+    #[test]
+    fn test_should_not_convert_ticket_without_id() {
+        let insert = TicketInsertRequest {
+            id: None,
+            title: "printer is on fire".to_string().into(),
+        };
+        assert!(matches!(
+            insert.clone().into_record(),
+            Err(IcDbmsError::Query(QueryError::MissingNonNullableField(
+                "id"
+            )))
+        ));
+
+        let ticket = TicketInsertRequest {
+            id: Some(7u64.into()),
+            ..insert
+        }
+        .into_record()
+        .expect("failed to convert ticket");
+        assert_eq!(ticket.id, 7u64.into());
+    }
+}
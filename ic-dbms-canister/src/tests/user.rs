@@ -73,11 +73,11 @@ impl InsertRecord for UserInsertRequest {
         ]
     }
 
-    fn into_record(self) -> Self::Schema {
-        User {
+    fn into_record(self) -> ic_dbms_api::prelude::IcDbmsResult<Self::Schema> {
+        Ok(User {
             id: self.id,
             name: self.name,
-        }
+        })
     }
 }
 
@@ -121,7 +121,10 @@ impl UpdateRecord for UserUpdateRequest {
                     data_type: DataTypeKind::Uint32,
                     nullable: false,
                     primary_key: true,
+                    unique: false,
+                    auto_increment: false,
                     foreign_key: None,
+                    check: None,
                 },
                 ic_dbms_api::prelude::Value::Uint32(id),
             ));
@@ -133,7 +136,10 @@ impl UpdateRecord for UserUpdateRequest {
                     data_type: DataTypeKind::Text,
                     nullable: false,
                     primary_key: false,
+                    unique: false,
+                    auto_increment: false,
                     foreign_key: None,
+                    check: None,
                 },
                 ic_dbms_api::prelude::Value::Text(name.clone()),
             ));
@@ -212,20 +218,26 @@ impl TableSchema for User {
                 data_type: DataTypeKind::Uint32,
                 nullable: false,
                 primary_key: true,
+                unique: false,
+                auto_increment: false,
                 foreign_key: None,
+                check: None,
             },
             ColumnDef {
                 name: "name",
                 data_type: DataTypeKind::Text,
                 nullable: false,
                 primary_key: false,
+                unique: false,
+                auto_increment: false,
                 foreign_key: None,
+                check: None,
             },
         ]
     }
 
-    fn primary_key() -> &'static str {
-        "id"
+    fn primary_keys() -> &'static [&'static str] {
+        &["id"]
     }
 
     fn to_values(self) -> Vec<(ColumnDef, Value)> {
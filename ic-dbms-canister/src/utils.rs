@@ -1,3 +1,15 @@
+mod caller;
+mod performance_counter;
+mod time;
 mod trap;
 
+pub use self::caller::caller;
+#[cfg(not(target_family = "wasm"))]
+pub use self::caller::set_caller;
+pub use self::performance_counter::performance_counter;
+#[cfg(not(target_family = "wasm"))]
+pub use self::performance_counter::set_performance_counter;
+#[cfg(not(target_family = "wasm"))]
+pub use self::time::set_time;
+pub use self::time::time;
 pub use self::trap::trap;
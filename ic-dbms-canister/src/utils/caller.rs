@@ -0,0 +1,43 @@
+use candid::Principal;
+
+#[cfg(not(target_family = "wasm"))]
+thread_local! {
+    /// Caller returned by [`caller`] on non-Wasm targets.
+    static CALLER: std::cell::Cell<Principal> = const { std::cell::Cell::new(Principal::anonymous()) };
+}
+
+/// Returns the [`Principal`] of the caller of the current message.
+///
+/// On WebAssembly targets, it uses `ic_cdk::api::msg_caller`, while on non-Wasm targets,
+/// it returns the caller set with [`set_caller`], which defaults to [`Principal::anonymous`].
+pub fn caller() -> Principal {
+    #[cfg(target_family = "wasm")]
+    {
+        ic_cdk::api::msg_caller()
+    }
+    #[cfg(not(target_family = "wasm"))]
+    {
+        CALLER.get()
+    }
+}
+
+/// Sets the [`Principal`] returned by [`caller`] on non-Wasm targets, to simulate calls in tests.
+#[cfg(not(target_family = "wasm"))]
+pub fn set_caller(caller: Principal) {
+    CALLER.set(caller);
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_should_get_caller() {
+        assert_eq!(caller(), Principal::anonymous());
+
+        let alice = Principal::from_text("ryjl3-tyaaa-aaaaa-aaaba-cai").unwrap();
+        set_caller(alice);
+        assert_eq!(caller(), alice);
+    }
+}
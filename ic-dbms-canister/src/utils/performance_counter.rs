@@ -0,0 +1,50 @@
+#[cfg(not(target_family = "wasm"))]
+thread_local! {
+    /// Hook returning the value of [`performance_counter`] on non-Wasm targets.
+    static PERFORMANCE_COUNTER_HOOK: std::cell::RefCell<Option<Box<dyn FnMut() -> u64>>> =
+        std::cell::RefCell::new(None);
+}
+
+/// Returns the number of instructions executed since the beginning of the current message.
+///
+/// On WebAssembly targets, it uses `ic_cdk::api::performance_counter(0)`, while on non-Wasm targets,
+/// it returns the value of the hook set with [`set_performance_counter`], which defaults to `0`.
+pub fn performance_counter() -> u64 {
+    #[cfg(target_family = "wasm")]
+    {
+        ic_cdk::api::performance_counter(0)
+    }
+    #[cfg(not(target_family = "wasm"))]
+    {
+        PERFORMANCE_COUNTER_HOOK
+            .with_borrow_mut(|hook| hook.as_mut().map(|hook| hook()).unwrap_or_default())
+    }
+}
+
+/// Sets the hook called by [`performance_counter`] on non-Wasm targets, to simulate instruction usage in tests.
+#[cfg(not(target_family = "wasm"))]
+pub fn set_performance_counter<F>(hook: F)
+where
+    F: FnMut() -> u64 + 'static,
+{
+    PERFORMANCE_COUNTER_HOOK.with_borrow_mut(|current| *current = Some(Box::new(hook)));
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_should_get_performance_counter() {
+        assert_eq!(performance_counter(), 0);
+
+        let mut instructions = 0;
+        set_performance_counter(move || {
+            instructions += 100;
+            instructions
+        });
+        assert_eq!(performance_counter(), 100);
+        assert_eq!(performance_counter(), 200);
+    }
+}
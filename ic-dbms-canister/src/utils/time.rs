@@ -0,0 +1,45 @@
+#[cfg(not(target_family = "wasm"))]
+thread_local! {
+    /// Time returned by [`time`] on non-Wasm targets, if set.
+    static TIME: std::cell::Cell<Option<u64>> = const { std::cell::Cell::new(None) };
+}
+
+/// Returns the current time as nanoseconds since the UNIX epoch.
+///
+/// On WebAssembly targets, it uses `ic_cdk::api::time`, while on non-Wasm targets,
+/// it returns the time set with [`set_time`], which defaults to the system clock.
+pub fn time() -> u64 {
+    #[cfg(target_family = "wasm")]
+    {
+        ic_cdk::api::time()
+    }
+    #[cfg(not(target_family = "wasm"))]
+    {
+        TIME.get().unwrap_or_else(|| {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .expect("system time before UNIX epoch")
+                .as_nanos() as u64
+        })
+    }
+}
+
+/// Sets the time returned by [`time`] on non-Wasm targets, to simulate time in tests.
+#[cfg(not(target_family = "wasm"))]
+pub fn set_time(time: u64) {
+    TIME.set(Some(time));
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_should_get_time() {
+        assert!(time() > 0);
+
+        set_time(100);
+        assert_eq!(time(), 100);
+    }
+}
@@ -0,0 +1,237 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{Field, GenericArgument, Ident, LitStr, Path, PathArguments, PathSegment, Type};
+
+/// Names of the `DataTypeKind` variants, which are also the names of the DBMS types and `Value` variants.
+pub const DATA_TYPE_KINDS: &[&str] = &[
+    "Blob",
+    "Boolean",
+    "Date",
+    "DateTime",
+    "Decimal",
+    "Float64",
+    "Int32",
+    "Int64",
+    "Principal",
+    "Text",
+    "Uint32",
+    "Uint64",
+    "Uuid",
+];
+
+/// A column of the table, read from a struct field and its `#[column(...)]` attribute.
+pub struct Column<'a> {
+    pub field: &'a Ident,
+    pub ty: &'a Type,
+    pub name: String,
+    /// The `DataTypeKind` variant of the column, or of its elements if it's an `Array`.
+    pub kind: Ident,
+    pub array: bool,
+    pub nullable: bool,
+    pub primary_key: bool,
+    pub unique: bool,
+    pub auto_increment: bool,
+    pub foreign_key: Option<(String, String)>,
+    /// The description and the predicate function of the CHECK constraint of the column, if any.
+    pub check: Option<(LitStr, Path)>,
+}
+
+/// Reads the column of the given field.
+///
+/// # Errors
+///
+/// Fails if the field is unnamed, if its type is not a DBMS type, an `Array` of a DBMS type,
+/// nor a `Nullable` of them, or if its `column` attribute is invalid.
+pub fn column<'a>(derive: &str, table: &Ident, field: &'a Field) -> syn::Result<Column<'a>> {
+    let Some(field_ident) = field.ident.as_ref() else {
+        return Err(syn::Error::new_spanned(
+            field,
+            format!("Cannot derive {derive} for {table}; tuple structs are not supported"),
+        ));
+    };
+    let Some((kind, array, nullable)) = data_type_kind(&field.ty) else {
+        return Err(syn::Error::new_spanned(
+            &field.ty,
+            format!(
+                "Cannot derive {derive} for {table}; the type of `{field_ident}` must be a DBMS type, an `Array` of a DBMS type or a `Nullable` of them"
+            ),
+        ));
+    };
+
+    let mut column = Column {
+        field: field_ident,
+        ty: &field.ty,
+        name: field_ident.to_string(),
+        kind,
+        array,
+        nullable,
+        primary_key: false,
+        unique: false,
+        auto_increment: false,
+        foreign_key: None,
+        check: None,
+    };
+    let mut not_null = false;
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("column"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                column.name = meta.value()?.parse::<LitStr>()?.value();
+            } else if meta.path.is_ident("primary_key") {
+                column.primary_key = true;
+            } else if meta.path.is_ident("not_null") {
+                not_null = true;
+            } else if meta.path.is_ident("unique") {
+                column.unique = true;
+            } else if meta.path.is_ident("auto_increment") {
+                column.auto_increment = true;
+            } else if meta.path.is_ident("foreign_key") {
+                let mut foreign_table = None;
+                let mut foreign_column = None;
+                meta.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("table") {
+                        foreign_table = Some(meta.value()?.parse::<LitStr>()?.value());
+                        Ok(())
+                    } else if meta.path.is_ident("column") {
+                        foreign_column = Some(meta.value()?.parse::<LitStr>()?.value());
+                        Ok(())
+                    } else {
+                        Err(meta.error("unsupported foreign_key attribute; expected `table` or `column`"))
+                    }
+                })?;
+                let (Some(foreign_table), Some(foreign_column)) = (foreign_table, foreign_column)
+                else {
+                    return Err(meta.error("foreign_key requires both `table` and `column`"));
+                };
+                column.foreign_key = Some((foreign_table, foreign_column));
+            } else if meta.path.is_ident("check") {
+                let mut description = None;
+                let mut predicate = None;
+                meta.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("description") {
+                        description = Some(meta.value()?.parse::<LitStr>()?);
+                        Ok(())
+                    } else if meta.path.is_ident("predicate") {
+                        predicate = Some(meta.value()?.parse::<Path>()?);
+                        Ok(())
+                    } else {
+                        Err(meta.error("unsupported check attribute; expected `description` or `predicate`"))
+                    }
+                })?;
+                let (Some(description), Some(predicate)) = (description, predicate) else {
+                    return Err(meta.error("check requires both `description` and `predicate`"));
+                };
+                column.check = Some((description, predicate));
+            } else {
+                return Err(meta.error(
+                    "unsupported column attribute; expected `name`, `primary_key`, `not_null`, `unique`, `auto_increment`, `foreign_key` or `check`",
+                ));
+            }
+            Ok(())
+        })?;
+    }
+
+    if not_null && column.nullable {
+        return Err(syn::Error::new_spanned(
+            &field.ty,
+            format!(
+                "Cannot derive {derive} for {table}; `{field_ident}` is marked `not_null`, but its type is `Nullable`"
+            ),
+        ));
+    }
+    if column.primary_key && column.nullable {
+        return Err(syn::Error::new_spanned(
+            &field.ty,
+            format!(
+                "Cannot derive {derive} for {table}; the primary key `{field_ident}` can't be `Nullable`"
+            ),
+        ));
+    }
+
+    Ok(column)
+}
+
+/// Returns the `DataTypeKind` variant of the given field type, whether it's an `Array` and whether it's a `Nullable`.
+fn data_type_kind(ty: &Type) -> Option<(Ident, bool, bool)> {
+    let Type::Path(type_path) = ty else {
+        return None;
+    };
+    let segment = type_path.path.segments.last()?;
+    if segment.ident == "Nullable" {
+        return data_type_kind(type_argument(segment)?)
+            .filter(|(_, _, nullable)| !nullable)
+            .map(|(kind, array, _)| (kind, array, true));
+    }
+    if segment.ident == "Array" {
+        return scalar_kind(type_argument(segment)?).map(|kind| (kind, true, false));
+    }
+
+    scalar_kind(ty).map(|kind| (kind, false, false))
+}
+
+/// Returns the `DataTypeKind` variant of the given field type, if it's a DBMS type.
+fn scalar_kind(ty: &Type) -> Option<Ident> {
+    let Type::Path(type_path) = ty else {
+        return None;
+    };
+    let segment = type_path.path.segments.last()?;
+    (segment.arguments.is_empty() && DATA_TYPE_KINDS.contains(&segment.ident.to_string().as_str()))
+        .then(|| segment.ident.clone())
+}
+
+/// Returns the single type argument of the given path segment, e.g. `T` for `Nullable<T>`.
+fn type_argument(segment: &PathSegment) -> Option<&Type> {
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    match args.args.first() {
+        Some(GenericArgument::Type(inner)) if args.args.len() == 1 => Some(inner),
+        _ => None,
+    }
+}
+
+/// Generate the expression converting the `value` of the given column into an `Option` of the field type;
+/// values of another type are ignored.
+pub fn from_value(column: &Column) -> TokenStream2 {
+    let kind = &column.kind;
+    if column.array {
+        let array = quote! {
+            ::ic_dbms_api::prelude::Array::<::ic_dbms_api::prelude::#kind>::try_from(value).ok()
+        };
+        return if column.nullable {
+            quote! {
+                match value {
+                    ::ic_dbms_api::prelude::Value::Null => Some(::ic_dbms_api::prelude::Nullable::Null),
+                    value @ ::ic_dbms_api::prelude::Value::Array(_) => #array.map(::ic_dbms_api::prelude::Nullable::Value),
+                    _ => None,
+                }
+            }
+        } else {
+            quote! {
+                match value {
+                    value @ ::ic_dbms_api::prelude::Value::Array(_) => #array,
+                    _ => None,
+                }
+            }
+        };
+    }
+    if column.nullable {
+        quote! {
+            match value {
+                ::ic_dbms_api::prelude::Value::Null => Some(::ic_dbms_api::prelude::Nullable::Null),
+                ::ic_dbms_api::prelude::Value::#kind(value) => Some(::ic_dbms_api::prelude::Nullable::Value(value)),
+                _ => None,
+            }
+        }
+    } else {
+        quote! {
+            match value {
+                ::ic_dbms_api::prelude::Value::#kind(value) => Some(value),
+                _ => None,
+            }
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use proc_macro::TokenStream;
 use proc_macro2::TokenStream as TokenStream2;
-use syn::{DataStruct, DeriveInput};
+use syn::{DataStruct, DeriveInput, Field};
 
 use crate::utils;
 
@@ -37,11 +37,32 @@ pub fn encode(
     .into()
 }
 
+/// Returns the fields to encode, excluding the ones marked with `#[encode(skip)]`.
+fn encoded_fields(struct_data: &DataStruct) -> impl Iterator<Item = &Field> {
+    struct_data
+        .fields
+        .iter()
+        .filter(|field| !utils::is_skipped(field))
+}
+
 /// Generate implementation of `SIZE` const value.
 fn impl_size_const(struct_data: &DataStruct) -> TokenStream2 {
+    let fields_len = encoded_fields(struct_data).count();
+    if fields_len == 0 {
+        return quote::quote! {
+            ::ic_dbms_api::prelude::DataSize::Fixed(0)
+        };
+    }
+
     let tuple_expansion = {
-        let items = struct_data.fields.iter().map(|field| {
+        let items = encoded_fields(struct_data).map(|field| {
             let field_ty = &field.ty;
+            // optional fields are encoded with a tag, followed by the payload only if set
+            if utils::option_inner_type(field_ty).is_some() {
+                return quote::quote! {
+                    ::ic_dbms_api::prelude::DataSize::Dynamic
+                };
+            }
             quote::quote! {
                 <#field_ty as ::ic_dbms_api::prelude::Encode>::SIZE
             }
@@ -50,7 +71,7 @@ fn impl_size_const(struct_data: &DataStruct) -> TokenStream2 {
     };
 
     let anon_idents = utils::anon_ident_iter(None)
-        .take(struct_data.fields.len())
+        .take(fields_len)
         .collect::<Vec<_>>();
 
     // extract sizes from fields
@@ -66,10 +87,16 @@ fn impl_size_const(struct_data: &DataStruct) -> TokenStream2 {
 
 /// Generate implementation of `size` method.
 fn impl_size(struct_data: &DataStruct) -> TokenStream2 {
-    let items = struct_data.fields.iter().map(|field| {
+    let items = encoded_fields(struct_data).map(|field| {
         let field_name = &field.ident;
         let field_ty = &field.ty;
 
+        if let Some(inner_ty) = utils::option_inner_type(field_ty) {
+            return quote::quote! {
+                (1 + self.#field_name.as_ref().map_or(0, <#inner_ty as ::ic_dbms_api::prelude::Encode>::size))
+            };
+        }
+
         quote::quote! {
             <#field_ty as ::ic_dbms_api::prelude::Encode>::size(&self.#field_name)
         }
@@ -85,10 +112,23 @@ fn impl_size(struct_data: &DataStruct) -> TokenStream2 {
 /// Generate implementation of `encode` method.
 fn impl_encode(struct_data: &DataStruct) -> TokenStream2 {
     // make token for each field for encoding
-    let encodings = struct_data.fields.iter().map(|field| {
+    let encodings = encoded_fields(struct_data).map(|field| {
         let field_ty = &field.ty;
         let field_name = &field.ident;
 
+        // `Option<T>` is encoded as a tag byte (0 = None, 1 = Some) followed by the payload
+        if let Some(inner_ty) = utils::option_inner_type(field_ty) {
+            return quote::quote! {
+                match &self.#field_name {
+                    None => encoded.push(0),
+                    Some(value) => {
+                        encoded.push(1);
+                        encoded.extend_from_slice(&<#inner_ty as ::ic_dbms_api::prelude::Encode>::encode(value));
+                    }
+                }
+            };
+        }
+
         quote::quote! {
             encoded.extend_from_slice(&<#field_ty as ::ic_dbms_api::prelude::Encode>::encode(&self.#field_name));
         }
@@ -109,8 +149,31 @@ fn impl_decode(struct_data: &DataStruct) -> TokenStream2 {
         let field_name = &field.ident;
         let field_ty = &field.ty;
 
+        // skipped fields are not encoded, so they're restored to their default value
+        if utils::is_skipped(field) {
+            return quote::quote! {
+                let #field_name = <#field_ty as ::std::default::Default>::default();
+            };
+        }
+
+        if let Some(inner_ty) = utils::option_inner_type(field_ty) {
+            return quote::quote! {
+                let __option_tag = *data.get(offset).ok_or(::ic_dbms_api::prelude::MemoryError::DecodeError(
+                    ::ic_dbms_api::prelude::DecodeError::TooShort,
+                ))?;
+                offset += 1;
+                let #field_name = if __option_tag == 0 {
+                    None
+                } else {
+                    let value = <#inner_ty as ::ic_dbms_api::prelude::Encode>::decode_ref(&data[offset..])?;
+                    offset += <#inner_ty as ::ic_dbms_api::prelude::Encode>::size(&value) as usize;
+                    Some(value)
+                };
+            };
+        }
+
         quote::quote! {
-            let #field_name = <#field_ty as ::ic_dbms_api::prelude::Encode>::decode(std::borrow::Cow::Borrowed(&data[offset..]))?;
+            let #field_name = <#field_ty as ::ic_dbms_api::prelude::Encode>::decode_ref(&data[offset..])?;
             offset += #field_name.size() as usize;
         }
     });
@@ -0,0 +1,176 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{Attribute, DeriveInput, Ident, Type};
+
+use crate::column::{Column, column, from_value};
+
+/// Generate implementation of `InsertRecord` trait.
+pub fn insert_record(input: DeriveInput) -> TokenStream {
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand(
+    DeriveInput {
+        attrs, ident, data, ..
+    }: DeriveInput,
+) -> syn::Result<TokenStream2> {
+    let syn::Data::Struct(struct_data) = data else {
+        return Err(syn::Error::new_spanned(
+            &ident,
+            format!("Cannot derive InsertRecord for {ident}; it can only be derived for structs"),
+        ));
+    };
+
+    let (schema, schema_fields) = match schema_type(&attrs)? {
+        Some(schema) => (quote! { #schema }, SchemaFields::ColumnNames),
+        None => (quote! { Self }, SchemaFields::Fields),
+    };
+    let columns = struct_data
+        .fields
+        .iter()
+        .map(|field| column("InsertRecord", &ident, field))
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    impl_insert_record(&ident, &schema, schema_fields, &columns)
+}
+
+/// How the fields of the table schema are named, to build it in `into_record`.
+#[derive(Clone, Copy)]
+pub enum SchemaFields {
+    /// The fields of the table schema are the fields of the insert record.
+    Fields,
+    /// The fields of the table schema are named after the columns of the insert record.
+    ColumnNames,
+}
+
+/// Generate the `InsertRecord` implementation of `ident` for the given table schema,
+/// whose fields are the given columns.
+///
+/// # Errors
+///
+/// Fails if the fields of the table schema are named after the columns,
+/// but the name of a column is not an identifier.
+pub fn impl_insert_record(
+    ident: &Ident,
+    schema: &TokenStream2,
+    schema_fields: SchemaFields,
+    columns: &[Column],
+) -> syn::Result<TokenStream2> {
+    let fields = columns
+        .iter()
+        .map(|column| column.field)
+        .collect::<Vec<_>>();
+    // spanned at the insert record fields, so that a column which is not a field of the table
+    // is reported there, while a field of the table missing from the insert record is reported
+    // at the table schema path
+    let record_fields = match schema_fields {
+        SchemaFields::Fields => columns.iter().map(|column| column.field.clone()).collect(),
+        SchemaFields::ColumnNames => columns
+            .iter()
+            .map(|column| {
+                let mut record_field = syn::parse_str::<Ident>(&column.name).map_err(|_| {
+                    syn::Error::new_spanned(
+                        column.field,
+                        format!(
+                            "Cannot derive InsertRecord for {ident}; the column `{}` can't be a field of the table",
+                            column.name
+                        ),
+                    )
+                })?;
+                record_field.set_span(column.field.span());
+                Ok(record_field)
+            })
+            .collect::<syn::Result<Vec<_>>>()?,
+    };
+    let types = columns.iter().map(|column| column.ty);
+    let names = columns
+        .iter()
+        .map(|column| column.name.as_str())
+        .collect::<Vec<_>>();
+    // prefixed, so that they can't shadow the other variables of the generated code
+    let vars = columns
+        .iter()
+        .map(|column| format_ident!("__{}", column.field))
+        .collect::<Vec<_>>();
+    let from_values = columns.iter().map(from_value);
+    let unwraps = columns.iter().zip(&vars).map(|(column, var)| {
+        let name = &column.name;
+        if column.nullable {
+            quote! {
+                #var.unwrap_or(::ic_dbms_api::prelude::Nullable::Null)
+            }
+        } else {
+            quote! {
+                #var.ok_or(::ic_dbms_api::prelude::IcDbmsError::Query(
+                    ::ic_dbms_api::prelude::QueryError::MissingNonNullableField(#name),
+                ))?
+            }
+        }
+    });
+
+    Ok(quote! {
+        impl ::ic_dbms_api::prelude::InsertRecord for #ident {
+            type Record = <#schema as ::ic_dbms_api::prelude::TableSchema>::Record;
+            type Schema = #schema;
+
+            fn from_values(
+                values: &[(::ic_dbms_api::prelude::ColumnDef, ::ic_dbms_api::prelude::Value)],
+            ) -> ::ic_dbms_api::prelude::IcDbmsResult<Self> {
+                #(let mut #vars: Option<#types> = None;)*
+
+                for (column, value) in values.iter().cloned() {
+                    match column.name {
+                        #(#names => #vars = #from_values,)*
+                        _ => {}
+                    }
+                }
+
+                Ok(Self {
+                    #(#fields: #unwraps,)*
+                })
+            }
+
+            fn into_values(self) -> Vec<(::ic_dbms_api::prelude::ColumnDef, ::ic_dbms_api::prelude::Value)> {
+                let columns = <#schema as ::ic_dbms_api::prelude::TableSchema>::columns();
+                let column_def = |name: &str| {
+                    *columns
+                        .iter()
+                        .find(|column| column.name == name)
+                        .expect("the insert record field is not a column of the table")
+                };
+
+                vec![#((column_def(#names), ::ic_dbms_api::prelude::Value::from(self.#fields))),*]
+            }
+
+            fn into_record(self) -> ::ic_dbms_api::prelude::IcDbmsResult<Self::Schema> {
+                Ok(#schema {
+                    #(#record_fields: self.#fields,)*
+                })
+            }
+        }
+    })
+}
+
+/// Returns the table schema set with `#[insert(schema = ...)]`, if any.
+///
+/// # Errors
+///
+/// Fails if the `insert` attribute has an argument other than `schema`.
+fn schema_type(attrs: &[Attribute]) -> syn::Result<Option<Type>> {
+    let mut schema = None;
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("insert")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("schema") {
+                schema = Some(meta.value()?.parse::<Type>()?);
+                Ok(())
+            } else {
+                Err(meta.error("unsupported insert attribute; expected `schema`"))
+            }
+        })?;
+    }
+
+    Ok(schema)
+}
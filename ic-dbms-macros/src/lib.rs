@@ -10,6 +10,14 @@
 //! ## Provided Derive Macros
 //!
 //! - `Encode`: Automatically implements the `Encode` trait for structs.
+//! - `TableSchema`: Automatically implements the `TableSchema` trait for structs,
+//!   along with the record, insert and update types of the table.
+//! - `InsertRecord`: Automatically implements the `InsertRecord` trait for structs.
+//! - `UpdateRecord`: Automatically implements the `UpdateRecord` trait for structs.
+//!
+//! ## Provided Attribute Macros
+//!
+//! - `memory_test`: Runs a test restoring the memory state once it's finished.
 //!
 
 #![doc(html_playground_url = "https://play.rust-lang.org")]
@@ -21,9 +29,14 @@
 )]
 
 use proc_macro::TokenStream;
-use syn::{DeriveInput, parse_macro_input};
+use syn::{DeriveInput, ItemFn, parse_macro_input};
 
+mod column;
 mod encode;
+mod insert_record;
+mod memory_test;
+mod table_schema;
+mod update_record;
 mod utils;
 
 /// Automatically implements the `Encode`` trait for a struct.
@@ -71,17 +84,25 @@ mod utils;
 ///
 ///     fn decode(data: std::borrow::Cow<[u8]>) -> ::ic_dbms_canister::prelude::MemoryResult<Self> {
 ///         let mut offset = 0;
-///         let id = Uint32::decode(std::borrow::Borrowed(&data[offset..]))?;
+///         let id = Uint32::decode_ref(&data[offset..])?;
 ///         offset += id.size() as usize;
-///         let name = Text::decode(std::borrow::Borrowed(&data[offset..]))?;
+///         let name = Text::decode_ref(&data[offset..])?;
 ///         offset += name.size() as usize;
 ///         Ok(Self { id, name })
 ///     }
 /// }
 /// ```
+/// `Option<T>` fields are supported as long as `T` implements `Encode`:
+/// they are encoded as a tag byte (`0` for `None`, `1` for `Some`) followed by the encoded value if set.
+/// Structs with `Option<T>` fields always have a `DataSize::Dynamic` size.
+///
+/// Fields marked with `#[encode(skip)]` are not encoded, and don't contribute to `SIZE` nor `size()`:
+/// they're set to their `Default` value on decode. Use it for runtime state which must not be persisted.
+///
 /// # Requirements
 ///
-/// - Each field type must implement `Encode`.
+/// - Each field type must implement `Encode`, or be an `Option<T>` where `T` implements `Encode`.
+/// - Each field marked with `#[encode(skip)]` must implement `Default`.
 /// - Only works on `struct`s; enums and unions are not supported.
 /// - All field identifiers must be valid Rust identifiers (no tuple structs).
 ///
@@ -95,7 +116,7 @@ mod utils;
 /// The macro will fail to expand if:
 ///
 /// - The struct has unnamed fields (tuple struct)
-/// - A field type does not implement `Encode`
+/// - A field type does not implement `Encode` (nor is an `Option` of an `Encode` type)
 /// - The macro is applied to a non-struct item.
 ///
 /// # Example
@@ -114,8 +135,232 @@ mod utils;
 /// let decoded = Position::decode(encoded).unwrap();
 /// assert_eq!(pos, decoded);
 /// ```
-#[proc_macro_derive(Encode)]
+#[proc_macro_derive(Encode, attributes(encode))]
 pub fn derive_encode(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     self::encode::encode(input)
 }
+
+/// Automatically implements the `TableSchema` trait for a struct.
+///
+/// Along with the `TableSchema` implementation, the macro generates the types of the table
+/// named after the struct, e.g. for `User`:
+///
+/// - `UserRecord`: the `TableRecord` returned by queries, with an `Option` for each field.
+/// - `UserInsertRequest`: the `InsertRecord`, with the same fields of the struct.
+/// - `UserUpdateRequest`: the `UpdateRecord`, with an `Option` for each field and a `where_clause`.
+///
+/// The data type of each column is the type of its field, which must be one of the DBMS types
+/// (e.g. `Uint32`, `Text`) or an `Array` of one of them (e.g. `Array<Uint32>`), or a `Nullable` of them
+/// for nullable columns.
+/// The fingerprint of the table is the default one.
+///
+/// # Attributes
+///
+/// - `#[table(name = "...")]` on the struct sets the name of the table; it defaults to the struct name in snake case.
+/// - `#[table(insert = ...)]` on the struct sets the `InsertRecord` type of the table, e.g. derived with
+///   [`macro@InsertRecord`], instead of generating it.
+/// - `#[table(soft_delete = "...")]` on the struct makes the table soft-deletable on the given
+///   `Nullable<DateTime>` column, implementing `SoftDeleteSchema` too.
+/// - `#[table(computed(name = "...", kind = ..., expression = ...))]` on the struct adds a computed column,
+///   whose DBMS type is `kind` and whose value is returned by the `expression` function;
+///   the record type gets a field for it. It can be repeated.
+/// - `#[table(foreign_fetcher = ...)]` on the struct sets the `ForeignFetcher` loading the eager relations
+///   of the table; it defaults to `NoForeignFetcher`, which can't load them.
+/// - `#[column(...)]` on the fields sets the properties of the columns:
+///   - `name = "..."`: the name of the column, which defaults to the field name.
+///   - `primary_key`: marks the column as part of the primary key, in field order; at least one field must be marked.
+///   - `not_null`: asserts the column is not nullable, which is the default for non-`Nullable` fields.
+///   - `unique`: marks the column as unique.
+///   - `auto_increment`: marks the column as auto-increment; a `Uint64` column is an `AutoIncrement` one.
+///   - `foreign_key(table = "...", column = "...")`: sets the foreign key of the column.
+///   - `check(description = "...", predicate = ...)`: sets the CHECK constraint of the column,
+///     satisfied by the values for which the `predicate` function returns `true`.
+///
+/// # Requirements
+///
+/// - The struct must also implement `Encode`, e.g. with `#[derive(Encode)]`.
+/// - Only works on `struct`s with named fields.
+///
+/// # Errors
+///
+/// The macro will fail to expand if:
+///
+/// - The struct has unnamed fields (tuple struct), or it's not a struct.
+/// - A field type is not a DBMS type, an `Array` of a DBMS type, nor a `Nullable` of them.
+/// - No field is marked as `primary_key`.
+/// - A `Nullable` field is marked as `not_null` or `primary_key`.
+/// - The `soft_delete` column is not a `Nullable<DateTime>` column, or a computed column `kind` is not a DBMS type.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// #[derive(Debug, Clone, PartialEq, Eq, Encode, TableSchema)]
+/// #[table(name = "posts")]
+/// struct Post {
+///     #[column(primary_key)]
+///     id: Uint32,
+///     title: Text,
+///     #[column(name = "user_id", not_null, foreign_key(table = "users", column = "id"))]
+///     author: Uint32,
+/// }
+///
+/// assert_eq!(Post::table_name(), "posts");
+/// assert_eq!(Post::columns()[2].name, "user_id");
+/// ```
+#[proc_macro_derive(TableSchema, attributes(table, column))]
+pub fn derive_table_schema(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    self::table_schema::table_schema(input)
+}
+
+/// Automatically implements the `InsertRecord` trait for a struct.
+///
+/// The fields of the struct are read as [`macro@TableSchema`] does, including their `#[column(...)]` attributes,
+/// and must be columns of the table. `from_values` fails with `QueryError::MissingNonNullableField`
+/// if a non-`Nullable` field has no value, while missing `Nullable` fields are set to `Nullable::Null`.
+///
+/// The table is the struct itself, unless it is set with `#[insert(schema = ...)]`;
+/// in that case, `into_record` builds the table by naming its fields after the columns of the insert record,
+/// so that each field of the table must be named after one of them.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// #[derive(Debug, Clone, PartialEq, Eq, Encode, TableSchema, InsertRecord)]
+/// #[table(name = "tags", insert = Self)]
+/// struct Tag {
+///     #[column(primary_key)]
+///     id: Uint32,
+///     label: Text,
+/// }
+///
+/// #[derive(Clone, InsertRecord)]
+/// #[insert(schema = Tag)]
+/// struct NewTag {
+///     id: Uint32,
+///     label: Text,
+/// }
+/// ```
+///
+/// For `NewTag`, the macro expands into:
+///
+/// ```rust,ignore
+/// impl InsertRecord for NewTag {
+///     type Record = <Tag as TableSchema>::Record;
+///     type Schema = Tag;
+///
+///     fn from_values(values: &[(ColumnDef, Value)]) -> IcDbmsResult<Self> {
+///         let mut __id: Option<Uint32> = None;
+///         let mut __label: Option<Text> = None;
+///         for (column, value) in values.iter().cloned() {
+///             match column.name {
+///                 "id" => __id = match value { Value::Uint32(value) => Some(value), _ => None },
+///                 "label" => __label = match value { Value::Text(value) => Some(value), _ => None },
+///                 _ => {}
+///             }
+///         }
+///         Ok(Self {
+///             id: __id.ok_or(IcDbmsError::Query(QueryError::MissingNonNullableField("id")))?,
+///             label: __label.ok_or(IcDbmsError::Query(QueryError::MissingNonNullableField("label")))?,
+///         })
+///     }
+///
+///     fn into_values(self) -> Vec<(ColumnDef, Value)> { /* each field with its column */ }
+///
+///     fn into_record(self) -> IcDbmsResult<Self::Schema> {
+///         Ok(Tag { id: self.id, label: self.label })
+///     }
+/// }
+/// ```
+#[proc_macro_derive(InsertRecord, attributes(insert, column))]
+pub fn derive_insert_record(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    self::insert_record::insert_record(input)
+}
+
+/// Automatically implements the `UpdateRecord` trait for a struct.
+///
+/// Every field of the struct must be an `Option` of a column of the table, read as [`macro@TableSchema`] does,
+/// including its `#[column(...)]` attributes, but for the `where_clause: Option<Filter>` field.
+/// `update_values` returns only the fields which are set, while `where_clause` returns the `where_clause` field.
+///
+/// The table must be set with `#[update(schema = ...)]`.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// #[derive(Clone, UpdateRecord)]
+/// #[update(schema = Tag)]
+/// struct TagUpdate {
+///     #[column(name = "label")]
+///     text: Option<Text>,
+///     color: Option<Nullable<Text>>,
+///     where_clause: Option<Filter>,
+/// }
+/// ```
+///
+/// The macro expands into:
+///
+/// ```rust,ignore
+/// impl UpdateRecord for TagUpdate {
+///     type Record = <Tag as TableSchema>::Record;
+///     type Schema = Tag;
+///
+///     fn from_values(values: &[(ColumnDef, Value)], where_clause: Option<Filter>) -> Self {
+///         let mut __text: Option<Text> = None;
+///         let mut __color: Option<Nullable<Text>> = None;
+///         for (column, value) in values.iter().cloned() {
+///             match column.name {
+///                 "label" => __text = match value { Value::Text(value) => Some(value), _ => None },
+///                 "color" => __color = /* null or the text value */,
+///                 _ => {}
+///             }
+///         }
+///         Self { text: __text, color: __color, where_clause }
+///     }
+///
+///     fn update_values(&self) -> Vec<(ColumnDef, Value)> { /* each set field with its column */ }
+///
+///     fn where_clause(&self) -> Option<Filter> {
+///         self.where_clause.clone()
+///     }
+/// }
+/// ```
+#[proc_macro_derive(UpdateRecord, attributes(update, column))]
+pub fn derive_update_record(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    self::update_record::update_record(input)
+}
+
+/// Marks a function as a test which doesn't leak its memory state.
+///
+/// A snapshot of the `MEMORY_MANAGER` is taken before running the test body and restored afterwards;
+/// the schema registry and the ACL are then reloaded from the restored memory.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// #[memory_test]
+/// fn test_should_insert_user() {
+///     // memory written here is not visible to the next test running on the same thread
+/// }
+/// ```
+///
+/// The macro expands into:
+///
+/// ```rust,ignore
+/// #[test]
+/// fn test_should_insert_user() {
+///     let __snapshot = MEMORY_MANAGER.with_borrow(|manager| manager.snapshot());
+///     let __result = (|| { /* test body */ })();
+///     MEMORY_MANAGER.with_borrow_mut(|manager| manager.restore_snapshot(__snapshot));
+///     // reload SCHEMA_REGISTRY and ACL
+///     __result
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn memory_test(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    let item = parse_macro_input!(item as ItemFn);
+    self::memory_test::memory_test(item)
+}
@@ -0,0 +1,36 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::ItemFn;
+
+/// Wraps the test function body with a memory snapshot and its restore.
+pub fn memory_test(item: ItemFn) -> TokenStream {
+    let ItemFn {
+        attrs,
+        vis,
+        sig,
+        block,
+    } = item;
+    let output = &sig.output;
+
+    let expanded = quote! {
+        #(#attrs)*
+        #[test]
+        #vis #sig {
+            let __snapshot = ::ic_dbms_canister::memory::MEMORY_MANAGER
+                .with_borrow(|manager| manager.snapshot());
+            let __result = (|| #output #block)();
+            ::ic_dbms_canister::memory::MEMORY_MANAGER
+                .with_borrow_mut(|manager| manager.restore_snapshot(__snapshot));
+            // reload the registries cached in memory, so they don't point to restored pages
+            ::ic_dbms_canister::memory::SCHEMA_REGISTRY.with_borrow_mut(|registry| {
+                *registry = ::ic_dbms_canister::memory::SchemaRegistry::load().unwrap_or_default();
+            });
+            ::ic_dbms_canister::memory::ACL.with_borrow_mut(|acl| {
+                *acl = ::ic_dbms_canister::memory::AccessControlList::load().unwrap_or_default();
+            });
+            __result
+        }
+    };
+
+    expanded.into()
+}
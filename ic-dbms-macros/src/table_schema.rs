@@ -0,0 +1,427 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{Attribute, DeriveInput, Ident, LitStr, Path, Type};
+
+use crate::column::{Column, DATA_TYPE_KINDS, column, from_value};
+use crate::insert_record::{SchemaFields, impl_insert_record};
+use crate::update_record::impl_update_record;
+
+/// Generate implementation of `TableSchema` trait, along with the record, insert and update types of the table.
+pub fn table_schema(input: DeriveInput) -> TokenStream {
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand(
+    DeriveInput {
+        attrs,
+        vis,
+        ident,
+        data,
+        ..
+    }: DeriveInput,
+) -> syn::Result<TokenStream2> {
+    let syn::Data::Struct(struct_data) = data else {
+        return Err(syn::Error::new_spanned(
+            &ident,
+            format!("Cannot derive TableSchema for {ident}; it can only be derived for structs"),
+        ));
+    };
+
+    let TableAttrs {
+        name,
+        insert,
+        soft_delete,
+        computed,
+        foreign_fetcher,
+    } = table_attrs(&attrs)?;
+    let table_name = name.unwrap_or_else(|| snake_case(&ident.to_string()));
+    let columns = struct_data
+        .fields
+        .iter()
+        .map(|field| column("TableSchema", &ident, field))
+        .collect::<syn::Result<Vec<_>>>()?;
+    let primary_keys = columns
+        .iter()
+        .filter(|column| column.primary_key)
+        .map(|column| column.name.as_str())
+        .collect::<Vec<_>>();
+    if primary_keys.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &ident,
+            format!(
+                "Cannot derive TableSchema for {ident}; at least one field must be marked with `#[column(primary_key)]`"
+            ),
+        ));
+    }
+
+    let record_ident = format_ident!("{ident}Record");
+    let update_ident = format_ident!("{ident}UpdateRequest");
+
+    let fields = columns
+        .iter()
+        .map(|column| column.field)
+        .collect::<Vec<_>>();
+    let types = columns.iter().map(|column| column.ty).collect::<Vec<_>>();
+    let names = columns
+        .iter()
+        .map(|column| column.name.as_str())
+        .collect::<Vec<_>>();
+    let indexes = 0..columns.len();
+    let column_defs = columns.iter().map(column_def);
+    let from_values = columns.iter().map(from_value);
+    // the insert type is generated, unless the table uses its own `InsertRecord`
+    let (insert_type, insert_record) = match insert {
+        Some(insert_type) => (quote! { #insert_type }, TokenStream2::new()),
+        None => {
+            let insert_ident = format_ident!("{ident}InsertRequest");
+            let insert_record = impl_insert_record(
+                &insert_ident,
+                &quote! { #ident },
+                SchemaFields::Fields,
+                &columns,
+            )?;
+            (
+                quote! { #insert_ident },
+                quote! {
+                    #[derive(Debug, Clone, PartialEq, Eq)]
+                    #vis struct #insert_ident {
+                        #(pub #fields: #types,)*
+                    }
+
+                    #insert_record
+                },
+            )
+        }
+    };
+    let update_record = impl_update_record(&update_ident, &quote! { #ident }, &columns);
+    let (soft_delete_column, soft_delete_schema) =
+        soft_delete_impl(&ident, &columns, soft_delete.as_ref())?;
+    // eager relations are loaded by the given fetcher; without it, they can't be loaded
+    let foreign_fetcher = match foreign_fetcher {
+        Some(foreign_fetcher) => quote! { #foreign_fetcher },
+        None => quote! { ::ic_dbms_api::prelude::NoForeignFetcher },
+    };
+
+    // computed columns are only read by queries, so they are fields of the record type only
+    for computed in &computed {
+        if !DATA_TYPE_KINDS.contains(&computed.kind.to_string().as_str()) {
+            return Err(syn::Error::new_spanned(
+                &computed.kind,
+                format!(
+                    "Cannot derive TableSchema for {ident}; the type of the computed column `{}` must be a DBMS type",
+                    computed.name.value()
+                ),
+            ));
+        }
+    }
+    let computed_fields = computed
+        .iter()
+        .map(|computed| format_ident!("{}", computed.name.value()))
+        .collect::<Vec<_>>();
+    let computed_types = computed
+        .iter()
+        .map(|computed| &computed.kind)
+        .collect::<Vec<_>>();
+    let computed_names = computed.iter().map(|computed| &computed.name);
+    let computed_defs = computed.iter().map(|computed| {
+        let ComputedColumn {
+            name,
+            kind,
+            expression,
+        } = computed;
+        quote! {
+            ::ic_dbms_api::prelude::ComputedColumnDef {
+                name: #name,
+                data_type: ::ic_dbms_api::prelude::DataTypeKind::#kind,
+                expression: #expression,
+            }
+        }
+    });
+
+    Ok(quote! {
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        #vis struct #record_ident {
+            #(pub #fields: Option<#types>,)*
+            #(pub #computed_fields: Option<::ic_dbms_api::prelude::#computed_types>,)*
+        }
+
+        #insert_record
+
+        #[derive(Debug, Clone)]
+        #vis struct #update_ident {
+            #(pub #fields: Option<#types>,)*
+            pub where_clause: Option<::ic_dbms_api::prelude::Filter>,
+        }
+
+        impl ::ic_dbms_api::prelude::TableSchema for #ident {
+            type Record = #record_ident;
+            type Insert = #insert_type;
+            type Update = #update_ident;
+            type ForeignFetcher = #foreign_fetcher;
+
+            fn table_name() -> &'static str {
+                #table_name
+            }
+
+            fn columns() -> &'static [::ic_dbms_api::prelude::ColumnDef] {
+                &[#(#column_defs),*]
+            }
+
+            fn primary_keys() -> &'static [&'static str] {
+                &[#(#primary_keys),*]
+            }
+
+            fn computed_columns() -> &'static [::ic_dbms_api::prelude::ComputedColumnDef] {
+                &[#(#computed_defs),*]
+            }
+
+            #soft_delete_column
+
+            fn to_values(self) -> Vec<(::ic_dbms_api::prelude::ColumnDef, ::ic_dbms_api::prelude::Value)> {
+                let columns = <Self as ::ic_dbms_api::prelude::TableSchema>::columns();
+                vec![#((columns[#indexes], ::ic_dbms_api::prelude::Value::from(self.#fields))),*]
+            }
+        }
+
+        impl ::ic_dbms_api::prelude::TableRecord for #record_ident {
+            type Schema = #ident;
+
+            fn from_values(values: ::ic_dbms_api::prelude::TableColumns) -> Self {
+                let mut record = Self {
+                    #(#fields: None,)*
+                    #(#computed_fields: None,)*
+                };
+                let this_values = values
+                    .into_iter()
+                    .find(|(source, _)| *source == ::ic_dbms_api::prelude::ValuesSource::This)
+                    .map(|(_, values)| values)
+                    .unwrap_or_default();
+
+                for (column, value) in this_values {
+                    match column.name {
+                        #(#names => record.#fields = #from_values,)*
+                        #(#computed_names => record.#computed_fields = match value {
+                            ::ic_dbms_api::prelude::Value::#computed_types(value) => Some(value),
+                            _ => None,
+                        },)*
+                        _ => {}
+                    }
+                }
+
+                record
+            }
+
+            fn to_values(&self) -> Vec<(::ic_dbms_api::prelude::ColumnDef, ::ic_dbms_api::prelude::Value)> {
+                <#ident as ::ic_dbms_api::prelude::TableSchema>::columns()
+                    .iter()
+                    .zip(vec![#(
+                        self.#fields
+                            .clone()
+                            .map_or(::ic_dbms_api::prelude::Value::Null, ::ic_dbms_api::prelude::Value::from)
+                    ),*])
+                    .map(|(column, value)| (*column, value))
+                    .collect()
+            }
+        }
+
+        #update_record
+
+        #soft_delete_schema
+    })
+}
+
+/// The arguments of the `#[table(...)]` attributes of the struct.
+#[derive(Default)]
+struct TableAttrs {
+    /// The name of the table, set with `name = "..."`.
+    name: Option<String>,
+    /// The `InsertRecord` type of the table, set with `insert = ...`.
+    insert: Option<Type>,
+    /// The `deleted_at` column of a soft-deletable table, set with `soft_delete = "..."`.
+    soft_delete: Option<LitStr>,
+    /// The computed columns, set with `computed(...)`.
+    computed: Vec<ComputedColumn>,
+    /// The `ForeignFetcher` loading the eager relations of the table, set with `foreign_fetcher = ...`.
+    foreign_fetcher: Option<Type>,
+}
+
+/// A computed column, set with `#[table(computed(name = "...", kind = ..., expression = ...))]`.
+struct ComputedColumn {
+    name: LitStr,
+    /// The `DataTypeKind` variant of the column, which is also the name of its DBMS type.
+    kind: Ident,
+    expression: Path,
+}
+
+/// Reads the arguments of the `#[table(...)]` attributes.
+///
+/// # Errors
+///
+/// Fails if the `table` attribute has an argument other than `name`, `insert`, `soft_delete`, `computed`
+/// and `foreign_fetcher`.
+fn table_attrs(attrs: &[Attribute]) -> syn::Result<TableAttrs> {
+    let mut table = TableAttrs::default();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("table")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                table.name = Some(meta.value()?.parse::<LitStr>()?.value());
+                Ok(())
+            } else if meta.path.is_ident("insert") {
+                table.insert = Some(meta.value()?.parse::<Type>()?);
+                Ok(())
+            } else if meta.path.is_ident("soft_delete") {
+                table.soft_delete = Some(meta.value()?.parse::<LitStr>()?);
+                Ok(())
+            } else if meta.path.is_ident("computed") {
+                let mut name = None;
+                let mut kind = None;
+                let mut expression = None;
+                meta.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("name") {
+                        name = Some(meta.value()?.parse::<LitStr>()?);
+                        Ok(())
+                    } else if meta.path.is_ident("kind") {
+                        kind = Some(meta.value()?.parse::<Ident>()?);
+                        Ok(())
+                    } else if meta.path.is_ident("expression") {
+                        expression = Some(meta.value()?.parse::<Path>()?);
+                        Ok(())
+                    } else {
+                        Err(meta.error(
+                            "unsupported computed attribute; expected `name`, `kind` or `expression`",
+                        ))
+                    }
+                })?;
+                let (Some(name), Some(kind), Some(expression)) = (name, kind, expression) else {
+                    return Err(meta.error("computed requires `name`, `kind` and `expression`"));
+                };
+                table.computed.push(ComputedColumn {
+                    name,
+                    kind,
+                    expression,
+                });
+                Ok(())
+            } else if meta.path.is_ident("foreign_fetcher") {
+                table.foreign_fetcher = Some(meta.value()?.parse::<Type>()?);
+                Ok(())
+            } else {
+                Err(meta.error(
+                    "unsupported table attribute; expected `name`, `insert`, `soft_delete`, `computed` or `foreign_fetcher`",
+                ))
+            }
+        })?;
+    }
+
+    Ok(table)
+}
+
+/// Generate the `SOFT_DELETE_COLUMN` const of `TableSchema` and the `SoftDeleteSchema` implementation
+/// of a table soft-deleted on the given column, if any.
+///
+/// # Errors
+///
+/// Fails if the column is not a `Nullable<DateTime>` column of the table.
+fn soft_delete_impl(
+    ident: &Ident,
+    columns: &[Column],
+    deleted_at: Option<&LitStr>,
+) -> syn::Result<(TokenStream2, TokenStream2)> {
+    let Some(deleted_at) = deleted_at else {
+        return Ok((TokenStream2::new(), TokenStream2::new()));
+    };
+    if !columns.iter().any(|column| {
+        column.name == deleted_at.value()
+            && column.kind == "DateTime"
+            && column.nullable
+            && !column.array
+    }) {
+        return Err(syn::Error::new_spanned(
+            deleted_at,
+            format!(
+                "Cannot derive TableSchema for {ident}; the soft delete column `{}` must be a `Nullable<DateTime>` column",
+                deleted_at.value()
+            ),
+        ));
+    }
+
+    Ok((
+        quote! {
+            const SOFT_DELETE_COLUMN: Option<&'static str> = Some(#deleted_at);
+        },
+        quote! {
+            impl ::ic_dbms_api::prelude::SoftDeleteSchema for #ident {}
+        },
+    ))
+}
+
+/// Generate the `ColumnDef` of the given column.
+fn column_def(column: &Column) -> TokenStream2 {
+    let Column {
+        name,
+        kind,
+        array,
+        nullable,
+        primary_key,
+        unique,
+        auto_increment,
+        foreign_key,
+        check,
+        ..
+    } = column;
+    let data_type = if *array {
+        quote! { ::ic_dbms_api::prelude::DataTypeKind::Array(&::ic_dbms_api::prelude::DataTypeKind::#kind) }
+    } else if *auto_increment && kind == "Uint64" {
+        // the values generated by the sequence are stored as `Uint64`s
+        quote! { ::ic_dbms_api::prelude::DataTypeKind::AutoIncrement }
+    } else {
+        quote! { ::ic_dbms_api::prelude::DataTypeKind::#kind }
+    };
+    let foreign_key = match foreign_key {
+        Some((foreign_table, foreign_column)) => quote! {
+            Some(::ic_dbms_api::prelude::ForeignKeyDef {
+                local_column: #name,
+                foreign_table: #foreign_table,
+                foreign_column: #foreign_column,
+            })
+        },
+        None => quote! { None },
+    };
+    let check = match check {
+        Some((description, predicate)) => quote! {
+            Some(::ic_dbms_api::prelude::CheckConstraint::predicate(#description, #predicate))
+        },
+        None => quote! { None },
+    };
+
+    quote! {
+        ::ic_dbms_api::prelude::ColumnDef {
+            name: #name,
+            data_type: #data_type,
+            nullable: #nullable,
+            primary_key: #primary_key,
+            unique: #unique,
+            auto_increment: #auto_increment,
+            foreign_key: #foreign_key,
+            check: #check,
+        }
+    }
+}
+
+/// Converts a `CamelCase` identifier into `snake_case`.
+fn snake_case(ident: &str) -> String {
+    let mut name = String::with_capacity(ident.len() + 4);
+    for (i, ch) in ident.chars().enumerate() {
+        if ch.is_uppercase() {
+            if i > 0 {
+                name.push('_');
+            }
+            name.extend(ch.to_lowercase());
+        } else {
+            name.push(ch);
+        }
+    }
+    name
+}
@@ -0,0 +1,192 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{Attribute, DeriveInput, Field, GenericArgument, Ident, PathArguments, Type};
+
+use crate::column::{Column, column, from_value};
+
+/// Name of the field holding the where clause of the update record.
+const WHERE_CLAUSE: &str = "where_clause";
+
+/// Generate implementation of `UpdateRecord` trait.
+pub fn update_record(input: DeriveInput) -> TokenStream {
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand(
+    DeriveInput {
+        attrs, ident, data, ..
+    }: DeriveInput,
+) -> syn::Result<TokenStream2> {
+    let syn::Data::Struct(struct_data) = data else {
+        return Err(syn::Error::new_spanned(
+            &ident,
+            format!("Cannot derive UpdateRecord for {ident}; it can only be derived for structs"),
+        ));
+    };
+    let Some(schema) = schema_type(&attrs)? else {
+        return Err(syn::Error::new_spanned(
+            &ident,
+            format!(
+                "Cannot derive UpdateRecord for {ident}; the table must be set with `#[update(schema = ...)]`"
+            ),
+        ));
+    };
+
+    let mut has_where_clause = false;
+    // the fields with their `Option` unwrapped, so that they can be read as columns
+    let fields = struct_data
+        .fields
+        .iter()
+        .filter(|field| {
+            let is_where_clause = field
+                .ident
+                .as_ref()
+                .is_some_and(|field_ident| field_ident == WHERE_CLAUSE);
+            has_where_clause |= is_where_clause;
+            !is_where_clause
+        })
+        .map(|field| {
+            let Some(ty) = option_argument(&field.ty) else {
+                return Err(syn::Error::new_spanned(
+                    &field.ty,
+                    format!(
+                        "Cannot derive UpdateRecord for {ident}; the type of every field must be an `Option`"
+                    ),
+                ));
+            };
+            Ok(Field {
+                ty: ty.clone(),
+                ..field.clone()
+            })
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+    if !has_where_clause {
+        return Err(syn::Error::new_spanned(
+            &ident,
+            format!(
+                "Cannot derive UpdateRecord for {ident}; a `where_clause: Option<Filter>` field is required"
+            ),
+        ));
+    }
+    let columns = fields
+        .iter()
+        .map(|field| column("UpdateRecord", &ident, field))
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    Ok(impl_update_record(&ident, &quote! { #schema }, &columns))
+}
+
+/// Generate the `UpdateRecord` implementation of `ident` for the given table schema,
+/// whose fields are an `Option` of the given columns, along with the `where_clause`.
+pub fn impl_update_record(
+    ident: &Ident,
+    schema: &TokenStream2,
+    columns: &[Column],
+) -> TokenStream2 {
+    let fields = columns
+        .iter()
+        .map(|column| column.field)
+        .collect::<Vec<_>>();
+    let types = columns.iter().map(|column| column.ty);
+    let names = columns
+        .iter()
+        .map(|column| column.name.as_str())
+        .collect::<Vec<_>>();
+    // prefixed, so that they can't shadow the other variables of the generated code
+    let vars = columns
+        .iter()
+        .map(|column| format_ident!("__{}", column.field))
+        .collect::<Vec<_>>();
+    let from_values = columns.iter().map(from_value);
+
+    quote! {
+        impl ::ic_dbms_api::prelude::UpdateRecord for #ident {
+            type Record = <#schema as ::ic_dbms_api::prelude::TableSchema>::Record;
+            type Schema = #schema;
+
+            fn from_values(
+                values: &[(::ic_dbms_api::prelude::ColumnDef, ::ic_dbms_api::prelude::Value)],
+                where_clause: Option<::ic_dbms_api::prelude::Filter>,
+            ) -> Self {
+                #(let mut #vars: Option<#types> = None;)*
+
+                for (column, value) in values.iter().cloned() {
+                    match column.name {
+                        #(#names => #vars = #from_values,)*
+                        _ => {}
+                    }
+                }
+
+                Self {
+                    #(#fields: #vars,)*
+                    where_clause,
+                }
+            }
+
+            fn update_values(&self) -> Vec<(::ic_dbms_api::prelude::ColumnDef, ::ic_dbms_api::prelude::Value)> {
+                let columns = <#schema as ::ic_dbms_api::prelude::TableSchema>::columns();
+                let column_def = |name: &str| {
+                    *columns
+                        .iter()
+                        .find(|column| column.name == name)
+                        .expect("the update record field is not a column of the table")
+                };
+
+                vec![#(
+                    self.#fields
+                        .clone()
+                        .map(|value| (column_def(#names), ::ic_dbms_api::prelude::Value::from(value)))
+                ),*]
+                .into_iter()
+                .flatten()
+                .collect()
+            }
+
+            fn where_clause(&self) -> Option<::ic_dbms_api::prelude::Filter> {
+                self.where_clause.clone()
+            }
+        }
+    }
+}
+
+/// Returns the table schema set with `#[update(schema = ...)]`, if any.
+///
+/// # Errors
+///
+/// Fails if the `update` attribute has an argument other than `schema`.
+fn schema_type(attrs: &[Attribute]) -> syn::Result<Option<Type>> {
+    let mut schema = None;
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("update")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("schema") {
+                schema = Some(meta.value()?.parse::<Type>()?);
+                Ok(())
+            } else {
+                Err(meta.error("unsupported update attribute; expected `schema`"))
+            }
+        })?;
+    }
+
+    Ok(schema)
+}
+
+/// Returns `T` if the given type is an `Option<T>`.
+fn option_argument(ty: &Type) -> Option<&Type> {
+    let Type::Path(type_path) = ty else {
+        return None;
+    };
+    let segment = type_path.path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    match args.args.first() {
+        Some(GenericArgument::Type(inner)) if args.args.len() == 1 => Some(inner),
+        _ => None,
+    }
+}
@@ -1,5 +1,5 @@
 use proc_macro2::Span;
-use syn::Ident;
+use syn::{Field, GenericArgument, Ident, PathArguments, Type};
 
 /// Generate an infinite iterator of anonymous identifiers with an optional prefix.
 pub fn anon_ident_iter(prefix: Option<&str>) -> impl Iterator<Item = Ident> + Clone + use<'_> {
@@ -14,3 +14,49 @@ pub fn anon_ident_iter(prefix: Option<&str>) -> impl Iterator<Item = Ident> + Cl
         Ident::new(&name, Span::call_site())
     })
 }
+
+/// Returns the inner type `T` if the given type is an `Option<T>`, matching the type path.
+pub fn option_inner_type(ty: &Type) -> Option<&Type> {
+    let Type::Path(type_path) = ty else {
+        return None;
+    };
+    if type_path.qself.is_some() {
+        return None;
+    }
+    let segment = type_path.path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    match args.args.first() {
+        Some(GenericArgument::Type(inner)) if args.args.len() == 1 => Some(inner),
+        _ => None,
+    }
+}
+
+/// Returns whether the field is marked with `#[encode(skip)]`.
+///
+/// # Panics
+///
+/// Panics if the `encode` attribute has an argument other than `skip`.
+pub fn is_skipped(field: &Field) -> bool {
+    let mut skip = false;
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("encode"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("skip") {
+                skip = true;
+                Ok(())
+            } else {
+                Err(meta.error("unsupported encode attribute; expected `skip`"))
+            }
+        })
+        .unwrap_or_else(|err| panic!("{err}"));
+    }
+    skip
+}
@@ -0,0 +1,93 @@
+use ic_dbms_api::prelude::{
+    Encode, IcDbmsError, InsertRecord, Nullable, QueryError, TableSchema, Text, Uint32, Value,
+};
+use ic_dbms_macros::{Encode, InsertRecord, TableSchema};
+
+#[derive(Debug, Clone, PartialEq, Eq, Encode, TableSchema, InsertRecord)]
+#[table(name = "tags", insert = Self)]
+pub struct Tag {
+    #[column(primary_key)]
+    pub id: Uint32,
+    pub label: Text,
+    pub color: Nullable<Text>,
+}
+
+/// An insert record of the `tags` table with its columns in a different order.
+#[derive(Debug, Clone, PartialEq, Eq, InsertRecord)]
+#[insert(schema = Tag)]
+pub struct NewTag {
+    pub color: Nullable<Text>,
+    #[column(name = "label")]
+    pub text: Text,
+    pub id: Uint32,
+}
+
+#[test]
+fn test_should_derive_insert_record_for_the_table() {
+    let tag = Tag {
+        id: 1u32.into(),
+        label: "urgent".to_string().into(),
+        color: Nullable::Null,
+    };
+    let values = tag.clone().into_values();
+    assert_eq!(values, tag.clone().to_values());
+    assert_eq!(Tag::from_values(&values).expect("failed to build tag"), tag);
+    assert_eq!(
+        tag.clone().into_record().expect("failed to build record"),
+        tag
+    );
+}
+
+#[test]
+fn test_should_derive_insert_record_for_another_struct() {
+    let new_tag = NewTag {
+        color: Nullable::Value("red".to_string().into()),
+        text: "bug".to_string().into(),
+        id: 2u32.into(),
+    };
+    let values = new_tag.clone().into_values();
+    assert_eq!(
+        values[0],
+        (Tag::columns()[2], Value::Text("red".to_string().into()))
+    );
+    assert_eq!(
+        values[1],
+        (Tag::columns()[1], Value::Text("bug".to_string().into()))
+    );
+    assert_eq!(values[2], (Tag::columns()[0], Value::Uint32(2u32.into())));
+    assert_eq!(
+        NewTag::from_values(&values).expect("failed to build tag"),
+        new_tag
+    );
+    assert_eq!(
+        new_tag.into_record().expect("failed to build record"),
+        Tag {
+            id: 2u32.into(),
+            label: "bug".to_string().into(),
+            color: Nullable::Value("red".to_string().into()),
+        }
+    );
+}
+
+#[test]
+fn test_should_not_derive_insert_record_without_non_nullable_field() {
+    let values = [(Tag::columns()[0], Value::Uint32(3u32.into()))];
+    assert!(matches!(
+        NewTag::from_values(&values),
+        Err(IcDbmsError::Query(QueryError::MissingNonNullableField(
+            "label"
+        )))
+    ));
+
+    // nullable fields default to null
+    let values = [
+        (Tag::columns()[0], Value::Uint32(3u32.into())),
+        (Tag::columns()[1], Value::Text("chore".to_string().into())),
+    ];
+    assert_eq!(
+        Tag::from_values(&values)
+            .expect("failed to build tag")
+            .color,
+        Nullable::Null
+    );
+}
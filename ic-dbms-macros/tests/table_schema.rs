@@ -0,0 +1,210 @@
+use ic_dbms_api::prelude::{
+    Array, ColumnDef, DataTypeKind, Database, Encode, Filter, ForeignFetcher, ForeignKeyDef,
+    IcDbmsResult, InsertRecord, Nullable, TableColumns, TableRecord, TableSchema, Text, Uint32,
+    UpdateRecord, Value, ValuesSource,
+};
+use ic_dbms_macros::{Encode, TableSchema};
+
+#[derive(Debug, Clone, PartialEq, Eq, Encode, TableSchema)]
+#[table(name = "employees", foreign_fetcher = ManagerFetcher)]
+pub struct Employee {
+    #[column(primary_key)]
+    pub id: Uint32,
+    #[column(not_null, unique)]
+    pub name: Text,
+    #[column(name = "manager_id", foreign_key(table = "employees", column = "id"))]
+    pub manager: Nullable<Uint32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Encode, TableSchema)]
+pub struct Playlist {
+    #[column(primary_key)]
+    pub id: Uint32,
+    pub tracks: Array<Uint32>,
+    pub genres: Nullable<Array<Text>>,
+}
+
+/// Fetches nothing, as the tests never load the manager of an employee.
+#[derive(Default)]
+pub struct ManagerFetcher;
+
+impl ForeignFetcher for ManagerFetcher {
+    fn fetch(
+        &self,
+        _database: &impl Database,
+        table: &'static str,
+        local_column: &'static str,
+        _pk_value: Value,
+    ) -> IcDbmsResult<TableColumns> {
+        Ok(vec![(
+            ValuesSource::Foreign {
+                table,
+                column: local_column,
+            },
+            vec![],
+        )])
+    }
+}
+
+fn employee() -> Employee {
+    Employee {
+        id: 1u32.into(),
+        name: "Alice".to_string().into(),
+        manager: Nullable::Value(7u32.into()),
+    }
+}
+
+#[test]
+fn test_should_derive_table_schema() {
+    assert_eq!(Employee::table_name(), "employees");
+    assert_eq!(Employee::primary_key(), "id");
+    assert_eq!(Employee::columns().len(), 3);
+    assert_eq!(
+        Employee::columns()[0],
+        ColumnDef {
+            name: "id",
+            data_type: DataTypeKind::Uint32,
+            nullable: false,
+            primary_key: true,
+            unique: false,
+            auto_increment: false,
+            foreign_key: None,
+            check: None,
+        }
+    );
+    assert!(Employee::columns()[1].unique);
+    assert_eq!(
+        Employee::columns()[2],
+        ColumnDef {
+            name: "manager_id",
+            data_type: DataTypeKind::Uint32,
+            nullable: true,
+            primary_key: false,
+            unique: false,
+            auto_increment: false,
+            foreign_key: Some(ForeignKeyDef {
+                local_column: "manager_id",
+                foreign_table: "employees",
+                foreign_column: "id",
+            }),
+            check: None,
+        }
+    );
+}
+
+#[test]
+fn test_should_round_trip_values() {
+    let values = employee().to_values();
+    assert_eq!(values.len(), 3);
+    assert_eq!(values[0].1, Value::Uint32(1u32.into()));
+    assert_eq!(values[1].1, Value::Text("Alice".to_string().into()));
+    assert_eq!(values[2].1, Value::Uint32(7u32.into()));
+
+    let insert = EmployeeInsertRequest::from_values(&values).expect("failed to build insert");
+    assert_eq!(
+        insert
+            .clone()
+            .into_record()
+            .expect("failed to build record"),
+        employee()
+    );
+    assert_eq!(insert.into_values(), values);
+
+    let record = EmployeeRecord::from_values(vec![(ValuesSource::This, values.clone())]);
+    assert_eq!(record.id, Some(1u32.into()));
+    assert_eq!(record.manager, Some(Nullable::Value(7u32.into())));
+    assert_eq!(record.to_values(), values);
+
+    let decoded = Employee::decode(employee().encode()).expect("failed to decode");
+    assert_eq!(decoded, employee());
+}
+
+#[test]
+fn test_should_build_insert_with_null_values() {
+    let insert = EmployeeInsertRequest::from_values(&[
+        (Employee::columns()[0], Value::Uint32(2u32.into())),
+        (
+            Employee::columns()[1],
+            Value::Text("Bob".to_string().into()),
+        ),
+        (Employee::columns()[2], Value::Null),
+    ])
+    .expect("failed to build insert");
+    assert_eq!(insert.manager, Nullable::Null);
+
+    // the name is missing
+    assert!(
+        EmployeeInsertRequest::from_values(&[(Employee::columns()[0], Value::Uint32(2u32.into()))])
+            .is_err()
+    );
+}
+
+#[test]
+fn test_should_build_update() {
+    let update = EmployeeUpdateRequest::from_values(
+        &[(
+            Employee::columns()[1],
+            Value::Text("Carol".to_string().into()),
+        )],
+        Some(Filter::eq("id", Value::Uint32(1u32.into()))),
+    );
+    assert_eq!(update.id, None);
+    assert_eq!(
+        update.update_values(),
+        vec![(
+            Employee::columns()[1],
+            Value::Text("Carol".to_string().into())
+        )]
+    );
+    assert_eq!(
+        update.where_clause(),
+        Some(Filter::eq("id", Value::Uint32(1u32.into())))
+    );
+}
+
+#[test]
+fn test_should_derive_array_columns() {
+    assert_eq!(
+        Playlist::columns()[1].data_type,
+        DataTypeKind::Array(&DataTypeKind::Uint32)
+    );
+    assert!(!Playlist::columns()[1].nullable);
+    assert_eq!(
+        Playlist::columns()[2].data_type,
+        DataTypeKind::Array(&DataTypeKind::Text)
+    );
+    assert!(Playlist::columns()[2].nullable);
+
+    let playlist = Playlist {
+        id: 1u32.into(),
+        tracks: Array(vec![3u32.into(), 5u32.into()]),
+        genres: Nullable::Null,
+    };
+    let values = playlist.clone().to_values();
+    assert_eq!(
+        values[1].1,
+        Value::Array(vec![Value::Uint32(3u32.into()), Value::Uint32(5u32.into())])
+    );
+    assert_eq!(values[2].1, Value::Null);
+
+    let insert = PlaylistInsertRequest::from_values(&values).expect("failed to build insert");
+    assert_eq!(
+        insert.into_record().expect("failed to build record"),
+        playlist
+    );
+
+    let record = PlaylistRecord::from_values(vec![(ValuesSource::This, values.clone())]);
+    assert_eq!(record.tracks, Some(playlist.tracks));
+    assert_eq!(record.genres, Some(Nullable::Null));
+    assert_eq!(record.to_values(), values);
+}
+
+#[test]
+fn test_should_set_foreign_fetcher() {
+    let _: ManagerFetcher = <Employee as TableSchema>::ForeignFetcher::default();
+    // tables without `foreign_fetcher` can't load eager relations
+    assert_eq!(
+        std::any::type_name::<<Playlist as TableSchema>::ForeignFetcher>(),
+        std::any::type_name::<ic_dbms_api::prelude::NoForeignFetcher>()
+    );
+}
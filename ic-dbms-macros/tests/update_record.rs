@@ -0,0 +1,84 @@
+use ic_dbms_api::prelude::{
+    Encode, Filter, Nullable, TableSchema, Text, Uint32, UpdateRecord, Value,
+};
+use ic_dbms_macros::{Encode, TableSchema, UpdateRecord};
+
+#[derive(Debug, Clone, PartialEq, Eq, Encode, TableSchema)]
+#[table(name = "users")]
+pub struct User {
+    #[column(primary_key)]
+    pub id: Uint32,
+    pub name: Text,
+    pub email: Text,
+    pub nickname: Nullable<Text>,
+}
+
+/// An update record of the `users` table with a renamed field.
+#[derive(Debug, Clone, UpdateRecord)]
+#[update(schema = User)]
+pub struct UserUpdate {
+    pub name: Option<Text>,
+    #[column(name = "email")]
+    pub mail: Option<Text>,
+    pub nickname: Option<Nullable<Text>>,
+    pub where_clause: Option<Filter>,
+}
+
+#[test]
+fn test_should_derive_update_record_with_set_fields_only() {
+    let update = UserUpdate {
+        name: Some("alice".to_string().into()),
+        mail: None,
+        nickname: Some(Nullable::Value("ally".to_string().into())),
+        where_clause: Some(Filter::eq("id", Value::Uint32(1u32.into()))),
+    };
+
+    assert_eq!(
+        update.update_values(),
+        vec![
+            (User::columns()[1], Value::Text("alice".to_string().into())),
+            (User::columns()[3], Value::Text("ally".to_string().into())),
+        ]
+    );
+    assert_eq!(
+        update.where_clause(),
+        Some(Filter::eq("id", Value::Uint32(1u32.into())))
+    );
+}
+
+#[test]
+fn test_should_derive_update_record_from_values() {
+    let values = [
+        (
+            User::columns()[2],
+            Value::Text("bob@example.com".to_string().into()),
+        ),
+        (User::columns()[3], Value::Null),
+    ];
+    let update = UserUpdate::from_values(&values, None);
+
+    assert_eq!(update.name, None);
+    assert_eq!(update.mail, Some("bob@example.com".to_string().into()));
+    assert_eq!(update.nickname, Some(Nullable::Null));
+    assert!(update.where_clause.is_none());
+    assert_eq!(update.update_values(), values.to_vec());
+}
+
+#[test]
+fn test_should_derive_update_record_for_the_table() {
+    let update = UserUpdateRequest {
+        id: None,
+        name: None,
+        email: Some("carol@example.com".to_string().into()),
+        nickname: None,
+        where_clause: None,
+    };
+
+    assert_eq!(
+        update.update_values(),
+        vec![(
+            User::columns()[2],
+            Value::Text("carol@example.com".to_string().into())
+        )]
+    );
+}